@@ -1,6 +1,8 @@
+mod metrics;
+
 use candid::{Nat, Principal};
 use ic_cdk::api::call::call;
-use ic_cdk::update;
+use ic_cdk::{query, update};
 use ic_llm::{tool, ChatMessage, Model, ParameterType};
 
 const MODEL: Model = Model::Llama3_1_8B;
@@ -253,3 +255,8 @@ async fn chat(messages: Vec<ChatMessage>) -> String {
 
    "I didn't get your question. Can you try again?".to_string()
 }
+
+#[query]
+fn http_request(request: bit10_core::metrics::HttpRequest) -> bit10_core::metrics::HttpResponse {
+    metrics::http_request(request)
+}
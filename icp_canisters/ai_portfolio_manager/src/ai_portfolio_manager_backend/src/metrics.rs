@@ -0,0 +1,21 @@
+use bit10_core::metrics::{self, HealthReport, HttpRequest, HttpResponse, MetricsEncoder};
+
+// The manager holds no funds and reads no price feeds, so only the canister
+// itself is reported and /health is a liveness check.
+pub fn http_request(request: HttpRequest) -> HttpResponse {
+    match request.path() {
+        "/metrics" => encode_metrics().into_response(),
+        "/health" => HealthReport::new(ic_cdk::api::time()).into_response(),
+        _ => HttpResponse::not_found(),
+    }
+}
+
+fn encode_metrics() -> MetricsEncoder {
+    let mut encoder = MetricsEncoder::new();
+    metrics::encode_canister(
+        &mut encoder,
+        ic_cdk::api::canister_cycle_balance(),
+        ic_cdk::stable::stable_size() * 65536,
+    );
+    encoder
+}
@@ -6,9 +6,17 @@ mod utils;
 use candid::Principal;
 use utils::controller::assert_controller;
 use wallet::bsc_wallet::get_bsc_address;
-use services::bsc_service::{get_balance, send_bnb_transaction};
-use state::{init_state, read_state};
+use services::bsc_service::{allowlisted_balances, balance_of, estimate_fees, get_balance, get_nonce_status, reconcile_nonces, speed_up_bnb_transaction};
+use services::metrics;
+use services::spending_service::{self, BscPendingTransfer, BscTransfer};
+use bit10_core::providers::{ProviderSet, ProviderSetView, RpcProvider};
+use bit10_core::spending::{PolicyChange, PolicyChangeOutcome, SpendingPolicy, TransferOutcome, TransferQueue};
+use state::{init_state, mutate_state, read_state, PersistedState};
 use candid::{CandidType, Deserialize, Nat};
+use std::time::Duration;
+
+const NONCE_RECONCILE_INTERVAL: Duration = Duration::from_secs(120);
+const GAS_CHECK_INTERVAL: Duration = Duration::from_secs(600);
 
 #[ic_cdk::init]
 fn init(maybe_init: Option<state::InitArg>) {
@@ -16,6 +24,59 @@ fn init(maybe_init: Option<state::InitArg>) {
     if let Some(init_arg) = maybe_init {
         init_state(init_arg)
     }
+    start_nonce_reconciliation();
+    start_gas_checks();
+}
+
+#[ic_cdk::pre_upgrade]
+fn pre_upgrade() {
+    ic_cdk::storage::stable_save((
+        Some(spending_service::export_transfers()),
+        read_state(|s| s.configured_rpc_providers()),
+        Some(read_state(|s| s.export_persisted())),
+    ))
+        .expect("Failed to save state to stable storage");
+}
+
+#[ic_cdk::post_upgrade]
+fn post_upgrade() {
+    if let Ok((transfers, rpc_providers, persisted)) = ic_cdk::storage::stable_restore::<(
+        Option<TransferQueue<BscTransfer>>,
+        Option<ProviderSet>,
+        Option<PersistedState>,
+    )>() {
+        if let Some(transfers) = transfers {
+            spending_service::import_transfers(transfers);
+        }
+        mutate_state(|s| {
+            s.set_configured_rpc_providers(rpc_providers);
+            if let Some(persisted) = persisted {
+                s.import_persisted(persisted);
+            }
+        });
+    }
+    start_nonce_reconciliation();
+    start_gas_checks();
+}
+
+fn start_nonce_reconciliation() {
+    ic_cdk_timers::set_timer_interval(NONCE_RECONCILE_INTERVAL, || {
+        ic_cdk::spawn(async {
+            if let Err(e) = reconcile_nonces().await {
+                ic_cdk::println!("Nonce reconciliation failed: {}", e);
+            }
+        })
+    });
+}
+
+fn start_gas_checks() {
+    ic_cdk_timers::set_timer_interval(GAS_CHECK_INTERVAL, || {
+        ic_cdk::spawn(async {
+            if let Err(e) = metrics::check_gas_balance().await {
+                ic_cdk::println!("Gas balance check failed: {}", e);
+            }
+        })
+    });
 }
 
 #[ic_cdk::update]
@@ -31,7 +92,184 @@ async fn bnb_balance() -> Nat {
 }
 
 #[ic_cdk::update]
-async fn send_bnb(tx_input: services::bsc_service::BscTransactionInput) -> String {
+async fn send_bnb(tx_input: services::bsc_service::BscTransactionInput) -> Result<TransferOutcome<BscTransfer>, String> {
+    assert_controller();
+    spending_service::send_bnb(tx_input).await
+}
+
+#[ic_cdk::update]
+async fn bep20_balance_of(token_address: String, owner: Option<String>) -> Result<Nat, String> {
+    assert_controller();
+    balance_of(token_address, owner).await
+}
+
+#[ic_cdk::update]
+async fn bep20_balances(owner: Option<String>) -> Vec<(String, Result<Nat, String>)> {
+    assert_controller();
+    allowlisted_balances(owner).await
+}
+
+#[ic_cdk::update]
+async fn send_bep20_token(to: String, amount: Nat, token_address: String) -> Result<TransferOutcome<BscTransfer>, String> {
+    assert_controller();
+    spending_service::send_bep20(to, amount, token_address).await
+}
+
+#[ic_cdk::query]
+fn get_spending_policy() -> SpendingPolicy {
+    spending_service::assert_controller_or_approver();
+    spending_service::get_policy()
+}
+
+#[ic_cdk::update]
+fn set_spending_policy(policy: SpendingPolicy) -> Result<PolicyChangeOutcome, String> {
+    assert_controller();
+    spending_service::propose_policy(policy)
+}
+
+#[ic_cdk::query]
+fn get_policy_change() -> Option<PolicyChange> {
+    spending_service::assert_controller_or_approver();
+    spending_service::get_policy_change()
+}
+
+#[ic_cdk::update]
+fn approve_policy_change() -> Result<PolicyChange, String> {
+    spending_service::approve_policy_change()
+}
+
+#[ic_cdk::update]
+fn apply_policy_change() -> Result<(), String> {
+    spending_service::assert_controller_or_approver();
+    spending_service::apply_policy_change()
+}
+
+#[ic_cdk::update]
+fn cancel_policy_change() -> Result<PolicyChange, String> {
+    spending_service::assert_controller_or_approver();
+    spending_service::cancel_policy_change()
+}
+
+#[ic_cdk::query]
+fn get_pending_transfers() -> Vec<BscPendingTransfer> {
+    spending_service::assert_controller_or_approver();
+    spending_service::get_pending_transfers()
+}
+
+#[ic_cdk::query]
+fn get_transfer(id: u64) -> Option<BscPendingTransfer> {
+    spending_service::assert_controller_or_approver();
+    spending_service::get_transfer(id)
+}
+
+#[ic_cdk::update]
+fn approve_transfer(id: u64) -> Result<BscPendingTransfer, String> {
+    spending_service::approve(id)
+}
+
+#[ic_cdk::update]
+fn cancel_transfer(id: u64) -> Result<BscPendingTransfer, String> {
+    spending_service::assert_controller_or_approver();
+    spending_service::cancel(id)
+}
+
+#[ic_cdk::update]
+async fn execute_transfer(id: u64) -> Result<String, String> {
+    spending_service::assert_controller_or_approver();
+    spending_service::execute(id).await
+}
+
+#[ic_cdk::update]
+fn resolve_stuck_transfer(id: u64, tx: Option<String>) -> Result<BscPendingTransfer, String> {
+    assert_controller();
+    spending_service::resolve_stuck(id, tx)
+}
+
+#[ic_cdk::query]
+fn get_token_allowlist() -> Vec<String> {
+    read_state(|s| s.token_allowlist().iter().cloned().collect())
+}
+
+#[ic_cdk::update]
+fn add_allowed_token(token_address: String) -> Result<(), String> {
     assert_controller();
-    send_bnb_transaction(tx_input).await
-}
\ No newline at end of file
+    utils::parsing::validate_hex_address(&token_address)?;
+    mutate_state(|s| s.add_allowed_token(&token_address));
+    Ok(())
+}
+
+#[ic_cdk::update]
+fn remove_allowed_token(token_address: String) -> Result<(), String> {
+    assert_controller();
+    if mutate_state(|s| s.remove_allowed_token(&token_address)) {
+        Ok(())
+    } else {
+        Err(format!("Token {} is not on the allowlist", token_address))
+    }
+}
+
+#[ic_cdk::update]
+async fn speed_up_bnb(tx_hash: String) -> String {
+    assert_controller();
+    speed_up_bnb_transaction(tx_hash).await
+}
+
+#[ic_cdk::update]
+async fn estimate_bnb_fees() -> Result<bit10_core::fees::Eip1559Fees, String> {
+    assert_controller();
+    estimate_fees().await
+}
+
+#[ic_cdk::query]
+fn get_fee_config() -> bit10_core::fees::FeeConfig {
+    read_state(|s| s.fee_config())
+}
+
+#[ic_cdk::update]
+fn set_fee_config(config: bit10_core::fees::FeeConfig) -> Result<(), String> {
+    assert_controller();
+    config.validate()?;
+    mutate_state(|s| s.set_fee_config(config));
+    Ok(())
+}
+
+#[ic_cdk::query]
+fn nonce_status() -> Vec<(String, bit10_core::nonce::WalletNonces)> {
+    assert_controller();
+    get_nonce_status()
+}
+
+#[ic_cdk::update]
+async fn reconcile_bnb_nonces() -> Result<Vec<bit10_core::nonce::ReconcileReport>, String> {
+    assert_controller();
+    reconcile_nonces().await
+}
+
+#[ic_cdk::query]
+fn get_rpc_config() -> ProviderSetView {
+    assert_controller();
+    read_state(|s| s.rpc_providers().view())
+}
+
+#[ic_cdk::update]
+fn set_rpc_providers(rpc_providers: Vec<RpcProvider>, quorum: u8) -> Result<(), String> {
+    assert_controller();
+    mutate_state(|s| s.rpc_providers_mut().replace_providers(rpc_providers, quorum))
+}
+
+#[ic_cdk::update]
+fn set_rpc_api_key(provider: String, api_key: Option<String>) -> Result<(), String> {
+    assert_controller();
+    mutate_state(|s| s.rpc_providers_mut().set_api_key(&provider, api_key))
+}
+
+#[ic_cdk::update]
+fn reset_rpc_providers() {
+    assert_controller();
+    mutate_state(|s| s.set_configured_rpc_providers(None));
+}
+
+#[ic_cdk::query]
+fn http_request(request: bit10_core::metrics::HttpRequest) -> bit10_core::metrics::HttpResponse {
+    metrics::http_request(request)
+}
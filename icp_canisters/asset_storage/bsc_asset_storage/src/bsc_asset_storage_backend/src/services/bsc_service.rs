@@ -5,14 +5,18 @@ use crate::utils::parsing::*;
 use alloy_primitives::{U256, Bytes};
 use num::BigUint;
 use std::str::FromStr;
-use evm_rpc_canister_types::{BlockTag, EvmRpcCanister, GetTransactionCountArgs, GetTransactionCountResult, MultiGetTransactionCountResult, RequestResult, RpcService, RpcApi, HttpHeader};
+use evm_rpc_canister_types::{BlockTag, EvmRpcCanister, GetTransactionCountArgs, GetTransactionCountResult, MultiGetTransactionCountResult, RequestResult, RpcService};
 use ic_cdk::api::management_canister::ecdsa::{EcdsaCurve, EcdsaKeyId};
 use ic_cdk;
 use alloy_eips::eip2718::Encodable2718;
 use crate::state::BscNetwork;
 use crate::wallet::bsc_wallet::BscWallet;
-use crate::state::{read_state, BSC_RPC};
+use crate::state::{mutate_state, read_state, BSC_RPC};
 use crate::utils::parsing::*;
+use bit10_core::cycles;
+use bit10_core::fees::{self, Eip1559Fees};
+use bit10_core::nonce::{self, ReconcileReport, TransactionCall, WalletNonces};
+use bit10_core::providers::normalize_json_rpc;
 use alloy_primitives::{hex, Signature, TxKind, U256, Bytes, Address as AlloyAddress};
 use std::str::FromStr;
 
@@ -38,54 +42,14 @@ pub async fn get_balance(address: Option<String>) -> Nat {
         address
     );
 
-    let max_response_size_bytes = 500_u64;
-    let num_cycles = 1_000_000_000u128;
-
-    let bsc_network = read_state(|s| s.bsc_network());
-
-    let rpc_service = match bsc_network {
-        crate::state::BscNetwork::Mainnet => RpcService::Custom(RpcApi {
-            url: "https://bsc-dataseed.binance.org/".to_string(),
-            headers: None,
-        }),
-        crate::state::BscNetwork::Testnet => RpcService::Custom(RpcApi {
-            url: "https://bsc-testnet.publicnode.com".to_string(),
-            headers: None,
-        }),
-    };
-
-    let response = crate::state::BSC_RPC
-        .request(rpc_service, json, max_response_size_bytes, num_cycles)
-        .await;
-
-    let (response,) = match response {
-        Ok(result) => result,
-        Err(e) => {
-            ic_cdk::println!("RPC call failed: {:?}", e);
-            ic_cdk::trap(&format!("RPC call failed: {:?}", e));
-        }
-    };
-
-    let hex_balance = match response {
-        RequestResult::Ok(balance_result) => {
-            let response: serde_json::Value = serde_json::from_str(&balance_result).unwrap_or_else(|e| {
-                ic_cdk::println!("Failed to parse balance result: {:?}", e);
-                ic_cdk::trap(&format!("Failed to parse balance result: {:?}", e));
-            });
-            response
-                .get("result")
-                .and_then(|v| v.as_str())
-                .unwrap_or_else(|| {
-                    ic_cdk::println!("No result field in response: {:?}", response);
-                    ic_cdk::trap("No result field in response");
-                })
-                .to_string()
-        }
-        RequestResult::Err(e) => {
-            ic_cdk::println!("Received an error response: {:?}", e);
-            ic_cdk::trap(&format!("Received an error response: {:?}", e));
-        }
-    };
+    let result = bsc_quorum_rpc(json, 500).await.unwrap_or_else(|e| {
+        ic_cdk::println!("RPC call failed: {}", e);
+        ic_cdk::trap(&format!("RPC call failed: {}", e));
+    });
+    let hex_balance = result.as_str().unwrap_or_else(|| {
+        ic_cdk::println!("Unexpected balance result: {}", result);
+        ic_cdk::trap("Unexpected balance result");
+    });
 
     Nat(BigUint::from_str_radix(&hex_balance[2..], 16).unwrap_or_else(|e| {
         ic_cdk::println!("Failed to parse hex balance: {:?}", e);
@@ -93,12 +57,13 @@ pub async fn get_balance(address: Option<String>) -> Nat {
     }))
 }
 
-pub async fn send_bnb_transaction(tx_input: BscTransactionInput) -> String {
+/// Sends from the wallet derived for `caller`, which is the principal that
+/// requested the transfer rather than whoever executes it.
+pub async fn send_bnb_transaction(caller: Principal, tx_input: BscTransactionInput) -> String {
     fn convert_to_alloy_address(address: &str) -> Result<AlloyAddress, String> {
         AlloyAddress::from_str(address).map_err(|e| format!("Failed to parse address: {}", e))
     }
 
-    let caller = ic_cdk::caller();
     if caller == Principal::anonymous() {
         ic_cdk::trap("anonymous principal is not allowed");
     }
@@ -118,111 +83,6 @@ pub async fn send_bnb_transaction(tx_input: BscTransactionInput) -> String {
 
     let chain_id = bsc_network.chain_id();
 
-    async fn get_bnb_transaction_count_with_retry(owner: Principal, max_retries: u32) -> Result<Nat, String> {
-        let wallet = BscWallet::new(owner).await;
-        let address = wallet.bsc_address().to_string();
-        let bsc_network = read_state(|s| s.bsc_network());
-
-        fn get_bnb_rpc_endpoints(bsc_network: BscNetwork) -> Vec<(String, RpcService)> {
-            match bsc_network {
-                BscNetwork::Mainnet => {
-                    vec![
-                        ("Tatum-BSC-Mainnet".to_string(), RpcService::Custom(RpcApi {
-                            url: "https://bsc-mainnet.gateway.tatum.io/".to_string(),
-                            headers: Some(vec![HttpHeader {
-                                name: "x-api-key".to_string(),
-                                value: "<API_KEY>".to_string(),
-                            }]),
-                        })),
-                        ("Binance-Public".to_string(), RpcService::Custom(RpcApi {
-                            url: "https://bsc-dataseed.binance.org/".to_string(),
-                            headers: None,
-                        })),
-                        ("PublicNode-BSC".to_string(), RpcService::Custom(RpcApi {
-                            url: "https://bsc.publicnode.com".to_string(),
-                            headers: None,
-                        })),
-                    ]
-                }
-                BscNetwork::Testnet => {
-                    vec![
-                        ("Tatum-BSC-Testnet".to_string(), RpcService::Custom(RpcApi {
-                            url: "https://bsc-testnet.gateway.tatum.io/".to_string(),
-                            headers: Some(vec![HttpHeader {
-                                name: "x-api-key".to_string(),
-                                value: "<API_KEY>".to_string(),
-                            }]),
-                        })),
-                        ("PublicNode-BSC-Testnet".to_string(), RpcService::Custom(RpcApi {
-                            url: "https://bsc-testnet.publicnode.com".to_string(),
-                            headers: None,
-                        })),
-                    ]
-                }
-                _ => vec![]
-            }
-        }
-
-        for attempt in 0..max_retries {
-            for (endpoint_name, rpc_service) in &get_bnb_rpc_endpoints(bsc_network) {
-                ic_cdk::println!("Attempt {} using endpoint {}", attempt + 1, endpoint_name);
-
-                let json = format!(
-                    r#"{{ "jsonrpc": "2.0", "method": "eth_getTransactionCount", "params": ["{}", "latest"], "id": 1 }}"#,
-                    address
-                );
-
-                let max_response_size_bytes = 2000_u64;
-                let num_cycles = 3_000_000_000u128;
-
-                let response = BSC_RPC
-                    .request(rpc_service.clone(), json, max_response_size_bytes, num_cycles)
-                    .await;
-
-                match response {
-                    Ok((response,)) => {
-                        match response {
-                            RequestResult::Ok(count_result) => {
-                                let response: serde_json::Value = serde_json::from_str(&count_result)
-                                    .map_err(|e| format!("Failed to parse JSON: {}", e))?;
-                                let hex_count = response
-                                    .get("result")
-                                    .and_then(|v| v.as_str())
-                                    .ok_or("No result field in response")?;
-                                let count = BigUint::from_str_radix(&hex_count[2..], 16)
-                                    .map_err(|e| format!("Failed to parse hex count: {}", e))?;
-                                return Ok(Nat(count));
-                            }
-                            RequestResult::Err(e) => {
-                                ic_cdk::println!("RPC error from {}: {:?}", endpoint_name, e);
-                                continue;
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        ic_cdk::println!("HTTP outcall error with {}: {:?}", endpoint_name, e);
-                        continue;
-                    }
-                }
-            }
-        }
-        Err("All retry attempts failed".to_string())
-    }
-
-    fn nat_to_u64(nat: Nat) -> u64 {
-        use num_traits::cast::ToPrimitive;
-        nat.0
-            .to_u64()
-            .unwrap_or_else(|| ic_cdk::trap(&format!("Nat {} doesn't fit into a u64", nat)))
-    }
-
-    let nonce = match get_bnb_transaction_count_with_retry(caller, 3).await {
-        Ok(count) => nat_to_u64(count),
-        Err(e) => {
-            ic_cdk::trap(&format!("Failed to get transaction count after retries: {}", e));
-        }
-    };
-
     let value = parse_hex_to_u256(&tx_input.value).unwrap_or_else(|e| {
         ic_cdk::trap(&format!("Failed to parse value '{}': {}", tx_input.value, e))
     });
@@ -231,34 +91,26 @@ pub async fn send_bnb_transaction(tx_input: BscTransactionInput) -> String {
         ic_cdk::trap(&format!("Failed to parse data '{}': {}", tx_input.data, e))
     });
 
+    let wallet = BscWallet::new(caller).await;
+    let from_address = wallet.bsc_address().await.to_string();
+
     let gas_limit = if let Some(gl) = &tx_input.gas_limit {
         parse_hex_to_u128(gl).unwrap_or_else(|e| {
             ic_cdk::trap(&format!("Failed to parse gas_limit '{}': {}", gl, e))
         })
     } else {
-        500_000u128
+        estimate_gas_limit(&from_address, &tx_input.to, value, &data)
+            .await
+            .unwrap_or_else(|e| ic_cdk::trap(&format!("Failed to estimate gas limit: {}", e)))
     };
 
-    fn get_recommended_gas_price(bsc_network: BscNetwork) -> (u128, u128) {
-        match bsc_network {
-            BscNetwork::Mainnet => (5_000_000_000, 1_000_000_000),
-            BscNetwork::Testnet => (10_000_000_000, 1_500_000_000),
-            _ => (50_000_000_000, 1_500_000_000),
-        }
-    }
-
-    fn parse_decimal_to_u128(decimal_str: &str) -> Result<u128, String> {
-        decimal_str.parse::<u128>()
-            .map_err(|e| format!("Invalid decimal string: {}", e))
-    }
-
     let (max_fee_per_gas, max_priority_fee_per_gas) = if tx_input.max_fee_per_gas.is_some() || tx_input.max_priority_fee_per_gas.is_some() {
         let max_fee = if let Some(fee) = &tx_input.max_fee_per_gas {
             parse_decimal_to_u128(fee).unwrap_or_else(|e| {
                 ic_cdk::trap(&format!("Failed to parse max_fee_per_gas '{}': {}", fee, e))
             })
         } else {
-            get_recommended_gas_price(bsc_network).0
+            recommended_fees().await.max_fee_per_gas
         };
 
         let max_priority_fee = if let Some(fee) = &tx_input.max_priority_fee_per_gas {
@@ -266,7 +118,7 @@ pub async fn send_bnb_transaction(tx_input: BscTransactionInput) -> String {
                 ic_cdk::trap(&format!("Failed to parse max_priority_fee_per_gas '{}': {}", fee, e))
             })
         } else {
-            get_recommended_gas_price(bsc_network).1
+            recommended_fees().await.max_priority_fee_per_gas
         };
 
         (max_fee, max_priority_fee)
@@ -275,16 +127,22 @@ pub async fn send_bnb_transaction(tx_input: BscTransactionInput) -> String {
             ic_cdk::trap(&format!("Failed to parse gas_price '{}': {}", gas_price, e))
         });
 
-        let (min_gas_price, min_priority_fee) = get_recommended_gas_price(bsc_network);
+        let recommended = recommended_fees().await;
 
-        let effective_gas_price = std::cmp::max(provided_gas_price, min_gas_price);
-        let priority_fee = std::cmp::max(provided_gas_price / 10, min_priority_fee);
+        let effective_gas_price = std::cmp::max(provided_gas_price, recommended.max_fee_per_gas);
+        let priority_fee = std::cmp::max(provided_gas_price / 10, recommended.max_priority_fee_per_gas);
 
         (effective_gas_price, priority_fee)
     } else {
-        get_recommended_gas_price(bsc_network)
+        let recommended = recommended_fees().await;
+        (recommended.max_fee_per_gas, recommended.max_priority_fee_per_gas)
     };
 
+    let nonce_key = nonce::wallet_key(chain_id, &from_address);
+    let nonce = reserve_nonce(caller, &nonce_key, &from_address)
+        .await
+        .unwrap_or_else(|e| ic_cdk::trap(&format!("Failed to reserve a nonce: {}", e)));
+
     let transaction = alloy_consensus::TxEip1559 {
         chain_id,
         nonce,
@@ -297,9 +155,6 @@ pub async fn send_bnb_transaction(tx_input: BscTransactionInput) -> String {
         input: data,
     };
 
-    let wallet = BscWallet::new(caller).await;
-    let from_address = wallet.bsc_address().to_string();
-
     ic_cdk::println!(
         "Creating BNB transaction:\n  From: {}\n  To: {}\n  Value: {} wei\n  Gas Limit: {}\n  Max Fee Per Gas: {} wei ({} Gwei)\n  Max Priority Fee: {} wei ({} Gwei)\n  Data Length: {} bytes\n  Nonce: {}",
         from_address,
@@ -314,6 +169,438 @@ pub async fn send_bnb_transaction(tx_input: BscTransactionInput) -> String {
         nonce
     );
 
+    let tx_hash = sign_and_send_bnb_transaction(&wallet, transaction.clone()).await;
+    record_submitted(&nonce_key, &transaction, &tx_hash);
+    tx_hash
+}
+
+const BEP20_BALANCE_OF_SELECTOR: &str = "70a08231";
+const BEP20_TRANSFER_SELECTOR: &str = "a9059cbb";
+
+/// Reads `balanceOf(owner)` on a BEP-20 contract. The owner defaults to the
+/// caller's wallet.
+pub async fn balance_of(token_address: String, owner: Option<String>) -> Result<Nat, String> {
+    let token = AlloyAddress::from_str(&token_address)
+        .map_err(|e| format!("Failed to parse token address '{}': {}", token_address, e))?;
+    let owner = match owner {
+        Some(addr) => addr,
+        None => crate::wallet::bsc_wallet::get_bsc_address(None).await,
+    };
+    let owner = AlloyAddress::from_str(&owner)
+        .map_err(|e| format!("Failed to parse owner address '{}': {}", owner, e))?;
+
+    let data = format!("0x{}{}", BEP20_BALANCE_OF_SELECTOR, abi_word(owner.as_slice()));
+    let json = format!(
+        r#"{{ "jsonrpc": "2.0", "method": "eth_call", "params": [{{ "to": "{}", "data": "{}" }}, "latest"], "id": 1 }}"#,
+        token, data
+    );
+    let result = bsc_quorum_rpc(json, 1_000).await?;
+    let hex_balance = result.as_str().ok_or("balanceOf result is not a string")?;
+    let cleaned = hex_balance.strip_prefix("0x").unwrap_or(hex_balance);
+    if cleaned.is_empty() {
+        return Err(format!("Token {} returned no data for balanceOf", token_address));
+    }
+    BigUint::from_str_radix(cleaned, 16)
+        .map(Nat)
+        .map_err(|e| format!("Failed to parse hex balance: {}", e))
+}
+
+/// Balances of every allowlisted token. A token whose call fails reports the
+/// error instead of failing the whole batch.
+pub async fn allowlisted_balances(owner: Option<String>) -> Vec<(String, Result<Nat, String>)> {
+    let owner = match owner {
+        Some(addr) => addr,
+        None => crate::wallet::bsc_wallet::get_bsc_address(None).await,
+    };
+    let tokens = read_state(|s| s.token_allowlist().iter().cloned().collect::<Vec<_>>());
+
+    let mut balances = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        let balance = balance_of(token.clone(), Some(owner.clone())).await;
+        balances.push((token, balance));
+    }
+    balances
+}
+
+/// Transfers an allowlisted BEP-20 token. The call goes through
+/// `send_bnb_transaction`, so it gets the same gas estimation, nonce
+/// reservation and multi-endpoint broadcast as native transfers.
+pub async fn send_bep20(caller: Principal, to: String, amount: Nat, token_address: String) -> String {
+    let token_key = token_address.to_ascii_lowercase();
+    if !read_state(|s| s.token_allowlist().contains(&token_key)) {
+        ic_cdk::trap(&format!("Token {} is not on the allowlist", token_address));
+    }
+    if amount == Nat::from(0u64) {
+        ic_cdk::trap("Amount must be greater than 0");
+    }
+
+    let to_address = AlloyAddress::from_str(&to).unwrap_or_else(|e| {
+        ic_cdk::trap(&format!("failed to parse the recipient address '{}': {}", to, e))
+    });
+    let amount_bytes = amount.0.to_bytes_be();
+    if amount_bytes.len() > 32 {
+        ic_cdk::trap(&format!("Amount {} does not fit into uint256", amount));
+    }
+
+    let owner = crate::wallet::bsc_wallet::get_bsc_address(Some(caller)).await;
+    let balance = balance_of(token_address.clone(), Some(owner))
+        .await
+        .unwrap_or_else(|e| ic_cdk::trap(&format!("Failed to read token balance: {}", e)));
+    if balance < amount {
+        ic_cdk::trap(&format!(
+            "Insufficient token balance: have {}, need {}",
+            balance, amount
+        ));
+    }
+
+    let data = format!(
+        "0x{}{}{}",
+        BEP20_TRANSFER_SELECTOR,
+        abi_word(to_address.as_slice()),
+        abi_word(&amount_bytes)
+    );
+
+    send_bnb_transaction(caller, BscTransactionInput {
+        to: token_address,
+        value: "0x0".to_string(),
+        data,
+        gas_limit: None,
+        gas_price: None,
+        max_fee_per_gas: None,
+        max_priority_fee_per_gas: None,
+    })
+    .await
+}
+
+fn abi_word(bytes: &[u8]) -> String {
+    format!("{:0>64}", hex::encode(bytes))
+}
+
+/// Replaces a pending transaction sent by the caller with a copy that pays
+/// higher fees at the same nonce. The copy is rebuilt from what this canister
+/// recorded when it sent the original; hashes it did not send are refused.
+pub async fn speed_up_bnb_transaction(tx_hash: String) -> String {
+    let caller = ic_cdk::caller();
+    if caller == Principal::anonymous() {
+        ic_cdk::trap("anonymous principal is not allowed");
+    }
+
+    let wallet = BscWallet::new(caller).await;
+    let from_address = wallet.bsc_address().await.to_string();
+    let bsc_network = read_state(|s| s.bsc_network());
+    let nonce_key = nonce::wallet_key(bsc_network.chain_id(), &from_address);
+
+    let pending = read_state(|s| s.nonce_manager().find_submitted(&nonce_key, &tx_hash).cloned())
+        .unwrap_or_else(|| {
+            ic_cdk::trap(&format!("Transaction {} is not a pending transaction sent by this canister", tx_hash))
+        });
+    let call = pending.call.clone().unwrap_or_else(|| {
+        ic_cdk::trap(&format!("Transaction {} was sent without a recorded call and cannot be replaced", tx_hash))
+    });
+    let previous = match (pending.max_fee_per_gas, pending.max_priority_fee_per_gas) {
+        (Some(max_fee_per_gas), Some(max_priority_fee_per_gas)) => Eip1559Fees {
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+        },
+        _ => ic_cdk::trap(&format!("Transaction {} has no recorded fees", tx_hash)),
+    };
+
+    let confirmed_nonce = bnb_transaction_count(&from_address, "latest")
+        .await
+        .unwrap_or_else(|e| ic_cdk::trap(&format!("Failed to read the transaction count: {}", e)));
+    if pending.nonce < confirmed_nonce {
+        ic_cdk::trap(&format!("Transaction {} is already mined", tx_hash));
+    }
+
+    let current = recommended_fees().await;
+    let replacement = fees::replacement_fees(&previous, &current, &read_state(|s| s.fee_config()))
+        .unwrap_or_else(|e| ic_cdk::trap(&e));
+
+    // Another replacement may have landed while the fees were being fetched.
+    if read_state(|s| s.nonce_manager().find_submitted(&nonce_key, &tx_hash).is_none()) {
+        ic_cdk::trap(&format!("Transaction {} was replaced in the meantime", tx_hash));
+    }
+
+    let transaction = alloy_consensus::TxEip1559 {
+        chain_id: bsc_network.chain_id(),
+        nonce: pending.nonce,
+        gas_limit: call.gas_limit,
+        max_fee_per_gas: replacement.max_fee_per_gas,
+        max_priority_fee_per_gas: replacement.max_priority_fee_per_gas,
+        to: alloy_primitives::TxKind::Call(
+            AlloyAddress::from_str(&call.to).unwrap_or_else(|e| {
+                ic_cdk::trap(&format!("Failed to parse recipient address: {}", e))
+            }),
+        ),
+        value: parse_hex_to_u256(&call.value).unwrap_or_else(|e| ic_cdk::trap(&e)),
+        access_list: Default::default(),
+        input: parse_hex_to_bytes(&call.data).unwrap_or_else(|e| ic_cdk::trap(&e)),
+    };
+
+    ic_cdk::println!(
+        "Replacing transaction {} at nonce {} with max fee {} wei and priority fee {} wei",
+        tx_hash,
+        transaction.nonce,
+        replacement.max_fee_per_gas,
+        replacement.max_priority_fee_per_gas
+    );
+
+    let tx_hash = sign_and_send_bnb_transaction(&wallet, transaction.clone()).await;
+    record_submitted(&nonce_key, &transaction, &tx_hash);
+    tx_hash
+}
+
+// Submissions older than this are reported as stuck, and reservations that
+// never reached the network are cancelled.
+const NONCE_STALE_AFTER_NS: u64 = 10 * 60 * 1_000_000_000;
+
+pub fn get_nonce_status() -> Vec<(String, WalletNonces)> {
+    read_state(|s| {
+        s.nonce_manager()
+            .wallets()
+            .map(|(key, wallet)| (key.clone(), wallet.clone()))
+            .collect()
+    })
+}
+
+pub fn pending_transactions() -> usize {
+    read_state(|s| s.nonce_manager().wallets().map(|(_, wallet)| wallet.pending.len()).sum())
+}
+
+/// Compares the locally reserved nonces of every tracked wallet with the
+/// chain and fills abandoned nonces with zero-value self transfers. A send
+/// that traps after reserving its nonce leaves the reservation behind, so it
+/// is cancelled here once it goes stale.
+pub async fn reconcile_nonces() -> Result<Vec<ReconcileReport>, String> {
+    let owners: Vec<(String, Principal)> = read_state(|s| {
+        s.nonce_owners()
+            .iter()
+            .map(|(key, owner)| (key.clone(), *owner))
+            .collect()
+    });
+
+    let mut reports = Vec::with_capacity(owners.len());
+    for (nonce_key, owner) in owners {
+        let wallet = BscWallet::new(owner).await;
+        let address = wallet.bsc_address().await.to_string();
+        let confirmed_nonce = bnb_transaction_count(&address, "latest").await?;
+        let report = mutate_state(|s| {
+            s.nonce_manager_mut().reconcile(
+                &nonce_key,
+                confirmed_nonce,
+                ic_cdk::api::time(),
+                NONCE_STALE_AFTER_NS,
+            )
+        });
+
+        for stuck in &report.stuck {
+            ic_cdk::println!(
+                "Transaction {:?} with nonce {} from {} is still pending",
+                stuck.tx_hash,
+                stuck.nonce,
+                nonce_key
+            );
+        }
+
+        for gap in report.gaps.iter().copied() {
+            let fees = estimate_fees().await?;
+            mutate_state(|s| s.nonce_manager_mut().mark_cancel(&nonce_key, gap, ic_cdk::api::time()));
+
+            let own_address = AlloyAddress::from_str(&address)
+                .map_err(|e| format!("Failed to parse wallet address: {}", e))?;
+            let bsc_network = read_state(|s| s.bsc_network());
+            let transaction = alloy_consensus::TxEip1559 {
+                chain_id: bsc_network.chain_id(),
+                nonce: gap,
+                gas_limit: 21_000,
+                max_fee_per_gas: fees.max_fee_per_gas,
+                max_priority_fee_per_gas: fees.max_priority_fee_per_gas,
+                to: TxKind::Call(own_address),
+                value: U256::ZERO,
+                access_list: Default::default(),
+                input: Default::default(),
+            };
+
+            let tx_hash = sign_and_send_bnb_transaction(&wallet, transaction.clone()).await;
+            record_submitted(&nonce_key, &transaction, &tx_hash);
+            ic_cdk::println!("Filled nonce gap {} of {} with {}", gap, nonce_key, tx_hash);
+        }
+
+        reports.push(report);
+    }
+
+    Ok(reports)
+}
+
+/// Hands out the next nonce for the owner's wallet. The first call for a
+/// wallet seeds the counter from its `pending` transaction count; afterwards
+/// reservations never leave the canister, so concurrent sends cannot collide.
+async fn reserve_nonce(owner: Principal, nonce_key: &str, address: &str) -> Result<u64, String> {
+    if !read_state(|s| s.nonce_manager().is_tracked(nonce_key)) {
+        let chain_nonce = bnb_transaction_count(address, "pending").await?;
+        mutate_state(|s| {
+            s.nonce_manager_mut().track(nonce_key, chain_nonce);
+            s.set_nonce_owner(nonce_key.to_string(), owner);
+        });
+    }
+
+    mutate_state(|s| s.nonce_manager_mut().reserve(nonce_key, ic_cdk::api::time()))
+}
+
+fn record_submitted(nonce_key: &str, transaction: &alloy_consensus::TxEip1559, tx_hash: &str) {
+    let call = TransactionCall {
+        to: transaction.to.to().map(|to| to.to_string()).unwrap_or_default(),
+        value: format!("0x{:x}", transaction.value),
+        data: format!("0x{}", hex::encode(&transaction.input)),
+        gas_limit: transaction.gas_limit,
+    };
+
+    mutate_state(|s| {
+        let nonces = s.nonce_manager_mut();
+        nonces.mark_submitted(
+            nonce_key,
+            transaction.nonce,
+            tx_hash.to_string(),
+            transaction.max_fee_per_gas,
+            transaction.max_priority_fee_per_gas,
+            ic_cdk::api::time(),
+        );
+        nonces.record_call(nonce_key, transaction.nonce, call);
+    });
+}
+
+async fn bnb_transaction_count(address: &str, block_tag: &str) -> Result<u64, String> {
+    let json = format!(
+        r#"{{ "jsonrpc": "2.0", "method": "eth_getTransactionCount", "params": ["{}", "{}"], "id": 1 }}"#,
+        address, block_tag
+    );
+    let body = bsc_json_rpc(json, 2_000).await?;
+    let response: serde_json::Value = serde_json::from_str(&body)
+        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
+    let hex_count = response
+        .get("result")
+        .and_then(|v| v.as_str())
+        .ok_or("No result field in response")?;
+    fees::parse_hex_u128(hex_count).map(|count| count as u64)
+}
+
+pub async fn estimate_fees() -> Result<Eip1559Fees, String> {
+    let config = read_state(|s| s.fee_config());
+    let body = bsc_json_rpc(fees::fee_history_payload(&config), 5_000).await?;
+    let history = fees::parse_fee_history(&body)?;
+    fees::suggest_fees(&history, &config)
+}
+
+/// Reads the BNB balance of the canister's own wallet without trapping, so
+/// the periodic gas check can log and retry instead.
+pub async fn canister_balance() -> Result<u128, String> {
+    let address = crate::wallet::bsc_wallet::get_bsc_address(None).await;
+    let json = format!(
+        r#"{{ "jsonrpc": "2.0", "method": "eth_getBalance", "params": ["{}", "latest"], "id": 1 }}"#,
+        address
+    );
+    let body = bsc_json_rpc(json, 500).await?;
+    let response: serde_json::Value =
+        serde_json::from_str(&body).map_err(|e| format!("Failed to parse balance result: {}", e))?;
+    let hex_balance = response
+        .get("result")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| "No result field in response".to_string())?;
+    u128::from_str_radix(hex_balance.trim_start_matches("0x"), 16)
+        .map_err(|e| format!("Failed to parse hex balance: {}", e))
+}
+
+async fn recommended_fees() -> Eip1559Fees {
+    estimate_fees()
+        .await
+        .unwrap_or_else(|e| ic_cdk::trap(&format!("Failed to estimate fees: {}", e)))
+}
+
+async fn estimate_gas_limit(from: &str, to: &str, value: U256, data: &Bytes) -> Result<u128, String> {
+    let json = fees::estimate_gas_payload(
+        from,
+        to,
+        &format!("0x{:x}", value),
+        &format!("0x{}", hex::encode(data)),
+    );
+    let body = bsc_json_rpc(json, 1_000).await?;
+    let estimate = fees::parse_estimate_gas(&body)?;
+    fees::gas_limit_from_estimate(estimate, &read_state(|s| s.fee_config()))
+}
+
+/// Sends a read to every configured endpoint and returns the `result` that
+/// the provider set's quorum agrees on. Balances users rely on go through
+/// here; reads that legitimately differ between nodes use `bsc_json_rpc`.
+async fn bsc_quorum_rpc(json: String, max_response_size_bytes: u64) -> Result<serde_json::Value, String> {
+    let endpoints = get_bnb_rpc_endpoints();
+    let mut responses = Vec::with_capacity(endpoints.len());
+
+    for (endpoint_name, rpc_service) in endpoints {
+        let response = BSC_RPC
+            .request(
+                rpc_service,
+                json.clone(),
+                max_response_size_bytes,
+                cycles::evm_rpc_request_cost(json.len() as u64, max_response_size_bytes, 1),
+            )
+            .await;
+
+        let response = match response {
+            Ok((RequestResult::Ok(body),)) => normalize_json_rpc(&body),
+            Ok((RequestResult::Err(e),)) => Err(format!("{:?}", e)),
+            Err(e) => Err(format!("HTTP outcall error: {:?}", e)),
+        };
+        responses.push((endpoint_name, response));
+    }
+
+    let result = mutate_state(|s| s.rpc_providers_mut().tally(responses, ic_cdk::api::time()))?;
+    serde_json::from_str(&result).map_err(|e| format!("Failed to parse JSON: {}", e))
+}
+
+/// Sends a read-only JSON-RPC request, falling back through the configured
+/// endpoints until one answers without an RPC error.
+async fn bsc_json_rpc(json: String, max_response_size_bytes: u64) -> Result<String, String> {
+    let mut last_error = "No RPC endpoints configured".to_string();
+
+    for (endpoint_name, rpc_service) in get_bnb_rpc_endpoints() {
+        let response = BSC_RPC
+            .request(
+                rpc_service,
+                json.clone(),
+                max_response_size_bytes,
+                cycles::evm_rpc_request_cost(json.len() as u64, max_response_size_bytes, 1),
+            )
+            .await;
+
+        match response {
+            Ok((RequestResult::Ok(body),)) => {
+                let has_error = serde_json::from_str::<serde_json::Value>(&body)
+                    .map(|v| v.get("error").is_some())
+                    .unwrap_or(true);
+                if !has_error {
+                    return Ok(body);
+                }
+                ic_cdk::println!("RPC error from {}: {}", endpoint_name, body);
+                last_error = body;
+            }
+            Ok((RequestResult::Err(e),)) => {
+                ic_cdk::println!("RPC error from {}: {:?}", endpoint_name, e);
+                last_error = format!("{:?}", e);
+            }
+            Err(e) => {
+                ic_cdk::println!("HTTP outcall error with {}: {:?}", endpoint_name, e);
+                last_error = format!("{:?}", e);
+            }
+        }
+    }
+
+    Err(last_error)
+}
+
+async fn sign_and_send_bnb_transaction(
+    wallet: &BscWallet,
+    transaction: alloy_consensus::TxEip1559,
+) -> String {
     let tx_hash = transaction.signature_hash().0;
     let (raw_signature, recovery_id) = wallet.sign_with_ecdsa(tx_hash).await;
     let signature = Signature::from_bytes_and_parity(&raw_signature, recovery_id.is_y_odd())
@@ -331,150 +618,118 @@ pub async fn send_bnb_transaction(tx_input: BscTransactionInput) -> String {
         raw_transaction_hex
     );
 
-    async fn send_bnb_transaction_with_better_retry(raw_transaction_hex: &str, bsc_network: BscNetwork, max_retries: u32) -> Result<String, String> {
-        fn get_bnb_rpc_endpoints(bsc_network: BscNetwork) -> Vec<(String, RpcService)> {
-            match bsc_network {
-                BscNetwork::Mainnet => {
-                    vec![
-                        ("Tatum-BSC-Mainnet".to_string(), RpcService::Custom(RpcApi {
-                            url: "https://bsc-mainnet.gateway.tatum.io/".to_string(),
-                            headers: Some(vec![HttpHeader {
-                                name: "x-api-key".to_string(),
-                                value: "<API_KEY>".to_string(),
-                            }]),
-                        })),
-                        ("Binance-Public".to_string(), RpcService::Custom(RpcApi {
-                            url: "https://bsc-dataseed.binance.org/".to_string(),
-                            headers: None,
-                        })),
-                        ("PublicNode-BSC".to_string(), RpcService::Custom(RpcApi {
-                            url: "https://bsc.publicnode.com".to_string(),
-                            headers: None,
-                        })),
-                    ]
-                }
-                BscNetwork::Testnet => {
-                    vec![
-                        ("Tatum-BSC-Testnet".to_string(), RpcService::Custom(RpcApi {
-                            url: "https://bsc-testnet.gateway.tatum.io/".to_string(),
-                            headers: Some(vec![HttpHeader {
-                                name: "x-api-key".to_string(),
-                                value: "<API_KEY>".to_string(),
-                            }]),
-                        })),
-                        ("PublicNode-BSC-Testnet".to_string(), RpcService::Custom(RpcApi {
-                            url: "https://bsc-testnet.publicnode.com".to_string(),
-                            headers: None,
-                        })),
-                    ]
-                }
-                _ => vec![]
-            }
+    match send_bnb_transaction_with_better_retry(&raw_transaction_hex, 3).await {
+        Ok(tx_hash) => {
+            ic_cdk::println!("BNB transaction sent successfully with hash: {}", tx_hash);
+            tx_hash
+        },
+        Err(e) => {
+            ic_cdk::println!(
+                "Failed to send transaction after retries. Error: {}. Expected tx hash: {}",
+                e,
+                raw_transaction_hash
+            );
+            ic_cdk::trap(&format!("Transaction failed: {}", e));
         }
+    }
+}
 
-        let rpc_endpoints = get_bnb_rpc_endpoints(bsc_network);
-        let mut last_error = String::new();
-
-        for attempt in 0..max_retries {
-            for (endpoint_name, rpc_service) in &rpc_endpoints {
-                ic_cdk::println!("Send attempt {} using endpoint {}", attempt + 1, endpoint_name);
-
-                let json = format!(
-                    r#"{{ "jsonrpc": "2.0", "method": "eth_sendRawTransaction", "params": ["{}"], "id": 1 }}"#,
-                    raw_transaction_hex
-                );
-
-                let max_response_size_bytes = 2000_u64;
-                let num_cycles = 5_000_000_000u128;
-
-                let response = BSC_RPC
-                    .request(rpc_service.clone(), json, max_response_size_bytes, num_cycles)
-                    .await;
-
-                match response {
-                    Ok((response,)) => {
-                        match response {
-                            RequestResult::Ok(tx_result) => {
-                                match serde_json::from_str::<serde_json::Value>(&tx_result) {
-                                    Ok(json_response) => {
-                                        if let Some(error) = json_response.get("error") {
-                                            let error_code = error.get("code").and_then(|c| c.as_i64()).unwrap_or(0);
-                                            let error_message = error.get("message")
-                                                .and_then(|m| m.as_str())
-                                                .unwrap_or("Unknown error");
-
-                                            last_error = format!("RPC Error {}: {}", error_code, error_message);
-                                            ic_cdk::println!("RPC error from {}: {}", endpoint_name, last_error);
-
-                                            match error_code {
-                                                -32000 => {
-                                                    if error_message.contains("nonce too low") {
-                                                        return Err("Transaction failed: nonce too low (transaction may have already been sent)".to_string());
-                                                    } else if error_message.contains("insufficient funds") {
-                                                        return Err("Transaction failed: insufficient funds for gas or value".to_string());
-                                                    } else if error_message.contains("gas price too low") || error_message.contains("underpriced") {
-                                                        return Err("Transaction failed: gas price too low for current network conditions".to_string());
-                                                    }
-                                                }
-                                                -32602 => {
-                                                    return Err("Transaction failed: invalid transaction parameters".to_string());
+async fn send_bnb_transaction_with_better_retry(raw_transaction_hex: &str, max_retries: u32) -> Result<String, String> {
+    let rpc_endpoints = get_bnb_rpc_endpoints();
+    let mut last_error = String::new();
+
+    for attempt in 0..max_retries {
+        for (endpoint_name, rpc_service) in &rpc_endpoints {
+            ic_cdk::println!("Send attempt {} using endpoint {}", attempt + 1, endpoint_name);
+
+            let json = format!(
+                r#"{{ "jsonrpc": "2.0", "method": "eth_sendRawTransaction", "params": ["{}"], "id": 1 }}"#,
+                raw_transaction_hex
+            );
+
+            let max_response_size_bytes = 2000_u64;
+            let num_cycles = cycles::evm_rpc_request_cost(json.len() as u64, max_response_size_bytes, 1);
+
+            let response = BSC_RPC
+                .request(rpc_service.clone(), json, max_response_size_bytes, num_cycles)
+                .await;
+
+            match response {
+                Ok((response,)) => {
+                    match response {
+                        RequestResult::Ok(tx_result) => {
+                            match serde_json::from_str::<serde_json::Value>(&tx_result) {
+                                Ok(json_response) => {
+                                    if let Some(error) = json_response.get("error") {
+                                        let error_code = error.get("code").and_then(|c| c.as_i64()).unwrap_or(0);
+                                        let error_message = error.get("message")
+                                            .and_then(|m| m.as_str())
+                                            .unwrap_or("Unknown error");
+
+                                        last_error = format!("RPC Error {}: {}", error_code, error_message);
+                                        ic_cdk::println!("RPC error from {}: {}", endpoint_name, last_error);
+
+                                        match error_code {
+                                            -32000 => {
+                                                if error_message.contains("nonce too low") {
+                                                    return Err("Transaction failed: nonce too low (transaction may have already been sent)".to_string());
+                                                } else if error_message.contains("insufficient funds") {
+                                                    return Err("Transaction failed: insufficient funds for gas or value".to_string());
+                                                } else if error_message.contains("gas price too low") || error_message.contains("underpriced") {
+                                                    return Err("Transaction failed: gas price too low for current network conditions".to_string());
                                                 }
-                                                _ => {}
                                             }
-
-                                            continue;
+                                            -32602 => {
+                                                return Err("Transaction failed: invalid transaction parameters".to_string());
+                                            }
+                                            _ => {}
                                         }
 
-                                        if let Some(tx_hash) = json_response.get("result").and_then(|v| v.as_str()) {
-                                            ic_cdk::println!("Transaction sent successfully via {}: {}", endpoint_name, tx_hash);
-                                            return Ok(tx_hash.to_string());
-                                        } else {
-                                            last_error = "No transaction hash in response".to_string();
-                                            continue;
-                                        }
+                                        continue;
                                     }
-                                    Err(e) => {
-                                        last_error = format!("JSON parse error: {}", e);
-                                        ic_cdk::println!("Failed to parse response from {}: {}. Raw: {}", endpoint_name, e, tx_result);
+
+                                    if let Some(tx_hash) = json_response.get("result").and_then(|v| v.as_str()) {
+                                        ic_cdk::println!("Transaction sent successfully via {}: {}", endpoint_name, tx_hash);
+                                        return Ok(tx_hash.to_string());
+                                    } else {
+                                        last_error = "No transaction hash in response".to_string();
                                         continue;
                                     }
                                 }
-                            }
-                            RequestResult::Err(e) => {
-                                last_error = format!("RPC request error: {:?}", e);
-                                ic_cdk::println!("RPC request error from {}: {:?}", endpoint_name, e);
-                                continue;
+                                Err(e) => {
+                                    last_error = format!("JSON parse error: {}", e);
+                                    ic_cdk::println!("Failed to parse response from {}: {}. Raw: {}", endpoint_name, e, tx_result);
+                                    continue;
+                                }
                             }
                         }
-                    }
-                    Err(e) => {
-                        last_error = format!("HTTP outcall error: {:?}", e);
-                        ic_cdk::println!("HTTP outcall error with {}: {:?}", endpoint_name, e);
-                        continue;
+                        RequestResult::Err(e) => {
+                            last_error = format!("RPC request error: {:?}", e);
+                            ic_cdk::println!("RPC request error from {}: {:?}", endpoint_name, e);
+                            continue;
+                        }
                     }
                 }
-            }
-
-            if attempt < max_retries - 1 {
-                ic_cdk::println!("All endpoints failed for attempt {}, retrying...", attempt + 1);
+                Err(e) => {
+                    last_error = format!("HTTP outcall error: {:?}", e);
+                    ic_cdk::println!("HTTP outcall error with {}: {:?}", endpoint_name, e);
+                    continue;
+                }
             }
         }
 
-        Err(format!("All send attempts failed. Last error: {}", last_error))
-    }
-
-    match send_bnb_transaction_with_better_retry(&raw_transaction_hex, bsc_network, 3).await {
-        Ok(tx_hash) => {
-            ic_cdk::println!("BNB transaction sent successfully with hash: {}", tx_hash);
-            tx_hash
-        },
-        Err(e) => {
-            ic_cdk::println!(
-                "Failed to send transaction after retries. Error: {}. Expected tx hash: {}",
-                e,
-                raw_transaction_hash
-            );
-            ic_cdk::trap(&format!("Transaction failed: {}", e));
+        if attempt < max_retries - 1 {
+            ic_cdk::println!("All endpoints failed for attempt {}, retrying...", attempt + 1);
         }
     }
-}
\ No newline at end of file
+
+    Err(format!("All send attempts failed. Last error: {}", last_error))
+}
+
+fn get_bnb_rpc_endpoints() -> Vec<(String, RpcService)> {
+    read_state(|s| s.rpc_providers())
+        .callable()
+        .iter()
+        .map(|provider| (provider.name.clone(), RpcService::Custom(crate::state::rpc_api(provider))))
+        .collect()
+}
@@ -1 +1,3 @@
-pub mod bsc_service;
\ No newline at end of file
+pub mod bsc_service;
+pub mod metrics;
+pub mod spending_service;
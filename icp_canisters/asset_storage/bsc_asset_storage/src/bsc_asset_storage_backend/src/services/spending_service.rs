@@ -0,0 +1,164 @@
+use crate::services::bsc_service::{self, BscTransactionInput};
+use bit10_core::spending::{
+    PendingTransfer, PolicyChange, PolicyChangeOutcome, SpendingPolicy, TransferOutcome, TransferQueue,
+};
+use candid::{CandidType, Deserialize, Nat};
+use num::{BigUint, Num};
+use std::cell::RefCell;
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub enum BscTransfer {
+    Bnb(BscTransactionInput),
+    Bep20 { token_address: String },
+}
+
+pub type BscPendingTransfer = PendingTransfer<BscTransfer>;
+
+thread_local! {
+    static TRANSFERS: RefCell<TransferQueue<BscTransfer>> = RefCell::new(TransferQueue::default());
+}
+
+pub fn export_transfers() -> TransferQueue<BscTransfer> {
+    TRANSFERS.with(|t| t.borrow().clone())
+}
+
+pub fn import_transfers(transfers: TransferQueue<BscTransfer>) {
+    TRANSFERS.with(|t| *t.borrow_mut() = transfers);
+}
+
+pub fn assert_controller_or_approver() {
+    let caller = ic_cdk::api::caller();
+    if caller != ic_cdk::api::id() && !TRANSFERS.with(|t| t.borrow().is_approver(&caller)) {
+        ic_cdk::trap("Only the canister controller or an approver can call this method.");
+    }
+}
+
+pub fn get_policy() -> SpendingPolicy {
+    TRANSFERS.with(|t| t.borrow().policy().clone())
+}
+
+/// Addresses are compared lowercase, so allowlisted destinations and asset
+/// keys are stored that way.
+pub fn propose_policy(mut policy: SpendingPolicy) -> Result<PolicyChangeOutcome, String> {
+    policy.assets = policy
+        .assets
+        .into_iter()
+        .map(|(asset, mut asset_policy)| {
+            lowercase_destinations(&mut asset_policy.allowed_destinations);
+            (asset_key(&asset), asset_policy)
+        })
+        .collect();
+    lowercase_destinations(&mut policy.default_policy.allowed_destinations);
+    TRANSFERS.with(|t| t.borrow_mut().propose_policy(policy, ic_cdk::api::caller(), ic_cdk::api::time()))
+}
+
+fn lowercase_destinations(destinations: &mut [String]) {
+    for destination in destinations.iter_mut() {
+        *destination = destination.to_ascii_lowercase();
+    }
+}
+
+fn asset_key(asset: &str) -> String {
+    if asset.eq_ignore_ascii_case("BNB") {
+        "BNB".to_string()
+    } else {
+        asset.to_ascii_lowercase()
+    }
+}
+
+/// Native transfers only. Arbitrary calldata could move BEP-20 tokens
+/// without being checked against that token's policy, so contract transfers
+/// have to go through `send_bep20`.
+pub async fn send_bnb(tx_input: BscTransactionInput) -> Result<TransferOutcome<BscTransfer>, String> {
+    let data = tx_input.data.strip_prefix("0x").unwrap_or(&tx_input.data);
+    if !data.is_empty() {
+        return Err("send_bnb does not accept calldata, use send_bep20_token for token transfers".to_string());
+    }
+    let value = tx_input.value.strip_prefix("0x").unwrap_or(&tx_input.value);
+    let amount = if value.is_empty() {
+        Nat::from(0u64)
+    } else {
+        BigUint::from_str_radix(value, 16)
+            .map(Nat)
+            .map_err(|e| format!("Failed to parse value '{}': {}", tx_input.value, e))?
+    };
+
+    let to = tx_input.to.clone();
+    send("BNB".to_string(), to, amount, BscTransfer::Bnb(tx_input)).await
+}
+
+pub async fn send_bep20(to: String, amount: Nat, token_address: String) -> Result<TransferOutcome<BscTransfer>, String> {
+    send(asset_key(&token_address), to, amount, BscTransfer::Bep20 { token_address }).await
+}
+
+/// Runs the transfer straight away if the policy allows it, otherwise leaves
+/// it queued for approvals or its delay.
+async fn send(asset: String, to: String, amount: Nat, args: BscTransfer) -> Result<TransferOutcome<BscTransfer>, String> {
+    let now = ic_cdk::api::time();
+    let transfer = TRANSFERS.with(|t| {
+        t.borrow_mut()
+            .request(&asset, &to.to_ascii_lowercase(), amount, args, ic_cdk::api::caller(), now)
+    })?;
+
+    if !transfer.is_ready(now) {
+        return Ok(TransferOutcome::Pending(transfer));
+    }
+    execute(transfer.id).await.map(TransferOutcome::Executed)
+}
+
+pub async fn execute(id: u64) -> Result<String, String> {
+    let transfer = TRANSFERS.with(|t| t.borrow_mut().start_execution(id, ic_cdk::api::time()))?;
+
+    // The BSC service traps on failure, so a returned hash means success.
+    let tx_hash = match transfer.args {
+        BscTransfer::Bnb(tx_input) => bsc_service::send_bnb_transaction(transfer.requested_by, tx_input).await,
+        BscTransfer::Bep20 { token_address } => {
+            bsc_service::send_bep20(transfer.requested_by, transfer.destination, transfer.amount, token_address).await
+        }
+    };
+
+    let result = Ok(tx_hash);
+    TRANSFERS.with(|t| t.borrow_mut().finish_execution(id, &result, ic_cdk::api::time()));
+    result
+}
+
+pub fn approve(id: u64) -> Result<BscPendingTransfer, String> {
+    TRANSFERS.with(|t| t.borrow_mut().approve(id, ic_cdk::api::caller(), ic_cdk::api::time()))
+}
+
+pub fn cancel(id: u64) -> Result<BscPendingTransfer, String> {
+    TRANSFERS.with(|t| t.borrow_mut().cancel(id, ic_cdk::api::time()))
+}
+
+pub fn get_policy_change() -> Option<PolicyChange> {
+    TRANSFERS.with(|t| t.borrow().policy_change().cloned())
+}
+
+pub fn approve_policy_change() -> Result<PolicyChange, String> {
+    TRANSFERS.with(|t| t.borrow_mut().approve_policy_change(ic_cdk::api::caller()))
+}
+
+pub fn apply_policy_change() -> Result<(), String> {
+    TRANSFERS.with(|t| t.borrow_mut().apply_policy_change(ic_cdk::api::time()))
+}
+
+pub fn cancel_policy_change() -> Result<PolicyChange, String> {
+    TRANSFERS.with(|t| t.borrow_mut().cancel_policy_change())
+}
+
+pub fn resolve_stuck(id: u64, tx: Option<String>) -> Result<BscPendingTransfer, String> {
+    let result = tx.ok_or_else(|| "Marked failed by a controller".to_string());
+    TRANSFERS.with(|t| t.borrow_mut().resolve_stuck(id, &result, ic_cdk::api::time()))
+}
+
+pub fn get_transfer(id: u64) -> Option<BscPendingTransfer> {
+    TRANSFERS.with(|t| t.borrow().get(id).cloned())
+}
+
+pub fn get_pending_transfers() -> Vec<BscPendingTransfer> {
+    TRANSFERS.with(|t| t.borrow().pending())
+}
+
+pub fn pending_count() -> usize {
+    TRANSFERS.with(|t| t.borrow().pending().len())
+}
@@ -1,8 +1,13 @@
 use crate::ecdsa::EcdsaPublicKey;
 use crate::{EcdsaKeyName, BscNetwork, InitArg};
+use bit10_core::fees::FeeConfig;
+use bit10_core::nonce::NonceManager;
+use bit10_core::providers::{ProviderSet, RpcProvider};
+use candid::{CandidType, Deserialize, Principal};
 use evm_rpc_canister_types::{RpcServices, RpcApi, HttpHeader};
 use ic_cdk::api::management_canister::ecdsa::EcdsaKeyId;
 use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
 use std::ops::{Deref, DerefMut};
 
 thread_local! {
@@ -29,6 +34,20 @@ pub struct State {
     bsc_network: BscNetwork,
     ecdsa_key_name: EcdsaKeyName,
     ecdsa_public_key: Option<EcdsaPublicKey>,
+    fee_config: Option<FeeConfig>,
+    nonce_manager: NonceManager,
+    nonce_owners: BTreeMap<String, Principal>,
+    token_allowlist: BTreeSet<String>,
+    rpc_providers: Option<ProviderSet>,
+}
+
+/// The parts of `State` that are carried across upgrades.
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct PersistedState {
+    pub fee_config: Option<FeeConfig>,
+    pub nonce_manager: NonceManager,
+    pub nonce_owners: BTreeMap<String, Principal>,
+    pub token_allowlist: BTreeSet<String>,
 }
 
 impl State {
@@ -40,41 +59,134 @@ impl State {
         self.bsc_network
     }
 
+    pub fn fee_config(&self) -> FeeConfig {
+        self.fee_config.clone().unwrap_or_else(FeeConfig::bsc)
+    }
+
+    pub fn set_fee_config(&mut self, fee_config: FeeConfig) {
+        self.fee_config = Some(fee_config);
+    }
+
+    pub fn nonce_manager(&self) -> &NonceManager {
+        &self.nonce_manager
+    }
+
+    pub fn nonce_manager_mut(&mut self) -> &mut NonceManager {
+        &mut self.nonce_manager
+    }
+
+    /// Owner principal of every wallet tracked by the nonce manager, needed to
+    /// sign cancel transactions during reconciliation.
+    pub fn nonce_owners(&self) -> &BTreeMap<String, Principal> {
+        &self.nonce_owners
+    }
+
+    pub fn set_nonce_owner(&mut self, nonce_key: String, owner: Principal) {
+        self.nonce_owners.insert(nonce_key, owner);
+    }
+
+    /// BEP-20 contracts this canister may transfer, stored lowercase.
+    pub fn token_allowlist(&self) -> &BTreeSet<String> {
+        &self.token_allowlist
+    }
+
+    pub fn add_allowed_token(&mut self, token_address: &str) {
+        self.token_allowlist.insert(token_address.to_ascii_lowercase());
+    }
+
+    pub fn remove_allowed_token(&mut self, token_address: &str) -> bool {
+        self.token_allowlist.remove(&token_address.to_ascii_lowercase())
+    }
+
+    /// Endpoints in the order sends and single-endpoint reads fall back
+    /// through them. Balance reads need `quorum` of them to agree.
+    pub fn rpc_providers(&self) -> ProviderSet {
+        self.rpc_providers
+            .clone()
+            .unwrap_or_else(|| default_rpc_providers(self.bsc_network))
+    }
+
+    pub fn rpc_providers_mut(&mut self) -> &mut ProviderSet {
+        let bsc_network = self.bsc_network;
+        self.rpc_providers
+            .get_or_insert_with(|| default_rpc_providers(bsc_network))
+    }
+
+    /// `None` until a controller changes the defaults or a quorum read
+    /// starts tracking the default providers' health.
+    pub fn configured_rpc_providers(&self) -> Option<ProviderSet> {
+        self.rpc_providers.clone()
+    }
+
+    pub fn set_configured_rpc_providers(&mut self, rpc_providers: Option<ProviderSet>) {
+        self.rpc_providers = rpc_providers;
+    }
+
+    pub fn export_persisted(&self) -> PersistedState {
+        PersistedState {
+            fee_config: self.fee_config.clone(),
+            nonce_manager: self.nonce_manager.clone(),
+            nonce_owners: self.nonce_owners.clone(),
+            token_allowlist: self.token_allowlist.clone(),
+        }
+    }
+
+    pub fn import_persisted(&mut self, persisted: PersistedState) {
+        self.fee_config = persisted.fee_config;
+        self.nonce_manager = persisted.nonce_manager;
+        self.nonce_owners = persisted.nonce_owners;
+        self.token_allowlist = persisted.token_allowlist;
+    }
+
     pub fn evm_rpc_services(&self) -> RpcServices {
-        match self.bsc_network {
-            BscNetwork::Mainnet => RpcServices::Custom {
-                chainId: 56,
-                services: vec![RpcApi {
-                    url: "https://bsc-mainnet.gateway.tatum.io/".to_string(),
-                    headers: Some(vec![
-                        HttpHeader {
-                            name: "x-api-key".to_string(),
-                            value: "<API_KEY>".to_string(),
-                        }
-                    ]),
-                }],
-            },
-            BscNetwork::Testnet => RpcServices::Custom {
-                chainId: 97,
-                services: vec![RpcApi {
-                    url: "https://bsc-testnet.gateway.tatum.io/".to_string(),
-                    headers: Some(vec![
-                        HttpHeader {
-                            name: "x-api-key".to_string(),
-                            value: "<API_KEY>".to_string(),
-                        }
-                    ]),
-                }],
-            },
+        RpcServices::Custom {
+            chainId: self.bsc_network.chain_id(),
+            services: self.rpc_providers().callable().iter().map(rpc_api).collect(),
         }
     }
 }
 
+fn default_rpc_providers(bsc_network: BscNetwork) -> ProviderSet {
+    let providers = match bsc_network {
+        BscNetwork::Mainnet => vec![
+            RpcProvider::new("binance", "https://bsc-dataseed.binance.org/"),
+            RpcProvider::new("publicnode", "https://bsc.publicnode.com"),
+            RpcProvider::new("tatum", "https://bsc-mainnet.gateway.tatum.io/").with_header("x-api-key", "{api_key}"),
+        ],
+        BscNetwork::Testnet => vec![
+            RpcProvider::new("publicnode", "https://bsc-testnet.publicnode.com"),
+            RpcProvider::new("binance", "https://data-seed-prebsc-1-s1.binance.org:8545/"),
+            RpcProvider::new("tatum", "https://bsc-testnet.gateway.tatum.io/").with_header("x-api-key", "{api_key}"),
+        ],
+    };
+    ProviderSet::new(providers, 2).expect("Invalid default BSC providers")
+}
+
+/// The provider as the EVM RPC canister takes it, with its key filled in.
+pub fn rpc_api(provider: &RpcProvider) -> RpcApi {
+    let headers: Vec<HttpHeader> = provider
+        .resolved_headers()
+        .into_iter()
+        .map(|(name, value)| HttpHeader { name, value })
+        .collect();
+    RpcApi {
+        url: provider.resolved_url(),
+        headers: (!headers.is_empty()).then_some(headers),
+    }
+}
+
 impl From<InitArg> for State {
     fn from(init_arg: InitArg) -> Self {
         State {
             bsc_network: init_arg.bsc_network.unwrap_or_default(),
             ecdsa_key_name: init_arg.ecdsa_key_name.unwrap_or_default(),
+            fee_config: init_arg.fee_config,
+            token_allowlist: init_arg
+                .token_allowlist
+                .unwrap_or_default()
+                .iter()
+                .map(|token| token.to_ascii_lowercase())
+                .collect(),
             ..Default::default()
         }
     }
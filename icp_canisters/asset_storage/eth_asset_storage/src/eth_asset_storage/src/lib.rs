@@ -3,14 +3,99 @@ mod services;
 mod state;
 mod wallet;
 
+use bit10_core::fees::FeeConfig;
+use bit10_core::nonce::NonceManager;
+use bit10_core::providers::{ProviderSet, ProviderSetView, RpcProvider};
+use bit10_core::spending::{PolicyChange, PolicyChangeOutcome, SpendingPolicy, TransferOutcome, TransferQueue};
 use candid::{CandidType, Deserialize, Nat, Principal};
-use ic_cdk::{init, update};
+use ic_cdk::{init, post_upgrade, pre_upgrade, query, update};
+use services::spending_service::{self, EthPendingTransfer, EthTransfer};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+const NONCE_RECONCILE_INTERVAL: Duration = Duration::from_secs(120);
+const GAS_CHECK_INTERVAL: Duration = Duration::from_secs(600);
 
 #[init]
 fn init(maybe_init: Option<state::InitArg>) {
     if let Some(init_arg) = maybe_init {
         state::init_state(init_arg)
     }
+    start_nonce_reconciliation();
+    start_gas_checks();
+}
+
+#[pre_upgrade]
+fn pre_upgrade() {
+    let (rpc_providers, fee_config, nonce_manager, nonce_owners) = state::read_state(|s| {
+        (
+            s.rpc_providers.clone(),
+            s.fee_config.clone(),
+            s.nonce_manager.clone(),
+            s.nonce_owners.clone(),
+        )
+    });
+    ic_cdk::storage::stable_save((
+        Some(spending_service::export_transfers()),
+        rpc_providers,
+        Some(fee_config),
+        Some(nonce_manager),
+        Some(nonce_owners),
+    ))
+        .expect("Failed to save state to stable storage");
+}
+
+#[post_upgrade]
+fn post_upgrade() {
+    if let Ok((transfers, rpc_providers, fee_config, nonce_manager, nonce_owners)) =
+        ic_cdk::storage::stable_restore::<(
+            Option<TransferQueue<EthTransfer>>,
+            Option<ProviderSet>,
+            Option<FeeConfig>,
+            Option<NonceManager>,
+            Option<BTreeMap<String, Principal>>,
+        )>()
+    {
+        if let Some(transfers) = transfers {
+            spending_service::import_transfers(transfers);
+        }
+        state::mutate_state(|s| {
+            s.rpc_providers = rpc_providers;
+            if let Some(fee_config) = fee_config {
+                s.fee_config = fee_config;
+            }
+            // Without the reservations the next send would re-seed from the
+            // chain's `pending` count and could reuse an in-flight nonce.
+            if let Some(nonce_manager) = nonce_manager {
+                s.nonce_manager = nonce_manager;
+            }
+            if let Some(nonce_owners) = nonce_owners {
+                s.nonce_owners = nonce_owners;
+            }
+        });
+    }
+    start_nonce_reconciliation();
+    start_gas_checks();
+}
+
+fn start_nonce_reconciliation() {
+    ic_cdk_timers::set_timer_interval(NONCE_RECONCILE_INTERVAL, || {
+        ic_cdk::spawn(async {
+            if let Err(e) = services::eth_service::reconcile_nonces().await {
+                ic_cdk::println!("Nonce reconciliation failed: {}", e);
+            }
+        })
+    });
+}
+
+fn start_gas_checks() {
+    ic_cdk_timers::set_timer_interval(GAS_CHECK_INTERVAL, || {
+        ic_cdk::spawn(async {
+            if let Err(e) = services::metrics::check_gas_balance().await {
+                ic_cdk::println!("Gas balance check failed: {}", e);
+            }
+        })
+    });
 }
 
 fn assert_controller() {
@@ -40,13 +125,159 @@ async fn transaction_count(owner: Option<Principal>, block: Option<services::eth
 }
 
 #[update]
-async fn send_eth(to: String, amount: Nat) -> Result<String, String> {
+async fn send_eth(to: String, amount: Nat) -> Result<TransferOutcome<EthTransfer>, String> {
     assert_controller();
-    services::eth_service::send_eth(to, amount).await
+    spending_service::send(to, amount, EthTransfer::Eth).await
 }
 
 #[update]
-async fn send_erc20(to: String, amount: Nat, token_address: String) -> Result<String, String> {
+async fn send_erc20(to: String, amount: Nat, token_address: String) -> Result<TransferOutcome<EthTransfer>, String> {
     assert_controller();
-    services::eth_service::send_erc20(to, amount, token_address).await
-}
\ No newline at end of file
+    spending_service::send(to, amount, EthTransfer::Erc20 { token_address }).await
+}
+
+#[query]
+fn get_spending_policy() -> SpendingPolicy {
+    spending_service::assert_controller_or_approver();
+    spending_service::get_policy()
+}
+
+#[update]
+fn set_spending_policy(policy: SpendingPolicy) -> Result<PolicyChangeOutcome, String> {
+    assert_controller();
+    spending_service::propose_policy(policy)
+}
+
+#[query]
+fn get_policy_change() -> Option<PolicyChange> {
+    spending_service::assert_controller_or_approver();
+    spending_service::get_policy_change()
+}
+
+#[update]
+fn approve_policy_change() -> Result<PolicyChange, String> {
+    spending_service::approve_policy_change()
+}
+
+#[update]
+fn apply_policy_change() -> Result<(), String> {
+    spending_service::assert_controller_or_approver();
+    spending_service::apply_policy_change()
+}
+
+#[update]
+fn cancel_policy_change() -> Result<PolicyChange, String> {
+    spending_service::assert_controller_or_approver();
+    spending_service::cancel_policy_change()
+}
+
+#[query]
+fn get_pending_transfers() -> Vec<EthPendingTransfer> {
+    spending_service::assert_controller_or_approver();
+    spending_service::get_pending_transfers()
+}
+
+#[query]
+fn get_transfer(id: u64) -> Option<EthPendingTransfer> {
+    spending_service::assert_controller_or_approver();
+    spending_service::get_transfer(id)
+}
+
+#[update]
+fn approve_transfer(id: u64) -> Result<EthPendingTransfer, String> {
+    spending_service::approve(id)
+}
+
+#[update]
+fn cancel_transfer(id: u64) -> Result<EthPendingTransfer, String> {
+    spending_service::assert_controller_or_approver();
+    spending_service::cancel(id)
+}
+
+#[update]
+async fn execute_transfer(id: u64) -> Result<String, String> {
+    spending_service::assert_controller_or_approver();
+    spending_service::execute(id).await
+}
+
+#[update]
+fn resolve_stuck_transfer(id: u64, tx: Option<String>) -> Result<EthPendingTransfer, String> {
+    assert_controller();
+    spending_service::resolve_stuck(id, tx)
+}
+
+#[update]
+async fn speed_up_transaction(tx_hash: String) -> Result<String, String> {
+    assert_controller();
+    services::eth_service::speed_up_transaction(tx_hash).await
+}
+
+#[update]
+async fn estimate_fees() -> Result<services::eth_service::Eip1559Fees, String> {
+    assert_controller();
+    services::eth_service::estimate_fees().await
+}
+
+#[query]
+fn get_fee_config() -> services::eth_service::FeeConfig {
+    state::read_state(|s| s.fee_config.clone())
+}
+
+#[update]
+fn set_fee_config(config: services::eth_service::FeeConfig) -> Result<(), String> {
+    assert_controller();
+    config.validate()?;
+    state::mutate_state(|s| s.fee_config = config);
+    Ok(())
+}
+
+#[query]
+fn get_nonce_status() -> Vec<(String, bit10_core::nonce::WalletNonces)> {
+    assert_controller();
+    services::eth_service::get_nonce_status()
+}
+
+#[update]
+async fn reconcile_nonces() -> Result<Vec<bit10_core::nonce::ReconcileReport>, String> {
+    assert_controller();
+    services::eth_service::reconcile_nonces().await
+}
+
+/// `None` while the EVM RPC canister's built-in providers are in use.
+#[query]
+fn get_rpc_config() -> Option<ProviderSetView> {
+    assert_controller();
+    state::read_state(|s| s.rpc_providers.as_ref().map(ProviderSet::view))
+}
+
+#[update]
+fn set_rpc_providers(rpc_providers: Vec<RpcProvider>, quorum: u8) -> Result<(), String> {
+    assert_controller();
+    state::mutate_state(|s| {
+        if let Some(set) = s.rpc_providers.as_mut() {
+            return set.replace_providers(rpc_providers, quorum);
+        }
+        s.rpc_providers = Some(ProviderSet::new(rpc_providers, quorum)?);
+        Ok(())
+    })
+}
+
+#[update]
+fn set_rpc_api_key(provider: String, api_key: Option<String>) -> Result<(), String> {
+    assert_controller();
+    state::mutate_state(|s| match &mut s.rpc_providers {
+        Some(set) => set.set_api_key(&provider, api_key),
+        None => Err("No custom RPC providers are configured".to_string()),
+    })
+}
+
+#[update]
+fn reset_rpc_providers() {
+    assert_controller();
+    state::mutate_state(|s| s.rpc_providers = None);
+}
+
+#[query]
+fn http_request(request: bit10_core::metrics::HttpRequest) -> bit10_core::metrics::HttpResponse {
+    services::metrics::http_request(request)
+}
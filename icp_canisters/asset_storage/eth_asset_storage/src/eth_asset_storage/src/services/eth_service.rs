@@ -1,17 +1,25 @@
 use crate::wallet::ethereum_wallet::EthereumWallet;
-use crate::state::{read_state, lazy_call_ecdsa_public_key};
+use crate::state::{read_state, mutate_state, lazy_call_ecdsa_public_key};
+use alloy_consensus::TxEip1559;
+use alloy_primitives::U256;
+use bit10_core::cycles;
+use bit10_core::fees;
+use bit10_core::nonce::{self, ReconcileReport, TransactionCall, WalletNonces};
 use candid::{CandidType, Deserialize, Nat, Principal};
 use ic_ethereum_types::Address;
 use num::{BigUint, Num};
 use std::str::FromStr;
 
-pub use evm_rpc_canister_types::{BlockTag, EthMainnetService, EthSepoliaService, RpcServices, GetTransactionCountArgs, GetTransactionCountResult, MultiGetTransactionCountResult, RequestResult};
+pub use bit10_core::fees::{Eip1559Fees, FeeConfig};
+
+pub use evm_rpc_canister_types::{BlockTag, EthMainnetService, EthSepoliaService, RpcService, RpcServices, GetTransactionCountArgs, GetTransactionCountResult, MultiGetTransactionCountResult, RequestResult, RpcError};
 pub use ic_cdk::api::management_canister::ecdsa::{EcdsaCurve, EcdsaKeyId};
 
 #[derive(CandidType, Deserialize, Debug, Default, PartialEq, Eq)]
 pub struct InitArg {
     pub ethereum_network: Option<EthereumNetwork>,
     pub ecdsa_key_name: Option<EcdsaKeyName>,
+    pub fee_config: Option<FeeConfig>,
 }
 
 #[derive(CandidType, Deserialize, Debug, Default, PartialEq, Eq, Clone, Copy)]
@@ -70,47 +78,35 @@ pub async fn get_balance(address: Option<String>) -> Result<Nat, String> {
         address
     );
 
-    let max_response_size_bytes = 500_u64;
-    let num_cycles = 1_000_000_000u128;
-
-    let ethereum_network = read_state(|s| s.ethereum_network());
+    let body = evm_json_rpc(json, 500).await?;
+    let response: serde_json::Value = serde_json::from_str(&body).map_err(|e| e.to_string())?;
+    let hex_balance = response
+        .get("result")
+        .and_then(|v| v.as_str())
+        .ok_or("No result field in response".to_string())?;
 
-    let rpc_service = match ethereum_network {
-        EthereumNetwork::Mainnet => RpcServices::EthMainnet(Some(vec![EthMainnetService::PublicNode])),
-        EthereumNetwork::Sepolia => RpcServices::EthSepolia(Some(vec![EthSepoliaService::PublicNode])),
-    };
+    Ok(Nat(BigUint::from_str_radix(&hex_balance[2..], 16).map_err(|e| e.to_string())?))
+}
 
-    let (response,) = crate::EVM_RPC
-        .request(rpc_service, json, max_response_size_bytes, num_cycles)
-        .await
-        .map_err(|e| format!("RPC call failed: {:?}", e))?;
-
-    let hex_balance = match response {
-        RequestResult::Ok(balance_result) => {
-            let response: serde_json::Value = serde_json::from_str(&balance_result).map_err(|e| e.to_string())?;
-            response
-                .get("result")
-                .and_then(|v| v.as_str())
-                .ok_or("No result field in response".to_string())?
-                .to_string()
-        }
-        RequestResult::Err(e) => return Err(format!("Received an error response: {:?}", e)),
-    };
+// Typed EVM RPC methods build their own payloads, which stay under this
+// apart from any raw transaction they carry.
+const TYPED_REQUEST_BYTES: usize = 256;
 
-    Ok(Nat(BigUint::from_str_radix(&hex_balance[2..], 16).map_err(|e| e.to_string())?))
+fn typed_request_cost(providers: u128) -> u128 {
+    cycles::evm_rpc_request_cost(TYPED_REQUEST_BYTES as u64, cycles::JSON_RPC_RESPONSE_BYTES, providers)
 }
 
 pub async fn transaction_count(owner: Option<Principal>, block: Option<BlockTag>) -> Result<Nat, String> {
     let caller = ic_cdk::caller();
     let owner = owner.unwrap_or(caller);
     let wallet = EthereumWallet::new(owner).await;
-    let rpc_services = read_state(|s| s.evm_rpc_services());
+    let (rpc_services, providers) = read_state(|s| (s.evm_rpc_services(), s.evm_rpc_provider_count()));
     let args = GetTransactionCountArgs {
         address: wallet.ethereum_address().to_string(),
         block: block.unwrap_or(BlockTag::Finalized),
     };
     let (result,) = crate::EVM_RPC
-        .eth_get_transaction_count(rpc_services, None, args.clone(), 2_000_000_000_u128)
+        .eth_get_transaction_count(rpc_services, None, args.clone(), typed_request_cost(providers))
         .await
         .map_err(|e| format!("failed to get transaction count for {:?}, error: {:?}", args, e))?;
     match result {
@@ -124,82 +120,48 @@ pub async fn transaction_count(owner: Option<Principal>, block: Option<BlockTag>
     }
 }
 
-pub async fn send_eth(to: String, amount: Nat) -> Result<String, String> {
-    use alloy_eips::eip2718::Encodable2718;
+/// Sends from the wallet derived for `caller`, which is the principal that
+/// requested the transfer rather than whoever executes it.
+pub async fn send_eth(caller: Principal, to: String, amount: Nat) -> Result<String, String> {
     use crate::wallet::ethereum_wallet::EthereumWallet;
     use crate::state::read_state;
-    use alloy_primitives::{Signature, TxKind, U256};
-    use alloy_consensus::{TxEip1559, TxEnvelope};
+    use alloy_primitives::TxKind;
     use std::str::FromStr;
 
-    let caller = ic_cdk::caller();
     let to_address = Address::from_str(&to)
         .map_err(|e| format!("failed to parse the recipient address: {:?}", e))?;
     let chain_id = read_state(|s| s.ethereum_network().chain_id());
-    let nonce = super::super::services::eth_service::transaction_count(Some(caller), Some(BlockTag::Latest)).await
-        .map_err(|e| format!("failed to get transaction count: {}", e))?;
-    let nonce = nat_to_u64(nonce)?;
 
-    let (gas_limit, max_fee_per_gas, max_priority_fee_per_gas) = estimate_transaction_fees();
+    let value = nat_to_u256(amount.clone());
+    let from_address = EthereumWallet::new(caller).await.ethereum_address().to_string();
+    let gas_limit = estimate_gas_limit(&from_address, &to_address.to_string(), value, &[]).await?;
+    let fees = estimate_fees().await?;
+    let (nonce_key, nonce) = reserve_nonce(caller, &from_address).await?;
 
     let transaction = TxEip1559 {
         chain_id,
         nonce,
         gas_limit,
-        max_fee_per_gas,
-        max_priority_fee_per_gas,
+        max_fee_per_gas: fees.max_fee_per_gas,
+        max_priority_fee_per_gas: fees.max_priority_fee_per_gas,
         to: TxKind::Call(to_address),
-        value: nat_to_u256(amount.clone()),
+        value,
         access_list: Default::default(),
         input: Default::default(),
     };
 
-    let wallet = EthereumWallet::new(caller).await;
-    let tx_hash = transaction.signature_hash().0;
-    let (raw_signature, recovery_id) = wallet.sign_with_ecdsa(tx_hash).await;
-    let signature = Signature::from_bytes_and_parity(&raw_signature, recovery_id.is_y_odd())
-        .map_err(|_| "BUG: failed to create a signature".to_string())?;
-    let signed_tx = transaction.into_signed(signature);
-
-    let raw_transaction_hash = *signed_tx.hash();
-    let mut tx_bytes: Vec<u8> = vec![];
-    TxEnvelope::from(signed_tx).encode_2718(&mut tx_bytes);
-    let raw_transaction_hex = format!("0x{}", alloy_primitives::hex::encode(&tx_bytes));
-
-    let single_rpc_service = read_state(|s| s.single_evm_rpc_service());
-    let (result,) = crate::EVM_RPC
-        .eth_send_raw_transaction(
-            single_rpc_service,
-            None,
-            raw_transaction_hex.clone(),
-            2_000_000_000_u128,
-        )
-        .await
-        .map_err(|e| format!("failed to send raw transaction {}, error: {:?}", raw_transaction_hex, e))?;
-
-    match result {
-        evm_rpc_canister_types::RequestResult::Ok(_) => Ok(raw_transaction_hash.to_string()),
-        evm_rpc_canister_types::RequestResult::Err(e) => Err(format!("Error sending transaction: {:?}", e)),
-    }
+    submit_transaction(caller, &nonce_key, transaction).await
 }
 
-pub async fn send_erc20(to: String, amount: Nat, token_address: String) -> Result<String, String> {
-    use alloy_eips::eip2718::Encodable2718;
+pub async fn send_erc20(caller: Principal, to: String, amount: Nat, token_address: String) -> Result<String, String> {
     use crate::wallet::ethereum_wallet::EthereumWallet;
     use crate::state::read_state;
-    use alloy_primitives::{Signature, TxKind, U256, Bytes};
-    use alloy_consensus::{TxEip1559, TxEnvelope};
+    use alloy_primitives::{TxKind, U256, Bytes};
     use std::str::FromStr;
 
-    let caller = ic_cdk::caller();
     let to_address = Address::from_str(&to)
         .map_err(|e| format!("failed to parse the recipient address: {:?}", e))?;
     let chain_id = read_state(|s| s.ethereum_network().chain_id());
-    let nonce = super::super::services::eth_service::transaction_count(Some(caller), Some(BlockTag::Latest)).await
-        .map_err(|e| format!("failed to get transaction count: {}", e))?;
-    let nonce = nat_to_u64(nonce)?;
-
-    let (gas_limit, max_fee_per_gas, max_priority_fee_per_gas) = estimate_transaction_fees();
 
     let mut input_data = alloy_primitives::hex::decode("a9059cbb")
         .map_err(|e| format!("failed to decode method selector: {:?}", e))?;
@@ -216,19 +178,264 @@ pub async fn send_erc20(to: String, amount: Nat, token_address: String) -> Resul
     let amount_bytes = amount_u256.to_be_bytes::<32>();
     input_data.extend_from_slice(&amount_bytes);
 
+    let token_address: alloy_primitives::Address = token_address
+        .parse()
+        .map_err(|e| format!("failed to parse token address: {:?}", e))?;
+    let from_address = EthereumWallet::new(caller).await.ethereum_address().to_string();
+    let gas_limit = estimate_gas_limit(
+        &from_address,
+        &token_address.to_string(),
+        U256::from(0),
+        &input_data,
+    )
+    .await?;
+    let fees = estimate_fees().await?;
+    let (nonce_key, nonce) = reserve_nonce(caller, &from_address).await?;
+
     let transaction = TxEip1559 {
         chain_id,
         nonce,
         gas_limit,
-        max_fee_per_gas,
-        max_priority_fee_per_gas,
-        to: TxKind::Call(token_address.parse().map_err(|e| format!("failed to parse token address: {:?}", e))?),
+        max_fee_per_gas: fees.max_fee_per_gas,
+        max_priority_fee_per_gas: fees.max_priority_fee_per_gas,
+        to: TxKind::Call(token_address),
         value: U256::from(0),
         access_list: Default::default(),
         input: Bytes::from(input_data),
     };
 
-    let wallet = EthereumWallet::new(caller).await;
+    submit_transaction(caller, &nonce_key, transaction).await
+}
+
+/// Re-broadcasts a pending transaction sent by the caller with the same nonce
+/// and higher fees so it replaces the original in the mempool. Only the fees
+/// change: everything else comes from the copy recorded when this canister
+/// sent the transaction, so a hash it never sent is refused.
+pub async fn speed_up_transaction(tx_hash: String) -> Result<String, String> {
+    use crate::wallet::ethereum_wallet::EthereumWallet;
+    use alloy_primitives::{Bytes, TxKind};
+
+    let caller = ic_cdk::caller();
+    let wallet_address = EthereumWallet::new(caller).await.ethereum_address().to_string();
+    let chain_id = read_state(|s| s.ethereum_network().chain_id());
+    let nonce_key = nonce::wallet_key(chain_id, &wallet_address);
+
+    let pending = read_state(|s| s.nonce_manager.find_submitted(&nonce_key, &tx_hash).cloned())
+        .ok_or_else(|| format!("Transaction {} is not a pending transaction sent by this canister", tx_hash))?;
+    let call = pending
+        .call
+        .clone()
+        .ok_or_else(|| format!("Transaction {} was sent without a recorded call and cannot be replaced", tx_hash))?;
+    let previous = match (pending.max_fee_per_gas, pending.max_priority_fee_per_gas) {
+        (Some(max_fee_per_gas), Some(max_priority_fee_per_gas)) => Eip1559Fees {
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+        },
+        _ => return Err(format!("Transaction {} has no recorded fees", tx_hash)),
+    };
+
+    let confirmed_nonce = nat_to_u64(transaction_count(Some(caller), Some(BlockTag::Latest)).await?)?;
+    if pending.nonce < confirmed_nonce {
+        return Err(format!("Transaction {} is already mined", tx_hash));
+    }
+
+    let current = estimate_fees().await?;
+    let replacement = fees::replacement_fees(&previous, &current, &read_state(|s| s.fee_config.clone()))?;
+
+    // Another replacement may have landed while the fees were being fetched.
+    if read_state(|s| s.nonce_manager.find_submitted(&nonce_key, &tx_hash).is_none()) {
+        return Err(format!("Transaction {} was replaced in the meantime", tx_hash));
+    }
+
+    let to: alloy_primitives::Address = call
+        .to
+        .parse()
+        .map_err(|e| format!("failed to parse recipient address: {:?}", e))?;
+    let value = U256::from_str_radix(call.value.trim_start_matches("0x"), 16)
+        .map_err(|e| format!("failed to parse value: {:?}", e))?;
+    let input = alloy_primitives::hex::decode(&call.data)
+        .map_err(|e| format!("failed to decode input: {:?}", e))?;
+
+    let transaction = TxEip1559 {
+        chain_id,
+        nonce: pending.nonce,
+        gas_limit: call.gas_limit,
+        max_fee_per_gas: replacement.max_fee_per_gas,
+        max_priority_fee_per_gas: replacement.max_priority_fee_per_gas,
+        to: TxKind::Call(to),
+        value,
+        access_list: Default::default(),
+        input: Bytes::from(input),
+    };
+
+    let tx_hash = sign_and_send_transaction(caller, transaction).await?;
+    mutate_state(|s| {
+        s.nonce_manager.mark_submitted(
+            &nonce_key,
+            pending.nonce,
+            tx_hash.clone(),
+            replacement.max_fee_per_gas,
+            replacement.max_priority_fee_per_gas,
+            ic_cdk::api::time(),
+        )
+    });
+    Ok(tx_hash)
+}
+
+// Submissions older than this are reported as stuck, and reservations that
+// never reached the network are cancelled.
+const NONCE_STALE_AFTER_NS: u64 = 10 * 60 * 1_000_000_000;
+
+pub fn get_nonce_status() -> Vec<(String, WalletNonces)> {
+    read_state(|s| {
+        s.nonce_manager
+            .wallets()
+            .map(|(key, wallet)| (key.clone(), wallet.clone()))
+            .collect()
+    })
+}
+
+pub fn pending_transactions() -> usize {
+    read_state(|s| s.nonce_manager.wallets().map(|(_, wallet)| wallet.pending.len()).sum())
+}
+
+/// Compares the locally reserved nonces of every tracked wallet with the
+/// chain and fills abandoned nonces with zero-value self transfers.
+pub async fn reconcile_nonces() -> Result<Vec<ReconcileReport>, String> {
+    let owners: Vec<(String, Principal)> = read_state(|s| {
+        s.nonce_owners
+            .iter()
+            .map(|(key, owner)| (key.clone(), *owner))
+            .collect()
+    });
+
+    let mut reports = Vec::with_capacity(owners.len());
+    for (nonce_key, owner) in owners {
+        let confirmed_nonce = nat_to_u64(transaction_count(Some(owner), Some(BlockTag::Latest)).await?)?;
+        let report = mutate_state(|s| {
+            s.nonce_manager.reconcile(
+                &nonce_key,
+                confirmed_nonce,
+                ic_cdk::api::time(),
+                NONCE_STALE_AFTER_NS,
+            )
+        });
+
+        for stuck in &report.stuck {
+            ic_cdk::println!(
+                "Transaction {:?} with nonce {} from {} is still pending",
+                stuck.tx_hash,
+                stuck.nonce,
+                nonce_key
+            );
+        }
+
+        for gap in &report.gaps {
+            match send_cancel_transaction(owner, &nonce_key, *gap).await {
+                Ok(tx_hash) => ic_cdk::println!("Filled nonce gap {} of {} with {}", gap, nonce_key, tx_hash),
+                Err(e) => ic_cdk::println!("Failed to fill nonce gap {} of {}: {}", gap, nonce_key, e),
+            }
+        }
+
+        reports.push(report);
+    }
+
+    Ok(reports)
+}
+
+async fn send_cancel_transaction(owner: Principal, nonce_key: &str, gap: u64) -> Result<String, String> {
+    use alloy_primitives::TxKind;
+
+    let fees = estimate_fees().await?;
+    let own_address: alloy_primitives::Address = EthereumWallet::new(owner)
+        .await
+        .ethereum_address()
+        .to_string()
+        .parse()
+        .map_err(|e| format!("failed to parse wallet address: {:?}", e))?;
+
+    mutate_state(|s| s.nonce_manager.mark_cancel(nonce_key, gap, ic_cdk::api::time()));
+
+    let transaction = TxEip1559 {
+        chain_id: read_state(|s| s.ethereum_network().chain_id()),
+        nonce: gap,
+        gas_limit: 21_000,
+        max_fee_per_gas: fees.max_fee_per_gas,
+        max_priority_fee_per_gas: fees.max_priority_fee_per_gas,
+        to: TxKind::Call(own_address),
+        value: U256::ZERO,
+        access_list: Default::default(),
+        input: Default::default(),
+    };
+
+    submit_transaction(owner, nonce_key, transaction).await
+}
+
+/// Hands out the next nonce for the owner's wallet. The first call for a
+/// wallet seeds the counter from its `pending` transaction count; afterwards
+/// reservations never leave the canister, so concurrent sends cannot collide.
+async fn reserve_nonce(owner: Principal, address: &str) -> Result<(String, u64), String> {
+    let chain_id = read_state(|s| s.ethereum_network().chain_id());
+    let nonce_key = nonce::wallet_key(chain_id, address);
+
+    if !read_state(|s| s.nonce_manager.is_tracked(&nonce_key)) {
+        let chain_nonce = nat_to_u64(transaction_count(Some(owner), Some(BlockTag::Pending)).await?)?;
+        mutate_state(|s| {
+            s.nonce_manager.track(&nonce_key, chain_nonce);
+            s.nonce_owners.insert(nonce_key.clone(), owner);
+        });
+    }
+
+    let reserved = mutate_state(|s| s.nonce_manager.reserve(&nonce_key, ic_cdk::api::time()))?;
+    Ok((nonce_key, reserved))
+}
+
+async fn submit_transaction(owner: Principal, nonce_key: &str, transaction: TxEip1559) -> Result<String, String> {
+    let tx_nonce = transaction.nonce;
+    let max_fee_per_gas = transaction.max_fee_per_gas;
+    let max_priority_fee_per_gas = transaction.max_priority_fee_per_gas;
+    let call = TransactionCall {
+        to: transaction.to.to().map(|to| to.to_string()).unwrap_or_default(),
+        value: format!("0x{:x}", transaction.value),
+        data: format!("0x{}", alloy_primitives::hex::encode(&transaction.input)),
+        gas_limit: transaction.gas_limit,
+    };
+
+    match sign_and_send_transaction(owner, transaction).await {
+        Ok(tx_hash) => {
+            mutate_state(|s| {
+                s.nonce_manager.mark_submitted(
+                    nonce_key,
+                    tx_nonce,
+                    tx_hash.clone(),
+                    max_fee_per_gas,
+                    max_priority_fee_per_gas,
+                    ic_cdk::api::time(),
+                );
+                s.nonce_manager.record_call(nonce_key, tx_nonce, call);
+            });
+            Ok(tx_hash)
+        }
+        Err(e) => {
+            mutate_state(|s| s.nonce_manager.mark_failed(nonce_key, tx_nonce));
+            if e.contains("nonce too low") {
+                if let Ok(chain_nonce) = transaction_count(Some(owner), Some(BlockTag::Pending)).await {
+                    if let Ok(chain_nonce) = nat_to_u64(chain_nonce) {
+                        mutate_state(|s| s.nonce_manager.track(nonce_key, chain_nonce));
+                    }
+                }
+            }
+            Err(e)
+        }
+    }
+}
+
+async fn sign_and_send_transaction(owner: Principal, transaction: TxEip1559) -> Result<String, String> {
+    use alloy_consensus::TxEnvelope;
+    use alloy_eips::eip2718::Encodable2718;
+    use alloy_primitives::Signature;
+
+    let wallet = EthereumWallet::new(owner).await;
     let tx_hash = transaction.signature_hash().0;
     let (raw_signature, recovery_id) = wallet.sign_with_ecdsa(tx_hash).await;
     let signature = Signature::from_bytes_and_parity(&raw_signature, recovery_id.is_y_odd())
@@ -240,21 +447,37 @@ pub async fn send_erc20(to: String, amount: Nat, token_address: String) -> Resul
     TxEnvelope::from(signed_tx).encode_2718(&mut tx_bytes);
     let raw_transaction_hex = format!("0x{}", alloy_primitives::hex::encode(&tx_bytes));
 
-    let single_rpc_service = read_state(|s| s.single_evm_rpc_service());
-    let (result,) = crate::EVM_RPC
-        .eth_send_raw_transaction(
-            single_rpc_service,
-            None,
-            raw_transaction_hex.clone(),
-            2_000_000_000_u128,
-        )
-        .await
-        .map_err(|e| format!("failed to send raw transaction {}, error: {:?}", raw_transaction_hex, e))?;
-
-    match result {
-        evm_rpc_canister_types::RequestResult::Ok(_) => Ok(raw_transaction_hash.to_string()),
-        evm_rpc_canister_types::RequestResult::Err(e) => Err(format!("Error sending transaction: {:?}", e)),
+    // Another node accepting the same signed bytes is harmless, so a send
+    // moves on to the next provider unless the node itself rejected it.
+    let mut last_error = "No RPC provider can be called".to_string();
+    for rpc_service in read_state(|s| s.evm_rpc_failover()) {
+        let rpc_services = read_state(|s| s.single_evm_rpc_services(rpc_service));
+        let result = crate::EVM_RPC
+            .eth_send_raw_transaction(
+                rpc_services,
+                None,
+                raw_transaction_hex.clone(),
+                cycles::evm_rpc_request_cost(
+                    (raw_transaction_hex.len() + TYPED_REQUEST_BYTES) as u64,
+                    cycles::JSON_RPC_RESPONSE_BYTES,
+                    1,
+                ),
+            )
+            .await;
+
+        match result {
+            Ok((RequestResult::Ok(_),)) => return Ok(raw_transaction_hash.to_string()),
+            Ok((RequestResult::Err(RpcError::JsonRpcError(e)),)) => {
+                return Err(format!("Error sending transaction: {:?}", e))
+            }
+            Ok((RequestResult::Err(e),)) => last_error = format!("{:?}", e),
+            Err(e) => last_error = format!("{:?}", e),
+        }
     }
+    Err(format!(
+        "failed to send raw transaction {}, error: {}",
+        raw_transaction_hex, last_error
+    ))
 }
 
 fn nat_to_u64(nat: Nat) -> Result<u64, String> {
@@ -276,9 +499,39 @@ fn nat_to_u256(value: Nat) -> U256 {
     U256::from_be_bytes(value_u256)
 }
 
-fn estimate_transaction_fees() -> (u128, u128, u128) {
-    const GAS_LIMIT: u128 = 700_000;
-    const MAX_FEE_PER_GAS: u128 = 50_000_000_000;
-    const MAX_PRIORITY_FEE_PER_GAS: u128 = 1_500_000_000;
-    (GAS_LIMIT, MAX_FEE_PER_GAS, MAX_PRIORITY_FEE_PER_GAS)
+pub async fn estimate_fees() -> Result<Eip1559Fees, String> {
+    let config = read_state(|s| s.fee_config.clone());
+    let body = evm_json_rpc(fees::fee_history_payload(&config), 5_000).await?;
+    let history = fees::parse_fee_history(&body)?;
+    fees::suggest_fees(&history, &config)
+}
+
+async fn estimate_gas_limit(from: &str, to: &str, value: U256, data: &[u8]) -> Result<u128, String> {
+    let json = fees::estimate_gas_payload(
+        from,
+        to,
+        &format!("0x{:x}", value),
+        &format!("0x{}", alloy_primitives::hex::encode(data)),
+    );
+    let body = evm_json_rpc(json, 1_000).await?;
+    let estimate = fees::parse_estimate_gas(&body)?;
+    fees::gas_limit_from_estimate(estimate, &read_state(|s| s.fee_config.clone()))
+}
+
+/// Sends a JSON-RPC request to one provider at a time, moving on when the
+/// outcall or the provider fails.
+async fn evm_json_rpc(json: String, max_response_size_bytes: u64) -> Result<String, String> {
+    let num_cycles = cycles::evm_rpc_request_cost(json.len() as u64, max_response_size_bytes, 1);
+    let mut last_error = "No RPC provider can be called".to_string();
+    for rpc_service in read_state(|s| s.evm_rpc_failover()) {
+        match crate::EVM_RPC
+            .request(rpc_service, json.clone(), max_response_size_bytes, num_cycles)
+            .await
+        {
+            Ok((RequestResult::Ok(body),)) => return Ok(body),
+            Ok((RequestResult::Err(e),)) => last_error = format!("Received an error response: {:?}", e),
+            Err(e) => last_error = format!("RPC call failed: {:?}", e),
+        }
+    }
+    Err(last_error)
 }
@@ -0,0 +1,56 @@
+use crate::services::{eth_service, spending_service};
+use bit10_core::metrics::{self, GasMonitor, HealthReport, HttpRequest, HttpResponse, MetricsEncoder, EVM_TRANSFER_GAS};
+use num_traits::ToPrimitive;
+use std::cell::RefCell;
+
+thread_local! {
+    static GAS: RefCell<GasMonitor> = RefCell::new(GasMonitor::default());
+}
+
+/// Checks the controller's wallet, which signs the transfers this canister
+/// sends.
+pub async fn check_gas_balance() -> Result<(), String> {
+    let address = eth_service::get_ethereum_address(Some(ic_cdk::api::canister::controller())).await?;
+    let balance = eth_service::get_balance(Some(address)).await?;
+    let fees = eth_service::estimate_fees().await?;
+    let minimum = fees.max_fee_per_gas.saturating_mul(EVM_TRANSFER_GAS);
+    GAS.with(|g| {
+        g.borrow_mut()
+            .record("ethereum", balance.0.to_u128().unwrap_or(u128::MAX), minimum, ic_cdk::api::time())
+    });
+    Ok(())
+}
+
+pub fn http_request(request: HttpRequest) -> HttpResponse {
+    match request.path() {
+        "/metrics" => encode_metrics().into_response(),
+        "/health" => health().into_response(),
+        _ => HttpResponse::not_found(),
+    }
+}
+
+// Reads go through the EVM RPC canister, which keeps no per-provider health
+// for this canister to report.
+fn encode_metrics() -> MetricsEncoder {
+    let mut encoder = MetricsEncoder::new();
+    metrics::encode_canister(
+        &mut encoder,
+        ic_cdk::api::canister_balance128(),
+        ic_cdk::api::stable::stable_size() * 65536,
+    );
+    metrics::encode_pending_sagas(
+        &mut encoder,
+        &[
+            ("transfer", spending_service::pending_count()),
+            ("evm_transaction", eth_service::pending_transactions()),
+        ],
+    );
+    GAS.with(|g| g.borrow().encode(&mut encoder));
+    encoder
+}
+
+fn health() -> HealthReport {
+    let mut report = HealthReport::new(ic_cdk::api::time());
+    GAS.with(|g| g.borrow().check(&mut report));
+    report
+}
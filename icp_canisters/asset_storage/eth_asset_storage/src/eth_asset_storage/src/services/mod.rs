@@ -1 +1,3 @@
-pub mod eth_service;
\ No newline at end of file
+pub mod eth_service;
+pub mod metrics;
+pub mod spending_service;
\ No newline at end of file
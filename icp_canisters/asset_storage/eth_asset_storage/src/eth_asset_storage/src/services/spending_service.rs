@@ -0,0 +1,143 @@
+use crate::services::eth_service;
+use bit10_core::spending::{
+    PendingTransfer, PolicyChange, PolicyChangeOutcome, SpendingPolicy, TransferOutcome, TransferQueue,
+};
+use candid::{CandidType, Deserialize, Nat};
+use std::cell::RefCell;
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum EthTransfer {
+    Eth,
+    Erc20 { token_address: String },
+}
+
+pub type EthPendingTransfer = PendingTransfer<EthTransfer>;
+
+thread_local! {
+    static TRANSFERS: RefCell<TransferQueue<EthTransfer>> = RefCell::new(TransferQueue::default());
+}
+
+pub fn export_transfers() -> TransferQueue<EthTransfer> {
+    TRANSFERS.with(|t| t.borrow().clone())
+}
+
+pub fn import_transfers(transfers: TransferQueue<EthTransfer>) {
+    TRANSFERS.with(|t| *t.borrow_mut() = transfers);
+}
+
+pub fn assert_controller_or_approver() {
+    let caller = ic_cdk::caller();
+    if caller != ic_cdk::api::canister::controller() && !TRANSFERS.with(|t| t.borrow().is_approver(&caller)) {
+        ic_cdk::trap("Only the canister controller or an approver can call this method.");
+    }
+}
+
+pub fn get_policy() -> SpendingPolicy {
+    TRANSFERS.with(|t| t.borrow().policy().clone())
+}
+
+/// Addresses are compared lowercase, so allowlisted destinations and asset
+/// keys are stored that way.
+pub fn propose_policy(mut policy: SpendingPolicy) -> Result<PolicyChangeOutcome, String> {
+    policy.assets = policy
+        .assets
+        .into_iter()
+        .map(|(asset, mut asset_policy)| {
+            lowercase_destinations(&mut asset_policy.allowed_destinations);
+            (asset_key(&asset), asset_policy)
+        })
+        .collect();
+    lowercase_destinations(&mut policy.default_policy.allowed_destinations);
+    TRANSFERS.with(|t| t.borrow_mut().propose_policy(policy, ic_cdk::caller(), ic_cdk::api::time()))
+}
+
+fn lowercase_destinations(destinations: &mut [String]) {
+    for destination in destinations.iter_mut() {
+        *destination = destination.to_ascii_lowercase();
+    }
+}
+
+fn asset_key(asset: &str) -> String {
+    if asset.eq_ignore_ascii_case("ETH") {
+        "ETH".to_string()
+    } else {
+        asset.to_ascii_lowercase()
+    }
+}
+
+/// Runs the transfer straight away if the policy allows it, otherwise leaves
+/// it queued for approvals or its delay.
+pub async fn send(to: String, amount: Nat, args: EthTransfer) -> Result<TransferOutcome<EthTransfer>, String> {
+    let asset = match &args {
+        EthTransfer::Eth => "ETH".to_string(),
+        EthTransfer::Erc20 { token_address } => asset_key(token_address),
+    };
+    let now = ic_cdk::api::time();
+    let transfer = TRANSFERS.with(|t| {
+        t.borrow_mut()
+            .request(&asset, &to.to_ascii_lowercase(), amount, args, ic_cdk::caller(), now)
+    })?;
+
+    if !transfer.is_ready(now) {
+        return Ok(TransferOutcome::Pending(transfer));
+    }
+    execute(transfer.id).await.map(TransferOutcome::Executed)
+}
+
+pub async fn execute(id: u64) -> Result<String, String> {
+    let transfer = TRANSFERS.with(|t| t.borrow_mut().start_execution(id, ic_cdk::api::time()))?;
+
+    let result = match transfer.args {
+        EthTransfer::Eth => {
+            eth_service::send_eth(transfer.requested_by, transfer.destination, transfer.amount).await
+        }
+        EthTransfer::Erc20 { token_address } => {
+            eth_service::send_erc20(transfer.requested_by, transfer.destination, transfer.amount, token_address)
+                .await
+        }
+    };
+
+    TRANSFERS.with(|t| t.borrow_mut().finish_execution(id, &result, ic_cdk::api::time()));
+    result
+}
+
+pub fn approve(id: u64) -> Result<EthPendingTransfer, String> {
+    TRANSFERS.with(|t| t.borrow_mut().approve(id, ic_cdk::caller(), ic_cdk::api::time()))
+}
+
+pub fn cancel(id: u64) -> Result<EthPendingTransfer, String> {
+    TRANSFERS.with(|t| t.borrow_mut().cancel(id, ic_cdk::api::time()))
+}
+
+pub fn get_policy_change() -> Option<PolicyChange> {
+    TRANSFERS.with(|t| t.borrow().policy_change().cloned())
+}
+
+pub fn approve_policy_change() -> Result<PolicyChange, String> {
+    TRANSFERS.with(|t| t.borrow_mut().approve_policy_change(ic_cdk::caller()))
+}
+
+pub fn apply_policy_change() -> Result<(), String> {
+    TRANSFERS.with(|t| t.borrow_mut().apply_policy_change(ic_cdk::api::time()))
+}
+
+pub fn cancel_policy_change() -> Result<PolicyChange, String> {
+    TRANSFERS.with(|t| t.borrow_mut().cancel_policy_change())
+}
+
+pub fn resolve_stuck(id: u64, tx: Option<String>) -> Result<EthPendingTransfer, String> {
+    let result = tx.ok_or_else(|| "Marked failed by a controller".to_string());
+    TRANSFERS.with(|t| t.borrow_mut().resolve_stuck(id, &result, ic_cdk::api::time()))
+}
+
+pub fn get_transfer(id: u64) -> Option<EthPendingTransfer> {
+    TRANSFERS.with(|t| t.borrow().get(id).cloned())
+}
+
+pub fn get_pending_transfers() -> Vec<EthPendingTransfer> {
+    TRANSFERS.with(|t| t.borrow().pending())
+}
+
+pub fn pending_count() -> usize {
+    TRANSFERS.with(|t| t.borrow().pending().len())
+}
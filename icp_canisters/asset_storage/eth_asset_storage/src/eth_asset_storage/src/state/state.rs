@@ -1,8 +1,12 @@
 use crate::ecdsa::ecdsa_key::EcdsaPublicKey;
-use crate::{services::eth_service::EcdsaKeyName, services::eth_service::EthereumNetwork, services::eth_service::InitArg};
-use evm_rpc_canister_types::{EthMainnetService, EthSepoliaService, RpcServices};
+use crate::{services::eth_service::EcdsaKeyName, services::eth_service::EthereumNetwork, services::eth_service::FeeConfig, services::eth_service::InitArg};
+use bit10_core::nonce::NonceManager;
+use bit10_core::providers::{ProviderSet, RpcProvider};
+use candid::Principal;
+use evm_rpc_canister_types::{EthMainnetService, EthSepoliaService, HttpHeader, RpcApi, RpcService, RpcServices};
 use ic_cdk::api::management_canister::ecdsa::EcdsaKeyId;
 use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::ops::{Deref, DerefMut};
 
 thread_local! {
@@ -29,6 +33,14 @@ pub struct State {
     pub ethereum_network: EthereumNetwork,
     pub ecdsa_key_name: EcdsaKeyName,
     pub ecdsa_public_key: Option<EcdsaPublicKey>,
+    pub fee_config: FeeConfig,
+    pub nonce_manager: NonceManager,
+    /// Owner principal of every wallet tracked by `nonce_manager`, needed to
+    /// sign cancel transactions during reconciliation.
+    pub nonce_owners: BTreeMap<String, Principal>,
+    /// Custom endpoints set by a controller, in place of the EVM RPC
+    /// canister's built-in providers.
+    pub rpc_providers: Option<ProviderSet>,
 }
 
 impl State {
@@ -40,30 +52,82 @@ impl State {
         self.ethereum_network
     }
 
+    /// Without a configured provider set the EVM RPC canister's built-in
+    /// providers are used, which need no keys.
     pub fn evm_rpc_services(&self) -> RpcServices {
+        if let Some(rpc_providers) = &self.rpc_providers {
+            return RpcServices::Custom {
+                chainId: self.ethereum_network.chain_id(),
+                services: rpc_providers.callable().iter().map(rpc_api).collect(),
+            };
+        }
         match self.ethereum_network {
             EthereumNetwork::Mainnet => RpcServices::EthMainnet(None),
             EthereumNetwork::Sepolia => RpcServices::EthSepolia(None),
         }
     }
 
-    pub fn single_evm_rpc_service(&self) -> RpcServices {
+    /// How many providers `evm_rpc_services` fans a call out to. The EVM
+    /// RPC canister's defaults for Ethereum are three providers.
+    pub fn evm_rpc_provider_count(&self) -> u128 {
+        self.rpc_providers.as_ref().map_or(3, |p| p.callable().len() as u128)
+    }
+
+    /// One-provider routes for sends and for reads whose answer differs
+    /// between nodes, in the order callers fall back through them.
+    pub fn evm_rpc_failover(&self) -> Vec<RpcService> {
+        if let Some(rpc_providers) = &self.rpc_providers {
+            return rpc_providers
+                .callable()
+                .iter()
+                .map(|provider| RpcService::Custom(rpc_api(provider)))
+                .collect();
+        }
         match self.ethereum_network {
-            EthereumNetwork::Mainnet => {
-                RpcServices::EthMainnet(Some(vec![EthMainnetService::PublicNode]))
-            }
-            EthereumNetwork::Sepolia => {
-                RpcServices::EthSepolia(Some(vec![EthSepoliaService::PublicNode]))
-            }
+            EthereumNetwork::Mainnet => [EthMainnetService::PublicNode, EthMainnetService::Ankr, EthMainnetService::BlockPi]
+                .into_iter()
+                .map(RpcService::EthMainnet)
+                .collect(),
+            EthereumNetwork::Sepolia => [EthSepoliaService::PublicNode, EthSepoliaService::Ankr, EthSepoliaService::BlockPi]
+                .into_iter()
+                .map(RpcService::EthSepolia)
+                .collect(),
+        }
+    }
+
+    /// `service` in the form the typed EVM RPC methods take.
+    pub fn single_evm_rpc_services(&self, service: RpcService) -> RpcServices {
+        match service {
+            RpcService::EthMainnet(service) => RpcServices::EthMainnet(Some(vec![service])),
+            RpcService::EthSepolia(service) => RpcServices::EthSepolia(Some(vec![service])),
+            RpcService::Custom(api) => RpcServices::Custom {
+                chainId: self.ethereum_network.chain_id(),
+                services: vec![api],
+            },
+            other => ic_cdk::trap(&format!("Unsupported RPC service: {:?}", other)),
         }
     }
 }
 
+/// The provider as the EVM RPC canister takes it, with its key filled in.
+fn rpc_api(provider: &RpcProvider) -> RpcApi {
+    let headers: Vec<HttpHeader> = provider
+        .resolved_headers()
+        .into_iter()
+        .map(|(name, value)| HttpHeader { name, value })
+        .collect();
+    RpcApi {
+        url: provider.resolved_url(),
+        headers: (!headers.is_empty()).then_some(headers),
+    }
+}
+
 impl From<InitArg> for State {
     fn from(init_arg: InitArg) -> Self {
         State {
             ethereum_network: init_arg.ethereum_network.unwrap_or_default(),
             ecdsa_key_name: init_arg.ecdsa_key_name.unwrap_or_default(),
+            fee_config: init_arg.fee_config.unwrap_or_default(),
             ..Default::default()
         }
     }
@@ -1,14 +1,100 @@
 use candid::{CandidType, Principal};
 use ic_cdk_macros::*;
 use ic_ledger_types::{BlockIndex, Tokens};
+use bit10_core::spending::{PolicyChange, PolicyChangeOutcome, SpendingPolicy, TransferOutcome, TransferQueue};
 mod controller;
 mod transfer;
 mod account;
+mod spending;
+mod metrics;
+
+#[pre_upgrade]
+fn pre_upgrade() {
+    ic_cdk::storage::stable_save((Some(spending::export_transfers()),))
+        .expect("Failed to save spending policy to stable storage");
+}
+
+#[post_upgrade]
+fn post_upgrade() {
+    if let Ok((Some(transfers),)) = ic_cdk::storage::stable_restore::<(Option<TransferQueue<transfer::TransferArgs>>,)>() {
+        spending::import_transfers(transfers);
+    }
+}
+
+#[update]
+async fn transfer(args: transfer::TransferArgs) -> Result<TransferOutcome<transfer::TransferArgs>, String> {
+    controller::assert_controller().await?;
+    spending::transfer(args).await
+}
+
+#[query]
+fn get_spending_policy() -> Result<SpendingPolicy, String> {
+    spending::assert_controller_or_approver()?;
+    Ok(spending::get_policy())
+}
 
 #[update]
-async fn transfer(args: transfer::TransferArgs) -> Result<BlockIndex, String> {
+async fn set_spending_policy(policy: SpendingPolicy) -> Result<PolicyChangeOutcome, String> {
     controller::assert_controller().await?;
-    transfer::transfer_tokens(args).await
+    spending::propose_policy(policy)
+}
+
+#[query]
+fn get_policy_change() -> Result<Option<PolicyChange>, String> {
+    spending::assert_controller_or_approver()?;
+    Ok(spending::get_policy_change())
+}
+
+#[update]
+fn approve_policy_change() -> Result<PolicyChange, String> {
+    spending::approve_policy_change()
+}
+
+#[update]
+fn apply_policy_change() -> Result<(), String> {
+    spending::assert_controller_or_approver()?;
+    spending::apply_policy_change()
+}
+
+#[update]
+fn cancel_policy_change() -> Result<PolicyChange, String> {
+    spending::assert_controller_or_approver()?;
+    spending::cancel_policy_change()
+}
+
+#[query]
+fn get_pending_transfers() -> Result<Vec<spending::IcpPendingTransfer>, String> {
+    spending::assert_controller_or_approver()?;
+    Ok(spending::get_pending_transfers())
+}
+
+#[query]
+fn get_transfer(id: u64) -> Result<Option<spending::IcpPendingTransfer>, String> {
+    spending::assert_controller_or_approver()?;
+    Ok(spending::get_transfer(id))
+}
+
+#[update]
+fn approve_transfer(id: u64) -> Result<spending::IcpPendingTransfer, String> {
+    spending::approve(id)
+}
+
+#[update]
+fn cancel_transfer(id: u64) -> Result<spending::IcpPendingTransfer, String> {
+    spending::assert_controller_or_approver()?;
+    spending::cancel(id)
+}
+
+#[update]
+async fn execute_transfer(id: u64) -> Result<String, String> {
+    spending::assert_controller_or_approver()?;
+    spending::execute(id).await
+}
+
+#[update]
+async fn resolve_stuck_transfer(id: u64, tx: Option<String>) -> Result<spending::IcpPendingTransfer, String> {
+    controller::assert_controller().await?;
+    spending::resolve_stuck(id, tx)
 }
 
 #[query]
@@ -22,4 +108,9 @@ fn get_special_principal() -> String {
     account::get_special_principal().to_text()
 }
 
+#[query]
+fn http_request(request: bit10_core::metrics::HttpRequest) -> bit10_core::metrics::HttpResponse {
+    metrics::http_request(request)
+}
+
 ic_cdk::export_candid!();
\ No newline at end of file
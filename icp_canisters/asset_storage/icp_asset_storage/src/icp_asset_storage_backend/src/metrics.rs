@@ -0,0 +1,23 @@
+use crate::spending;
+use bit10_core::metrics::{self, HealthReport, HttpRequest, HttpResponse, MetricsEncoder};
+
+// Ledger fees come out of the transferred account itself, so there is no
+// separate gas wallet to watch and /health only reports liveness.
+pub fn http_request(request: HttpRequest) -> HttpResponse {
+    match request.path() {
+        "/metrics" => encode_metrics().into_response(),
+        "/health" => HealthReport::new(ic_cdk::api::time()).into_response(),
+        _ => HttpResponse::not_found(),
+    }
+}
+
+fn encode_metrics() -> MetricsEncoder {
+    let mut encoder = MetricsEncoder::new();
+    metrics::encode_canister(
+        &mut encoder,
+        ic_cdk::api::canister_balance128(),
+        ic_cdk::api::stable::stable64_size() * 65536,
+    );
+    metrics::encode_pending_sagas(&mut encoder, &[("transfer", spending::pending_count())]);
+    encoder
+}
@@ -0,0 +1,107 @@
+use crate::transfer::{transfer_tokens, TransferArgs};
+use bit10_core::spending::{
+    PendingTransfer, PolicyChange, PolicyChangeOutcome, SpendingPolicy, TransferOutcome, TransferQueue,
+};
+use ic_cdk::api::{caller, is_controller};
+use ic_ledger_types::{AccountIdentifier, DEFAULT_SUBACCOUNT};
+use std::cell::RefCell;
+
+pub type IcpPendingTransfer = PendingTransfer<TransferArgs>;
+
+const ASSET: &str = "ICP";
+
+thread_local! {
+    static TRANSFERS: RefCell<TransferQueue<TransferArgs>> = RefCell::new(TransferQueue::default());
+}
+
+pub fn export_transfers() -> TransferQueue<TransferArgs> {
+    TRANSFERS.with(|t| t.borrow().clone())
+}
+
+pub fn import_transfers(transfers: TransferQueue<TransferArgs>) {
+    TRANSFERS.with(|t| *t.borrow_mut() = transfers);
+}
+
+pub fn assert_controller_or_approver() -> Result<(), String> {
+    let caller = caller();
+    if !is_controller(&caller) && !TRANSFERS.with(|t| t.borrow().is_approver(&caller)) {
+        return Err("Only canister controllers or approvers can call this method".to_string());
+    }
+    Ok(())
+}
+
+pub fn get_policy() -> SpendingPolicy {
+    TRANSFERS.with(|t| t.borrow().policy().clone())
+}
+
+/// Destinations are ledger account identifiers in lowercase hex.
+pub fn propose_policy(mut policy: SpendingPolicy) -> Result<PolicyChangeOutcome, String> {
+    for asset_policy in policy.assets.values_mut().chain(std::iter::once(&mut policy.default_policy)) {
+        for destination in asset_policy.allowed_destinations.iter_mut() {
+            *destination = destination.to_ascii_lowercase();
+        }
+    }
+    TRANSFERS.with(|t| t.borrow_mut().propose_policy(policy, caller(), ic_cdk::api::time()))
+}
+
+/// Runs the transfer straight away if the policy allows it, otherwise leaves
+/// it queued for approvals or its delay.
+pub async fn transfer(args: TransferArgs) -> Result<TransferOutcome<TransferArgs>, String> {
+    let destination = AccountIdentifier::new(&args.to_principal, &args.to_subaccount.unwrap_or(DEFAULT_SUBACCOUNT)).to_hex();
+    let amount = candid::Nat::from(args.amount.e8s());
+    let now = ic_cdk::api::time();
+    let transfer = TRANSFERS.with(|t| t.borrow_mut().request(ASSET, &destination, amount, args, caller(), now))?;
+
+    if !transfer.is_ready(now) {
+        return Ok(TransferOutcome::Pending(transfer));
+    }
+    execute(transfer.id).await.map(TransferOutcome::Executed)
+}
+
+pub async fn execute(id: u64) -> Result<String, String> {
+    let transfer = TRANSFERS.with(|t| t.borrow_mut().start_execution(id, ic_cdk::api::time()))?;
+    let result = transfer_tokens(transfer.args).await.map(|block| block.to_string());
+    TRANSFERS.with(|t| t.borrow_mut().finish_execution(id, &result, ic_cdk::api::time()));
+    result
+}
+
+pub fn approve(id: u64) -> Result<IcpPendingTransfer, String> {
+    TRANSFERS.with(|t| t.borrow_mut().approve(id, caller(), ic_cdk::api::time()))
+}
+
+pub fn cancel(id: u64) -> Result<IcpPendingTransfer, String> {
+    TRANSFERS.with(|t| t.borrow_mut().cancel(id, ic_cdk::api::time()))
+}
+
+pub fn get_policy_change() -> Option<PolicyChange> {
+    TRANSFERS.with(|t| t.borrow().policy_change().cloned())
+}
+
+pub fn approve_policy_change() -> Result<PolicyChange, String> {
+    TRANSFERS.with(|t| t.borrow_mut().approve_policy_change(caller()))
+}
+
+pub fn apply_policy_change() -> Result<(), String> {
+    TRANSFERS.with(|t| t.borrow_mut().apply_policy_change(ic_cdk::api::time()))
+}
+
+pub fn cancel_policy_change() -> Result<PolicyChange, String> {
+    TRANSFERS.with(|t| t.borrow_mut().cancel_policy_change())
+}
+
+pub fn resolve_stuck(id: u64, tx: Option<String>) -> Result<IcpPendingTransfer, String> {
+    let result = tx.ok_or_else(|| "Marked failed by a controller".to_string());
+    TRANSFERS.with(|t| t.borrow_mut().resolve_stuck(id, &result, ic_cdk::api::time()))
+}
+
+pub fn get_transfer(id: u64) -> Option<IcpPendingTransfer> {
+    TRANSFERS.with(|t| t.borrow().get(id).cloned())
+}
+
+pub fn get_pending_transfers() -> Vec<IcpPendingTransfer> {
+    TRANSFERS.with(|t| t.borrow().pending())
+}
+
+pub fn pending_count() -> usize {
+    TRANSFERS.with(|t| t.borrow().pending().len())
+}
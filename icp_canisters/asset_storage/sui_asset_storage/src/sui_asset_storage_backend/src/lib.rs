@@ -5,15 +5,48 @@ mod rpc;
 mod utils;
 
 use service::sui::*;
-use state::{init_state, InitArg};
+use service::metrics;
+use service::spending::{self, SuiPendingTransfer, SuiTransfer};
+use state::{init_state, providers, InitArg};
+use bit10_core::providers::{ProviderHealth, ProviderSet, ProviderSetView, RpcProvider};
+use bit10_core::spending::{PolicyChange, PolicyChangeOutcome, SpendingPolicy, TransferOutcome, TransferQueue};
 use candid::Principal;
-use ic_cdk::{init, query, update};
+use ic_cdk::{init, post_upgrade, pre_upgrade, query, update};
+use std::time::Duration;
+
+const GAS_CHECK_INTERVAL: Duration = Duration::from_secs(600);
 
 #[init]
 fn init(maybe_init: Option<InitArg>) {
     if let Some(init_arg) = maybe_init {
         init_state(init_arg)
     }
+    start_gas_checks();
+}
+
+#[pre_upgrade]
+fn pre_upgrade() {
+    ic_cdk::storage::stable_save((Some(spending::export_transfers()), providers::export_providers()))
+        .expect("Failed to save spending policy to stable storage");
+}
+
+#[post_upgrade]
+fn post_upgrade() {
+    if let Ok((transfers, rpc_providers)) =
+        ic_cdk::storage::stable_restore::<(Option<TransferQueue<SuiTransfer>>, Option<ProviderSet>)>()
+    {
+        if let Some(transfers) = transfers {
+            spending::import_transfers(transfers);
+        }
+        if let Some(rpc_providers) = rpc_providers {
+            providers::import_providers(rpc_providers);
+        }
+    }
+    start_gas_checks();
+}
+
+fn start_gas_checks() {
+    ic_cdk_timers::set_timer_interval(GAS_CHECK_INTERVAL, || ic_cdk::spawn(metrics::check_gas_balance()));
 }
 
 #[update]
@@ -25,13 +58,160 @@ async fn sui_address(owner: Option<Principal>) -> String {
 #[update]
 async fn get_sui_balance(address: Option<String>) -> candid::Nat {
     utils::address::assert_controller();
-    get_sui_balance_impl(address).await
+    get_sui_balance_impl(address).await.unwrap_or_else(|e| ic_cdk::trap(&e))
+}
+
+#[update]
+async fn send_sui(to: String, amount: Option<candid::Nat>) -> Result<TransferOutcome<SuiTransfer>, String> {
+    utils::address::assert_controller();
+    spending::send_sui(to, amount).await
 }
 
 #[update]
-async fn send_sui(to: String, amount: Option<candid::Nat>) -> String {
+async fn get_coin_balance(address: Option<String>, coin_type: String) -> candid::Nat {
     utils::address::assert_controller();
-    send_sui_impl(to, amount).await
+    get_coin_balance_impl(address, coin_type).await.unwrap_or_else(|e| ic_cdk::trap(&e))
+}
+
+#[update]
+async fn get_all_balances(address: Option<String>) -> Vec<rpc::sui_rpc::CoinBalance> {
+    utils::address::assert_controller();
+    get_all_balances_impl(address).await.unwrap_or_else(|e| ic_cdk::trap(&e))
+}
+
+#[update]
+async fn get_coins(address: Option<String>, coin_type: String) -> Vec<rpc::sui_rpc::CoinObject> {
+    utils::address::assert_controller();
+    get_coins_impl(address, coin_type).await.unwrap_or_else(|e| ic_cdk::trap(&e))
+}
+
+#[update]
+async fn send_coin(to: String, coin_type: String, amount: candid::Nat) -> Result<TransferOutcome<SuiTransfer>, String> {
+    utils::address::assert_controller();
+    spending::send_coin(to, coin_type, amount).await
+}
+
+#[update]
+async fn merge_coins(coin_type: String) -> Result<String, String> {
+    utils::address::assert_controller();
+    merge_coins_impl(coin_type).await
+}
+
+#[update]
+async fn split_coin(coin_object_id: String, split_amounts: Vec<candid::Nat>) -> Result<String, String> {
+    utils::address::assert_controller();
+    split_coin_impl(coin_object_id, split_amounts).await
+}
+
+#[query]
+fn get_spending_policy() -> SpendingPolicy {
+    spending::assert_controller_or_approver();
+    spending::get_policy()
+}
+
+#[update]
+fn set_spending_policy(policy: SpendingPolicy) -> Result<PolicyChangeOutcome, String> {
+    utils::address::assert_controller();
+    spending::propose_policy(policy)
+}
+
+#[query]
+fn get_policy_change() -> Option<PolicyChange> {
+    spending::assert_controller_or_approver();
+    spending::get_policy_change()
+}
+
+#[update]
+fn approve_policy_change() -> Result<PolicyChange, String> {
+    spending::approve_policy_change()
+}
+
+#[update]
+fn apply_policy_change() -> Result<(), String> {
+    spending::assert_controller_or_approver();
+    spending::apply_policy_change()
+}
+
+#[update]
+fn cancel_policy_change() -> Result<PolicyChange, String> {
+    spending::assert_controller_or_approver();
+    spending::cancel_policy_change()
+}
+
+#[query]
+fn get_pending_transfers() -> Vec<SuiPendingTransfer> {
+    spending::assert_controller_or_approver();
+    spending::get_pending_transfers()
+}
+
+#[query]
+fn get_transfer(id: u64) -> Option<SuiPendingTransfer> {
+    spending::assert_controller_or_approver();
+    spending::get_transfer(id)
+}
+
+#[update]
+fn approve_transfer(id: u64) -> Result<SuiPendingTransfer, String> {
+    spending::approve(id)
+}
+
+#[update]
+fn cancel_transfer(id: u64) -> Result<SuiPendingTransfer, String> {
+    spending::assert_controller_or_approver();
+    spending::cancel(id)
+}
+
+#[update]
+async fn execute_transfer(id: u64) -> Result<String, String> {
+    spending::assert_controller_or_approver();
+    spending::execute(id).await
+}
+
+#[update]
+fn resolve_stuck_transfer(id: u64, tx: Option<String>) -> Result<SuiPendingTransfer, String> {
+    utils::address::assert_controller();
+    spending::resolve_stuck(id, tx)
+}
+
+#[query]
+fn get_rpc_provider_health() -> Vec<(String, ProviderHealth)> {
+    utils::address::assert_controller();
+    providers::provider_health()
+}
+
+#[update]
+fn clear_rpc_provider_flag(name: String) -> Result<(), String> {
+    utils::address::assert_controller();
+    providers::clear_provider_flag(&name)
+}
+
+#[query]
+fn get_rpc_config() -> ProviderSetView {
+    utils::address::assert_controller();
+    providers::rpc_config()
+}
+
+#[update]
+fn set_rpc_providers(rpc_providers: Vec<RpcProvider>, quorum: u8) -> Result<(), String> {
+    utils::address::assert_controller();
+    providers::set_rpc_providers(rpc_providers, quorum)
+}
+
+#[update]
+fn set_rpc_api_key(provider: String, api_key: Option<String>) -> Result<(), String> {
+    utils::address::assert_controller();
+    providers::set_rpc_api_key(&provider, api_key)
+}
+
+#[update]
+fn reset_rpc_providers() {
+    utils::address::assert_controller();
+    providers::reset_rpc_providers()
+}
+
+#[query]
+fn http_request(request: bit10_core::metrics::HttpRequest) -> bit10_core::metrics::HttpResponse {
+    metrics::http_request(request)
 }
 
 #[query]
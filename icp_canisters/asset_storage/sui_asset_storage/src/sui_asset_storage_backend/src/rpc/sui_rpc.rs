@@ -1,165 +1,371 @@
-use candid::Nat;
+use crate::state::providers;
+use bit10_core::cycles;
+use bit10_core::providers::RpcProvider;
+use bit10_core::transform::ResponseShape;
+use candid::{CandidType, Deserialize, Nat};
 use ic_cdk::api::management_canister::http_request::{
     http_request, CanisterHttpRequestArgument, HttpHeader, HttpMethod, TransformContext,
 };
+use num_traits::ToPrimitive;
 use serde_json::json;
 
-pub async fn get_sui_balance_rpc(address: &str) -> Nat {
-    let rpc_url = crate::state::read_state(|s| s.rpc_url());
+pub const SUI_COIN_TYPE: &str = "0x2::sui::SUI";
+// Budget attached to transactions built by the `unsafe_*` endpoints. Unused
+// gas is refunded, so this only has to cover the worst case.
+pub const DEFAULT_GAS_BUDGET_MIST: u64 = 20_000_000;
+
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CoinBalance {
+    pub coin_type: String,
+    pub coin_object_count: u64,
+    pub total_balance: Nat,
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CoinObject {
+    pub coin_type: String,
+    pub coin_object_id: String,
+    pub version: String,
+    pub digest: String,
+    pub balance: u64,
+}
+
+/// Returns true if `coin_type` is native SUI, regardless of how the `0x2`
+/// package address is padded.
+pub fn is_sui_coin_type(coin_type: &str) -> bool {
+    let mut parts = coin_type.splitn(2, "::");
+    let package = parts.next().unwrap_or_default();
+    let rest = parts.next().unwrap_or_default();
+    let package = package.trim_start_matches("0x").trim_start_matches('0');
+    package == "2" && rest == "sui::SUI"
+}
+
+/// Cycles the IC charges for `request`, from its size and response limit.
+fn outcall_cost(request: &CanisterHttpRequestArgument) -> u128 {
+    let bytes = cycles::request_bytes(
+        &request.url,
+        request.headers.iter().map(|h| (h.name.as_str(), h.value.as_str())),
+        request.body.as_deref(),
+        request
+            .transform
+            .as_ref()
+            .map(|t| (t.function.0.method.as_str(), t.context.as_slice())),
+    );
+    cycles::http_request_cost(bytes, request.max_response_bytes)
+}
+
+async fn post_sui_rpc(
+    provider: &RpcProvider,
+    method: &str,
+    params: &serde_json::Value,
+    max_response_bytes: u64,
+) -> Result<serde_json::Value, String> {
     let json_body = json!({
         "jsonrpc": "2.0",
         "id": 1,
-        "method": "suix_getBalance",
-        "params": [address]
+        "method": method,
+        "params": params
     })
     .to_string();
 
+    let mut request_headers = vec![
+        HttpHeader { name: "accept".to_string(), value: "application/json".to_string() },
+        HttpHeader { name: "content-type".to_string(), value: "application/json".to_string() },
+    ];
+    request_headers.extend(
+        provider
+            .resolved_headers()
+            .into_iter()
+            .map(|(name, value)| HttpHeader { name, value }),
+    );
+
     let request = CanisterHttpRequestArgument {
-        url: rpc_url.to_string(),
+        url: provider.resolved_url(),
         method: HttpMethod::POST,
         body: Some(json_body.into_bytes()),
-        max_response_bytes: Some(1000),
-        transform: Some(TransformContext::from_name("transform_sui_response".to_string(), vec![])),
-        headers: vec![
-            HttpHeader {
-                name: "accept".to_string(),
-                value: "application/json".to_string(),
-            },
-            HttpHeader {
-                name: "content-type".to_string(),
-                value: "application/json".to_string(),
-            },
-        ],
+        max_response_bytes: Some(max_response_bytes),
+        transform: Some(TransformContext::from_name(
+            "transform_sui_response".to_string(),
+            ResponseShape::JsonRpc(method.to_string()).to_context(),
+        )),
+        headers: request_headers,
     };
 
-    let cycles = 1_000_000_000u128;
-    let (response,) = http_request(request, cycles).await.expect("HTTP request failed");
-    let response_body = String::from_utf8(response.body).expect("Invalid UTF-8");
-    let json_response: serde_json::Value = serde_json::from_str(&response_body).expect("Invalid JSON");
+    let cycles = outcall_cost(&request);
+    let (response,) = http_request(request, cycles)
+        .await
+        .map_err(|(code, msg)| format!("{} request failed: {:?} - {}", method, code, msg))?;
+    let response_body = String::from_utf8(response.body).map_err(|_| "Invalid UTF-8".to_string())?;
+    serde_json::from_str(&response_body).map_err(|e| format!("Invalid JSON ({}): {}", e, response_body))
+}
 
-    let total_balance = json_response
-        .get("result")
-        .and_then(|r| r.get("totalBalance"))
-        .and_then(|v| v.as_str())
-        .unwrap_or("0");
+fn rpc_result(method: &str, json_response: serde_json::Value) -> Result<serde_json::Value, String> {
+    if let Some(error) = json_response.get("error") {
+        return Err(format!("{} failed: {}", method, error));
+    }
+    Ok(json_response.get("result").cloned().unwrap_or(serde_json::Value::Null))
+}
 
-    Nat::from(total_balance.parse::<u64>().unwrap_or(0))
+/// Sends to one provider at a time, moving on only when the outcall fails.
+/// Used for transaction building and execution, and for reads whose answer
+/// moves with every checkpoint.
+async fn sui_rpc_call(
+    method: &str,
+    params: serde_json::Value,
+    max_response_bytes: u64,
+) -> Result<serde_json::Value, String> {
+    let mut errors = Vec::new();
+    for provider in providers::rpc_providers() {
+        match post_sui_rpc(&provider, method, &params, max_response_bytes).await {
+            Ok(json_response) => return rpc_result(method, json_response),
+            Err(e) => errors.push(format!("{}: {}", provider.name, e)),
+        }
+    }
+    Err(format!("{} failed on every provider: {}", method, errors.join("; ")))
 }
 
-pub async fn send_sui_rpc(wallet: crate::wallet::sui_wallet::SuiWallet, to: String, amount: Option<Nat>) -> String {
-    use candid::Principal;
-    use base64;
-    use blake2::{Blake2b, Digest as BlakeDigest};
-    use sha2::{Sha256, Digest as ShaDigest};
+/// Sends the read to every configured provider and fails unless a quorum of
+/// them return the same result.
+async fn sui_quorum_call(
+    method: &str,
+    params: serde_json::Value,
+    max_response_bytes: u64,
+) -> Result<serde_json::Value, String> {
+    let mut responses = Vec::new();
+    for provider in providers::rpc_providers() {
+        let response = post_sui_rpc(&provider, method, &params, max_response_bytes)
+            .await
+            .and_then(|json_response| rpc_result(method, json_response))
+            .and_then(|result| serde_json::to_string(&result).map_err(|e| e.to_string()));
+        responses.push((provider.name, response));
+    }
 
-    let sender_address = wallet.sui_address();
-    let amount_mist = amount.unwrap_or(Nat::from(100_000_000u64));
+    let result = providers::tally(responses).map_err(|e| format!("{} failed: {}", method, e))?;
+    serde_json::from_str(&result).map_err(|e| format!("{} returned invalid JSON ({}): {}", method, e, result))
+}
 
-    let rpc_url = crate::state::read_state(|s| s.rpc_url());
+pub async fn get_sui_balance_rpc(address: &str) -> Result<Nat, String> {
+    get_coin_balance_rpc(address, SUI_COIN_TYPE).await
+}
 
-    let gas_request_body = serde_json::json!({
-        "jsonrpc": "2.0",
-        "id": 1,
-        "method": "suix_getCoins",
-        "params": [sender_address, "0x2::sui::SUI", null, null]
-    }).to_string();
+pub async fn get_coin_balance_rpc(address: &str, coin_type: &str) -> Result<Nat, String> {
+    let result = sui_quorum_call("suix_getBalance", json!([address, coin_type]), 1000).await?;
+    Ok(parse_balance(&result["totalBalance"]))
+}
 
-    let gas_request = CanisterHttpRequestArgument {
-        url: rpc_url.to_string(),
-        method: HttpMethod::POST,
-        body: Some(gas_request_body.into_bytes()),
-        max_response_bytes: Some(2000),
-        transform: Some(TransformContext::from_name("transform_sui_response".to_string(), vec![])),
-        headers: vec![
-            HttpHeader { name: "accept".to_string(), value: "application/json".to_string() },
-            HttpHeader { name: "content-type".to_string(), value: "application/json".to_string() },
-        ],
-    };
+pub async fn get_all_balances_rpc(address: &str) -> Result<Vec<CoinBalance>, String> {
+    let result = sui_quorum_call("suix_getAllBalances", json!([address]), 10_000).await?;
+    Ok(result
+        .as_array()
+        .map(|balances| {
+            balances
+                .iter()
+                .map(|b| CoinBalance {
+                    coin_type: b["coinType"].as_str().unwrap_or_default().to_string(),
+                    coin_object_count: b["coinObjectCount"].as_u64().unwrap_or(0),
+                    total_balance: parse_balance(&b["totalBalance"]),
+                })
+                .collect()
+        })
+        .unwrap_or_default())
+}
 
-    let cycles = 2_000_000_000u128;
-    let (gas_response,) = http_request(gas_request, cycles).await.expect("Failed to get coins");
-    let gas_body = String::from_utf8(gas_response.body).expect("Invalid UTF-8");
-    let gas_json: serde_json::Value = serde_json::from_str(&gas_body).expect("Invalid JSON");
+/// Every coin object of `coin_type` owned by `address`, following the
+/// pagination cursor until the node reports no further pages.
+pub async fn get_coins_rpc(address: &str, coin_type: &str) -> Result<Vec<CoinObject>, String> {
+    let mut coins = Vec::new();
+    let mut cursor = serde_json::Value::Null;
+
+    loop {
+        let result = sui_rpc_call(
+            "suix_getCoins",
+            json!([address, coin_type, cursor, 50]),
+            20_000,
+        )
+        .await?;
+
+        for coin in result["data"].as_array().cloned().unwrap_or_default() {
+            let coin_object_id = coin["coinObjectId"]
+                .as_str()
+                .ok_or_else(|| format!("suix_getCoins returned a coin without an ID: {}", coin))?;
+            let balance = coin["balance"]
+                .as_str()
+                .and_then(|s| s.parse::<u64>().ok())
+                .ok_or_else(|| format!("suix_getCoins returned a coin without a balance: {}", coin))?;
+            coins.push(CoinObject {
+                coin_type: coin["coinType"].as_str().unwrap_or(coin_type).to_string(),
+                coin_object_id: coin_object_id.to_string(),
+                version: coin["version"].as_str().unwrap_or_default().to_string(),
+                digest: coin["digest"].as_str().unwrap_or_default().to_string(),
+                balance,
+            });
+        }
+
+        if !result["hasNextPage"].as_bool().unwrap_or(false) {
+            break;
+        }
+        cursor = result["nextCursor"].clone();
+    }
 
-    let coins = gas_json
-        .get("result")
-        .and_then(|r| r.get("data"))
-        .and_then(|d| d.as_array())
-        .expect("No coins data found");
+    Ok(coins)
+}
+
+pub async fn get_reference_gas_price_rpc() -> Result<u64, String> {
+    let result = sui_rpc_call("suix_getReferenceGasPrice", json!([]), 500).await?;
+    Ok(result
+        .as_str()
+        .and_then(|p| p.parse::<u64>().ok())
+        .or_else(|| result.as_u64())
+        .unwrap_or(1000))
+}
 
+/// Picks the smallest SUI coin that covers `gas_budget` and is not one of
+/// `exclude`, so a transaction never pays gas with a coin it also moves.
+pub async fn select_gas_coin(address: &str, gas_budget: u64, exclude: &[String]) -> Result<CoinObject, String> {
+    get_coins_rpc(address, SUI_COIN_TYPE)
+        .await?
+        .into_iter()
+        .filter(|c| !exclude.contains(&c.coin_object_id) && c.balance >= gas_budget)
+        .min_by_key(|c| c.balance)
+        .ok_or_else(|| {
+            format!(
+                "No SUI coin with at least {} MIST is available for gas outside the coins being spent",
+                gas_budget
+            )
+        })
+}
+
+pub async fn unsafe_pay_rpc(
+    signer: &str,
+    input_coins: &[String],
+    recipients: &[String],
+    amounts: &[u64],
+    gas: &str,
+    gas_budget: u64,
+) -> Result<String, String> {
+    let amounts: Vec<String> = amounts.iter().map(|a| a.to_string()).collect();
+    let result = sui_rpc_call(
+        "unsafe_pay",
+        json!([signer, input_coins, recipients, amounts, gas, gas_budget.to_string()]),
+        10_000,
+    )
+    .await?;
+    tx_bytes_from("unsafe_pay", &result)
+}
+
+pub async fn unsafe_split_coin_rpc(
+    signer: &str,
+    coin_object_id: &str,
+    split_amounts: &[u64],
+    gas: &str,
+    gas_budget: u64,
+) -> Result<String, String> {
+    let split_amounts: Vec<String> = split_amounts.iter().map(|a| a.to_string()).collect();
+    let result = sui_rpc_call(
+        "unsafe_splitCoin",
+        json!([signer, coin_object_id, split_amounts, gas, gas_budget.to_string()]),
+        10_000,
+    )
+    .await?;
+    tx_bytes_from("unsafe_splitCoin", &result)
+}
+
+/// Joins `coins_to_merge` into `primary_coin` in one transaction through
+/// `0x2::pay::join_vec`.
+pub async fn unsafe_join_coins_rpc(
+    signer: &str,
+    coin_type: &str,
+    primary_coin: &str,
+    coins_to_merge: &[String],
+    gas: &str,
+    gas_budget: u64,
+) -> Result<String, String> {
+    let result = sui_rpc_call(
+        "unsafe_moveCall",
+        json!([
+            signer,
+            "0x2",
+            "pay",
+            "join_vec",
+            [coin_type],
+            [primary_coin, coins_to_merge],
+            gas,
+            gas_budget.to_string()
+        ]),
+        10_000,
+    )
+    .await?;
+    tx_bytes_from("unsafe_moveCall", &result)
+}
+
+fn tx_bytes_from(method: &str, result: &serde_json::Value) -> Result<String, String> {
+    result["txBytes"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| format!("{} returned no txBytes: {}", method, result))
+}
+
+fn parse_balance(value: &serde_json::Value) -> Nat {
+    let balance = value
+        .as_str()
+        .and_then(|s| s.parse::<u128>().ok())
+        .unwrap_or(0);
+    Nat::from(balance)
+}
+
+pub async fn send_sui_rpc(
+    wallet: crate::wallet::sui_wallet::SuiWallet,
+    to: String,
+    amount: Option<Nat>,
+) -> Result<String, String> {
+    let sender_address = wallet.sui_address();
+    let amount_mist = amount.unwrap_or(Nat::from(100_000_000u64));
+
+    let coins = get_coins_rpc(&sender_address, SUI_COIN_TYPE).await?;
     if coins.is_empty() {
-        panic!("No SUI coins available. You need to have SUI in your wallet first.");
+        return Err("No SUI coins available. You need to have SUI in your wallet first.".to_string());
     }
 
-    let mut coin_ids = Vec::new();
-    let mut total_balance = 0u64;
-    for coin in coins {
-        let coin_id = coin.get("coinObjectId").and_then(|id| id.as_str()).expect("No coin ID");
-        let balance = coin.get("balance").and_then(|b| b.as_str()).and_then(|s| s.parse::<u64>().ok()).expect("No balance");
-        coin_ids.push(coin_id);
-        total_balance += balance;
-    }
+    let coin_ids: Vec<String> = coins.iter().map(|c| c.coin_object_id.clone()).collect();
+    let total_balance: u64 = coins.iter().map(|c| c.balance).sum();
 
     let amount_u64 = amount_mist.0.to_u64().unwrap_or(0);
     if total_balance < amount_u64 {
-        panic!("Insufficient balance. Available: {} MIST, Required: {} MIST", total_balance, amount_u64);
+        return Err(format!(
+            "Insufficient balance. Available: {} MIST, Required: {} MIST",
+            total_balance, amount_u64
+        ));
     }
 
-    let gas_price_request_body = serde_json::json!({
-        "jsonrpc": "2.0",
-        "id": 1,
-        "method": "suix_getReferenceGasPrice",
-        "params": []
-    }).to_string();
-
-    let gas_price_request = CanisterHttpRequestArgument {
-        url: rpc_url.to_string(),
-        method: HttpMethod::POST,
-        body: Some(gas_price_request_body.into_bytes()),
-        max_response_bytes: Some(500),
-        transform: Some(TransformContext::from_name("transform_sui_response".to_string(), vec![])),
-        headers: vec![
-            HttpHeader { name: "accept".to_string(), value: "application/json".to_string() },
-            HttpHeader { name: "content-type".to_string(), value: "application/json".to_string() },
-        ],
-    };
-
-    let (gas_price_response,) = http_request(gas_price_request, cycles).await.expect("Failed to get gas price");
-    let gas_price_body = String::from_utf8(gas_price_response.body).expect("Invalid UTF-8");
-    let gas_price_json: serde_json::Value = serde_json::from_str(&gas_price_body).expect("Invalid JSON");
-    let gas_price = gas_price_json.get("result").and_then(|r| r.as_str()).unwrap_or("1000");
+    let gas_price = get_reference_gas_price_rpc().await?;
 
-    let tx_request_body = serde_json::json!({
-        "jsonrpc": "2.0",
-        "id": 1,
-        "method": "unsafe_paySui",
-        "params": [
+    let result = sui_rpc_call(
+        "unsafe_paySui",
+        json!([
             sender_address,
             coin_ids,
             [to],
             [amount_u64.to_string()],
-            gas_price
-        ]
-    }).to_string();
-
-    let tx_request = CanisterHttpRequestArgument {
-        url: rpc_url.to_string(),
-        method: HttpMethod::POST,
-        body: Some(tx_request_body.into_bytes()),
-        max_response_bytes: Some(5000),
-        transform: Some(TransformContext::from_name("transform_sui_response".to_string(), vec![])),
-        headers: vec![
-            HttpHeader { name: "accept".to_string(), value: "application/json".to_string() },
-            HttpHeader { name: "content-type".to_string(), value: "application/json".to_string() },
-        ],
-    };
+            gas_price.to_string()
+        ]),
+        5000,
+    )
+    .await?;
+    let tx_bytes = tx_bytes_from("unsafe_paySui", &result)?;
+
+    sign_and_execute_rpc(&wallet, &tx_bytes).await
+}
 
-    let (tx_response,) = http_request(tx_request, cycles).await.expect("Failed to create transaction");
-    let tx_body = String::from_utf8(tx_response.body).expect("Invalid UTF-8");
-    let tx_json: serde_json::Value = serde_json::from_str(&tx_body).expect("Invalid JSON");
-    let tx_bytes = tx_json.get("result").and_then(|r| r.get("txBytes")).and_then(|b| b.as_str()).expect("No txBytes");
+pub async fn sign_and_execute_rpc(
+    wallet: &crate::wallet::sui_wallet::SuiWallet,
+    tx_bytes: &str,
+) -> Result<String, String> {
+    use base64;
+    use blake2::{Blake2b, Digest as BlakeDigest};
+    use sha2::{Sha256, Digest as ShaDigest};
 
-    let tx_bytes_decoded = base64::decode(tx_bytes).expect("Failed to decode txBytes");
+    let tx_bytes_decoded =
+        base64::decode(tx_bytes).map_err(|e| format!("Failed to decode txBytes ({}): {}", e, tx_bytes))?;
     let mut intent_message = vec![0x01, 0x01, 0x01];
     intent_message.extend_from_slice(&tx_bytes_decoded);
 
@@ -181,11 +387,9 @@ pub async fn send_sui_rpc(wallet: crate::wallet::sui_wallet::SuiWallet, to: Stri
 
     let signature_base64 = base64::encode(&sui_signature);
 
-    let execute_request_body = serde_json::json!({
-        "jsonrpc": "2.0",
-        "id": 1,
-        "method": "sui_executeTransactionBlock",
-        "params": [
+    let result = sui_rpc_call(
+        "sui_executeTransactionBlock",
+        json!([
             tx_bytes,
             [signature_base64],
             {
@@ -197,42 +401,31 @@ pub async fn send_sui_rpc(wallet: crate::wallet::sui_wallet::SuiWallet, to: Stri
                 "showBalanceChanges": true
             },
             "WaitForEffectsCert"
-        ]
-    }).to_string();
-
-    let execute_request = CanisterHttpRequestArgument {
-        url: rpc_url.to_string(),
-        method: HttpMethod::POST,
-        body: Some(execute_request_body.into_bytes()),
-        max_response_bytes: Some(10000),
-        transform: Some(TransformContext::from_name("transform_sui_response".to_string(), vec![])),
-        headers: vec![
-            HttpHeader { name: "accept".to_string(), value: "application/json".to_string() },
-            HttpHeader { name: "content-type".to_string(), value: "application/json".to_string() },
-        ],
-    };
-
-    let (execute_response,) = http_request(execute_request, cycles).await.expect("Failed to execute transaction");
-    let execute_body = String::from_utf8(execute_response.body).expect("Invalid UTF-8");
-    let execute_json: serde_json::Value = serde_json::from_str(&execute_body).expect("Invalid JSON");
+        ]),
+        10000,
+    )
+    .await?;
+
+    if let Some(status) = result["effects"]["status"]["status"].as_str() {
+        if status != "success" {
+            return Err(format!(
+                "Transaction failed on chain: {}",
+                result["effects"]["status"]["error"].as_str().unwrap_or(status)
+            ));
+        }
+    }
 
-    let tx_digest = execute_json
-        .get("result")
-        .and_then(|r| r.get("digest"))
+    let tx_digest = result
+        .get("digest")
         .and_then(|d| d.as_str())
         .or_else(|| {
-            execute_json
-                .get("result")
-                .and_then(|r| r.get("effects"))
+            result
+                .get("effects")
                 .and_then(|e| e.get("transactionDigest"))
                 .and_then(|d| d.as_str())
         })
-        .or_else(|| {
-            execute_json
-                .get("result")
-                .and_then(|r| r.as_str())
-        })
-        .expect("Failed to get transaction digest from response");
+        .or_else(|| result.as_str())
+        .ok_or_else(|| format!("sui_executeTransactionBlock returned no transaction digest: {}", result))?;
 
-    tx_digest.to_string()
-}
\ No newline at end of file
+    Ok(tx_digest.to_string())
+}
@@ -0,0 +1,52 @@
+use crate::rpc::sui_rpc::{get_sui_balance_rpc, DEFAULT_GAS_BUDGET_MIST};
+use crate::service::{spending, sui::sui_address_impl};
+use crate::state::providers;
+use crate::utils::address::controller_principal;
+use bit10_core::metrics::{self, GasMonitor, HealthReport, HttpRequest, HttpResponse, MetricsEncoder};
+use num_traits::ToPrimitive;
+use std::cell::RefCell;
+
+thread_local! {
+    static GAS: RefCell<GasMonitor> = RefCell::new(GasMonitor::default());
+}
+
+/// Transfers are signed by the controller's wallet, so that is the one
+/// that has to hold enough SUI for gas.
+pub async fn check_gas_balance() {
+    let address = sui_address_impl(Some(controller_principal())).await;
+    let Ok(balance) = get_sui_balance_rpc(&address).await else {
+        return;
+    };
+    let balance = balance.0.to_u128().unwrap_or(u128::MAX);
+    GAS.with(|g| {
+        g.borrow_mut()
+            .record("sui", balance, DEFAULT_GAS_BUDGET_MIST as u128, ic_cdk::api::time())
+    });
+}
+
+pub fn http_request(request: HttpRequest) -> HttpResponse {
+    match request.path() {
+        "/metrics" => encode_metrics().into_response(),
+        "/health" => health().into_response(),
+        _ => HttpResponse::not_found(),
+    }
+}
+
+fn encode_metrics() -> MetricsEncoder {
+    let mut encoder = MetricsEncoder::new();
+    metrics::encode_canister(
+        &mut encoder,
+        ic_cdk::api::canister_balance128(),
+        ic_cdk::api::stable::stable_size() * 65536,
+    );
+    metrics::encode_provider_health(&mut encoder, &[("sui".to_string(), providers::provider_health())]);
+    metrics::encode_pending_sagas(&mut encoder, &[("transfer", spending::pending_count())]);
+    GAS.with(|g| g.borrow().encode(&mut encoder));
+    encoder
+}
+
+fn health() -> HealthReport {
+    let mut report = HealthReport::new(ic_cdk::api::time());
+    GAS.with(|g| g.borrow().check(&mut report));
+    report
+}
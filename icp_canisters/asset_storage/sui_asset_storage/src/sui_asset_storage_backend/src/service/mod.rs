@@ -1 +1,3 @@
+pub mod metrics;
+pub mod spending;
 pub mod sui;
\ No newline at end of file
@@ -0,0 +1,163 @@
+use crate::rpc::sui_rpc::is_sui_coin_type;
+use crate::service::sui::{send_coin_impl, send_sui_impl};
+use crate::utils::address::controller_principal;
+use bit10_core::spending::{
+    PendingTransfer, PolicyChange, PolicyChangeOutcome, SpendingPolicy, TransferOutcome, TransferQueue,
+};
+use candid::{CandidType, Deserialize, Nat};
+use std::cell::RefCell;
+
+const DEFAULT_SEND_AMOUNT_MIST: u64 = 100_000_000;
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum SuiTransfer {
+    Sui,
+    Coin { coin_type: String },
+}
+
+pub type SuiPendingTransfer = PendingTransfer<SuiTransfer>;
+
+thread_local! {
+    static TRANSFERS: RefCell<TransferQueue<SuiTransfer>> = RefCell::new(TransferQueue::default());
+}
+
+pub fn export_transfers() -> TransferQueue<SuiTransfer> {
+    TRANSFERS.with(|t| t.borrow().clone())
+}
+
+pub fn import_transfers(transfers: TransferQueue<SuiTransfer>) {
+    TRANSFERS.with(|t| *t.borrow_mut() = transfers);
+}
+
+pub fn assert_controller_or_approver() {
+    let caller = ic_cdk::caller();
+    if caller != controller_principal() && !TRANSFERS.with(|t| t.borrow().is_approver(&caller)) {
+        ic_cdk::trap("Only the controller or an approver can call this method");
+    }
+}
+
+pub fn get_policy() -> SpendingPolicy {
+    TRANSFERS.with(|t| t.borrow().policy().clone())
+}
+
+/// Sui addresses are hex and compared lowercase. Native SUI is keyed as
+/// "SUI", other coins by their full coin type.
+pub fn propose_policy(mut policy: SpendingPolicy) -> Result<PolicyChangeOutcome, String> {
+    policy.assets = policy
+        .assets
+        .into_iter()
+        .map(|(asset, mut asset_policy)| {
+            lowercase_destinations(&mut asset_policy.allowed_destinations);
+            (asset_key(&asset), asset_policy)
+        })
+        .collect();
+    lowercase_destinations(&mut policy.default_policy.allowed_destinations);
+    TRANSFERS.with(|t| t.borrow_mut().propose_policy(policy, ic_cdk::caller(), ic_cdk::api::time()))
+}
+
+fn lowercase_destinations(destinations: &mut [String]) {
+    for destination in destinations.iter_mut() {
+        *destination = destination.to_ascii_lowercase();
+    }
+}
+
+fn asset_key(asset: &str) -> String {
+    if asset.eq_ignore_ascii_case("SUI") || is_sui_coin_type(asset) {
+        "SUI".to_string()
+    } else {
+        asset.to_string()
+    }
+}
+
+pub async fn send_sui(to: String, amount: Option<Nat>) -> Result<TransferOutcome<SuiTransfer>, String> {
+    let amount = amount.unwrap_or_else(|| Nat::from(DEFAULT_SEND_AMOUNT_MIST));
+    send(to, amount, SuiTransfer::Sui).await
+}
+
+pub async fn send_coin(to: String, coin_type: String, amount: Nat) -> Result<TransferOutcome<SuiTransfer>, String> {
+    let args = if is_sui_coin_type(&coin_type) {
+        SuiTransfer::Sui
+    } else {
+        SuiTransfer::Coin { coin_type }
+    };
+    send(to, amount, args).await
+}
+
+/// Runs the transfer straight away if the policy allows it, otherwise leaves
+/// it queued for approvals or its delay.
+async fn send(to: String, amount: Nat, args: SuiTransfer) -> Result<TransferOutcome<SuiTransfer>, String> {
+    let asset = match &args {
+        SuiTransfer::Sui => "SUI".to_string(),
+        SuiTransfer::Coin { coin_type } => asset_key(coin_type),
+    };
+    let now = ic_cdk::api::time();
+    let transfer = TRANSFERS.with(|t| {
+        t.borrow_mut()
+            .request(&asset, &to.to_ascii_lowercase(), amount, args, ic_cdk::caller(), now)
+    })?;
+
+    if !transfer.is_ready(now) {
+        return Ok(TransferOutcome::Pending(transfer));
+    }
+    execute(transfer.id).await.map(TransferOutcome::Executed)
+}
+
+pub async fn execute(id: u64) -> Result<String, String> {
+    let transfer = TRANSFERS.with(|t| t.borrow_mut().start_execution(id, ic_cdk::api::time()))?;
+
+    // An error may come after the transaction was submitted, so it traps and
+    // leaves the transfer `Executing` for `resolve_stuck` rather than freeing
+    // its share of the daily limit.
+    let digest = match transfer.args {
+        SuiTransfer::Sui => send_sui_impl(transfer.requested_by, transfer.destination, Some(transfer.amount)).await,
+        SuiTransfer::Coin { coin_type } => {
+            send_coin_impl(transfer.requested_by, transfer.destination, coin_type, transfer.amount).await
+        }
+    }
+    .unwrap_or_else(|e| ic_cdk::trap(&e));
+
+    let result = Ok(digest);
+    TRANSFERS.with(|t| t.borrow_mut().finish_execution(id, &result, ic_cdk::api::time()));
+    result
+}
+
+pub fn approve(id: u64) -> Result<SuiPendingTransfer, String> {
+    TRANSFERS.with(|t| t.borrow_mut().approve(id, ic_cdk::caller(), ic_cdk::api::time()))
+}
+
+pub fn cancel(id: u64) -> Result<SuiPendingTransfer, String> {
+    TRANSFERS.with(|t| t.borrow_mut().cancel(id, ic_cdk::api::time()))
+}
+
+pub fn get_policy_change() -> Option<PolicyChange> {
+    TRANSFERS.with(|t| t.borrow().policy_change().cloned())
+}
+
+pub fn approve_policy_change() -> Result<PolicyChange, String> {
+    TRANSFERS.with(|t| t.borrow_mut().approve_policy_change(ic_cdk::caller()))
+}
+
+pub fn apply_policy_change() -> Result<(), String> {
+    TRANSFERS.with(|t| t.borrow_mut().apply_policy_change(ic_cdk::api::time()))
+}
+
+pub fn cancel_policy_change() -> Result<PolicyChange, String> {
+    TRANSFERS.with(|t| t.borrow_mut().cancel_policy_change())
+}
+
+pub fn resolve_stuck(id: u64, tx: Option<String>) -> Result<SuiPendingTransfer, String> {
+    let result = tx.ok_or_else(|| "Marked failed by a controller".to_string());
+    TRANSFERS.with(|t| t.borrow_mut().resolve_stuck(id, &result, ic_cdk::api::time()))
+}
+
+pub fn get_transfer(id: u64) -> Option<SuiPendingTransfer> {
+    TRANSFERS.with(|t| t.borrow().get(id).cloned())
+}
+
+pub fn get_pending_transfers() -> Vec<SuiPendingTransfer> {
+    TRANSFERS.with(|t| t.borrow().pending())
+}
+
+pub fn pending_count() -> usize {
+    TRANSFERS.with(|t| t.borrow().pending().len())
+}
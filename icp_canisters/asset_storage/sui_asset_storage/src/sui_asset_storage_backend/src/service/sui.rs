@@ -3,6 +3,7 @@ use crate::state::read_state;
 use crate::rpc::sui_rpc::*;
 use candid::{Principal, Nat};
 use ic_cdk::api::management_canister::http_request::{TransformArgs, HttpResponse};
+use num_traits::ToPrimitive;
 
 pub async fn sui_address_impl(owner: Option<Principal>) -> String {
     let owner = owner.unwrap_or(ic_cdk::caller());
@@ -10,7 +11,7 @@ pub async fn sui_address_impl(owner: Option<Principal>) -> String {
     wallet.sui_address()
 }
 
-pub async fn get_sui_balance_impl(address: Option<String>) -> Nat {
+pub async fn get_sui_balance_impl(address: Option<String>) -> Result<Nat, String> {
     let address = match address {
         Some(addr) => addr,
         None => sui_address_impl(None).await,
@@ -18,16 +19,163 @@ pub async fn get_sui_balance_impl(address: Option<String>) -> Nat {
     get_sui_balance_rpc(&address).await
 }
 
-pub async fn send_sui_impl(to: String, amount: Option<Nat>) -> String {
-    let caller = ic_cdk::caller();
+/// Sends from the wallet derived for `caller`, which is the principal that
+/// requested the transfer rather than whoever executes it.
+pub async fn send_sui_impl(caller: Principal, to: String, amount: Option<Nat>) -> Result<String, String> {
     let wallet = SuiWallet::new(caller).await;
     send_sui_rpc(wallet, to, amount).await
 }
 
+pub async fn get_coin_balance_impl(address: Option<String>, coin_type: String) -> Result<Nat, String> {
+    let address = match address {
+        Some(addr) => addr,
+        None => sui_address_impl(None).await,
+    };
+    get_coin_balance_rpc(&address, &coin_type).await
+}
+
+pub async fn get_all_balances_impl(address: Option<String>) -> Result<Vec<CoinBalance>, String> {
+    let address = match address {
+        Some(addr) => addr,
+        None => sui_address_impl(None).await,
+    };
+    get_all_balances_rpc(&address).await
+}
+
+pub async fn get_coins_impl(address: Option<String>, coin_type: String) -> Result<Vec<CoinObject>, String> {
+    let address = match address {
+        Some(addr) => addr,
+        None => sui_address_impl(None).await,
+    };
+    get_coins_rpc(&address, &coin_type).await
+}
+
+/// Sends `amount` of any `Coin<T>`. Native SUI goes through `paySui`, which
+/// pays gas out of the coins being sent; every other coin type is paid with
+/// `pay` and a separate SUI gas coin.
+pub async fn send_coin_impl(caller: Principal, to: String, coin_type: String, amount: Nat) -> Result<String, String> {
+    if is_sui_coin_type(&coin_type) {
+        return send_sui_impl(caller, to, Some(amount)).await;
+    }
+
+    let amount = amount.0.to_u64().ok_or("Amount doesn't fit into a u64")?;
+    if amount == 0 {
+        return Err("Amount must be greater than 0".to_string());
+    }
+
+    let wallet = SuiWallet::new(caller).await;
+    let sender = wallet.sui_address();
+
+    let mut coins = get_coins_rpc(&sender, &coin_type).await?;
+    coins.sort_by(|a, b| b.balance.cmp(&a.balance));
+
+    let mut input_coins = Vec::new();
+    let mut selected = 0u64;
+    for coin in coins {
+        if selected >= amount {
+            break;
+        }
+        selected = selected.saturating_add(coin.balance);
+        input_coins.push(coin.coin_object_id);
+    }
+    if selected < amount {
+        return Err(format!(
+            "Insufficient {} balance. Available: {}, Required: {}",
+            coin_type, selected, amount
+        ));
+    }
+
+    let gas_coin = select_gas_coin(&sender, DEFAULT_GAS_BUDGET_MIST, &input_coins).await?;
+    let tx_bytes = unsafe_pay_rpc(
+        &sender,
+        &input_coins,
+        &[to],
+        &[amount],
+        &gas_coin.coin_object_id,
+        DEFAULT_GAS_BUDGET_MIST,
+    )
+    .await?;
+
+    sign_and_execute_rpc(&wallet, &tx_bytes).await
+}
+
+/// Merges every coin object of `coin_type` into the largest one. For SUI the
+/// gas coin is chosen first and left out of the merge.
+pub async fn merge_coins_impl(coin_type: String) -> Result<String, String> {
+    let caller = ic_cdk::caller();
+    let wallet = SuiWallet::new(caller).await;
+    let sender = wallet.sui_address();
+
+    let (mut coins, gas_coin) = if is_sui_coin_type(&coin_type) {
+        let gas_coin = select_gas_coin(&sender, DEFAULT_GAS_BUDGET_MIST, &[]).await?;
+        let coins: Vec<CoinObject> = get_coins_rpc(&sender, SUI_COIN_TYPE)
+            .await?
+            .into_iter()
+            .filter(|c| c.coin_object_id != gas_coin.coin_object_id)
+            .collect();
+        (coins, gas_coin)
+    } else {
+        let coins = get_coins_rpc(&sender, &coin_type).await?;
+        let gas_coin = select_gas_coin(&sender, DEFAULT_GAS_BUDGET_MIST, &[]).await?;
+        (coins, gas_coin)
+    };
+
+    if coins.len() < 2 {
+        return Err(format!("Nothing to merge: {} coin object(s) of {}", coins.len(), coin_type));
+    }
+
+    coins.sort_by(|a, b| b.balance.cmp(&a.balance));
+    let primary = coins.remove(0).coin_object_id;
+    let to_merge: Vec<String> = coins.into_iter().map(|c| c.coin_object_id).collect();
+
+    let tx_bytes = unsafe_join_coins_rpc(
+        &sender,
+        &coin_type,
+        &primary,
+        &to_merge,
+        &gas_coin.coin_object_id,
+        DEFAULT_GAS_BUDGET_MIST,
+    )
+    .await?;
+
+    sign_and_execute_rpc(&wallet, &tx_bytes).await
+}
+
+/// Splits `coin_object_id` into new coins of the given amounts, paying gas
+/// with a different SUI coin.
+pub async fn split_coin_impl(coin_object_id: String, split_amounts: Vec<Nat>) -> Result<String, String> {
+    if split_amounts.is_empty() {
+        return Err("At least one split amount is required".to_string());
+    }
+    let split_amounts: Vec<u64> = split_amounts
+        .iter()
+        .map(|a| {
+            a.0.to_u64()
+                .ok_or_else(|| format!("Split amount {} doesn't fit into a u64", a))
+        })
+        .collect::<Result<_, _>>()?;
+
+    let caller = ic_cdk::caller();
+    let wallet = SuiWallet::new(caller).await;
+    let sender = wallet.sui_address();
+
+    let gas_coin = select_gas_coin(&sender, DEFAULT_GAS_BUDGET_MIST, &[coin_object_id.clone()]).await?;
+    let tx_bytes = unsafe_split_coin_rpc(
+        &sender,
+        &coin_object_id,
+        &split_amounts,
+        &gas_coin.coin_object_id,
+        DEFAULT_GAS_BUDGET_MIST,
+    )
+    .await?;
+
+    sign_and_execute_rpc(&wallet, &tx_bytes).await
+}
+
 pub fn transform_sui_response_impl(args: TransformArgs) -> HttpResponse {
     HttpResponse {
         status: args.response.status.clone(),
-        body: args.response.body.clone(),
+        body: bit10_core::transform::canonical_body(&args.context, &args.response.body),
         headers: vec![],
     }
-}
\ No newline at end of file
+}
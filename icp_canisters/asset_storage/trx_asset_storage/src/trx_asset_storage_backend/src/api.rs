@@ -1,19 +1,52 @@
 use crate::{
-    service::tron::{get_tron_balance_impl, send_trx_impl, tron_address_impl},
-    state::init_state,
-    types::{InitArg, Nat},
+    service::metrics,
+    service::spending::{self, TronPendingTransfer, TronTransfer},
+    service::tron::{
+        estimate_trc20_transfer_impl, get_trc20_balance_impl, get_tron_balance_impl,
+        transform_tron_response_impl, tron_address_impl,
+    },
+    state::{init_state, mutate_state, providers, read_state},
+    types::{InitArg, Nat, Trc20FeeConfig, Trc20TransferEstimate},
     utils::controller::assert_controller,
 };
+use bit10_core::providers::{ProviderHealth, ProviderSet, ProviderSetView, RpcProvider};
+use bit10_core::spending::{PolicyChange, PolicyChangeOutcome, SpendingPolicy, TransferOutcome, TransferQueue};
 use candid::Principal;
+use ic_cdk::api::management_canister::http_request::{HttpResponse, TransformArgs};
 
 #[ic_cdk::init]
 fn init(maybe_init: Option<InitArg>) {
     assert_controller();
     if let Some(init_arg) = maybe_init {
+        if let Some(config) = &init_arg.trc20_fee_config {
+            config
+                .validate()
+                .unwrap_or_else(|e| ic_cdk::trap(&format!("Invalid trc20_fee_config: {}", e)));
+        }
         init_state(init_arg)
     }
 }
 
+#[ic_cdk::pre_upgrade]
+fn pre_upgrade() {
+    ic_cdk::storage::stable_save((Some(spending::export_transfers()), providers::export_providers()))
+        .expect("Failed to save spending policy to stable storage");
+}
+
+#[ic_cdk::post_upgrade]
+fn post_upgrade() {
+    if let Ok((transfers, rpc_providers)) =
+        ic_cdk::storage::stable_restore::<(Option<TransferQueue<TronTransfer>>, Option<ProviderSet>)>()
+    {
+        if let Some(transfers) = transfers {
+            spending::import_transfers(transfers);
+        }
+        if let Some(rpc_providers) = rpc_providers {
+            providers::import_providers(rpc_providers);
+        }
+    }
+}
+
 #[ic_cdk::update]
 async fn tron_address(owner: Option<Principal>) -> String {
     assert_controller();
@@ -27,7 +60,154 @@ async fn get_tron_balance(address: Option<String>) -> Result<Nat, String> {
 }
 
 #[ic_cdk::update]
-async fn send_trx(to: String, amount: Nat) -> Result<String, String> {
+async fn send_trx(to: String, amount: Nat) -> Result<TransferOutcome<TronTransfer>, String> {
+    assert_controller();
+    spending::send(to, amount, TronTransfer::Trx).await
+}
+
+#[ic_cdk::update]
+async fn get_trc20_balance(address: Option<String>, contract: String) -> Result<Nat, String> {
+    assert_controller();
+    get_trc20_balance_impl(address, contract).await
+}
+
+#[ic_cdk::update]
+async fn estimate_trc20_transfer(to: String, amount: Nat, contract: String) -> Result<Trc20TransferEstimate, String> {
+    assert_controller();
+    estimate_trc20_transfer_impl(to, amount, contract).await
+}
+
+#[ic_cdk::update]
+async fn send_trc20(to: String, amount: Nat, contract: String) -> Result<TransferOutcome<TronTransfer>, String> {
+    assert_controller();
+    spending::send(to, amount, TronTransfer::Trc20 { contract }).await
+}
+
+#[ic_cdk::query]
+fn get_trc20_fee_config() -> Trc20FeeConfig {
+    read_state(|s| s.trc20_fee_config.clone())
+}
+
+#[ic_cdk::update]
+fn set_trc20_fee_config(config: Trc20FeeConfig) -> Result<(), String> {
+    assert_controller();
+    config.validate()?;
+    mutate_state(|s| s.trc20_fee_config = config);
+    Ok(())
+}
+
+#[ic_cdk::query]
+fn get_spending_policy() -> SpendingPolicy {
+    spending::assert_controller_or_approver();
+    spending::get_policy()
+}
+
+#[ic_cdk::update]
+fn set_spending_policy(policy: SpendingPolicy) -> Result<PolicyChangeOutcome, String> {
+    assert_controller();
+    spending::propose_policy(policy)
+}
+
+#[ic_cdk::query]
+fn get_policy_change() -> Option<PolicyChange> {
+    spending::assert_controller_or_approver();
+    spending::get_policy_change()
+}
+
+#[ic_cdk::update]
+fn approve_policy_change() -> Result<PolicyChange, String> {
+    spending::approve_policy_change()
+}
+
+#[ic_cdk::update]
+fn apply_policy_change() -> Result<(), String> {
+    spending::assert_controller_or_approver();
+    spending::apply_policy_change()
+}
+
+#[ic_cdk::update]
+fn cancel_policy_change() -> Result<PolicyChange, String> {
+    spending::assert_controller_or_approver();
+    spending::cancel_policy_change()
+}
+
+#[ic_cdk::query]
+fn get_pending_transfers() -> Vec<TronPendingTransfer> {
+    spending::assert_controller_or_approver();
+    spending::get_pending_transfers()
+}
+
+#[ic_cdk::query]
+fn get_transfer(id: u64) -> Option<TronPendingTransfer> {
+    spending::assert_controller_or_approver();
+    spending::get_transfer(id)
+}
+
+#[ic_cdk::update]
+fn approve_transfer(id: u64) -> Result<TronPendingTransfer, String> {
+    spending::approve(id)
+}
+
+#[ic_cdk::update]
+fn cancel_transfer(id: u64) -> Result<TronPendingTransfer, String> {
+    spending::assert_controller_or_approver();
+    spending::cancel(id)
+}
+
+#[ic_cdk::update]
+async fn execute_transfer(id: u64) -> Result<String, String> {
+    spending::assert_controller_or_approver();
+    spending::execute(id).await
+}
+
+#[ic_cdk::update]
+fn resolve_stuck_transfer(id: u64, tx: Option<String>) -> Result<TronPendingTransfer, String> {
+    assert_controller();
+    spending::resolve_stuck(id, tx)
+}
+
+#[ic_cdk::query]
+fn get_rpc_provider_health() -> Vec<(String, ProviderHealth)> {
+    assert_controller();
+    providers::provider_health()
+}
+
+#[ic_cdk::update]
+fn clear_rpc_provider_flag(name: String) -> Result<(), String> {
+    assert_controller();
+    providers::clear_provider_flag(&name)
+}
+
+#[ic_cdk::query]
+fn get_rpc_config() -> ProviderSetView {
     assert_controller();
-    send_trx_impl(to, amount).await
-}
\ No newline at end of file
+    providers::rpc_config()
+}
+
+#[ic_cdk::update]
+fn set_rpc_providers(rpc_providers: Vec<RpcProvider>, quorum: u8) -> Result<(), String> {
+    assert_controller();
+    providers::set_rpc_providers(rpc_providers, quorum)
+}
+
+#[ic_cdk::update]
+fn set_rpc_api_key(provider: String, api_key: Option<String>) -> Result<(), String> {
+    assert_controller();
+    providers::set_rpc_api_key(&provider, api_key)
+}
+
+#[ic_cdk::update]
+fn reset_rpc_providers() {
+    assert_controller();
+    providers::reset_rpc_providers()
+}
+
+#[ic_cdk::query]
+fn http_request(request: bit10_core::metrics::HttpRequest) -> bit10_core::metrics::HttpResponse {
+    metrics::http_request(request)
+}
+
+#[ic_cdk::query]
+fn transform_tron_response(args: TransformArgs) -> HttpResponse {
+    transform_tron_response_impl(args)
+}
@@ -0,0 +1,41 @@
+use crate::{service::spending, state::providers};
+use bit10_core::metrics::{self, GasMonitor, HealthReport, HttpRequest, HttpResponse, MetricsEncoder};
+use std::cell::RefCell;
+
+thread_local! {
+    static GAS: RefCell<GasMonitor> = RefCell::new(GasMonitor::default());
+}
+
+/// Every requester signs from their own wallet, so there is no single fee
+/// wallet to poll; the balance is sampled whenever a TRC-20 transfer checks
+/// its fees instead.
+pub fn record_gas(chain: &str, balance: u128, minimum: u128) {
+    GAS.with(|g| g.borrow_mut().record(chain, balance, minimum, ic_cdk::api::time()));
+}
+
+pub fn http_request(request: HttpRequest) -> HttpResponse {
+    match request.path() {
+        "/metrics" => encode_metrics().into_response(),
+        "/health" => health().into_response(),
+        _ => HttpResponse::not_found(),
+    }
+}
+
+fn encode_metrics() -> MetricsEncoder {
+    let mut encoder = MetricsEncoder::new();
+    metrics::encode_canister(
+        &mut encoder,
+        ic_cdk::api::canister_balance128(),
+        ic_cdk::api::stable::stable_size() * 65536,
+    );
+    metrics::encode_provider_health(&mut encoder, &[("tron".to_string(), providers::provider_health())]);
+    metrics::encode_pending_sagas(&mut encoder, &[("transfer", spending::pending_count())]);
+    GAS.with(|g| g.borrow().encode(&mut encoder));
+    encoder
+}
+
+fn health() -> HealthReport {
+    let mut report = HealthReport::new(ic_cdk::api::time());
+    GAS.with(|g| g.borrow().check(&mut report));
+    report
+}
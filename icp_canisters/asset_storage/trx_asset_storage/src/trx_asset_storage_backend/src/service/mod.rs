@@ -1 +1,3 @@
+pub mod metrics;
+pub mod spending;
 pub mod tron;
\ No newline at end of file
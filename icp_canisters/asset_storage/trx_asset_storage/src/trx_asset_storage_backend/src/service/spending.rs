@@ -0,0 +1,118 @@
+use crate::{
+    service::tron::{send_trc20_impl, send_trx_impl},
+    types::Nat,
+};
+use bit10_core::spending::{
+    PendingTransfer, PolicyChange, PolicyChangeOutcome, SpendingPolicy, TransferOutcome, TransferQueue,
+};
+use candid::{CandidType, Deserialize};
+use ic_cdk::api::{caller, is_controller};
+use std::cell::RefCell;
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum TronTransfer {
+    Trx,
+    Trc20 { contract: String },
+}
+
+pub type TronPendingTransfer = PendingTransfer<TronTransfer>;
+
+// Tron addresses are base58check and case-sensitive, so destinations and
+// contracts are compared exactly as given.
+thread_local! {
+    static TRANSFERS: RefCell<TransferQueue<TronTransfer>> = RefCell::new(TransferQueue::default());
+}
+
+pub fn export_transfers() -> TransferQueue<TronTransfer> {
+    TRANSFERS.with(|t| t.borrow().clone())
+}
+
+pub fn import_transfers(transfers: TransferQueue<TronTransfer>) {
+    TRANSFERS.with(|t| *t.borrow_mut() = transfers);
+}
+
+pub fn assert_controller_or_approver() {
+    let caller = caller();
+    if !is_controller(&caller) && !TRANSFERS.with(|t| t.borrow().is_approver(&caller)) {
+        ic_cdk::trap("Only the canister controller or an approver can call this method.");
+    }
+}
+
+pub fn get_policy() -> SpendingPolicy {
+    TRANSFERS.with(|t| t.borrow().policy().clone())
+}
+
+pub fn propose_policy(policy: SpendingPolicy) -> Result<PolicyChangeOutcome, String> {
+    TRANSFERS.with(|t| t.borrow_mut().propose_policy(policy, caller(), ic_cdk::api::time()))
+}
+
+/// Runs the transfer straight away if the policy allows it, otherwise leaves
+/// it queued for approvals or its delay.
+pub async fn send(to: String, amount: Nat, args: TronTransfer) -> Result<TransferOutcome<TronTransfer>, String> {
+    let asset = match &args {
+        TronTransfer::Trx => "TRX".to_string(),
+        TronTransfer::Trc20 { contract } => contract.clone(),
+    };
+    let now = ic_cdk::api::time();
+    let transfer = TRANSFERS.with(|t| t.borrow_mut().request(&asset, &to, amount, args, caller(), now))?;
+
+    if !transfer.is_ready(now) {
+        return Ok(TransferOutcome::Pending(transfer));
+    }
+    execute(transfer.id).await.map(TransferOutcome::Executed)
+}
+
+pub async fn execute(id: u64) -> Result<String, String> {
+    let transfer = TRANSFERS.with(|t| t.borrow_mut().start_execution(id, ic_cdk::api::time()))?;
+
+    let result = match transfer.args {
+        TronTransfer::Trx => send_trx_impl(transfer.requested_by, transfer.destination, transfer.amount).await,
+        TronTransfer::Trc20 { contract } => {
+            send_trc20_impl(transfer.requested_by, transfer.destination, transfer.amount, contract).await
+        }
+    };
+
+    TRANSFERS.with(|t| t.borrow_mut().finish_execution(id, &result, ic_cdk::api::time()));
+    result
+}
+
+pub fn approve(id: u64) -> Result<TronPendingTransfer, String> {
+    TRANSFERS.with(|t| t.borrow_mut().approve(id, caller(), ic_cdk::api::time()))
+}
+
+pub fn cancel(id: u64) -> Result<TronPendingTransfer, String> {
+    TRANSFERS.with(|t| t.borrow_mut().cancel(id, ic_cdk::api::time()))
+}
+
+pub fn get_policy_change() -> Option<PolicyChange> {
+    TRANSFERS.with(|t| t.borrow().policy_change().cloned())
+}
+
+pub fn approve_policy_change() -> Result<PolicyChange, String> {
+    TRANSFERS.with(|t| t.borrow_mut().approve_policy_change(caller()))
+}
+
+pub fn apply_policy_change() -> Result<(), String> {
+    TRANSFERS.with(|t| t.borrow_mut().apply_policy_change(ic_cdk::api::time()))
+}
+
+pub fn cancel_policy_change() -> Result<PolicyChange, String> {
+    TRANSFERS.with(|t| t.borrow_mut().cancel_policy_change())
+}
+
+pub fn resolve_stuck(id: u64, tx: Option<String>) -> Result<TronPendingTransfer, String> {
+    let result = tx.ok_or_else(|| "Marked failed by a controller".to_string());
+    TRANSFERS.with(|t| t.borrow_mut().resolve_stuck(id, &result, ic_cdk::api::time()))
+}
+
+pub fn get_transfer(id: u64) -> Option<TronPendingTransfer> {
+    TRANSFERS.with(|t| t.borrow().get(id).cloned())
+}
+
+pub fn get_pending_transfers() -> Vec<TronPendingTransfer> {
+    TRANSFERS.with(|t| t.borrow().pending())
+}
+
+pub fn pending_count() -> usize {
+    TRANSFERS.with(|t| t.borrow().pending().len())
+}
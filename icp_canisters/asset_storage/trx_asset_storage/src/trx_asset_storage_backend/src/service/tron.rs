@@ -1,16 +1,27 @@
 use crate::{
-    state::{read_state, init_state},
-    types::{Nat, TronNetwork},
+    service::metrics,
+    state::{providers, read_state},
+    types::{Nat, Trc20TransferEstimate},
+    utils::abi::{decode_uint256, encode_address, encode_uint256},
     utils::principal::principal_to_tron_address,
 };
+use bit10_core::cycles;
+use bit10_core::providers::RpcProvider;
+use bit10_core::transform::{canonical_body, ResponseShape};
 use candid::Principal;
 use ic_cdk::api::management_canister::http_request::{
-    http_request, CanisterHttpRequestArgument, HttpHeader, HttpMethod,
+    http_request, CanisterHttpRequestArgument, HttpHeader, HttpMethod, HttpResponse, TransformArgs,
+    TransformContext,
 };
 use sha2::{Sha256, Digest};
-use crate::utils::principal::principal_to_tron_address;
 use crate::wallet::bsc_wallet::TronWallet;
 
+const TRC20_TRANSFER_SELECTOR: &str = "transfer(address,uint256)";
+const TRC20_BALANCE_OF_SELECTOR: &str = "balanceOf(address)";
+// Raw data of a TRC-20 transfer plus one signature and the result slot.
+const TRC20_TRANSFER_BANDWIDTH: u64 = 350;
+const BANDWIDTH_PRICE_SUN: u64 = 1_000;
+
 pub async fn tron_address_impl(owner: Option<Principal>) -> String {
     let owner = owner.unwrap_or_else(ic_cdk::caller);
     principal_to_tron_address(&owner)
@@ -22,18 +33,6 @@ pub async fn get_tron_balance_impl(address: Option<String>) -> Result<Nat, Strin
         None => principal_to_tron_address(&ic_cdk::caller()),
     };
 
-    let tron_network = read_state(|s| s.tron_network());
-    let (api_url, api_key) = match tron_network {
-        TronNetwork::Mainnet => (
-            "https://tron-mainnet.gateway.tatum.io",
-            "<API_KEY>",
-        ),
-        TronNetwork::Nile => (
-            "https://tron-testnet.gateway.tatum.io",
-            "<API_KEY>",
-        ),
-    };
-
     let balance_request_body = format!(
         r#"{{
             "address": "{}",
@@ -42,50 +41,15 @@ pub async fn get_tron_balance_impl(address: Option<String>) -> Result<Nat, Strin
         address
     );
 
-    let balance_request = CanisterHttpRequestArgument {
-        url: format!("{}/wallet/getaccount", api_url),
-        method: HttpMethod::POST,
-        body: Some(balance_request_body.into_bytes()),
-        max_response_bytes: Some(2000),
-        transform: None,
-        headers: vec![
-            HttpHeader {
-                name: "Content-Type".to_string(),
-                value: "application/json".to_string(),
-            },
-            HttpHeader {
-                name: "accept".to_string(),
-                value: "application/json".to_string(),
-            },
-            HttpHeader {
-                name: "x-api-key".to_string(),
-                value: api_key.to_string(),
-            },
-        ],
-    };
-
-    let cycles = 2_000_000_000u128;
-    match http_request(balance_request, cycles).await {
-        Ok((response,)) => {
-            if response.status == 200u32 {
-                let body = String::from_utf8(response.body)
-                    .map_err(|e| format!("Failed to parse balance response body: {:?}", e))?;
-                let json_response: serde_json::Value = serde_json::from_str(&body)
-                    .map_err(|e| format!("Failed to parse balance JSON response: {:?}", e))?;
-                let balance = json_response["balance"]
-                    .as_u64()
-                    .ok_or_else(|| "Failed to get balance from response".to_string())?;
-                Ok(Nat::from(balance))
-            } else {
-                Err(format!("Balance request failed with status: {}", response.status))
-            }
-        }
-        Err((code, message)) => Err(format!("Balance request failed: {:?} - {}", code, message)),
-    }
+    let json_response = tron_quorum_post("getaccount", balance_request_body, 2000).await?;
+    // Accounts that were never activated come back as an empty object.
+    let balance = json_response["balance"].as_u64().unwrap_or(0);
+    Ok(Nat::from(balance))
 }
 
-pub async fn send_trx_impl(to: String, amount: Nat) -> Result<String, String> {
-    let caller = ic_cdk::caller();
+/// Sends from the wallet derived for `caller`, which is the principal that
+/// requested the transfer rather than whoever executes it.
+pub async fn send_trx_impl(caller: Principal, to: String, amount: Nat) -> Result<String, String> {
     let wallet = TronWallet::new(caller).await;
     let from_address = wallet.tron_address();
 
@@ -94,18 +58,6 @@ pub async fn send_trx_impl(to: String, amount: Nat) -> Result<String, String> {
         return Err("Amount must be greater than 0".to_string());
     }
 
-    let tron_network = read_state(|s| s.tron_network());
-    let (api_url, api_key) = match tron_network {
-        TronNetwork::Mainnet => (
-            "https://tron-mainnet.gateway.tatum.io",
-            "<API_KEY>",
-        ),
-        TronNetwork::Nile => (
-            "https://tron-testnet.gateway.tatum.io",
-            "<API_KEY>",
-        ),
-    };
-
     let create_tx_body = format!(
         r#"{{
             "owner_address": "{}",
@@ -116,53 +68,200 @@ pub async fn send_trx_impl(to: String, amount: Nat) -> Result<String, String> {
         from_address, to, amount_sun
     );
 
-    let create_tx_request = CanisterHttpRequestArgument {
-        url: format!("{}/wallet/createtransaction", api_url),
-        method: HttpMethod::POST,
-        body: Some(create_tx_body.into_bytes()),
-        max_response_bytes: Some(4000),
-        transform: None,
-        headers: vec![
-            HttpHeader {
-                name: "Content-Type".to_string(),
-                value: "application/json".to_string(),
-            },
-            HttpHeader {
-                name: "accept".to_string(),
-                value: "application/json".to_string(),
-            },
-            HttpHeader {
-                name: "x-api-key".to_string(),
-                value: api_key.to_string(),
-            },
-        ],
-    };
+    let create_response = tron_api_post("createtransaction", create_tx_body, 4000).await?;
+    if let Some(error) = create_response.get("Error") {
+        return Err(format!("Transaction creation failed: {}", error));
+    }
 
-    let cycles = 3_000_000_000u128;
-    let create_response = match http_request(create_tx_request, cycles).await {
-        Ok((response,)) => {
-            if response.status == 200u32 {
-                let body = String::from_utf8(response.body)
-                    .map_err(|e| format!("Failed to parse create transaction response: {:?}", e))?;
-                let json_response: serde_json::Value = serde_json::from_str(&body)
-                    .map_err(|e| format!("Failed to parse create transaction JSON: {:?}", e))?;
-                if let Some(error) = json_response.get("Error") {
-                    return Err(format!("Transaction creation failed: {}", error));
-                }
-                json_response
-            } else {
-                return Err(format!("Create transaction failed with status: {}", response.status));
-            }
-        }
-        Err((code, message)) => {
-            return Err(format!("Create transaction request failed: {:?} - {}", code, message));
-        }
+    sign_and_broadcast(&wallet, &create_response).await
+}
+
+pub async fn get_trc20_balance_impl(address: Option<String>, contract: String) -> Result<Nat, String> {
+    let address = match address {
+        Some(addr) => addr,
+        None => principal_to_tron_address(&ic_cdk::caller()),
     };
 
-    let tx_id = create_response["txID"]
+    let response = trigger_constant_contract(
+        &address,
+        &contract,
+        TRC20_BALANCE_OF_SELECTOR,
+        &encode_address(&address)?,
+    )
+    .await?;
+
+    let result = response["constant_result"][0]
+        .as_str()
+        .ok_or_else(|| "Failed to get constant_result from response".to_string())?;
+    decode_uint256(result)
+}
+
+pub async fn estimate_trc20_transfer_impl(
+    to: String,
+    amount: Nat,
+    contract: String,
+) -> Result<Trc20TransferEstimate, String> {
+    let from_address = principal_to_tron_address(&ic_cdk::caller());
+    estimate_trc20_transfer(&from_address, &to, &amount, &contract).await
+}
+
+pub async fn send_trc20_impl(caller: Principal, to: String, amount: Nat, contract: String) -> Result<String, String> {
+    let wallet = TronWallet::new(caller).await;
+    let from_address = wallet.tron_address();
+
+    if amount == Nat::from(0u64) {
+        return Err("Amount must be greater than 0".to_string());
+    }
+
+    let token_balance = get_trc20_balance_impl(Some(from_address.clone()), contract.clone()).await?;
+    if token_balance < amount {
+        return Err(format!(
+            "Insufficient token balance: have {}, need {}",
+            token_balance, amount
+        ));
+    }
+
+    let estimate = estimate_trc20_transfer(&from_address, &to, &amount, &contract).await?;
+
+    let trx_balance = nat_to_u64(get_tron_balance_impl(Some(from_address.clone())).await?);
+    let bandwidth_burn = estimate
+        .bandwidth_required
+        .saturating_sub(estimate.available_bandwidth)
+        .saturating_mul(BANDWIDTH_PRICE_SUN);
+    let energy_burn = estimate
+        .energy_required
+        .saturating_sub(estimate.available_energy)
+        .saturating_mul(estimate.energy_price_sun);
+    metrics::record_gas("tron", trx_balance as u128, bandwidth_burn.saturating_add(energy_burn) as u128);
+    if trx_balance < bandwidth_burn.saturating_add(energy_burn) {
+        return Err(format!(
+            "Insufficient TRX for fees: have {} sun, need about {} sun",
+            trx_balance,
+            bandwidth_burn.saturating_add(energy_burn)
+        ));
+    }
+
+    let parameter = format!("{}{}", encode_address(&to)?, encode_uint256(&amount)?);
+    let trigger_body = format!(
+        r#"{{
+            "owner_address": "{}",
+            "contract_address": "{}",
+            "function_selector": "{}",
+            "parameter": "{}",
+            "fee_limit": {},
+            "call_value": 0,
+            "visible": true
+        }}"#,
+        from_address, contract, TRC20_TRANSFER_SELECTOR, parameter, estimate.fee_limit_sun
+    );
+
+    let trigger_response = tron_api_post("triggersmartcontract", trigger_body, 8000).await?;
+    if !trigger_response["result"]["result"].as_bool().unwrap_or(false) {
+        let message = trigger_response["result"]["message"]
+            .as_str()
+            .map(decode_tron_message)
+            .unwrap_or_else(|| "Unknown error".to_string());
+        return Err(format!("Contract call creation failed: {}", message));
+    }
+
+    sign_and_broadcast(&wallet, &trigger_response["transaction"]).await
+}
+
+async fn estimate_trc20_transfer(
+    from_address: &str,
+    to: &str,
+    amount: &Nat,
+    contract: &str,
+) -> Result<Trc20TransferEstimate, String> {
+    let parameter = format!("{}{}", encode_address(to)?, encode_uint256(amount)?);
+    let simulation =
+        trigger_constant_contract(from_address, contract, TRC20_TRANSFER_SELECTOR, &parameter).await?;
+    if !simulation["result"]["result"].as_bool().unwrap_or(false) {
+        let message = simulation["result"]["message"]
+            .as_str()
+            .map(decode_tron_message)
+            .unwrap_or_else(|| "Unknown error".to_string());
+        return Err(format!("Transfer simulation failed: {}", message));
+    }
+    let energy_required = simulation["energy_used"].as_u64().unwrap_or(0)
+        + simulation["energy_penalty"].as_u64().unwrap_or(0);
+
+    let resource_body = format!(
+        r#"{{
+            "address": "{}",
+            "visible": true
+        }}"#,
+        from_address
+    );
+    let resources = tron_api_post("getaccountresource", resource_body, 2000).await?;
+    let field = |name: &str| resources[name].as_u64().unwrap_or(0);
+    let available_energy = field("EnergyLimit").saturating_sub(field("EnergyUsed"));
+    let available_bandwidth = field("freeNetLimit").saturating_sub(field("freeNetUsed"))
+        + field("NetLimit").saturating_sub(field("NetUsed"));
+
+    let config = read_state(|s| s.trc20_fee_config.clone());
+    let energy_price_sun = current_energy_price()
+        .await
+        .unwrap_or(config.fallback_energy_price_sun);
+
+    let energy_burn = energy_required
+        .saturating_sub(available_energy)
+        .saturating_mul(energy_price_sun);
+    if energy_burn > config.max_fee_limit_sun {
+        return Err(format!(
+            "Energy cost of {} sun exceeds the configured fee limit ceiling of {} sun",
+            energy_burn, config.max_fee_limit_sun
+        ));
+    }
+
+    let fee_limit_sun = (energy_burn.saturating_mul(config.fee_limit_multiplier_percent) / 100)
+        .clamp(config.min_fee_limit_sun, config.max_fee_limit_sun);
+
+    Ok(Trc20TransferEstimate {
+        energy_required,
+        bandwidth_required: TRC20_TRANSFER_BANDWIDTH,
+        available_energy,
+        available_bandwidth,
+        energy_price_sun,
+        fee_limit_sun,
+    })
+}
+
+async fn trigger_constant_contract(
+    owner_address: &str,
+    contract: &str,
+    function_selector: &str,
+    parameter: &str,
+) -> Result<serde_json::Value, String> {
+    let body = format!(
+        r#"{{
+            "owner_address": "{}",
+            "contract_address": "{}",
+            "function_selector": "{}",
+            "parameter": "{}",
+            "visible": true
+        }}"#,
+        owner_address, contract, function_selector, parameter
+    );
+    tron_quorum_post("triggerconstantcontract", body, 8000).await
+}
+
+/// Latest entry of `getenergyprices`, which lists `timestamp:price` pairs.
+async fn current_energy_price() -> Result<u64, String> {
+    let response = tron_api_post("getenergyprices", "{}".to_string(), 8000).await?;
+    response["prices"]
+        .as_str()
+        .and_then(|prices| prices.rsplit(',').next())
+        .and_then(|latest| latest.split(':').nth(1))
+        .and_then(|price| price.parse::<u64>().ok())
+        .ok_or_else(|| "Failed to parse energy prices".to_string())
+}
+
+async fn sign_and_broadcast(wallet: &TronWallet, transaction: &serde_json::Value) -> Result<String, String> {
+    let tx_id = transaction["txID"]
         .as_str()
         .ok_or_else(|| "Failed to get txID from response".to_string())?;
-    let raw_data_hex = create_response["raw_data_hex"]
+    let raw_data_hex = transaction["raw_data_hex"]
         .as_str()
         .ok_or_else(|| "Failed to get raw_data_hex from response".to_string())?;
 
@@ -178,6 +277,12 @@ pub async fn send_trx_impl(to: String, amount: Nat) -> Result<String, String> {
     hasher.update(&raw_data);
     let tx_hash: [u8; 32] = hasher.finalize().into();
 
+    // The node builds the transaction, so make sure the txID it reports is the
+    // hash of the raw data we are about to sign.
+    if hex::encode(tx_hash) != tx_id.to_lowercase() {
+        return Err("txID does not match the hash of raw_data_hex".to_string());
+    }
+
     let signature_with_recovery = wallet.sign_with_ecdsa_enhanced(tx_hash).await;
     let signature_hex = hex::encode(&signature_with_recovery);
 
@@ -189,58 +294,162 @@ pub async fn send_trx_impl(to: String, amount: Nat) -> Result<String, String> {
             "signature": ["{}"],
             "visible": true
         }}"#,
-        create_response["raw_data"],
+        transaction["raw_data"],
         raw_data_hex,
         tx_id,
         signature_hex
     );
 
-    let broadcast_request = CanisterHttpRequestArgument {
-        url: format!("{}/wallet/broadcasttransaction", api_url),
+    let json_response = tron_api_post("broadcasttransaction", signed_tx_body, 2000).await?;
+    let result = json_response["result"].as_bool().unwrap_or(false);
+    if result {
+        Ok(tx_id.to_string())
+    } else {
+        let message = json_response["message"]
+            .as_str()
+            .map(decode_tron_message)
+            .unwrap_or_else(|| "Unknown error".to_string());
+        Err(format!("Transaction broadcast failed: {}", message))
+    }
+}
+
+/// Cycles the IC charges for `request`, from its size and response limit.
+fn outcall_cost(request: &CanisterHttpRequestArgument) -> u128 {
+    let bytes = cycles::request_bytes(
+        &request.url,
+        request.headers.iter().map(|h| (h.name.as_str(), h.value.as_str())),
+        request.body.as_deref(),
+        request
+            .transform
+            .as_ref()
+            .map(|t| (t.function.0.method.as_str(), t.context.as_slice())),
+    );
+    cycles::http_request_cost(bytes, request.max_response_bytes)
+}
+
+async fn post_tron_api(
+    provider: &RpcProvider,
+    method: &str,
+    body: String,
+    max_response_bytes: u64,
+) -> Result<serde_json::Value, String> {
+    let mut headers = vec![
+        HttpHeader {
+            name: "Content-Type".to_string(),
+            value: "application/json".to_string(),
+        },
+        HttpHeader {
+            name: "accept".to_string(),
+            value: "application/json".to_string(),
+        },
+    ];
+    headers.extend(
+        provider
+            .resolved_headers()
+            .into_iter()
+            .map(|(name, value)| HttpHeader { name, value }),
+    );
+
+    let request = CanisterHttpRequestArgument {
+        url: format!("{}/wallet/{}", provider.resolved_url(), method),
         method: HttpMethod::POST,
-        body: Some(signed_tx_body.into_bytes()),
-        max_response_bytes: Some(2000),
-        transform: None,
-        headers: vec![
-            HttpHeader {
-                name: "Content-Type".to_string(),
-                value: "application/json".to_string(),
-            },
-            HttpHeader {
-                name: "accept".to_string(),
-                value: "application/json".to_string(),
-            },
-            HttpHeader {
-                name: "x-api-key".to_string(),
-                value: api_key.to_string(),
-            },
-        ],
+        body: Some(body.into_bytes()),
+        max_response_bytes: Some(max_response_bytes),
+        transform: Some(TransformContext::from_name(
+            "transform_tron_response".to_string(),
+            ResponseShape::json(response_fields(method)).to_context(),
+        )),
+        headers,
     };
 
-    match http_request(broadcast_request, cycles).await {
+    let cycles = outcall_cost(&request);
+    match http_request(request, cycles).await {
         Ok((response,)) => {
             let body = String::from_utf8(response.body)
-                .map_err(|e| format!("Failed to parse broadcast response: {:?}", e))?;
+                .map_err(|e| format!("Failed to parse {} response body: {:?}", method, e))?;
             if response.status == 200u32 {
-                let json_response: serde_json::Value = serde_json::from_str(&body)
-                    .map_err(|e| format!("Failed to parse broadcast JSON: {:?}", e))?;
-                let result = json_response["result"].as_bool().unwrap_or(false);
-                if result {
-                    Ok(tx_id.to_string())
-                } else {
-                    let message = json_response["message"]
-                        .as_str()
-                        .unwrap_or("Unknown error");
-                    Err(format!("Transaction broadcast failed: {}", message))
-                }
+                serde_json::from_str(&body)
+                    .map_err(|e| format!("Failed to parse {} JSON response: {:?}", method, e))
             } else {
-                Err(format!("Broadcast failed with status: {} - Response: {}", response.status, body))
+                Err(format!("{} failed with status: {} - Response: {}", method, response.status, body))
             }
         }
-        Err((code, message)) => {
-            Err(format!("Broadcast request failed: {:?} - {}", code, message))
+        Err((code, message)) => Err(format!("{} request failed: {:?} - {}", method, code, message)),
+    }
+}
+
+/// Sends to one provider at a time, moving on when the outcall fails or the
+/// node answers with an HTTP error. Used for building and broadcasting
+/// transactions and for fee inputs that differ from node to node.
+async fn tron_api_post(
+    method: &str,
+    body: String,
+    max_response_bytes: u64,
+) -> Result<serde_json::Value, String> {
+    let mut errors = Vec::new();
+    for provider in providers::rpc_providers() {
+        match post_tron_api(&provider, method, body.clone(), max_response_bytes).await {
+            Ok(response) => return Ok(response),
+            Err(e) => errors.push(format!("{}: {}", provider.name, e)),
         }
     }
+    Err(format!("{} failed on every provider: {}", method, errors.join("; ")))
+}
+
+/// Sends the read to every provider and requires a quorum to agree on the
+/// response. The transform has already reduced it to `response_fields`, so
+/// node-specific data such as timestamps is not compared.
+async fn tron_quorum_post(
+    method: &str,
+    body: String,
+    max_response_bytes: u64,
+) -> Result<serde_json::Value, String> {
+    let mut responses = Vec::new();
+    for provider in providers::rpc_providers() {
+        let response = post_tron_api(&provider, method, body.clone(), max_response_bytes)
+            .await
+            .map(|json| json.to_string());
+        responses.push((provider.name, response));
+    }
+
+    let result = providers::tally(responses).map_err(|e| format!("{} failed: {}", method, e))?;
+    serde_json::from_str(&result).map_err(|e| format!("Failed to parse {} JSON response: {:?}", method, e))
+}
+
+/// Fields read from each `/wallet/` method's response. Everything else, such
+/// as node-side timestamps, is dropped by the transform.
+fn response_fields(method: &str) -> &'static [&'static str] {
+    match method {
+        "getaccount" => &["balance"],
+        "createtransaction" => &["Error", "txID", "raw_data", "raw_data_hex"],
+        "triggersmartcontract" => &[
+            "result",
+            "transaction.txID",
+            "transaction.raw_data",
+            "transaction.raw_data_hex",
+        ],
+        "triggerconstantcontract" => &["constant_result", "energy_used", "energy_penalty", "result"],
+        "getaccountresource" => &["EnergyLimit", "EnergyUsed", "freeNetLimit", "freeNetUsed", "NetLimit", "NetUsed"],
+        "getenergyprices" => &["prices"],
+        "broadcasttransaction" => &["result", "code", "message"],
+        _ => &[],
+    }
+}
+
+pub fn transform_tron_response_impl(args: TransformArgs) -> HttpResponse {
+    HttpResponse {
+        status: args.response.status.clone(),
+        body: canonical_body(&args.context, &args.response.body),
+        headers: vec![],
+    }
+}
+
+/// TRON nodes return error messages hex encoded.
+fn decode_tron_message(message: &str) -> String {
+    hex::decode(message)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .unwrap_or_else(|| message.to_string())
 }
 
 fn nat_to_u64(nat: Nat) -> u64 {
@@ -1,2 +1,3 @@
+pub mod providers;
 pub mod state;
 pub use state::*;
\ No newline at end of file
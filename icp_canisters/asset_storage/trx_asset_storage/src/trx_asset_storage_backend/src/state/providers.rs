@@ -0,0 +1,73 @@
+use super::read_state;
+use crate::types::TronNetwork;
+use bit10_core::providers::{ProviderHealth, ProviderSet, ProviderSetView, RpcProvider};
+use std::cell::RefCell;
+
+thread_local! {
+    static PROVIDERS: RefCell<Option<ProviderSet>> = const { RefCell::new(None) };
+}
+
+fn default_providers(network: TronNetwork) -> ProviderSet {
+    let providers = match network {
+        TronNetwork::Mainnet => vec![
+            RpcProvider::new("publicnode", "https://tron-rpc.publicnode.com"),
+            RpcProvider::new("trongrid", "https://api.trongrid.io").with_header("TRON-PRO-API-KEY", "{api_key}"),
+            RpcProvider::new("tatum", "https://tron-mainnet.gateway.tatum.io").with_header("x-api-key", "{api_key}"),
+        ],
+        TronNetwork::Nile => vec![
+            RpcProvider::new("trongrid", "https://nile.trongrid.io"),
+            RpcProvider::new("tatum", "https://tron-testnet.gateway.tatum.io").with_header("x-api-key", "{api_key}"),
+        ],
+    };
+    ProviderSet::new(providers, 2).expect("Invalid default Tron providers")
+}
+
+fn with_providers<R>(f: impl FnOnce(&mut ProviderSet) -> R) -> R {
+    PROVIDERS.with(|p| {
+        let mut providers = p.borrow_mut();
+        let set = providers.get_or_insert_with(|| default_providers(read_state(|s| s.tron_network())));
+        f(set)
+    })
+}
+
+pub fn export_providers() -> Option<ProviderSet> {
+    PROVIDERS.with(|p| p.borrow().clone())
+}
+
+pub fn import_providers(providers: ProviderSet) {
+    PROVIDERS.with(|p| *p.borrow_mut() = Some(providers));
+}
+
+/// Callable providers, in the order single-provider calls fall back through.
+pub fn rpc_providers() -> Vec<RpcProvider> {
+    with_providers(|set| set.callable())
+}
+
+pub fn tally(responses: Vec<(String, Result<String, String>)>) -> Result<String, String> {
+    with_providers(|set| set.tally(responses, ic_cdk::api::time()))
+}
+
+pub fn provider_health() -> Vec<(String, ProviderHealth)> {
+    with_providers(|set| set.health())
+}
+
+pub fn clear_provider_flag(name: &str) -> Result<(), String> {
+    with_providers(|set| set.clear_flag(name))
+}
+
+pub fn rpc_config() -> ProviderSetView {
+    with_providers(|set| set.view())
+}
+
+pub fn set_rpc_providers(providers: Vec<RpcProvider>, quorum: u8) -> Result<(), String> {
+    with_providers(|set| set.replace_providers(providers, quorum))
+}
+
+pub fn set_rpc_api_key(name: &str, api_key: Option<String>) -> Result<(), String> {
+    with_providers(|set| set.set_api_key(name, api_key))
+}
+
+/// Back to the network defaults, dropping any keys and health.
+pub fn reset_rpc_providers() {
+    PROVIDERS.with(|p| *p.borrow_mut() = None);
+}
@@ -1,4 +1,4 @@
-use crate::types::{InitArg, TronNetwork, EcdsaKeyName};
+use crate::types::{InitArg, TronNetwork, EcdsaKeyName, Trc20FeeConfig};
 
 static mut STATE: Option<State> = None;
 
@@ -6,12 +6,20 @@ static mut STATE: Option<State> = None;
 pub struct State {
     pub tron_network: TronNetwork,
     pub ecdsa_key_name: EcdsaKeyName,
+    pub trc20_fee_config: Trc20FeeConfig,
+}
+
+impl State {
+    pub fn tron_network(&self) -> TronNetwork {
+        self.tron_network
+    }
 }
 
 pub fn init_state(arg: InitArg) {
     let state = State {
         tron_network: arg.tron_network.unwrap_or_default(),
         ecdsa_key_name: arg.ecdsa_key_name.unwrap_or_default(),
+        trc20_fee_config: arg.trc20_fee_config.unwrap_or_default(),
     };
     unsafe { STATE = Some(state); }
 }
@@ -23,4 +31,13 @@ where
     unsafe {
         f(STATE.as_ref().expect("State not initialized"))
     }
-}
\ No newline at end of file
+}
+
+pub fn mutate_state<F, R>(f: F) -> R
+where
+    F: FnOnce(&mut State) -> R,
+{
+    unsafe {
+        f(STATE.as_mut().expect("State not initialized"))
+    }
+}
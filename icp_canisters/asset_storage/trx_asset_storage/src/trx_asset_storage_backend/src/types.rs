@@ -1,9 +1,11 @@
-use candid::{CandidType, Deserialize, Principal, Nat};
+use candid::{CandidType, Deserialize, Principal};
+pub use candid::Nat;
 
 #[derive(CandidType, Deserialize, Debug, Default, PartialEq, Eq)]
 pub struct InitArg {
     pub tron_network: Option<TronNetwork>,
     pub ecdsa_key_name: Option<EcdsaKeyName>,
+    pub trc20_fee_config: Option<Trc20FeeConfig>,
 }
 
 #[derive(CandidType, Deserialize, Debug, Default, PartialEq, Eq, Clone, Copy)]
@@ -19,4 +21,54 @@ pub enum EcdsaKeyName {
     TestKeyLocalDevelopment,
     TestKey1,
     ProductionKey1,
-}
\ No newline at end of file
+}
+
+/// Controls the `fee_limit` attached to TRC-20 calls. The limit only caps
+/// what the network may burn for energy the wallet does not already have.
+#[derive(CandidType, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Trc20FeeConfig {
+    pub fee_limit_multiplier_percent: u64,
+    pub min_fee_limit_sun: u64,
+    pub max_fee_limit_sun: u64,
+    pub fallback_energy_price_sun: u64,
+}
+
+impl Default for Trc20FeeConfig {
+    fn default() -> Self {
+        Trc20FeeConfig {
+            fee_limit_multiplier_percent: 130,
+            min_fee_limit_sun: 1_000_000,
+            max_fee_limit_sun: 100_000_000,
+            fallback_energy_price_sun: 420,
+        }
+    }
+}
+
+impl Trc20FeeConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.fee_limit_multiplier_percent < 100 {
+            return Err("fee_limit_multiplier_percent must be at least 100".to_string());
+        }
+        if self.min_fee_limit_sun > self.max_fee_limit_sun {
+            return Err("min_fee_limit_sun must not exceed max_fee_limit_sun".to_string());
+        }
+        // The TRON protocol rejects fee limits above 15,000 TRX.
+        if self.max_fee_limit_sun > 15_000_000_000 {
+            return Err("max_fee_limit_sun must not exceed 15000 TRX".to_string());
+        }
+        if self.fallback_energy_price_sun == 0 {
+            return Err("fallback_energy_price_sun must be greater than 0".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone)]
+pub struct Trc20TransferEstimate {
+    pub energy_required: u64,
+    pub bandwidth_required: u64,
+    pub available_energy: u64,
+    pub available_bandwidth: u64,
+    pub energy_price_sun: u64,
+    pub fee_limit_sun: u64,
+}
@@ -0,0 +1,34 @@
+use crate::types::Nat;
+use num_bigint::BigUint;
+use num_traits::Num;
+
+/// Encodes a base58check TRON address as a 32-byte ABI word. The `0x41`
+/// network prefix is dropped so the word matches the EVM address layout.
+pub fn encode_address(address: &str) -> Result<String, String> {
+    let bytes = bs58::decode(address)
+        .with_check(None)
+        .into_vec()
+        .map_err(|e| format!("Invalid TRON address '{}': {}", address, e))?;
+    if bytes.len() != 21 || bytes[0] != 0x41 {
+        return Err(format!("Invalid TRON address '{}'", address));
+    }
+    Ok(format!("{:0>64}", hex::encode(&bytes[1..])))
+}
+
+pub fn encode_uint256(value: &Nat) -> Result<String, String> {
+    let hex = value.0.to_str_radix(16);
+    if hex.len() > 64 {
+        return Err(format!("Value {} does not fit into uint256", value));
+    }
+    Ok(format!("{:0>64}", hex))
+}
+
+pub fn decode_uint256(word: &str) -> Result<Nat, String> {
+    let word = word.trim_start_matches("0x");
+    if word.is_empty() {
+        return Ok(Nat::from(0u64));
+    }
+    BigUint::from_str_radix(word, 16)
+        .map(Nat)
+        .map_err(|e| format!("Failed to decode uint256 '{}': {}", word, e))
+}
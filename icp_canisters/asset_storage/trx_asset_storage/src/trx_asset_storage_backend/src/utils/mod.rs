@@ -1,2 +1,3 @@
+pub mod abi;
 pub mod controller;
 pub mod principal;
\ No newline at end of file
@@ -0,0 +1,343 @@
+use candid::{CandidType, Deserialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+pub type Hash = [u8; 32];
+
+/// A query result with what a client needs to verify it: the system
+/// certificate, only present on query calls, and a CBOR witness from the
+/// canister's `CertifiedTree` revealing the labels the result is hashed under.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct Certified<T> {
+    pub data: T,
+    pub certificate: Option<Vec<u8>>,
+    pub hash_tree: Vec<u8>,
+}
+
+/// The leaf certified for a query result: SHA-256 of its candid encoding.
+pub fn candid_hash<T: CandidType>(value: &T) -> Vec<u8> {
+    let encoded = candid::encode_one(value).expect("Failed to encode certified value");
+    Sha256::digest(&encoded).to_vec()
+}
+
+/// Leaf for an append-only list such as a trade history. Each entry is folded
+/// into the leaf of the list before it, so appending hashes one entry rather
+/// than the whole list. Clients check a list by folding its entries in order,
+/// starting from `list_hash(&[])`.
+pub fn chain_hash<T: CandidType>(prev: &[u8], entry: &T) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(prev);
+    hasher.update(candid_hash(entry));
+    hasher.finalize().to_vec()
+}
+
+pub fn list_hash<T: CandidType>(entries: &[T]) -> Vec<u8> {
+    let empty = Sha256::digest([]).to_vec();
+    entries.iter().fold(empty, |prev, entry| chain_hash(&prev, entry))
+}
+
+/// The IC hash tree that clients check a certificate's `certified_data`
+/// against. A witness reveals some of the leaves and prunes the rest down to
+/// their hashes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HashTree {
+    Empty,
+    Fork(Box<HashTree>, Box<HashTree>),
+    Labeled(Vec<u8>, Box<HashTree>),
+    Leaf(Vec<u8>),
+    Pruned(Hash),
+}
+
+impl HashTree {
+    pub fn digest(&self) -> Hash {
+        match self {
+            HashTree::Empty => domain_hash("ic-hashtree-empty", &[]),
+            HashTree::Fork(left, right) => domain_hash("ic-hashtree-fork", &[&left.digest(), &right.digest()]),
+            HashTree::Labeled(label, tree) => domain_hash("ic-hashtree-labeled", &[label, &tree.digest()]),
+            HashTree::Leaf(value) => domain_hash("ic-hashtree-leaf", &[value]),
+            HashTree::Pruned(hash) => *hash,
+        }
+    }
+
+    /// CBOR encoding as expected by agents, e.g. in ICRC-3's `hash_tree`.
+    pub fn to_cbor(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.write_cbor(&mut out);
+        out
+    }
+
+    fn write_cbor(&self, out: &mut Vec<u8>) {
+        match self {
+            HashTree::Empty => {
+                out.push(0x81);
+                out.push(0);
+            }
+            HashTree::Fork(left, right) => {
+                out.push(0x83);
+                out.push(1);
+                left.write_cbor(out);
+                right.write_cbor(out);
+            }
+            HashTree::Labeled(label, tree) => {
+                out.push(0x83);
+                out.push(2);
+                write_cbor_bytes(label, out);
+                tree.write_cbor(out);
+            }
+            HashTree::Leaf(value) => {
+                out.push(0x82);
+                out.push(3);
+                write_cbor_bytes(value, out);
+            }
+            HashTree::Pruned(hash) => {
+                out.push(0x82);
+                out.push(4);
+                write_cbor_bytes(hash, out);
+            }
+        }
+    }
+}
+
+fn domain_hash(separator: &str, parts: &[&[u8]]) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update([separator.len() as u8]);
+    hasher.update(separator.as_bytes());
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+fn write_cbor_bytes(bytes: &[u8], out: &mut Vec<u8>) {
+    let len = bytes.len();
+    if len < 24 {
+        out.push(0x40 | len as u8);
+    } else if len <= u8::MAX as usize {
+        out.push(0x58);
+        out.push(len as u8);
+    } else if len <= u16::MAX as usize {
+        out.push(0x59);
+        out.extend_from_slice(&(len as u16).to_be_bytes());
+    } else {
+        out.push(0x5a);
+        out.extend_from_slice(&(len as u32).to_be_bytes());
+    }
+    out.extend_from_slice(bytes);
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Node {
+    Leaf(Vec<u8>),
+    Subtree(CertifiedTree),
+}
+
+/// Labeled values that a canister certifies, nested by path. Canisters keep
+/// one in state, update the labels touched by a change, pass `root_hash` to
+/// `set_certified_data` and cut witnesses from it in queries.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CertifiedTree {
+    entries: BTreeMap<Vec<u8>, Node>,
+}
+
+impl CertifiedTree {
+    pub fn insert(&mut self, label: impl AsRef<[u8]>, value: impl Into<Vec<u8>>) {
+        self.entries.insert(label.as_ref().to_vec(), Node::Leaf(value.into()));
+    }
+
+    pub fn insert_subtree(&mut self, label: impl AsRef<[u8]>, tree: CertifiedTree) {
+        self.entries.insert(label.as_ref().to_vec(), Node::Subtree(tree));
+    }
+
+    pub fn get(&self, label: impl AsRef<[u8]>) -> Option<&[u8]> {
+        match self.entries.get(label.as_ref())? {
+            Node::Leaf(value) => Some(value),
+            Node::Subtree(_) => None,
+        }
+    }
+
+    pub fn remove(&mut self, label: impl AsRef<[u8]>) {
+        self.entries.remove(label.as_ref());
+    }
+
+    /// The subtree under `label`, created empty if missing. A leaf under the
+    /// same label is replaced.
+    pub fn subtree_mut(&mut self, label: impl AsRef<[u8]>) -> &mut CertifiedTree {
+        let node = self
+            .entries
+            .entry(label.as_ref().to_vec())
+            .or_insert_with(|| Node::Subtree(CertifiedTree::default()));
+        if let Node::Leaf(_) = node {
+            *node = Node::Subtree(CertifiedTree::default());
+        }
+        match node {
+            Node::Subtree(tree) => tree,
+            Node::Leaf(_) => unreachable!("leaf was just replaced"),
+        }
+    }
+
+    /// Folds `entry` into the list leaf under `label`, see `chain_hash`.
+    pub fn append<T: CandidType>(&mut self, label: impl AsRef<[u8]>, entry: &T) {
+        let prev = match self.get(&label) {
+            Some(prev) => prev.to_vec(),
+            None => list_hash::<T>(&[]),
+        };
+        self.insert(label, chain_hash(&prev, entry));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn root_hash(&self) -> Hash {
+        self.to_hash_tree().digest()
+    }
+
+    pub fn to_hash_tree(&self) -> HashTree {
+        let labeled: Vec<HashTree> = self
+            .entries
+            .iter()
+            .map(|(label, node)| {
+                let tree = match node {
+                    Node::Leaf(value) => HashTree::Leaf(value.clone()),
+                    Node::Subtree(subtree) => subtree.to_hash_tree(),
+                };
+                HashTree::Labeled(label.clone(), Box::new(tree))
+            })
+            .collect();
+        balanced_forks(labeled)
+    }
+
+    /// The tree with only the given paths revealed. A path that stops at a
+    /// subtree reveals all of it; paths that do not exist are left out.
+    pub fn witness(&self, paths: &[&[&[u8]]]) -> HashTree {
+        prune(&self.to_hash_tree(), paths)
+    }
+}
+
+fn balanced_forks(mut trees: Vec<HashTree>) -> HashTree {
+    match trees.len() {
+        0 => HashTree::Empty,
+        1 => trees.pop().expect("one tree"),
+        len => {
+            let right = trees.split_off(len / 2);
+            HashTree::Fork(Box::new(balanced_forks(trees)), Box::new(balanced_forks(right)))
+        }
+    }
+}
+
+fn prune(tree: &HashTree, paths: &[&[&[u8]]]) -> HashTree {
+    match tree {
+        HashTree::Fork(left, right) => match (prune(left, paths), prune(right, paths)) {
+            (HashTree::Pruned(_), HashTree::Pruned(_)) => HashTree::Pruned(tree.digest()),
+            (left, right) => HashTree::Fork(Box::new(left), Box::new(right)),
+        },
+        HashTree::Labeled(label, subtree) => {
+            let rest: Vec<&[&[u8]]> = paths
+                .iter()
+                .filter(|path| path.first().is_some_and(|first| *first == label.as_slice()))
+                .map(|path| &path[1..])
+                .collect();
+            if rest.is_empty() {
+                HashTree::Pruned(tree.digest())
+            } else if rest.iter().any(|path| path.is_empty()) {
+                tree.clone()
+            } else {
+                HashTree::Labeled(label.clone(), Box::new(prune(subtree, &rest)))
+            }
+        }
+        HashTree::Empty => HashTree::Empty,
+        _ => HashTree::Pruned(tree.digest()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> CertifiedTree {
+        let mut tree = CertifiedTree::default();
+        tree.insert("a", vec![1]);
+        tree.insert("b", vec![2]);
+        let mut pools = CertifiedTree::default();
+        pools.insert("x", vec![3]);
+        pools.insert("y", vec![4]);
+        tree.insert_subtree("pools", pools);
+        tree
+    }
+
+    #[test]
+    fn hash_tree_encodes_as_cbor() {
+        let labeled = HashTree::Labeled(b"a".to_vec(), Box::new(HashTree::Leaf(b"x".to_vec())));
+        assert_eq!(labeled.to_cbor(), vec![0x83, 0x02, 0x41, b'a', 0x82, 0x03, 0x41, b'x']);
+
+        let fork = HashTree::Fork(Box::new(HashTree::Empty), Box::new(HashTree::Pruned([7; 32])));
+        let mut expected = vec![0x83, 0x01, 0x81, 0x00, 0x82, 0x04, 0x58, 0x20];
+        expected.extend_from_slice(&[7; 32]);
+        assert_eq!(fork.to_cbor(), expected);
+
+        let long = HashTree::Leaf(vec![0; 300]).to_cbor();
+        assert_eq!(&long[..5], &[0x82, 0x03, 0x59, 0x01, 0x2c]);
+        assert_eq!(long.len(), 305);
+    }
+
+    #[test]
+    fn witness_keeps_the_root_hash() {
+        let tree = tree();
+        let root = tree.root_hash();
+        let paths: [&[&[u8]]; 4] = [&[b"a"], &[b"pools"], &[b"pools", b"y"], &[b"missing"]];
+        for path in paths {
+            assert_eq!(tree.witness(&[path]).digest(), root);
+        }
+    }
+
+    #[test]
+    fn witness_reveals_only_the_requested_paths() {
+        fn leaves(tree: &HashTree, out: &mut Vec<Vec<u8>>) {
+            match tree {
+                HashTree::Fork(left, right) => {
+                    leaves(left, out);
+                    leaves(right, out);
+                }
+                HashTree::Labeled(_, tree) => leaves(tree, out),
+                HashTree::Leaf(value) => out.push(value.clone()),
+                HashTree::Empty | HashTree::Pruned(_) => {}
+            }
+        }
+        let revealed = |paths: &[&[&[u8]]]| {
+            let mut out = Vec::new();
+            leaves(&tree().witness(paths), &mut out);
+            out
+        };
+
+        assert_eq!(revealed(&[&[b"a"]]), vec![vec![1]]);
+        assert_eq!(revealed(&[&[b"pools", b"y"]]), vec![vec![4]]);
+        assert_eq!(revealed(&[&[b"pools"]]), vec![vec![3], vec![4]]);
+        assert!(revealed(&[&[b"missing"]]).is_empty());
+        assert_eq!(tree().witness(&[&[b"missing"]]), HashTree::Pruned(tree().root_hash()));
+    }
+
+    #[test]
+    fn appending_matches_hashing_the_whole_list() {
+        let entries = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let mut tree = CertifiedTree::default();
+        for entry in &entries {
+            tree.append("history", entry);
+        }
+        assert_eq!(tree.get("history"), Some(list_hash(&entries).as_slice()));
+        assert_ne!(list_hash(&entries[..2]), list_hash(&entries));
+    }
+
+    #[test]
+    fn subtree_mut_replaces_a_leaf() {
+        let mut tree = CertifiedTree::default();
+        tree.insert("pools", vec![1]);
+        tree.subtree_mut("pools").insert("a", vec![2]);
+        tree.subtree_mut("pools").remove("b");
+
+        let mut expected = CertifiedTree::default();
+        let mut pools = CertifiedTree::default();
+        pools.insert("a", vec![2]);
+        expected.insert_subtree("pools", pools);
+        assert_eq!(tree.root_hash(), expected.root_hash());
+    }
+}
@@ -0,0 +1,365 @@
+use crate::errors::ApiError;
+use candid::{CandidType, Principal};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Nodes on the application subnets the canisters run on.
+pub const SUBNET_SIZE: u128 = 13;
+/// Nodes on the fiduciary subnet that hosts the EVM RPC canister and the
+/// production threshold keys.
+pub const FIDUCIARY_SUBNET_SIZE: u128 = 34;
+/// Response size the IC charges for when a request leaves
+/// `max_response_bytes` unset.
+pub const DEFAULT_MAX_RESPONSE_BYTES: u64 = 2_000_000;
+
+/// Sizes assumed for a JSON-RPC read when estimating an action before any of
+/// its requests are built. The response matches the limit `post_json_rpc`
+/// sets.
+pub const JSON_RPC_REQUEST_BYTES: u64 = 1_024;
+pub const JSON_RPC_RESPONSE_BYTES: u64 = 8_192;
+
+// The EVM RPC canister adds the provider URL and headers to the payload and
+// holds back a per-node collateral, which it refunds with anything unused.
+const EVM_RPC_REQUEST_OVERHEAD_BYTES: u64 = 1_024;
+const EVM_RPC_COLLATERAL_PER_NODE: u128 = 10_000_000;
+
+const PRODUCTION_SIGNATURE_CYCLES: u128 = 26_153_846_153;
+const TEST_SIGNATURE_CYCLES: u128 = 10_000_000_000;
+
+const DEFAULT_MAX_CYCLES_PER_CALLER: u128 = 200_000_000_000;
+const DEFAULT_WINDOW_SECONDS: u64 = 60 * 60;
+
+/// HTTPS outcall fee on a subnet of `subnet_size` nodes:
+/// `(3M + 60K·n)·n + 400·n` per request byte `+ 800·n` per response byte.
+pub fn http_request_cost_on(subnet_size: u128, request_bytes: u64, max_response_bytes: Option<u64>) -> u128 {
+    let n = subnet_size;
+    let response_bytes = max_response_bytes.unwrap_or(DEFAULT_MAX_RESPONSE_BYTES) as u128;
+    (3_000_000 + 60_000 * n) * n + 400 * n * request_bytes as u128 + 800 * n * response_bytes
+}
+
+pub fn http_request_cost(request_bytes: u64, max_response_bytes: Option<u64>) -> u128 {
+    http_request_cost_on(SUBNET_SIZE, request_bytes, max_response_bytes)
+}
+
+/// Bytes the IC counts as the request: URL, headers, body and the transform
+/// function's name and context.
+pub fn request_bytes<'a>(
+    url: &str,
+    headers: impl IntoIterator<Item = (&'a str, &'a str)>,
+    body: Option<&[u8]>,
+    transform: Option<(&str, &[u8])>,
+) -> u64 {
+    let headers: usize = headers.into_iter().map(|(name, value)| name.len() + value.len()).sum();
+    let body = body.map_or(0, <[u8]>::len);
+    let transform = transform.map_or(0, |(name, context)| name.len() + context.len());
+    (url.len() + headers + body + transform) as u64
+}
+
+/// Cycles to attach to an EVM RPC canister request sent to `providers`
+/// providers.
+pub fn evm_rpc_request_cost(payload_bytes: u64, max_response_bytes: u64, providers: u128) -> u128 {
+    let per_provider = http_request_cost_on(
+        FIDUCIARY_SUBNET_SIZE,
+        payload_bytes + EVM_RPC_REQUEST_OVERHEAD_BYTES,
+        Some(max_response_bytes),
+    );
+    providers * (per_provider + EVM_RPC_COLLATERAL_PER_NODE * FIDUCIARY_SUBNET_SIZE)
+}
+
+/// `calls` JSON-RPC reads of the assumed size from this subnet.
+pub fn json_rpc_cost(calls: u32) -> u128 {
+    calls as u128 * http_request_cost(JSON_RPC_REQUEST_BYTES, Some(JSON_RPC_RESPONSE_BYTES))
+}
+
+/// Fee for one threshold ECDSA or Schnorr signature. `key_1` lives on the
+/// fiduciary subnet; the test keys are priced for a 13-node subnet.
+pub fn signature_cost(key_name: &str) -> u128 {
+    match key_name {
+        "key_1" => PRODUCTION_SIGNATURE_CYCLES,
+        _ => TEST_SIGNATURE_CYCLES,
+    }
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CyclesBudget {
+    /// Most cycles the actions of one caller may cost in a window. `None`
+    /// means no limit.
+    pub max_cycles_per_caller: Option<u128>,
+    /// Most cycles the actions of all callers together may cost in a window,
+    /// so spreading calls over many principals cannot drain the canister.
+    /// Once reached it refuses everyone, so it is off (`None`) unless a
+    /// controller sets it.
+    pub max_cycles_per_window: Option<u128>,
+    pub window_seconds: u64,
+    /// Callers without a limit of their own, such as a router that forwards
+    /// calls and enforces its own budget. They still count towards
+    /// `max_cycles_per_window`.
+    pub exempt: Vec<Principal>,
+}
+
+impl Default for CyclesBudget {
+    fn default() -> Self {
+        Self {
+            max_cycles_per_caller: Some(DEFAULT_MAX_CYCLES_PER_CALLER),
+            max_cycles_per_window: None,
+            window_seconds: DEFAULT_WINDOW_SECONDS,
+            exempt: Vec::new(),
+        }
+    }
+}
+
+impl CyclesBudget {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.window_seconds == 0 {
+            return Err("window_seconds must be greater than 0".to_string());
+        }
+        Ok(())
+    }
+
+    fn window_ns(&self) -> u64 {
+        self.window_seconds.saturating_mul(1_000_000_000)
+    }
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+struct CallerSpend {
+    window_start: u64,
+    cycles: u128,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ActionCycles {
+    pub action: String,
+    pub count: u64,
+    pub cycles: u128,
+    /// Calls refused because the caller, or all callers together, had used
+    /// up the budget.
+    pub rejected: u64,
+}
+
+/// Cycles attributed to each kind of action, and what each caller and all
+/// callers together have spent in the current window. Amounts are estimates
+/// made before the action runs, so a call that fails half way still counts
+/// in full.
+#[derive(CandidType, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CyclesLedger {
+    budget: CyclesBudget,
+    callers: BTreeMap<Principal, CallerSpend>,
+    total: CallerSpend,
+    actions: BTreeMap<String, ActionCycles>,
+}
+
+impl CyclesLedger {
+    pub fn budget(&self) -> &CyclesBudget {
+        &self.budget
+    }
+
+    pub fn set_budget(&mut self, budget: CyclesBudget) -> Result<(), String> {
+        budget.validate()?;
+        self.budget = budget;
+        Ok(())
+    }
+
+    /// Lifts the per-caller limit for `caller`, e.g. a router whose calls
+    /// stand for many users.
+    pub fn exempt(&mut self, caller: Principal) {
+        if !self.budget.exempt.contains(&caller) {
+            self.budget.exempt.push(caller);
+        }
+    }
+
+    /// Attributes `cycles` to `action` and to `caller`, refusing the action
+    /// if it would take the caller, or all callers together, over the budget
+    /// for the window. The anonymous principal is anyone, so it is refused
+    /// outright.
+    pub fn charge(&mut self, caller: Principal, action: &str, cycles: u128, now: u64) -> Result<(), ApiError> {
+        if caller == Principal::anonymous() {
+            self.action(action).rejected += 1;
+            return Err(ApiError::unauthorized(format!("{} requires an authenticated caller", action)));
+        }
+        self.prune(now);
+
+        let window_ns = self.budget.window_ns();
+        let total_spent = self.total.cycles.saturating_add(cycles);
+        if self.budget.max_cycles_per_window.is_some_and(|max| total_spent > max) {
+            let retry_in_seconds = self.total.window_start.saturating_add(window_ns).saturating_sub(now) / 1_000_000_000;
+            self.action(action).rejected += 1;
+            return Err(ApiError::CyclesBudgetExceeded { action: action.to_string(), retry_in_seconds });
+        }
+
+        if !self.budget.exempt.contains(&caller) {
+            let spend = self.callers.entry(caller).or_insert(CallerSpend { window_start: now, cycles: 0 });
+            let spent = spend.cycles.saturating_add(cycles);
+            if self.budget.max_cycles_per_caller.is_some_and(|max| spent > max) {
+                let retry_in_seconds = spend.window_start.saturating_add(window_ns).saturating_sub(now) / 1_000_000_000;
+                self.action(action).rejected += 1;
+                return Err(ApiError::CyclesBudgetExceeded { action: action.to_string(), retry_in_seconds });
+            }
+            spend.cycles = spent;
+        }
+
+        self.total.cycles = total_spent;
+        self.record(action, cycles);
+        Ok(())
+    }
+
+    /// Attributes `cycles` to `action` without charging anyone, for work the
+    /// canister starts itself.
+    pub fn record(&mut self, action: &str, cycles: u128) {
+        let entry = self.action(action);
+        entry.count += 1;
+        entry.cycles = entry.cycles.saturating_add(cycles);
+    }
+
+    /// What `caller` has spent in their current window.
+    pub fn caller_spend(&self, caller: &Principal, now: u64) -> u128 {
+        self.callers
+            .get(caller)
+            .filter(|spend| now < spend.window_start.saturating_add(self.budget.window_ns()))
+            .map_or(0, |spend| spend.cycles)
+    }
+
+    /// What all callers together have spent in the current window.
+    pub fn window_spend(&self, now: u64) -> u128 {
+        if now < self.total.window_start.saturating_add(self.budget.window_ns()) {
+            self.total.cycles
+        } else {
+            0
+        }
+    }
+
+    pub fn actions(&self) -> impl Iterator<Item = &ActionCycles> {
+        self.actions.values()
+    }
+
+    fn action(&mut self, action: &str) -> &mut ActionCycles {
+        self.actions.entry(action.to_string()).or_insert_with(|| ActionCycles {
+            action: action.to_string(),
+            ..Default::default()
+        })
+    }
+
+    /// Drops callers whose window has ended so the map only holds recent
+    /// callers, and starts a new overall window once the last one ended.
+    fn prune(&mut self, now: u64) {
+        let window_ns = self.budget.window_ns();
+        self.callers.retain(|_, spend| now < spend.window_start.saturating_add(window_ns));
+        if now >= self.total.window_start.saturating_add(window_ns) {
+            self.total = CallerSpend { window_start: now, cycles: 0 };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR_NS: u64 = 60 * 60 * 1_000_000_000;
+
+    fn ledger(max_cycles_per_caller: u128) -> CyclesLedger {
+        let mut ledger = CyclesLedger::default();
+        ledger
+            .set_budget(CyclesBudget {
+                max_cycles_per_caller: Some(max_cycles_per_caller),
+                max_cycles_per_window: Some(3 * max_cycles_per_caller),
+                window_seconds: 60 * 60,
+                exempt: vec![Principal::management_canister()],
+            })
+            .unwrap();
+        ledger
+    }
+
+    // A single 0x04 byte is the anonymous principal.
+    fn caller(id: u8) -> Principal {
+        Principal::from_slice(&[id, 1])
+    }
+
+    #[test]
+    fn caller_is_refused_over_budget_until_the_window_ends() {
+        let mut ledger = ledger(100);
+        ledger.charge(caller(1), "swap", 60, 0).unwrap();
+        ledger.charge(caller(2), "swap", 60, 0).unwrap();
+
+        let err = ledger.charge(caller(1), "swap", 60, HOUR_NS / 2).unwrap_err();
+        assert_eq!(
+            err,
+            ApiError::CyclesBudgetExceeded {
+                action: "swap".to_string(),
+                retry_in_seconds: 30 * 60,
+            }
+        );
+        assert_eq!(ledger.caller_spend(&caller(1), HOUR_NS / 2), 60);
+
+        ledger.charge(caller(1), "swap", 60, HOUR_NS).unwrap();
+        assert_eq!(ledger.caller_spend(&caller(1), HOUR_NS), 60);
+
+        let swap = ledger.actions().find(|a| a.action == "swap").unwrap();
+        assert_eq!((swap.count, swap.cycles, swap.rejected), (3, 180, 1));
+    }
+
+    #[test]
+    fn all_callers_share_the_window_cap() {
+        let mut ledger = ledger(100);
+        for id in 1..=3 {
+            ledger.charge(caller(id), "swap", 100, 0).unwrap();
+        }
+        assert_eq!(ledger.window_spend(0), 300);
+
+        let err = ledger.charge(caller(4), "swap", 1, HOUR_NS / 4).unwrap_err();
+        assert_eq!(
+            err,
+            ApiError::CyclesBudgetExceeded {
+                action: "swap".to_string(),
+                retry_in_seconds: 45 * 60,
+            }
+        );
+        assert_eq!(ledger.caller_spend(&caller(4), HOUR_NS / 4), 0);
+
+        ledger.charge(caller(4), "swap", 1, HOUR_NS).unwrap();
+        assert_eq!(ledger.window_spend(HOUR_NS), 1);
+    }
+
+    #[test]
+    fn exempt_callers_only_count_towards_the_window_cap() {
+        let mut ledger = ledger(100);
+        for _ in 0..5 {
+            ledger.charge(Principal::management_canister(), "swap", 60, 0).unwrap();
+        }
+        assert_eq!(ledger.caller_spend(&Principal::management_canister(), 0), 0);
+        assert!(ledger.charge(Principal::management_canister(), "swap", 60, 0).is_err());
+        assert_eq!(ledger.actions().next().unwrap().cycles, 300);
+    }
+
+    #[test]
+    fn router_forwarded_swaps_are_not_limited_by_default() {
+        let router = caller(9);
+        let mut ledger = CyclesLedger::default();
+        ledger.exempt(router);
+        ledger.exempt(router);
+        assert_eq!(ledger.budget().exempt, vec![router]);
+
+        for _ in 0..50 {
+            ledger.charge(router, "swap", DEFAULT_MAX_CYCLES_PER_CALLER, 0).unwrap();
+        }
+        assert!(ledger.charge(caller(1), "swap", DEFAULT_MAX_CYCLES_PER_CALLER + 1, 0).is_err());
+    }
+
+    #[test]
+    fn anonymous_callers_are_refused() {
+        let mut ledger = CyclesLedger::default();
+        let err = ledger.charge(Principal::anonymous(), "swap", 1, 0).unwrap_err();
+        assert!(matches!(err, ApiError::Unauthorized { .. }));
+        assert_eq!(ledger.window_spend(0), 0);
+        assert_eq!(ledger.actions().next().unwrap().rejected, 1);
+    }
+
+    #[test]
+    fn budget_needs_a_window() {
+        let mut ledger = CyclesLedger::default();
+        let budget = CyclesBudget {
+            window_seconds: 0,
+            ..Default::default()
+        };
+        assert!(ledger.set_budget(budget).is_err());
+    }
+}
@@ -0,0 +1,382 @@
+use candid::CandidType;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum DepositStatus {
+    /// Claimed by a call that is still verifying the deposit.
+    Verifying,
+    /// Verified; the payout or refund has been started.
+    Settling,
+    Processed,
+    /// Settlement failed after funds may have moved. The key stays claimed
+    /// until someone checks what happened on chain.
+    Failed,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DepositRecord {
+    pub status: DepositStatus,
+    pub claimed_at: u64,
+    pub updated_at: u64,
+}
+
+/// A `Verifying` claim older than this belongs to a call that trapped or
+/// never came back, and may be taken over by a new claim.
+pub const VERIFYING_TIMEOUT_NS: u64 = 30 * 60 * 1_000_000_000;
+
+/// Deposits keyed by (chain, tx hash, index). A key is claimed synchronously
+/// before the first await, so two concurrent calls for the same deposit can
+/// never both get past verification.
+#[derive(CandidType, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DepositRegistry {
+    deposits: BTreeMap<String, DepositRecord>,
+}
+
+/// `index` picks one deposit out of a transaction that carries several: the
+/// log index of an EVM token transfer or the output index of a Bitcoin
+/// payment. `None` keys the whole transaction, for flows that credit at most
+/// one deposit per transaction, and conflicts with every index of it.
+///
+/// EVM and Bitcoin hashes are hex and compared case-insensitively; anything
+/// else (e.g. base58 Solana signatures) is kept as is.
+pub fn deposit_key(chain: &str, tx_hash: &str, index: Option<u32>) -> String {
+    let hash = tx_hash.trim();
+    let digits = hash.strip_prefix("0x").or_else(|| hash.strip_prefix("0X")).unwrap_or(hash);
+    let hash = if !digits.is_empty() && digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        hash.to_ascii_lowercase()
+    } else {
+        hash.to_string()
+    };
+
+    match index {
+        Some(index) => format!("{}:{}:{}", chain.to_ascii_lowercase(), hash, index),
+        None => format!("{}:{}", chain.to_ascii_lowercase(), hash),
+    }
+}
+
+/// The whole-transaction key a deposit key belongs to.
+pub fn transaction_key(key: &str) -> &str {
+    match key.match_indices(':').nth(1) {
+        Some((at, _)) => &key[..at],
+        None => key,
+    }
+}
+
+/// Proof that a call holds the claim on `key`. A stale claim that was taken
+/// over keeps its old `claimed_at`, so it can no longer settle or release the
+/// new one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DepositClaim {
+    pub key: String,
+    pub claimed_at: u64,
+}
+
+impl DepositRegistry {
+    pub fn get(&self, key: &str) -> Option<&DepositRecord> {
+        self.deposits.get(key)
+    }
+
+    pub fn deposits(&self) -> impl Iterator<Item = (&String, &DepositRecord)> {
+        self.deposits.iter()
+    }
+
+    pub fn claim(&mut self, key: &str, now: u64) -> Result<DepositClaim, String> {
+        if let Some((held, record)) = self.overlapping(key).find(|(_, record)| {
+            record.status != DepositStatus::Verifying
+                || now.saturating_sub(record.claimed_at) <= VERIFYING_TIMEOUT_NS
+        }) {
+            return Err(claimed_error(held, record));
+        }
+
+        // Anything left overlapping is a stale claim. Dropping it means its
+        // call can no longer settle, same as when the key itself is taken over.
+        let stale: Vec<String> = self.overlapping(key).map(|(held, _)| held.clone()).collect();
+        for held in stale {
+            self.deposits.remove(&held);
+        }
+
+        self.deposits.insert(
+            key.to_string(),
+            DepositRecord {
+                status: DepositStatus::Verifying,
+                claimed_at: now,
+                updated_at: now,
+            },
+        );
+        Ok(DepositClaim {
+            key: key.to_string(),
+            claimed_at: now,
+        })
+    }
+
+    /// Called right before the payout or refund. From here on a failure no
+    /// longer releases the key. Fails if the claim timed out and was taken
+    /// over, in which case nothing may be paid out.
+    pub fn start_settlement(&mut self, claim: &DepositClaim, now: u64) -> Result<(), String> {
+        match self.owned_record(claim) {
+            Some(record) if record.status == DepositStatus::Verifying => {
+                record.status = DepositStatus::Settling;
+                record.updated_at = now;
+                Ok(())
+            }
+            _ => Err(format!("Claim on deposit {} expired before settlement", claim.key)),
+        }
+    }
+
+    /// Ends a claim. Failed verifications are released so the deposit can be
+    /// submitted again; failed settlements are kept as `Failed`.
+    pub fn finish(&mut self, claim: &DepositClaim, succeeded: bool, now: u64) {
+        let Some(record) = self.owned_record(claim) else {
+            return;
+        };
+
+        if succeeded {
+            record.status = DepositStatus::Processed;
+            record.updated_at = now;
+        } else if record.status == DepositStatus::Verifying {
+            self.deposits.remove(&claim.key);
+        } else {
+            record.status = DepositStatus::Failed;
+            record.updated_at = now;
+        }
+    }
+
+    /// Drops a claim regardless of its status, e.g. after a `Failed` deposit
+    /// was checked by hand. Returns false if the key was not claimed.
+    pub fn release(&mut self, key: &str) -> bool {
+        self.deposits.remove(key).is_some()
+    }
+
+    /// Every record of the transaction `key` belongs to: its whole-transaction
+    /// key and all of its indexes. Any change made through `key` stays within
+    /// these, so a copy of the registry only has to resync them.
+    pub fn transaction_deposits<'a>(&'a self, key: &str) -> impl Iterator<Item = (&'a String, &'a DepositRecord)> + 'a {
+        let transaction = transaction_key(key);
+        let prefix = format!("{}:", transaction);
+        self.deposits.get_key_value(transaction).into_iter().chain(
+            self.deposits
+                .range(prefix.clone()..)
+                .take_while(move |(held, _)| held.starts_with(&prefix)),
+        )
+    }
+
+    /// Records for `key` itself and for the same deposit keyed the other way:
+    /// a whole-transaction key overlaps every index of that transaction.
+    fn overlapping<'a>(&'a self, key: &'a str) -> impl Iterator<Item = (&'a String, &'a DepositRecord)> + 'a {
+        let transaction = transaction_key(key);
+        let whole = key == transaction;
+        self.transaction_deposits(key)
+            .filter(move |(held, _)| whole || held.as_str() == transaction || held.as_str() == key)
+    }
+
+    fn owned_record(&mut self, claim: &DepositClaim) -> Option<&mut DepositRecord> {
+        self.deposits
+            .get_mut(&claim.key)
+            .filter(|record| record.claimed_at == claim.claimed_at)
+    }
+}
+
+impl FromIterator<(String, DepositRecord)> for DepositRegistry {
+    fn from_iter<I: IntoIterator<Item = (String, DepositRecord)>>(records: I) -> Self {
+        DepositRegistry {
+            deposits: records.into_iter().collect(),
+        }
+    }
+}
+
+fn claimed_error(key: &str, record: &DepositRecord) -> String {
+    match record.status {
+        DepositStatus::Verifying | DepositStatus::Settling => {
+            format!("Deposit {} is already being processed", key)
+        }
+        DepositStatus::Processed => format!("Deposit {} already processed", key),
+        DepositStatus::Failed => format!("Deposit {} failed during settlement and needs review", key),
+    }
+}
+
+/// Whether a settlement ended with the deposit processed, so
+/// `with_deposit_claim` knows how to finish the claim.
+pub trait Settlement {
+    fn succeeded(&self) -> bool;
+}
+
+impl<T, E> Settlement for Result<T, E> {
+    fn succeeded(&self) -> bool {
+        self.is_ok()
+    }
+}
+
+#[cfg(feature = "canister")]
+pub use canister::{with_deposit_claim, ClaimedDeposit, DepositCell};
+
+#[cfg(feature = "canister")]
+mod canister {
+    use super::{DepositClaim, DepositRegistry, Settlement};
+    use std::cell::RefCell;
+    use std::future::Future;
+    use std::thread::LocalKey;
+
+    pub type DepositCell = LocalKey<RefCell<DepositRegistry>>;
+
+    /// A claim held inside `with_deposit_claim`, tied to the registry it was
+    /// taken from.
+    #[derive(Clone)]
+    pub struct ClaimedDeposit {
+        registry: &'static DepositCell,
+        claim: DepositClaim,
+    }
+
+    impl ClaimedDeposit {
+        pub fn key(&self) -> &str {
+            &self.claim.key
+        }
+
+        /// See `DepositRegistry::start_settlement`. Call right before the
+        /// payout or refund.
+        pub fn start_settlement(&self) -> Result<(), String> {
+            self.registry
+                .with(|d| d.borrow_mut().start_settlement(&self.claim, ic_cdk::api::time()))
+        }
+    }
+
+    /// Claims `key`, runs `settle` while the claim is held and finishes the
+    /// claim with its outcome. Fails without running `settle` if the deposit
+    /// is already claimed.
+    pub async fn with_deposit_claim<R, F, Fut>(
+        registry: &'static DepositCell,
+        key: &str,
+        settle: F,
+    ) -> Result<R, String>
+    where
+        R: Settlement,
+        F: FnOnce(ClaimedDeposit) -> Fut,
+        Fut: Future<Output = R>,
+    {
+        let claim = registry.with(|d| d.borrow_mut().claim(key, ic_cdk::api::time()))?;
+        let outcome = settle(ClaimedDeposit { registry, claim: claim.clone() }).await;
+        registry.with(|d| d.borrow_mut().finish(&claim, outcome.succeeded(), ic_cdk::api::time()));
+        Ok(outcome)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "ethereum:0xaa";
+
+    #[test]
+    fn hex_hashes_are_compared_case_insensitively() {
+        assert_eq!(deposit_key("Ethereum", " 0XAbC ", None), "ethereum:0xabc");
+        assert_eq!(deposit_key("solana", "5VERv8NMvzbJ", None), "solana:5VERv8NMvzbJ");
+        assert_eq!(deposit_key("bitcoin", "AB", Some(3)), "bitcoin:ab:3");
+    }
+
+    #[test]
+    fn indexes_of_one_transaction_are_claimed_separately() {
+        let mut registry = DepositRegistry::default();
+        let first = deposit_key("bitcoin", "ab", Some(0));
+        let second = deposit_key("bitcoin", "ab", Some(1));
+        registry.claim(&first, 0).unwrap();
+        assert!(registry.claim(&first, 1).is_err());
+        assert!(registry.claim(&second, 1).is_ok());
+        assert!(registry.claim(&deposit_key("bitcoin", "abc", Some(0)), 1).is_ok());
+    }
+
+    #[test]
+    fn whole_transaction_keys_overlap_every_index() {
+        let mut registry = DepositRegistry::default();
+        let whole = registry.claim("bitcoin:ab", 0).unwrap();
+        registry.finish(&whole, true, 1);
+        let err = registry.claim("bitcoin:ab:0", 2).unwrap_err();
+        assert!(err.contains("bitcoin:ab already processed"));
+
+        registry.claim("bitcoin:cd:1", 0).unwrap();
+        assert!(registry.claim("bitcoin:cd", 1).is_err());
+        let fresh = registry.claim("bitcoin:cd", VERIFYING_TIMEOUT_NS + 1).unwrap();
+        assert!(registry.get("bitcoin:cd:1").is_none());
+        assert_eq!(registry.get(&fresh.key).unwrap().status, DepositStatus::Verifying);
+    }
+
+    #[test]
+    fn transaction_deposits_cover_every_index_and_nothing_else() {
+        let mut registry = DepositRegistry::default();
+        for key in ["bitcoin:ab", "bitcoin:ab:0", "bitcoin:ab:7", "bitcoin:ab0", "bitcoin:abc:1"] {
+            registry.deposits.insert(key.to_string(), processed_record());
+        }
+
+        let keys: Vec<&str> = registry.transaction_deposits("bitcoin:ab:7").map(|(k, _)| k.as_str()).collect();
+        assert_eq!(keys, ["bitcoin:ab", "bitcoin:ab:0", "bitcoin:ab:7"]);
+
+        let copy: DepositRegistry = registry.deposits().map(|(k, r)| (k.clone(), r.clone())).collect();
+        assert_eq!(copy, registry);
+    }
+
+    fn processed_record() -> DepositRecord {
+        DepositRecord {
+            status: DepositStatus::Processed,
+            claimed_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    #[test]
+    fn processed_deposit_cannot_be_claimed_again() {
+        let mut registry = DepositRegistry::default();
+        let claim = registry.claim(KEY, 0).unwrap();
+        assert!(registry.claim(KEY, 1).is_err());
+
+        registry.start_settlement(&claim, 1).unwrap();
+        registry.finish(&claim, true, 2);
+        let err = registry.claim(KEY, 10 * VERIFYING_TIMEOUT_NS).unwrap_err();
+        assert!(err.contains("already processed"));
+    }
+
+    #[test]
+    fn failed_verification_releases_the_key() {
+        let mut registry = DepositRegistry::default();
+        let claim = registry.claim(KEY, 0).unwrap();
+        registry.finish(&claim, false, 1);
+        assert!(registry.get(KEY).is_none());
+        assert!(registry.claim(KEY, 2).is_ok());
+    }
+
+    #[test]
+    fn failed_settlement_keeps_the_key() {
+        let mut registry = DepositRegistry::default();
+        let claim = registry.claim(KEY, 0).unwrap();
+        registry.start_settlement(&claim, 1).unwrap();
+        registry.finish(&claim, false, 2);
+
+        assert_eq!(registry.get(KEY).unwrap().status, DepositStatus::Failed);
+        assert!(registry.claim(KEY, 10 * VERIFYING_TIMEOUT_NS).is_err());
+    }
+
+    #[test]
+    fn stale_verifying_claim_is_taken_over() {
+        let mut registry = DepositRegistry::default();
+        let stale = registry.claim(KEY, 0).unwrap();
+        assert!(registry.claim(KEY, VERIFYING_TIMEOUT_NS).is_err());
+
+        let fresh = registry.claim(KEY, VERIFYING_TIMEOUT_NS + 1).unwrap();
+        assert!(registry.start_settlement(&stale, VERIFYING_TIMEOUT_NS + 2).is_err());
+        registry.finish(&stale, false, VERIFYING_TIMEOUT_NS + 2);
+        assert_eq!(registry.get(KEY).unwrap().claimed_at, fresh.claimed_at);
+
+        registry.start_settlement(&fresh, VERIFYING_TIMEOUT_NS + 3).unwrap();
+        registry.finish(&fresh, true, VERIFYING_TIMEOUT_NS + 4);
+        assert_eq!(registry.get(KEY).unwrap().status, DepositStatus::Processed);
+    }
+
+    #[test]
+    fn settling_claim_never_times_out() {
+        let mut registry = DepositRegistry::default();
+        let claim = registry.claim(KEY, 0).unwrap();
+        registry.start_settlement(&claim, 1).unwrap();
+        assert!(registry.claim(KEY, 10 * VERIFYING_TIMEOUT_NS).is_err());
+
+        assert!(registry.release(KEY));
+        assert!(registry.claim(KEY, 10 * VERIFYING_TIMEOUT_NS).is_ok());
+    }
+}
@@ -0,0 +1,130 @@
+use candid::{CandidType, Principal};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Error returned by the swap, buy, lending and liquidity endpoints.
+///
+/// Variants carry the fields a client needs to decide whether to retry,
+/// refund or show a message. Amounts are decimal strings in the token's
+/// display units, like the rest of the public API. Failures without a
+/// dedicated variant are reported as `Other`.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub enum ApiError {
+    /// The caller may not perform the action, or a signature did not match.
+    Unauthorized { reason: String },
+    /// An argument failed validation before anything was attempted.
+    InvalidArgument { field: String, reason: String },
+    /// The transaction, lend or withdrawal was settled by an earlier call.
+    AlreadyProcessed { id: String },
+    NotFound { id: String },
+    /// A deposit was found but has not confirmed or does not match the
+    /// expected transfer. Retrying once it confirms may succeed.
+    DepositInvalid { tx_hash: String, reason: String },
+    InsufficientLiquidity { token: String, available: String, required: String },
+    SlippageExceeded { expected: String, actual: String },
+    PriceFeedUnavailable { feed: String, reason: String },
+    /// A ledger or on-chain transfer was rejected; the funds did not move.
+    TransferFailed { ledger_error: String },
+    /// No RPC provider for the chain answered. Safe to retry.
+    ChainUnavailable { chain: String, reason: String },
+    /// The caller's cycles budget for the window is used up.
+    CyclesBudgetExceeded { action: String, retry_in_seconds: u64 },
+    /// A call to another canister was rejected.
+    CallRejected { canister: Principal, reject_code: u32, message: String },
+    Other { message: String },
+}
+
+impl ApiError {
+    pub fn unauthorized(reason: impl Into<String>) -> Self {
+        Self::Unauthorized { reason: reason.into() }
+    }
+
+    pub fn invalid_argument(field: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self::InvalidArgument { field: field.into(), reason: reason.into() }
+    }
+
+    pub fn already_processed(id: impl Into<String>) -> Self {
+        Self::AlreadyProcessed { id: id.into() }
+    }
+
+    pub fn not_found(id: impl Into<String>) -> Self {
+        Self::NotFound { id: id.into() }
+    }
+
+    pub fn deposit_invalid(tx_hash: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self::DepositInvalid { tx_hash: tx_hash.into(), reason: reason.into() }
+    }
+
+    pub fn price_feed_unavailable(feed: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self::PriceFeedUnavailable { feed: feed.into(), reason: reason.into() }
+    }
+
+    pub fn transfer_failed(ledger_error: impl Into<String>) -> Self {
+        Self::TransferFailed { ledger_error: ledger_error.into() }
+    }
+
+    pub fn chain_unavailable(chain: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self::ChainUnavailable { chain: chain.into(), reason: reason.into() }
+    }
+
+    pub fn call_rejected(canister: Principal, reject_code: u32, message: impl Into<String>) -> Self {
+        Self::CallRejected { canister, reject_code, message: message.into() }
+    }
+
+    /// Whether the same call may succeed later without any change by the
+    /// caller.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::DepositInvalid { .. }
+                | Self::PriceFeedUnavailable { .. }
+                | Self::ChainUnavailable { .. }
+                | Self::CyclesBudgetExceeded { .. }
+                | Self::CallRejected { .. }
+        )
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unauthorized { reason } => write!(f, "Unauthorized: {}", reason),
+            Self::InvalidArgument { field, reason } => write!(f, "Invalid {}: {}", field, reason),
+            Self::AlreadyProcessed { id } => write!(f, "{} has already been processed", id),
+            Self::NotFound { id } => write!(f, "{} not found", id),
+            Self::DepositInvalid { tx_hash, reason } => write!(f, "Deposit {} is invalid: {}", tx_hash, reason),
+            Self::InsufficientLiquidity { token, available, required } => write!(
+                f,
+                "Insufficient {} liquidity: {} available, {} required",
+                token, available, required
+            ),
+            Self::SlippageExceeded { expected, actual } => {
+                write!(f, "Slippage exceeded: expected {}, got {}", expected, actual)
+            }
+            Self::PriceFeedUnavailable { feed, reason } => write!(f, "Price feed {} unavailable: {}", feed, reason),
+            Self::TransferFailed { ledger_error } => write!(f, "Transfer failed: {}", ledger_error),
+            Self::ChainUnavailable { chain, reason } => write!(f, "{} RPC unavailable: {}", chain, reason),
+            Self::CyclesBudgetExceeded { action, retry_in_seconds } => write!(
+                f,
+                "Cycles budget exceeded for {}; retry in {} seconds",
+                action, retry_in_seconds
+            ),
+            Self::CallRejected { canister, reject_code, message } => {
+                write!(f, "Call to {} rejected ({}): {}", canister, reject_code, message)
+            }
+            Self::Other { message } => f.write_str(message),
+        }
+    }
+}
+
+impl From<String> for ApiError {
+    fn from(message: String) -> Self {
+        Self::Other { message }
+    }
+}
+
+impl From<&str> for ApiError {
+    fn from(message: &str) -> Self {
+        Self::Other { message: message.to_string() }
+    }
+}
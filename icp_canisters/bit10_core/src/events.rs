@@ -0,0 +1,569 @@
+use crate::certification::{CertifiedTree, Hash};
+use candid::{CandidType, Deserialize, Int, Nat, Principal};
+use sha2::{Digest, Sha256};
+
+pub const SWAP_BLOCK_TYPE: &str = "bit10_swap";
+pub const BUY_BLOCK_TYPE: &str = "bit10_buy";
+pub const SELL_BLOCK_TYPE: &str = "bit10_sell";
+pub const LEND_BLOCK_TYPE: &str = "bit10_lend";
+pub const LEND_WITHDRAW_BLOCK_TYPE: &str = "bit10_lend_withdraw";
+pub const BORROW_BLOCK_TYPE: &str = "bit10_borrow";
+
+/// Where the block schemas are described. The fields of each `tx` map are the
+/// ones written by `Event::to_value` below.
+pub const BLOCK_SCHEMA_URL: &str = "https://gitbook.bit10.app";
+
+/// Blocks returned by a single `icrc3_get_blocks` call.
+pub const MAX_BLOCKS_PER_RESPONSE: u64 = 500;
+
+const LAST_BLOCK_INDEX: &[u8] = b"last_block_index";
+const LAST_BLOCK_HASH: &[u8] = b"last_block_hash";
+
+/// ICRC-3 `Value`.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum Icrc3Value {
+    Blob(Vec<u8>),
+    Text(String),
+    Nat(Nat),
+    Int(Int),
+    Array(Vec<Icrc3Value>),
+    Map(Vec<(String, Icrc3Value)>),
+}
+
+impl Icrc3Value {
+    /// The representation-independent hash from ICRC-3.
+    pub fn hash(&self) -> Hash {
+        match self {
+            Icrc3Value::Blob(bytes) => Sha256::digest(bytes).into(),
+            Icrc3Value::Text(text) => Sha256::digest(text.as_bytes()).into(),
+            Icrc3Value::Nat(nat) => Sha256::digest(leb128(nat)).into(),
+            Icrc3Value::Int(int) => Sha256::digest(sleb128(int)).into(),
+            Icrc3Value::Array(items) => {
+                let mut hasher = Sha256::new();
+                for item in items {
+                    hasher.update(item.hash());
+                }
+                hasher.finalize().into()
+            }
+            Icrc3Value::Map(entries) => {
+                let mut pairs: Vec<Vec<u8>> = entries
+                    .iter()
+                    .map(|(key, value)| {
+                        let mut pair = Sha256::digest(key.as_bytes()).to_vec();
+                        pair.extend_from_slice(&value.hash());
+                        pair
+                    })
+                    .collect();
+                pairs.sort();
+                let mut hasher = Sha256::new();
+                for pair in pairs {
+                    hasher.update(pair);
+                }
+                hasher.finalize().into()
+            }
+        }
+    }
+
+    fn text(value: &str) -> Self {
+        Icrc3Value::Text(value.to_string())
+    }
+}
+
+fn text_field(key: &str, value: &str) -> (String, Icrc3Value) {
+    (key.to_string(), Icrc3Value::text(value))
+}
+
+fn leb128(nat: &Nat) -> Vec<u8> {
+    let mut bytes = nat.0.to_bytes_le().into_iter();
+    let (mut acc, mut bits, mut out) = (0u32, 0u32, Vec::new());
+    loop {
+        if bits < 7 {
+            if let Some(byte) = bytes.next() {
+                acc |= (byte as u32) << bits;
+                bits += 8;
+            }
+        }
+        let chunk = (acc & 0x7f) as u8;
+        acc >>= 7;
+        bits = bits.saturating_sub(7);
+        if acc == 0 && bytes.len() == 0 {
+            out.push(chunk);
+            return out;
+        }
+        out.push(chunk | 0x80);
+    }
+}
+
+fn sleb128(int: &Int) -> Vec<u8> {
+    let bytes = int.0.to_signed_bytes_le();
+    let negative = bytes.last().is_some_and(|byte| byte & 0x80 != 0);
+    let fill: u8 = if negative { 0xff } else { 0x00 };
+    let mut bytes = bytes.into_iter();
+    let (mut acc, mut bits, mut out) = (0u32, 0u32, Vec::new());
+    loop {
+        if bits < 7 {
+            acc |= (bytes.next().unwrap_or(fill) as u32) << bits;
+            bits += 8;
+        }
+        let chunk = (acc & 0x7f) as u8;
+        acc >>= 7;
+        bits -= 7;
+        let mask = (1u32 << bits) - 1;
+        let rest_is_sign = bytes.len() == 0 && acc & mask == (fill as u32) & mask;
+        if rest_is_sign && (chunk & 0x40 != 0) == negative {
+            out.push(chunk);
+            return out;
+        }
+        out.push(chunk | 0x80);
+    }
+}
+
+/// One leg of an event: an amount of a token on a chain and, once known, the
+/// transaction that moved it. Amounts are decimal strings in whole tokens, as
+/// in the canisters' history records.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TokenTransfer {
+    pub chain: String,
+    pub token: String,
+    pub amount: String,
+    pub tx_hash: Option<String>,
+}
+
+impl TokenTransfer {
+    /// History records use an empty string for a transaction that has not
+    /// happened.
+    pub fn new(chain: &str, token: &str, amount: &str, tx_hash: &str) -> Self {
+        TokenTransfer {
+            chain: chain.to_string(),
+            token: token.to_string(),
+            amount: amount.to_string(),
+            tx_hash: Some(tx_hash.to_string()).filter(|hash| !hash.is_empty()),
+        }
+    }
+
+    fn to_value(&self) -> Icrc3Value {
+        let mut fields = vec![
+            text_field("chain", &self.chain),
+            text_field("token", &self.token),
+            text_field("amount", &self.amount),
+        ];
+        if let Some(tx_hash) = &self.tx_hash {
+            fields.push(text_field("tx_hash", tx_hash));
+        }
+        Icrc3Value::Map(fields)
+    }
+}
+
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SwapStatus {
+    Completed,
+    /// The input was sent back instead of paying out.
+    Reverted,
+}
+
+impl SwapStatus {
+    /// Swap histories mark refunds with a status starting with "reverted".
+    pub fn from_history(status: &str) -> Self {
+        if status.to_ascii_lowercase().starts_with("reverted") {
+            SwapStatus::Reverted
+        } else {
+            SwapStatus::Completed
+        }
+    }
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SwapEvent {
+    pub swap_id: Option<String>,
+    pub pool_id: String,
+    pub sender: String,
+    pub recipient: String,
+    pub token_in: TokenTransfer,
+    pub token_out: TokenTransfer,
+    pub slippage: Option<String>,
+    pub status: SwapStatus,
+}
+
+/// A buy or sell of a BIT10 token against another asset.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TradeEvent {
+    pub trade_id: Option<String>,
+    pub account: String,
+    pub paid: TokenTransfer,
+    pub received: TokenTransfer,
+    pub usd_amount: Option<String>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LendEvent {
+    pub lend_id: String,
+    pub lender: String,
+    pub deposit: TokenTransfer,
+    pub interest_rate: String,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LendWithdrawEvent {
+    pub lend_id: String,
+    pub lender: String,
+    pub withdrawal: TokenTransfer,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BorrowEvent {
+    pub borrow_id: String,
+    pub borrower: String,
+    pub borrowed: TokenTransfer,
+    pub collateral: TokenTransfer,
+    pub interest_rate: String,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    Swap(SwapEvent),
+    Buy(TradeEvent),
+    Sell(TradeEvent),
+    Lend(LendEvent),
+    LendWithdraw(LendWithdrawEvent),
+    Borrow(BorrowEvent),
+}
+
+impl Event {
+    pub fn block_type(&self) -> &'static str {
+        match self {
+            Event::Swap(_) => SWAP_BLOCK_TYPE,
+            Event::Buy(_) => BUY_BLOCK_TYPE,
+            Event::Sell(_) => SELL_BLOCK_TYPE,
+            Event::Lend(_) => LEND_BLOCK_TYPE,
+            Event::LendWithdraw(_) => LEND_WITHDRAW_BLOCK_TYPE,
+            Event::Borrow(_) => BORROW_BLOCK_TYPE,
+        }
+    }
+
+    /// The block's `tx` map.
+    pub fn to_value(&self) -> Icrc3Value {
+        let mut fields: Vec<(String, Icrc3Value)> = Vec::new();
+        match self {
+            Event::Swap(swap) => {
+                if let Some(swap_id) = &swap.swap_id {
+                    fields.push(text_field("swap_id", swap_id));
+                }
+                fields.push(text_field("pool_id", &swap.pool_id));
+                fields.push(text_field("from", &swap.sender));
+                fields.push(text_field("to", &swap.recipient));
+                if let Some(slippage) = &swap.slippage {
+                    fields.push(text_field("slippage", slippage));
+                }
+                let status = match swap.status {
+                    SwapStatus::Completed => "completed",
+                    SwapStatus::Reverted => "reverted",
+                };
+                fields.push(text_field("status", status));
+                fields.push(("in".to_string(), swap.token_in.to_value()));
+                fields.push(("out".to_string(), swap.token_out.to_value()));
+            }
+            Event::Buy(trade) | Event::Sell(trade) => {
+                if let Some(trade_id) = &trade.trade_id {
+                    fields.push(text_field("trade_id", trade_id));
+                }
+                fields.push(text_field("account", &trade.account));
+                if let Some(usd_amount) = &trade.usd_amount {
+                    fields.push(text_field("usd_amount", usd_amount));
+                }
+                fields.push(("paid".to_string(), trade.paid.to_value()));
+                fields.push(("received".to_string(), trade.received.to_value()));
+            }
+            Event::Lend(lend) => {
+                fields.push(text_field("lend_id", &lend.lend_id));
+                fields.push(text_field("lender", &lend.lender));
+                fields.push(text_field("interest_rate", &lend.interest_rate));
+                fields.push(("deposit".to_string(), lend.deposit.to_value()));
+            }
+            Event::LendWithdraw(withdraw) => {
+                fields.push(text_field("lend_id", &withdraw.lend_id));
+                fields.push(text_field("lender", &withdraw.lender));
+                fields.push(("withdrawal".to_string(), withdraw.withdrawal.to_value()));
+            }
+            Event::Borrow(borrow) => {
+                fields.push(text_field("borrow_id", &borrow.borrow_id));
+                fields.push(text_field("borrower", &borrow.borrower));
+                fields.push(text_field("interest_rate", &borrow.interest_rate));
+                fields.push(("borrowed".to_string(), borrow.borrowed.to_value()));
+                fields.push(("collateral".to_string(), borrow.collateral.to_value()));
+            }
+        }
+        Icrc3Value::Map(fields)
+    }
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct EventBlock {
+    pub timestamp: u64,
+    pub event: Event,
+    pub parent_hash: Option<Vec<u8>>,
+    pub hash: Vec<u8>,
+}
+
+impl EventBlock {
+    fn new(event: Event, timestamp: u64, parent_hash: Option<Vec<u8>>) -> Self {
+        let mut block = EventBlock {
+            timestamp,
+            event,
+            parent_hash,
+            hash: Vec::new(),
+        };
+        block.hash = block.to_value().hash().to_vec();
+        block
+    }
+
+    pub fn to_value(&self) -> Icrc3Value {
+        let mut fields = vec![
+            text_field("btype", self.event.block_type()),
+            ("ts".to_string(), Icrc3Value::Nat(Nat::from(self.timestamp))),
+            ("tx".to_string(), self.event.to_value()),
+        ];
+        if let Some(parent_hash) = &self.parent_hash {
+            fields.push(("phash".to_string(), Icrc3Value::Blob(parent_hash.clone())));
+        }
+        Icrc3Value::Map(fields)
+    }
+}
+
+/// Append-only log of everything a product canister did. Each block carries
+/// the hash of the one before it, and the tip is certified, so a client that
+/// checks the tip certificate can verify every block it reads back.
+#[derive(CandidType, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct EventLog {
+    blocks: Vec<EventBlock>,
+}
+
+impl EventLog {
+    pub fn append(&mut self, event: Event, timestamp: u64) -> u64 {
+        let parent_hash = self.blocks.last().map(|block| block.hash.clone());
+        self.blocks.push(EventBlock::new(event, timestamp, parent_hash));
+        self.blocks.len() as u64 - 1
+    }
+
+    pub fn len(&self) -> u64 {
+        self.blocks.len() as u64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    pub fn get(&self, id: u64) -> Option<&EventBlock> {
+        self.blocks.get(id as usize)
+    }
+
+    pub fn blocks(&self) -> impl Iterator<Item = &EventBlock> {
+        self.blocks.iter()
+    }
+
+    pub fn get_blocks(&self, requests: &[GetBlocksRequest]) -> GetBlocksResult {
+        let mut blocks = Vec::new();
+        for request in requests {
+            let start = u64::try_from(&request.start.0).unwrap_or(u64::MAX);
+            let length = u64::try_from(&request.length.0).unwrap_or(u64::MAX);
+            let end = start.saturating_add(length).min(self.len());
+            for id in start..end {
+                if blocks.len() as u64 >= MAX_BLOCKS_PER_RESPONSE {
+                    break;
+                }
+                blocks.push(BlockWithId {
+                    id: Nat::from(id),
+                    block: self.blocks[id as usize].to_value(),
+                });
+            }
+        }
+
+        GetBlocksResult {
+            log_length: Nat::from(self.len()),
+            blocks,
+            archived_blocks: Vec::new(),
+        }
+    }
+
+    /// Adds `last_block_index` and `last_block_hash` at the root of the
+    /// canister's certified tree, where ICRC-3 clients look for them.
+    pub fn certify(&self, tree: &mut CertifiedTree) {
+        if let Some(last) = self.blocks.last() {
+            tree.insert(LAST_BLOCK_INDEX, leb128(&Nat::from(self.len() - 1)));
+            tree.insert(LAST_BLOCK_HASH, last.hash.clone());
+        }
+    }
+}
+
+/// The `hash_tree` for `icrc3_get_tip_certificate`.
+pub fn tip_hash_tree(tree: &CertifiedTree) -> Vec<u8> {
+    tree.witness(&[&[LAST_BLOCK_INDEX], &[LAST_BLOCK_HASH]]).to_cbor()
+}
+
+pub fn supported_block_types(block_types: &[&str]) -> Vec<SupportedBlockType> {
+    block_types
+        .iter()
+        .map(|block_type| SupportedBlockType {
+            block_type: block_type.to_string(),
+            url: BLOCK_SCHEMA_URL.to_string(),
+        })
+        .collect()
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct GetBlocksRequest {
+    pub start: Nat,
+    pub length: Nat,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BlockWithId {
+    pub id: Nat,
+    pub block: Icrc3Value,
+}
+
+candid::define_function!(pub GetBlocksCallback : (Vec<GetBlocksRequest>) -> (GetBlocksResult) query);
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ArchivedBlocks {
+    pub args: Vec<GetBlocksRequest>,
+    pub callback: GetBlocksCallback,
+}
+
+/// Nothing is archived yet, so `archived_blocks` is always empty.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct GetBlocksResult {
+    pub log_length: Nat,
+    pub blocks: Vec<BlockWithId>,
+    pub archived_blocks: Vec<ArchivedBlocks>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct DataCertificate {
+    pub certificate: Vec<u8>,
+    pub hash_tree: Vec<u8>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct SupportedBlockType {
+    pub block_type: String,
+    pub url: String,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct GetArchivesArgs {
+    pub from: Option<Principal>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ArchiveInfo {
+    pub canister_id: Principal,
+    pub start: Nat,
+    pub end: Nat,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(hash: &[u8]) -> String {
+        hash.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    fn lend(lend_id: &str) -> Event {
+        Event::Lend(LendEvent {
+            lend_id: lend_id.to_string(),
+            lender: "aaaaa-aa".to_string(),
+            deposit: TokenTransfer::new("ICP", "ckBTC", "1.5", ""),
+            interest_rate: "5".to_string(),
+        })
+    }
+
+    // Examples from the ICRC-3 specification.
+    #[test]
+    fn values_hash_as_in_icrc3() {
+        assert_eq!(
+            hex(&Icrc3Value::Nat(Nat::from(42u64)).hash()),
+            "684888c0ebb17f374298b65ee2807526c066094c701bcc7ebbe1c1095f494fc1"
+        );
+        assert_eq!(
+            hex(&Icrc3Value::Int(Int::from(-42)).hash()),
+            "de5a6f78116eca62d7fc5ce159d23ae6b889b365a1739ad2cf36f925a140d0cc"
+        );
+        assert_eq!(
+            hex(&Icrc3Value::text("Hello, World!").hash()),
+            "dffd6021bb2bd5b0af676290809ec3a53191dd81c7f70a4b28688a362182986f"
+        );
+        assert_eq!(
+            hex(&Icrc3Value::Blob(vec![1, 2, 3, 4]).hash()),
+            "9f64a747e1b97f131fabb6b447296c9b6f0201e79fb3c5356e6c77e89b6a806a"
+        );
+        assert_eq!(
+            hex(&Icrc3Value::Array(vec![
+                Icrc3Value::Nat(Nat::from(3u64)),
+                Icrc3Value::text("foo"),
+                Icrc3Value::Blob(vec![5, 6]),
+            ])
+            .hash()),
+            "514a04011caa503990d446b7dec5d79e19c221ae607fb08b2848c67734d468d6"
+        );
+    }
+
+    #[test]
+    fn leb128_matches_known_encodings() {
+        assert_eq!(leb128(&Nat::from(0u64)), vec![0x00]);
+        assert_eq!(leb128(&Nat::from(624_485u64)), vec![0xe5, 0x8e, 0x26]);
+        assert_eq!(sleb128(&Int::from(-123_456)), vec![0xc0, 0xbb, 0x78]);
+        assert_eq!(sleb128(&Int::from(64)), vec![0xc0, 0x00]);
+    }
+
+    #[test]
+    fn each_block_links_to_the_one_before() {
+        let mut log = EventLog::default();
+        for id in ["1", "2", "3"] {
+            log.append(lend(id), 10);
+        }
+
+        let blocks: Vec<&EventBlock> = log.blocks().collect();
+        assert_eq!(blocks[0].parent_hash, None);
+        for pair in blocks.windows(2) {
+            assert_eq!(pair[1].parent_hash.as_ref(), Some(&pair[0].hash));
+        }
+        for block in &blocks {
+            assert_eq!(block.hash, block.to_value().hash().to_vec());
+        }
+
+        // Changing an earlier block breaks the link from the next one.
+        let mut tampered = blocks[0].clone();
+        tampered.timestamp = 11;
+        assert_ne!(tampered.to_value().hash().to_vec(), blocks[0].hash);
+    }
+
+    #[test]
+    fn tip_is_certified() {
+        let mut log = EventLog::default();
+        let mut tree = CertifiedTree::default();
+        log.certify(&mut tree);
+        assert!(tree.is_empty());
+
+        log.append(lend("1"), 10);
+        let last = log.append(lend("2"), 20);
+        log.certify(&mut tree);
+        assert_eq!(tree.get(LAST_BLOCK_INDEX), Some(leb128(&Nat::from(last)).as_slice()));
+        assert_eq!(tree.get(LAST_BLOCK_HASH), Some(log.get(last).unwrap().hash.as_slice()));
+    }
+
+    #[test]
+    fn get_blocks_stops_at_the_log_end() {
+        let mut log = EventLog::default();
+        for id in ["1", "2", "3"] {
+            log.append(lend(id), 10);
+        }
+        let result = log.get_blocks(&[GetBlocksRequest {
+            start: Nat::from(1u64),
+            length: Nat::from(10u64),
+        }]);
+        assert_eq!(result.log_length, Nat::from(3u64));
+        let ids: Vec<Nat> = result.blocks.into_iter().map(|block| block.id).collect();
+        assert_eq!(ids, vec![Nat::from(1u64), Nat::from(2u64)]);
+    }
+}
@@ -0,0 +1,340 @@
+use candid::{CandidType, Deserialize};
+
+/// Tuning knobs for EIP-1559 fee and gas-limit estimation.
+///
+/// Multipliers are expressed in percent, so `200` doubles a value and `100`
+/// leaves it unchanged.
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FeeConfig {
+    /// Number of recent blocks sampled through `eth_feeHistory`.
+    pub fee_history_blocks: u64,
+    /// Reward percentile requested from `eth_feeHistory` for the tip.
+    pub priority_fee_percentile: u8,
+    /// Applied to the pending block's base fee to absorb base-fee growth.
+    pub base_fee_multiplier_percent: u64,
+    /// Applied to the sampled priority fee.
+    pub priority_fee_multiplier_percent: u64,
+    pub min_priority_fee_per_gas: u128,
+    pub max_priority_fee_per_gas: u128,
+    /// Upper bound for `max_fee_per_gas`. Estimation fails if the pending
+    /// base fee alone is above it.
+    pub max_fee_per_gas_ceiling: u128,
+    /// Headroom added on top of `eth_estimateGas`.
+    pub gas_limit_multiplier_percent: u64,
+    pub gas_limit_ceiling: u128,
+    /// Minimum fee increase when replacing a pending transaction.
+    pub replacement_bump_percent: u64,
+}
+
+impl FeeConfig {
+    pub fn ethereum() -> Self {
+        FeeConfig {
+            fee_history_blocks: 10,
+            priority_fee_percentile: 50,
+            base_fee_multiplier_percent: 200,
+            priority_fee_multiplier_percent: 100,
+            min_priority_fee_per_gas: 1_000_000_000,
+            max_priority_fee_per_gas: 5_000_000_000,
+            max_fee_per_gas_ceiling: 200_000_000_000,
+            gas_limit_multiplier_percent: 120,
+            gas_limit_ceiling: 1_000_000,
+            replacement_bump_percent: 12,
+        }
+    }
+
+    pub fn bsc() -> Self {
+        FeeConfig {
+            fee_history_blocks: 10,
+            priority_fee_percentile: 50,
+            base_fee_multiplier_percent: 200,
+            priority_fee_multiplier_percent: 100,
+            min_priority_fee_per_gas: 1_000_000_000,
+            max_priority_fee_per_gas: 3_000_000_000,
+            max_fee_per_gas_ceiling: 20_000_000_000,
+            gas_limit_multiplier_percent: 120,
+            gas_limit_ceiling: 1_000_000,
+            replacement_bump_percent: 12,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.fee_history_blocks == 0 || self.fee_history_blocks > 1024 {
+            return Err("fee_history_blocks must be between 1 and 1024".to_string());
+        }
+        if self.priority_fee_percentile > 100 {
+            return Err("priority_fee_percentile must be between 0 and 100".to_string());
+        }
+        if self.base_fee_multiplier_percent < 100 {
+            return Err("base_fee_multiplier_percent must be at least 100".to_string());
+        }
+        if self.gas_limit_multiplier_percent < 100 {
+            return Err("gas_limit_multiplier_percent must be at least 100".to_string());
+        }
+        if self.min_priority_fee_per_gas > self.max_priority_fee_per_gas {
+            return Err("min_priority_fee_per_gas cannot exceed max_priority_fee_per_gas".to_string());
+        }
+        if self.max_priority_fee_per_gas > self.max_fee_per_gas_ceiling {
+            return Err("max_priority_fee_per_gas cannot exceed max_fee_per_gas_ceiling".to_string());
+        }
+        if self.replacement_bump_percent < 10 {
+            return Err("replacement_bump_percent must be at least 10".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Default for FeeConfig {
+    fn default() -> Self {
+        FeeConfig::ethereum()
+    }
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Eip1559Fees {
+    pub max_fee_per_gas: u128,
+    pub max_priority_fee_per_gas: u128,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeeHistory {
+    /// Base fees of the sampled blocks followed by the pending block.
+    pub base_fee_per_gas: Vec<u128>,
+    /// Priority fee paid at the requested percentile in each sampled block.
+    pub rewards: Vec<u128>,
+}
+
+impl FeeHistory {
+    pub fn pending_base_fee(&self) -> Option<u128> {
+        self.base_fee_per_gas.last().copied()
+    }
+}
+
+pub fn fee_history_payload(config: &FeeConfig) -> String {
+    format!(
+        r#"{{"jsonrpc": "2.0", "method": "eth_feeHistory", "params": ["0x{:x}", "latest", [{}]], "id": 1}}"#,
+        config.fee_history_blocks, config.priority_fee_percentile
+    )
+}
+
+pub fn parse_fee_history(body: &str) -> Result<FeeHistory, String> {
+    let result = rpc_result(body, "fee history")?;
+
+    let base_fee_per_gas = result
+        .get("baseFeePerGas")
+        .and_then(|v| v.as_array())
+        .ok_or("eth_feeHistory returned no baseFeePerGas")?
+        .iter()
+        .map(|v| v.as_str().ok_or("Invalid baseFeePerGas entry".to_string()).and_then(parse_hex_u128))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let rewards = match result.get("reward").and_then(|v| v.as_array()) {
+        Some(blocks) => blocks
+            .iter()
+            .filter_map(|block| block.as_array().and_then(|r| r.first()).and_then(|v| v.as_str()))
+            .map(parse_hex_u128)
+            .collect::<Result<Vec<_>, _>>()?,
+        None => Vec::new(),
+    };
+
+    Ok(FeeHistory {
+        base_fee_per_gas,
+        rewards,
+    })
+}
+
+/// Derives EIP-1559 fees from a fee history sample.
+///
+/// The tip is the median of the non-zero sampled rewards, scaled and clamped
+/// to the configured bounds. `max_fee_per_gas` covers the pending base fee
+/// times the base fee multiplier plus the tip, capped at the ceiling.
+pub fn suggest_fees(history: &FeeHistory, config: &FeeConfig) -> Result<Eip1559Fees, String> {
+    let pending_base_fee = history
+        .pending_base_fee()
+        .ok_or("Fee history contains no base fee")?;
+
+    if pending_base_fee > config.max_fee_per_gas_ceiling {
+        return Err(format!(
+            "Network base fee {} exceeds the configured ceiling {}",
+            pending_base_fee, config.max_fee_per_gas_ceiling
+        ));
+    }
+
+    let mut rewards: Vec<u128> = history.rewards.iter().copied().filter(|r| *r > 0).collect();
+    rewards.sort_unstable();
+    let sampled_tip = rewards.get(rewards.len() / 2).copied().unwrap_or(0);
+
+    let max_priority_fee_per_gas = apply_percent(sampled_tip, config.priority_fee_multiplier_percent)
+        .clamp(config.min_priority_fee_per_gas, config.max_priority_fee_per_gas);
+
+    let max_fee_per_gas = apply_percent(pending_base_fee, config.base_fee_multiplier_percent)
+        .saturating_add(max_priority_fee_per_gas)
+        .min(config.max_fee_per_gas_ceiling);
+
+    Ok(Eip1559Fees {
+        max_fee_per_gas,
+        max_priority_fee_per_gas: max_priority_fee_per_gas.min(max_fee_per_gas),
+    })
+}
+
+/// Fees for a transaction replacing `previous` at the same nonce.
+///
+/// Nodes only accept a replacement when both fee fields grow by a minimum
+/// percentage, so the result is the larger of the bumped previous fees and
+/// the current market estimate.
+pub fn replacement_fees(
+    previous: &Eip1559Fees,
+    current: &Eip1559Fees,
+    config: &FeeConfig,
+) -> Result<Eip1559Fees, String> {
+    let bump = |value: u128| apply_percent_ceil(value, 100 + config.replacement_bump_percent);
+
+    let max_priority_fee_per_gas = bump(previous.max_priority_fee_per_gas)
+        .max(current.max_priority_fee_per_gas);
+    let max_fee_per_gas = bump(previous.max_fee_per_gas)
+        .max(current.max_fee_per_gas)
+        .max(max_priority_fee_per_gas);
+
+    if max_fee_per_gas > config.max_fee_per_gas_ceiling {
+        return Err(format!(
+            "Replacement max fee {} exceeds the configured ceiling {}",
+            max_fee_per_gas, config.max_fee_per_gas_ceiling
+        ));
+    }
+
+    Ok(Eip1559Fees {
+        max_fee_per_gas,
+        max_priority_fee_per_gas,
+    })
+}
+
+pub fn estimate_gas_payload(from: &str, to: &str, value_hex: &str, data_hex: &str) -> String {
+    format!(
+        r#"{{"jsonrpc": "2.0", "method": "eth_estimateGas", "params": [{{"from": "{}", "to": "{}", "value": "{}", "data": "{}"}}], "id": 1}}"#,
+        from, to, value_hex, data_hex
+    )
+}
+
+pub fn parse_estimate_gas(body: &str) -> Result<u128, String> {
+    let result = rpc_result(body, "gas estimate")?;
+    let hex = result.as_str().ok_or("eth_estimateGas returned no result")?;
+    parse_hex_u128(hex)
+}
+
+/// Applies the configured headroom to a gas estimate.
+pub fn gas_limit_from_estimate(estimate: u128, config: &FeeConfig) -> Result<u128, String> {
+    let gas_limit = apply_percent_ceil(estimate, config.gas_limit_multiplier_percent);
+    if gas_limit > config.gas_limit_ceiling {
+        return Err(format!(
+            "Estimated gas limit {} exceeds the configured ceiling {}",
+            gas_limit, config.gas_limit_ceiling
+        ));
+    }
+    Ok(gas_limit)
+}
+
+pub fn parse_hex_u128(value: &str) -> Result<u128, String> {
+    let cleaned = value.strip_prefix("0x").unwrap_or(value);
+    if cleaned.is_empty() {
+        return Ok(0);
+    }
+    u128::from_str_radix(cleaned, 16).map_err(|e| format!("Invalid hex quantity '{}': {}", value, e))
+}
+
+fn rpc_result(body: &str, what: &str) -> Result<serde_json::Value, String> {
+    let response: serde_json::Value = serde_json::from_str(body)
+        .map_err(|e| format!("Failed to parse {} response: {}", what, e))?;
+
+    if let Some(error) = response.get("error") {
+        return Err(format!("RPC error: {}", error));
+    }
+
+    match response.get("result") {
+        Some(result) if !result.is_null() => Ok(result.clone()),
+        _ => Err(format!("No result in {} response", what)),
+    }
+}
+
+fn apply_percent(value: u128, percent: u64) -> u128 {
+    value.saturating_mul(percent as u128) / 100
+}
+
+fn apply_percent_ceil(value: u128, percent: u64) -> u128 {
+    value.saturating_mul(percent as u128).div_ceil(100)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GWEI: u128 = 1_000_000_000;
+
+    fn history(pending_base_fee: u128, rewards: Vec<u128>) -> FeeHistory {
+        FeeHistory {
+            base_fee_per_gas: vec![GWEI, pending_base_fee],
+            rewards,
+        }
+    }
+
+    #[test]
+    fn parses_fee_history() {
+        let body = r#"{"jsonrpc":"2.0","id":1,"result":{"baseFeePerGas":["0x3b9aca00","0x77359400"],"reward":[["0x0"],["0x3b9aca00"]]}}"#;
+        let parsed = parse_fee_history(body).unwrap();
+        assert_eq!(parsed.base_fee_per_gas, vec![GWEI, 2 * GWEI]);
+        assert_eq!(parsed.rewards, vec![0, GWEI]);
+        assert_eq!(parsed.pending_base_fee(), Some(2 * GWEI));
+
+        let error = r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32000,"message":"boom"}}"#;
+        assert!(parse_fee_history(error).unwrap_err().starts_with("RPC error"));
+    }
+
+    #[test]
+    fn tip_is_the_median_of_non_zero_rewards() {
+        let config = FeeConfig::ethereum();
+        let fees = suggest_fees(&history(10 * GWEI, vec![2 * GWEI, 0, 3 * GWEI, GWEI]), &config).unwrap();
+        assert_eq!(fees.max_priority_fee_per_gas, 2 * GWEI);
+        assert_eq!(fees.max_fee_per_gas, 22 * GWEI);
+    }
+
+    #[test]
+    fn fees_are_clamped_to_the_config() {
+        let config = FeeConfig::ethereum();
+        let quiet = suggest_fees(&history(GWEI, vec![0, 0]), &config).unwrap();
+        assert_eq!(quiet.max_priority_fee_per_gas, config.min_priority_fee_per_gas);
+
+        let busy = suggest_fees(&history(99 * GWEI, vec![50 * GWEI]), &config).unwrap();
+        assert_eq!(busy.max_priority_fee_per_gas, config.max_priority_fee_per_gas);
+        assert_eq!(busy.max_fee_per_gas, config.max_fee_per_gas_ceiling);
+
+        assert!(suggest_fees(&history(201 * GWEI, vec![]), &config).is_err());
+    }
+
+    #[test]
+    fn replacement_bumps_both_fees() {
+        let config = FeeConfig::ethereum();
+        let previous = Eip1559Fees {
+            max_fee_per_gas: 22 * GWEI,
+            max_priority_fee_per_gas: 2 * GWEI,
+        };
+        let current = Eip1559Fees {
+            max_fee_per_gas: 10 * GWEI,
+            max_priority_fee_per_gas: 3 * GWEI,
+        };
+        let fees = replacement_fees(&previous, &current, &config).unwrap();
+        assert_eq!(fees.max_fee_per_gas, 24_640_000_000);
+        assert_eq!(fees.max_priority_fee_per_gas, 3 * GWEI);
+
+        let expensive = Eip1559Fees {
+            max_fee_per_gas: 190 * GWEI,
+            max_priority_fee_per_gas: 2 * GWEI,
+        };
+        assert!(replacement_fees(&expensive, &current, &config).is_err());
+    }
+
+    #[test]
+    fn gas_limit_gets_headroom_up_to_the_ceiling() {
+        let config = FeeConfig::ethereum();
+        assert_eq!(gas_limit_from_estimate(21_000, &config), Ok(25_200));
+        assert!(gas_limit_from_estimate(900_000, &config).is_err());
+        assert_eq!(parse_estimate_gas(r#"{"jsonrpc":"2.0","id":1,"result":"0x5208"}"#), Ok(21_000));
+    }
+}
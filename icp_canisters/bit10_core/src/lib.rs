@@ -0,0 +1,19 @@
+pub mod certification;
+pub mod cycles;
+pub mod deposits;
+pub mod errors;
+pub mod events;
+pub mod fees;
+pub mod metrics;
+pub mod nonce;
+pub mod providers;
+pub mod spending;
+pub mod transform;
+pub mod types;
+
+#[cfg(feature = "canister")]
+pub mod rpc;
+#[cfg(feature = "canister")]
+pub mod utils;
+#[cfg(feature = "canister")]
+pub mod wallet;
@@ -0,0 +1,415 @@
+use crate::cycles::CyclesLedger;
+use crate::deposits::{DepositRegistry, DepositStatus};
+use crate::events::{Event, EventLog, TokenTransfer};
+use crate::providers::ProviderHealth;
+use candid::{CandidType, Deserialize};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// How long a price feed may go without a successful read before `/health`
+/// reports it as stale.
+pub const DEFAULT_MAX_FEED_AGE_NS: u64 = 30 * 60 * 1_000_000_000;
+
+/// Gas for one token transfer, the most expensive call the canisters make
+/// from an EVM wallet. A wallet that cannot pay for it at the current gas
+/// price has insufficient gas.
+pub const EVM_TRANSFER_GAS: u128 = 100_000;
+
+/// A Solana wallet needs the fee plus rent for any associated token account
+/// it creates.
+pub const SOLANA_MIN_BALANCE_LAMPORTS: u128 = 5_000_000;
+
+/// The request the HTTP gateway passes to a canister's `http_request` query.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct HttpRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpRequest {
+    /// The URL without its query string.
+    pub fn path(&self) -> &str {
+        self.url.split('?').next().unwrap_or_default()
+    }
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct HttpResponse {
+    pub status_code: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    pub fn new(status_code: u16, content_type: &str, body: Vec<u8>) -> Self {
+        HttpResponse {
+            status_code,
+            headers: vec![
+                ("Content-Type".to_string(), content_type.to_string()),
+                ("Content-Length".to_string(), body.len().to_string()),
+                ("Cache-Control".to_string(), "no-store".to_string()),
+            ],
+            body,
+        }
+    }
+
+    pub fn not_found() -> Self {
+        HttpResponse::new(404, "text/plain", b"Not found".to_vec())
+    }
+}
+
+pub type Labels<'a> = &'a [(&'a str, &'a str)];
+
+const NO_LABELS: Labels = &[];
+
+/// Writes metrics in the Prometheus text exposition format. Every family is
+/// written in one go, with its `# HELP` and `# TYPE` lines first.
+#[derive(Default)]
+pub struct MetricsEncoder {
+    out: String,
+}
+
+impl MetricsEncoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn gauge(&mut self, name: &str, help: &str, value: f64) {
+        self.family(name, help, "gauge", &[(NO_LABELS, value)]);
+    }
+
+    pub fn counter(&mut self, name: &str, help: &str, value: f64) {
+        self.family(name, help, "counter", &[(NO_LABELS, value)]);
+    }
+
+    pub fn gauge_vec(&mut self, name: &str, help: &str, samples: &[(Labels, f64)]) {
+        self.family(name, help, "gauge", samples);
+    }
+
+    pub fn counter_vec(&mut self, name: &str, help: &str, samples: &[(Labels, f64)]) {
+        self.family(name, help, "counter", samples);
+    }
+
+    fn family(&mut self, name: &str, help: &str, kind: &str, samples: &[(Labels, f64)]) {
+        let _ = writeln!(self.out, "# HELP {} {}", name, help);
+        let _ = writeln!(self.out, "# TYPE {} {}", name, kind);
+        for (labels, value) in samples {
+            self.out.push_str(name);
+            if !labels.is_empty() {
+                let labels: Vec<String> = labels
+                    .iter()
+                    .map(|(key, value)| format!("{}=\"{}\"", key, escape_label(value)))
+                    .collect();
+                let _ = write!(self.out, "{{{}}}", labels.join(","));
+            }
+            let _ = writeln!(self.out, " {}", value);
+        }
+    }
+
+    pub fn into_response(self) -> HttpResponse {
+        HttpResponse::new(200, "text/plain; version=0.0.4", self.out.into_bytes())
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Cycles and memory. The heap size is read here; the stable memory size
+/// comes from the canister, whose ic-cdk release decides how to ask for it.
+pub fn encode_canister(encoder: &mut MetricsEncoder, cycles: u128, stable_memory_bytes: u64) {
+    encoder.gauge("bit10_cycle_balance", "Cycle balance of the canister.", cycles as f64);
+    encoder.gauge("bit10_heap_memory_bytes", "Size of the Wasm heap in bytes.", heap_memory_bytes() as f64);
+    encoder.gauge(
+        "bit10_stable_memory_bytes",
+        "Size of stable memory in bytes.",
+        stable_memory_bytes as f64,
+    );
+}
+
+#[cfg(target_arch = "wasm32")]
+fn heap_memory_bytes() -> u64 {
+    core::arch::wasm32::memory_size(0) as u64 * 65536
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn heap_memory_bytes() -> u64 {
+    0
+}
+
+/// Event counts by block type and chain, and volumes by block type, chain and
+/// token. The volume of an event is the leg that went into the canister, or
+/// out of it for withdrawals and borrows.
+pub fn encode_events(encoder: &mut MetricsEncoder, log: &EventLog) {
+    let mut counts: BTreeMap<(&str, &str), u64> = BTreeMap::new();
+    let mut volumes: BTreeMap<(&str, &str, &str), f64> = BTreeMap::new();
+    for block in log.blocks() {
+        let transfer = volume_leg(&block.event);
+        let block_type = block.event.block_type();
+        *counts.entry((block_type, transfer.chain.as_str())).or_default() += 1;
+        *volumes.entry((block_type, transfer.chain.as_str(), transfer.token.as_str())).or_default() +=
+            transfer.amount.parse::<f64>().unwrap_or(0.0);
+    }
+
+    let samples: Vec<([(&str, &str); 2], f64)> = counts
+        .iter()
+        .map(|((block_type, chain), count)| ([("type", *block_type), ("chain", *chain)], *count as f64))
+        .collect();
+    let samples: Vec<(Labels, f64)> = samples.iter().map(|(labels, value)| (&labels[..], *value)).collect();
+    encoder.counter_vec("bit10_events_total", "Recorded events by block type and chain.", &samples);
+
+    let samples: Vec<([(&str, &str); 3], f64)> = volumes
+        .iter()
+        .map(|((block_type, chain, token), volume)| {
+            ([("type", *block_type), ("chain", *chain), ("token", *token)], *volume)
+        })
+        .collect();
+    let samples: Vec<(Labels, f64)> = samples.iter().map(|(labels, value)| (&labels[..], *value)).collect();
+    encoder.counter_vec(
+        "bit10_event_volume",
+        "Volume of recorded events in whole tokens, by block type, chain and token.",
+        &samples,
+    );
+    encoder.gauge("bit10_event_log_length", "Number of blocks in the event log.", log.len() as f64);
+}
+
+fn volume_leg(event: &Event) -> &TokenTransfer {
+    match event {
+        Event::Swap(swap) => &swap.token_in,
+        Event::Buy(trade) | Event::Sell(trade) => &trade.paid,
+        Event::Lend(lend) => &lend.deposit,
+        Event::LendWithdraw(withdraw) => &withdraw.withdrawal,
+        Event::Borrow(borrow) => &borrow.borrowed,
+    }
+}
+
+/// Outcall results per chain and provider, as tallied by `ProviderSet`.
+pub fn encode_provider_health(encoder: &mut MetricsEncoder, chains: &[(String, Vec<(String, ProviderHealth)>)]) {
+    let mut outcalls: Vec<([(&str, &str); 3], f64)> = Vec::new();
+    let mut flagged: Vec<([(&str, &str); 2], f64)> = Vec::new();
+    for (chain, providers) in chains {
+        for (provider, health) in providers {
+            for (result, count) in [("agreed", health.agreed), ("disagreed", health.disagreed), ("failed", health.failed)] {
+                outcalls.push((
+                    [("chain", chain.as_str()), ("provider", provider.as_str()), ("result", result)],
+                    count as f64,
+                ));
+            }
+            let is_flagged = if health.flagged_at.is_some() { 1.0 } else { 0.0 };
+            flagged.push(([("chain", chain.as_str()), ("provider", provider.as_str())], is_flagged));
+        }
+    }
+
+    let samples: Vec<(Labels, f64)> = outcalls.iter().map(|(labels, value)| (&labels[..], *value)).collect();
+    encoder.counter_vec(
+        "bit10_rpc_outcalls_total",
+        "RPC outcalls by chain, provider and result. Agreed calls matched the quorum.",
+        &samples,
+    );
+    let samples: Vec<(Labels, f64)> = flagged.iter().map(|(labels, value)| (&labels[..], *value)).collect();
+    encoder.gauge_vec(
+        "bit10_rpc_provider_flagged",
+        "1 while a provider is flagged for repeated disagreements.",
+        &samples,
+    );
+}
+
+/// Multi-step operations that have started but not finished, by kind, e.g.
+/// deposits being verified or settled and queued transfers.
+pub fn encode_pending_sagas(encoder: &mut MetricsEncoder, pending: &[(&str, usize)]) {
+    let samples: Vec<([(&str, &str); 1], f64)> =
+        pending.iter().map(|(kind, count)| ([("kind", *kind)], *count as f64)).collect();
+    let samples: Vec<(Labels, f64)> = samples.iter().map(|(labels, value)| (&labels[..], *value)).collect();
+    encoder.gauge_vec("bit10_pending_sagas", "Operations started but not yet finished, by kind.", &samples);
+}
+
+/// Cycles attributed to each action, how many times it ran and how many calls
+/// the budget turned away.
+pub fn encode_cycles(encoder: &mut MetricsEncoder, ledger: &CyclesLedger) {
+    let mut spent: Vec<([(&str, &str); 1], f64)> = Vec::new();
+    let mut counts: Vec<([(&str, &str); 2], f64)> = Vec::new();
+    for action in ledger.actions() {
+        spent.push(([("action", action.action.as_str())], action.cycles as f64));
+        counts.push(([("action", action.action.as_str()), ("result", "charged")], action.count as f64));
+        counts.push(([("action", action.action.as_str()), ("result", "rejected")], action.rejected as f64));
+    }
+
+    let samples: Vec<(Labels, f64)> = spent.iter().map(|(labels, value)| (&labels[..], *value)).collect();
+    encoder.counter_vec("bit10_cycles_spent_total", "Estimated cycles spent by action.", &samples);
+    let samples: Vec<(Labels, f64)> = counts.iter().map(|(labels, value)| (&labels[..], *value)).collect();
+    encoder.counter_vec(
+        "bit10_cycles_charges_total",
+        "Actions charged to a caller's cycles budget, or rejected for exceeding it.",
+        &samples,
+    );
+}
+
+/// Deposits still being verified or settled.
+pub fn deposits_in_flight(registry: &DepositRegistry) -> usize {
+    registry
+        .deposits()
+        .filter(|(_, record)| matches!(record.status, DepositStatus::Verifying | DepositStatus::Settling))
+        .count()
+}
+
+#[derive(Clone, Debug, Default)]
+struct FeedReading {
+    last_success: Option<u64>,
+    last_failure: Option<u64>,
+}
+
+/// When each price feed was last read. Feeds are only read when a trade needs
+/// them, so this is kept in memory and starts empty after an upgrade.
+#[derive(Clone, Debug, Default)]
+pub struct PriceFeedClock {
+    feeds: BTreeMap<String, FeedReading>,
+}
+
+impl PriceFeedClock {
+    pub fn record(&mut self, feed: &str, succeeded: bool, now: u64) {
+        let reading = self.feeds.entry(feed.to_string()).or_default();
+        if succeeded {
+            reading.last_success = Some(now);
+        } else {
+            reading.last_failure = Some(now);
+        }
+    }
+
+    pub fn encode(&self, encoder: &mut MetricsEncoder) {
+        let samples: Vec<([(&str, &str); 1], f64)> = self
+            .feeds
+            .iter()
+            .filter_map(|(feed, reading)| Some(([("feed", feed.as_str())], reading.last_success? as f64 / 1e9)))
+            .collect();
+        let samples: Vec<(Labels, f64)> = samples.iter().map(|(labels, value)| (&labels[..], *value)).collect();
+        encoder.gauge_vec(
+            "bit10_price_feed_last_success_seconds",
+            "Unix time of the last successful read of each price feed.",
+            &samples,
+        );
+    }
+
+    /// A feed is stale when its latest read failed or its last successful
+    /// read is older than `max_age`.
+    pub fn check(&self, report: &mut HealthReport, max_age: u64) {
+        let now = report.timestamp;
+        for (feed, reading) in &self.feeds {
+            let failed_last = match (reading.last_success, reading.last_failure) {
+                (Some(success), Some(failure)) => failure > success,
+                (None, Some(_)) => true,
+                _ => false,
+            };
+            let too_old = reading.last_success.is_some_and(|success| now.saturating_sub(success) > max_age);
+            let detail = if failed_last {
+                "latest read failed".to_string()
+            } else if too_old {
+                "no successful read within the maximum age".to_string()
+            } else {
+                "fresh".to_string()
+            };
+            report.check(&format!("price_feed:{}", feed), !failed_last && !too_old, detail);
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct GasReading {
+    balance: u128,
+    minimum: u128,
+    at: u64,
+}
+
+/// The native balance of each chain wallet the canister pays fees from, as
+/// last read, in the chain's smallest unit.
+#[derive(Clone, Debug, Default)]
+pub struct GasMonitor {
+    chains: BTreeMap<String, GasReading>,
+}
+
+impl GasMonitor {
+    pub fn record(&mut self, chain: &str, balance: u128, minimum: u128, now: u64) {
+        self.chains.insert(chain.to_string(), GasReading { balance, minimum, at: now });
+    }
+
+    pub fn encode(&self, encoder: &mut MetricsEncoder) {
+        let balances: Vec<([(&str, &str); 1], f64)> = self
+            .chains
+            .iter()
+            .map(|(chain, reading)| ([("chain", chain.as_str())], reading.balance as f64))
+            .collect();
+        let samples: Vec<(Labels, f64)> = balances.iter().map(|(labels, value)| (&labels[..], *value)).collect();
+        encoder.gauge_vec(
+            "bit10_gas_balance",
+            "Native balance of the canister's wallet in the chain's smallest unit, as last read.",
+            &samples,
+        );
+        let read_at: Vec<([(&str, &str); 1], f64)> = self
+            .chains
+            .iter()
+            .map(|(chain, reading)| ([("chain", chain.as_str())], reading.at as f64 / 1e9))
+            .collect();
+        let samples: Vec<(Labels, f64)> = read_at.iter().map(|(labels, value)| (&labels[..], *value)).collect();
+        encoder.gauge_vec("bit10_gas_balance_read_seconds", "Unix time the gas balance was last read.", &samples);
+    }
+
+    pub fn check(&self, report: &mut HealthReport) {
+        for (chain, reading) in &self.chains {
+            let ok = reading.balance >= reading.minimum;
+            let detail = format!("balance {} (minimum {})", reading.balance, reading.minimum);
+            report.check(&format!("gas:{}", chain), ok, detail);
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct HealthCheck {
+    pub name: String,
+    pub ok: bool,
+    pub detail: String,
+}
+
+/// What `/health` returns. The canister is ready while it answers at all; the
+/// status is `degraded` when any check fails, and the response is then a 503
+/// so that probes notice without parsing the body.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct HealthReport {
+    pub status: String,
+    pub ready: bool,
+    pub timestamp: u64,
+    pub checks: Vec<HealthCheck>,
+}
+
+impl HealthReport {
+    pub fn new(now: u64) -> Self {
+        HealthReport {
+            status: "ok".to_string(),
+            ready: true,
+            timestamp: now,
+            checks: Vec::new(),
+        }
+    }
+
+    pub fn check(&mut self, name: &str, ok: bool, detail: impl Into<String>) {
+        if !ok {
+            self.status = "degraded".to_string();
+        }
+        self.checks.push(HealthCheck {
+            name: name.to_string(),
+            ok,
+            detail: detail.into(),
+        });
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.checks.iter().all(|check| check.ok)
+    }
+
+    pub fn into_response(self) -> HttpResponse {
+        let status_code = if self.is_ok() { 200 } else { 503 };
+        let body = serde_json::to_vec(&self).expect("Failed to encode health report");
+        HttpResponse::new(status_code, "application/json", body)
+    }
+}
@@ -0,0 +1,370 @@
+use candid::CandidType;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum PendingStatus {
+    Reserved,
+    Submitted,
+    Failed,
+}
+
+/// Everything in a submitted transaction except its fees, kept so that a
+/// replacement can be rebuilt from the canister's own record. Amounts and
+/// calldata are `0x`-prefixed hex, as in JSON-RPC.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TransactionCall {
+    pub to: String,
+    pub value: String,
+    pub data: String,
+    pub gas_limit: u128,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PendingTransaction {
+    pub nonce: u64,
+    pub status: PendingStatus,
+    pub tx_hash: Option<String>,
+    pub max_fee_per_gas: Option<u128>,
+    pub max_priority_fee_per_gas: Option<u128>,
+    pub is_cancel: bool,
+    pub reserved_at: u64,
+    pub submitted_at: Option<u64>,
+    pub call: Option<TransactionCall>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct WalletNonces {
+    pub next_nonce: u64,
+    pub confirmed_nonce: u64,
+    pub tracked_from: u64,
+    pub last_reconciled_at: Option<u64>,
+    pub pending: BTreeMap<u64, PendingTransaction>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReconcileReport {
+    pub confirmed: Vec<PendingTransaction>,
+    pub gaps: Vec<u64>,
+    pub stuck: Vec<PendingTransaction>,
+}
+
+/// Nonces handed out per (chain, address). Reservations happen synchronously
+/// in canister state so concurrent update calls never share a nonce.
+#[derive(CandidType, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct NonceManager {
+    wallets: BTreeMap<String, WalletNonces>,
+}
+
+pub fn wallet_key(chain_id: u64, address: &str) -> String {
+    format!("{}:{}", chain_id, address.to_ascii_lowercase())
+}
+
+impl NonceManager {
+    pub fn is_tracked(&self, key: &str) -> bool {
+        self.wallets.contains_key(key)
+    }
+
+    pub fn wallet(&self, key: &str) -> Option<&WalletNonces> {
+        self.wallets.get(key)
+    }
+
+    pub fn wallets(&self) -> impl Iterator<Item = (&String, &WalletNonces)> {
+        self.wallets.iter()
+    }
+
+    pub fn keys(&self) -> Vec<String> {
+        self.wallets.keys().cloned().collect()
+    }
+
+    /// Starts tracking a wallet from its on-chain `pending` transaction count.
+    /// If the wallet is already tracked the local counter only moves forward.
+    pub fn track(&mut self, key: &str, chain_nonce: u64) {
+        let wallet = self
+            .wallets
+            .entry(key.to_string())
+            .or_insert_with(|| WalletNonces {
+                tracked_from: chain_nonce,
+                ..Default::default()
+            });
+        wallet.next_nonce = wallet.next_nonce.max(chain_nonce);
+    }
+
+    pub fn reserve(&mut self, key: &str, now: u64) -> Result<u64, String> {
+        let wallet = self
+            .wallets
+            .get_mut(key)
+            .ok_or_else(|| format!("Wallet {} is not tracked by the nonce manager", key))?;
+
+        let nonce = wallet.next_nonce;
+        wallet.next_nonce += 1;
+        wallet.pending.insert(
+            nonce,
+            PendingTransaction {
+                nonce,
+                status: PendingStatus::Reserved,
+                tx_hash: None,
+                max_fee_per_gas: None,
+                max_priority_fee_per_gas: None,
+                is_cancel: false,
+                reserved_at: now,
+                submitted_at: None,
+                call: None,
+            },
+        );
+        Ok(nonce)
+    }
+
+    pub fn mark_submitted(
+        &mut self,
+        key: &str,
+        nonce: u64,
+        tx_hash: String,
+        max_fee_per_gas: u128,
+        max_priority_fee_per_gas: u128,
+        now: u64,
+    ) {
+        if let Some(pending) = self
+            .wallets
+            .get_mut(key)
+            .and_then(|w| w.pending.get_mut(&nonce))
+        {
+            pending.status = PendingStatus::Submitted;
+            pending.tx_hash = Some(tx_hash);
+            pending.max_fee_per_gas = Some(max_fee_per_gas);
+            pending.max_priority_fee_per_gas = Some(max_priority_fee_per_gas);
+            pending.submitted_at = Some(now);
+        }
+    }
+
+    pub fn record_call(&mut self, key: &str, nonce: u64, call: TransactionCall) {
+        if let Some(pending) = self
+            .wallets
+            .get_mut(key)
+            .and_then(|w| w.pending.get_mut(&nonce))
+        {
+            pending.call = Some(call);
+        }
+    }
+
+    /// Looks up the submission currently holding `tx_hash`. Hashes this
+    /// manager never recorded, and ones that have since been replaced, are
+    /// not found.
+    pub fn find_submitted(&self, key: &str, tx_hash: &str) -> Option<&PendingTransaction> {
+        self.wallets.get(key)?.pending.values().find(|pending| {
+            pending.status == PendingStatus::Submitted
+                && pending
+                    .tx_hash
+                    .as_deref()
+                    .is_some_and(|hash| hash.eq_ignore_ascii_case(tx_hash))
+        })
+    }
+
+    /// Releases a nonce whose transaction never reached the network. The most
+    /// recent reservation is handed back; anything older becomes a gap that
+    /// the next reconciliation fills with a cancel transaction.
+    pub fn mark_failed(&mut self, key: &str, nonce: u64) {
+        let Some(wallet) = self.wallets.get_mut(key) else {
+            return;
+        };
+
+        if nonce + 1 == wallet.next_nonce && nonce >= wallet.confirmed_nonce {
+            wallet.pending.remove(&nonce);
+            wallet.next_nonce = nonce;
+        } else if let Some(pending) = wallet.pending.get_mut(&nonce) {
+            pending.status = PendingStatus::Failed;
+        }
+    }
+
+    pub fn mark_cancel(&mut self, key: &str, nonce: u64, now: u64) {
+        let Some(wallet) = self.wallets.get_mut(key) else {
+            return;
+        };
+
+        let pending = wallet.pending.entry(nonce).or_insert(PendingTransaction {
+            nonce,
+            status: PendingStatus::Reserved,
+            tx_hash: None,
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            is_cancel: true,
+            reserved_at: now,
+            submitted_at: None,
+            call: None,
+        });
+        pending.status = PendingStatus::Reserved;
+        pending.is_cancel = true;
+        pending.reserved_at = now;
+    }
+
+    /// Applies the on-chain `latest` transaction count. Every nonce below it
+    /// is final, so those entries are dropped. Returns the nonces that must be
+    /// filled with cancel transactions and the submissions that have been
+    /// waiting longer than `stale_after_ns`.
+    pub fn reconcile(
+        &mut self,
+        key: &str,
+        confirmed_nonce: u64,
+        now: u64,
+        stale_after_ns: u64,
+    ) -> ReconcileReport {
+        let mut report = ReconcileReport::default();
+        let Some(wallet) = self.wallets.get_mut(key) else {
+            return report;
+        };
+
+        let still_pending = wallet.pending.split_off(&confirmed_nonce);
+        report.confirmed = std::mem::replace(&mut wallet.pending, still_pending)
+            .into_values()
+            .collect();
+
+        wallet.confirmed_nonce = confirmed_nonce;
+        wallet.next_nonce = wallet.next_nonce.max(confirmed_nonce);
+        wallet.last_reconciled_at = Some(now);
+
+        // Nonces below `tracked_from` were already in the mempool when tracking
+        // started and belong to transactions this manager never saw.
+        for nonce in confirmed_nonce.max(wallet.tracked_from)..wallet.next_nonce {
+            match wallet.pending.get(&nonce) {
+                None => report.gaps.push(nonce),
+                Some(pending) => match pending.status {
+                    PendingStatus::Failed => report.gaps.push(nonce),
+                    PendingStatus::Reserved
+                        if now.saturating_sub(pending.reserved_at) > stale_after_ns =>
+                    {
+                        report.gaps.push(nonce)
+                    }
+                    PendingStatus::Submitted
+                        if now.saturating_sub(pending.submitted_at.unwrap_or(now))
+                            > stale_after_ns =>
+                    {
+                        report.stuck.push(pending.clone())
+                    }
+                    _ => {}
+                },
+            }
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "1:0xabc";
+
+    fn call() -> TransactionCall {
+        TransactionCall {
+            to: "0xdef".to_string(),
+            value: "0x1".to_string(),
+            data: "0x".to_string(),
+            gas_limit: 21_000,
+        }
+    }
+
+    fn submitted(manager: &mut NonceManager, tx_hash: &str) -> u64 {
+        let nonce = manager.reserve(KEY, 0).unwrap();
+        manager.mark_submitted(KEY, nonce, tx_hash.to_string(), 10, 1, 0);
+        manager.record_call(KEY, nonce, call());
+        nonce
+    }
+
+    #[test]
+    fn find_submitted_matches_only_recorded_hashes() {
+        let mut manager = NonceManager::default();
+        manager.track(KEY, 5);
+        let nonce = submitted(&mut manager, "0xAA");
+
+        let pending = manager.find_submitted(KEY, "0xaa").unwrap();
+        assert_eq!(pending.nonce, nonce);
+        assert_eq!(pending.call, Some(call()));
+        assert!(manager.find_submitted(KEY, "0xbb").is_none());
+        assert!(manager.find_submitted("1:0xother", "0xaa").is_none());
+    }
+
+    #[test]
+    fn replaced_hash_is_no_longer_found() {
+        let mut manager = NonceManager::default();
+        manager.track(KEY, 0);
+        let nonce = submitted(&mut manager, "0xaa");
+        manager.mark_submitted(KEY, nonce, "0xbb".to_string(), 20, 2, 1);
+
+        assert!(manager.find_submitted(KEY, "0xaa").is_none());
+        let pending = manager.find_submitted(KEY, "0xbb").unwrap();
+        assert_eq!(pending.max_fee_per_gas, Some(20));
+        assert_eq!(pending.call, Some(call()));
+    }
+
+    #[test]
+    fn reservations_never_share_a_nonce() {
+        let mut manager = NonceManager::default();
+        assert!(manager.reserve(KEY, 0).is_err());
+
+        manager.track(KEY, 7);
+        assert_eq!(manager.reserve(KEY, 0), Ok(7));
+        assert_eq!(manager.reserve(KEY, 0), Ok(8));
+
+        // A lower chain count never moves the local counter back.
+        manager.track(KEY, 3);
+        assert_eq!(manager.reserve(KEY, 0), Ok(9));
+    }
+
+    #[test]
+    fn failed_latest_nonce_is_handed_back() {
+        let mut manager = NonceManager::default();
+        manager.track(KEY, 0);
+        let first = manager.reserve(KEY, 0).unwrap();
+        let second = manager.reserve(KEY, 0).unwrap();
+
+        manager.mark_failed(KEY, second);
+        assert_eq!(manager.reserve(KEY, 0), Ok(second));
+
+        manager.mark_failed(KEY, first);
+        let wallet = manager.wallet(KEY).unwrap();
+        assert_eq!(wallet.next_nonce, second + 1);
+        assert_eq!(wallet.pending[&first].status, PendingStatus::Failed);
+    }
+
+    #[test]
+    fn reconcile_reports_gaps_and_stuck_submissions() {
+        let mut manager = NonceManager::default();
+        manager.track(KEY, 0);
+        submitted(&mut manager, "0xaa");
+        let failed = manager.reserve(KEY, 0).unwrap();
+        submitted(&mut manager, "0xbb");
+        let stale = manager.reserve(KEY, 0).unwrap();
+        manager.reserve(KEY, 80).unwrap();
+        manager.mark_failed(KEY, failed);
+
+        let report = manager.reconcile(KEY, 1, 100, 50);
+        assert_eq!(report.confirmed.len(), 1);
+        assert_eq!(report.confirmed[0].nonce, 0);
+        assert_eq!(report.gaps, vec![failed, stale]);
+        assert_eq!(report.stuck.len(), 1);
+        assert_eq!(report.stuck[0].tx_hash.as_deref(), Some("0xbb"));
+
+        let wallet = manager.wallet(KEY).unwrap();
+        assert_eq!(wallet.confirmed_nonce, 1);
+        assert_eq!(wallet.last_reconciled_at, Some(100));
+        assert!(!wallet.pending.contains_key(&0));
+    }
+
+    #[test]
+    fn reconcile_skips_nonces_from_before_tracking() {
+        let mut manager = NonceManager::default();
+        manager.track(KEY, 5);
+        let report = manager.reconcile(KEY, 2, 0, 50);
+        assert!(report.gaps.is_empty());
+        assert_eq!(manager.wallet(KEY).unwrap().next_nonce, 5);
+    }
+
+    #[test]
+    fn reserved_nonce_is_not_a_submission() {
+        let mut manager = NonceManager::default();
+        manager.track(KEY, 0);
+        manager.reserve(KEY, 0).unwrap();
+        assert!(manager.find_submitted(KEY, "").is_none());
+    }
+}
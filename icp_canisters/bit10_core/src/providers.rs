@@ -0,0 +1,458 @@
+use crate::transform::without_solana_context;
+use candid::{CandidType, Deserialize};
+use std::collections::BTreeMap;
+
+/// Consecutive disagreements with the quorum after which a provider is flagged.
+pub const DEFAULT_FLAG_AFTER: u32 = 3;
+
+/// Placeholder replaced by the provider's `api_key` in its URL and header
+/// values, so a key can be rotated without touching the endpoint.
+pub const API_KEY_PLACEHOLDER: &str = "{api_key}";
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct RpcProvider {
+    pub name: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    /// Secret substituted for `{api_key}`. Never returned by queries.
+    pub api_key: Option<String>,
+}
+
+impl RpcProvider {
+    pub fn new(name: &str, url: impl Into<String>) -> Self {
+        RpcProvider {
+            name: name.to_string(),
+            url: url.into(),
+            headers: Vec::new(),
+            api_key: None,
+        }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// A URL that embeds `{api_key}` points nowhere until a key is set.
+    /// Header keys are left out instead, so those providers stay callable.
+    pub fn needs_api_key(&self) -> bool {
+        self.api_key.is_none() && self.url.contains(API_KEY_PLACEHOLDER)
+    }
+
+    pub fn resolved_url(&self) -> String {
+        self.resolve(&self.url)
+    }
+
+    /// Headers that need a key are left out until one is set, so keyless
+    /// defaults still reach endpoints with a free tier.
+    pub fn resolved_headers(&self) -> Vec<(String, String)> {
+        self.headers
+            .iter()
+            .filter(|(_, value)| self.api_key.is_some() || !value.contains(API_KEY_PLACEHOLDER))
+            .map(|(name, value)| (name.clone(), self.resolve(value)))
+            .collect()
+    }
+
+    fn resolve(&self, template: &str) -> String {
+        template.replace(API_KEY_PLACEHOLDER, self.api_key.as_deref().unwrap_or_default())
+    }
+
+    pub fn view(&self) -> RpcProviderView {
+        RpcProviderView {
+            name: self.name.clone(),
+            url: self.url.clone(),
+            header_names: self.headers.iter().map(|(name, _)| name.clone()).collect(),
+            has_api_key: self.api_key.is_some(),
+        }
+    }
+}
+
+/// What queries return for a provider: header values may carry secrets, so
+/// only their names are shown.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RpcProviderView {
+    pub name: String,
+    pub url: String,
+    pub header_names: Vec<String>,
+    pub has_api_key: bool,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ProviderSetView {
+    pub providers: Vec<RpcProviderView>,
+    pub quorum: u8,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, Default, PartialEq, Eq, serde::Serialize)]
+pub struct ProviderHealth {
+    pub agreed: u64,
+    pub disagreed: u64,
+    pub failed: u64,
+    pub consecutive_disagreements: u32,
+    pub last_disagreement_at: Option<u64>,
+    /// Set once `consecutive_disagreements` reaches the set's threshold and
+    /// kept until cleared, so a provider that recovers still gets looked at.
+    pub flagged_at: Option<u64>,
+}
+
+/// A group of endpoints for one chain. Reads go to every provider and only
+/// succeed when at least `quorum` of them return the same normalized result.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct ProviderSet {
+    providers: Vec<RpcProvider>,
+    quorum: u8,
+    flag_after: u32,
+    health: BTreeMap<String, ProviderHealth>,
+}
+
+impl ProviderSet {
+    pub fn new(providers: Vec<RpcProvider>, quorum: u8) -> Result<Self, String> {
+        if providers.is_empty() {
+            return Err("A provider set needs at least one provider".to_string());
+        }
+        if quorum == 0 || quorum as usize > providers.len() {
+            return Err(format!(
+                "Quorum must be between 1 and {}, got {}",
+                providers.len(),
+                quorum
+            ));
+        }
+        for (i, provider) in providers.iter().enumerate() {
+            if providers[..i].iter().any(|p| p.name == provider.name) {
+                return Err(format!("Duplicate provider name: {}", provider.name));
+            }
+        }
+
+        Ok(ProviderSet {
+            providers,
+            quorum,
+            flag_after: DEFAULT_FLAG_AFTER,
+            health: BTreeMap::new(),
+        })
+    }
+
+    /// Swaps in a new endpoint list. Providers that keep their name keep
+    /// their health and, when no new key is given, their API key, so URLs can
+    /// be edited without resending secrets.
+    pub fn replace_providers(&mut self, mut providers: Vec<RpcProvider>, quorum: u8) -> Result<(), String> {
+        for provider in &mut providers {
+            if provider.api_key.is_none() {
+                provider.api_key = self
+                    .providers
+                    .iter()
+                    .find(|p| p.name == provider.name)
+                    .and_then(|p| p.api_key.clone());
+            }
+        }
+
+        let mut replacement = ProviderSet::new(providers, quorum)?;
+        replacement.flag_after = self.flag_after;
+        replacement.health = std::mem::take(&mut self.health)
+            .into_iter()
+            .filter(|(name, _)| replacement.providers.iter().any(|p| &p.name == name))
+            .collect();
+        *self = replacement;
+        Ok(())
+    }
+
+    /// Sets, rotates or, with `None`, removes a provider's API key.
+    pub fn set_api_key(&mut self, name: &str, api_key: Option<String>) -> Result<(), String> {
+        let provider = self
+            .providers
+            .iter_mut()
+            .find(|p| p.name == name)
+            .ok_or_else(|| format!("Unknown provider: {}", name))?;
+        provider.api_key = api_key;
+        Ok(())
+    }
+
+    pub fn view(&self) -> ProviderSetView {
+        ProviderSetView {
+            providers: self.providers.iter().map(RpcProvider::view).collect(),
+            quorum: self.quorum,
+        }
+    }
+
+    pub fn providers(&self) -> &[RpcProvider] {
+        &self.providers
+    }
+
+    /// The providers requests can actually be sent to, in order. Those still
+    /// waiting for an API key are skipped rather than called with an empty
+    /// one.
+    pub fn callable(&self) -> Vec<RpcProvider> {
+        self.providers
+            .iter()
+            .filter(|p| !p.needs_api_key())
+            .cloned()
+            .collect()
+    }
+
+    /// Writes and reads whose answer legitimately differs between nodes
+    /// (fee estimates, pending nonces) go to this provider alone: the first
+    /// callable one, or the first overall when none is.
+    pub fn primary(&self) -> &RpcProvider {
+        self.providers
+            .iter()
+            .find(|p| !p.needs_api_key())
+            .unwrap_or(&self.providers[0])
+    }
+
+    pub fn quorum(&self) -> u8 {
+        self.quorum
+    }
+
+    pub fn health(&self) -> Vec<(String, ProviderHealth)> {
+        self.providers
+            .iter()
+            .map(|p| (p.name.clone(), self.health.get(&p.name).cloned().unwrap_or_default()))
+            .collect()
+    }
+
+    pub fn flagged(&self) -> Vec<String> {
+        self.health
+            .iter()
+            .filter(|(_, health)| health.flagged_at.is_some())
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    pub fn clear_flag(&mut self, name: &str) -> Result<(), String> {
+        let health = self
+            .health
+            .get_mut(name)
+            .ok_or_else(|| format!("Unknown provider: {}", name))?;
+        health.flagged_at = None;
+        health.consecutive_disagreements = 0;
+        Ok(())
+    }
+
+    /// Picks the normalized result that at least `quorum` providers agree on
+    /// and updates every provider's health. When no value reaches the quorum
+    /// nobody can be blamed, so only failures are recorded.
+    pub fn tally(&mut self, responses: Vec<(String, Result<String, String>)>, now: u64) -> Result<String, String> {
+        let mut counts: BTreeMap<&str, u8> = BTreeMap::new();
+        for (_, response) in &responses {
+            if let Ok(value) = response {
+                *counts.entry(value.as_str()).or_default() += 1;
+            }
+        }
+
+        let mut winners = counts.iter().filter(|(_, count)| **count >= self.quorum);
+        let winner = match (winners.next(), winners.next()) {
+            (Some((value, _)), None) => Some(value.to_string()),
+            _ => None,
+        };
+
+        for (name, response) in &responses {
+            let health = self.health.entry(name.clone()).or_default();
+            match (response, &winner) {
+                (Err(_), _) => health.failed += 1,
+                (Ok(value), Some(winner)) if value == winner => {
+                    health.agreed += 1;
+                    health.consecutive_disagreements = 0;
+                }
+                (Ok(_), Some(_)) => {
+                    health.disagreed += 1;
+                    health.consecutive_disagreements += 1;
+                    health.last_disagreement_at = Some(now);
+                    if health.consecutive_disagreements >= self.flag_after && health.flagged_at.is_none() {
+                        health.flagged_at = Some(now);
+                    }
+                }
+                (Ok(_), None) => {}
+            }
+        }
+
+        winner.ok_or_else(|| no_quorum_error(self.quorum, &responses))
+    }
+}
+
+/// Per-chain provider sets as configured by a canister's controllers. Chains
+/// that were never configured, or were reset, fall back to the canister's
+/// compiled-in defaults, which carry no secrets.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RpcConfig<K: Ord>(BTreeMap<K, ProviderSet>);
+
+impl<K: Ord> Default for RpcConfig<K> {
+    fn default() -> Self {
+        RpcConfig(BTreeMap::new())
+    }
+}
+
+impl<K: Ord + Clone> RpcConfig<K> {
+    pub fn get_or_insert_with(&mut self, chain: K, default: impl FnOnce() -> ProviderSet) -> &mut ProviderSet {
+        self.0.entry(chain).or_insert_with(default)
+    }
+
+    pub fn set_providers(
+        &mut self,
+        chain: K,
+        providers: Vec<RpcProvider>,
+        quorum: u8,
+        default: impl FnOnce() -> ProviderSet,
+    ) -> Result<(), String> {
+        self.get_or_insert_with(chain, default).replace_providers(providers, quorum)
+    }
+
+    pub fn set_api_key(
+        &mut self,
+        chain: K,
+        provider: &str,
+        api_key: Option<String>,
+        default: impl FnOnce() -> ProviderSet,
+    ) -> Result<(), String> {
+        self.get_or_insert_with(chain, default).set_api_key(provider, api_key)
+    }
+
+    /// Drops the chain's configuration, including its keys and health.
+    pub fn reset(&mut self, chain: &K) {
+        self.0.remove(chain);
+    }
+
+    pub fn view(&self) -> Vec<(K, ProviderSetView)> {
+        self.0
+            .iter()
+            .map(|(chain, set)| (chain.clone(), set.view()))
+            .collect()
+    }
+
+    /// Outcall tallies for every chain that has been used since the last
+    /// reset. Chains still on their defaults and never read have none.
+    pub fn health(&self) -> Vec<(K, Vec<(String, ProviderHealth)>)> {
+        self.0
+            .iter()
+            .map(|(chain, set)| (chain.clone(), set.health()))
+            .collect()
+    }
+}
+
+fn no_quorum_error(quorum: u8, responses: &[(String, Result<String, String>)]) -> String {
+    let summary: Vec<String> = responses
+        .iter()
+        .map(|(name, response)| match response {
+            Ok(value) => format!("{}: {}", name, truncate(value)),
+            Err(e) => format!("{}: error {}", name, truncate(e)),
+        })
+        .collect();
+    format!(
+        "RPC providers did not reach a quorum of {}: {}",
+        quorum,
+        summary.join("; ")
+    )
+}
+
+fn truncate(value: &str) -> &str {
+    match value.char_indices().nth(120) {
+        Some((end, _)) => &value[..end],
+        None => value,
+    }
+}
+
+/// Reduces a JSON-RPC response body to its `result`, serialized with sorted
+/// keys, so responses that differ only in `id` or key order compare equal.
+/// Solana's per-node `context` is dropped. A JSON-RPC error becomes an `Err`
+/// and does not count as a vote.
+pub fn normalize_json_rpc(body: &str) -> Result<String, String> {
+    let response: serde_json::Value =
+        serde_json::from_str(body).map_err(|e| format!("Invalid JSON-RPC response: {}", e))?;
+
+    if let Some(error) = response.get("error") {
+        return Err(format!("RPC error: {}", error));
+    }
+    let result = response
+        .get("result")
+        .ok_or("JSON-RPC response has no result")?;
+    serde_json::to_string(&without_solana_context(result))
+        .map_err(|e| format!("Failed to serialize result: {}", e))
+}
+
+/// Wraps a tallied result back into a response body so the existing parsers
+/// can read it unchanged.
+pub fn json_rpc_body(result: &str) -> String {
+    format!(r#"{{"jsonrpc":"2.0","id":1,"result":{}}}"#, result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keyed_set() -> ProviderSet {
+        ProviderSet::new(
+            vec![
+                RpcProvider::new("ankr", "https://rpc.example/{api_key}"),
+                RpcProvider::new("public", "https://public.example"),
+                RpcProvider::new("tatum", "https://tatum.example").with_header("x-api-key", API_KEY_PLACEHOLDER),
+            ],
+            2,
+        )
+        .unwrap()
+    }
+
+    fn responses(values: [Result<&str, &str>; 3]) -> Vec<(String, Result<String, String>)> {
+        ["ankr", "public", "tatum"]
+            .into_iter()
+            .zip(values)
+            .map(|(name, value)| (name.to_string(), value.map(str::to_string).map_err(str::to_string)))
+            .collect()
+    }
+
+    #[test]
+    fn tally_needs_a_quorum() {
+        let mut set = keyed_set();
+        assert_eq!(set.tally(responses([Ok("1"), Ok("1"), Err("timeout")]), 0), Ok("1".to_string()));
+        assert!(set.tally(responses([Ok("1"), Ok("2"), Err("timeout")]), 0).is_err());
+
+        let health: BTreeMap<String, ProviderHealth> = set.health().into_iter().collect();
+        assert_eq!(health["ankr"].agreed, 1);
+        assert_eq!(health["tatum"].failed, 2);
+        // Without a quorum nobody is blamed for disagreeing.
+        assert_eq!(health["public"].disagreed, 0);
+    }
+
+    #[test]
+    fn tally_rejects_two_values_reaching_the_quorum() {
+        let mut set = ProviderSet::new(
+            vec![RpcProvider::new("a", "https://a.example"), RpcProvider::new("b", "https://b.example")],
+            1,
+        )
+        .unwrap();
+        let responses = vec![("a".to_string(), Ok("1".to_string())), ("b".to_string(), Ok("2".to_string()))];
+        assert!(set.tally(responses, 0).is_err());
+    }
+
+    #[test]
+    fn repeated_disagreement_flags_a_provider() {
+        let mut set = keyed_set();
+        for now in 1..=DEFAULT_FLAG_AFTER as u64 {
+            assert!(set.tally(responses([Ok("1"), Ok("1"), Ok("2")]), now).is_ok());
+        }
+        assert_eq!(set.flagged(), vec!["tatum"]);
+
+        // Agreeing again resets the streak but keeps the flag until cleared.
+        set.tally(responses([Ok("1"), Ok("1"), Ok("1")]), 10).unwrap();
+        assert_eq!(set.flagged(), vec!["tatum"]);
+        set.clear_flag("tatum").unwrap();
+        assert!(set.flagged().is_empty());
+    }
+
+    #[test]
+    fn providers_waiting_for_a_url_key_are_skipped() {
+        let mut set = keyed_set();
+        assert_eq!(set.primary().name, "public");
+        let names: Vec<String> = set.callable().into_iter().map(|p| p.name).collect();
+        assert_eq!(names, vec!["public", "tatum"]);
+
+        set.set_api_key("ankr", Some("secret".to_string())).unwrap();
+        assert_eq!(set.primary().name, "ankr");
+        assert_eq!(set.primary().resolved_url(), "https://rpc.example/secret");
+        assert_eq!(set.callable().len(), 3);
+    }
+
+    #[test]
+    fn primary_falls_back_to_the_first_provider() {
+        let set = ProviderSet::new(vec![RpcProvider::new("ankr", "https://rpc.example/{api_key}")], 1).unwrap();
+        assert!(set.callable().is_empty());
+        assert_eq!(set.primary().name, "ankr");
+    }
+}
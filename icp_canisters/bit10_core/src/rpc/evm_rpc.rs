@@ -0,0 +1,226 @@
+use crate::fees::{self, Eip1559Fees, FeeConfig, FeeHistory};
+use crate::rpc::http::JsonRpc;
+
+pub async fn get_transaction_count(rpc: &(impl JsonRpc + ?Sized), address: &str) -> Result<u64, String> {
+    transaction_count_at(rpc, address, "pending").await
+}
+
+pub async fn get_confirmed_transaction_count(rpc: &(impl JsonRpc + ?Sized), address: &str) -> Result<u64, String> {
+    transaction_count_at(rpc, address, "latest").await
+}
+
+async fn transaction_count_at(rpc: &(impl JsonRpc + ?Sized), address: &str, block_tag: &str) -> Result<u64, String> {
+    let json_payload = format!(
+        r#"{{"jsonrpc": "2.0", "method": "eth_getTransactionCount", "params": ["{}", "{}"], "id": 1}}"#,
+        address, block_tag
+    );
+    
+    match rpc.call(json_payload).await {
+        Ok(body_str) => {
+            let response: serde_json::Value = serde_json::from_str(&body_str)
+                .map_err(|e| format!("Failed to parse nonce response: {}", e))?;
+                
+            if let Some(result) = response.get("result").and_then(|v| v.as_str()) {
+                let count_hex = result.strip_prefix("0x").unwrap_or(result);
+                let count = u64::from_str_radix(count_hex, 16)
+                    .map_err(|e| format!("Failed to parse nonce hex: {}", e))?;
+                Ok(count)
+            } else {
+                Err("Failed to get transaction count from response".to_string())
+            }
+        }
+        Err(e) => Err(format!("Failed to get transaction count: {}", e)),
+    }
+}
+
+pub async fn get_gas_price(rpc: &(impl JsonRpc + ?Sized)) -> Result<(u128, u128), String> {
+    let json_payload = r#"{"jsonrpc": "2.0", "method": "eth_gasPrice", "params": [], "id": 1}"#
+        .to_string();
+
+    match rpc.call(json_payload).await {
+        Ok(result_str) => {
+            let response: serde_json::Value = serde_json::from_str(&result_str)
+                .map_err(|e| format!("Failed to parse gas price response: {}", e))?;
+
+            if let Some(gas_price_hex) = response.get("result").and_then(|v| v.as_str()) {
+                let gas_price =
+                    u128::from_str_radix(gas_price_hex.strip_prefix("0x").unwrap_or(""), 16)
+                        .map_err(|e| format!("Failed to parse gas price hex: {}", e))?;
+
+                let max_priority_fee_per_gas = 2_000_000_000u128; // 2 gwei
+                let max_fee_per_gas = gas_price + max_priority_fee_per_gas;
+
+                Ok((max_fee_per_gas, max_priority_fee_per_gas))
+            } else {
+                Err("eth_gasPrice returned no result".to_string())
+            }
+        }
+        Err(e) => Err(format!("RPC call for gas price failed: {}", e)),
+    }
+}
+
+pub async fn get_transaction_by_hash(rpc: &(impl JsonRpc + ?Sized), tx_hash: &str) -> Result<serde_json::Value, String> {
+    let json_payload = format!(
+        r#"{{"jsonrpc": "2.0", "method": "eth_getTransactionByHash", "params": ["{}"], "id": 1}}"#,
+        tx_hash
+    );
+    
+    match rpc.call(json_payload).await {
+        Ok(result_str) => {
+            let response: serde_json::Value = serde_json::from_str(&result_str)
+                .map_err(|e| format!("Failed to parse transaction response: {}", e))?;
+
+            if let Some(result) = response.get("result") {
+                if result.is_null() {
+                    return Err("Transaction not found (null result)".to_string());
+                }
+                Ok(result.clone())
+            } else if let Some(error) = response.get("error") {
+                Err(format!("RPC error: {}", error))
+            } else {
+                Err("No result or error in transaction response".to_string())
+            }
+        }
+        Err(e) => Err(format!("RPC call for transaction data failed: {}", e)),
+    }
+}
+
+pub async fn get_transaction_receipt(rpc: &(impl JsonRpc + ?Sized), tx_hash: &str) -> Result<serde_json::Value, String> {
+    let json_payload = format!(
+        r#"{{"jsonrpc": "2.0", "method": "eth_getTransactionReceipt", "params": ["{}"], "id": 1}}"#,
+        tx_hash
+    );
+    
+    match rpc.call(json_payload).await {
+        Ok(result_str) => {
+            let response: serde_json::Value = serde_json::from_str(&result_str)
+                .map_err(|e| format!("Failed to parse receipt response: {}", e))?;
+
+            if let Some(result) = response.get("result") {
+                if result.is_null() {
+                    return Err("Transaction receipt not found (null result)".to_string());
+                }
+                Ok(result.clone())
+            } else if let Some(error) = response.get("error") {
+                Err(format!("RPC error: {}", error))
+            } else {
+                Err("No result or error in receipt response".to_string())
+            }
+        }
+        Err(e) => Err(format!("RPC call for transaction receipt failed: {}", e)),
+    }
+}
+
+pub async fn send_raw_transaction(rpc: &(impl JsonRpc + ?Sized), raw_tx_hex: &str) -> Result<String, String> {
+    let json_payload = format!(
+        r#"{{"jsonrpc": "2.0", "method": "eth_sendRawTransaction", "params": ["{}"], "id": 1}}"#,
+        raw_tx_hex
+    );
+
+    match rpc.call(json_payload).await {
+        Ok(response_body) => {
+            match serde_json::from_str::<serde_json::Value>(&response_body) {
+                Ok(response) => {
+                    if let Some(error) = response.get("error") {
+                        if let Some(message) = error.get("message").and_then(|m| m.as_str()) {
+                            if message.contains("already known")
+                                || message.contains("ALREADY_EXISTS")
+                                || message.contains("replacement transaction underpriced")
+                            {
+                                return Err("Transaction already known".to_string());
+                            }
+                        }
+                        return Err(format!("RPC error: {}", error));
+                    }
+
+                    if let Some(result) = response.get("result") {
+                        if let Some(tx_hash_str) = result.as_str() {
+                            return Ok(tx_hash_str.to_string());
+                        }
+                    }
+
+                    Err("No transaction hash in response".to_string())
+                }
+                Err(e) => Err(format!("Failed to parse RPC response: {}", e)),
+            }
+        }
+        Err(e) => Err(format!("Failed to send raw transaction: {}", e)),
+    }
+}
+
+pub async fn get_fee_history(rpc: &(impl JsonRpc + ?Sized), config: &FeeConfig) -> Result<FeeHistory, String> {
+    let body = rpc
+        .call(fees::fee_history_payload(config))
+        .await
+        .map_err(|e| format!("RPC call for fee history failed: {}", e))?;
+    fees::parse_fee_history(&body)
+}
+
+pub async fn get_eip1559_fees(rpc: &(impl JsonRpc + ?Sized), config: &FeeConfig) -> Result<Eip1559Fees, String> {
+    let history = get_fee_history(rpc, config).await?;
+    fees::suggest_fees(&history, config)
+}
+
+pub async fn estimate_gas(
+    rpc: &(impl JsonRpc + ?Sized),
+    from: &str,
+    to: &str,
+    value_hex: &str,
+    data_hex: &str,
+) -> Result<u128, String> {
+    let body = rpc
+        .call(fees::estimate_gas_payload(from, to, value_hex, data_hex))
+        .await
+        .map_err(|e| format!("RPC call for gas estimate failed: {}", e))?;
+    fees::parse_estimate_gas(&body)
+}
+
+pub async fn get_balance(rpc: &(impl JsonRpc + ?Sized), address: &str) -> Result<u128, String> {
+    let json_payload = format!(
+        r#"{{"jsonrpc": "2.0", "method": "eth_getBalance", "params": ["{}", "latest"], "id": 1}}"#,
+        address
+    );
+
+    let body = rpc
+        .call(json_payload)
+        .await
+        .map_err(|e| format!("RPC call for balance failed: {}", e))?;
+    parse_quantity(&body, "balance")
+}
+
+/// Calls `balanceOf(owner)` on an ERC-20 style contract.
+pub async fn get_token_balance(rpc: &(impl JsonRpc + ?Sized), token_address: &str, owner: &str) -> Result<u128, String> {
+    let owner_hex = owner.strip_prefix("0x").unwrap_or(owner).to_ascii_lowercase();
+    if owner_hex.len() != 40 {
+        return Err(format!("Invalid owner address: {}", owner));
+    }
+    let json_payload = format!(
+        r#"{{"jsonrpc": "2.0", "method": "eth_call", "params": [{{"to": "{}", "data": "0x70a08231{:0>64}"}}, "latest"], "id": 1}}"#,
+        token_address, owner_hex
+    );
+
+    let body = rpc
+        .call(json_payload)
+        .await
+        .map_err(|e| format!("RPC call for token balance failed: {}", e))?;
+    parse_quantity(&body, "token balance")
+}
+
+fn parse_quantity(body: &str, what: &str) -> Result<u128, String> {
+    let response: serde_json::Value = serde_json::from_str(body)
+        .map_err(|e| format!("Failed to parse {} response: {}", what, e))?;
+
+    if let Some(error) = response.get("error") {
+        return Err(format!("RPC error: {}", error));
+    }
+
+    let result = response
+        .get("result")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| format!("No {} in response", what))?;
+    let hex = result.strip_prefix("0x").unwrap_or(result).trim_start_matches('0');
+    if hex.is_empty() {
+        return Ok(0);
+    }
+    u128::from_str_radix(hex, 16).map_err(|e| format!("Failed to parse {} hex: {}", what, e))
+}
@@ -0,0 +1,166 @@
+use ic_cdk::api::management_canister::http_request::{
+    CanisterHttpRequestArgument, HttpHeader, HttpMethod, TransformContext,
+};
+use crate::cycles;
+use crate::providers::{normalize_json_rpc, RpcProvider};
+pub use crate::providers::json_rpc_body;
+use crate::transform::ResponseShape;
+use num_traits::ToPrimitive;
+use std::future::Future;
+
+pub async fn make_http_request(request: CanisterHttpRequestArgument) -> Result<Vec<u8>, String> {
+    const MAX_RETRIES: u8 = 5;
+    
+    let mut retries = 0;
+    
+    let cycles = outcall_cost(&request);
+    while retries < MAX_RETRIES {
+        match ic_cdk::api::management_canister::http_request::http_request(
+            request.clone(),
+            cycles,
+        )
+        .await
+        {
+            Ok((response,)) => {
+                if response.status.0.to_u64().unwrap_or(0) == 200 {
+                    return Ok(response.body);
+                } else {
+                    return Err(format!("HTTP error: status {}", response.status));
+                }
+            }
+            Err((_, msg)) if msg.contains("No consensus") || msg.contains("SysTransient") => {
+                retries += 1;
+                continue;
+            }
+            Err((_, msg)) => return Err(msg),
+        }
+    }
+    
+    Err(format!(
+        "Failed after {} retries. Last error: No consensus could be reached",
+        MAX_RETRIES
+    ))
+}
+
+/// Cycles the IC charges for `request`, from its size and response limit.
+pub fn outcall_cost(request: &CanisterHttpRequestArgument) -> u128 {
+    let bytes = cycles::request_bytes(
+        &request.url,
+        request.headers.iter().map(|h| (h.name.as_str(), h.value.as_str())),
+        request.body.as_deref(),
+        request
+            .transform
+            .as_ref()
+            .map(|t| (t.function.0.method.as_str(), t.context.as_slice())),
+    );
+    cycles::http_request_cost(bytes, request.max_response_bytes)
+}
+
+/// POSTs a JSON-RPC payload to `url` through the canister's `transform`
+/// function, which is expected to apply `transform::canonical_body`.
+pub async fn post_json_rpc(url: String, json_payload: String) -> Result<String, String> {
+    post_json_rpc_with_headers(url, Vec::new(), json_payload).await
+}
+
+/// Like `post_json_rpc`, with extra headers such as an API key.
+pub async fn post_json_rpc_with_headers(
+    url: String,
+    headers: Vec<(String, String)>,
+    json_payload: String,
+) -> Result<String, String> {
+    let mut request_headers = vec![HttpHeader {
+        name: "Content-Type".to_string(),
+        value: "application/json".to_string(),
+    }];
+    request_headers.extend(
+        headers
+            .into_iter()
+            .map(|(name, value)| HttpHeader { name, value }),
+    );
+
+    let transform_context =
+        TransformContext::from_name("transform".to_string(), ResponseShape::for_payload(&json_payload).to_context());
+
+    let request = CanisterHttpRequestArgument {
+        url,
+        method: HttpMethod::POST,
+        body: Some(json_payload.into_bytes()),
+        max_response_bytes: Some(8192),
+        transform: Some(transform_context),
+        headers: request_headers,
+    };
+
+    match make_http_request(request).await {
+        Ok(body) => {
+            let body_str = String::from_utf8(body)
+                .map_err(|e| format!("Failed to decode response: {}", e))?;
+            Ok(body_str)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Anything that can answer a JSON-RPC request with a response body. A plain
+/// URL posts to that one endpoint; canisters implement it for provider sets
+/// that fan a read out and require a quorum.
+pub trait JsonRpc {
+    fn call(&self, json_payload: String) -> impl Future<Output = Result<String, String>>;
+}
+
+impl JsonRpc for str {
+    fn call(&self, json_payload: String) -> impl Future<Output = Result<String, String>> {
+        post_json_rpc(self.to_string(), json_payload)
+    }
+}
+
+impl JsonRpc for String {
+    fn call(&self, json_payload: String) -> impl Future<Output = Result<String, String>> {
+        post_json_rpc(self.clone(), json_payload)
+    }
+}
+
+impl JsonRpc for RpcProvider {
+    fn call(&self, json_payload: String) -> impl Future<Output = Result<String, String>> {
+        post_json_rpc_with_headers(self.resolved_url(), self.resolved_headers(), json_payload)
+    }
+}
+
+/// Sends each request to the first provider that answers, moving on only
+/// when the outcall itself fails. A JSON-RPC error is an answer, so a
+/// rejected broadcast is reported rather than retried elsewhere.
+pub struct Failover(pub Vec<RpcProvider>);
+
+impl JsonRpc for Failover {
+    fn call(&self, json_payload: String) -> impl Future<Output = Result<String, String>> {
+        async move {
+            let mut errors = Vec::with_capacity(self.0.len());
+            for provider in &self.0 {
+                match provider.call(json_payload.clone()).await {
+                    Ok(body) => return Ok(body),
+                    Err(e) => errors.push(format!("{}: {}", provider.name, e)),
+                }
+            }
+            if errors.is_empty() {
+                return Err("No RPC provider can be called; set the missing API keys".to_string());
+            }
+            Err(format!("Every RPC provider failed: {}", errors.join("; ")))
+        }
+    }
+}
+
+/// Sends `json_payload` to every provider in turn and returns each one's
+/// normalized response, ready for `ProviderSet::tally`.
+pub async fn query_providers(
+    providers: &[RpcProvider],
+    json_payload: &str,
+) -> Vec<(String, Result<String, String>)> {
+    let mut responses = Vec::with_capacity(providers.len());
+    for provider in providers {
+        let response = provider
+            .call(json_payload.to_string())
+            .await
+            .and_then(|body| normalize_json_rpc(&body));
+        responses.push((provider.name.clone(), response));
+    }
+    responses
+}
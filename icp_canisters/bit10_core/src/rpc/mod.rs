@@ -0,0 +1,4 @@
+pub mod evm_rpc;
+pub mod http;
+pub mod solana_rpc;
+pub mod sui_rpc;
\ No newline at end of file
@@ -0,0 +1,237 @@
+use crate::rpc::http::JsonRpc;
+
+pub async fn get_recent_blockhash(rpc: &(impl JsonRpc + ?Sized)) -> Result<String, String> {
+    let json_payload = r#"{"jsonrpc":"2.0","id":1,"method":"getLatestBlockhash"}"#.to_string();
+
+    match rpc.call(json_payload).await {
+        Ok(body_str) => {
+            let response: serde_json::Value = serde_json::from_str(&body_str)
+                .map_err(|e| format!("Failed to parse blockhash response: {}", e))?;
+
+            if let Some(blockhash) = response
+                .get("result")
+                .and_then(|r| r.get("value"))
+                .and_then(|v| v.get("blockhash"))
+                .and_then(|b| b.as_str())
+            {
+                Ok(blockhash.to_string())
+            } else {
+                Err("Failed to get blockhash from response".to_string())
+            }
+        }
+        Err(e) => Err(format!("Failed to get recent blockhash: {}", e)),
+    }
+}
+
+pub async fn send_transaction(rpc: &(impl JsonRpc + ?Sized), serialized_transaction: &str) -> Result<String, String> {
+    let json_payload = format!(
+        r#"{{"jsonrpc":"2.0","id":1,"method":"sendTransaction","params":["{}",{{"encoding":"base64"}}]}}"#,
+        serialized_transaction
+    );
+
+    match rpc.call(json_payload).await {
+        Ok(body_str) => {
+            let response: serde_json::Value = serde_json::from_str(&body_str)
+                .map_err(|e| format!("Failed to parse send transaction response: {}", e))?;
+
+            if let Some(error) = response.get("error") {
+                return Err(format!("Transaction error: {}", error));
+            }
+
+            if let Some(result) = response.get("result").and_then(|r| r.as_str()) {
+                Ok(result.to_string())
+            } else {
+                Err("Failed to get transaction signature from response".to_string())
+            }
+        }
+        Err(e) => Err(format!("Failed to send transaction: {}", e)),
+    }
+}
+
+pub async fn get_account_info(rpc: &(impl JsonRpc + ?Sized), address: &str) -> Result<Option<serde_json::Value>, String> {
+    let json_payload = format!(
+        r#"{{"jsonrpc":"2.0","id":1,"method":"getAccountInfo","params":["{}",{{"encoding":"base64"}}]}}"#,
+        address
+    );
+
+    match rpc.call(json_payload).await {
+        Ok(body_str) => {
+            let response: serde_json::Value = serde_json::from_str(&body_str)
+                .map_err(|e| format!("Failed to parse account info response: {}", e))?;
+
+            if let Some(result) = response.get("result").and_then(|r| r.get("value")) {
+                if result.is_null() {
+                    Ok(None)
+                } else {
+                    Ok(Some(result.clone()))
+                }
+            } else {
+                Err("Failed to get account info from response".to_string())
+            }
+        }
+        Err(e) => Err(format!("Failed to get account info: {}", e)),
+    }
+}
+
+pub async fn get_transaction_by_hash(rpc: &(impl JsonRpc + ?Sized), tx_hash: &str) -> Result<serde_json::Value, String> {
+    const MAX_RETRIES: u8 = 10;
+    const RETRY_DELAY_MS: u64 = 2000;
+
+    for attempt in 0..MAX_RETRIES {
+        let json_payload = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"getTransaction","params":["{}",{{"encoding":"json","maxSupportedTransactionVersion":0,"commitment":"confirmed"}}]}}"#,
+            tx_hash
+        );
+
+        match rpc.call(json_payload).await {
+            Ok(result_str) => {
+                let response: serde_json::Value = serde_json::from_str(&result_str)
+                    .map_err(|e| format!("Failed to parse transaction response: {}", e))?;
+
+                if let Some(result) = response.get("result") {
+                    if result.is_null() {
+                        if attempt < MAX_RETRIES - 1 {
+                            ic_cdk::println!(
+                                "Transaction not found, retrying in {}ms... (attempt {}/{})",
+                                RETRY_DELAY_MS,
+                                attempt + 1,
+                                MAX_RETRIES
+                            );
+
+                            let delay_nanos = RETRY_DELAY_MS * 1_000_000;
+                            let start = ic_cdk::api::time();
+                            while ic_cdk::api::time() - start < delay_nanos {}
+                            continue;
+                        } else {
+                            return Err(
+                                "Transaction not found after multiple retries".to_string()
+                            );
+                        }
+                    }
+                    return Ok(result.clone());
+                } else if let Some(error) = response.get("error") {
+                    return Err(format!("RPC error: {}", error));
+                } else {
+                    return Err("No result or error in transaction response".to_string());
+                }
+            }
+            Err(e) => {
+                if attempt < MAX_RETRIES - 1 {
+                    ic_cdk::println!(
+                        "RPC call failed: {}, retrying... (attempt {}/{})",
+                        e,
+                        attempt + 1,
+                        MAX_RETRIES
+                    );
+
+                    let delay_nanos = RETRY_DELAY_MS * 1_000_000;
+                    let start = ic_cdk::api::time();
+                    while ic_cdk::api::time() - start < delay_nanos {}
+                    continue;
+                } else {
+                    return Err(format!(
+                        "RPC call for transaction data failed after {} retries: {}",
+                        MAX_RETRIES, e
+                    ));
+                }
+            }
+        }
+    }
+
+    Err("Failed to get transaction after all retries".to_string())
+}
+
+pub async fn get_transaction_status(rpc: &(impl JsonRpc + ?Sized), tx_hash: &str) -> Result<String, String> {
+    let json_payload = format!(
+        r#"{{"jsonrpc":"2.0","id":1,"method":"getSignatureStatuses","params":[["{}"],{{"searchTransactionHistory":true}}]}}"#,
+        tx_hash
+    );
+
+    match rpc.call(json_payload).await {
+        Ok(result_str) => {
+            let response: serde_json::Value = serde_json::from_str(&result_str)
+                .map_err(|e| format!("Failed to parse status response: {}", e))?;
+
+            if let Some(result) = response
+                .get("result")
+                .and_then(|r| r.get("value"))
+                .and_then(|v| v.get(0))
+            {
+                if result.is_null() {
+                    return Ok("not_found".to_string());
+                }
+
+                if let Some(confirmation_status) =
+                    result.get("confirmationStatus").and_then(|s| s.as_str())
+                {
+                    return Ok(confirmation_status.to_string());
+                }
+
+                return Ok("unknown".to_string());
+            }
+
+            Err("Failed to get transaction status from response".to_string())
+        }
+        Err(e) => Err(format!("Failed to get transaction status: {}", e)),
+    }
+}
+pub async fn get_balance(rpc: &(impl JsonRpc + ?Sized), address: &str) -> Result<u64, String> {
+    let json_payload = format!(
+        r#"{{"jsonrpc":"2.0","id":1,"method":"getBalance","params":["{}",{{"commitment":"finalized"}}]}}"#,
+        address
+    );
+
+    let body_str = rpc
+        .call(json_payload)
+        .await
+        .map_err(|e| format!("Failed to get balance: {}", e))?;
+    let response: serde_json::Value = serde_json::from_str(&body_str)
+        .map_err(|e| format!("Failed to parse balance response: {}", e))?;
+
+    if let Some(error) = response.get("error") {
+        return Err(format!("RPC error: {}", error));
+    }
+
+    response
+        .get("result")
+        .and_then(|r| r.get("value"))
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| "Failed to get balance from response".to_string())
+}
+
+/// Sums the raw amounts of every token account `owner` holds for `mint`.
+pub async fn get_token_balance(rpc: &(impl JsonRpc + ?Sized), owner: &str, mint: &str) -> Result<u128, String> {
+    let json_payload = format!(
+        r#"{{"jsonrpc":"2.0","id":1,"method":"getTokenAccountsByOwner","params":["{}",{{"mint":"{}"}},{{"encoding":"jsonParsed","commitment":"finalized"}}]}}"#,
+        owner, mint
+    );
+
+    let body_str = rpc
+        .call(json_payload)
+        .await
+        .map_err(|e| format!("Failed to get token accounts: {}", e))?;
+    let response: serde_json::Value = serde_json::from_str(&body_str)
+        .map_err(|e| format!("Failed to parse token accounts response: {}", e))?;
+
+    if let Some(error) = response.get("error") {
+        return Err(format!("RPC error: {}", error));
+    }
+
+    let accounts = response
+        .get("result")
+        .and_then(|r| r.get("value"))
+        .and_then(|v| v.as_array())
+        .ok_or("Failed to get token accounts from response")?;
+
+    let mut total: u128 = 0;
+    for account in accounts {
+        let amount = account
+            .pointer("/account/data/parsed/info/tokenAmount/amount")
+            .and_then(|a| a.as_str())
+            .ok_or("Token account is missing its amount")?
+            .parse::<u128>()
+            .map_err(|e| format!("Failed to parse token amount: {}", e))?;
+        total = total.saturating_add(amount);
+    }
+    Ok(total)
+}
@@ -0,0 +1,28 @@
+use crate::rpc::http::JsonRpc;
+
+/// Total balance of `coin_type` (e.g. `0x2::sui::SUI`) across all of `owner`'s coin objects.
+pub async fn get_balance(rpc: &(impl JsonRpc + ?Sized), owner: &str, coin_type: &str) -> Result<u128, String> {
+    let json_payload = format!(
+        r#"{{"jsonrpc":"2.0","id":1,"method":"suix_getBalance","params":["{}","{}"]}}"#,
+        owner, coin_type
+    );
+
+    let body_str = rpc
+        .call(json_payload)
+        .await
+        .map_err(|e| format!("Failed to get balance: {}", e))?;
+    let response: serde_json::Value = serde_json::from_str(&body_str)
+        .map_err(|e| format!("Failed to parse balance response: {}", e))?;
+
+    if let Some(error) = response.get("error") {
+        return Err(format!("RPC error: {}", error));
+    }
+
+    response
+        .get("result")
+        .and_then(|r| r.get("totalBalance"))
+        .and_then(|b| b.as_str())
+        .ok_or("Failed to get balance from response")?
+        .parse::<u128>()
+        .map_err(|e| format!("Failed to parse balance: {}", e))
+}
@@ -0,0 +1,641 @@
+use candid::{CandidType, Nat, Principal};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+const DAY_NS: u64 = 24 * 60 * 60 * 1_000_000_000;
+/// How long a transfer may stay `Executing` before a controller can settle it
+/// by hand. Long enough that a call still in flight has returned or trapped.
+pub const EXECUTION_TIMEOUT_NS: u64 = 30 * 60 * 1_000_000_000;
+
+/// Limits for one asset. Amounts are in the asset's base unit.
+#[derive(CandidType, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct AssetPolicy {
+    /// Most that may leave in any 24 hours, counting transfers that are still
+    /// waiting. `None` means no limit.
+    pub daily_limit: Option<Nat>,
+    /// Destinations transfers may go to. Empty means any destination.
+    pub allowed_destinations: Vec<String>,
+    /// Transfers above this wait `delay_seconds` before they can be executed.
+    pub delay_threshold: Option<Nat>,
+    pub delay_seconds: u64,
+    /// Transfers above this need `required_approvals` approvers to sign off.
+    pub approval_threshold: Option<Nat>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SpendingPolicy {
+    pub assets: BTreeMap<String, AssetPolicy>,
+    /// Applied to assets without their own entry.
+    pub default_policy: AssetPolicy,
+    pub approvers: Vec<Principal>,
+    pub required_approvals: u8,
+    /// How long an approved policy change waits before it can be applied.
+    pub policy_change_delay_seconds: Option<u64>,
+}
+
+impl SpendingPolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        let mut approvers = self.approvers.clone();
+        approvers.sort();
+        approvers.dedup();
+        if approvers.len() != self.approvers.len() {
+            return Err("approvers must not contain duplicates".to_string());
+        }
+        if self.required_approvals as usize > self.approvers.len() {
+            return Err(format!(
+                "required_approvals ({}) exceeds the number of approvers ({})",
+                self.required_approvals,
+                self.approvers.len()
+            ));
+        }
+
+        let uses_approvals = self
+            .assets
+            .values()
+            .chain(std::iter::once(&self.default_policy))
+            .any(|policy| policy.approval_threshold.is_some());
+        if uses_approvals && self.required_approvals == 0 {
+            return Err("required_approvals must be at least 1 when an approval threshold is set".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn asset_policy(&self, asset: &str) -> &AssetPolicy {
+        self.assets.get(asset).unwrap_or(&self.default_policy)
+    }
+
+    /// Approvals a change to this policy needs. Once approvers are set they
+    /// have to sign off on every change, even if no transfer needs them.
+    fn change_approvals(&self) -> u8 {
+        if self.approvers.is_empty() {
+            0
+        } else {
+            self.required_approvals.max(1)
+        }
+    }
+}
+
+/// A replacement policy waiting for the current approvers and delay.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PolicyChange {
+    pub policy: SpendingPolicy,
+    pub proposed_by: Principal,
+    pub proposed_at: u64,
+    pub executable_at: u64,
+    pub required_approvals: u8,
+    pub approvals: Vec<Principal>,
+}
+
+impl PolicyChange {
+    pub fn is_ready(&self, now: u64) -> bool {
+        self.approvals.len() >= self.required_approvals as usize && now >= self.executable_at
+    }
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum PolicyChangeOutcome {
+    Applied,
+    Pending(Box<PolicyChange>),
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum TransferStatus {
+    /// Waiting for approvals or for its delay to pass.
+    Pending,
+    Executing,
+    Executed,
+    Failed,
+    Cancelled,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PendingTransfer<T> {
+    pub id: u64,
+    pub asset: String,
+    pub destination: String,
+    pub amount: Nat,
+    /// What the canister needs to execute the transfer later.
+    pub args: T,
+    pub requested_by: Principal,
+    pub requested_at: u64,
+    pub executable_at: u64,
+    pub required_approvals: u8,
+    pub approvals: Vec<Principal>,
+    pub status: TransferStatus,
+    /// Transaction hash or block once executed, the error if it failed.
+    pub result: Option<String>,
+    pub updated_at: u64,
+}
+
+impl<T> PendingTransfer<T> {
+    pub fn is_ready(&self, now: u64) -> bool {
+        self.status == TransferStatus::Pending
+            && self.approvals.len() >= self.required_approvals as usize
+            && now >= self.executable_at
+    }
+
+    fn is_finished(&self) -> bool {
+        matches!(
+            self.status,
+            TransferStatus::Executed | TransferStatus::Failed | TransferStatus::Cancelled
+        )
+    }
+
+    fn counts_against_limit(&self, now: u64) -> bool {
+        matches!(
+            self.status,
+            TransferStatus::Pending | TransferStatus::Executing | TransferStatus::Executed
+        ) && self.requested_at.saturating_add(DAY_NS) > now
+    }
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum TransferOutcome<T> {
+    Executed(String),
+    Pending(PendingTransfer<T>),
+}
+
+/// Outgoing transfers checked against a `SpendingPolicy`. A transfer is
+/// recorded when it is requested, so it counts against the daily limit from
+/// then on. Finished transfers are dropped a day after their last update,
+/// once they can no longer count against the limit.
+///
+/// The policy itself is changed through the same approvals and delay, so a
+/// single controller cannot lift the limits it is meant to enforce.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TransferQueue<T> {
+    policy: SpendingPolicy,
+    next_id: u64,
+    transfers: BTreeMap<u64, PendingTransfer<T>>,
+    policy_change: Option<PolicyChange>,
+}
+
+impl<T> Default for TransferQueue<T> {
+    fn default() -> Self {
+        TransferQueue {
+            policy: SpendingPolicy::default(),
+            next_id: 0,
+            transfers: BTreeMap::new(),
+            policy_change: None,
+        }
+    }
+}
+
+impl<T: Clone> TransferQueue<T> {
+    pub fn policy(&self) -> &SpendingPolicy {
+        &self.policy
+    }
+
+    pub fn policy_change(&self) -> Option<&PolicyChange> {
+        self.policy_change.as_ref()
+    }
+
+    /// Applies `policy` straight away while the current policy has no
+    /// approvers and no change delay, otherwise queues it. A new proposal
+    /// replaces any earlier one along with its approvals.
+    pub fn propose_policy(
+        &mut self,
+        policy: SpendingPolicy,
+        proposed_by: Principal,
+        now: u64,
+    ) -> Result<PolicyChangeOutcome, String> {
+        policy.validate()?;
+        let required_approvals = self.policy.change_approvals();
+        let delay_seconds = self.policy.policy_change_delay_seconds.unwrap_or(0);
+        if required_approvals == 0 && delay_seconds == 0 {
+            self.policy = policy;
+            self.policy_change = None;
+            return Ok(PolicyChangeOutcome::Applied);
+        }
+
+        let change = PolicyChange {
+            policy,
+            proposed_by,
+            proposed_at: now,
+            executable_at: now.saturating_add(delay_seconds.saturating_mul(1_000_000_000)),
+            required_approvals,
+            approvals: Vec::new(),
+        };
+        self.policy_change = Some(change.clone());
+        Ok(PolicyChangeOutcome::Pending(Box::new(change)))
+    }
+
+    /// Only approvers of the policy in force can approve its replacement.
+    pub fn approve_policy_change(&mut self, approver: Principal) -> Result<PolicyChange, String> {
+        if !self.is_approver(&approver) {
+            return Err("Caller is not an approver".to_string());
+        }
+        let change = self.policy_change.as_mut().ok_or("No policy change is pending")?;
+        if change.approvals.contains(&approver) {
+            return Err("The policy change was already approved by this principal".to_string());
+        }
+        change.approvals.push(approver);
+        Ok(change.clone())
+    }
+
+    pub fn apply_policy_change(&mut self, now: u64) -> Result<(), String> {
+        let change = self.policy_change.as_ref().ok_or("No policy change is pending")?;
+        if change.approvals.len() < change.required_approvals as usize {
+            return Err(format!(
+                "The policy change has {} of {} approvals",
+                change.approvals.len(),
+                change.required_approvals
+            ));
+        }
+        if now < change.executable_at {
+            return Err(format!("The policy change cannot be applied before {}", change.executable_at));
+        }
+        self.policy = change.policy.clone();
+        self.policy_change = None;
+        Ok(())
+    }
+
+    pub fn cancel_policy_change(&mut self) -> Result<PolicyChange, String> {
+        self.policy_change.take().ok_or_else(|| "No policy change is pending".to_string())
+    }
+
+    pub fn is_approver(&self, principal: &Principal) -> bool {
+        self.policy.approvers.contains(principal)
+    }
+
+    pub fn get(&self, id: u64) -> Option<&PendingTransfer<T>> {
+        self.transfers.get(&id)
+    }
+
+    pub fn pending(&self) -> Vec<PendingTransfer<T>> {
+        self.transfers
+            .values()
+            .filter(|t| matches!(t.status, TransferStatus::Pending | TransferStatus::Executing))
+            .cloned()
+            .collect()
+    }
+
+    /// Spent or reserved for `asset` in the last 24 hours.
+    pub fn spent_today(&self, asset: &str, now: u64) -> Nat {
+        self.transfers
+            .values()
+            .filter(|t| t.asset == asset && t.counts_against_limit(now))
+            .fold(Nat::from(0u64), |acc, t| acc + t.amount.clone())
+    }
+
+    /// Checks the transfer against the policy and records it. The caller
+    /// executes it right away if the returned transfer `is_ready`.
+    pub fn request(
+        &mut self,
+        asset: &str,
+        destination: &str,
+        amount: Nat,
+        args: T,
+        requested_by: Principal,
+        now: u64,
+    ) -> Result<PendingTransfer<T>, String> {
+        self.prune(now);
+        let policy = self.policy.asset_policy(asset).clone();
+
+        if !policy.allowed_destinations.is_empty()
+            && !policy.allowed_destinations.iter().any(|d| d == destination)
+        {
+            return Err(format!("Destination {} is not allowed for {}", destination, asset));
+        }
+
+        if let Some(limit) = &policy.daily_limit {
+            let spent = self.spent_today(asset, now);
+            if spent.clone() + amount.clone() > *limit {
+                return Err(format!(
+                    "Daily limit for {} exceeded. Limit: {}, already used: {}, requested: {}",
+                    asset, limit, spent, amount
+                ));
+            }
+        }
+
+        let executable_at = match &policy.delay_threshold {
+            Some(threshold) if amount > *threshold => {
+                now.saturating_add(policy.delay_seconds.saturating_mul(1_000_000_000))
+            }
+            _ => now,
+        };
+        let required_approvals = match &policy.approval_threshold {
+            Some(threshold) if amount > *threshold => self.policy.required_approvals,
+            _ => 0,
+        };
+
+        let id = self.next_id;
+        self.next_id += 1;
+        let transfer = PendingTransfer {
+            id,
+            asset: asset.to_string(),
+            destination: destination.to_string(),
+            amount,
+            args,
+            requested_by,
+            requested_at: now,
+            executable_at,
+            required_approvals,
+            approvals: Vec::new(),
+            status: TransferStatus::Pending,
+            result: None,
+            updated_at: now,
+        };
+        self.transfers.insert(id, transfer.clone());
+        Ok(transfer)
+    }
+
+    pub fn approve(&mut self, id: u64, approver: Principal, now: u64) -> Result<PendingTransfer<T>, String> {
+        if !self.is_approver(&approver) {
+            return Err("Caller is not an approver".to_string());
+        }
+        let transfer = self.pending_mut(id)?;
+        if transfer.requested_by == approver {
+            return Err(format!("Transfer {} cannot be approved by the principal that requested it", id));
+        }
+        if transfer.approvals.contains(&approver) {
+            return Err(format!("Transfer {} was already approved by this principal", id));
+        }
+        transfer.approvals.push(approver);
+        transfer.updated_at = now;
+        Ok(transfer.clone())
+    }
+
+    pub fn cancel(&mut self, id: u64, now: u64) -> Result<PendingTransfer<T>, String> {
+        let transfer = self.pending_mut(id)?;
+        transfer.status = TransferStatus::Cancelled;
+        transfer.updated_at = now;
+        Ok(transfer.clone())
+    }
+
+    /// Marks a ready transfer as executing so it cannot be started twice.
+    pub fn start_execution(&mut self, id: u64, now: u64) -> Result<PendingTransfer<T>, String> {
+        let transfer = self.pending_mut(id)?;
+        if transfer.approvals.len() < transfer.required_approvals as usize {
+            return Err(format!(
+                "Transfer {} has {} of {} approvals",
+                id,
+                transfer.approvals.len(),
+                transfer.required_approvals
+            ));
+        }
+        if now < transfer.executable_at {
+            return Err(format!("Transfer {} cannot be executed before {}", id, transfer.executable_at));
+        }
+        transfer.status = TransferStatus::Executing;
+        transfer.updated_at = now;
+        Ok(transfer.clone())
+    }
+
+    /// A failed transfer no longer counts against the daily limit.
+    pub fn finish_execution(&mut self, id: u64, result: &Result<String, String>, now: u64) {
+        if let Some(transfer) = self.transfers.get_mut(&id) {
+            let (status, detail) = match result {
+                Ok(tx) => (TransferStatus::Executed, tx.clone()),
+                Err(e) => (TransferStatus::Failed, e.clone()),
+            };
+            transfer.status = status;
+            transfer.result = Some(detail);
+            transfer.updated_at = now;
+        }
+    }
+
+    /// Settles a transfer left `Executing` by a trap between its calls.
+    /// `result` is what the caller found on chain: the transaction if the
+    /// transfer went out, an error if it did not. Only allowed once
+    /// `EXECUTION_TIMEOUT_NS` has passed, so it cannot race a live execution.
+    pub fn resolve_stuck(
+        &mut self,
+        id: u64,
+        result: &Result<String, String>,
+        now: u64,
+    ) -> Result<PendingTransfer<T>, String> {
+        let transfer = self.transfers.get(&id).ok_or_else(|| format!("Transfer {} not found", id))?;
+        if transfer.status != TransferStatus::Executing {
+            return Err(format!("Transfer {} is {:?}", id, transfer.status));
+        }
+        if now < transfer.updated_at.saturating_add(EXECUTION_TIMEOUT_NS) {
+            return Err(format!("Transfer {} started executing too recently to be resolved", id));
+        }
+        self.finish_execution(id, result, now);
+        Ok(self.transfers[&id].clone())
+    }
+
+    /// Keeps `spent_today` from scanning every transfer ever made. Anything
+    /// still counting against the limit was updated within the last day, so
+    /// it is never dropped.
+    fn prune(&mut self, now: u64) {
+        self.transfers
+            .retain(|_, t| !t.is_finished() || t.updated_at.saturating_add(DAY_NS) > now);
+    }
+
+    fn pending_mut(&mut self, id: u64) -> Result<&mut PendingTransfer<T>, String> {
+        match self.transfers.get_mut(&id) {
+            Some(transfer) if transfer.status == TransferStatus::Pending => Ok(transfer),
+            Some(transfer) => Err(format!("Transfer {} is {:?}", id, transfer.status)),
+            None => Err(format!("Transfer {} not found", id)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: u64 = 1_000_000_000;
+
+    fn principal(id: u8) -> Principal {
+        Principal::from_slice(&[id])
+    }
+
+    fn nat(n: u64) -> Nat {
+        Nat::from(n)
+    }
+
+    fn queue(policy: SpendingPolicy) -> TransferQueue<()> {
+        let mut queue = TransferQueue::default();
+        assert_eq!(queue.propose_policy(policy, principal(0), 0), Ok(PolicyChangeOutcome::Applied));
+        queue
+    }
+
+    fn guarded_policy() -> SpendingPolicy {
+        SpendingPolicy {
+            default_policy: AssetPolicy {
+                daily_limit: Some(nat(100)),
+                approval_threshold: Some(nat(10)),
+                delay_threshold: Some(nat(50)),
+                delay_seconds: 60,
+                ..AssetPolicy::default()
+            },
+            approvers: vec![principal(1), principal(2), principal(3)],
+            required_approvals: 2,
+            policy_change_delay_seconds: Some(3600),
+            ..SpendingPolicy::default()
+        }
+    }
+
+    #[test]
+    fn validate_rejects_unsatisfiable_approvals() {
+        let mut policy = guarded_policy();
+        policy.required_approvals = 4;
+        assert!(policy.validate().is_err());
+
+        policy.required_approvals = 0;
+        assert!(policy.validate().is_err());
+
+        policy.approvers = vec![principal(1), principal(1)];
+        policy.required_approvals = 1;
+        assert!(policy.validate().is_err());
+    }
+
+    #[test]
+    fn small_transfers_are_ready_at_once() {
+        let mut queue = queue(guarded_policy());
+        let transfer = queue.request("ETH", "0xabc", nat(5), (), principal(0), 0).unwrap();
+        assert!(transfer.is_ready(0));
+        assert_eq!(transfer.required_approvals, 0);
+    }
+
+    #[test]
+    fn daily_limit_counts_pending_and_expires() {
+        let mut queue = queue(guarded_policy());
+        queue.request("ETH", "0xabc", nat(60), (), principal(0), 0).unwrap();
+        assert!(queue.request("ETH", "0xabc", nat(41), (), principal(0), SECOND).is_err());
+        queue.request("ETH", "0xabc", nat(40), (), principal(0), SECOND).unwrap();
+
+        assert_eq!(queue.spent_today("ETH", DAY_NS), nat(40));
+        queue.request("ETH", "0xabc", nat(60), (), principal(0), DAY_NS).unwrap();
+    }
+
+    #[test]
+    fn requesters_cannot_approve_their_own_transfers() {
+        let mut queue = queue(guarded_policy());
+        let transfer = queue.request("ETH", "0xabc", nat(60), (), principal(1), 0).unwrap();
+        let err = queue.approve(transfer.id, principal(1), 0).unwrap_err();
+        assert!(err.contains("requested it"));
+        queue.approve(transfer.id, principal(2), 0).unwrap();
+    }
+
+    #[test]
+    fn finished_transfers_are_dropped_after_a_day() {
+        let mut queue = queue(guarded_policy());
+        let executed = queue.request("ETH", "0xabc", nat(5), (), principal(0), 0).unwrap();
+        queue.start_execution(executed.id, 0).unwrap();
+        queue.finish_execution(executed.id, &Ok("0x1".to_string()), SECOND);
+        let waiting = queue.request("ETH", "0xabc", nat(60), (), principal(0), 0).unwrap();
+
+        queue.request("ETH", "0xabc", nat(1), (), principal(0), DAY_NS).unwrap();
+        assert!(queue.get(executed.id).is_some());
+
+        queue.request("ETH", "0xabc", nat(1), (), principal(0), DAY_NS + SECOND).unwrap();
+        assert!(queue.get(executed.id).is_none());
+        assert!(queue.get(waiting.id).is_some());
+    }
+
+    #[test]
+    fn limits_are_per_asset() {
+        let mut queue = queue(guarded_policy());
+        queue.request("ETH", "0xabc", nat(100), (), principal(0), 0).unwrap();
+        queue.request("usdc", "0xabc", nat(100), (), principal(0), 0).unwrap();
+        assert!(queue.request("ETH", "0xabc", nat(1), (), principal(0), 0).is_err());
+    }
+
+    #[test]
+    fn cancelled_and_failed_transfers_release_the_limit() {
+        let mut queue = queue(guarded_policy());
+        let cancelled = queue.request("ETH", "0xabc", nat(60), (), principal(0), 0).unwrap();
+        queue.cancel(cancelled.id, 0).unwrap();
+
+        let failed = queue.request("ETH", "0xabc", nat(8), (), principal(0), 0).unwrap();
+        queue.start_execution(failed.id, 0).unwrap();
+        queue.finish_execution(failed.id, &Err("rejected".to_string()), 0);
+
+        assert_eq!(queue.spent_today("ETH", 0), nat(0));
+    }
+
+    #[test]
+    fn destinations_are_enforced() {
+        let mut policy = guarded_policy();
+        policy.default_policy.allowed_destinations = vec!["0xabc".to_string()];
+        let mut queue = queue(policy);
+        assert!(queue.request("ETH", "0xdef", nat(1), (), principal(0), 0).is_err());
+        assert!(queue.request("ETH", "0xabc", nat(1), (), principal(0), 0).is_ok());
+    }
+
+    #[test]
+    fn large_transfers_wait_for_approvals_and_delay() {
+        let mut queue = queue(guarded_policy());
+        let transfer = queue.request("ETH", "0xabc", nat(60), (), principal(0), 0).unwrap();
+        assert_eq!(transfer.required_approvals, 2);
+        assert_eq!(transfer.executable_at, 60 * SECOND);
+
+        assert!(queue.approve(transfer.id, principal(9), 0).is_err());
+        assert!(queue.approve(transfer.id, principal(0), 0).is_err());
+        queue.approve(transfer.id, principal(1), 0).unwrap();
+        assert!(queue.approve(transfer.id, principal(1), 0).is_err());
+        assert!(queue.start_execution(transfer.id, 60 * SECOND).is_err());
+
+        queue.approve(transfer.id, principal(2), 0).unwrap();
+        assert!(queue.start_execution(transfer.id, 59 * SECOND).is_err());
+        queue.start_execution(transfer.id, 60 * SECOND).unwrap();
+        assert!(queue.start_execution(transfer.id, 60 * SECOND).is_err());
+    }
+
+    #[test]
+    fn policy_changes_need_current_approvers_and_delay() {
+        let mut queue = queue(guarded_policy());
+        let lifted = SpendingPolicy::default();
+
+        let change = match queue.propose_policy(lifted.clone(), principal(0), 0).unwrap() {
+            PolicyChangeOutcome::Pending(change) => *change,
+            PolicyChangeOutcome::Applied => panic!("change applied without approvals"),
+        };
+        assert_eq!(change.required_approvals, 2);
+        assert_eq!(change.executable_at, 3600 * SECOND);
+        assert_eq!(queue.policy(), &guarded_policy());
+
+        assert!(queue.approve_policy_change(principal(0)).is_err());
+        queue.approve_policy_change(principal(1)).unwrap();
+        assert!(queue.apply_policy_change(3600 * SECOND).is_err());
+        queue.approve_policy_change(principal(2)).unwrap();
+        assert!(queue.apply_policy_change(3599 * SECOND).is_err());
+
+        queue.apply_policy_change(3600 * SECOND).unwrap();
+        assert_eq!(queue.policy(), &lifted);
+        assert!(queue.policy_change().is_none());
+    }
+
+    #[test]
+    fn approvers_sign_off_even_without_approval_thresholds() {
+        let policy = SpendingPolicy {
+            approvers: vec![principal(1)],
+            ..SpendingPolicy::default()
+        };
+        let mut queue = queue(policy);
+        let outcome = queue.propose_policy(SpendingPolicy::default(), principal(0), 0).unwrap();
+        assert!(matches!(outcome, PolicyChangeOutcome::Pending(change) if change.required_approvals == 1));
+    }
+
+    #[test]
+    fn a_new_proposal_resets_approvals() {
+        let mut queue = queue(guarded_policy());
+        queue.propose_policy(SpendingPolicy::default(), principal(0), 0).unwrap();
+        queue.approve_policy_change(principal(1)).unwrap();
+        queue.propose_policy(SpendingPolicy::default(), principal(0), 0).unwrap();
+        assert!(queue.policy_change().unwrap().approvals.is_empty());
+
+        queue.cancel_policy_change().unwrap();
+        assert!(queue.apply_policy_change(u64::MAX).is_err());
+    }
+
+    #[test]
+    fn stuck_transfers_resolve_only_after_timeout() {
+        let mut queue = queue(guarded_policy());
+        let transfer = queue.request("ETH", "0xabc", nat(5), (), principal(0), 0).unwrap();
+        assert!(queue.resolve_stuck(transfer.id, &Err("stuck".to_string()), EXECUTION_TIMEOUT_NS).is_err());
+
+        queue.start_execution(transfer.id, SECOND).unwrap();
+        assert!(queue.resolve_stuck(transfer.id, &Err("stuck".to_string()), EXECUTION_TIMEOUT_NS).is_err());
+
+        let resolved = queue
+            .resolve_stuck(transfer.id, &Err("stuck".to_string()), SECOND + EXECUTION_TIMEOUT_NS)
+            .unwrap();
+        assert_eq!(resolved.status, TransferStatus::Failed);
+        assert_eq!(queue.spent_today("ETH", SECOND + EXECUTION_TIMEOUT_NS), nat(0));
+    }
+}
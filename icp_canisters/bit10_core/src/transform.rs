@@ -0,0 +1,210 @@
+use candid::Deserialize;
+use serde_json::{Map, Value};
+
+/// What an HTTP outcall's response looks like, carried to the canister's
+/// `transform` in the transform context. Every replica fetches the response on
+/// its own, so the transform rebuilds the body from the fields the canister
+/// reads, serialized with sorted keys. Anything else, such as the echoed `id`,
+/// timestamps or node versions, would otherwise break consensus.
+#[derive(Deserialize, serde::Serialize, Clone, Debug, PartialEq, Eq)]
+pub enum ResponseShape {
+    /// A JSON-RPC response to this method.
+    JsonRpc(String),
+    /// A plain JSON document reduced to these field paths.
+    Json(Vec<String>),
+}
+
+impl ResponseShape {
+    /// Reads the method from a JSON-RPC request payload.
+    pub fn for_payload(json_payload: &str) -> Self {
+        let method = serde_json::from_str::<Value>(json_payload)
+            .ok()
+            .and_then(|payload| payload.get("method").and_then(Value::as_str).map(str::to_string))
+            .unwrap_or_default();
+        ResponseShape::JsonRpc(method)
+    }
+
+    pub fn json(fields: &[&str]) -> Self {
+        ResponseShape::Json(fields.iter().map(|field| field.to_string()).collect())
+    }
+
+    pub fn to_context(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("Failed to encode response shape")
+    }
+}
+
+/// Canonical form of a response body. An empty or unknown context keeps the
+/// whole document but still sorts its keys; a body that is not JSON is
+/// returned unchanged.
+pub fn canonical_body(context: &[u8], body: &[u8]) -> Vec<u8> {
+    let Ok(document) = serde_json::from_slice::<Value>(body) else {
+        return body.to_vec();
+    };
+    let canonical = match serde_json::from_slice::<ResponseShape>(context) {
+        Ok(ResponseShape::JsonRpc(method)) => canonical_json_rpc(&method, &document),
+        Ok(ResponseShape::Json(fields)) if !fields.is_empty() => select(&document, &fields),
+        _ => document,
+    };
+    serde_json::to_vec(&canonical).unwrap_or_else(|_| body.to_vec())
+}
+
+fn canonical_json_rpc(method: &str, response: &Value) -> Value {
+    let mut canonical = Map::new();
+    canonical.insert("jsonrpc".to_string(), Value::from("2.0"));
+    canonical.insert("id".to_string(), Value::from(1));
+
+    if let Some(error) = response.get("error") {
+        let error = match error {
+            Value::Object(_) => select(error, &["code", "message"]),
+            other => other.clone(),
+        };
+        canonical.insert("error".to_string(), error);
+    } else if let Some(result) = response.get("result") {
+        let result = without_solana_context(result);
+        let result = match result_fields(method) {
+            Some(fields) => select(&result, fields),
+            None => result,
+        };
+        canonical.insert("result".to_string(), result);
+    }
+    Value::Object(canonical)
+}
+
+/// Solana wraps results in `{ context: { slot, apiVersion }, value }`. The
+/// context is whatever each node has seen, so only `value` is kept.
+pub(crate) fn without_solana_context(result: &Value) -> Value {
+    let mut result = result.clone();
+    if let Some(object) = result.as_object_mut() {
+        if object.contains_key("value") {
+            object.remove("context");
+        }
+    }
+    result
+}
+
+/// Fields of the `result` that the canisters read, per method. Methods that
+/// return a scalar, or a small object that is read whole, are not listed.
+fn result_fields(method: &str) -> Option<&'static [&'static str]> {
+    let fields: &'static [&'static str] = match method {
+        "eth_getTransactionByHash" => &[
+            "hash",
+            "from",
+            "to",
+            "value",
+            "input",
+            "nonce",
+            "gas",
+            "gasPrice",
+            "maxFeePerGas",
+            "maxPriorityFeePerGas",
+            "blockNumber",
+            "chainId",
+        ],
+        "eth_getTransactionReceipt" => &[
+            "transactionHash",
+            "from",
+            "to",
+            "status",
+            "blockNumber",
+            "gasUsed",
+            "effectiveGasPrice",
+            "contractAddress",
+            "logs[].address",
+            "logs[].topics",
+            "logs[].data",
+        ],
+        "eth_feeHistory" => &["oldestBlock", "baseFeePerGas", "gasUsedRatio", "reward"],
+        "eth_getBlockByNumber" => &["number", "hash", "timestamp", "baseFeePerGas"],
+        "getTransaction" => &[
+            "slot",
+            "blockTime",
+            "meta.err",
+            "meta.fee",
+            "meta.preBalances",
+            "meta.postBalances",
+            "meta.preTokenBalances",
+            "meta.postTokenBalances",
+            "meta.loadedAddresses",
+            "transaction.signatures",
+            "transaction.message.accountKeys",
+            "transaction.message.instructions",
+            "transaction.message.recentBlockhash",
+        ],
+        "getLatestBlockhash" => &["value.blockhash", "value.lastValidBlockHeight"],
+        // `confirmations` grows with every block, so it is dropped.
+        "getSignatureStatuses" => &["value[].slot", "value[].err", "value[].confirmationStatus"],
+        "suix_getBalance" => &["coinType", "coinObjectCount", "totalBalance"],
+        "suix_getAllBalances" => &["[].coinType", "[].coinObjectCount", "[].totalBalance"],
+        "suix_getCoins" => &[
+            "data[].coinType",
+            "data[].coinObjectId",
+            "data[].version",
+            "data[].digest",
+            "data[].balance",
+            "hasNextPage",
+            "nextCursor",
+        ],
+        "sui_executeTransactionBlock" => &["digest", "effects.status", "effects.transactionDigest"],
+        "unsafe_pay" | "unsafe_paySui" | "unsafe_splitCoin" | "unsafe_moveCall" => &["txBytes"],
+        _ => return None,
+    };
+    Some(fields)
+}
+
+/// Copies the given paths out of `value`. A path is a list of keys joined by
+/// `.`; `key[]` (or a bare `[]`) applies the rest of the path to every element
+/// of an array. Missing keys stay missing and explicit nulls stay null, so
+/// callers that test for either still see the same thing.
+pub fn select<S: AsRef<str>>(value: &Value, paths: &[S]) -> Value {
+    let mut selected = match value {
+        Value::Object(_) => Value::Object(Map::new()),
+        _ => Value::Null,
+    };
+    for path in paths {
+        let segments: Vec<&str> = path.as_ref().split('.').collect();
+        copy_path(value, &segments, &mut selected);
+    }
+    selected
+}
+
+fn copy_path(source: &Value, segments: &[&str], target: &mut Value) {
+    let Some((segment, rest)) = segments.split_first() else {
+        *target = source.clone();
+        return;
+    };
+    let (key, each) = match segment.strip_suffix("[]") {
+        Some(key) => (key, true),
+        None => (*segment, false),
+    };
+
+    let (source, target) = if key.is_empty() {
+        (source, target)
+    } else {
+        let Some(child) = source.get(key) else {
+            return;
+        };
+        if !target.is_object() {
+            *target = Value::Object(Map::new());
+        }
+        let target = target
+            .as_object_mut()
+            .expect("target is an object")
+            .entry(key.to_string())
+            .or_insert(Value::Null);
+        (child, target)
+    };
+
+    match (each, source.as_array()) {
+        (true, Some(items)) => {
+            if target.as_array().is_none_or(|targets| targets.len() != items.len()) {
+                *target = Value::Array(vec![Value::Null; items.len()]);
+            }
+            let targets = target.as_array_mut().expect("target is an array");
+            for (item, target) in items.iter().zip(targets.iter_mut()) {
+                copy_path(item, rest, target);
+            }
+        }
+        (true, None) => *target = source.clone(),
+        (false, _) => copy_path(source, rest, target),
+    }
+}
@@ -0,0 +1 @@
+pub mod token;
\ No newline at end of file
@@ -0,0 +1,53 @@
+use candid::{CandidType, Deserialize};
+
+/// A token listed by the buy, lending and DEX canisters. The DEX canisters
+/// always set `token_id` and `price_feed_id`; the others may leave them out
+/// and price a token through `price_feed_link` instead.
+#[derive(CandidType, Deserialize, Debug, Clone, serde::Serialize)]
+pub struct Token {
+    pub token_id: Option<String>,
+    pub token_name: String,
+    pub token_symbol: String,
+    pub token_address: Option<String>,
+    pub token_chain: String,
+    pub token_decimals: u8,
+    pub is_native: bool,
+    pub is_active: bool,
+    pub price_feed_id: Option<String>,
+    pub price_feed_link: Option<String>,
+}
+
+impl Token {
+    pub fn matches(&self, address: &str, chain: &str) -> bool {
+        self.token_address
+            .as_ref()
+            .map(|addr| {
+                addr.eq_ignore_ascii_case(address) && self.token_chain.eq_ignore_ascii_case(chain)
+            })
+            .unwrap_or(false)
+    }
+
+    pub fn has_id(&self, token_id: &str) -> bool {
+        self.token_id.as_deref() == Some(token_id)
+    }
+
+    /// The key of this token in the price feed canister.
+    pub fn feed_id(&self) -> Result<&str, String> {
+        self.price_feed_id
+            .as_deref()
+            .filter(|id| !id.trim().is_empty())
+            .ok_or_else(|| format!("Token {} has no price feed id", self.token_symbol))
+    }
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone, serde::Serialize)]
+pub struct Pair {
+    pub pool_id: String,
+    pub token_a_symbol: String,
+    pub token_a_chain: String,
+    pub token_a_token_id: String,
+    pub token_b_symbol: String,
+    pub token_b_chain: String,
+    pub token_b_token_id: String,
+    pub pair_type: String,
+}
@@ -0,0 +1,2 @@
+pub mod converters;
+pub mod validators;
\ No newline at end of file
@@ -2,6 +2,8 @@ use ic_cdk::api::management_canister::ecdsa::EcdsaPublicKeyResponse;
 use ic_ethereum_types::Address;
 use ic_secp256k1::{DerivationPath, PublicKey};
 
+/// A threshold-ECDSA public key together with its BIP32 chain code, so that
+/// per-principal keys can be derived from the canister's root key.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct EcdsaPublicKey {
     public_key: PublicKey,
@@ -9,6 +11,8 @@ pub struct EcdsaPublicKey {
 }
 
 impl EcdsaPublicKey {
+    /// Derives the child key the management canister signs with for
+    /// `derivation_path`.
     pub fn derive_new_public_key(&self, derivation_path: &DerivationPath) -> Self {
         let (dk, cc) = self.public_key.derive_subkey(derivation_path);
 
@@ -18,12 +22,13 @@ impl EcdsaPublicKey {
         }
     }
 
+    /// SEC1 encoding with the 0x04 prefix, as hashed into EVM addresses.
     pub fn public_key_bytes(&self) -> Vec<u8> {
-        self.public_key.serialize_sec1(false)
+        self.public_key.serialize_sec1(/*compressed=*/ false)
     }
-    
+
     pub fn public_key_compressed(&self) -> Vec<u8> {
-        self.public_key.serialize_sec1(true)
+        self.public_key.serialize_sec1(/*compressed=*/ true)
     }
 }
 
@@ -37,7 +42,7 @@ impl From<EcdsaPublicKeyResponse> for EcdsaPublicKey {
     fn from(value: EcdsaPublicKeyResponse) -> Self {
         EcdsaPublicKey {
             public_key: PublicKey::deserialize_sec1(&value.public_key)
-                .expect("BUG: invalid public key"),
+                .expect("BUG: invalid public key deserialization from management canister response"),
             chain_code: value.chain_code,
         }
     }
@@ -45,8 +50,11 @@ impl From<EcdsaPublicKeyResponse> for EcdsaPublicKey {
 
 impl From<&EcdsaPublicKey> for Address {
     fn from(value: &EcdsaPublicKey) -> Self {
-        let key_bytes = value.as_ref().serialize_sec1(false);
-        debug_assert_eq!(key_bytes[0], 0x04);
+        let key_bytes = value.as_ref().serialize_sec1(/*compressed=*/ false);
+        debug_assert_eq!(
+            key_bytes[0], 0x04,
+            "Public key must be uncompressed (0x04 prefix) for Ethereum address derivation"
+        );
         let hash = ic_sha3::Keccak256::hash(&key_bytes[1..]);
         let mut addr = [0u8; 20];
         addr[..].copy_from_slice(&hash[12..32]);
@@ -1,6 +1,8 @@
-use crate::types::network::Ed25519KeyName;
 use ic_ed25519::PublicKey;
-use sol_rpc_client::{ed25519::DerivationPath, IcRuntime};
+use sol_rpc_client::{
+    ed25519::{DerivationPath, Ed25519KeyId},
+    IcRuntime,
+};
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Ed25519ExtendedPublicKey {
@@ -26,10 +28,10 @@ impl Ed25519ExtendedPublicKey {
     }
 }
 
-pub async fn get_ed25519_public_key(key_name: Ed25519KeyName, derivation_path: &DerivationPath) -> Ed25519ExtendedPublicKey {
-    use sol_rpc_client::ed25519::Ed25519KeyId;
-    
-    let key_id: Ed25519KeyId = key_name.into();
+pub async fn get_ed25519_public_key(
+    key_id: Ed25519KeyId,
+    derivation_path: &DerivationPath,
+) -> Ed25519ExtendedPublicKey {
     let (pubkey, chain_code) = sol_rpc_client::ed25519::get_pubkey(
         &IcRuntime,
         None,
@@ -42,4 +44,4 @@ pub async fn get_ed25519_public_key(key_name: Ed25519KeyName, derivation_path: &
         public_key: PublicKey::deserialize_raw(&pubkey.to_bytes()).unwrap(),
         chain_code,
     }
-}
\ No newline at end of file
+}
@@ -1,53 +1,62 @@
-use crate::state::{lazy_call_ecdsa_public_key, read_state};
 use crate::wallet::ecdsa::EcdsaPublicKey;
 use candid::Principal;
+use ic_cdk::api::management_canister::ecdsa::EcdsaKeyId;
 use ic_ethereum_types::Address;
-use ic_secp256k1::{PublicKey, RecoveryId};
+use ic_secp256k1::{DerivationIndex, DerivationPath, PublicKey, RecoveryId};
 use serde_bytes::ByteBuf;
 
+/// Threshold-ECDSA wallet shared by every EVM chain (Base, BSC, Ethereum).
+///
+/// The canister's own wallet uses the root key with an empty derivation
+/// path; any other owner gets a per-principal derived key.
 #[derive(Debug, PartialEq, Eq, Clone)]
-pub struct EthereumWallet {
+pub struct EvmWallet {
     derived_public_key: EcdsaPublicKey,
+    owner: Principal,
+    key_id: EcdsaKeyId,
 }
 
-impl AsRef<PublicKey> for EthereumWallet {
+impl AsRef<PublicKey> for EvmWallet {
     fn as_ref(&self) -> &PublicKey {
         self.derived_public_key.as_ref()
     }
 }
 
-impl EthereumWallet {
-    pub async fn new(owner: Principal) -> Self {
-        let derived_public_key = derive_public_key(&owner, &lazy_call_ecdsa_public_key().await);
+impl EvmWallet {
+    pub fn new(owner: Principal, root_public_key: &EcdsaPublicKey, key_id: EcdsaKeyId) -> Self {
         Self {
-            derived_public_key,
+            derived_public_key: derive_public_key(&owner, root_public_key),
+            owner,
+            key_id,
         }
     }
 
-    pub async fn new_canister_wallet() -> Self {
-        let canister_public_key = lazy_call_ecdsa_public_key().await;
-        Self {
-            derived_public_key: canister_public_key,
-        }
+    pub fn new_canister_wallet(root_public_key: &EcdsaPublicKey, key_id: EcdsaKeyId) -> Self {
+        Self::new(ic_cdk::id(), root_public_key, key_id)
     }
 
-    pub fn ethereum_address(&self) -> Address {
+    pub fn address(&self) -> Address {
         Address::from(&self.derived_public_key)
     }
 
     pub async fn sign_with_ecdsa(&self, message_hash: [u8; 32]) -> ([u8; 64], RecoveryId) {
         use ic_cdk::api::management_canister::ecdsa::SignWithEcdsaArgument;
 
-        let derivation_path = vec![];
-        let key_id = read_state(|s| s.ecdsa_key_id());
+        let derivation_path = if self.owner == ic_cdk::id() {
+            vec![]
+        } else {
+            derivation_path(&self.owner)
+        };
+
         let (result,) =
             ic_cdk::api::management_canister::ecdsa::sign_with_ecdsa(SignWithEcdsaArgument {
                 message_hash: message_hash.to_vec(),
                 derivation_path,
-                key_id,
+                key_id: self.key_id.clone(),
             })
             .await
             .expect("failed to sign with ecdsa");
+
         let signature_length = result.signature.len();
         let signature = <[u8; 64]>::try_from(result.signature).unwrap_or_else(|_| {
             panic!(
@@ -55,6 +64,7 @@ impl EthereumWallet {
                 signature_length
             )
         });
+
         let recovery_id = self.compute_recovery_id(&message_hash, &signature);
         if recovery_id.is_x_reduced() {
             ic_cdk::trap("BUG: affine x-coordinate of r is reduced which is so unlikely to happen that it's probably a bug");
@@ -88,14 +98,17 @@ impl EthereumWallet {
 }
 
 fn derive_public_key(owner: &Principal, public_key: &EcdsaPublicKey) -> EcdsaPublicKey {
-    use ic_secp256k1::{DerivationIndex, DerivationPath};
-    let derivation_path = DerivationPath::new(
-        derivation_path(owner)
-            .into_iter()
-            .map(DerivationIndex)
-            .collect(),
-    );
-    public_key.derive_new_public_key(&derivation_path)
+    if *owner == ic_cdk::id() {
+        public_key.clone()
+    } else {
+        let derivation_path = DerivationPath::new(
+            derivation_path(owner)
+                .into_iter()
+                .map(DerivationIndex)
+                .collect(),
+        );
+        public_key.derive_new_public_key(&derivation_path)
+    }
 }
 
 fn derivation_path(owner: &Principal) -> Vec<Vec<u8>> {
@@ -107,4 +120,4 @@ fn derivation_path(owner: &Principal) -> Vec<Vec<u8>> {
     .iter()
     .map(|x| x.to_vec())
     .collect()
-}
\ No newline at end of file
+}
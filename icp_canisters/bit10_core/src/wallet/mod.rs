@@ -0,0 +1,4 @@
+pub mod ecdsa;
+pub mod ed25519;
+pub mod evm_wallet;
+pub mod solana_wallet;
\ No newline at end of file
@@ -1,7 +1,9 @@
 use crate::wallet::ed25519::Ed25519ExtendedPublicKey;
-use crate::state::state::{lazy_call_ed25519_public_key, read_state};
 use candid::Principal;
-use sol_rpc_client::{ed25519::{sign_message, DerivationPath}, IcRuntime};
+use sol_rpc_client::{
+    ed25519::{sign_message, DerivationPath, Ed25519KeyId},
+    IcRuntime,
+};
 use solana_message::Message;
 use solana_pubkey::Pubkey;
 use solana_signature::Signature;
@@ -11,12 +13,14 @@ use std::fmt::Display;
 pub struct SolanaAccount {
     pub ed25519_public_key: Pubkey,
     pub derivation_path: DerivationPath,
+    key_id: Ed25519KeyId,
 }
 
 impl SolanaAccount {
     pub fn new_derived_account(
         root_public_key: &Ed25519ExtendedPublicKey,
         derivation_path: DerivationPath,
+        key_id: Ed25519KeyId,
     ) -> Self {
         let ed25519_public_key = root_public_key
             .derive_public_key(derivation_path.clone())
@@ -26,17 +30,15 @@ impl SolanaAccount {
         Self {
             ed25519_public_key,
             derivation_path,
+            key_id,
         }
     }
 
     pub async fn sign_message(&self, message: &Message) -> Signature {
-        use sol_rpc_client::ed25519::Ed25519KeyId;
-        
-        let key_id: Ed25519KeyId = read_state(|s| s.ed25519_key_name()).into();
         sign_message(
             &IcRuntime,
             message,
-            key_id,
+            self.key_id,
             Some(&self.derivation_path),
         )
         .await
@@ -64,24 +66,24 @@ impl Display for SolanaAccount {
 pub struct SolanaWallet {
     owner: Principal,
     root_public_key: Ed25519ExtendedPublicKey,
+    key_id: Ed25519KeyId,
 }
 
 impl SolanaWallet {
-    pub async fn new(owner: Principal) -> Self {
-        let root_public_key = lazy_call_ed25519_public_key().await;
+    pub fn new(owner: Principal, root_public_key: Ed25519ExtendedPublicKey, key_id: Ed25519KeyId) -> Self {
         Self {
             owner,
             root_public_key,
+            key_id,
         }
     }
 
-    pub async fn new_canister_wallet() -> Self {
-        let canister_id = ic_cdk::id();
-        Self::new(canister_id).await
+    pub fn new_canister_wallet(root_public_key: Ed25519ExtendedPublicKey, key_id: Ed25519KeyId) -> Self {
+        Self::new(ic_cdk::id(), root_public_key, key_id)
     }
 
     pub fn derive_account(&self, derivation_path: DerivationPath) -> SolanaAccount {
-        SolanaAccount::new_derived_account(&self.root_public_key, derivation_path)
+        SolanaAccount::new_derived_account(&self.root_public_key, derivation_path, self.key_id)
     }
 
     pub fn solana_account(&self) -> SolanaAccount {
@@ -100,4 +102,4 @@ impl SolanaWallet {
     pub fn solana_address(&self) -> String {
         self.solana_account().ed25519_public_key.to_string()
     }
-}
\ No newline at end of file
+}
@@ -4,19 +4,35 @@ mod types;
 mod utils;
 mod wallet;
 
+use bit10_core::certification::Certified;
+use bit10_core::cycles::{ActionCycles, CyclesBudget};
+use bit10_core::events::{
+    ArchiveInfo, DataCertificate, GetArchivesArgs, GetBlocksRequest, GetBlocksResult, SupportedBlockType,
+};
+use bit10_core::metrics::{HttpRequest, HttpResponse};
+use bit10_core::nonce::{ReconcileReport, WalletNonces};
+use bit10_core::providers::{ProviderHealth, ProviderSetView, RpcProvider};
 use candid::{CandidType, Deserialize, Nat};
 use ic_cdk::{init, post_upgrade, pre_upgrade, query, update};
 use services::swap_service;
 use services::token_service;
+use services::gas_service;
+use services::reserves_service;
 use services::transaction_service;
 use services::bsc_swap_service; 
 use services::bsc_transaction_service;
 use services::solana_transaction_service;
 use services::solana_swap_service;
-use state::storage;
-use types::network::InitArg;
+use state::{certified, cycles, deposits, events, metrics, nonces, providers, registry, reserves, storage};
+use types::network::{InitArg, RpcChain};
+use types::registry::{BuyPair, RegistryEvent, TokenKind};
+use types::reserves::{CertifiedReservesReport, CustodyAccount, ReservesReport};
 use types::swap::{SwapResponse, SwapResponseData};
-use types::token::BIT10TokenResponse;
+use types::token::{BIT10TokenResponse, Token};
+use std::time::Duration;
+
+const NONCE_RECONCILE_INTERVAL: Duration = Duration::from_secs(120);
+const GAS_CHECK_INTERVAL: Duration = Duration::from_secs(600);
 
 #[derive(CandidType, Deserialize)]
 pub struct ICPBuyArgs {
@@ -60,6 +76,8 @@ fn post_upgrade_hook(maybe_init: Option<InitArg>) {
         state::state::init_state(init_arg);
     }
     storage::restore_from_stable_storage();
+    start_nonce_reconciliation();
+    start_gas_checks();
 }
 
 #[init]
@@ -67,6 +85,28 @@ pub fn init(maybe_init: Option<InitArg>) {
     if let Some(init_arg) = maybe_init {
         state::state::init_state(init_arg);
     }
+    start_nonce_reconciliation();
+    start_gas_checks();
+}
+
+fn start_nonce_reconciliation() {
+    ic_cdk_timers::set_timer_interval(NONCE_RECONCILE_INTERVAL, || {
+        ic_cdk::spawn(async {
+            if let Err(e) = reconcile_all_nonces().await {
+                ic_cdk::println!("Nonce reconciliation failed: {}", e);
+            }
+        })
+    });
+}
+
+fn start_gas_checks() {
+    ic_cdk_timers::set_timer_interval(GAS_CHECK_INTERVAL, || ic_cdk::spawn(gas_service::check_gas_balances()));
+}
+
+async fn reconcile_all_nonces() -> Result<Vec<ReconcileReport>, String> {
+    let base_report = transaction_service::reconcile_nonces().await;
+    let bsc_report = bsc_transaction_service::reconcile_bsc_nonces().await;
+    Ok(vec![base_report?, bsc_report?])
 }
 
 #[query]
@@ -74,6 +114,248 @@ pub fn bit10_token() -> BIT10TokenResponse {
     token_service::get_bit10_token_info()
 }
 
+/// Each token is hashed under `bit10_tokens/<name>`.
+#[query]
+fn get_certified_bit10_token() -> Certified<BIT10TokenResponse> {
+    certified::certified(token_service::get_bit10_token_info(), certified::BIT10_TOKENS_LABEL)
+}
+
+#[query]
+fn get_supported_tokens() -> Vec<Token> {
+    token_service::get_supported_tokens()
+}
+
+#[query]
+fn get_supported_bit10_tokens() -> Vec<Token> {
+    token_service::get_supported_bit10_tokens()
+}
+
+#[query]
+fn get_supported_pairs() -> Vec<BuyPair> {
+    registry::get_pairs()
+}
+
+#[query]
+fn get_registry_version() -> u64 {
+    registry::get_registry_version()
+}
+
+#[query]
+fn get_registry_events(offset: u64, limit: u64) -> Vec<RegistryEvent> {
+    registry::get_registry_events(offset, limit)
+}
+
+#[update]
+fn add_token(kind: TokenKind, token: Token) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    if !ic_cdk::api::is_controller(&caller) {
+        return Err("Only controllers can add tokens".to_string());
+    }
+
+    let version = registry::add_token(caller, kind, token)?;
+    certified::certify_bit10_tokens();
+    Ok(version)
+}
+
+#[update]
+fn update_token(token: Token) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    if !ic_cdk::api::is_controller(&caller) {
+        return Err("Only controllers can update tokens".to_string());
+    }
+
+    let version = registry::update_token(caller, token)?;
+    certified::certify_bit10_tokens();
+    Ok(version)
+}
+
+#[update]
+fn set_token_active(token_chain: String, token_address: String, is_active: bool) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    if !ic_cdk::api::is_controller(&caller) {
+        return Err("Only controllers can enable or disable tokens".to_string());
+    }
+
+    let version = registry::set_token_active(caller, &token_chain, &token_address, is_active)?;
+    certified::certify_bit10_tokens();
+    Ok(version)
+}
+
+#[update]
+fn add_pair(pair: BuyPair) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    if !ic_cdk::api::is_controller(&caller) {
+        return Err("Only controllers can add pairs".to_string());
+    }
+
+    registry::add_pair(caller, pair)
+}
+
+#[update]
+fn remove_pair(pair: BuyPair) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    if !ic_cdk::api::is_controller(&caller) {
+        return Err("Only controllers can remove pairs".to_string());
+    }
+
+    registry::remove_pair(caller, pair)
+}
+
+#[query]
+fn get_nonce_status() -> Vec<(String, WalletNonces)> {
+    nonces::get_wallet_nonces()
+}
+
+#[update]
+async fn reconcile_nonces() -> Result<Vec<ReconcileReport>, String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can reconcile nonces".to_string());
+    }
+
+    reconcile_all_nonces().await
+}
+
+#[update]
+fn release_deposit(key: String) -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can release deposits".to_string());
+    }
+
+    if deposits::release(&key) {
+        Ok(())
+    } else {
+        Err(format!("Deposit {} is not claimed", key))
+    }
+}
+
+#[query]
+fn get_rpc_provider_health(chain: RpcChain) -> Vec<(String, ProviderHealth)> {
+    providers::get_provider_health(chain)
+}
+
+#[update]
+fn clear_rpc_provider_flag(chain: RpcChain, name: String) -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can clear provider flags".to_string());
+    }
+
+    providers::clear_provider_flag(chain, &name)
+}
+
+#[query]
+fn get_rpc_config() -> Result<Vec<(RpcChain, ProviderSetView)>, String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can read the RPC config".to_string());
+    }
+
+    Ok(providers::get_rpc_config())
+}
+
+#[update]
+fn set_rpc_providers(chain: RpcChain, rpc_providers: Vec<RpcProvider>, quorum: u8) -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can set RPC providers".to_string());
+    }
+
+    providers::set_rpc_providers(chain, rpc_providers, quorum)
+}
+
+#[update]
+fn set_rpc_api_key(chain: RpcChain, provider: String, api_key: Option<String>) -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can set RPC API keys".to_string());
+    }
+
+    providers::set_rpc_api_key(chain, &provider, api_key)
+}
+
+#[update]
+fn reset_rpc_providers(chain: RpcChain) -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can reset RPC providers".to_string());
+    }
+
+    providers::reset_rpc_providers(chain);
+    Ok(())
+}
+
+#[query]
+fn get_cycles_budget() -> CyclesBudget {
+    cycles::get_budget()
+}
+
+#[update]
+fn set_cycles_budget(budget: CyclesBudget) -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can set the cycles budget".to_string());
+    }
+
+    cycles::set_budget(budget)
+}
+
+#[query]
+fn get_cycles_usage() -> Vec<ActionCycles> {
+    cycles::get_usage()
+}
+
+#[query]
+fn get_custody_accounts() -> Vec<CustodyAccount> {
+    reserves::get_custody_accounts()
+}
+
+#[update]
+fn set_custody_accounts(accounts: Vec<CustodyAccount>) -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can set custody accounts".to_string());
+    }
+
+    reserves_service::set_custody_accounts(accounts)
+}
+
+#[update]
+async fn generate_reserves_report() -> Result<ReservesReport, String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can generate reserves reports".to_string());
+    }
+
+    reserves_service::generate_report().await
+}
+
+#[query]
+fn get_reserves_report() -> Option<CertifiedReservesReport> {
+    reserves::latest_report().map(|report| CertifiedReservesReport {
+        report_hash: reserves::report_hash(&report),
+        report,
+        certificate: ic_cdk::api::data_certificate(),
+        hash_tree: certified::witness(certified::RESERVES_REPORT_LABEL),
+    })
+}
+
+#[query]
+fn get_reserves_report_by_id(id: u64) -> Option<ReservesReport> {
+    reserves::get_report(id)
+}
+
+#[query]
+fn icrc3_get_blocks(args: Vec<GetBlocksRequest>) -> GetBlocksResult {
+    events::get_blocks(args)
+}
+
+#[query]
+fn icrc3_get_tip_certificate() -> Option<DataCertificate> {
+    events::tip_certificate()
+}
+
+#[query]
+fn icrc3_get_archives(_args: GetArchivesArgs) -> Vec<ArchiveInfo> {
+    Vec::new()
+}
+
+#[query]
+fn icrc3_supported_block_types() -> Vec<SupportedBlockType> {
+    events::supported_block_types()
+}
+
 #[query]
 fn get_buy_history() -> Vec<SwapResponseData> {
     storage::get_buy_history()
@@ -84,13 +366,29 @@ fn get_sell_history() -> Vec<SwapResponseData> {
     storage::get_sell_history()
 }
 
+#[query]
+fn get_certified_buy_history() -> Certified<Vec<SwapResponseData>> {
+    certified::certified(storage::get_buy_history(), certified::BUY_HISTORY_LABEL)
+}
+
+#[query]
+fn get_certified_sell_history() -> Certified<Vec<SwapResponseData>> {
+    certified::certified(storage::get_sell_history(), certified::SELL_HISTORY_LABEL)
+}
+
 #[update]
 pub async fn icp_buy(args: ICPBuyArgs) -> SwapResponse {
+    if let Err(err) = cycles::charge("buy", cycles::trade_cost()) {
+        return SwapResponse::Err(err);
+    }
     swap_service::process_icp_buy(args).await
 }
 
 #[update]
 pub async fn icp_sell(args: ICPSellArgs) -> SwapResponse {
+    if let Err(err) = cycles::charge("sell", cycles::trade_cost()) {
+        return SwapResponse::Err(err);
+    }
     swap_service::process_icp_sell(args).await
 }
 
@@ -111,6 +409,9 @@ pub async fn base_create_transaction(args: SwapArgs) -> TransactionResponse {
 
 #[update]
 pub async fn base_buy(trx_hash: String) -> SwapResponse {
+    if let Err(err) = cycles::charge("buy", cycles::trade_cost()) {
+        return SwapResponse::Err(err);
+    }
     swap_service::process_base_buy(trx_hash).await
 }
 
@@ -131,6 +432,9 @@ pub async fn bsc_create_transaction(args: SwapArgs) -> TransactionResponse {
 
 #[update]
 pub async fn bsc_buy(trx_hash: String) -> SwapResponse {
+    if let Err(err) = cycles::charge("buy", cycles::trade_cost()) {
+        return SwapResponse::Err(err);
+    }
     bsc_swap_service::process_bsc_buy(trx_hash).await
 }
 
@@ -141,7 +445,7 @@ pub async fn solana_address() -> String {
 
 #[update]
 pub async fn nonce_account() -> String {
-    let wallet = wallet::solana_wallet::SolanaWallet::new_canister_wallet().await;
+    let wallet = wallet::canister_solana_wallet().await;
     wallet.derived_nonce_account().ed25519_public_key.to_string()
 }
 
@@ -167,16 +471,24 @@ pub async fn solana_create_transaction(args: SwapArgs) -> TransactionResponse {
 
 #[update]
 pub async fn solana_buy(trx_hash: String) -> SwapResponse {
+    if let Err(err) = cycles::charge("buy", cycles::trade_cost()) {
+        return SwapResponse::Err(err);
+    }
     solana_swap_service::process_solana_buy(trx_hash).await
 }
 
+#[query]
+fn http_request(request: HttpRequest) -> HttpResponse {
+    metrics::http_request(request)
+}
+
 #[query]
 fn transform(raw: ic_cdk::api::management_canister::http_request::TransformArgs) -> ic_cdk::api::management_canister::http_request::HttpResponse {
     use ic_cdk::api::management_canister::http_request::HttpResponse;
 
-    let mut res = HttpResponse {
+    let res = HttpResponse {
         status: raw.response.status.clone(),
-        body: raw.response.body.clone(),
+        body: bit10_core::transform::canonical_body(&raw.context, &raw.response.body),
         headers: Vec::new(),
     };
 
@@ -1,9 +1,11 @@
 use crate::services::{bsc_rpc_service, bsc_transaction_service, token_service};
-use crate::state::storage;
+use crate::state::{deposits, storage};
 use crate::types::swap::{SwapResponse, SwapResponseData};
 use crate::utils::constants::BSC_TARGET_ADDRESS;
 use crate::utils::converters::{generate_uuid_without_hyphens, u256_to_decimal_string};
 use crate::utils::validators;
+use bit10_core::deposits::{with_deposit_claim, ClaimedDeposit};
+use bit10_core::errors::ApiError;
 use alloy_primitives::U256;
 use rust_decimal::prelude::FromPrimitive;
 use rust_decimal::Decimal;
@@ -12,15 +14,24 @@ use std::str::FromStr;
 
 pub async fn process_bsc_buy(trx_hash: String) -> SwapResponse {
     if let Err(e) = validators::validate_transaction_hash(&trx_hash) {
-        return SwapResponse::Err(e);
+        return SwapResponse::Err(e.into());
     }
 
     let transaction_hash = trx_hash.to_lowercase();
 
     if storage::transaction_exists(&transaction_hash) {
-        return SwapResponse::Err("Transaction already processed".to_string());
+        return SwapResponse::Err(ApiError::already_processed(&transaction_hash));
     }
 
+    let deposit_key = deposits::key("bsc", &transaction_hash);
+    with_deposit_claim(&deposits::DEPOSIT_REGISTRY, &deposit_key, |claim| async move {
+        settle_bsc_buy(transaction_hash, &claim).await
+    })
+    .await
+    .unwrap_or_else(|e| SwapResponse::Err(e.into()))
+}
+
+async fn settle_bsc_buy(transaction_hash: String, claim: &ClaimedDeposit) -> SwapResponse {
     let tx_data = match bsc_rpc_service::get_bsc_transaction_by_hash(&transaction_hash).await {
         Ok(tx) => {
             if let Some(input) = tx.get("input").and_then(|v| v.as_str()) {
@@ -28,12 +39,12 @@ pub async fn process_bsc_buy(trx_hash: String) -> SwapResponse {
             }
             tx
         },
-        Err(e) => return SwapResponse::Err(format!("Failed to get transaction: {}", e)),
+        Err(e) => return SwapResponse::Err(ApiError::chain_unavailable("Binance Smart Chain", e)),
     };
 
     let tx_receipt = match bsc_rpc_service::get_bsc_transaction_receipt(&transaction_hash).await {
         Ok(receipt) => receipt,
-        Err(e) => return SwapResponse::Err(format!("Failed to get receipt: {}", e)),
+        Err(e) => return SwapResponse::Err(ApiError::chain_unavailable("Binance Smart Chain", e)),
     };
 
     let tx_status = tx_receipt
@@ -41,7 +52,7 @@ pub async fn process_bsc_buy(trx_hash: String) -> SwapResponse {
         .and_then(|v| v.as_str())
         .unwrap_or("0x0");
     if tx_status != "0x1" {
-        return SwapResponse::Err("Transaction was not successful".to_string());
+        return SwapResponse::Err(ApiError::deposit_invalid(&transaction_hash, "transaction reverted"));
     }
 
     let tx_to_address = tx_data
@@ -79,10 +90,10 @@ pub async fn process_bsc_buy(trx_hash: String) -> SwapResponse {
     };
 
     if !is_valid_transaction {
-        return SwapResponse::Err(format!(
+        return SwapResponse::Err(ApiError::deposit_invalid(&transaction_hash, format!(
             "Transaction was not sent to the target address {}",
             BSC_TARGET_ADDRESS
-        ));
+        )));
     }
 
     let (token_out_address, token_out_amount) =
@@ -91,9 +102,7 @@ pub async fn process_bsc_buy(trx_hash: String) -> SwapResponse {
                 (out_addr, out_amount)
             }
             None => {
-                return SwapResponse::Err(
-                    "Failed to decode token_out_address and token_out_amount".to_string(),
-                )
+                return SwapResponse::Err("Failed to decode token_out_address and token_out_amount".into())
             }
         };
 
@@ -103,7 +112,7 @@ pub async fn process_bsc_buy(trx_hash: String) -> SwapResponse {
             return SwapResponse::Err(format!(
                 "Token out address '{}' not found in supported BSC BIT10 tokens",
                 token_out_address
-            ))
+            ).into())
         }
     };
 
@@ -114,7 +123,7 @@ pub async fn process_bsc_buy(trx_hash: String) -> SwapResponse {
             .unwrap_or("0x0");
         let amount = match U256::from_str_radix(value_hex.strip_prefix("0x").unwrap_or("0"), 16) {
             Ok(amount) => amount,
-            Err(_) => return SwapResponse::Err("Failed to parse BNB amount".to_string()),
+            Err(_) => return SwapResponse::Err("Failed to parse BNB amount".into()),
         };
         (
             "0x0000000000000000000000000000000000000000bnb".to_string(),
@@ -128,7 +137,7 @@ pub async fn process_bsc_buy(trx_hash: String) -> SwapResponse {
         let amount = match validators::extract_erc20_amount_from_input(input_data, BSC_TARGET_ADDRESS)
         {
             Ok(amount) => amount,
-            Err(e) => return SwapResponse::Err(format!("Failed to extract amount: {}", e)),
+            Err(e) => return SwapResponse::Err(format!("Failed to extract amount: {}", e).into()),
         };
         (tx_to_address.clone(), amount)
     };
@@ -139,31 +148,35 @@ pub async fn process_bsc_buy(trx_hash: String) -> SwapResponse {
             return SwapResponse::Err(format!(
                 "Token in address '{}' not found in supported BSC tokens",
                 token_in_address
-            ))
+            ).into())
         }
     };
 
+    if let Err(e) = token_service::ensure_pair_listed(&token_in, &token_out) {
+        return SwapResponse::Err(e.into());
+    }
+
     let token_in_amount =
         match u256_to_decimal_string(actual_amount_received, token_in.token_decimals) {
             Ok(amount_str) => amount_str,
-            Err(e) => return SwapResponse::Err(format!("Failed to convert amount: {}", e)),
+            Err(e) => return SwapResponse::Err(format!("Failed to convert amount: {}", e).into()),
         };
 
     let token_in_amount_decimal = match Decimal::from_str(&token_in_amount) {
         Ok(amount) => amount,
-        Err(_) => return SwapResponse::Err("Failed to parse token_in_amount".to_string()),
+        Err(_) => return SwapResponse::Err("Failed to parse token_in_amount".into()),
     };
 
     let token_in_amount_without_fee = token_in_amount_decimal / dec!(1.01);
 
     let token_in_usd_price = match token_service::get_token_price_from_feed(&token_in).await {
         Ok(price) => price,
-        Err(e) => return SwapResponse::Err(format!("Failed to get token_in price: {}", e)),
+        Err(e) => return SwapResponse::Err(ApiError::price_feed_unavailable(&token_in.token_symbol, e)),
     };
 
     let token_out_usd_price = match token_service::get_bit10_token_price(&token_out).await {
         Ok(price) => price,
-        Err(e) => return SwapResponse::Err(format!("Failed to get token_out price: {}", e)),
+        Err(e) => return SwapResponse::Err(ApiError::price_feed_unavailable(&token_out.token_symbol, e)),
     };
 
     let token_in_usd_amount = token_in_amount_without_fee
@@ -171,7 +184,7 @@ pub async fn process_bsc_buy(trx_hash: String) -> SwapResponse {
 
     let token_out_amount_decimal = match Decimal::from_str(&token_out_amount) {
         Ok(amount) => amount,
-        Err(_) => return SwapResponse::Err("Failed to parse token_out_amount".to_string()),
+        Err(_) => return SwapResponse::Err("Failed to parse token_out_amount".into()),
     };
 
     let token_out_usd_value = token_out_amount_decimal
@@ -192,6 +205,10 @@ pub async fn process_bsc_buy(trx_hash: String) -> SwapResponse {
     let should_proceed = (token_out_usd_value <= token_in_usd_amount || difference_ratio <= dec!(3))
         && availability_check.is_ok();
 
+    if let Err(e) = claim.start_settlement() {
+        return SwapResponse::Err(e.into());
+    }
+
     let (transaction_type, token_out_tx_hash) = if should_proceed {
         match bsc_transaction_service::send_bsc_bit10_token_to_user(
             &token_out,
@@ -208,7 +225,7 @@ pub async fn process_bsc_buy(trx_hash: String) -> SwapResponse {
                 );
                 ("Buy".to_string(), tx_hash)
             }
-            Err(e) => return SwapResponse::Err(format!("Failed to send BIT10 token: {}", e)),
+            Err(e) => return SwapResponse::Err(ApiError::transfer_failed(format!("Failed to send BIT10 token: {}", e))),
         }
     } else {
         match bsc_transaction_service::revert_bsc_transaction(
@@ -219,7 +236,7 @@ pub async fn process_bsc_buy(trx_hash: String) -> SwapResponse {
         .await
         {
             Ok(tx_hash) => ("Revert".to_string(), tx_hash),
-            Err(e) => return SwapResponse::Err(format!("Failed to revert transaction: {}", e)),
+            Err(e) => return SwapResponse::Err(ApiError::transfer_failed(format!("Failed to revert transaction: {}", e))),
         }
     };
 
@@ -1,5 +1,7 @@
 pub mod token_service;
+pub mod gas_service;
 pub mod rpc_service;
+pub mod reserves_service;
 pub mod swap_service;
 pub mod transaction_service;
 pub mod bsc_rpc_service;
@@ -0,0 +1,275 @@
+use crate::services::{bsc_rpc_service, rpc_service, solana_rpc_service, token_service};
+use crate::state::providers::QuorumRpc;
+use crate::state::{reserves, storage};
+use crate::types::network::RpcChain;
+use crate::types::reserves::{
+    Bit10Supply, CustodyAccount, ReserveAsset, ReserveBalance, ReserveChain, ReservesReport,
+};
+use bit10_core::rpc::{evm_rpc, sui_rpc};
+use candid::{Nat, Principal};
+use icrc_ledger_types::icrc1::account::Account;
+use num_traits::ToPrimitive;
+use rust_decimal::Decimal;
+use sha2::{Digest, Sha256};
+
+const SUI_COIN_TYPE: &str = "0x2::sui::SUI";
+
+pub fn set_custody_accounts(accounts: Vec<CustodyAccount>) -> Result<(), String> {
+    for account in &accounts {
+        validate_account(account)?;
+    }
+    reserves::set_custody_accounts(accounts);
+    Ok(())
+}
+
+fn validate_account(account: &CustodyAccount) -> Result<(), String> {
+    match account.chain {
+        ReserveChain::Ethereum | ReserveChain::Base | ReserveChain::Bsc => {
+            validate_evm_address(&account.address)?;
+            for asset in &account.assets {
+                if let Some(token_address) = &asset.token_address {
+                    validate_evm_address(token_address)?;
+                }
+            }
+        }
+        ReserveChain::Tron => {
+            tron_to_hex(&account.address)?;
+            for asset in &account.assets {
+                if let Some(contract) = &asset.token_address {
+                    tron_to_hex(contract)?;
+                }
+            }
+        }
+        ReserveChain::Icp => {
+            Principal::from_text(&account.address)
+                .map_err(|e| format!("Invalid ICP custody principal {}: {}", account.address, e))?;
+            for asset in &account.assets {
+                let ledger = asset
+                    .token_address
+                    .as_ref()
+                    .ok_or_else(|| format!("ICP asset {} needs its ledger canister id", asset.symbol))?;
+                Principal::from_text(ledger)
+                    .map_err(|e| format!("Invalid ledger canister id {}: {}", ledger, e))?;
+            }
+        }
+        ReserveChain::Solana | ReserveChain::Sui => {
+            if account.address.is_empty() {
+                return Err("Custody address cannot be empty".to_string());
+            }
+        }
+    }
+
+    if account.assets.is_empty() {
+        return Err(format!("Custody account {} has no assets", account.address));
+    }
+    Ok(())
+}
+
+fn validate_evm_address(address: &str) -> Result<(), String> {
+    let hex = address.strip_prefix("0x").unwrap_or(address);
+    if hex.len() != 40 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Invalid EVM address: {}", address));
+    }
+    Ok(())
+}
+
+/// Converts a base58check Tron address to the 0x form its JSON-RPC endpoint expects.
+fn tron_to_hex(address: &str) -> Result<String, String> {
+    let bytes = bs58::decode(address)
+        .into_vec()
+        .map_err(|e| format!("Invalid Tron address {}: {}", address, e))?;
+    if bytes.len() != 25 || bytes[0] != 0x41 {
+        return Err(format!("Invalid Tron address: {}", address));
+    }
+
+    let checksum = Sha256::digest(Sha256::digest(&bytes[..21]));
+    if checksum[..4] != bytes[21..] {
+        return Err(format!("Invalid Tron address checksum: {}", address));
+    }
+
+    let hex: String = bytes[1..21].iter().map(|b| format!("{:02x}", b)).collect();
+    Ok(format!("0x{}", hex))
+}
+
+/// Reads every configured custody balance and the BIT10 supply, stores the
+/// report and certifies its hash.
+pub async fn generate_report() -> Result<ReservesReport, String> {
+    let accounts = reserves::get_custody_accounts();
+    if accounts.is_empty() {
+        return Err("No custody accounts configured".to_string());
+    }
+
+    let mut balances = Vec::new();
+    for account in &accounts {
+        for asset in &account.assets {
+            balances.push(read_balance(account, asset).await);
+        }
+    }
+    let supplies = read_supplies().await;
+
+    let reserves_usd: f64 = balances.iter().filter_map(|b| b.usd_value).sum();
+    let supply_usd: f64 = supplies.iter().filter_map(|s| s.usd_value).sum();
+    let complete = balances.iter().all(|b| b.usd_value.is_some())
+        && supplies.iter().all(|s| s.usd_value.is_some());
+
+    let report = ReservesReport {
+        id: reserves::next_report_id(),
+        generated_at: ic_cdk::api::time(),
+        balances,
+        supplies,
+        reserves_usd,
+        supply_usd,
+        collateral_ratio: (supply_usd > 0.0).then(|| reserves_usd / supply_usd),
+        complete,
+    };
+    reserves::add_report(report.clone());
+
+    Ok(report)
+}
+
+async fn read_balance(account: &CustodyAccount, asset: &ReserveAsset) -> ReserveBalance {
+    let mut balance = ReserveBalance {
+        chain: account.chain,
+        address: account.address.clone(),
+        symbol: asset.symbol.clone(),
+        token_address: asset.token_address.clone(),
+        raw_balance: Nat::from(0u64),
+        balance: "0".to_string(),
+        usd_price: None,
+        usd_value: None,
+        error: None,
+    };
+
+    let amount = match fetch_raw_balance(account.chain, &account.address, asset).await {
+        Ok(raw) => {
+            balance.raw_balance = Nat::from(raw);
+            match to_decimal(raw, asset.decimals) {
+                Ok(amount) => amount,
+                Err(e) => {
+                    balance.error = Some(e);
+                    return balance;
+                }
+            }
+        }
+        Err(e) => {
+            balance.error = Some(e);
+            return balance;
+        }
+    };
+    balance.balance = amount.to_string();
+
+    let Some(price_feed_id) = &asset.price_feed_id else {
+        return balance;
+    };
+    match token_service::get_price_from_feed(price_feed_id).await {
+        Ok(price) => {
+            balance.usd_price = Some(price);
+            balance.usd_value = amount.to_f64().map(|amount| amount * price);
+        }
+        Err(e) => balance.error = Some(format!("Failed to price {}: {}", asset.symbol, e)),
+    }
+    balance
+}
+
+async fn fetch_raw_balance(chain: ReserveChain, address: &str, asset: &ReserveAsset) -> Result<u128, String> {
+    let token_address = asset.token_address.as_deref();
+    match chain {
+        ReserveChain::Ethereum => {
+            let rpc = QuorumRpc(RpcChain::Ethereum);
+            match token_address {
+                Some(token) => evm_rpc::get_token_balance(&rpc, token, address).await,
+                None => evm_rpc::get_balance(&rpc, address).await,
+            }
+        }
+        ReserveChain::Base => match token_address {
+            Some(token) => rpc_service::get_token_balance(token, address).await,
+            None => rpc_service::get_balance(address).await,
+        },
+        ReserveChain::Bsc => match token_address {
+            Some(token) => bsc_rpc_service::get_bsc_token_balance(token, address).await,
+            None => bsc_rpc_service::get_bsc_balance(address).await,
+        },
+        ReserveChain::Tron => {
+            let rpc = QuorumRpc(RpcChain::Tron);
+            let owner = tron_to_hex(address)?;
+            match token_address {
+                Some(contract) => evm_rpc::get_token_balance(&rpc, &tron_to_hex(contract)?, &owner).await,
+                None => evm_rpc::get_balance(&rpc, &owner).await,
+            }
+        }
+        ReserveChain::Solana => match token_address {
+            Some(mint) => solana_rpc_service::get_token_balance(address, mint).await,
+            None => solana_rpc_service::get_balance(address).await.map(u128::from),
+        },
+        ReserveChain::Sui => {
+            let coin_type = token_address.unwrap_or(SUI_COIN_TYPE);
+            sui_rpc::get_balance(&QuorumRpc(RpcChain::Sui), address, coin_type).await
+        }
+        ReserveChain::Icp => {
+            let ledger = token_address.ok_or("ICP assets need a ledger canister id")?;
+            icrc1_balance_of(ledger, address).await
+        }
+    }
+}
+
+async fn icrc1_balance_of(ledger: &str, owner: &str) -> Result<u128, String> {
+    let ledger = Principal::from_text(ledger).map_err(|e| format!("Invalid ledger canister id: {}", e))?;
+    let owner = Principal::from_text(owner).map_err(|e| format!("Invalid owner principal: {}", e))?;
+    let account = Account { owner, subaccount: None };
+
+    let (balance,): (Nat,) = ic_cdk::call(ledger, "icrc1_balance_of", (account,))
+        .await
+        .map_err(|e| format!("Failed to get ledger balance: {:?}", e))?;
+    balance
+        .0
+        .to_u128()
+        .ok_or_else(|| "Ledger balance does not fit in u128".to_string())
+}
+
+fn to_decimal(raw: u128, decimals: u8) -> Result<Decimal, String> {
+    let raw = i128::try_from(raw).map_err(|_| "Balance is too large".to_string())?;
+    Decimal::try_from_i128_with_scale(raw, decimals as u32)
+        .map(|amount| amount.normalize())
+        .map_err(|e| format!("Failed to scale balance: {}", e))
+}
+
+/// Supplies are sorted by name so the same state always encodes, and
+/// therefore hashes, the same way.
+async fn read_supplies() -> Vec<Bit10Supply> {
+    let mut token_data: Vec<(String, String)> = storage::get_token_data()
+        .into_iter()
+        .map(|(token_name, (total_supply, _))| (token_name, total_supply))
+        .collect();
+    token_data.sort();
+
+    let bit10_tokens = token_service::get_supported_bit10_tokens();
+    let mut supplies = Vec::new();
+    for (token_name, total_supply) in token_data {
+        let mut supply = Bit10Supply {
+            token_name: token_name.clone(),
+            total_supply: total_supply.clone(),
+            usd_price: None,
+            usd_value: None,
+            error: None,
+        };
+
+        let token = bit10_tokens
+            .iter()
+            .find(|token| token.token_name == token_name && token.price_feed_link.is_some());
+        match token {
+            Some(token) => match token_service::get_bit10_token_price(token).await {
+                Ok(price) => {
+                    supply.usd_price = Some(price);
+                    match total_supply.parse::<f64>() {
+                        Ok(total_supply) => supply.usd_value = Some(total_supply * price),
+                        Err(e) => supply.error = Some(format!("Invalid total supply: {}", e)),
+                    }
+                }
+                Err(e) => supply.error = Some(format!("Failed to price {}: {}", token_name, e)),
+            },
+            None => supply.error = Some(format!("No price feed for {}", token_name)),
+        }
+        supplies.push(supply);
+    }
+    supplies
+}
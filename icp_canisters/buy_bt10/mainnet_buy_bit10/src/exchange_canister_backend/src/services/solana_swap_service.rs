@@ -1,9 +1,11 @@
 use crate::services::{solana_rpc_service, solana_transaction_service, token_service};
-use crate::state::storage;
+use crate::state::{deposits, storage};
 use crate::types::swap::{SwapResponse, SwapResponseData};
 use crate::utils::constants::SOLANA_TARGET_ADDRESS;
 use crate::utils::converters::{generate_uuid_without_hyphens, u256_to_decimal_string};
 use crate::utils::validators;
+use bit10_core::deposits::{with_deposit_claim, ClaimedDeposit};
+use bit10_core::errors::ApiError;
 use rust_decimal::prelude::FromPrimitive;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
@@ -11,21 +13,28 @@ use std::str::FromStr;
 
 pub async fn process_solana_buy(trx_hash: String) -> SwapResponse {
     if let Err(e) = validators::validate_solana_transaction_hash(&trx_hash) {
-        return SwapResponse::Err(e);
+        return SwapResponse::Err(e.into());
     }
 
     let transaction_hash = trx_hash.clone();
 
     if storage::transaction_exists(&transaction_hash) {
-        return SwapResponse::Err("Transaction already processed".to_string());
+        return SwapResponse::Err(ApiError::already_processed(&transaction_hash));
     }
 
+    let deposit_key = deposits::key("solana", &transaction_hash);
+    with_deposit_claim(&deposits::DEPOSIT_REGISTRY, &deposit_key, |claim| async move {
+        settle_solana_buy(transaction_hash, &claim).await
+    })
+    .await
+    .unwrap_or_else(|e| SwapResponse::Err(e.into()))
+}
+
+async fn settle_solana_buy(transaction_hash: String, claim: &ClaimedDeposit) -> SwapResponse {
     match solana_rpc_service::get_transaction_status(&transaction_hash).await {
         Ok(status) => {
             if status == "not_found" {
-                return SwapResponse::Err(
-                    "Transaction not found. Please ensure the transaction is confirmed on Solana blockchain.".to_string()
-                );
+                return SwapResponse::Err("Transaction not found. Please ensure the transaction is confirmed on Solana blockchain.".into());
             }
         }
         Err(e) => {
@@ -39,13 +48,13 @@ pub async fn process_solana_buy(trx_hash: String) -> SwapResponse {
         },
         Err(e) => {
             ic_cdk::println!("Failed to get transaction: {}", e);
-            return SwapResponse::Err(format!("Failed to get transaction: {}", e));
+            return SwapResponse::Err(ApiError::chain_unavailable("Solana", e));
         }
     };
 
     let meta = tx_data
         .get("meta")
-        .ok_or_else(|| SwapResponse::Err("Transaction metadata not found".to_string()));
+        .ok_or_else(|| SwapResponse::Err("Transaction metadata not found".into()));
     
     let meta = match meta {
         Ok(m) => m,
@@ -72,10 +81,10 @@ pub async fn process_solana_buy(trx_hash: String) -> SwapResponse {
             .map(|obj| format!("{:?}", obj))
             .unwrap_or_else(|| "Unknown error".to_string());
         
-        return SwapResponse::Err(format!(
+        return SwapResponse::Err(ApiError::deposit_invalid(&transaction_hash, format!(
             "Transaction was not successful. Error: {}",
             error_detail
-        ));
+        )));
     }
 
     let from_address = tx_data
@@ -88,7 +97,7 @@ pub async fn process_solana_buy(trx_hash: String) -> SwapResponse {
         .to_string();
 
     if from_address.is_empty() {
-        return SwapResponse::Err("Failed to extract sender address".to_string());
+        return SwapResponse::Err("Failed to extract sender address".into());
     }
 
     let target_address_lower = SOLANA_TARGET_ADDRESS.to_lowercase();
@@ -97,7 +106,7 @@ pub async fn process_solana_buy(trx_hash: String) -> SwapResponse {
         .and_then(|t| t.get("message"))
         .and_then(|m| m.get("accountKeys"))
         .and_then(|keys| keys.as_array())
-        .ok_or_else(|| SwapResponse::Err("Failed to get account keys".to_string()));
+        .ok_or_else(|| SwapResponse::Err("Failed to get account keys".into()));
 
     let account_keys = match account_keys {
         Ok(keys) => keys,
@@ -119,10 +128,10 @@ pub async fn process_solana_buy(trx_hash: String) -> SwapResponse {
         });
 
     if !is_valid_transaction {
-        return SwapResponse::Err(format!(
+        return SwapResponse::Err(ApiError::deposit_invalid(&transaction_hash, format!(
             "Transaction was not sent to the target address {}",
             SOLANA_TARGET_ADDRESS
-        ));
+        )));
     }
 
     let memo_data = solana_transaction_service::extract_memo_from_transaction(&tx_data);
@@ -134,14 +143,12 @@ pub async fn process_solana_buy(trx_hash: String) -> SwapResponse {
                     (out_addr, out_amount)
                 },
                 None => {
-                    return SwapResponse::Err(
-                        "Failed to decode token_out_address and token_out_amount".to_string(),
-                    )
+                    return SwapResponse::Err("Failed to decode token_out_address and token_out_amount".into())
                 }
             }
         }
         None => {
-            return SwapResponse::Err("Failed to extract memo data from transaction".to_string())
+            return SwapResponse::Err("Failed to extract memo data from transaction".into())
         }
     };
 
@@ -152,7 +159,7 @@ pub async fn process_solana_buy(trx_hash: String) -> SwapResponse {
             return SwapResponse::Err(format!(
                 "Token out address '{}' not found in supported Solana BIT10 tokens",
                 token_out_address
-            ))
+            ).into())
         }
     };
 
@@ -165,33 +172,37 @@ pub async fn process_solana_buy(trx_hash: String) -> SwapResponse {
             return SwapResponse::Err(format!(
                 "Token in address '{}' not found in supported Solana tokens",
                 token_in_address
-            ))
+            ).into())
         }
     };
 
+    if let Err(e) = token_service::ensure_pair_listed(&token_in, &token_out) {
+        return SwapResponse::Err(e.into());
+    }
+
     let token_in_amount = match u256_to_decimal_string(
         alloy_primitives::U256::from(actual_amount_received),
         token_in.token_decimals,
     ) {
         Ok(amount_str) => amount_str,
-        Err(e) => return SwapResponse::Err(format!("Failed to convert amount: {}", e)),
+        Err(e) => return SwapResponse::Err(format!("Failed to convert amount: {}", e).into()),
     };
 
     let token_in_amount_decimal = match Decimal::from_str(&token_in_amount) {
         Ok(amount) => amount,
-        Err(_) => return SwapResponse::Err("Failed to parse token_in_amount".to_string()),
+        Err(_) => return SwapResponse::Err("Failed to parse token_in_amount".into()),
     };
 
     let token_in_amount_without_fee = token_in_amount_decimal / dec!(1.01);
 
     let token_in_usd_price = match token_service::get_token_price_from_feed(&token_in).await {
         Ok(price) => price,
-        Err(e) => return SwapResponse::Err(format!("Failed to get token_in price: {}", e)),
+        Err(e) => return SwapResponse::Err(ApiError::price_feed_unavailable(&token_in.token_symbol, e)),
     };
 
     let token_out_usd_price = match token_service::get_bit10_token_price(&token_out).await {
         Ok(price) => price,
-        Err(e) => return SwapResponse::Err(format!("Failed to get token_out price: {}", e)),
+        Err(e) => return SwapResponse::Err(ApiError::price_feed_unavailable(&token_out.token_symbol, e)),
     };
 
     let token_in_usd_amount = token_in_amount_without_fee
@@ -199,7 +210,7 @@ pub async fn process_solana_buy(trx_hash: String) -> SwapResponse {
 
     let token_out_amount_decimal = match Decimal::from_str(&token_out_amount) {
         Ok(amount) => amount,
-        Err(_) => return SwapResponse::Err("Failed to parse token_out_amount".to_string()),
+        Err(_) => return SwapResponse::Err("Failed to parse token_out_amount".into()),
     };
 
     let token_out_usd_value = token_out_amount_decimal
@@ -220,6 +231,10 @@ pub async fn process_solana_buy(trx_hash: String) -> SwapResponse {
     let should_proceed = (token_out_usd_value <= token_in_usd_amount || difference_ratio <= dec!(3))
         && availability_check.is_ok();
 
+        if let Err(e) = claim.start_settlement() {
+            return SwapResponse::Err(e.into());
+        }
+
         let (transaction_type, token_out_tx_hash) = if should_proceed {
             match solana_transaction_service::send_solana_bit10_token_to_user(
                 &token_out,
@@ -236,7 +251,7 @@ pub async fn process_solana_buy(trx_hash: String) -> SwapResponse {
                     );
                     ("Buy".to_string(), tx_hash)
                 }
-                Err(e) => return SwapResponse::Err(format!("Failed to send BIT10 token: {}", e)),
+                Err(e) => return SwapResponse::Err(ApiError::transfer_failed(format!("Failed to send BIT10 token: {}", e))),
             }
         } else {
         match solana_transaction_service::revert_solana_transaction(
@@ -247,7 +262,7 @@ pub async fn process_solana_buy(trx_hash: String) -> SwapResponse {
         .await
         {
             Ok(tx_hash) => ("Revert".to_string(), tx_hash),
-            Err(e) => return SwapResponse::Err(format!("Failed to revert transaction: {}", e)),
+            Err(e) => return SwapResponse::Err(ApiError::transfer_failed(format!("Failed to revert transaction: {}", e))),
         }
     };
 
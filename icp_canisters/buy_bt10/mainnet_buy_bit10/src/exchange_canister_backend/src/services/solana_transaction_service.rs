@@ -1,5 +1,5 @@
 use crate::services::solana_rpc_service;
-use crate::wallet::solana_wallet::SolanaWallet;
+use crate::wallet::canister_solana_wallet;
 use crate::services::token_service;
 use crate::types::token::Token;
 use crate::utils::constants::SOLANA_TARGET_ADDRESS;
@@ -22,7 +22,7 @@ pub async fn create_associated_token_account_for_canister(mint_address: &str) ->
     // Verify caller is controller
     verify_is_controller().await?;
 
-    let wallet = SolanaWallet::new_canister_wallet().await;
+    let wallet = canister_solana_wallet().await;
     let payer = wallet.solana_account();
     
     let mint = Pubkey::from_str(mint_address)
@@ -62,7 +62,7 @@ pub async fn create_associated_token_account_for_canister(mint_address: &str) ->
 }
 
 pub async fn get_associated_token_account_address(mint_address: &str) -> Result<String, String> {
-    let wallet = SolanaWallet::new_canister_wallet().await;
+    let wallet = canister_solana_wallet().await;
     let owner = wallet.solana_account();
     
     let mint = Pubkey::from_str(mint_address)
@@ -83,7 +83,7 @@ pub async fn create_nonce_account_for_canister() -> Result<String, String> {
     // Verify caller is controller
     verify_is_controller().await?;
 
-    let wallet = SolanaWallet::new_canister_wallet().await;
+    let wallet = canister_solana_wallet().await;
     let payer = wallet.solana_account();
     let nonce_account = wallet.derived_nonce_account();
 
@@ -169,7 +169,7 @@ pub async fn send_spl_token(to_address: &str, mint_address: &str, amount: u64) -
     use solana_message::Message;
     use solana_transaction::Transaction;
 
-    let wallet = crate::wallet::solana_wallet::SolanaWallet::new_canister_wallet().await;
+    let wallet = canister_solana_wallet().await;
     let payer = wallet.solana_account();
 
     let recipient = Pubkey::from_str(to_address)
@@ -333,7 +333,7 @@ pub async fn create_solana_transaction(args: SwapArgs) -> TransactionResponse {
         ))
     });
 
-    let _token_out = token_service::find_bit10_token_by_address(
+    let token_out = token_service::find_bit10_token_by_address(
         &args.token_out_address,
         "Solana"
     )
@@ -344,6 +344,10 @@ pub async fn create_solana_transaction(args: SwapArgs) -> TransactionResponse {
         ))
     });
 
+    if let Err(e) = token_service::ensure_pair_listed(&token_in, &token_out) {
+        ic_cdk::trap(&e);
+    }
+
     let amount_decimal = validators::validate_amount(&args.token_in_amount)
         .unwrap_or_else(|e| ic_cdk::trap(&e));
 
@@ -397,7 +401,7 @@ pub async fn send_solana_native_sol(to_address: &str, amount: u64) -> Result<Str
     use solana_transaction::Transaction;
     use solana_system_interface::instruction as system_instruction;
 
-    let wallet = crate::wallet::solana_wallet::SolanaWallet::new_canister_wallet().await;
+    let wallet = canister_solana_wallet().await;
     let payer = wallet.solana_account();
 
     let recipient = Pubkey::from_str(to_address)
@@ -458,7 +462,7 @@ pub async fn send_spl_token_with_decimals(to_address: &str, mint_address: &str,
     use solana_message::Message;
     use solana_transaction::Transaction;
 
-    let wallet = crate::wallet::solana_wallet::SolanaWallet::new_canister_wallet().await;
+    let wallet = canister_solana_wallet().await;
     let payer = wallet.solana_account();
 
     let recipient = Pubkey::from_str(to_address)
@@ -1,9 +1,11 @@
 use crate::services::{rpc_service, token_service, transaction_service};
-use crate::state::storage;
+use crate::state::{deposits, storage};
 use crate::types::swap::{SwapResponse, SwapResponseData};
 use crate::utils::constants::{PLATFORM_WALLET, TARGET_ADDRESS};
 use crate::utils::converters::{generate_uuid_without_hyphens, u256_to_decimal_string};
 use crate::utils::validators;
+use bit10_core::deposits::{with_deposit_claim, ClaimedDeposit};
+use bit10_core::errors::ApiError;
 use crate::{ICPBuyArgs, ICPSellArgs};
 use alloy_primitives::U256;
 use candid::Principal;
@@ -22,7 +24,7 @@ pub async fn process_icp_buy(args: ICPBuyArgs) -> SwapResponse {
 
     let token_out_amount = match validators::validate_amount(&args.token_out_amount) {
         Ok(amount) => amount,
-        Err(e) => return SwapResponse::Err(e),
+        Err(e) => return SwapResponse::Err(e.into()),
     };
 
     let token_in = match token_service::find_token_by_address(&args.token_in_address, "ICP") {
@@ -31,7 +33,7 @@ pub async fn process_icp_buy(args: ICPBuyArgs) -> SwapResponse {
             return SwapResponse::Err(format!(
                 "Token in address {} is not a supported ICP token",
                 args.token_in_address
-            ))
+            ).into())
         }
     };
 
@@ -42,30 +44,34 @@ pub async fn process_icp_buy(args: ICPBuyArgs) -> SwapResponse {
                 return SwapResponse::Err(format!(
                     "Token out address {} is not a valid ICP BIT10 token",
                     args.token_out_address
-                ))
+                ).into())
             }
         };
 
+    if let Err(e) = token_service::ensure_pair_listed(&token_in, &token_out) {
+        return SwapResponse::Err(e.into());
+    }
+
     if let Err(e) = token_service::validate_bit10_token_availability(
         &token_out.token_name,
         &args.token_out_address,
         token_out_amount,
     ) {
-        return SwapResponse::Err(e);
+        return SwapResponse::Err(e.into());
     }
 
     let token_in_price = match token_service::get_token_price_from_feed(&token_in).await {
         Ok(price) => price,
-        Err(e) => return SwapResponse::Err(format!("Failed to get token_in price: {}", e)),
+        Err(e) => return SwapResponse::Err(ApiError::price_feed_unavailable(&token_in.token_symbol, e)),
     };
 
     let token_out_price = match token_service::get_bit10_token_price(&token_out).await {
         Ok(price) => price,
-        Err(e) => return SwapResponse::Err(format!("Failed to get token_out price: {}", e)),
+        Err(e) => return SwapResponse::Err(ApiError::price_feed_unavailable(&token_out.token_symbol, e)),
     };
 
     if token_in_price <= 0.0 || token_out_price <= 0.0 {
-        return SwapResponse::Err("Invalid token prices".to_string());
+        return SwapResponse::Err(ApiError::price_feed_unavailable(format!("{}/{}", token_in.token_symbol, token_out.token_symbol), "price is not positive"));
     }
 
     let token_ratio = token_out_price / token_in_price;
@@ -74,14 +80,14 @@ pub async fn process_icp_buy(args: ICPBuyArgs) -> SwapResponse {
         token_in_amount_for_one_token_out * token_out_amount.to_f64().unwrap_or(0.0);
 
     if token_in_amount_f64 <= 0.0 {
-        return SwapResponse::Err("Calculated token_in_amount must be greater than 0".to_string());
+        return SwapResponse::Err("Calculated token_in_amount must be greater than 0".into());
     }
 
     let token_in_tx_hash = match transfer_from_user_icp(&token_in, caller, token_in_amount_f64)
         .await
     {
         Ok(tx_hash) => tx_hash,
-        Err(e) => return SwapResponse::Err(e),
+        Err(e) => return SwapResponse::Err(ApiError::transfer_failed(e)),
     };
 
     let token_out_tx_hash = match transfer_to_user_icp(
@@ -92,7 +98,7 @@ pub async fn process_icp_buy(args: ICPBuyArgs) -> SwapResponse {
     .await
     {
         Ok(tx_hash) => tx_hash,
-        Err(e) => return SwapResponse::Err(e),
+        Err(e) => return SwapResponse::Err(ApiError::transfer_failed(e)),
     };
 
     storage::update_token_bought(
@@ -131,7 +137,7 @@ pub async fn process_icp_sell(args: ICPSellArgs) -> SwapResponse {
 
     let token_in_amount = match validators::validate_amount(&args.token_in_amount) {
         Ok(amount) => amount,
-        Err(e) => return SwapResponse::Err(e),
+        Err(e) => return SwapResponse::Err(e.into()),
     };
 
     let token_in =
@@ -141,7 +147,7 @@ pub async fn process_icp_sell(args: ICPSellArgs) -> SwapResponse {
                 return SwapResponse::Err(format!(
                     "Token in address {} is not a supported BIT10 token",
                     args.token_in_address
-                ))
+                ).into())
             }
         };
 
@@ -150,26 +156,30 @@ pub async fn process_icp_sell(args: ICPSellArgs) -> SwapResponse {
         return SwapResponse::Err(format!(
             "Token out address must be ICP ({})",
             icp_token_address
-        ));
+        ).into());
     }
 
     let token_out = match token_service::find_token_by_address(&args.token_out_address, "ICP") {
         Some(token) => token,
-        None => return SwapResponse::Err("ICP token not found".to_string()),
+        None => return SwapResponse::Err("ICP token not found".into()),
     };
 
+    if let Err(e) = token_service::ensure_pair_listed(&token_out, &token_in) {
+        return SwapResponse::Err(e.into());
+    }
+
     let token_in_price = match token_service::get_bit10_token_price(&token_in).await {
         Ok(price) => price,
-        Err(e) => return SwapResponse::Err(format!("Failed to get token_in price: {}", e)),
+        Err(e) => return SwapResponse::Err(ApiError::price_feed_unavailable(&token_in.token_symbol, e)),
     };
 
     let token_out_price = match token_service::get_token_price_from_feed(&token_out).await {
         Ok(price) => price,
-        Err(e) => return SwapResponse::Err(format!("Failed to get token_out price: {}", e)),
+        Err(e) => return SwapResponse::Err(ApiError::price_feed_unavailable(&token_out.token_symbol, e)),
     };
 
     if token_in_price <= 0.0 || token_out_price <= 0.0 {
-        return SwapResponse::Err("Invalid token prices".to_string());
+        return SwapResponse::Err(ApiError::price_feed_unavailable(format!("{}/{}", token_in.token_symbol, token_out.token_symbol), "price is not positive"));
     }
 
     let token_in_amount_f64 = token_in_amount.to_f64().unwrap_or(0.0);
@@ -179,23 +189,21 @@ pub async fn process_icp_sell(args: ICPSellArgs) -> SwapResponse {
     let token_out_amount_f64 = token_out_usd_value / token_out_price;
 
     if token_out_amount_f64 <= 0.0 {
-        return SwapResponse::Err(
-            "Calculated token_out_amount must be greater than 0".to_string(),
-        );
+        return SwapResponse::Err("Calculated token_out_amount must be greater than 0".into());
     }
 
     let token_in_tx_hash = match transfer_from_user_icp(&token_in, caller, token_in_amount_f64)
         .await
     {
         Ok(tx_hash) => tx_hash,
-        Err(e) => return SwapResponse::Err(e),
+        Err(e) => return SwapResponse::Err(ApiError::transfer_failed(e)),
     };
 
     let token_out_tx_hash = match transfer_to_user_icp(&token_out, caller, token_out_amount_f64)
         .await
     {
         Ok(tx_hash) => tx_hash,
-        Err(e) => return SwapResponse::Err(e),
+        Err(e) => return SwapResponse::Err(ApiError::transfer_failed(e)),
     };
 
     storage::update_token_sold(
@@ -228,23 +236,34 @@ pub async fn process_icp_sell(args: ICPSellArgs) -> SwapResponse {
 
 pub async fn process_base_buy(trx_hash: String) -> SwapResponse {
     if let Err(e) = validators::validate_transaction_hash(&trx_hash) {
-        return SwapResponse::Err(e);
+        return SwapResponse::Err(e.into());
     }
 
     let transaction_hash = trx_hash.to_lowercase();
 
     if storage::transaction_exists(&transaction_hash) {
-        return SwapResponse::Err("Transaction already processed".to_string());
+        return SwapResponse::Err(ApiError::already_processed(&transaction_hash));
     }
 
+    let deposit_key = deposits::key("base", &transaction_hash);
+    with_deposit_claim(&deposits::DEPOSIT_REGISTRY, &deposit_key, |claim| async move {
+        settle_base_buy(transaction_hash, &claim).await
+    })
+    .await
+    .unwrap_or_else(|e| SwapResponse::Err(e.into()))
+}
+
+// Runs with the deposit claimed. Errors returned before `start_settlement`
+// release the claim so the user can retry.
+async fn settle_base_buy(transaction_hash: String, claim: &ClaimedDeposit) -> SwapResponse {
     let tx_data = match rpc_service::get_transaction_by_hash(&transaction_hash).await {
         Ok(tx) => tx,
-        Err(e) => return SwapResponse::Err(format!("Failed to get transaction: {}", e)),
+        Err(e) => return SwapResponse::Err(ApiError::chain_unavailable("Base", e)),
     };
 
     let tx_receipt = match rpc_service::get_transaction_receipt(&transaction_hash).await {
         Ok(receipt) => receipt,
-        Err(e) => return SwapResponse::Err(format!("Failed to get receipt: {}", e)),
+        Err(e) => return SwapResponse::Err(ApiError::chain_unavailable("Base", e)),
     };
 
     let tx_status = tx_receipt
@@ -252,7 +271,7 @@ pub async fn process_base_buy(trx_hash: String) -> SwapResponse {
         .and_then(|v| v.as_str())
         .unwrap_or("0x0");
     if tx_status != "0x1" {
-        return SwapResponse::Err("Transaction was not successful".to_string());
+        return SwapResponse::Err(ApiError::deposit_invalid(&transaction_hash, "transaction reverted"));
     }
 
     let tx_to_address = tx_data
@@ -289,19 +308,17 @@ pub async fn process_base_buy(trx_hash: String) -> SwapResponse {
     };
 
     if !is_valid_transaction {
-        return SwapResponse::Err(format!(
+        return SwapResponse::Err(ApiError::deposit_invalid(&transaction_hash, format!(
             "Transaction was not sent to the target address {}",
             TARGET_ADDRESS
-        ));
+        )));
     }
 
     let (token_out_address, token_out_amount) =
         match transaction_service::decode_base_transaction_data(&tx_data).await {
             Some((out_addr, out_amount)) => (out_addr, out_amount),
             None => {
-                return SwapResponse::Err(
-                    "Failed to decode token_out_address and token_out_amount".to_string(),
-                )
+                return SwapResponse::Err("Failed to decode token_out_address and token_out_amount".into())
             }
         };
 
@@ -311,7 +328,7 @@ pub async fn process_base_buy(trx_hash: String) -> SwapResponse {
             return SwapResponse::Err(format!(
                 "Token out address '{}' not found in supported Base BIT10 tokens",
                 token_out_address
-            ))
+            ).into())
         }
     };
 
@@ -322,7 +339,7 @@ pub async fn process_base_buy(trx_hash: String) -> SwapResponse {
             .unwrap_or("0x0");
         let amount = match U256::from_str_radix(value_hex.strip_prefix("0x").unwrap_or("0"), 16) {
             Ok(amount) => amount,
-            Err(_) => return SwapResponse::Err("Failed to parse ETH amount".to_string()),
+            Err(_) => return SwapResponse::Err("Failed to parse ETH amount".into()),
         };
         (
             "0x0000000000000000000000000000000000000000b".to_string(),
@@ -336,7 +353,7 @@ pub async fn process_base_buy(trx_hash: String) -> SwapResponse {
         let amount = match validators::extract_erc20_amount_from_input(input_data, TARGET_ADDRESS)
         {
             Ok(amount) => amount,
-            Err(e) => return SwapResponse::Err(format!("Failed to extract amount: {}", e)),
+            Err(e) => return SwapResponse::Err(format!("Failed to extract amount: {}", e).into()),
         };
         (tx_to_address.clone(), amount)
     };
@@ -347,31 +364,35 @@ pub async fn process_base_buy(trx_hash: String) -> SwapResponse {
             return SwapResponse::Err(format!(
                 "Token in address '{}' not found in supported Base tokens",
                 token_in_address
-            ))
+            ).into())
         }
     };
 
+    if let Err(e) = token_service::ensure_pair_listed(&token_in, &token_out) {
+        return SwapResponse::Err(e.into());
+    }
+
     let token_in_amount =
         match u256_to_decimal_string(actual_amount_received, token_in.token_decimals) {
             Ok(amount_str) => amount_str,
-            Err(e) => return SwapResponse::Err(format!("Failed to convert amount: {}", e)),
+            Err(e) => return SwapResponse::Err(format!("Failed to convert amount: {}", e).into()),
         };
 
     let token_in_amount_decimal = match Decimal::from_str(&token_in_amount) {
         Ok(amount) => amount,
-        Err(_) => return SwapResponse::Err("Failed to parse token_in_amount".to_string()),
+        Err(_) => return SwapResponse::Err("Failed to parse token_in_amount".into()),
     };
 
     let token_in_amount_without_fee = token_in_amount_decimal / dec!(1.01);
 
     let token_in_usd_price = match token_service::get_token_price_from_feed(&token_in).await {
         Ok(price) => price,
-        Err(e) => return SwapResponse::Err(format!("Failed to get token_in price: {}", e)),
+        Err(e) => return SwapResponse::Err(ApiError::price_feed_unavailable(&token_in.token_symbol, e)),
     };
 
     let token_out_usd_price = match token_service::get_bit10_token_price(&token_out).await {
         Ok(price) => price,
-        Err(e) => return SwapResponse::Err(format!("Failed to get token_out price: {}", e)),
+        Err(e) => return SwapResponse::Err(ApiError::price_feed_unavailable(&token_out.token_symbol, e)),
     };
 
     let token_in_usd_amount = token_in_amount_without_fee
@@ -379,7 +400,7 @@ pub async fn process_base_buy(trx_hash: String) -> SwapResponse {
 
     let token_out_amount_decimal = match Decimal::from_str(&token_out_amount) {
         Ok(amount) => amount,
-        Err(_) => return SwapResponse::Err("Failed to parse token_out_amount".to_string()),
+        Err(_) => return SwapResponse::Err("Failed to parse token_out_amount".into()),
     };
 
     let token_out_usd_value = token_out_amount_decimal
@@ -400,6 +421,10 @@ pub async fn process_base_buy(trx_hash: String) -> SwapResponse {
     let should_proceed = (token_out_usd_value <= token_in_usd_amount || difference_ratio <= dec!(3))
         && availability_check.is_ok();
 
+    if let Err(e) = claim.start_settlement() {
+        return SwapResponse::Err(e.into());
+    }
+
     let (transaction_type, token_out_tx_hash) = if should_proceed {
         match transaction_service::send_bit10_token_to_user(
             &token_out,
@@ -416,7 +441,7 @@ pub async fn process_base_buy(trx_hash: String) -> SwapResponse {
                 );
                 ("Buy".to_string(), tx_hash)
             }
-            Err(e) => return SwapResponse::Err(format!("Failed to send BIT10 token: {}", e)),
+            Err(e) => return SwapResponse::Err(ApiError::transfer_failed(format!("Failed to send BIT10 token: {}", e))),
         }
     } else {
         match transaction_service::revert_transaction(
@@ -427,7 +452,7 @@ pub async fn process_base_buy(trx_hash: String) -> SwapResponse {
         .await
         {
             Ok(tx_hash) => ("Revert".to_string(), tx_hash),
-            Err(e) => return SwapResponse::Err(format!("Failed to revert transaction: {}", e)),
+            Err(e) => return SwapResponse::Err(ApiError::transfer_failed(format!("Failed to revert transaction: {}", e))),
         }
     };
 
@@ -1,14 +1,16 @@
-use crate::state::storage;
+use crate::state::{metrics, registry, storage};
+use crate::types::registry::TokenKind;
 use crate::types::token::{BIT10TokenResponse, Token, TokenAllocation, TokenDetails};
 use crate::utils::constants::PRICE_FEED_CANISTER;
 use candid::Principal;
 use ciborium::from_reader;
-use ic_cdk::api::management_canister::http_request::{CanisterHttpRequestArgument, HttpHeader, HttpMethod};
+use bit10_core::transform::ResponseShape;
+use ic_cdk::api::management_canister::http_request::{CanisterHttpRequestArgument, HttpHeader, HttpMethod, TransformContext};
 use rust_decimal::Decimal;
 use std::collections::HashMap;
 use std::str::FromStr;
 
-pub fn get_supported_tokens() -> Vec<Token> {
+pub fn default_supported_tokens() -> Vec<Token> {
     vec![
         Token {
             token_id: Some("8916".to_string()),
@@ -97,7 +99,7 @@ pub fn get_supported_tokens() -> Vec<Token> {
     ]
 }
 
-pub fn get_supported_bit10_tokens() -> Vec<Token> {
+pub fn default_supported_bit10_tokens() -> Vec<Token> {
     vec![
         Token {
             token_id: None,
@@ -177,6 +179,14 @@ pub fn get_supported_bit10_tokens() -> Vec<Token> {
     ]
 }
 
+pub fn get_supported_tokens() -> Vec<Token> {
+    registry::get_tokens(TokenKind::Payment)
+}
+
+pub fn get_supported_bit10_tokens() -> Vec<Token> {
+    registry::get_tokens(TokenKind::Bit10)
+}
+
 pub fn get_bit10_token_info() -> BIT10TokenResponse {
     let supported_tokens = get_supported_bit10_tokens();
     let token_data = storage::get_token_data();
@@ -239,6 +249,10 @@ pub async fn get_token_price_from_feed(token: &Token) -> Result<f64, String> {
         .as_ref()
         .ok_or("Token does not have a price_feed_id")?;
 
+    get_price_from_feed(price_feed_id).await
+}
+
+pub async fn get_price_from_feed(price_feed_id: &str) -> Result<f64, String> {
     #[derive(candid::CandidType, serde::Deserialize)]
     struct PriceFeedResult {
         value: Vec<u8>,
@@ -251,11 +265,11 @@ pub async fn get_token_price_from_feed(token: &Token) -> Result<f64, String> {
     let price_result: Result<(Option<PriceFeedResult>,), _> = ic_cdk::call(
         price_feed_canister,
         "get_value",
-        (price_feed_id.clone(),),
+        (price_feed_id.to_string(),),
     )
     .await;
 
-    match price_result {
+    let price = match price_result {
         Ok((Some(feed),)) => match from_reader::<f64, _>(&feed.value[..]) {
             Ok(value) => Ok(value),
             Err(_) => match from_reader::<u64, _>(&feed.value[..]) {
@@ -265,7 +279,9 @@ pub async fn get_token_price_from_feed(token: &Token) -> Result<f64, String> {
         },
         Ok((None,)) => Err("Price feed not found".to_string()),
         Err(e) => Err(format!("Failed to get price feed: {:?}", e)),
-    }
+    };
+    metrics::record_price_feed(price_feed_id, price.is_ok());
+    price
 }
 
 pub async fn get_bit10_token_price(token: &Token) -> Result<f64, String> {
@@ -284,11 +300,14 @@ pub async fn get_bit10_token_price(token: &Token) -> Result<f64, String> {
         method: HttpMethod::GET,
         body: None,
         max_response_bytes: Some(8192),
-        transform: None,
+        transform: Some(TransformContext::from_name(
+            "transform".to_string(),
+            ResponseShape::json(&["tokenPrice"]).to_context(),
+        )),
         headers: request_headers,
     };
 
-    match crate::services::rpc_service::make_http_request(request).await {
+    let price = match crate::services::rpc_service::make_http_request(request).await {
         Ok(bytes) => match String::from_utf8(bytes) {
             Ok(response_str) => match serde_json::from_str::<serde_json::Value>(&response_str) {
                 Ok(json) => {
@@ -303,36 +322,40 @@ pub async fn get_bit10_token_price(token: &Token) -> Result<f64, String> {
             Err(e) => Err(format!("Failed to decode response: {}", e)),
         },
         Err(e) => Err(format!("Failed to make HTTP request: {}", e)),
-    }
+    };
+    metrics::record_price_feed(&token.token_name, price.is_ok());
+    price
 }
 
 pub fn find_token_by_address(address: &str, chain: &str) -> Option<Token> {
     get_supported_tokens()
         .into_iter()
         .chain(get_supported_bit10_tokens())
-        .find(|token| {
-            token
-                .token_address
-                .as_ref()
-                .map(|addr| {
-                    addr.to_lowercase() == address.to_lowercase()
-                        && token.token_chain.to_lowercase() == chain.to_lowercase()
-                })
-                .unwrap_or(false)
-        })
+        .filter(|token| token.is_active)
+        .find(|token| token.matches(address, chain))
 }
 
 pub fn find_bit10_token_by_address(address: &str, chain: &str) -> Option<Token> {
-    get_supported_bit10_tokens().into_iter().find(|token| {
-        token
-            .token_address
-            .as_ref()
-            .map(|addr| {
-                addr.to_lowercase() == address.to_lowercase()
-                    && token.token_chain.to_lowercase() == chain.to_lowercase()
-            })
-            .unwrap_or(false)
-    })
+    get_supported_bit10_tokens()
+        .into_iter()
+        .filter(|token| token.is_active)
+        .find(|token| token.matches(address, chain))
+}
+
+/// Swaps go through only for pairs listed in the registry, in either
+/// direction.
+pub fn ensure_pair_listed(token: &Token, bit10_token: &Token) -> Result<(), String> {
+    let token_address = token.token_address.clone().unwrap_or_default();
+    let bit10_token_address = bit10_token.token_address.clone().unwrap_or_default();
+
+    if registry::is_pair_listed(&token.token_chain, &token_address, &bit10_token_address) {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} cannot be swapped for {} on {}",
+            token.token_symbol, bit10_token.token_symbol, token.token_chain
+        ))
+    }
 }
 
 pub fn validate_bit10_token_availability(token_name: &str, token_address: &str, requested_amount: Decimal ) -> Result<(), String> {
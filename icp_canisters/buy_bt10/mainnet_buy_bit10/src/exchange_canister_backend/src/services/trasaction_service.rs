@@ -1,15 +1,17 @@
 use crate::services::rpc_service;
+use crate::state::nonces;
 use crate::state::state::read_state;
 use crate::types::token::Token;
 use crate::utils::constants::TARGET_ADDRESS;
 use crate::utils::converters::decimal_to_u256;
 use crate::utils::validators;
-use crate::wallet::base_wallet::BaseWallet;
+use crate::wallet::{canister_evm_wallet, evm_wallet::EvmWallet};
 use crate::SwapArgs;
 use crate::TransactionResponse;
 use alloy_consensus::{SignableTransaction, TxEip1559, TxEnvelope};
 use alloy_eips::eip2718::Encodable2718;
 use alloy_primitives::{hex, Address, Signature, U256};
+use bit10_core::nonce::ReconcileReport;
 use candid::Nat;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
@@ -17,7 +19,7 @@ use std::str::FromStr;
 
 pub async fn create_base_transaction(args: SwapArgs) -> TransactionResponse {
     use crate::services::token_service::{
-        find_bit10_token_by_address, find_token_by_address,
+        ensure_pair_listed, find_bit10_token_by_address, find_token_by_address,
     };
 
     let token_in = find_token_by_address(&args.token_in_address, "Base")
@@ -28,7 +30,7 @@ pub async fn create_base_transaction(args: SwapArgs) -> TransactionResponse {
             ))
         });
 
-    let _token_out = find_bit10_token_by_address(&args.token_out_address, "Base")
+    let token_out = find_bit10_token_by_address(&args.token_out_address, "Base")
         .unwrap_or_else(|| {
             ic_cdk::trap(&format!(
                 "Token out address '{}' not found in supported Base BIT10 tokens",
@@ -36,6 +38,10 @@ pub async fn create_base_transaction(args: SwapArgs) -> TransactionResponse {
             ))
         });
 
+    if let Err(e) = ensure_pair_listed(&token_in, &token_out) {
+        ic_cdk::trap(&e);
+    }
+
     let amount_decimal = validators::validate_amount(&args.token_in_amount)
         .unwrap_or_else(|e| ic_cdk::trap(&e));
 
@@ -99,10 +105,7 @@ pub async fn get_transaction_count(address: &str) -> Nat {
 }
 
 pub async fn send_erc20_token(token_contract: Address, to: Address, amount: U256) -> Result<String, String> {
-    let wallet = BaseWallet::new_canister_wallet().await;
-    let canister_address = wallet.base_address().to_string();
-
-    let nonce = rpc_service::get_transaction_count(&canister_address).await?;
+    let wallet = canister_evm_wallet().await;
 
     let (max_fee_per_gas, max_priority_fee_per_gas) = rpc_service::get_gas_price().await?;
 
@@ -118,6 +121,8 @@ pub async fn send_erc20_token(token_contract: Address, to: Address, amount: U256
     encoded.extend_from_slice(&amount_bytes);
 
     let chain_id = read_state(|s| s.base_network().chain_id());
+    let nonce_key = nonces::nonce_key(chain_id, &wallet.address().to_string());
+    let nonce = reserve_nonce(&nonce_key, &wallet).await?;
 
     let transaction = TxEip1559 {
         chain_id,
@@ -131,18 +136,17 @@ pub async fn send_erc20_token(token_contract: Address, to: Address, amount: U256
         input: encoded.into(),
     };
 
-    sign_and_send_transaction(transaction, &wallet).await
+    submit_transaction(transaction, &wallet, &nonce_key).await
 }
 
 pub async fn send_native_eth(to: Address, amount: U256) -> Result<String, String> {
-    let wallet = BaseWallet::new_canister_wallet().await;
-    let canister_address = wallet.base_address().to_string();
-
-    let nonce = rpc_service::get_transaction_count(&canister_address).await?;
+    let wallet = canister_evm_wallet().await;
 
     let (max_fee_per_gas, max_priority_fee_per_gas) = rpc_service::get_gas_price().await?;
 
     let chain_id = read_state(|s| s.base_network().chain_id());
+    let nonce_key = nonces::nonce_key(chain_id, &wallet.address().to_string());
+    let nonce = reserve_nonce(&nonce_key, &wallet).await?;
 
     let transaction = TxEip1559 {
         chain_id,
@@ -156,10 +160,93 @@ pub async fn send_native_eth(to: Address, amount: U256) -> Result<String, String
         input: Default::default(),
     };
 
-    sign_and_send_transaction(transaction, &wallet).await
+    submit_transaction(transaction, &wallet, &nonce_key).await
+}
+
+/// Compares the locally reserved nonces with the chain, drops everything that
+/// has been mined and fills abandoned nonces with zero-value self transfers.
+pub async fn reconcile_nonces() -> Result<ReconcileReport, String> {
+    let wallet = canister_evm_wallet().await;
+    let canister_address = wallet.address().to_string();
+    let chain_id = read_state(|s| s.base_network().chain_id());
+    let nonce_key = nonces::nonce_key(chain_id, &canister_address);
+
+    if !nonces::is_tracked(&nonce_key) {
+        return Ok(ReconcileReport::default());
+    }
+
+    let confirmed_nonce = rpc_service::get_confirmed_transaction_count(&canister_address).await?;
+    let report = nonces::reconcile(&nonce_key, confirmed_nonce);
+
+    for stuck in &report.stuck {
+        ic_cdk::println!(
+            "Base transaction {:?} with nonce {} is still pending",
+            stuck.tx_hash,
+            stuck.nonce
+        );
+    }
+
+    for nonce in &report.gaps {
+        match send_cancel_transaction(&wallet, &nonce_key, *nonce).await {
+            Ok(tx_hash) => ic_cdk::println!("Filled Base nonce gap {} with {}", nonce, tx_hash),
+            Err(e) => ic_cdk::println!("Failed to fill Base nonce gap {}: {}", nonce, e),
+        }
+    }
+
+    Ok(report)
+}
+
+async fn send_cancel_transaction(wallet: &EvmWallet, nonce_key: &str, nonce: u64) -> Result<String, String> {
+    let (max_fee_per_gas, max_priority_fee_per_gas) = rpc_service::get_gas_price().await?;
+
+    nonces::mark_cancel(nonce_key, nonce);
+
+    let transaction = TxEip1559 {
+        chain_id: read_state(|s| s.base_network().chain_id()),
+        nonce,
+        gas_limit: 21_000,
+        max_fee_per_gas,
+        max_priority_fee_per_gas,
+        to: alloy_primitives::TxKind::Call(wallet.address()),
+        value: U256::ZERO,
+        access_list: Default::default(),
+        input: Default::default(),
+    };
+
+    submit_transaction(transaction, wallet, nonce_key).await
+}
+
+async fn reserve_nonce(nonce_key: &str, wallet: &EvmWallet) -> Result<u64, String> {
+    if !nonces::is_tracked(nonce_key) {
+        let chain_nonce = rpc_service::get_transaction_count(&wallet.address().to_string()).await?;
+        nonces::track(nonce_key, chain_nonce);
+    }
+    nonces::reserve(nonce_key)
+}
+
+async fn submit_transaction(transaction: TxEip1559, wallet: &EvmWallet, nonce_key: &str) -> Result<String, String> {
+    let nonce = transaction.nonce;
+    let max_fee_per_gas = transaction.max_fee_per_gas;
+    let max_priority_fee_per_gas = transaction.max_priority_fee_per_gas;
+
+    match sign_and_send_transaction(transaction, wallet).await {
+        Ok(tx_hash) => {
+            nonces::mark_submitted(nonce_key, nonce, tx_hash.clone(), max_fee_per_gas, max_priority_fee_per_gas);
+            Ok(tx_hash)
+        }
+        Err(e) => {
+            nonces::mark_failed(nonce_key, nonce);
+            if e.contains("nonce too low") {
+                if let Ok(chain_nonce) = rpc_service::get_transaction_count(&wallet.address().to_string()).await {
+                    nonces::track(nonce_key, chain_nonce);
+                }
+            }
+            Err(e)
+        }
+    }
 }
 
-async fn sign_and_send_transaction(transaction: TxEip1559, wallet: &BaseWallet) -> Result<String, String> {
+async fn sign_and_send_transaction(transaction: TxEip1559, wallet: &EvmWallet) -> Result<String, String> {
     let tx_hash = transaction.signature_hash().0;
 
     let (raw_signature, recovery_id) = wallet.sign_with_ecdsa(tx_hash).await;
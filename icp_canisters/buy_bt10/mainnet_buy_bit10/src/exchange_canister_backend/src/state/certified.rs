@@ -0,0 +1,89 @@
+use crate::services::token_service;
+use crate::state::{events, reserves, storage};
+use crate::types::swap::SwapResponseData;
+use bit10_core::certification::{candid_hash, list_hash, Certified, CertifiedTree};
+use std::cell::RefCell;
+
+pub const RESERVES_REPORT_LABEL: &[u8] = b"reserves_report_hash";
+pub const BIT10_TOKENS_LABEL: &[u8] = b"bit10_tokens";
+pub const BUY_HISTORY_LABEL: &[u8] = b"buy_history";
+pub const SELL_HISTORY_LABEL: &[u8] = b"sell_history";
+
+thread_local! {
+    /// Everything the canister certifies: the event log's tip, the latest
+    /// reserves report hash, each BIT10 token under `bit10_tokens/<name>` and
+    /// the buy and sell histories as `list_hash` chains.
+    static TREE: RefCell<CertifiedTree> = RefCell::new(CertifiedTree::default());
+}
+
+fn update(f: impl FnOnce(&mut CertifiedTree)) {
+    TREE.with(|tree| {
+        let mut tree = tree.borrow_mut();
+        f(&mut tree);
+        ic_cdk::api::set_certified_data(&tree.root_hash());
+    });
+}
+
+/// Derives the whole tree from state. Certified data does not survive an
+/// upgrade, so this runs on restore; everything else updates single labels.
+pub fn certify_all() {
+    update(|tree| {
+        *tree = CertifiedTree::default();
+        events::certify_tip(tree);
+        if let Some(report) = reserves::latest_report() {
+            tree.insert(RESERVES_REPORT_LABEL, reserves::report_hash(&report));
+        }
+        tree.insert_subtree(BIT10_TOKENS_LABEL, bit10_tokens());
+        tree.insert(BUY_HISTORY_LABEL, list_hash(&storage::get_buy_history()));
+        tree.insert(SELL_HISTORY_LABEL, list_hash(&storage::get_sell_history()));
+    });
+}
+
+pub fn certify_tip() {
+    update(events::certify_tip);
+}
+
+pub fn certify_reserves_report() {
+    if let Some(report) = reserves::latest_report() {
+        update(|tree| tree.insert(RESERVES_REPORT_LABEL, reserves::report_hash(&report)));
+    }
+}
+
+/// `bit10_token` builds a fresh map on every call, so tokens are hashed one
+/// by one rather than as a whole.
+pub fn certify_bit10_tokens() {
+    let tokens = bit10_tokens();
+    update(|tree| tree.insert_subtree(BIT10_TOKENS_LABEL, tokens));
+}
+
+pub fn append_buy(swap: &SwapResponseData) {
+    update(|tree| tree.append(BUY_HISTORY_LABEL, swap));
+}
+
+pub fn append_sell(swap: &SwapResponseData) {
+    update(|tree| tree.append(SELL_HISTORY_LABEL, swap));
+}
+
+fn bit10_tokens() -> CertifiedTree {
+    let mut tokens = CertifiedTree::default();
+    for (name, details) in token_service::get_bit10_token_info().tokens {
+        tokens.insert(name, candid_hash(&details));
+    }
+    tokens
+}
+
+pub fn witness(label: &[u8]) -> Vec<u8> {
+    TREE.with(|tree| tree.borrow().witness(&[&[label]]).to_cbor())
+}
+
+pub fn tip_hash_tree() -> Vec<u8> {
+    TREE.with(|tree| bit10_core::events::tip_hash_tree(&tree.borrow()))
+}
+
+pub fn certified<T>(data: T, label: &[u8]) -> Certified<T> {
+    Certified {
+        data,
+        certificate: ic_cdk::api::data_certificate(),
+        hash_tree: witness(label),
+    }
+}
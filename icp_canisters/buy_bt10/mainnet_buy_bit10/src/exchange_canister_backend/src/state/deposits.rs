@@ -0,0 +1,36 @@
+use crate::types::swap::SwapResponse;
+use bit10_core::deposits::{deposit_key, DepositRegistry, Settlement};
+use bit10_core::metrics::deposits_in_flight;
+use std::cell::RefCell;
+
+thread_local! {
+    pub static DEPOSIT_REGISTRY: RefCell<DepositRegistry> = RefCell::new(DepositRegistry::default());
+}
+
+pub fn export_deposits() -> DepositRegistry {
+    DEPOSIT_REGISTRY.with(|d| d.borrow().clone())
+}
+
+pub fn import_deposits(registry: DepositRegistry) {
+    DEPOSIT_REGISTRY.with(|d| *d.borrow_mut() = registry);
+}
+
+/// Every buy is decoded from its own transaction, so a transaction carries
+/// one deposit and is keyed as a whole.
+pub fn key(chain: &str, tx_hash: &str) -> String {
+    deposit_key(chain, tx_hash, None)
+}
+
+pub fn release(key: &str) -> bool {
+    DEPOSIT_REGISTRY.with(|d| d.borrow_mut().release(key))
+}
+
+pub fn in_flight() -> usize {
+    DEPOSIT_REGISTRY.with(|d| deposits_in_flight(&d.borrow()))
+}
+
+impl Settlement for SwapResponse {
+    fn succeeded(&self) -> bool {
+        matches!(self, SwapResponse::Ok(_))
+    }
+}
@@ -1,2 +1,11 @@
+pub mod certified;
+pub mod cycles;
+pub mod deposits;
+pub mod events;
+pub mod metrics;
+pub mod nonces;
+pub mod providers;
+pub mod registry;
+pub mod reserves;
 pub mod state;
 pub mod storage;
\ No newline at end of file
@@ -0,0 +1,117 @@
+use crate::state::state::read_state;
+use crate::types::network::RpcChain;
+use crate::utils::constants::{
+    get_bsc_rpc_providers, get_ethereum_rpc_providers, get_rpc_providers, get_sui_rpc_providers,
+    get_tron_rpc_providers,
+};
+use bit10_core::providers::{ProviderHealth, ProviderSet, ProviderSetView, RpcConfig, RpcProvider};
+use bit10_core::rpc::http::{json_rpc_body, query_providers, Failover, JsonRpc};
+use std::cell::RefCell;
+use std::future::Future;
+
+const DEFAULT_QUORUM: u8 = 2;
+
+const ALL_CHAINS: [RpcChain; 6] = [
+    RpcChain::Base,
+    RpcChain::Bsc,
+    RpcChain::Solana,
+    RpcChain::Ethereum,
+    RpcChain::Tron,
+    RpcChain::Sui,
+];
+
+thread_local! {
+    static RPC_CONFIG: RefCell<RpcConfig<RpcChain>> = RefCell::new(RpcConfig::default());
+}
+
+fn default_provider_set(chain: RpcChain) -> ProviderSet {
+    let providers = match chain {
+        RpcChain::Base => get_rpc_providers(read_state(|s| s.base_network())),
+        RpcChain::Bsc => get_bsc_rpc_providers(read_state(|s| s.bsc_network())),
+        RpcChain::Solana => read_state(|s| s.solana_network()).rpc_providers(),
+        RpcChain::Ethereum => get_ethereum_rpc_providers(),
+        RpcChain::Tron => get_tron_rpc_providers(),
+        RpcChain::Sui => get_sui_rpc_providers(),
+    };
+    ProviderSet::new(providers, DEFAULT_QUORUM).expect("Invalid default RPC providers")
+}
+
+fn with_provider_set<R>(chain: RpcChain, f: impl FnOnce(&mut ProviderSet) -> R) -> R {
+    RPC_CONFIG.with(|config| f(config.borrow_mut().get_or_insert_with(chain, || default_provider_set(chain))))
+}
+
+pub fn export_rpc_config() -> RpcConfig<RpcChain> {
+    RPC_CONFIG.with(|config| config.borrow().clone())
+}
+
+pub fn import_rpc_config(config: RpcConfig<RpcChain>) {
+    RPC_CONFIG.with(|c| *c.borrow_mut() = config);
+}
+
+/// The chain's callable providers, tried in order until one answers.
+pub fn failover(chain: RpcChain) -> Failover {
+    Failover(with_provider_set(chain, |set| set.callable()))
+}
+
+pub fn get_rpc_config() -> Vec<(RpcChain, ProviderSetView)> {
+    ALL_CHAINS
+        .iter()
+        .map(|&chain| (chain, with_provider_set(chain, |set| set.view())))
+        .collect()
+}
+
+pub fn set_rpc_providers(chain: RpcChain, providers: Vec<RpcProvider>, quorum: u8) -> Result<(), String> {
+    RPC_CONFIG.with(|config| {
+        config
+            .borrow_mut()
+            .set_providers(chain, providers, quorum, || default_provider_set(chain))
+    })
+}
+
+pub fn set_rpc_api_key(chain: RpcChain, provider: &str, api_key: Option<String>) -> Result<(), String> {
+    RPC_CONFIG.with(|config| {
+        config
+            .borrow_mut()
+            .set_api_key(chain, provider, api_key, || default_provider_set(chain))
+    })
+}
+
+pub fn reset_rpc_providers(chain: RpcChain) {
+    RPC_CONFIG.with(|config| config.borrow_mut().reset(&chain));
+}
+
+pub fn get_provider_health(chain: RpcChain) -> Vec<(String, ProviderHealth)> {
+    with_provider_set(chain, |set| set.health())
+}
+
+/// Health of every chain read since the last reset, for `/metrics`.
+pub fn get_all_provider_health() -> Vec<(String, Vec<(String, ProviderHealth)>)> {
+    RPC_CONFIG.with(|config| {
+        config
+            .borrow()
+            .health()
+            .into_iter()
+            .map(|(chain, health)| (format!("{:?}", chain), health))
+            .collect()
+    })
+}
+
+pub fn clear_provider_flag(chain: RpcChain, name: &str) -> Result<(), String> {
+    with_provider_set(chain, |set| set.clear_flag(name))
+}
+
+/// Sends each request to every provider for the chain and answers with the
+/// result a quorum of them agree on.
+pub struct QuorumRpc(pub RpcChain);
+
+impl JsonRpc for QuorumRpc {
+    fn call(&self, json_payload: String) -> impl Future<Output = Result<String, String>> {
+        let chain = self.0;
+        async move {
+            let providers = with_provider_set(chain, |set| set.callable());
+            let responses = query_providers(&providers, &json_payload).await;
+            let result = with_provider_set(chain, |set| set.tally(responses, ic_cdk::api::time()))?;
+            Ok(json_rpc_body(&result))
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use crate::services::token_service::{default_supported_bit10_tokens, default_supported_tokens};
+use crate::types::registry::{BuyPair, RegistryChange, RegistryEvent, TokenKind};
+use crate::types::token::Token;
+use candid::{CandidType, Deserialize, Principal};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+thread_local! {
+    static TOKEN_REGISTRY: RefCell<TokenRegistry> = RefCell::new(TokenRegistry::with_defaults());
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct TokenRegistry {
+    pub version: u64,
+    pub tokens: BTreeMap<String, Token>,
+    pub bit10_tokens: BTreeMap<String, Token>,
+    pub pairs: BTreeMap<String, BuyPair>,
+    pub events: Vec<RegistryEvent>,
+}
+
+impl TokenRegistry {
+    pub fn with_defaults() -> Self {
+        let index = |tokens: Vec<Token>| {
+            tokens
+                .into_iter()
+                .filter_map(|token| registry_key(&token).ok().map(|key| (key, token)))
+                .collect()
+        };
+
+        let tokens: BTreeMap<String, Token> = index(default_supported_tokens());
+        let bit10_tokens: BTreeMap<String, Token> = index(default_supported_bit10_tokens());
+
+        // Every payment token could buy every BIT10 token on its chain before
+        // pairs were listed, so that is what the defaults keep.
+        let mut pairs = BTreeMap::new();
+        for token in tokens.values() {
+            for bit10_token in bit10_tokens.values().filter(|t| t.token_chain == token.token_chain) {
+                let pair = BuyPair {
+                    token_chain: token.token_chain.clone(),
+                    token_address: token.token_address.clone().unwrap_or_default(),
+                    bit10_token_address: bit10_token.token_address.clone().unwrap_or_default(),
+                };
+                pairs.insert(pair_key(&pair), pair);
+            }
+        }
+
+        TokenRegistry {
+            version: 0,
+            tokens,
+            bit10_tokens,
+            pairs,
+            events: Vec::new(),
+        }
+    }
+
+    fn list(&self, kind: TokenKind) -> &BTreeMap<String, Token> {
+        match kind {
+            TokenKind::Payment => &self.tokens,
+            TokenKind::Bit10 => &self.bit10_tokens,
+        }
+    }
+
+    fn list_mut(&mut self, kind: TokenKind) -> &mut BTreeMap<String, Token> {
+        match kind {
+            TokenKind::Payment => &mut self.tokens,
+            TokenKind::Bit10 => &mut self.bit10_tokens,
+        }
+    }
+
+    fn kind_of(&self, key: &str) -> Option<TokenKind> {
+        if self.tokens.contains_key(key) {
+            Some(TokenKind::Payment)
+        } else if self.bit10_tokens.contains_key(key) {
+            Some(TokenKind::Bit10)
+        } else {
+            None
+        }
+    }
+
+    fn record(&mut self, caller: Principal, change: RegistryChange) -> u64 {
+        self.version += 1;
+        self.events.push(RegistryEvent {
+            version: self.version,
+            timestamp: ic_cdk::api::time(),
+            caller,
+            change,
+        });
+        self.version
+    }
+}
+
+pub fn export_registry() -> TokenRegistry {
+    TOKEN_REGISTRY.with(|r| r.borrow().clone())
+}
+
+pub fn import_registry(registry: TokenRegistry) {
+    TOKEN_REGISTRY.with(|r| *r.borrow_mut() = registry);
+}
+
+pub fn get_tokens(kind: TokenKind) -> Vec<Token> {
+    TOKEN_REGISTRY.with(|r| r.borrow().list(kind).values().cloned().collect())
+}
+
+pub fn get_pairs() -> Vec<BuyPair> {
+    TOKEN_REGISTRY.with(|r| r.borrow().pairs.values().cloned().collect())
+}
+
+pub fn is_pair_listed(token_chain: &str, token_address: &str, bit10_token_address: &str) -> bool {
+    let key = pair_key(&BuyPair {
+        token_chain: token_chain.to_string(),
+        token_address: token_address.to_string(),
+        bit10_token_address: bit10_token_address.to_string(),
+    });
+    TOKEN_REGISTRY.with(|r| r.borrow().pairs.contains_key(&key))
+}
+
+pub fn get_registry_version() -> u64 {
+    TOKEN_REGISTRY.with(|r| r.borrow().version)
+}
+
+pub fn get_registry_events(offset: u64, limit: u64) -> Vec<RegistryEvent> {
+    TOKEN_REGISTRY.with(|r| {
+        r.borrow()
+            .events
+            .iter()
+            .skip(usize::try_from(offset).unwrap_or(usize::MAX))
+            .take(usize::try_from(limit).unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    })
+}
+
+pub fn add_token(caller: Principal, kind: TokenKind, token: Token) -> Result<u64, String> {
+    let key = registry_key(&token)?;
+    validate_token(kind, &token)?;
+
+    TOKEN_REGISTRY.with(|r| {
+        let mut registry = r.borrow_mut();
+
+        if registry.kind_of(&key).is_some() {
+            return Err(format!(
+                "Token {} on {} is already registered",
+                token.token_address.clone().unwrap_or_default(),
+                token.token_chain
+            ));
+        }
+
+        registry.list_mut(kind).insert(key, token.clone());
+        Ok(registry.record(caller, RegistryChange::TokenAdded { kind, token }))
+    })
+}
+
+pub fn update_token(caller: Principal, token: Token) -> Result<u64, String> {
+    let key = registry_key(&token)?;
+
+    TOKEN_REGISTRY.with(|r| {
+        let mut registry = r.borrow_mut();
+
+        let kind = registry.kind_of(&key).ok_or_else(|| {
+            format!(
+                "Token {} on {} not found",
+                token.token_address.clone().unwrap_or_default(),
+                token.token_chain
+            )
+        })?;
+        validate_token(kind, &token)?;
+
+        registry.list_mut(kind).insert(key, token.clone());
+        Ok(registry.record(caller, RegistryChange::TokenUpdated { kind, token }))
+    })
+}
+
+pub fn set_token_active(
+    caller: Principal,
+    token_chain: &str,
+    token_address: &str,
+    is_active: bool,
+) -> Result<u64, String> {
+    let key = format!("{}:{}", token_chain, token_address).to_lowercase();
+
+    TOKEN_REGISTRY.with(|r| {
+        let mut registry = r.borrow_mut();
+
+        let kind = registry
+            .kind_of(&key)
+            .ok_or_else(|| format!("Token {} on {} not found", token_address, token_chain))?;
+
+        let token = registry
+            .list_mut(kind)
+            .get_mut(&key)
+            .expect("token kind was resolved from the same key");
+
+        if token.is_active == is_active {
+            return Err(format!(
+                "Token {} on {} is already {}",
+                token_address,
+                token_chain,
+                if is_active { "active" } else { "inactive" }
+            ));
+        }
+
+        token.is_active = is_active;
+        Ok(registry.record(
+            caller,
+            RegistryChange::TokenActiveSet {
+                kind,
+                token_chain: token_chain.to_string(),
+                token_address: token_address.to_string(),
+                is_active,
+            },
+        ))
+    })
+}
+
+pub fn add_pair(caller: Principal, pair: BuyPair) -> Result<u64, String> {
+    let key = pair_key(&pair);
+
+    TOKEN_REGISTRY.with(|r| {
+        let mut registry = r.borrow_mut();
+
+        let token_key = format!("{}:{}", pair.token_chain, pair.token_address).to_lowercase();
+        if !registry.tokens.contains_key(&token_key) {
+            return Err(format!(
+                "Payment token {} on {} not found",
+                pair.token_address, pair.token_chain
+            ));
+        }
+
+        let bit10_key = format!("{}:{}", pair.token_chain, pair.bit10_token_address).to_lowercase();
+        if !registry.bit10_tokens.contains_key(&bit10_key) {
+            return Err(format!(
+                "BIT10 token {} on {} not found",
+                pair.bit10_token_address, pair.token_chain
+            ));
+        }
+
+        if registry.pairs.contains_key(&key) {
+            return Err(format!(
+                "Pair {} / {} on {} already exists",
+                pair.token_address, pair.bit10_token_address, pair.token_chain
+            ));
+        }
+
+        registry.pairs.insert(key, pair.clone());
+        Ok(registry.record(caller, RegistryChange::PairAdded(pair)))
+    })
+}
+
+pub fn remove_pair(caller: Principal, pair: BuyPair) -> Result<u64, String> {
+    TOKEN_REGISTRY.with(|r| {
+        let mut registry = r.borrow_mut();
+
+        if registry.pairs.remove(&pair_key(&pair)).is_none() {
+            return Err(format!(
+                "Pair {} / {} on {} not found",
+                pair.token_address, pair.bit10_token_address, pair.token_chain
+            ));
+        }
+
+        Ok(registry.record(caller, RegistryChange::PairRemoved(pair)))
+    })
+}
+
+fn pair_key(pair: &BuyPair) -> String {
+    format!(
+        "{}:{}:{}",
+        pair.token_chain, pair.token_address, pair.bit10_token_address
+    )
+    .to_lowercase()
+}
+
+fn registry_key(token: &Token) -> Result<String, String> {
+    let address = token
+        .token_address
+        .as_ref()
+        .filter(|address| !address.trim().is_empty())
+        .ok_or("token_address is required")?;
+
+    if token.token_chain.trim().is_empty() {
+        return Err("token_chain cannot be empty".to_string());
+    }
+
+    Ok(format!("{}:{}", token.token_chain, address).to_lowercase())
+}
+
+fn validate_token(kind: TokenKind, token: &Token) -> Result<(), String> {
+    match kind {
+        TokenKind::Payment if token.price_feed_id.is_none() => {
+            Err("Payment tokens require a price_feed_id".to_string())
+        }
+        TokenKind::Bit10 if token.price_feed_link.is_none() => {
+            Err("BIT10 tokens require a price_feed_link".to_string())
+        }
+        _ => Ok(()),
+    }
+}
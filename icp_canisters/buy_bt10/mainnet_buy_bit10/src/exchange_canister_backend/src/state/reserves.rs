@@ -0,0 +1,53 @@
+use crate::state::certified;
+use crate::types::reserves::{CustodyAccount, ReservesReport};
+use candid::{CandidType, Deserialize};
+use sha2::{Digest, Sha256};
+use std::cell::RefCell;
+
+thread_local! {
+    static RESERVES: RefCell<ReservesState> = RefCell::new(ReservesState::default());
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct ReservesState {
+    pub custody_accounts: Vec<CustodyAccount>,
+    pub reports: Vec<ReservesReport>,
+}
+
+pub fn export_reserves() -> ReservesState {
+    RESERVES.with(|r| r.borrow().clone())
+}
+
+pub fn import_reserves(state: ReservesState) {
+    RESERVES.with(|r| *r.borrow_mut() = state);
+}
+
+pub fn get_custody_accounts() -> Vec<CustodyAccount> {
+    RESERVES.with(|r| r.borrow().custody_accounts.clone())
+}
+
+pub fn set_custody_accounts(accounts: Vec<CustodyAccount>) {
+    RESERVES.with(|r| r.borrow_mut().custody_accounts = accounts);
+}
+
+pub fn next_report_id() -> u64 {
+    RESERVES.with(|r| r.borrow().reports.len() as u64)
+}
+
+pub fn add_report(report: ReservesReport) {
+    RESERVES.with(|r| r.borrow_mut().reports.push(report));
+    certified::certify_reserves_report();
+}
+
+pub fn latest_report() -> Option<ReservesReport> {
+    RESERVES.with(|r| r.borrow().reports.last().cloned())
+}
+
+pub fn get_report(id: u64) -> Option<ReservesReport> {
+    RESERVES.with(|r| r.borrow().reports.get(id as usize).cloned())
+}
+
+pub fn report_hash(report: &ReservesReport) -> Vec<u8> {
+    let encoded = candid::encode_one(report).expect("Failed to encode reserves report");
+    Sha256::digest(&encoded).to_vec()
+}
@@ -1,5 +1,14 @@
+use crate::state::{certified, cycles, deposits, events, nonces, providers, reserves};
+use crate::state::registry::{self, TokenRegistry};
+use crate::state::reserves::ReservesState;
+use crate::types::network::RpcChain;
 use crate::types::swap::SwapResponseData;
-use crate::wallet::base_wallet::BaseWallet;
+use crate::wallet::canister_evm_wallet;
+use bit10_core::cycles::CyclesLedger;
+use bit10_core::deposits::DepositRegistry;
+use bit10_core::events::EventLog;
+use bit10_core::nonce::NonceManager;
+use bit10_core::providers::RpcConfig;
 use std::cell::RefCell;
 use std::collections::HashMap;
 
@@ -72,6 +81,13 @@ pub fn save_to_stable_storage() {
     let bsc_addr = CACHED_BSC_ADDRESS.with(|addr| addr.borrow().clone());
     let solana_addr = CACHED_SOLANA_ADDRESS.with(|addr| addr.borrow().clone());
     let token_data = TOKEN_DATA.with(|data| data.borrow().clone());
+    let token_registry = Some(registry::export_registry());
+    let nonce_manager = Some(nonces::export_nonces());
+    let deposit_registry = Some(deposits::export_deposits());
+    let reserves_state = Some(reserves::export_reserves());
+    let rpc_config = Some(providers::export_rpc_config());
+    let event_log = Some(events::export_events());
+    let cycles_ledger = Some(cycles::export_cycles());
 
     ic_cdk::storage::stable_save((
         base_addr,
@@ -80,12 +96,19 @@ pub fn save_to_stable_storage() {
         buy_history,
         sell_history,
         token_data,
+        token_registry,
+        nonce_manager,
+        deposit_registry,
+        reserves_state,
+        rpc_config,
+        event_log,
+        cycles_ledger,
     ))
     .expect("Failed to save data to stable storage");
 }
 
 pub fn restore_from_stable_storage() {
-    if let Ok((base_addr, bsc_addr, solana_addr, buy_history, sell_history, token_data)) =
+    if let Ok((base_addr, bsc_addr, solana_addr, buy_history, sell_history, token_data, token_registry, nonce_manager, deposit_registry, reserves_state, rpc_config, event_log, cycles_ledger)) =
         ic_cdk::storage::stable_restore::<(
             Option<String>,
             Option<String>,
@@ -93,15 +116,59 @@ pub fn restore_from_stable_storage() {
             Vec<SwapResponseData>,
             Vec<SwapResponseData>,
             HashMap<String, (String, Vec<(String, String, String, String)>)>,
+            Option<TokenRegistry>,
+            Option<NonceManager>,
+            Option<DepositRegistry>,
+            Option<ReservesState>,
+            Option<RpcConfig<RpcChain>>,
+            Option<EventLog>,
+            Option<CyclesLedger>,
         )>()
     {
         CACHED_BASE_ADDRESS.with(|addr| *addr.borrow_mut() = base_addr);
         CACHED_BSC_ADDRESS.with(|addr| *addr.borrow_mut() = bsc_addr);
         CACHED_SOLANA_ADDRESS.with(|addr| *addr.borrow_mut() = solana_addr);
+        // Trades recorded before the event log existed are replayed into it once.
+        let event_log = event_log.unwrap_or_else(|| backfill_events(&buy_history, &sell_history));
         BUY_HISTORY.with(|h| *h.borrow_mut() = buy_history);
         SELL_HISTORY.with(|h| *h.borrow_mut() = sell_history);
         TOKEN_DATA.with(|data| *data.borrow_mut() = token_data);
+        if let Some(token_registry) = token_registry {
+            registry::import_registry(token_registry);
+        }
+        if let Some(nonce_manager) = nonce_manager {
+            nonces::import_nonces(nonce_manager);
+        }
+        if let Some(deposit_registry) = deposit_registry {
+            deposits::import_deposits(deposit_registry);
+        }
+        if let Some(reserves_state) = reserves_state {
+            reserves::import_reserves(reserves_state);
+        }
+        if let Some(rpc_config) = rpc_config {
+            providers::import_rpc_config(rpc_config);
+        }
+        if let Some(cycles_ledger) = cycles_ledger {
+            cycles::import_cycles(cycles_ledger);
+        }
+        events::import_events(event_log);
+        certified::certify_all();
+    }
+}
+
+fn backfill_events(buy_history: &[SwapResponseData], sell_history: &[SwapResponseData]) -> EventLog {
+    let mut trades: Vec<(u64, &SwapResponseData)> = buy_history
+        .iter()
+        .chain(sell_history)
+        .map(|trade| (trade.transaction_timestamp.parse().unwrap_or_default(), trade))
+        .collect();
+    trades.sort_by_key(|(timestamp, _)| *timestamp);
+
+    let mut log = EventLog::default();
+    for (timestamp, trade) in trades {
+        log.append(trade.to_event(), timestamp);
     }
+    log
 }
 
 pub async fn get_cached_base_address() -> String {
@@ -110,9 +177,8 @@ pub async fn get_cached_base_address() -> String {
         return address;
     }
 
-    let canister_id = ic_cdk::id();
-    let wallet = BaseWallet::new(canister_id).await;
-    let address = wallet.base_address().to_string();
+    let wallet = canister_evm_wallet().await;
+    let address = wallet.address().to_string();
     
     CACHED_BASE_ADDRESS.with(|addr| *addr.borrow_mut() = Some(address.clone()));
     
@@ -125,9 +191,8 @@ pub async fn get_cached_bsc_address() -> String {
         return address;
     }
 
-    let canister_id = ic_cdk::id();
-    let wallet = BaseWallet::new(canister_id).await;
-    let address = wallet.base_address().to_string();
+    let wallet = canister_evm_wallet().await;
+    let address = wallet.address().to_string();
     
     CACHED_BSC_ADDRESS.with(|addr| *addr.borrow_mut() = Some(address.clone()));
     
@@ -140,7 +205,7 @@ pub async fn get_cached_solana_address() -> String {
         return address;
     }
 
-    let wallet = crate::wallet::solana_wallet::SolanaWallet::new_canister_wallet().await;
+    let wallet = crate::wallet::canister_solana_wallet().await;
     let address = wallet.solana_address();
     
     CACHED_SOLANA_ADDRESS.with(|addr| *addr.borrow_mut() = Some(address.clone()));
@@ -157,11 +222,17 @@ pub fn get_sell_history() -> Vec<SwapResponseData> {
 }
 
 pub fn add_to_buy_history(swap: SwapResponseData) {
+    let event = swap.to_event();
+    certified::append_buy(&swap);
     BUY_HISTORY.with(|h| h.borrow_mut().push(swap));
+    events::record_event(event);
 }
 
 pub fn add_to_sell_history(swap: SwapResponseData) {
+    let event = swap.to_event();
+    certified::append_sell(&swap);
     SELL_HISTORY.with(|h| h.borrow_mut().push(swap));
+    events::record_event(event);
 }
 
 pub fn get_token_data() -> HashMap<String, (String, Vec<(String, String, String, String)>)> {
@@ -190,6 +261,7 @@ pub fn update_token_bought(token_name: &str, token_address: &str, amount: &str)
             }
         }
     });
+    certified::certify_bit10_tokens();
 }
 
 pub fn update_token_sold(token_name: &str, token_address: &str, amount: &str) {
@@ -214,6 +286,7 @@ pub fn update_token_sold(token_name: &str, token_address: &str, amount: &str) {
             }
         }
     });
+    certified::certify_bit10_tokens();
 }
 
 pub fn transaction_exists(tx_hash: &str) -> bool {
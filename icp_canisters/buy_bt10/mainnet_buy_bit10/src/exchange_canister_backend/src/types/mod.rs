@@ -1,3 +1,5 @@
 pub mod network;
+pub mod registry;
+pub mod reserves;
 pub mod swap;
 pub mod token;
\ No newline at end of file
@@ -1,3 +1,4 @@
+use bit10_core::providers::RpcProvider;
 use candid::{CandidType, Deserialize};
 use ic_cdk::api::management_canister::ecdsa::{EcdsaCurve, EcdsaKeyId};
 
@@ -50,24 +51,33 @@ pub enum SolanaNetwork {
 }
 
 impl SolanaNetwork {
-    pub fn rpc_url(&self) -> String {
+    pub fn rpc_providers(&self) -> Vec<RpcProvider> {
         match self {
-            SolanaNetwork::Mainnet => {
-                format!(
-                    "https://rpc.ankr.com/solana_devnet/{}",
-                    crate::utils::constants::ANKR_API_KEY
-                )
-            }
-            SolanaNetwork::Devnet => {
-                format!(
-                    "https://rpc.ankr.com/solana_devnet/{}",
-                    crate::utils::constants::ANKR_API_KEY
-                )
-            }
+            SolanaNetwork::Mainnet => vec![
+                RpcProvider::new("solana", "https://api.mainnet-beta.solana.com"),
+                RpcProvider::new("publicnode", "https://solana-rpc.publicnode.com"),
+                RpcProvider::new("ankr", "https://rpc.ankr.com/solana/{api_key}"),
+            ],
+            SolanaNetwork::Devnet => vec![
+                RpcProvider::new("solana", "https://api.devnet.solana.com"),
+                RpcProvider::new("drpc", "https://solana-devnet.drpc.org"),
+                RpcProvider::new("ankr", "https://rpc.ankr.com/solana_devnet/{api_key}"),
+            ],
         }
     }
 }
 
+/// Chains the canister reads through a provider set.
+#[derive(CandidType, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum RpcChain {
+    Base,
+    Bsc,
+    Solana,
+    Ethereum,
+    Tron,
+    Sui,
+}
+
 #[derive(CandidType, Deserialize, Debug, Default, PartialEq, Eq, Clone, Copy)]
 pub enum Ed25519KeyName {
     #[default]
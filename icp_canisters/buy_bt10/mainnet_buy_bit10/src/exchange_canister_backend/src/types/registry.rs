@@ -0,0 +1,39 @@
+use crate::types::token::Token;
+use candid::{CandidType, Deserialize, Principal};
+
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenKind {
+    Payment,
+    Bit10,
+}
+
+/// Lets a payment token buy, and be paid out for, a BIT10 token. Both live on
+/// `token_chain`.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BuyPair {
+    pub token_chain: String,
+    pub token_address: String,
+    pub bit10_token_address: String,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub enum RegistryChange {
+    TokenAdded { kind: TokenKind, token: Token },
+    TokenUpdated { kind: TokenKind, token: Token },
+    TokenActiveSet {
+        kind: TokenKind,
+        token_chain: String,
+        token_address: String,
+        is_active: bool,
+    },
+    PairAdded(BuyPair),
+    PairRemoved(BuyPair),
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct RegistryEvent {
+    pub version: u64,
+    pub timestamp: u64,
+    pub caller: Principal,
+    pub change: RegistryChange,
+}
@@ -0,0 +1,78 @@
+use candid::{CandidType, Deserialize, Nat};
+
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReserveChain {
+    Ethereum,
+    Base,
+    Bsc,
+    Solana,
+    Tron,
+    Sui,
+    Icp,
+}
+
+/// An asset to read at a custody address. `token_address` is `None` for the
+/// chain's native coin; on ICP it is the ledger canister id and on Sui the
+/// coin type.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ReserveAsset {
+    pub symbol: String,
+    pub token_address: Option<String>,
+    pub decimals: u8,
+    pub price_feed_id: Option<String>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct CustodyAccount {
+    pub chain: ReserveChain,
+    pub address: String,
+    pub assets: Vec<ReserveAsset>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ReserveBalance {
+    pub chain: ReserveChain,
+    pub address: String,
+    pub symbol: String,
+    pub token_address: Option<String>,
+    pub raw_balance: Nat,
+    pub balance: String,
+    pub usd_price: Option<f64>,
+    pub usd_value: Option<f64>,
+    pub error: Option<String>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct Bit10Supply {
+    pub token_name: String,
+    pub total_supply: String,
+    pub usd_price: Option<f64>,
+    pub usd_value: Option<f64>,
+    pub error: Option<String>,
+}
+
+/// `complete` is false if any balance or price could not be read, in which
+/// case the USD totals and ratio only cover what was.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ReservesReport {
+    pub id: u64,
+    pub generated_at: u64,
+    pub balances: Vec<ReserveBalance>,
+    pub supplies: Vec<Bit10Supply>,
+    pub reserves_usd: f64,
+    pub supply_usd: f64,
+    pub collateral_ratio: Option<f64>,
+    pub complete: bool,
+}
+
+/// The latest report together with its certification. `report_hash` is the
+/// SHA-256 of the candid encoded report, certified under
+/// `reserves_report_hash` in the canister's hash tree; `hash_tree` is the
+/// witness for it. `certificate` is only present on query calls.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct CertifiedReservesReport {
+    pub report: ReservesReport,
+    pub report_hash: Vec<u8>,
+    pub certificate: Option<Vec<u8>>,
+    pub hash_tree: Vec<u8>,
+}
@@ -1,9 +1,11 @@
+use bit10_core::errors::ApiError;
+use bit10_core::events::{Event, TokenTransfer, TradeEvent};
 use candid::CandidType;
 
 #[derive(CandidType, serde::Serialize)]
 pub enum SwapResponse {
     Ok(SwapResponseData),
-    Err(String),
+    Err(ApiError),
 }
 
 #[derive(CandidType, serde::Deserialize, serde::Serialize, Clone)]
@@ -20,4 +22,28 @@ pub struct SwapResponseData {
     pub transaction_type: String,
     pub transaction_timestamp: String,
     pub network: String,
-}
\ No newline at end of file
+}
+
+impl SwapResponseData {
+    /// Sells come from the sell history; everything else was a buy, and a
+    /// reverted buy sent the input back instead of the BIT10 token.
+    pub fn to_event(&self) -> Event {
+        let received = if self.transaction_type == "Revert" {
+            TokenTransfer::new(&self.network, &self.token_in_address, &self.token_in_amount, &self.token_out_tx_hash)
+        } else {
+            TokenTransfer::new(&self.network, &self.token_out_address, &self.token_out_amount, &self.token_out_tx_hash)
+        };
+        let trade = TradeEvent {
+            trade_id: Some(self.swap_id.clone()),
+            account: self.user_wallet_address.clone(),
+            paid: TokenTransfer::new(&self.network, &self.token_in_address, &self.token_in_amount, &self.token_in_tx_hash),
+            received,
+            usd_amount: Some(self.token_in_usd_amount.clone()),
+        };
+        if self.transaction_type == "Sell" {
+            Event::Sell(trade)
+        } else {
+            Event::Buy(trade)
+        }
+    }
+}
@@ -1,19 +1,7 @@
 use candid::{CandidType, Deserialize};
 use std::collections::HashMap;
 
-#[derive(CandidType, Deserialize, Debug, Clone, serde::Serialize)]
-pub struct Token {
-    pub token_id: Option<String>,
-    pub token_name: String,
-    pub token_symbol: String,
-    pub token_address: Option<String>,
-    pub token_chain: String,
-    pub token_decimals: u8,
-    pub is_native: bool,
-    pub is_active: bool,
-    pub price_feed_id: Option<String>,
-    pub price_feed_link: Option<String>,
-}
+pub use bit10_core::types::token::Token;
 
 #[derive(CandidType, Deserialize, Debug, Clone, serde::Serialize)]
 pub struct TokenAllocation {
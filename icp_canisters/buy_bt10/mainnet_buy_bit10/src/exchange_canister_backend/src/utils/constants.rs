@@ -1,34 +1,66 @@
 use ic_principal::Principal;
 use crate::types::network::{BaseNetwork, BscNetwork};
+use bit10_core::providers::RpcProvider;
 
-pub const ANKR_API_KEY: &str = "hbshdbfjjndkfvnkdlfxv,df";
-
-pub fn get_rpc_url(network: BaseNetwork) -> String {
+// Ankr endpoints take the key as the last path segment. It is set per chain
+// with `set_rpc_api_key` and never compiled in; until then ankr is skipped, so
+// every set lists enough keyless endpoints for the quorum, and lists them
+// first so the primary provider works out of the box.
+pub fn get_rpc_providers(network: BaseNetwork) -> Vec<RpcProvider> {
     match network {
-        BaseNetwork::Mainnet => format!(
-            "https://rpc.ankr.com/base/{}",
-            ANKR_API_KEY
-        ),
-        BaseNetwork::Sepolia => format!(
-            "https://rpc.ankr.com/base_sepolia/{}",
-            ANKR_API_KEY
-        ),
+        BaseNetwork::Mainnet => vec![
+            RpcProvider::new("base", "https://mainnet.base.org"),
+            RpcProvider::new("publicnode", "https://base-rpc.publicnode.com"),
+            RpcProvider::new("ankr", "https://rpc.ankr.com/base/{api_key}"),
+        ],
+        BaseNetwork::Sepolia => vec![
+            RpcProvider::new("base", "https://sepolia.base.org"),
+            RpcProvider::new("publicnode", "https://base-sepolia-rpc.publicnode.com"),
+            RpcProvider::new("ankr", "https://rpc.ankr.com/base_sepolia/{api_key}"),
+        ],
     }
 }
 
-pub fn get_bsc_rpc_url(network: BscNetwork) -> String {
+pub fn get_bsc_rpc_providers(network: BscNetwork) -> Vec<RpcProvider> {
     match network {
-        BscNetwork::Mainnet => format!(
-            "https://rpc.ankr.com/bsc/{}",
-            ANKR_API_KEY
-        ),
-        BscNetwork::Testnet => format!(
-            "https://rpc.ankr.com/bsc_testnet_chapel/{}",
-            ANKR_API_KEY
-        ),
+        BscNetwork::Mainnet => vec![
+            RpcProvider::new("bnbchain", "https://bsc-dataseed.bnbchain.org"),
+            RpcProvider::new("publicnode", "https://bsc-rpc.publicnode.com"),
+            RpcProvider::new("ankr", "https://rpc.ankr.com/bsc/{api_key}"),
+        ],
+        BscNetwork::Testnet => vec![
+            RpcProvider::new("bnbchain", "https://data-seed-prebsc-1-s1.bnbchain.org:8545"),
+            RpcProvider::new("publicnode", "https://bsc-testnet-rpc.publicnode.com"),
+            RpcProvider::new("ankr", "https://rpc.ankr.com/bsc_testnet_chapel/{api_key}"),
+        ],
     }
 }
 
+pub fn get_ethereum_rpc_providers() -> Vec<RpcProvider> {
+    vec![
+        RpcProvider::new("publicnode", "https://ethereum-rpc.publicnode.com"),
+        RpcProvider::new("llamarpc", "https://eth.llamarpc.com"),
+        RpcProvider::new("ankr", "https://rpc.ankr.com/eth/{api_key}"),
+    ]
+}
+
+/// Tron's Ethereum-compatible JSON-RPC endpoints, which take hex addresses.
+pub fn get_tron_rpc_providers() -> Vec<RpcProvider> {
+    vec![
+        RpcProvider::new("trongrid", "https://api.trongrid.io/jsonrpc"),
+        RpcProvider::new("publicnode", "https://tron-rpc.publicnode.com/jsonrpc"),
+        RpcProvider::new("ankr", "https://rpc.ankr.com/tron_jsonrpc/{api_key}"),
+    ]
+}
+
+pub fn get_sui_rpc_providers() -> Vec<RpcProvider> {
+    vec![
+        RpcProvider::new("mysten", "https://fullnode.mainnet.sui.io:443"),
+        RpcProvider::new("publicnode", "https://sui-rpc.publicnode.com"),
+        RpcProvider::new("ankr", "https://rpc.ankr.com/sui/{api_key}"),
+    ]
+}
+
 pub static PLATFORM_WALLET: Lazy<Principal> = Lazy::new(|| {
     Principal::from_slice(b"\x5e\xd7\x74\xb7\x50\xc0\xf1\xe0\xca\x4e\x9c\xd8\x0c\x86\x36\x3b\xff\xc3\x1f\xd2\x90\xdd\x84\x4b\x30\x8b\x70\xa5\x02")
 });
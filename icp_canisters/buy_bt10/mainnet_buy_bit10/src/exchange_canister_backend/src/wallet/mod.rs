@@ -1,4 +1,20 @@
-pub mod base_wallet;
-pub mod solana_wallet;
-pub mod ecdsa;
-pub mod ed25519;
\ No newline at end of file
+use crate::state::state::{lazy_call_ecdsa_public_key, lazy_call_ed25519_public_key, read_state};
+
+pub use bit10_core::wallet::{ecdsa, ed25519, evm_wallet, solana_wallet};
+
+use evm_wallet::EvmWallet;
+use solana_wallet::SolanaWallet;
+
+pub async fn canister_evm_wallet() -> EvmWallet {
+    EvmWallet::new_canister_wallet(
+        &lazy_call_ecdsa_public_key().await,
+        read_state(|s| s.ecdsa_key_id()),
+    )
+}
+
+pub async fn canister_solana_wallet() -> SolanaWallet {
+    SolanaWallet::new_canister_wallet(
+        lazy_call_ed25519_public_key().await,
+        read_state(|s| s.ed25519_key_name()).into(),
+    )
+}
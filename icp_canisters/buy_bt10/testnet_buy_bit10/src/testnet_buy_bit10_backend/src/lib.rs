@@ -1,19 +1,34 @@
+mod cycles;
 mod ecdsa;
+mod events;
+mod metrics;
+mod nonces;
 mod state;
 mod utils;
 mod wallets;
 
 use crate::state::{
-    init_state, read_state,
-    CustomRpcConfig, EcdsaKeyName, EthereumNetwork, InitArg, SWAP_HISTORY,
+    claim_deposit, finish_deposit, init_state, read_state, start_deposit_settlement,
+    mutate_state, EcdsaKeyName, EthereumNetwork, InitArg, DEPOSIT_REGISTRY, SWAP_HISTORY,
 };
 use crate::utils::{
-    call_rpc_with_retry, decode_eth_transaction_data, decode_erc20_recipient_address,
-    extract_actual_amount_from_transaction, get_bit10_token_price, get_dynamic_fees,
-    get_rpc_url, get_token_price_from_feed, make_http_request, u256_to_decimal_string,
+    call_rpc_with_quorum, call_rpc_with_retry, decode_eth_transaction_data, decode_erc20_recipient_address,
+    extract_actual_amount_from_transaction, get_balance, get_bit10_token_price, get_dynamic_fees,
+    get_token_price_from_feed, make_http_request, u256_to_decimal_string,
     addresses_match, get_supported_bit10_tokens, get_supported_tokens,
 };
 use crate::wallets::{BscWallet, EthereumWallet};
+use bit10_core::cycles::{ActionCycles, CyclesBudget, CyclesLedger};
+use bit10_core::deposits::{deposit_key, DepositClaim, DepositRegistry};
+use bit10_core::errors::ApiError;
+use bit10_core::events::{
+    ArchiveInfo, DataCertificate, Event, EventLog, GetArchivesArgs, GetBlocksRequest, GetBlocksResult,
+    SupportedBlockType, TokenTransfer, TradeEvent,
+};
+use bit10_core::metrics::EVM_TRANSFER_GAS;
+use bit10_core::nonce::NonceManager;
+use bit10_core::providers::{ProviderSet, ProviderSetView, RpcProvider};
+use bit10_core::transform::{canonical_body, ResponseShape};
 use candid::{CandidType, Deserialize, Nat, Principal};
 use ic_cdk::api::management_canister::http_request::{
     CanisterHttpRequestArgument, HttpHeader, HttpMethod, HttpResponse, TransformArgs,
@@ -31,8 +46,6 @@ use serde_json::Value;
 use std::str::FromStr;
 use alloy_primitives::{hex, Address, U256};
 
-const TATUM_API_KEY: &str = "t-dhbfhdbfvjnfkbnkm;l";
-
 const BIT10_BTC_LEDGER_CANISTER_ID: Principal =
     Principal::from_slice(b"\x00\x00\x00\x00\x01\xf0\x3b\x0d\x01\x01");
 const TEST_BIT10_DEFI_LEDGER_CANISTER_ID: Principal =
@@ -121,7 +134,7 @@ struct ICPSwapResponseData {
 #[derive(candid::CandidType, serde::Serialize, Clone)]
 enum ICPSwapResponse {
     Ok(ICPSwapResponseData),
-    Err(String),
+    Err(ApiError),
 }
 
 #[derive(CandidType, Deserialize, Debug, Clone, serde::Serialize)]
@@ -139,10 +152,29 @@ pub struct SwapResponseData {
     pub network: String,
 }
 
+impl SwapResponseData {
+    /// A reverted buy refunds the input, so what the account received is the
+    /// input token again, sent back in the revert transaction.
+    pub fn to_event(&self) -> Event {
+        let received = if self.transaction_type == "Revert" {
+            TokenTransfer::new(&self.network, &self.token_in_address, &self.token_in_amount, &self.token_out_tx_hash)
+        } else {
+            TokenTransfer::new(&self.network, &self.token_out_address, &self.token_out_amount, &self.token_out_tx_hash)
+        };
+        Event::Buy(TradeEvent {
+            trade_id: None,
+            account: self.user_wallet_address.clone(),
+            paid: TokenTransfer::new(&self.network, &self.token_in_address, &self.token_in_amount, &self.token_in_tx_hash),
+            received,
+            usd_amount: Some(self.token_in_usd_amount.clone()),
+        })
+    }
+}
+
 #[derive(CandidType, Deserialize, Debug, Clone)]
 pub enum SwapResponse {
     Ok(SwapResponseData),
-    Err(String),
+    Err(ApiError),
 }
 
 #[derive(CandidType, Deserialize)]
@@ -157,10 +189,27 @@ pub struct FeedId {
     pub id: String,
 }
 
+fn record_swap(data: SwapResponseData) {
+    events::record_event(data.to_event());
+    SWAP_HISTORY.with(|h| h.borrow_mut().push(SwapResponse::Ok(data)));
+}
+
 #[pre_upgrade]
 fn pre_upgrade_hook() {
     let history = SWAP_HISTORY.with(|h| h.borrow().clone());
-    ic_cdk::storage::stable_save((history,))
+    let deposits = DEPOSIT_REGISTRY.with(|d| d.borrow().clone());
+    let rpc_providers = read_state(|s| s.configured_rpc_providers());
+    let event_log = events::export_events();
+    let cycles_ledger = cycles::export_cycles();
+    let nonce_manager = nonces::export_nonces();
+    ic_cdk::storage::stable_save((
+        history,
+        Some(deposits),
+        rpc_providers,
+        Some(event_log),
+        Some(cycles_ledger),
+        Some(nonce_manager),
+    ))
         .expect("Failed to save swap history to stable storage");
 }
 
@@ -169,9 +218,107 @@ fn post_upgrade_hook(maybe_init: Option<InitArg>) {
     if let Some(init_arg) = maybe_init {
         init_state(init_arg);
     }
-    if let Ok((history,)) = ic_cdk::storage::stable_restore::<(Vec<SwapResponse>,)>() {
+    if let Ok((history, deposits, rpc_providers, event_log, cycles_ledger, nonce_manager)) = ic_cdk::storage::stable_restore::<(
+        Vec<SwapResponse>,
+        Option<DepositRegistry>,
+        Option<ProviderSet>,
+        Option<EventLog>,
+        Option<CyclesLedger>,
+        Option<NonceManager>,
+    )>() {
+        // Swaps recorded before the event log existed are replayed into it once.
+        let event_log = event_log.unwrap_or_else(|| {
+            let mut log = EventLog::default();
+            for swap in &history {
+                if let SwapResponse::Ok(data) = swap {
+                    let timestamp = data.transaction_timestamp.parse().unwrap_or_default();
+                    log.append(data.to_event(), timestamp);
+                }
+            }
+            log
+        });
+        events::import_events(event_log);
         SWAP_HISTORY.with(|h| *h.borrow_mut() = history);
+        DEPOSIT_REGISTRY.with(|d| *d.borrow_mut() = deposits.unwrap_or_default());
+        mutate_state(|s| s.set_configured_rpc_providers(rpc_providers));
+        if let Some(cycles_ledger) = cycles_ledger {
+            cycles::import_cycles(cycles_ledger);
+        }
+        if let Some(nonce_manager) = nonce_manager {
+            nonces::import_nonces(nonce_manager);
+        }
     }
+    start_gas_checks();
+    start_nonce_reconciliation();
+}
+
+#[query]
+fn get_rpc_config() -> Result<ProviderSetView, String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can read the RPC config".to_string());
+    }
+
+    Ok(read_state(|s| s.rpc_providers().view()))
+}
+
+#[update]
+fn set_rpc_providers(rpc_providers: Vec<RpcProvider>, quorum: u8) -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can set RPC providers".to_string());
+    }
+
+    mutate_state(|s| s.rpc_providers_mut().replace_providers(rpc_providers, quorum))
+}
+
+#[update]
+fn set_rpc_api_key(provider: String, api_key: Option<String>) -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can set RPC API keys".to_string());
+    }
+
+    mutate_state(|s| s.rpc_providers_mut().set_api_key(&provider, api_key))
+}
+
+#[update]
+fn reset_rpc_providers() -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can reset RPC providers".to_string());
+    }
+
+    mutate_state(|s| s.set_configured_rpc_providers(None));
+    Ok(())
+}
+
+#[update]
+fn release_deposit(key: String) -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can release deposits".to_string());
+    }
+
+    if DEPOSIT_REGISTRY.with(|d| d.borrow_mut().release(&key)) {
+        Ok(())
+    } else {
+        Err(format!("Deposit {} is not claimed", key))
+    }
+}
+
+#[query]
+fn get_cycles_budget() -> CyclesBudget {
+    cycles::get_budget()
+}
+
+#[update]
+fn set_cycles_budget(budget: CyclesBudget) -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can set the cycles budget".to_string());
+    }
+
+    cycles::set_budget(budget)
+}
+
+#[query]
+fn get_cycles_usage() -> Vec<ActionCycles> {
+    cycles::get_usage()
 }
 
 #[init]
@@ -179,6 +326,37 @@ pub fn init(maybe_init: Option<InitArg>) {
     if let Some(init_arg) = maybe_init {
         init_state(init_arg)
     }
+    start_gas_checks();
+    start_nonce_reconciliation();
+}
+
+const NONCE_RECONCILE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(120);
+
+fn start_nonce_reconciliation() {
+    ic_cdk_timers::set_timer_interval(NONCE_RECONCILE_INTERVAL, || {
+        ic_cdk::spawn(async {
+            if let Err(e) = reconcile_nonces().await {
+                ic_cdk::println!("Nonce reconciliation failed: {}", e);
+            }
+        })
+    });
+}
+
+const GAS_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(600);
+
+fn start_gas_checks() {
+    ic_cdk_timers::set_timer_interval(GAS_CHECK_INTERVAL, || ic_cdk::spawn(check_gas_balance()));
+}
+
+/// Purchases are paid out from the canister's Ethereum wallet.
+async fn check_gas_balance() {
+    let address = ethereum_address().await;
+    match (get_balance(&address).await, get_dynamic_fees().await) {
+        (Ok(balance), Ok((max_fee_per_gas, _))) => {
+            metrics::record_gas("Ethereum", balance, max_fee_per_gas * EVM_TRANSFER_GAS)
+        }
+        (Err(e), _) | (_, Err(e)) => ic_cdk::println!("Failed to read Ethereum gas balance: {}", e),
+    }
 }
 
 #[query]
@@ -252,21 +430,22 @@ async fn icp_swap(args: ICPSwapArgs) -> ICPSwapResponse {
 
     let tick_in_ledger_canister_id = match args.tick_in_name.as_str() {
         "BIT10.BTC" => BIT10_BTC_LEDGER_CANISTER_ID,
-        _ => return ICPSwapResponse::Err(format!("Unsupported token: {:?}", args.tick_in_name)),
+        _ => return ICPSwapResponse::Err(ApiError::invalid_argument("tick_in_name", format!("unsupported token {}", args.tick_in_name))),
     };
 
     let data_id = match args.tick_in_name.as_str() {
         "BIT10.BTC" => "1234567890ABCDEF",
-        _ => return ICPSwapResponse::Err(format!("Unsupported token: {}", args.tick_in_name)),
+        _ => return ICPSwapResponse::Err(ApiError::invalid_argument("tick_in_name", format!("unsupported token {}", args.tick_in_name))),
     };
 
     let price_result: Result<(Option<PriceFeed>,), _> =
         ic_cdk::call(price_feed_canister, "get_value", (data_id.to_string(),)).await;
+    metrics::record_price_feed(data_id, matches!(price_result, Ok((Some(_),))));
 
     let price_feed = match price_result {
         Ok((Some(feed),)) => feed,
-        Err(e) => return ICPSwapResponse::Err(format!("Failed to get price feed: {:?}", e)),
-        Ok((None,)) => return ICPSwapResponse::Err("Price feed not found".to_string()),
+        Err(e) => return ICPSwapResponse::Err(ApiError::price_feed_unavailable(data_id, format!("{:?}", e))),
+        Ok((None,)) => return ICPSwapResponse::Err(ApiError::price_feed_unavailable(data_id, "no value")),
     };
 
     let tick_in_value = match ciborium::from_reader::<f64, _>(&price_feed.value[..]) {
@@ -274,7 +453,7 @@ async fn icp_swap(args: ICPSwapArgs) -> ICPSwapResponse {
         Err(_) => {
             match ciborium::from_reader::<u64, _>(&price_feed.value[..]) {
                 Ok(int_value) => int_value as f64,
-                Err(e) => return ICPSwapResponse::Err(format!("Failed to decode CBOR price: {}", e)),
+                Err(e) => return ICPSwapResponse::Err(ApiError::price_feed_unavailable(data_id, format!("Failed to decode CBOR price: {}", e))),
             }
         }
     };
@@ -282,7 +461,7 @@ async fn icp_swap(args: ICPSwapArgs) -> ICPSwapResponse {
     let (tick_out_ledger_canister_id, tick_out_api_url) = match args.tick_out_name.as_str() {
         "Test BIT10.TOP" => (TEST_BIT10_TOP_LEDGER_CANISTER_ID, "https://dufhdfjgnkfmbklcf.bit10.app/bit10-top-current-price"),
         "Test BIT10.MEME" => (TEST_BIT10_MEME_LEDGER_CANISTER_ID, "https://dufhdfjgnkfmbklcf.bit10.app/test-bit10-meme-current-price"),
-        _ => return ICPSwapResponse::Err(format!("Unsupported token: {:?}", args.tick_out_name)),
+        _ => return ICPSwapResponse::Err(ApiError::invalid_argument("tick_out_name", format!("unsupported token {}", args.tick_out_name))),
     };
 
     let tick_out_request = CanisterHttpRequestArgument {
@@ -290,29 +469,30 @@ async fn icp_swap(args: ICPSwapArgs) -> ICPSwapResponse {
         method: HttpMethod::GET,
         body: None,
         max_response_bytes: None,
-        transform: None,
+        transform: Some(TransformContext::from_name(
+            "transform".to_string(),
+            ResponseShape::json(&["tokenPrice"]).to_context(),
+        )),
         headers: vec![],
     };
 
     let tick_out_body = match make_http_request(tick_out_request.clone()).await {
         Ok(body) => match String::from_utf8(body) {
             Ok(b) => b,
-            Err(e) => return ICPSwapResponse::Err(format!("Failed to parse response body: {}", e)),
+            Err(e) => return ICPSwapResponse::Err(format!("Failed to parse response body: {}", e).into()),
         },
-        Err(e) => return ICPSwapResponse::Err(format!("2nd API tick_out_request failed: {}", e)),
+        Err(e) => return ICPSwapResponse::Err(format!("2nd API tick_out_request failed: {}", e).into()),
     };
 
     let tick_out_json: Value = match serde_json::from_str(&tick_out_body) {
         Ok(j) => j,
-        Err(e) => return ICPSwapResponse::Err(format!("Failed to parse JSON: {}", e)),
+        Err(e) => return ICPSwapResponse::Err(format!("Failed to parse JSON: {}", e).into()),
     };
 
     let tick_out = match tick_out_json["tokenPrice"].as_f64() {
         Some(a) => a,
         None => {
-            return ICPSwapResponse::Err(
-                "Failed to extract tick_out amount from JSON".to_string(),
-            )
+            return ICPSwapResponse::Err("Failed to extract tick_out amount from JSON".into())
         }
     };
 
@@ -325,9 +505,7 @@ async fn icp_swap(args: ICPSwapArgs) -> ICPSwapResponse {
     let tick_out_amount_value = match args.tick_out_amount.0.to_f64() {
         Some(v) => v,
         None => {
-            return ICPSwapResponse::Err(
-                "Failed to convert tick_out_amount to f64".to_string(),
-            )
+            return ICPSwapResponse::Err("Failed to convert tick_out_amount to f64".into())
         }
     };
 
@@ -354,7 +532,7 @@ async fn icp_swap(args: ICPSwapArgs) -> ICPSwapResponse {
 
     let balance_info = match balance_and_fee_result {
         Ok(balance_info) => balance_info,
-        Err(e) => return ICPSwapResponse::Err(format!("Failed to check balance: {}", e)),
+        Err(e) => return ICPSwapResponse::Err(format!("Failed to check balance: {}", e).into()),
     };
 
     let caller_balance = balance_info.balance;
@@ -370,7 +548,7 @@ async fn icp_swap(args: ICPSwapArgs) -> ICPSwapResponse {
         return ICPSwapResponse::Err(format!(
             "Insufficient balance: Current balance {:?} is less than required amount {:?} + fee {:?}",
             caller_balance.clone(), selected_amount_nat, fee
-        ));
+        ).into());
     }
 
     ic_cdk::println!(
@@ -408,21 +586,18 @@ async fn icp_swap(args: ICPSwapArgs) -> ICPSwapResponse {
         Ok((Ok(block_index),)) => block_index,
         Ok((Err(e),)) => {
             ic_cdk::println!("tick_in_transfer_result failed: {:?}", e);
-            return ICPSwapResponse::Err(format!(
-                "Transfer failed for tick_in_transfer_result: {:?} and selected_amount_nat is {:?} and fee {:?}",
-                e, selected_amount_nat, fee
-            ));
+            return ICPSwapResponse::Err(ApiError::transfer_failed(format!("{:?}", e)));
         }
-        Err(e) => {
-            ic_cdk::println!("tick_in_transfer_result failed: {:?}", e);
-            return ICPSwapResponse::Err(format!("Call failed: {:?}", e));
+        Err((code, msg)) => {
+            ic_cdk::println!("tick_in_transfer_result failed: {:?} {}", code, msg);
+            return ICPSwapResponse::Err(ApiError::call_rejected(tick_in_ledger_canister_id, code as u32, msg));
         }
     };
 
     let tick_out_transfer_result = if tick_in_transfer_result.0 > 0u64.into() {
         let tick_out_amount_fixed = match (args.tick_out_amount.0.clone() * BigUint::from(100_000_000u64)).to_u128() {
             Some(value) => value,
-            None => return ICPSwapResponse::Err("Failed to convert tick_out_amount to u128".to_string()),
+            None => return ICPSwapResponse::Err("Failed to convert tick_out_amount to u128".into()),
         };
 
         ic_cdk::println!(
@@ -452,19 +627,16 @@ async fn icp_swap(args: ICPSwapArgs) -> ICPSwapResponse {
             Ok((Ok(block_index),)) => block_index,
             Ok((Err(e),)) => {
                 ic_cdk::println!("tick_out_transfer_result failed: {:?}", e);
-                return ICPSwapResponse::Err(format!(
-                    "Transfer failed for tick_in_transfer failed: {:?}",
-                    e
-                ));
+                return ICPSwapResponse::Err(ApiError::transfer_failed(format!("{:?}", e)));
             }
-            Err(e) => {
-                ic_cdk::println!("tick_out_transfer_result failed: {:?}", e);
-                return ICPSwapResponse::Err(format!("Call failed: {:?}", e));
+            Err((code, msg)) => {
+                ic_cdk::println!("tick_out_transfer_result failed: {:?} {}", code, msg);
+                return ICPSwapResponse::Err(ApiError::call_rejected(tick_out_ledger_canister_id, code as u32, msg));
             }
         }
     } else {
         ic_cdk::println!("tick_in_transfer failed: block index is 0");
-        return ICPSwapResponse::Err("tick_in_transfer failed: block index is 0".to_string());
+        return ICPSwapResponse::Err("tick_in_transfer failed: block index is 0".into());
     };
 
     let response = ICPSwapResponse::Ok(ICPSwapResponseData {
@@ -495,7 +667,7 @@ async fn icp_swap(args: ICPSwapArgs) -> ICPSwapResponse {
             transaction_timestamp: icp_data.transaction_timestamp.clone(),
             network: icp_data.network.clone(),
         };
-        SWAP_HISTORY.with(|h| h.borrow_mut().push(SwapResponse::Ok(swap_response_data)));
+        record_swap(swap_response_data);
     }
 
     response
@@ -638,8 +810,12 @@ pub async fn eth_create_transaction(args: SwapArgs) -> TransactionResponse {
 
 #[update]
 pub async fn eth_swap(trx_hash: String) -> SwapResponse {
+    if let Err(err) = cycles::charge("swap", cycles::swap_cost()) {
+        return SwapResponse::Err(err);
+    }
+
     if !trx_hash.starts_with("0x") || trx_hash.len() != 66 {
-        return SwapResponse::Err("Invalid transaction hash format".to_string());
+        return SwapResponse::Err("Invalid transaction hash format".into());
     }
 
     let transaction_hash = trx_hash.to_lowercase();
@@ -656,14 +832,30 @@ pub async fn eth_swap(trx_hash: String) -> SwapResponse {
     });
 
     if already_exists {
-        return SwapResponse::Err("Transaction already processed".to_string());
+        return SwapResponse::Err(ApiError::already_processed(&transaction_hash));
     }
 
+    let deposit_key = deposit_key("ethereum", &transaction_hash, None);
+    let claim = match claim_deposit(&deposit_key) {
+        Ok(claim) => claim,
+        Err(e) => return SwapResponse::Err(e.into()),
+    };
+
+    let response = settle_eth_swap(transaction_hash, target_address, &claim).await;
+    finish_deposit(&claim, matches!(response, SwapResponse::Ok(_)));
+    response
+}
+
+async fn settle_eth_swap(
+    transaction_hash: String,
+    target_address: String,
+    claim: &DepositClaim,
+) -> SwapResponse {
     let tx_result = get_transaction_by_hash_ethereum(&transaction_hash).await;
     let tx_data = match tx_result {
         Ok(tx) => tx,
         Err(e) => {
-            return SwapResponse::Err(format!("Failed to get transaction data: {}", e));
+            return SwapResponse::Err(ApiError::chain_unavailable("Ethereum", e));
         }
     };
 
@@ -699,18 +891,16 @@ pub async fn eth_swap(trx_hash: String) -> SwapResponse {
     };
 
     if !is_valid_transaction {
-        return SwapResponse::Err(format!(
+        return SwapResponse::Err(ApiError::deposit_invalid(&transaction_hash, format!(
             "Transaction was not sent to the target address {}",
             target_address
-        ));
+        )));
     }
 
     let swap_args = match decode_eth_transaction_data(&tx_data, &from_address).await {
         Some(args) => args,
         None => {
-            return SwapResponse::Err(
-                "Failed to decode swap arguments from transaction data".to_string(),
-            );
+            return SwapResponse::Err("Failed to decode swap arguments from transaction data".into());
         }
     };
 
@@ -729,7 +919,7 @@ pub async fn eth_swap(trx_hash: String) -> SwapResponse {
             return SwapResponse::Err(format!(
                 "Token out address '{}' not found in supported BIT10 tokens",
                 swap_args.token_out_address
-            ));
+            ).into());
         }
     };
 
@@ -749,7 +939,7 @@ pub async fn eth_swap(trx_hash: String) -> SwapResponse {
             return SwapResponse::Err(format!(
                 "Token in address '{}' not found in supported Ethereum tokens",
                 swap_args.token_in_address
-            ));
+            ).into());
         }
     };
 
@@ -757,21 +947,21 @@ pub async fn eth_swap(trx_hash: String) -> SwapResponse {
         match extract_actual_amount_from_transaction(&tx_data, token_in, &target_address) {
             Ok(amount) => amount,
             Err(e) => {
-                return SwapResponse::Err(format!("Failed to extract transaction amount: {}", e));
+                return SwapResponse::Err(format!("Failed to extract transaction amount: {}", e).into());
             }
         };
 
     let token_in_amount = match u256_to_decimal_string(actual_amount_received, token_in.token_decimals) {
         Ok(amount_str) => amount_str,
         Err(e) => {
-            return SwapResponse::Err(format!("Failed to convert amount to decimal: {}", e));
+            return SwapResponse::Err(format!("Failed to convert amount to decimal: {}", e).into());
         }
     };
 
     let token_in_amount_decimal = match Decimal::from_str(&token_in_amount) {
         Ok(amount) => amount,
         Err(_) => {
-            return SwapResponse::Err("Failed to parse token_in_amount as decimal".to_string());
+            return SwapResponse::Err("Failed to parse token_in_amount as decimal".into());
         }
     };
     let token_in_amount_without_fee = token_in_amount_decimal / dec!(1.01);
@@ -779,14 +969,14 @@ pub async fn eth_swap(trx_hash: String) -> SwapResponse {
     let token_out_usd_price = match get_bit10_token_price(token_out).await {
         Ok(price) => price,
         Err(e) => {
-            return SwapResponse::Err(format!("Failed to get token_out USD price: {}", e));
+            return SwapResponse::Err(ApiError::price_feed_unavailable(&token_out.token_symbol, e));
         }
     };
 
     let token_in_usd_price = match get_token_price_from_feed(token_in).await {
         Ok(price) => price,
         Err(e) => {
-            return SwapResponse::Err(format!("Failed to get token_in USD price: {}", e));
+            return SwapResponse::Err(ApiError::price_feed_unavailable(&token_in.token_symbol, e));
         }
     };
 
@@ -796,7 +986,7 @@ pub async fn eth_swap(trx_hash: String) -> SwapResponse {
     let token_out_amount_decimal = match Decimal::from_str(&swap_args.token_out_amount) {
         Ok(amount) => amount,
         Err(_) => {
-            return SwapResponse::Err("Failed to parse token_out_amount".to_string());
+            return SwapResponse::Err("Failed to parse token_out_amount".into());
         }
     };
 
@@ -819,19 +1009,23 @@ pub async fn eth_swap(trx_hash: String) -> SwapResponse {
         difference_ratio.to_string()
     );
 
+    if let Err(e) = start_deposit_settlement(claim) {
+        return SwapResponse::Err(e.into());
+    }
+
     let (transaction_type, token_out_tx_hash) = if should_proceed {
         match send_bit10_token_to_user(token_out, &from_address, &swap_args.token_out_amount).await
         {
             Ok(tx_hash) => ("Swap".to_string(), tx_hash),
             Err(e) => {
-                return SwapResponse::Err(format!("Failed to send BIT10 token: {}", e));
+                return SwapResponse::Err(ApiError::transfer_failed(format!("Failed to send BIT10 token: {}", e)));
             }
         }
     } else {
         match revert_transaction(token_in, &from_address, actual_amount_received).await {
             Ok(tx_hash) => ("Revert".to_string(), tx_hash),
             Err(e) => {
-                return SwapResponse::Err(format!("Failed to revert transaction: {}", e));
+                return SwapResponse::Err(ApiError::transfer_failed(format!("Failed to revert transaction: {}", e)));
             }
         }
     };
@@ -841,7 +1035,7 @@ pub async fn eth_swap(trx_hash: String) -> SwapResponse {
         token_in_address: swap_args.token_in_address.to_lowercase(),
         token_in_amount: token_in_amount,
         token_in_usd_amount: token_in_usd_amount_value.to_string(),
-        token_in_tx_hash: trx_hash.to_lowercase(),
+        token_in_tx_hash: transaction_hash,
         token_out_address: swap_args.token_out_address.to_lowercase(),
         token_out_amount: swap_args.token_out_amount,
         token_out_tx_hash: token_out_tx_hash.to_lowercase(),
@@ -850,18 +1044,18 @@ pub async fn eth_swap(trx_hash: String) -> SwapResponse {
         network: "Ethereum".to_string(),
     };
 
-    SWAP_HISTORY.with(|h| h.borrow_mut().push(SwapResponse::Ok(response_data.clone())));
+    record_swap(response_data.clone());
 
     SwapResponse::Ok(response_data)
 }
 
-async fn get_transaction_count_ethereum(address: &str) -> Result<u64, String> {
+async fn get_transaction_count_ethereum(address: &str, block_tag: &str) -> Result<u64, String> {
     let json_payload = format!(
-        r#"{{"jsonrpc": "2.0", "method": "eth_getTransactionCount", "params": ["{}", "pending"], "id": 1}}"#,
-        address
+        r#"{{"jsonrpc": "2.0", "method": "eth_getTransactionCount", "params": ["{}", "{}"], "id": 1}}"#,
+        address, block_tag
     );
 
-    match call_rpc_with_retry(json_payload, TATUM_API_KEY).await {
+    match call_rpc_with_retry(json_payload).await {
         Ok(body_str) => {
             let response: serde_json::Value = serde_json::from_str(&body_str)
                 .map_err(|e| format!("Failed to parse nonce response: {}", e))?;
@@ -879,6 +1073,94 @@ async fn get_transaction_count_ethereum(address: &str) -> Result<u64, String> {
     }
 }
 
+/// Hands out the next nonce for the canister wallet. The first call seeds the
+/// counter from the `pending` transaction count; after that reservations stay
+/// in canister state, so concurrent payouts never share a nonce.
+async fn reserve_nonce(nonce_key: &str, address: &str) -> Result<u64, String> {
+    if !nonces::is_tracked(nonce_key) {
+        let chain_nonce = get_transaction_count_ethereum(address, "pending").await?;
+        nonces::track(nonce_key, chain_nonce);
+    }
+    nonces::reserve(nonce_key)
+}
+
+async fn submit_ethereum_transaction(
+    transaction: alloy_consensus::TxEip1559,
+    wallet: &EthereumWallet,
+    nonce_key: &str,
+) -> Result<String, String> {
+    let nonce = transaction.nonce;
+    let max_fee_per_gas = transaction.max_fee_per_gas;
+    let max_priority_fee_per_gas = transaction.max_priority_fee_per_gas;
+
+    match sign_and_send_ethereum_transaction(transaction, wallet).await {
+        Ok(tx_hash) => {
+            nonces::mark_submitted(nonce_key, nonce, tx_hash.clone(), max_fee_per_gas, max_priority_fee_per_gas);
+            Ok(tx_hash)
+        }
+        Err(e) => {
+            nonces::mark_failed(nonce_key, nonce);
+            if e.contains("nonce too low") {
+                let address = wallet.ethereum_address().to_string();
+                if let Ok(chain_nonce) = get_transaction_count_ethereum(&address, "pending").await {
+                    nonces::track(nonce_key, chain_nonce);
+                }
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Drops mined nonces and fills abandoned ones with zero-value self
+/// transfers, so one failed payout cannot hold back every later one.
+async fn reconcile_nonces() -> Result<(), String> {
+    let wallet = EthereumWallet::new_canister_wallet().await;
+    let canister_address = wallet.ethereum_address().to_string();
+    let chain_id = read_state(|s| s.ethereum_network().chain_id());
+    let nonce_key = nonces::nonce_key(chain_id, &canister_address);
+
+    if !nonces::is_tracked(&nonce_key) {
+        return Ok(());
+    }
+
+    let confirmed_nonce = get_transaction_count_ethereum(&canister_address, "latest").await?;
+    let report = nonces::reconcile(&nonce_key, confirmed_nonce);
+
+    for stuck in &report.stuck {
+        ic_cdk::println!(
+            "Ethereum transaction {:?} with nonce {} is still pending",
+            stuck.tx_hash,
+            stuck.nonce
+        );
+    }
+
+    for nonce in report.gaps {
+        let (max_fee_per_gas, max_priority_fee_per_gas) = get_dynamic_fees().await?;
+        nonces::mark_cancel(&nonce_key, nonce);
+
+        let transaction = alloy_consensus::TxEip1559 {
+            chain_id,
+            nonce,
+            gas_limit: 21_000,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            to: alloy_primitives::TxKind::Call(
+                Address::from_str(&canister_address).map_err(|e| format!("Invalid canister address: {}", e))?,
+            ),
+            value: U256::ZERO,
+            access_list: Default::default(),
+            input: Default::default(),
+        };
+
+        match submit_ethereum_transaction(transaction, &wallet, &nonce_key).await {
+            Ok(tx_hash) => ic_cdk::println!("Filled Ethereum nonce gap {} with {}", nonce, tx_hash),
+            Err(e) => ic_cdk::println!("Failed to fill Ethereum nonce gap {}: {}", nonce, e),
+        }
+    }
+
+    Ok(())
+}
+
 async fn sign_and_send_ethereum_transaction(
     transaction: alloy_consensus::TxEip1559,
     wallet: &EthereumWallet,
@@ -905,7 +1187,7 @@ async fn sign_and_send_ethereum_transaction(
         raw_transaction_hex
     );
 
-    match call_rpc_with_retry(json_payload, TATUM_API_KEY).await {
+    match call_rpc_with_retry(json_payload).await {
         Ok(response_body) => match serde_json::from_str::<serde_json::Value>(&response_body) {
             Ok(response) => {
                 if let Some(error) = response.get("error") {
@@ -940,7 +1222,7 @@ async fn get_transaction_by_hash_ethereum(tx_hash: &str) -> Result<serde_json::V
         tx_hash
     );
 
-    match call_rpc_with_retry(json_payload, TATUM_API_KEY).await {
+    match call_rpc_with_quorum(json_payload).await {
         Ok(result_str) => {
             let response: serde_json::Value = serde_json::from_str(&result_str)
                 .map_err(|e| format!("Failed to parse transaction response: {}", e))?;
@@ -968,14 +1250,15 @@ async fn send_erc20_token_from_canister(
     let wallet = EthereumWallet::new_canister_wallet().await;
     let canister_address = wallet.ethereum_address().to_string();
 
-    let nonce = match get_transaction_count_ethereum(&canister_address).await {
-        Ok(n) => n,
-        Err(e) => return Err(format!("Failed to fetch nonce: {}", e)),
-    };
-
     let (max_fee_per_gas, max_priority_fee_per_gas) = get_dynamic_fees().await
         .map_err(|e| format!("Failed to get gas fees: {}", e))?;
 
+    let chain_id = read_state(|s| s.ethereum_network().chain_id());
+    let nonce_key = nonces::nonce_key(chain_id, &canister_address);
+    let nonce = reserve_nonce(&nonce_key, &canister_address)
+        .await
+        .map_err(|e| format!("Failed to reserve a nonce: {}", e))?;
+
     let method_id = &hex::decode("a9059cbb").unwrap();
     let mut encoded = method_id.clone();
 
@@ -987,8 +1270,6 @@ async fn send_erc20_token_from_canister(
     amount_bytes.copy_from_slice(&amount.to_be_bytes::<32>());
     encoded.extend_from_slice(&amount_bytes);
 
-    let chain_id = read_state(|s| s.ethereum_network().chain_id());
-
     let transaction = alloy_consensus::TxEip1559 {
         chain_id,
         nonce,
@@ -1001,22 +1282,21 @@ async fn send_erc20_token_from_canister(
         input: encoded.into(),
     };
 
-    sign_and_send_ethereum_transaction(transaction, &wallet).await
+    submit_ethereum_transaction(transaction, &wallet, &nonce_key).await
 }
 
 async fn send_native_eth_from_canister(to: Address, amount: U256) -> Result<String, String> {
     let wallet = EthereumWallet::new_canister_wallet().await;
     let canister_address = wallet.ethereum_address().to_string();
 
-    let nonce = match get_transaction_count_ethereum(&canister_address).await {
-        Ok(n) => n,
-        Err(e) => return Err(format!("Failed to fetch nonce: {}", e)),
-    };
-
     let (max_fee_per_gas, max_priority_fee_per_gas) = get_dynamic_fees().await
         .map_err(|e| format!("Failed to get gas fees: {}", e))?;
 
     let chain_id = read_state(|s| s.ethereum_network().chain_id());
+    let nonce_key = nonces::nonce_key(chain_id, &canister_address);
+    let nonce = reserve_nonce(&nonce_key, &canister_address)
+        .await
+        .map_err(|e| format!("Failed to reserve a nonce: {}", e))?;
 
     let transaction = alloy_consensus::TxEip1559 {
         chain_id,
@@ -1030,7 +1310,7 @@ async fn send_native_eth_from_canister(to: Address, amount: U256) -> Result<Stri
         input: Default::default(),
     };
 
-    sign_and_send_ethereum_transaction(transaction, &wallet).await
+    submit_ethereum_transaction(transaction, &wallet, &nonce_key).await
 }
 
 async fn send_bit10_token_to_user(
@@ -1074,7 +1354,7 @@ fn transform(raw: TransformArgs) -> HttpResponse {
         ..Default::default()
     };
     if res.status == Nat::from(200u64) {
-        res.body = raw.response.body;
+        res.body = canonical_body(&raw.context, &raw.response.body);
     } else {
         ic_cdk::api::print(format!(
             "Received an error from the remote service:\n{:#?}",
@@ -1084,6 +1364,11 @@ fn transform(raw: TransformArgs) -> HttpResponse {
     res
 }
 
+#[query]
+fn http_request(request: bit10_core::metrics::HttpRequest) -> bit10_core::metrics::HttpResponse {
+    metrics::http_request(request)
+}
+
 #[query]
 fn get_swap_history() -> Vec<SwapResponse> {
     SWAP_HISTORY.with(|h| h.borrow().clone())
@@ -1102,4 +1387,24 @@ fn get_swap_history_by_address(address: String) -> Vec<SwapResponse> {
             .cloned()
             .collect()
     })
-}
\ No newline at end of file
+}
+
+#[query]
+fn icrc3_get_blocks(args: Vec<GetBlocksRequest>) -> GetBlocksResult {
+    events::get_blocks(args)
+}
+
+#[query]
+fn icrc3_get_tip_certificate() -> Option<DataCertificate> {
+    events::tip_certificate()
+}
+
+#[query]
+fn icrc3_get_archives(_args: GetArchivesArgs) -> Vec<ArchiveInfo> {
+    Vec::new()
+}
+
+#[query]
+fn icrc3_supported_block_types() -> Vec<SupportedBlockType> {
+    events::supported_block_types()
+}
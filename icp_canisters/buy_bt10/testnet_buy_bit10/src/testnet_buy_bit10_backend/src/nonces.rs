@@ -0,0 +1,62 @@
+use bit10_core::nonce::{wallet_key, NonceManager, ReconcileReport};
+use std::cell::RefCell;
+
+// Submissions older than this are reported as stuck, and reservations that
+// never reached the network are cancelled.
+const NONCE_STALE_AFTER_NS: u64 = 10 * 60 * 1_000_000_000;
+
+thread_local! {
+    static NONCE_MANAGER: RefCell<NonceManager> = RefCell::new(NonceManager::default());
+}
+
+pub fn export_nonces() -> NonceManager {
+    NONCE_MANAGER.with(|n| n.borrow().clone())
+}
+
+pub fn import_nonces(manager: NonceManager) {
+    NONCE_MANAGER.with(|n| *n.borrow_mut() = manager);
+}
+
+pub fn nonce_key(chain_id: u64, address: &str) -> String {
+    wallet_key(chain_id, address)
+}
+
+pub fn is_tracked(key: &str) -> bool {
+    NONCE_MANAGER.with(|n| n.borrow().is_tracked(key))
+}
+
+pub fn track(key: &str, chain_nonce: u64) {
+    NONCE_MANAGER.with(|n| n.borrow_mut().track(key, chain_nonce));
+}
+
+pub fn reserve(key: &str) -> Result<u64, String> {
+    NONCE_MANAGER.with(|n| n.borrow_mut().reserve(key, ic_cdk::api::time()))
+}
+
+pub fn mark_submitted(key: &str, nonce: u64, tx_hash: String, max_fee_per_gas: u128, max_priority_fee_per_gas: u128) {
+    NONCE_MANAGER.with(|n| {
+        n.borrow_mut().mark_submitted(
+            key,
+            nonce,
+            tx_hash,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            ic_cdk::api::time(),
+        )
+    });
+}
+
+pub fn mark_failed(key: &str, nonce: u64) {
+    NONCE_MANAGER.with(|n| n.borrow_mut().mark_failed(key, nonce));
+}
+
+pub fn mark_cancel(key: &str, nonce: u64) {
+    NONCE_MANAGER.with(|n| n.borrow_mut().mark_cancel(key, nonce, ic_cdk::api::time()));
+}
+
+pub fn reconcile(key: &str, confirmed_nonce: u64) -> ReconcileReport {
+    NONCE_MANAGER.with(|n| {
+        n.borrow_mut()
+            .reconcile(key, confirmed_nonce, ic_cdk::api::time(), NONCE_STALE_AFTER_NS)
+    })
+}
@@ -1,5 +1,7 @@
 use crate::ecdsa::keys::EcdsaPublicKey;
 use crate::SwapResponse;
+use bit10_core::deposits::{DepositClaim, DepositRegistry};
+use bit10_core::providers::{ProviderSet, RpcProvider};
 use ic_cdk::api::management_canister::ecdsa::{EcdsaCurve, EcdsaKeyId};
 use std::cell::RefCell;
 use std::ops::{Deref, DerefMut};
@@ -13,6 +15,19 @@ pub const PRICE_FEED_CANISTER: Principal =
 thread_local! {
     pub static STATE: RefCell<State> = RefCell::default();
     pub static SWAP_HISTORY: RefCell<Vec<SwapResponse>> = RefCell::new(Vec::new());
+    pub static DEPOSIT_REGISTRY: RefCell<DepositRegistry> = RefCell::new(DepositRegistry::default());
+}
+
+pub fn claim_deposit(key: &str) -> Result<DepositClaim, String> {
+    DEPOSIT_REGISTRY.with(|d| d.borrow_mut().claim(key, ic_cdk::api::time()))
+}
+
+pub fn start_deposit_settlement(claim: &DepositClaim) -> Result<(), String> {
+    DEPOSIT_REGISTRY.with(|d| d.borrow_mut().start_settlement(claim, ic_cdk::api::time()))
+}
+
+pub fn finish_deposit(claim: &DepositClaim, succeeded: bool) {
+    DEPOSIT_REGISTRY.with(|d| d.borrow_mut().finish(claim, succeeded, ic_cdk::api::time()));
 }
 
 pub fn init_state(init_arg: InitArg) {
@@ -30,19 +45,13 @@ where
     STATE.with(|s| f(s.borrow_mut().deref_mut()))
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub struct CustomRpcConfig {
-    pub url: String,
-    pub api_key: Option<String>,
-}
-
 #[derive(Debug, PartialEq, Eq)]
 pub struct State {
     bsc_network: BscNetwork,
     ethereum_network: EthereumNetwork,
     ecdsa_key_name: EcdsaKeyName,
     ecdsa_public_key: Option<EcdsaPublicKey>,
-    custom_rpc: Option<CustomRpcConfig>,
+    rpc_providers: Option<ProviderSet>,
 }
 
 impl Default for State {
@@ -52,10 +61,7 @@ impl Default for State {
             ethereum_network: EthereumNetwork::default(),
             ecdsa_key_name: EcdsaKeyName::default(),
             ecdsa_public_key: None,
-            custom_rpc: Some(CustomRpcConfig {
-                url: "https://ethereum-sepolia.gateway.tatum.io/".to_string(),
-                api_key: Some("t-zdbfhdbfhbgnfjngjkmb".to_string()),
-            }),
+            rpc_providers: None,
         }
     }
 }
@@ -73,16 +79,28 @@ impl State {
         self.ethereum_network
     }
 
-    pub fn custom_rpc(&self) -> Option<&CustomRpcConfig> {
-        self.custom_rpc.as_ref()
+    /// Ethereum endpoints. Deposit lookups need `quorum` of them to agree;
+    /// everything else goes to the first callable one that answers.
+    pub fn rpc_providers(&self) -> ProviderSet {
+        self.rpc_providers
+            .clone()
+            .unwrap_or_else(|| default_rpc_providers(self.ethereum_network))
+    }
+
+    pub fn rpc_providers_mut(&mut self) -> &mut ProviderSet {
+        let ethereum_network = self.ethereum_network;
+        self.rpc_providers
+            .get_or_insert_with(|| default_rpc_providers(ethereum_network))
     }
 
-    pub fn set_custom_rpc(&mut self, config: CustomRpcConfig) {
-        self.custom_rpc = Some(config);
+    /// `None` until a controller changes the defaults or a quorum read
+    /// starts tracking the default providers' health.
+    pub fn configured_rpc_providers(&self) -> Option<ProviderSet> {
+        self.rpc_providers.clone()
     }
 
-    pub fn clear_custom_rpc(&mut self) {
-        self.custom_rpc = None;
+    pub fn set_configured_rpc_providers(&mut self, rpc_providers: Option<ProviderSet>) {
+        self.rpc_providers = rpc_providers;
     }
 
     pub fn bsc_rpc_url(&self) -> &'static str {
@@ -97,12 +115,23 @@ impl State {
         }
     }
 
-    pub fn ethereum_rpc_url(&self) -> &'static str {
-        match self.ethereum_network {
-            EthereumNetwork::Mainnet => "https://eth.llamarpc.com",
-            EthereumNetwork::Sepolia => "https://ethereum-sepolia.gateway.tatum.io/",
-        }
-    }
+}
+
+fn default_rpc_providers(ethereum_network: EthereumNetwork) -> ProviderSet {
+    let providers = match ethereum_network {
+        EthereumNetwork::Mainnet => vec![
+            RpcProvider::new("llamarpc", "https://eth.llamarpc.com"),
+            RpcProvider::new("publicnode", "https://ethereum-rpc.publicnode.com"),
+            RpcProvider::new("drpc", "https://eth.drpc.org"),
+        ],
+        EthereumNetwork::Sepolia => vec![
+            RpcProvider::new("publicnode", "https://ethereum-sepolia-rpc.publicnode.com"),
+            RpcProvider::new("drpc", "https://sepolia.drpc.org"),
+            RpcProvider::new("tatum", "https://ethereum-sepolia.gateway.tatum.io/")
+                .with_header("X-API-Key", "{api_key}"),
+        ],
+    };
+    ProviderSet::new(providers, 2).expect("Invalid default Ethereum providers")
 }
 
 impl From<InitArg> for State {
@@ -1,8 +1,11 @@
 use crate::lib::{
-    PriceFeed, SwapArgs, Token, TATUM_API_KEY,
+    PriceFeed, SwapArgs, Token,
 };
-use crate::state::{read_state, EthereumNetwork, PRICE_FEED_CANISTER};
+use crate::metrics;
+use crate::state::{mutate_state, read_state, PRICE_FEED_CANISTER};
 use alloy_primitives::{hex, Address, U256};
+use bit10_core::providers::{json_rpc_body, normalize_json_rpc, RpcProvider};
+use bit10_core::transform::ResponseShape;
 use candid::{Nat, Principal};
 use ic_cdk::api::management_canister::http_request::{
     CanisterHttpRequestArgument, HttpHeader, HttpMethod, HttpResponse, TransformArgs,
@@ -14,13 +17,27 @@ use rust_decimal_macros::dec;
 use serde_json::Value;
 use std::str::FromStr;
 
+/// Cycles the IC charges for `request`, from its size and response limit.
+fn outcall_cost(request: &CanisterHttpRequestArgument) -> u128 {
+    let bytes = bit10_core::cycles::request_bytes(
+        &request.url,
+        request.headers.iter().map(|h| (h.name.as_str(), h.value.as_str())),
+        request.body.as_deref(),
+        request
+            .transform
+            .as_ref()
+            .map(|t| (t.function.0.method.as_str(), t.context.as_slice())),
+    );
+    bit10_core::cycles::http_request_cost(bytes, request.max_response_bytes)
+}
+
 pub async fn make_http_request(request: CanisterHttpRequestArgument) -> Result<Vec<u8>, String> {
     const MAX_RETRIES: u8 = 5;
 
     let mut retries = 0;
 
     while retries < MAX_RETRIES {
-        let cycles: u128 = 25_000_000_000;
+        let cycles = outcall_cost(&request);
         match http_request(request.clone(), cycles).await {
             Ok((response,)) => {
                 if response.status.0.to_u64().unwrap_or(0) == 200 {
@@ -44,45 +61,61 @@ pub async fn make_http_request(request: CanisterHttpRequestArgument) -> Result<V
     ))
 }
 
-pub async fn call_rpc_with_retry(json_payload: String, api_key: &str) -> Result<String, String> {
-    let request_headers = vec![
-        HttpHeader {
-            name: "Content-Type".to_string(),
-            value: "application/json".to_string(),
-        },
-        HttpHeader {
-            name: "X-API-Key".to_string(),
-            value: api_key.to_string(),
-        },
-    ];
+/// Sends the request to one provider at a time, moving on only when the
+/// outcall fails. Nonces, fees and broadcasts go through here.
+pub async fn call_rpc_with_retry(json_payload: String) -> Result<String, String> {
+    let providers = read_state(|s| s.rpc_providers()).callable();
+    let mut errors = Vec::with_capacity(providers.len());
+    for provider in &providers {
+        match post_to_provider(provider, &json_payload).await {
+            Ok(body) => return Ok(body),
+            Err(e) => errors.push(format!("{}: {}", provider.name, e)),
+        }
+    }
+    Err(format!("Every RPC provider failed: {}", errors.join("; ")))
+}
 
-    let transform_context = TransformContext::from_name("transform".to_string(), vec![]);
-    let network = read_state(|s| s.ethereum_network());
-    let url = get_rpc_url(network).to_string();
+/// Sends the read to every provider and answers with the result a quorum of
+/// them agree on. Deposits are verified through here.
+pub async fn call_rpc_with_quorum(json_payload: String) -> Result<String, String> {
+    let providers = read_state(|s| s.rpc_providers()).callable();
+    let mut responses = Vec::with_capacity(providers.len());
+    for provider in &providers {
+        let response = post_to_provider(provider, &json_payload)
+            .await
+            .and_then(|body| normalize_json_rpc(&body));
+        responses.push((provider.name.clone(), response));
+    }
+    let result = mutate_state(|s| s.rpc_providers_mut().tally(responses, ic_cdk::api::time()))?;
+    Ok(json_rpc_body(&result))
+}
+
+async fn post_to_provider(provider: &RpcProvider, json_payload: &str) -> Result<String, String> {
+    let mut request_headers = vec![HttpHeader {
+        name: "Content-Type".to_string(),
+        value: "application/json".to_string(),
+    }];
+    request_headers.extend(
+        provider
+            .resolved_headers()
+            .into_iter()
+            .map(|(name, value)| HttpHeader { name, value }),
+    );
+
+    let transform_context =
+        TransformContext::from_name("transform".to_string(), ResponseShape::for_payload(json_payload).to_context());
 
     let request = CanisterHttpRequestArgument {
-        url,
+        url: provider.resolved_url(),
         method: HttpMethod::POST,
-        body: Some(json_payload.into_bytes()),
+        body: Some(json_payload.as_bytes().to_vec()),
         max_response_bytes: Some(8192),
         transform: Some(transform_context),
         headers: request_headers,
     };
 
-    match make_http_request(request).await {
-        Ok(body) => {
-            let body_str = String::from_utf8(body).map_err(|e| format!("Failed to decode response: {}", e))?;
-            Ok(body_str)
-        }
-        Err(e) => Err(e),
-    }
-}
-
-pub fn get_rpc_url(network: EthereumNetwork) -> &'static str {
-    match network {
-        EthereumNetwork::Mainnet => "https://eth.llamarpc.com",
-        EthereumNetwork::Sepolia => "https://ethereum-sepolia.gateway.tatum.io/",
-    }
+    let body = make_http_request(request).await?;
+    String::from_utf8(body).map_err(|e| format!("Failed to decode response: {}", e))
 }
 
 pub fn get_supported_tokens() -> Vec<Token> {
@@ -201,12 +234,21 @@ pub async fn get_bit10_token_price(token: &Token) -> Result<f64, String> {
         .as_ref()
         .ok_or("Token does not have a price_feed_link")?;
 
+    let price = read_price_feed_link(price_feed_link).await;
+    metrics::record_price_feed(&token.token_name, price.is_ok());
+    price
+}
+
+async fn read_price_feed_link(price_feed_link: &str) -> Result<f64, String> {
     let request = CanisterHttpRequestArgument {
-        url: price_feed_link.clone(),
+        url: price_feed_link.to_string(),
         method: HttpMethod::GET,
         body: None,
         max_response_bytes: Some(4096),
-        transform: Some(TransformContext::from_name("transform".to_string(), vec![])),
+        transform: Some(TransformContext::from_name(
+            "transform".to_string(),
+            ResponseShape::json(&["tokenPrice"]).to_context(),
+        )),
         headers: vec![],
     };
 
@@ -231,8 +273,14 @@ pub async fn get_token_price_from_feed(token: &Token) -> Result<f64, String> {
         .as_ref()
         .ok_or("Token does not have a price_feed_id")?;
 
+    let price = read_price_feed(price_feed_id).await;
+    metrics::record_price_feed(price_feed_id, price.is_ok());
+    price
+}
+
+async fn read_price_feed(price_feed_id: &str) -> Result<f64, String> {
     let price_result: Result<(Option<PriceFeed>,), _> =
-        ic_cdk::call(PRICE_FEED_CANISTER, "get_value", (price_feed_id.clone(),)).await;
+        ic_cdk::call(PRICE_FEED_CANISTER, "get_value", (price_feed_id.to_string(),)).await;
 
     let price_feed = match price_result {
         Ok((Some(feed),)) => feed,
@@ -257,7 +305,7 @@ pub async fn get_dynamic_fees() -> Result<(u128, u128), String> {
     let json_payload =
         r#"{"jsonrpc": "2.0", "method": "eth_gasPrice", "params": [], "id": 1}"#.to_string();
 
-    match call_rpc_with_retry(json_payload, TATUM_API_KEY).await {
+    match call_rpc_with_retry(json_payload).await {
         Ok(result_str) => {
             let response: serde_json::Value = serde_json::from_str(&result_str)
                 .map_err(|e| format!("Failed to parse gas price response: {}", e))?;
@@ -281,6 +329,28 @@ pub async fn get_dynamic_fees() -> Result<(u128, u128), String> {
     }
 }
 
+pub async fn get_balance(address: &str) -> Result<u128, String> {
+    let json_payload = format!(
+        r#"{{"jsonrpc": "2.0", "method": "eth_getBalance", "params": ["{}", "latest"], "id": 1}}"#,
+        address
+    );
+
+    match call_rpc_with_retry(json_payload).await {
+        Ok(result_str) => {
+            let response: serde_json::Value = serde_json::from_str(&result_str)
+                .map_err(|e| format!("Failed to parse balance response: {}", e))?;
+
+            if let Some(balance_hex) = response.get("result").and_then(|v| v.as_str()) {
+                u128::from_str_radix(balance_hex.strip_prefix("0x").unwrap_or(""), 16)
+                    .map_err(|e| format!("Failed to parse balance hex: {}", e))
+            } else {
+                Err("eth_getBalance returned no result".to_string())
+            }
+        }
+        Err(e) => Err(format!("RPC call for balance failed: {}", e)),
+    }
+}
+
 pub fn extract_erc20_amount_from_transaction_input(
     input_data: &str,
     expected_recipient: &str,
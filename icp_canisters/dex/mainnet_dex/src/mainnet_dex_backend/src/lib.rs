@@ -4,17 +4,30 @@ mod types;
 mod utils;
 mod wallet;
 
+use bit10_core::certification::Certified;
+use bit10_core::cycles::{ActionCycles, CyclesBudget};
+use bit10_core::events::{ArchiveInfo, DataCertificate, GetArchivesArgs, GetBlocksRequest, GetBlocksResult, SupportedBlockType};
+use bit10_core::metrics::{HttpRequest, HttpResponse};
+use bit10_core::nonce::{ReconcileReport, WalletNonces};
+use bit10_core::providers::{ProviderSetView, RpcProvider};
 use candid::Nat;
 use ic_cdk::{init, post_upgrade, pre_upgrade, query, update};
 use services::base_transaction_service;
+use services::gas_service;
 use services::solana_transaction_service;
 use services::bsc_transaction_service;
 use services::token_service;
-use state::storage;
-use types::network::InitArg;
-use types::pool::PoolsResponse;
+use state::{certified, cycles, events, metrics, nonces, providers, registry, storage};
+use types::network::{InitArg, RpcChain};
+use types::pool::{PoolInfo, PoolsResponse};
+use types::registry::RegistryEvent;
+use types::token::{Pair, Token};
 use types::swap::{SwapArgs, SwapResult, SwapResponse};
 use ic_cdk::caller;
+use std::time::Duration;
+
+const NONCE_RECONCILE_INTERVAL: Duration = Duration::from_secs(120);
+const GAS_CHECK_INTERVAL: Duration = Duration::from_secs(600);
 
 #[pre_upgrade]
 fn pre_upgrade_hook() {
@@ -27,6 +40,8 @@ fn post_upgrade_hook(maybe_init: Option<InitArg>) {
         state::state::init_state(init_arg);
     }
     storage::restore_from_stable_storage();
+    start_nonce_reconciliation();
+    start_gas_checks();
 }
 
 #[init]
@@ -34,6 +49,28 @@ pub fn init(maybe_init: Option<InitArg>) {
     if let Some(init_arg) = maybe_init {
         state::state::init_state(init_arg);
     }
+    start_nonce_reconciliation();
+    start_gas_checks();
+}
+
+fn start_nonce_reconciliation() {
+    ic_cdk_timers::set_timer_interval(NONCE_RECONCILE_INTERVAL, || {
+        ic_cdk::spawn(async {
+            if let Err(e) = reconcile_all_nonces().await {
+                ic_cdk::println!("Nonce reconciliation failed: {}", e);
+            }
+        })
+    });
+}
+
+fn start_gas_checks() {
+    ic_cdk_timers::set_timer_interval(GAS_CHECK_INTERVAL, || ic_cdk::spawn(gas_service::check_gas_balances()));
+}
+
+async fn reconcile_all_nonces() -> Result<Vec<ReconcileReport>, String> {
+    let base_report = base_transaction_service::reconcile_base_nonces().await;
+    let bsc_report = bsc_transaction_service::reconcile_bsc_nonces().await;
+    Ok(vec![base_report?, bsc_report?])
 }
 
 #[query]
@@ -41,6 +78,142 @@ pub fn get_pool_info() -> PoolsResponse {
     token_service::get_pool_info()
 }
 
+/// Unlike `get_pool_info`, pools are never initialized here, so the result is
+/// exactly what was certified. Each pool is hashed under `pools/<pool_id>`.
+#[query]
+pub fn get_certified_pool_info() -> Certified<PoolsResponse> {
+    let pools = storage::get_all_pools().into_iter().map(PoolInfo::from).collect();
+    certified::certified(PoolsResponse { pools }, certified::POOLS_LABEL)
+}
+
+#[query]
+pub fn get_supported_tokens() -> Vec<Token> {
+    token_service::get_supported_tokens()
+}
+
+#[query]
+pub fn get_supported_pairs() -> Vec<Pair> {
+    token_service::get_supported_pairs()
+}
+
+#[query]
+pub fn get_registry_version() -> u64 {
+    registry::get_registry_version()
+}
+
+#[query]
+pub fn get_registry_events(offset: u64, limit: u64) -> Vec<RegistryEvent> {
+    registry::get_registry_events(offset, limit)
+}
+
+#[update]
+pub fn add_token(token: Token) -> Result<u64, String> {
+    if !ic_cdk::api::is_controller(&caller()) {
+        return Err("Only controllers can add tokens".to_string());
+    }
+
+    registry::add_token(caller(), token)
+}
+
+#[update]
+pub fn update_token(token: Token) -> Result<u64, String> {
+    if !ic_cdk::api::is_controller(&caller()) {
+        return Err("Only controllers can update tokens".to_string());
+    }
+
+    registry::update_token(caller(), token)
+}
+
+#[update]
+pub fn set_token_active(token_id: String, is_active: bool) -> Result<u64, String> {
+    if !ic_cdk::api::is_controller(&caller()) {
+        return Err("Only controllers can enable or disable tokens".to_string());
+    }
+
+    registry::set_token_active(caller(), &token_id, is_active)
+}
+
+#[update]
+pub fn add_pair(pair: Pair) -> Result<u64, String> {
+    if !ic_cdk::api::is_controller(&caller()) {
+        return Err("Only controllers can add pairs".to_string());
+    }
+
+    let version = registry::add_pair(caller(), pair.clone())?;
+    storage::ensure_pool(token_service::pool_data_for_pair(&pair)?);
+
+    Ok(version)
+}
+
+#[update]
+pub fn remove_pair(pool_id: String) -> Result<u64, String> {
+    if !ic_cdk::api::is_controller(&caller()) {
+        return Err("Only controllers can remove pairs".to_string());
+    }
+
+    storage::ensure_pool_empty(&pool_id)?;
+    let version = registry::remove_pair(caller(), &pool_id)?;
+    storage::remove_pool(&pool_id);
+
+    Ok(version)
+}
+
+#[query]
+pub fn get_rpc_config() -> Result<Vec<(RpcChain, ProviderSetView)>, String> {
+    if !ic_cdk::api::is_controller(&caller()) {
+        return Err("Only controllers can read the RPC config".to_string());
+    }
+
+    Ok(providers::get_rpc_config())
+}
+
+#[update]
+pub fn set_rpc_providers(chain: RpcChain, rpc_providers: Vec<RpcProvider>, quorum: u8) -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&caller()) {
+        return Err("Only controllers can set RPC providers".to_string());
+    }
+
+    providers::set_rpc_providers(chain, rpc_providers, quorum)
+}
+
+#[update]
+pub fn set_rpc_api_key(chain: RpcChain, provider: String, api_key: Option<String>) -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&caller()) {
+        return Err("Only controllers can set RPC API keys".to_string());
+    }
+
+    providers::set_rpc_api_key(chain, &provider, api_key)
+}
+
+#[update]
+pub fn reset_rpc_providers(chain: RpcChain) -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&caller()) {
+        return Err("Only controllers can reset RPC providers".to_string());
+    }
+
+    providers::reset_rpc_providers(chain);
+    Ok(())
+}
+
+#[query]
+pub fn get_cycles_budget() -> CyclesBudget {
+    cycles::get_budget()
+}
+
+#[update]
+pub fn set_cycles_budget(budget: CyclesBudget) -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&caller()) {
+        return Err("Only controllers can set the cycles budget".to_string());
+    }
+
+    cycles::set_budget(budget)
+}
+
+#[query]
+pub fn get_cycles_usage() -> Vec<ActionCycles> {
+    cycles::get_usage()
+}
+
 #[query]
 pub fn get_swap_history() -> Vec<SwapResponse> {
     storage::get_swap_history()
@@ -61,6 +234,31 @@ pub fn get_swap_history_count() -> usize {
     storage::get_swap_history_count()
 }
 
+#[query]
+pub fn get_certified_swap_history() -> Certified<Vec<SwapResponse>> {
+    certified::certified(storage::get_swap_history(), certified::SWAP_HISTORY_LABEL)
+}
+
+#[query]
+pub fn icrc3_get_blocks(args: Vec<GetBlocksRequest>) -> GetBlocksResult {
+    events::get_blocks(args)
+}
+
+#[query]
+pub fn icrc3_get_tip_certificate() -> Option<DataCertificate> {
+    events::tip_certificate()
+}
+
+#[query]
+pub fn icrc3_get_archives(_args: GetArchivesArgs) -> Vec<ArchiveInfo> {
+    Vec::new()
+}
+
+#[query]
+pub fn icrc3_supported_block_types() -> Vec<SupportedBlockType> {
+    events::supported_block_types()
+}
+
 #[update]
 pub async fn icp_address() -> String {
     storage::get_cached_icp_address().await
@@ -68,6 +266,7 @@ pub async fn icp_address() -> String {
 
 #[update]
 pub async fn icp_swap(args: SwapArgs) -> SwapResult {
+    cycles::charge("swap", cycles::swap_cost())?;
     services::icp_swap_service::process_icp_swap(args).await
 }
 
@@ -88,7 +287,7 @@ pub async fn solana_address() -> String {
 
 #[update]
 pub async fn nonce_account() -> String {
-    let wallet = wallet::solana_wallet::SolanaWallet::new_canister_wallet().await;
+    let wallet = wallet::canister_solana_wallet().await;
     wallet
         .derived_nonce_account()
         .ed25519_public_key
@@ -138,7 +337,7 @@ fn transform(
     
     let res = HttpResponse {
         status: raw.response.status.clone(),
-        body: raw.response.body.clone(),
+        body: bit10_core::transform::canonical_body(&raw.context, &raw.response.body),
         headers: Vec::new(),
     };
     
@@ -152,6 +351,20 @@ fn transform(
     res
 }
 
+#[query]
+pub fn get_nonce_status() -> Vec<(String, WalletNonces)> {
+    nonces::get_wallet_nonces()
+}
+
+#[update]
+pub async fn reconcile_nonces() -> Result<Vec<ReconcileReport>, String> {
+    if !ic_cdk::api::is_controller(&caller()) {
+        return Err("Only controllers can reconcile nonces".to_string());
+    }
+
+    reconcile_all_nonces().await
+}
+
 // ToDo: Temp code
 #[update]
 pub fn initialize_pool_data() -> Result<String, String> {
@@ -160,6 +373,7 @@ pub fn initialize_pool_data() -> Result<String, String> {
     }
 
     token_service::initialize_pools_from_pairs();
+    certified::certify_pools();
     let pool_count = storage::get_all_pools().len();
     
     Ok(format!("Pool data initialized successfully with {} pools", pool_count))
@@ -174,4 +388,9 @@ pub fn update_pool_balances(pool_id: String, token_a_balance: String, token_b_ba
     storage::update_pool_balances(&pool_id, token_a_balance, token_b_balance)
 }
 
+#[query]
+fn http_request(request: HttpRequest) -> HttpResponse {
+    metrics::http_request(request)
+}
+
 ic_cdk::export_candid!();
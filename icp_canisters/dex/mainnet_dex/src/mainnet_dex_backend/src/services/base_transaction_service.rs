@@ -1,9 +1,11 @@
 use crate::services::base_rpc_service;
+use crate::state::nonces;
 use crate::state::state::read_state;
-use crate::wallet::base_wallet::BaseWallet;
+use crate::wallet::{canister_evm_wallet, evm_wallet::EvmWallet};
 use alloy_consensus::{SignableTransaction, TxEip1559, TxEnvelope};
 use alloy_eips::eip2718::Encodable2718;
 use alloy_primitives::{hex, Address, Signature, U256};
+use bit10_core::nonce::ReconcileReport;
 use candid::Nat;
 
 pub async fn get_base_transaction_count(address: &str) -> Nat {
@@ -18,10 +20,7 @@ pub async fn send_base_erc20_token(
     to: Address,
     amount: U256,
 ) -> Result<String, String> {
-    let wallet = BaseWallet::new_canister_wallet().await;
-    let canister_address = wallet.base_address().to_string();
-
-    let nonce = base_rpc_service::get_base_transaction_count(&canister_address).await?;
+    let wallet = canister_evm_wallet().await;
 
     let (max_fee_per_gas, max_priority_fee_per_gas) =
         base_rpc_service::get_base_gas_price().await?;
@@ -38,6 +37,8 @@ pub async fn send_base_erc20_token(
     encoded.extend_from_slice(&amount_bytes);
 
     let chain_id = read_state(|s| s.base_network().chain_id());
+    let nonce_key = nonces::nonce_key(chain_id, &wallet.address().to_string());
+    let nonce = reserve_base_nonce(&nonce_key, &wallet).await?;
 
     let transaction = TxEip1559 {
         chain_id,
@@ -51,19 +52,18 @@ pub async fn send_base_erc20_token(
         input: encoded.into(),
     };
 
-    sign_and_send_base_transaction(transaction, &wallet).await
+    submit_base_transaction(transaction, &wallet, &nonce_key).await
 }
 
 pub async fn send_base_native_eth(to: Address, amount: U256) -> Result<String, String> {
-    let wallet = BaseWallet::new_canister_wallet().await;
-    let canister_address = wallet.base_address().to_string();
-
-    let nonce = base_rpc_service::get_base_transaction_count(&canister_address).await?;
+    let wallet = canister_evm_wallet().await;
 
     let (max_fee_per_gas, max_priority_fee_per_gas) =
         base_rpc_service::get_base_gas_price().await?;
 
     let chain_id = read_state(|s| s.base_network().chain_id());
+    let nonce_key = nonces::nonce_key(chain_id, &wallet.address().to_string());
+    let nonce = reserve_base_nonce(&nonce_key, &wallet).await?;
 
     let transaction = TxEip1559 {
         chain_id,
@@ -77,12 +77,114 @@ pub async fn send_base_native_eth(to: Address, amount: U256) -> Result<String, S
         input: Default::default(),
     };
 
-    sign_and_send_base_transaction(transaction, &wallet).await
+    submit_base_transaction(transaction, &wallet, &nonce_key).await
+}
+
+/// Compares the locally reserved nonces with the chain, drops everything that
+/// has been mined and fills abandoned nonces with zero-value self transfers.
+pub async fn reconcile_base_nonces() -> Result<ReconcileReport, String> {
+    let wallet = canister_evm_wallet().await;
+    let canister_address = wallet.address().to_string();
+    let chain_id = read_state(|s| s.base_network().chain_id());
+    let nonce_key = nonces::nonce_key(chain_id, &canister_address);
+
+    if !nonces::is_tracked(&nonce_key) {
+        return Ok(ReconcileReport::default());
+    }
+
+    let confirmed_nonce =
+        base_rpc_service::get_base_confirmed_transaction_count(&canister_address).await?;
+    let report = nonces::reconcile(&nonce_key, confirmed_nonce);
+
+    for stuck in &report.stuck {
+        ic_cdk::println!(
+            "Base transaction {:?} with nonce {} is still pending",
+            stuck.tx_hash,
+            stuck.nonce
+        );
+    }
+
+    for nonce in &report.gaps {
+        match send_base_cancel_transaction(&wallet, &nonce_key, *nonce).await {
+            Ok(tx_hash) => ic_cdk::println!("Filled Base nonce gap {} with {}", nonce, tx_hash),
+            Err(e) => ic_cdk::println!("Failed to fill Base nonce gap {}: {}", nonce, e),
+        }
+    }
+
+    Ok(report)
+}
+
+async fn send_base_cancel_transaction(
+    wallet: &EvmWallet,
+    nonce_key: &str,
+    nonce: u64,
+) -> Result<String, String> {
+    let (max_fee_per_gas, max_priority_fee_per_gas) =
+        base_rpc_service::get_base_gas_price().await?;
+
+    nonces::mark_cancel(nonce_key, nonce);
+
+    let transaction = TxEip1559 {
+        chain_id: read_state(|s| s.base_network().chain_id()),
+        nonce,
+        gas_limit: 21_000,
+        max_fee_per_gas,
+        max_priority_fee_per_gas,
+        to: alloy_primitives::TxKind::Call(wallet.address()),
+        value: U256::ZERO,
+        access_list: Default::default(),
+        input: Default::default(),
+    };
+
+    submit_base_transaction(transaction, wallet, nonce_key).await
+}
+
+async fn reserve_base_nonce(nonce_key: &str, wallet: &EvmWallet) -> Result<u64, String> {
+    if !nonces::is_tracked(nonce_key) {
+        let chain_nonce =
+            base_rpc_service::get_base_transaction_count(&wallet.address().to_string()).await?;
+        nonces::track(nonce_key, chain_nonce);
+    }
+    nonces::reserve(nonce_key)
+}
+
+async fn submit_base_transaction(
+    transaction: TxEip1559,
+    wallet: &EvmWallet,
+    nonce_key: &str,
+) -> Result<String, String> {
+    let nonce = transaction.nonce;
+    let max_fee_per_gas = transaction.max_fee_per_gas;
+    let max_priority_fee_per_gas = transaction.max_priority_fee_per_gas;
+
+    match sign_and_send_base_transaction(transaction, wallet).await {
+        Ok(tx_hash) => {
+            nonces::mark_submitted(
+                nonce_key,
+                nonce,
+                tx_hash.clone(),
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            );
+            Ok(tx_hash)
+        }
+        Err(e) => {
+            nonces::mark_failed(nonce_key, nonce);
+            if e.contains("nonce too low") {
+                if let Ok(chain_nonce) =
+                    base_rpc_service::get_base_transaction_count(&wallet.address().to_string()).await
+                {
+                    nonces::track(nonce_key, chain_nonce);
+                }
+            }
+            Err(e)
+        }
+    }
 }
 
 async fn sign_and_send_base_transaction(
     transaction: TxEip1559,
-    wallet: &BaseWallet,
+    wallet: &EvmWallet,
 ) -> Result<String, String> {
     let tx_hash = transaction.signature_hash().0;
 
@@ -0,0 +1,36 @@
+use crate::services::{base_rpc_service, bsc_rpc_service, solana_rpc_service};
+use crate::state::{metrics, storage};
+use bit10_core::metrics::{EVM_TRANSFER_GAS, SOLANA_MIN_BALANCE_LAMPORTS};
+
+/// Reads the native balance of each wallet the DEX pays out from, for
+/// `/health` and `/metrics`.
+pub async fn check_gas_balances() {
+    let base_address = storage::get_cached_base_address().await;
+    record_evm_gas(
+        "Base",
+        base_rpc_service::get_base_balance(&base_address).await,
+        base_rpc_service::get_base_gas_price().await,
+    );
+
+    let bsc_address = storage::get_cached_bsc_address().await;
+    record_evm_gas(
+        "Bsc",
+        bsc_rpc_service::get_bsc_balance(&bsc_address).await,
+        bsc_rpc_service::get_bsc_gas_price().await,
+    );
+
+    let solana_address = storage::get_cached_solana_address().await;
+    match solana_rpc_service::get_balance(&solana_address).await {
+        Ok(balance) => metrics::record_gas("Solana", balance as u128, SOLANA_MIN_BALANCE_LAMPORTS),
+        Err(e) => ic_cdk::println!("Failed to read Solana gas balance: {}", e),
+    }
+}
+
+fn record_evm_gas(chain: &str, balance: Result<u128, String>, gas_price: Result<(u128, u128), String>) {
+    match (balance, gas_price) {
+        (Ok(balance), Ok((max_fee_per_gas, _))) => {
+            metrics::record_gas(chain, balance, max_fee_per_gas * EVM_TRANSFER_GAS)
+        }
+        (Err(e), _) | (_, Err(e)) => ic_cdk::println!("Failed to read {} gas balance: {}", chain, e),
+    }
+}
@@ -5,6 +5,7 @@ use crate::services::{
 use crate::types::swap::{SwapArgs, SwapResponse, SwapResult};
 use crate::utils::{converters, validators};
 use crate::state::storage;
+use bit10_core::errors::ApiError;
 use rust_decimal::Decimal;
 use std::str::FromStr;
 use candid::Principal;
@@ -23,18 +24,18 @@ pub async fn process_icp_swap(args: SwapArgs) -> SwapResult {
     ic_cdk::println!("[DEBUG] Swap Args: {:?}", args);
 
     let pair = token_service::get_pair_by_pool_id(&args.pool_id)
-        .ok_or_else(|| format!("Pool with ID {} does not exist", args.pool_id))?;
+        .ok_or_else(|| ApiError::not_found(format!("Pool {}", args.pool_id)))?;
 
     let token_in = token_service::find_token_by_address(&args.token_in_address, &args.source_chain)
-        .ok_or_else(|| format!(
-            "Token with address {} on chain {} not found in supported tokens",
-            args.token_in_address, args.source_chain
+        .ok_or_else(|| ApiError::invalid_argument(
+            "token_in_address",
+            format!("{} on chain {} is not a supported token", args.token_in_address, args.source_chain),
         ))?;
 
     let token_out = token_service::find_token_by_address(&args.token_out_address, &args.destination_chain)
-        .ok_or_else(|| format!(
-            "Token with address {} on chain {} not found in supported tokens",
-            args.token_out_address, args.destination_chain
+        .ok_or_else(|| ApiError::invalid_argument(
+            "token_out_address",
+            format!("{} on chain {} is not a supported token", args.token_out_address, args.destination_chain),
         ))?;
 
     let verified_source_chain = token_in.token_chain.clone();
@@ -42,43 +43,45 @@ pub async fn process_icp_swap(args: SwapArgs) -> SwapResult {
     let verified_token_in_address = token_in.token_address.clone().unwrap_or_else(|| args.token_in_address.clone());
     let verified_token_out_address = token_out.token_address.clone().unwrap_or_else(|| args.token_out_address.clone());
 
-    let token_in_matches = (token_in.token_id == pair.token_a_token_id && 
+    let token_in_matches = (token_in.has_id(&pair.token_a_token_id) && 
                             token_in.token_chain == pair.token_a_chain) ||
-                           (token_in.token_id == pair.token_b_token_id && 
+                           (token_in.has_id(&pair.token_b_token_id) && 
                             token_in.token_chain == pair.token_b_chain);
 
-    let token_out_matches = (token_out.token_id == pair.token_a_token_id && 
+    let token_out_matches = (token_out.has_id(&pair.token_a_token_id) && 
                              token_out.token_chain == pair.token_a_chain) ||
-                            (token_out.token_id == pair.token_b_token_id && 
+                            (token_out.has_id(&pair.token_b_token_id) && 
                              token_out.token_chain == pair.token_b_chain);
 
     if !token_in_matches {
-        return Err(format!(
-            "Token in (address: {}, chain: {}) does not belong to pool {}",
-            args.token_in_address, args.source_chain, args.pool_id
+        return Err(ApiError::invalid_argument(
+            "token_in_address",
+            format!("{} on chain {} does not belong to pool {}", args.token_in_address, args.source_chain, args.pool_id),
         ));
     }
 
     if !token_out_matches {
-        return Err(format!(
-            "Token out (address: {}, chain: {}) does not belong to pool {}",
-            args.token_out_address, args.destination_chain, args.pool_id
+        return Err(ApiError::invalid_argument(
+            "token_out_address",
+            format!("{} on chain {} does not belong to pool {}", args.token_out_address, args.destination_chain, args.pool_id),
         ));
     }
 
-    let amount_in = validators::validate_amount(&args.amount_in)?;
+    let amount_in = validators::validate_amount(&args.amount_in)
+        .map_err(|e| ApiError::invalid_argument("amount_in", e))?;
 
     if args.expected_amount_out.is_empty() {
-        return Err("expected_amount_out is required".to_string());
+        return Err(ApiError::invalid_argument("expected_amount_out", "is required"));
     }
     
-    let expected_amount_out = validators::validate_amount(&args.expected_amount_out)?;
+    let expected_amount_out = validators::validate_amount(&args.expected_amount_out)
+        .map_err(|e| ApiError::invalid_argument("expected_amount_out", e))?;
 
     let slippage = Decimal::from_str(&args.slippage)
-        .map_err(|_| format!("Invalid slippage: {}", args.slippage))?;
+        .map_err(|_| ApiError::invalid_argument("slippage", format!("{} is not a number", args.slippage)))?;
 
     if slippage < Decimal::ZERO || slippage > Decimal::from(12) {
-        return Err("Slippage must be between 0 and 12".to_string());
+        return Err(ApiError::invalid_argument("slippage", "must be between 0 and 12"));
     }
 
     ic_cdk::println!("[DEBUG] Slippage set to: {}%", slippage);
@@ -108,7 +111,7 @@ pub async fn process_icp_swap(args: SwapArgs) -> SwapResult {
         return Err(format!(
             "Token out does not match pool structure for pool {}",
             args.pool_id
-        ));
+        ).into());
     };
 
     let available = Decimal::from_str(available_balance)
@@ -120,11 +123,11 @@ pub async fn process_icp_swap(args: SwapArgs) -> SwapResult {
             expected_amount_out,
             available
         );
-        return Err(format!(
-            "Insufficient liquidity in pool. Available: {}, Required: {}",
-            available,
-            expected_amount_out
-        ));
+        return Err(ApiError::InsufficientLiquidity {
+            token: token_out.token_symbol.clone(),
+            available: available.to_string(),
+            required: expected_amount_out.to_string(),
+        });
     }
 
     ic_cdk::println!(
@@ -152,7 +155,7 @@ pub async fn process_icp_swap(args: SwapArgs) -> SwapResult {
         },
         Err(e) => {
             ic_cdk::println!("[ERROR] Failed to receive payment: {}", e);
-            return Err(format!("Failed to receive payment: {}", e));
+            return Err(ApiError::transfer_failed(e));
         }
     };
 
@@ -168,7 +171,7 @@ pub async fn process_icp_swap(args: SwapArgs) -> SwapResult {
                 amount_in_with_fee,
             )
             .await;
-            return Err(format!("Failed to get token_in price: {}", e));
+            return Err(ApiError::price_feed_unavailable(&token_in.token_symbol, e));
         }
     };
 
@@ -184,7 +187,7 @@ pub async fn process_icp_swap(args: SwapArgs) -> SwapResult {
                 amount_in_with_fee,
             )
             .await;
-            return Err(format!("Failed to get token_out price: {}", e));
+            return Err(ApiError::price_feed_unavailable(&token_out.token_symbol, e));
         }
     };
 
@@ -200,7 +203,7 @@ pub async fn process_icp_swap(args: SwapArgs) -> SwapResult {
             amount_in_with_fee,
         )
         .await;
-        return Err("Token out price is zero".to_string());
+        return Err(ApiError::price_feed_unavailable(&token_out.token_symbol, "price is zero"));
     };
 
     let calculated_amount_out_decimal = Decimal::from_str(&calculated_amount_out.to_string())
@@ -349,7 +352,7 @@ pub async fn process_icp_swap(args: SwapArgs) -> SwapResult {
                     "revert_failed".to_string()
                 });
                 
-                return Err(format!("Swap failed and reverted: {}", e));
+                return Err(ApiError::transfer_failed(format!("Payout failed and was reverted: {}", e)));
             }
         }
     } else {
@@ -371,7 +374,7 @@ pub async fn process_icp_swap(args: SwapArgs) -> SwapResult {
         .await
         .map_err(|e| {
             ic_cdk::println!("[CRITICAL ERROR] Revert failed: {}", e);
-            format!("Slippage exceeded and revert failed: {}", e)
+            ApiError::transfer_failed(format!("Slippage exceeded and revert failed: {}", e))
         })?;
 
         ic_cdk::println!("[SUCCESS] Reverted due to excessive slippage. Returned {} to user. Tx Hash: {}", 
@@ -1,4 +1,5 @@
 pub mod token_service;
+pub mod gas_service;
 pub mod icp_transaction_service;
 pub mod icp_swap_service;
 pub mod base_rpc_service;
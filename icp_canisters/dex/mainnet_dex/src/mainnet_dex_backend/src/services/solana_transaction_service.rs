@@ -1,5 +1,5 @@
 use crate::services::solana_rpc_service;
-use crate::wallet::solana_wallet::SolanaWallet;
+use crate::wallet::canister_solana_wallet;
 use base64::{engine::general_purpose, Engine as _};
 use solana_hash::Hash;
 use solana_instruction::{AccountMeta, Instruction};
@@ -16,7 +16,7 @@ use std::str::FromStr;
 pub async fn create_associated_token_account_for_canister(mint_address: &str) -> Result<String, String> {
     verify_is_controller().await?;
 
-    let wallet = SolanaWallet::new_canister_wallet().await;
+    let wallet = canister_solana_wallet().await;
     let payer = wallet.solana_account();
 
     let mint = Pubkey::from_str(mint_address)
@@ -48,7 +48,7 @@ pub async fn create_associated_token_account_for_canister(mint_address: &str) ->
 }
 
 pub async fn get_associated_token_account_address(mint_address: &str) -> Result<String, String> {
-    let wallet = SolanaWallet::new_canister_wallet().await;
+    let wallet = canister_solana_wallet().await;
     let owner = wallet.solana_account();
 
     let mint = Pubkey::from_str(mint_address)
@@ -65,7 +65,7 @@ pub async fn get_associated_token_account_address(mint_address: &str) -> Result<
 pub async fn create_nonce_account_for_canister() -> Result<String, String> {
     verify_is_controller().await?;
 
-    let wallet = SolanaWallet::new_canister_wallet().await;
+    let wallet = canister_solana_wallet().await;
     let payer = wallet.solana_account();
     let nonce_account = wallet.derived_nonce_account();
 
@@ -148,7 +148,7 @@ fn bincode_serialize<T: serde::Serialize>(value: &T) -> Vec<u8> {
 }
 
 pub async fn send_spl_token(to_address: &str, mint_address: &str, amount: u64) -> Result<String, String> {
-    let wallet = SolanaWallet::new_canister_wallet().await;
+    let wallet = canister_solana_wallet().await;
     let payer = wallet.solana_account();
 
     let recipient =
@@ -243,7 +243,7 @@ pub async fn send_spl_token(to_address: &str, mint_address: &str, amount: u64) -
 }
 
 pub async fn send_solana_native_sol(to_address: &str, amount: u64) -> Result<String, String> {
-    let wallet = SolanaWallet::new_canister_wallet().await;
+    let wallet = canister_solana_wallet().await;
     let payer = wallet.solana_account();
 
     let recipient =
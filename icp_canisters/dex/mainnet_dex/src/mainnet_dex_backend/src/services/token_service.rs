@@ -1,13 +1,14 @@
+use crate::state::{metrics, registry};
 use crate::utils::constants::PRICE_FEED_CANISTER;
 use crate::types::pool::{PoolData, PoolInfo, PoolsResponse};
 use crate::types::token::{Token, Pair};
 use candid::Principal;
 use ciborium::from_reader;
 
-pub fn get_supported_tokens() -> Vec<Token> {
+pub fn default_supported_tokens() -> Vec<Token> {
     vec![
         Token {
-            token_id: "8916".to_string(),
+            token_id: Some("8916".to_string()),
             token_name: "ICP".to_string(),
             token_symbol: "ICP".to_string(),
             token_address: Some("ryjl3-tyaaa-aaaaa-aaaba-cai".to_string()),
@@ -15,10 +16,11 @@ pub fn get_supported_tokens() -> Vec<Token> {
             token_decimals: 8,
             is_native: true,
             is_active: true,
-            price_feed_id: "xbygsdfghjdfkgf".to_string(),
+            price_feed_id: Some("xbygsdfghjdfkgf".to_string()),
+            price_feed_link: None,
         },
         Token {
-            token_id: "3408_8916".to_string(),
+            token_id: Some("3408_8916".to_string()),
             token_name: "ckUSDC".to_string(),
             token_symbol: "ckUSDC".to_string(),
             token_address: Some("xevnm-gaaaa-aaaar-qafnq-cai".to_string()),
@@ -26,10 +28,11 @@ pub fn get_supported_tokens() -> Vec<Token> {
             token_decimals: 6,
             is_native: false,
             is_active: true,
-            price_feed_id: "hgdfhgdfhbdjgf".to_string(),
+            price_feed_id: Some("hgdfhgdfhbdjgf".to_string()),
+            price_feed_link: None,
         },
         Token {
-            token_id: "1027".to_string(),
+            token_id: Some("1027".to_string()),
             token_name: "Ethereum".to_string(),
             token_symbol: "ETH".to_string(),
             token_address: Some("0x0000000000000000000000000000000000000000base".to_string()),
@@ -37,10 +40,11 @@ pub fn get_supported_tokens() -> Vec<Token> {
             token_decimals: 18,
             is_native: true,
             is_active: true,
-            price_feed_id: "xgvhjcbxjvcjxnbjcvbvcb".to_string(),
+            price_feed_id: Some("xgvhjcbxjvcjxnbjcvbvcb".to_string()),
+            price_feed_link: None,
         },
         Token {
-            token_id: "3408_1027".to_string(),
+            token_id: Some("3408_1027".to_string()),
             token_name: "USD Coin".to_string(),
             token_symbol: "USDC".to_string(),
             // token_address: Some("0x036cbd53842c5426634e7929541ec2318f3dcf7e".to_string()), // ToDo: Update this local
@@ -49,10 +53,11 @@ pub fn get_supported_tokens() -> Vec<Token> {
             token_decimals: 6,
             is_native: false,
             is_active: true,
-            price_feed_id: "zxvghbxfjcbnjcnbkg".to_string(),
+            price_feed_id: Some("zxvghbxfjcbnjcnbkg".to_string()),
+            price_feed_link: None,
         },
         Token {
-            token_id: "5426".to_string(),
+            token_id: Some("5426".to_string()),
             token_name: "Solana".to_string(),
             token_symbol: "SOL".to_string(),
             token_address: Some("So11111111111111111111111111111111111111111".to_string()),
@@ -60,10 +65,11 @@ pub fn get_supported_tokens() -> Vec<Token> {
             token_decimals: 9,
             is_native: true,
             is_active: true,
-            price_feed_id: "zhxbdjbxfjbvkcfbv".to_string(),
+            price_feed_id: Some("zhxbdjbxfjbvkcfbv".to_string()),
+            price_feed_link: None,
         },
         Token {
-            token_id: "3408_5426".to_string(),
+            token_id: Some("3408_5426".to_string()),
             token_name: "USD Coin".to_string(),
             token_symbol: "USDC".to_string(),
             // token_address: Some("4zMMC9srt5Ri5X14GAgXhaHii3GnPAEERYPJgZJDncDU".to_string()), // ToDo: Update this local (Case sensitive)
@@ -72,10 +78,11 @@ pub fn get_supported_tokens() -> Vec<Token> {
             token_decimals: 6,
             is_native: false,
             is_active: true,
-            price_feed_id: "dvhbxfbhjxcbfvjkf".to_string(),
+            price_feed_id: Some("dvhbxfbhjxcbfvjkf".to_string()),
+            price_feed_link: None,
         },
         Token {
-            token_id: "1839".to_string(),
+            token_id: Some("1839".to_string()),
             token_name: "BNB".to_string(),
             token_symbol: "BNB".to_string(),
             token_address: Some("0x0000000000000000000000000000000000000000bnb".to_string()),
@@ -83,10 +90,11 @@ pub fn get_supported_tokens() -> Vec<Token> {
             token_decimals: 18,
             is_native: true,
             is_active: true,
-            price_feed_id: "hsgdvfhvdfbvjfv".to_string(),
+            price_feed_id: Some("hsgdvfhvdfbvjfv".to_string()),
+            price_feed_link: None,
         },
         Token {
-            token_id: "3408_1839".to_string(),
+            token_id: Some("3408_1839".to_string()),
             token_name: "USD Coin".to_string(),
             token_symbol: "USDC".to_string(),
             // token_address: Some("0x64544969ed7ebf5f083679233325356ebe738930".to_string()), // ToDo: Update this local
@@ -95,12 +103,13 @@ pub fn get_supported_tokens() -> Vec<Token> {
             token_decimals: 18,
             is_native: false,
             is_active: true,
-            price_feed_id: "xhbvhjbcjvbcvnb".to_string(),
+            price_feed_id: Some("xhbvhjbcjvbcvnb".to_string()),
+            price_feed_link: None,
         },
     ]
 }
 
-pub fn get_supported_pairs() -> Vec<Pair> {
+pub fn default_supported_pairs() -> Vec<Pair> {
     vec![
         Pair {
             pool_id: "2998n9dml3".to_string(),
@@ -235,23 +244,33 @@ pub fn get_supported_pairs() -> Vec<Pair> {
     ]
 }
 
+pub fn get_supported_tokens() -> Vec<Token> {
+    registry::get_tokens()
+}
+
+pub fn get_supported_pairs() -> Vec<Pair> {
+    registry::get_pairs()
+}
+
 pub fn get_pair_by_pool_id(pool_id: &str) -> Option<Pair> {
+    let tokens = get_supported_tokens();
+    let is_active = |token_id: &str| {
+        tokens
+            .iter()
+            .any(|t| t.has_id(token_id) && t.is_active)
+    };
+
     get_supported_pairs()
         .into_iter()
         .find(|p| p.pool_id == pool_id)
+        .filter(|p| is_active(&p.token_a_token_id) && is_active(&p.token_b_token_id))
 }
 
 pub fn find_token_by_address(address: &str, chain: &str) -> Option<Token> {
-    get_supported_tokens().into_iter().find(|token| {
-        token
-            .token_address
-            .as_ref()
-            .map(|addr| {
-                addr.eq_ignore_ascii_case(address)
-                    && token.token_chain.eq_ignore_ascii_case(chain)
-            })
-            .unwrap_or(false)
-    })
+    get_supported_tokens()
+        .into_iter()
+        .filter(|token| token.is_active)
+        .find(|token| token.matches(address, chain))
 }
 
 pub async fn get_token_price_from_feed(token: &Token) -> Result<f64, String> {
@@ -261,17 +280,18 @@ pub async fn get_token_price_from_feed(token: &Token) -> Result<f64, String> {
         timestamp: u64,
     }
 
+    let price_feed_id = token.feed_id()?;
     let price_feed_canister = Principal::from_text(PRICE_FEED_CANISTER)
         .map_err(|e| format!("Invalid price feed canister ID: {}", e))?;
 
     let price_result: Result<(Option<PriceFeedResult>,), _> = ic_cdk::call(
         price_feed_canister,
         "get_value",
-        (token.price_feed_id.clone(),),
+        (price_feed_id.to_string(),),
     )
     .await;
 
-    match price_result {
+    let price = match price_result {
         Ok((Some(feed),)) => match from_reader::<f64, _>(&feed.value[..]) {
             Ok(value) => Ok(value),
             Err(_) => match from_reader::<u64, _>(&feed.value[..]) {
@@ -281,43 +301,45 @@ pub async fn get_token_price_from_feed(token: &Token) -> Result<f64, String> {
         },
         Ok((None,)) => Err("Price feed not found".to_string()),
         Err(e) => Err(format!("Failed to get price feed: {:?}", e)),
-    }
+    };
+    metrics::record_price_feed(price_feed_id, price.is_ok());
+    price
 }
 
 pub fn initialize_pools_from_pairs() {
-    let pairs = get_supported_pairs();
-    let tokens = get_supported_tokens();
-    
-    let pool_data: Vec<PoolData> = pairs
-        .into_iter()
-        .map(|pair| {
-            let token_a = tokens
-                .iter()
-                .find(|t| t.token_id == pair.token_a_token_id)
-                .expect(&format!("Token A not found for pair {}", pair.pool_id));
-            
-            let token_b = tokens
-                .iter()
-                .find(|t| t.token_id == pair.token_b_token_id)
-                .expect(&format!("Token B not found for pair {}", pair.pool_id));
-            
-            PoolData {
-                pool_id: pair.pool_id,
-                token_a: pair.token_a_symbol,
-                token_b: pair.token_b_symbol,
-                token_a_address: token_a.token_address.clone().unwrap_or_default(),
-                token_b_address: token_b.token_address.clone().unwrap_or_default(),
-                token_a_chain: pair.token_a_chain,
-                token_b_chain: pair.token_b_chain,
-                token_a_balance: "0.00".to_string(),
-                token_b_balance: "0.00".to_string(),
-            }
-        })
+    let pool_data: Vec<PoolData> = get_supported_pairs()
+        .iter()
+        .map(|pair| pool_data_for_pair(pair).unwrap_or_else(|e| ic_cdk::trap(&e)))
         .collect();
     
     crate::state::storage::initialize_pools(pool_data);
 }
 
+pub fn pool_data_for_pair(pair: &Pair) -> Result<PoolData, String> {
+    let tokens = get_supported_tokens();
+    let find = |token_id: &str| {
+        tokens
+            .iter()
+            .find(|t| t.has_id(token_id))
+            .ok_or_else(|| format!("Token {} not found for pair {}", token_id, pair.pool_id))
+    };
+
+    let token_a = find(&pair.token_a_token_id)?;
+    let token_b = find(&pair.token_b_token_id)?;
+
+    Ok(PoolData {
+        pool_id: pair.pool_id.clone(),
+        token_a: pair.token_a_symbol.clone(),
+        token_b: pair.token_b_symbol.clone(),
+        token_a_address: token_a.token_address.clone().unwrap_or_default(),
+        token_b_address: token_b.token_address.clone().unwrap_or_default(),
+        token_a_chain: pair.token_a_chain.clone(),
+        token_b_chain: pair.token_b_chain.clone(),
+        token_a_balance: "0.00".to_string(),
+        token_b_balance: "0.00".to_string(),
+    })
+}
+
 pub fn get_pool_info() -> PoolsResponse {
     let pool_data = crate::state::storage::get_all_pools();
     
@@ -327,17 +349,7 @@ pub fn get_pool_info() -> PoolsResponse {
         
         let pools = pool_data
             .into_iter()
-            .map(|p| PoolInfo {
-                pool_id: p.pool_id,
-                token_a: p.token_a,
-                token_b: p.token_b,
-                token_a_address: p.token_a_address,
-                token_b_address: p.token_b_address,
-                token_a_chain: p.token_a_chain,
-                token_b_chain: p.token_b_chain,
-                token_a_balance: p.token_a_balance,
-                token_b_balance: p.token_b_balance,
-            })
+            .map(PoolInfo::from)
             .collect();
         
         return PoolsResponse { pools };
@@ -345,17 +357,7 @@ pub fn get_pool_info() -> PoolsResponse {
     
     let pools = pool_data
         .into_iter()
-        .map(|p| PoolInfo {
-            pool_id: p.pool_id,
-            token_a: p.token_a,
-            token_b: p.token_b,
-            token_a_address: p.token_a_address,
-            token_b_address: p.token_b_address,
-            token_a_chain: p.token_a_chain,
-            token_b_chain: p.token_b_chain,
-            token_a_balance: p.token_a_balance,
-            token_b_balance: p.token_b_balance,
-        })
+        .map(PoolInfo::from)
         .collect();
 
     PoolsResponse { pools }
@@ -0,0 +1,85 @@
+use crate::state::{events, storage};
+use crate::types::pool::{PoolData, PoolInfo};
+use crate::types::swap::SwapResponse;
+use bit10_core::certification::{candid_hash, list_hash, Certified, CertifiedTree};
+use std::cell::RefCell;
+
+pub const POOLS_LABEL: &[u8] = b"pools";
+pub const SWAP_HISTORY_LABEL: &[u8] = b"swap_history";
+
+thread_local! {
+    /// Everything the canister certifies: the event log's tip, each pool
+    /// under `pools/<pool_id>` and the swap history as a `list_hash` chain.
+    /// Pools are hashed one by one since they are kept in a map with no
+    /// stable order.
+    static TREE: RefCell<CertifiedTree> = RefCell::new(CertifiedTree::default());
+}
+
+fn update(f: impl FnOnce(&mut CertifiedTree)) {
+    TREE.with(|tree| {
+        let mut tree = tree.borrow_mut();
+        f(&mut tree);
+        ic_cdk::api::set_certified_data(&tree.root_hash());
+    });
+}
+
+/// Derives the whole tree from state. Certified data does not survive an
+/// upgrade, so this runs on restore; everything else updates single labels.
+pub fn certify_all() {
+    update(|tree| {
+        *tree = CertifiedTree::default();
+        events::certify_tip(tree);
+        let pools = tree.subtree_mut(POOLS_LABEL);
+        for pool in storage::get_all_pools() {
+            insert_pool(pools, pool);
+        }
+        tree.insert(SWAP_HISTORY_LABEL, list_hash(&storage::get_swap_history()));
+    });
+}
+
+pub fn certify_tip() {
+    update(events::certify_tip);
+}
+
+pub fn certify_pool(pool_id: &str) {
+    let pool = storage::get_pool(pool_id);
+    update(|tree| {
+        let pools = tree.subtree_mut(POOLS_LABEL);
+        match pool {
+            Some(pool) => insert_pool(pools, pool),
+            None => pools.remove(pool_id),
+        }
+    });
+}
+
+pub fn certify_pools() {
+    let all_pools = storage::get_all_pools();
+    update(|tree| {
+        let pools = tree.subtree_mut(POOLS_LABEL);
+        *pools = CertifiedTree::default();
+        for pool in all_pools {
+            insert_pool(pools, pool);
+        }
+    });
+}
+
+pub fn append_swap(swap: &SwapResponse) {
+    update(|tree| tree.append(SWAP_HISTORY_LABEL, swap));
+}
+
+fn insert_pool(pools: &mut CertifiedTree, pool: PoolData) {
+    let pool = PoolInfo::from(pool);
+    pools.insert(&pool.pool_id, candid_hash(&pool));
+}
+
+pub fn tip_hash_tree() -> Vec<u8> {
+    TREE.with(|tree| bit10_core::events::tip_hash_tree(&tree.borrow()))
+}
+
+pub fn certified<T>(data: T, label: &[u8]) -> Certified<T> {
+    Certified {
+        data,
+        certificate: ic_cdk::api::data_certificate(),
+        hash_tree: TREE.with(|tree| tree.borrow().witness(&[&[label]]).to_cbor()),
+    }
+}
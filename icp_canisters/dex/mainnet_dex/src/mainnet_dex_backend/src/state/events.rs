@@ -0,0 +1,49 @@
+use crate::state::certified;
+use bit10_core::certification::CertifiedTree;
+use bit10_core::events::{self, DataCertificate, Event, EventLog, GetBlocksRequest, GetBlocksResult, SupportedBlockType};
+use bit10_core::metrics::{self, MetricsEncoder};
+use std::cell::RefCell;
+
+thread_local! {
+    static EVENT_LOG: RefCell<EventLog> = RefCell::new(EventLog::default());
+}
+
+pub fn export_events() -> EventLog {
+    EVENT_LOG.with(|log| log.borrow().clone())
+}
+
+pub fn import_events(log: EventLog) {
+    EVENT_LOG.with(|l| *l.borrow_mut() = log);
+}
+
+pub fn record_event(event: Event) -> u64 {
+    let id = EVENT_LOG.with(|log| log.borrow_mut().append(event, ic_cdk::api::time()));
+    certified::certify_tip();
+    id
+}
+
+pub fn get_blocks(requests: Vec<GetBlocksRequest>) -> GetBlocksResult {
+    EVENT_LOG.with(|log| log.borrow().get_blocks(&requests))
+}
+
+pub fn tip_certificate() -> Option<DataCertificate> {
+    if EVENT_LOG.with(|log| log.borrow().is_empty()) {
+        return None;
+    }
+    Some(DataCertificate {
+        certificate: ic_cdk::api::data_certificate()?,
+        hash_tree: certified::tip_hash_tree(),
+    })
+}
+
+pub fn supported_block_types() -> Vec<SupportedBlockType> {
+    events::supported_block_types(&[events::SWAP_BLOCK_TYPE])
+}
+
+pub fn certify_tip(tree: &mut CertifiedTree) {
+    EVENT_LOG.with(|log| log.borrow().certify(tree));
+}
+
+pub fn encode_metrics(encoder: &mut MetricsEncoder) {
+    EVENT_LOG.with(|log| metrics::encode_events(encoder, &log.borrow()));
+}
@@ -0,0 +1,51 @@
+use crate::state::{cycles, events, nonces};
+use bit10_core::metrics::{
+    self, GasMonitor, HealthReport, HttpRequest, HttpResponse, MetricsEncoder, PriceFeedClock,
+    DEFAULT_MAX_FEED_AGE_NS,
+};
+use std::cell::RefCell;
+
+thread_local! {
+    static PRICE_FEEDS: RefCell<PriceFeedClock> = RefCell::new(PriceFeedClock::default());
+    static GAS: RefCell<GasMonitor> = RefCell::new(GasMonitor::default());
+}
+
+pub fn record_price_feed(feed: &str, succeeded: bool) {
+    PRICE_FEEDS.with(|f| f.borrow_mut().record(feed, succeeded, ic_cdk::api::time()));
+}
+
+pub fn record_gas(chain: &str, balance: u128, minimum: u128) {
+    GAS.with(|g| g.borrow_mut().record(chain, balance, minimum, ic_cdk::api::time()));
+}
+
+pub fn http_request(request: HttpRequest) -> HttpResponse {
+    match request.path() {
+        "/metrics" => encode_metrics().into_response(),
+        "/health" => health().into_response(),
+        _ => HttpResponse::not_found(),
+    }
+}
+
+// The DEX reads from a single provider per chain without tallying, so there
+// are no per-provider outcall counts to report.
+fn encode_metrics() -> MetricsEncoder {
+    let mut encoder = MetricsEncoder::new();
+    metrics::encode_canister(
+        &mut encoder,
+        ic_cdk::api::canister_balance128(),
+        ic_cdk::api::stable::stable_size() * 65536,
+    );
+    events::encode_metrics(&mut encoder);
+    cycles::encode_metrics(&mut encoder);
+    metrics::encode_pending_sagas(&mut encoder, &[("evm_transaction", nonces::pending_transactions())]);
+    PRICE_FEEDS.with(|f| f.borrow().encode(&mut encoder));
+    GAS.with(|g| g.borrow().encode(&mut encoder));
+    encoder
+}
+
+fn health() -> HealthReport {
+    let mut report = HealthReport::new(ic_cdk::api::time());
+    PRICE_FEEDS.with(|f| f.borrow().check(&mut report, DEFAULT_MAX_FEED_AGE_NS));
+    GAS.with(|g| g.borrow().check(&mut report));
+    report
+}
@@ -0,0 +1,255 @@
+use crate::services::token_service::{default_supported_pairs, default_supported_tokens};
+use crate::types::registry::{RegistryChange, RegistryEvent};
+use crate::types::token::{Pair, Token};
+use candid::{CandidType, Deserialize, Principal};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+thread_local! {
+    static TOKEN_REGISTRY: RefCell<TokenRegistry> = RefCell::new(TokenRegistry::with_defaults());
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct TokenRegistry {
+    pub version: u64,
+    pub tokens: BTreeMap<String, Token>,
+    pub pairs: BTreeMap<String, Pair>,
+    pub events: Vec<RegistryEvent>,
+}
+
+impl TokenRegistry {
+    pub fn with_defaults() -> Self {
+        TokenRegistry {
+            version: 0,
+            tokens: default_supported_tokens()
+                .into_iter()
+                .filter_map(|token| Some((token.token_id.clone()?, token)))
+                .collect(),
+            pairs: default_supported_pairs()
+                .into_iter()
+                .map(|pair| (pair.pool_id.clone(), pair))
+                .collect(),
+            events: Vec::new(),
+        }
+    }
+
+    fn record(&mut self, caller: Principal, change: RegistryChange) -> u64 {
+        self.version += 1;
+        self.events.push(RegistryEvent {
+            version: self.version,
+            timestamp: ic_cdk::api::time(),
+            caller,
+            change,
+        });
+        self.version
+    }
+}
+
+pub fn export_registry() -> TokenRegistry {
+    TOKEN_REGISTRY.with(|r| r.borrow().clone())
+}
+
+pub fn import_registry(registry: TokenRegistry) {
+    TOKEN_REGISTRY.with(|r| *r.borrow_mut() = registry);
+}
+
+pub fn get_tokens() -> Vec<Token> {
+    TOKEN_REGISTRY.with(|r| r.borrow().tokens.values().cloned().collect())
+}
+
+pub fn get_pairs() -> Vec<Pair> {
+    TOKEN_REGISTRY.with(|r| r.borrow().pairs.values().cloned().collect())
+}
+
+pub fn get_registry_version() -> u64 {
+    TOKEN_REGISTRY.with(|r| r.borrow().version)
+}
+
+pub fn get_registry_events(offset: u64, limit: u64) -> Vec<RegistryEvent> {
+    TOKEN_REGISTRY.with(|r| {
+        r.borrow()
+            .events
+            .iter()
+            .skip(usize::try_from(offset).unwrap_or(usize::MAX))
+            .take(usize::try_from(limit).unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    })
+}
+
+pub fn add_token(caller: Principal, token: Token) -> Result<u64, String> {
+    let token_id = validate_token(&token)?;
+
+    TOKEN_REGISTRY.with(|r| {
+        let mut registry = r.borrow_mut();
+
+        if registry.tokens.contains_key(&token_id) {
+            return Err(format!("Token with id {} already exists", token_id));
+        }
+
+        if let Some((existing_id, _)) = registry.tokens.iter().find(|(_, t)| same_listing(t, &token)) {
+            return Err(format!(
+                "Token address is already registered as {}",
+                existing_id
+            ));
+        }
+
+        registry.tokens.insert(token_id, token.clone());
+        Ok(registry.record(caller, RegistryChange::TokenAdded(token)))
+    })
+}
+
+pub fn update_token(caller: Principal, token: Token) -> Result<u64, String> {
+    let token_id = validate_token(&token)?;
+
+    TOKEN_REGISTRY.with(|r| {
+        let mut registry = r.borrow_mut();
+
+        if !registry.tokens.contains_key(&token_id) {
+            return Err(format!("Token with id {} not found", token_id));
+        }
+
+        if let Some((existing_id, _)) = registry
+            .tokens
+            .iter()
+            .find(|(id, t)| **id != token_id && same_listing(t, &token))
+        {
+            return Err(format!(
+                "Token address is already registered as {}",
+                existing_id
+            ));
+        }
+
+        let referenced = registry.pairs.values().any(|p| {
+            (p.token_a_token_id == token_id && p.token_a_chain != token.token_chain)
+                || (p.token_b_token_id == token_id && p.token_b_chain != token.token_chain)
+        });
+        if referenced {
+            return Err(format!(
+                "Cannot change the chain of token {} while pairs reference it",
+                token_id
+            ));
+        }
+
+        registry.tokens.insert(token_id, token.clone());
+        Ok(registry.record(caller, RegistryChange::TokenUpdated(token)))
+    })
+}
+
+pub fn set_token_active(caller: Principal, token_id: &str, is_active: bool) -> Result<u64, String> {
+    TOKEN_REGISTRY.with(|r| {
+        let mut registry = r.borrow_mut();
+
+        let token = registry
+            .tokens
+            .get_mut(token_id)
+            .ok_or_else(|| format!("Token with id {} not found", token_id))?;
+
+        if token.is_active == is_active {
+            return Err(format!(
+                "Token {} is already {}",
+                token_id,
+                if is_active { "active" } else { "inactive" }
+            ));
+        }
+
+        token.is_active = is_active;
+        Ok(registry.record(
+            caller,
+            RegistryChange::TokenActiveSet {
+                token_id: token_id.to_string(),
+                is_active,
+            },
+        ))
+    })
+}
+
+pub fn add_pair(caller: Principal, pair: Pair) -> Result<u64, String> {
+    if pair.pool_id.trim().is_empty() {
+        return Err("pool_id cannot be empty".to_string());
+    }
+
+    if pair.token_a_token_id == pair.token_b_token_id {
+        return Err("A pair must consist of two different tokens".to_string());
+    }
+
+    TOKEN_REGISTRY.with(|r| {
+        let mut registry = r.borrow_mut();
+
+        if registry.pairs.contains_key(&pair.pool_id) {
+            return Err(format!("Pair with pool id {} already exists", pair.pool_id));
+        }
+
+        for (token_id, chain) in [
+            (&pair.token_a_token_id, &pair.token_a_chain),
+            (&pair.token_b_token_id, &pair.token_b_chain),
+        ] {
+            let token = registry
+                .tokens
+                .get(token_id)
+                .ok_or_else(|| format!("Token with id {} not found", token_id))?;
+
+            if &token.token_chain != chain {
+                return Err(format!(
+                    "Token {} is on chain {}, not {}",
+                    token_id, token.token_chain, chain
+                ));
+            }
+        }
+
+        let duplicate = registry.pairs.values().any(|p| {
+            (p.token_a_token_id == pair.token_a_token_id && p.token_b_token_id == pair.token_b_token_id)
+                || (p.token_a_token_id == pair.token_b_token_id
+                    && p.token_b_token_id == pair.token_a_token_id)
+        });
+        if duplicate {
+            return Err("A pair for these tokens already exists".to_string());
+        }
+
+        registry.pairs.insert(pair.pool_id.clone(), pair.clone());
+        Ok(registry.record(caller, RegistryChange::PairAdded(pair)))
+    })
+}
+
+pub fn remove_pair(caller: Principal, pool_id: &str) -> Result<u64, String> {
+    TOKEN_REGISTRY.with(|r| {
+        let mut registry = r.borrow_mut();
+
+        if registry.pairs.remove(pool_id).is_none() {
+            return Err(format!("Pair with pool id {} not found", pool_id));
+        }
+
+        Ok(registry.record(
+            caller,
+            RegistryChange::PairRemoved {
+                pool_id: pool_id.to_string(),
+            },
+        ))
+    })
+}
+
+fn validate_token(token: &Token) -> Result<String, String> {
+    let token_id = token
+        .token_id
+        .as_ref()
+        .filter(|id| !id.trim().is_empty())
+        .ok_or_else(|| "token_id cannot be empty".to_string())?;
+
+    if token.token_chain.trim().is_empty() {
+        return Err("token_chain cannot be empty".to_string());
+    }
+
+    token.feed_id()?;
+
+    Ok(token_id.clone())
+}
+
+fn same_listing(a: &Token, b: &Token) -> bool {
+    match (&a.token_address, &b.token_address) {
+        (Some(a_address), Some(b_address)) => {
+            a_address.eq_ignore_ascii_case(b_address)
+                && a.token_chain.eq_ignore_ascii_case(&b.token_chain)
+        }
+        _ => false,
+    }
+}
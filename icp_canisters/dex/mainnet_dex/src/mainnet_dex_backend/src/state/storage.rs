@@ -1,7 +1,16 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::str::FromStr;
+use crate::state::{certified, cycles, events, nonces, providers};
+use crate::state::registry::{self, TokenRegistry};
+use bit10_core::cycles::CyclesLedger;
+use bit10_core::events::EventLog;
+use bit10_core::nonce::NonceManager;
+use bit10_core::providers::RpcConfig;
 use crate::types::pool::PoolData;
+use crate::types::network::RpcChain;
 use crate::types::swap::SwapResponse;
+use rust_decimal::Decimal;
 
 thread_local! {
     static CACHED_ICP_ADDRESS: RefCell<Option<String>> = RefCell::new(None);
@@ -19,13 +28,18 @@ pub fn save_to_stable_storage() {
     let solana_addr = CACHED_SOLANA_ADDRESS.with(|addr| addr.borrow().clone());
     let pools = POOL_DATA.with(|data| data.borrow().clone());
     let swap_history = SWAP_HISTORY.with(|history| history.borrow().clone());
+    let token_registry = Some(registry::export_registry());
+    let nonce_manager = Some(nonces::export_nonces());
+    let rpc_config = Some(providers::export_rpc_config());
+    let event_log = Some(events::export_events());
+    let cycles_ledger = Some(cycles::export_cycles());
 
-    ic_cdk::storage::stable_save((icp_addr, base_addr, bsc_addr, solana_addr, pools, swap_history))
+    ic_cdk::storage::stable_save((icp_addr, base_addr, bsc_addr, solana_addr, pools, swap_history, token_registry, nonce_manager, rpc_config, event_log, cycles_ledger))
         .expect("Failed to save data to stable storage");
 }
 
 pub fn restore_from_stable_storage() {
-    if let Ok((icp_addr, base_addr, bsc_addr, solana_addr, pools, swap_history)) =
+    if let Ok((icp_addr, base_addr, bsc_addr, solana_addr, pools, swap_history, token_registry, nonce_manager, rpc_config, event_log, cycles_ledger)) =
         ic_cdk::storage::stable_restore::<(
             Option<String>,
             Option<String>,
@@ -33,6 +47,11 @@ pub fn restore_from_stable_storage() {
             Option<String>,
             HashMap<String, PoolData>,
             Vec<SwapResponse>,
+            Option<TokenRegistry>,
+            Option<NonceManager>,
+            Option<RpcConfig<RpcChain>>,
+            Option<EventLog>,
+            Option<CyclesLedger>,
         )>()
     {
         CACHED_ICP_ADDRESS.with(|addr| *addr.borrow_mut() = icp_addr);
@@ -40,7 +59,29 @@ pub fn restore_from_stable_storage() {
         CACHED_BSC_ADDRESS.with(|addr| *addr.borrow_mut() = bsc_addr);
         CACHED_SOLANA_ADDRESS.with(|addr| *addr.borrow_mut() = solana_addr);
         POOL_DATA.with(|data| *data.borrow_mut() = pools);
+        // Swaps recorded before the event log existed are replayed into it once.
+        let event_log = event_log.unwrap_or_else(|| {
+            let mut log = EventLog::default();
+            for swap in &swap_history {
+                log.append(swap.to_event(), swap.timestamp);
+            }
+            log
+        });
+        events::import_events(event_log);
         SWAP_HISTORY.with(|history| *history.borrow_mut() = swap_history);
+        if let Some(token_registry) = token_registry {
+            registry::import_registry(token_registry);
+        }
+        if let Some(nonce_manager) = nonce_manager {
+            nonces::import_nonces(nonce_manager);
+        }
+        if let Some(rpc_config) = rpc_config {
+            providers::import_rpc_config(rpc_config);
+        }
+        if let Some(cycles_ledger) = cycles_ledger {
+            cycles::import_cycles(cycles_ledger);
+        }
+        certified::certify_all();
     }
 }
 
@@ -55,11 +96,17 @@ pub fn update_pool_balances(
         if let Some(pool) = pools.get_mut(pool_id) {
             pool.token_a_balance = token_a_balance;
             pool.token_b_balance = token_b_balance;
-            Ok(format!("Pool {} balances updated successfully", pool_id))
+            Ok(())
         } else {
             Err(format!("Pool with id {} not found", pool_id))
         }
-    })
+    })?;
+    certified::certify_pool(pool_id);
+    Ok(format!("Pool {} balances updated successfully", pool_id))
+}
+
+pub fn get_pool(pool_id: &str) -> Option<PoolData> {
+    POOL_DATA.with(|data| data.borrow().get(pool_id).cloned())
 }
 
 pub fn get_all_pools() -> Vec<PoolData> {
@@ -77,10 +124,47 @@ pub fn initialize_pools(pools: Vec<PoolData>) {
     });
 }
 
+pub fn ensure_pool(pool: PoolData) {
+    let pool_id = pool.pool_id.clone();
+    POOL_DATA.with(|data| {
+        data.borrow_mut().entry(pool_id.clone()).or_insert(pool);
+    });
+    certified::certify_pool(&pool_id);
+}
+
+/// A pool can only be dropped once both reserves are gone; otherwise the
+/// funds it accounts for would no longer be recorded anywhere.
+pub fn ensure_pool_empty(pool_id: &str) -> Result<(), String> {
+    let Some(pool) = get_pool(pool_id) else {
+        return Ok(());
+    };
+
+    for (token, balance) in [(&pool.token_a, &pool.token_a_balance), (&pool.token_b, &pool.token_b_balance)] {
+        let is_empty = Decimal::from_str(balance).is_ok_and(|balance| balance.is_zero());
+        if !is_empty {
+            return Err(format!(
+                "Pool {} still holds {} {}; withdraw its liquidity before removing the pair",
+                pool_id, balance, token
+            ));
+        }
+    }
+    Ok(())
+}
+
+pub fn remove_pool(pool_id: &str) {
+    POOL_DATA.with(|data| {
+        data.borrow_mut().remove(pool_id);
+    });
+    certified::certify_pool(pool_id);
+}
+
 pub fn add_swap_to_history(swap: SwapResponse) {
+    let event = swap.to_event();
+    certified::append_swap(&swap);
     SWAP_HISTORY.with(|history| {
         history.borrow_mut().push(swap);
     });
+    events::record_event(event);
 }
 
 pub fn get_swap_history() -> Vec<SwapResponse> {
@@ -135,8 +219,8 @@ pub async fn get_cached_base_address() -> String {
         return address;
     }
 
-    let wallet = crate::wallet::base_wallet::BaseWallet::new_canister_wallet().await;
-    let address = format!("{:?}", wallet.base_address());
+    let wallet = crate::wallet::canister_evm_wallet().await;
+    let address = format!("{:?}", wallet.address());
 
     CACHED_BASE_ADDRESS.with(|addr| *addr.borrow_mut() = Some(address.clone()));
 
@@ -149,8 +233,8 @@ pub async fn get_cached_bsc_address() -> String {
         return address;
     }
 
-    let wallet = crate::wallet::bsc_wallet::BscWallet::new_canister_wallet().await;
-    let address = format!("{:?}", wallet.bsc_address());
+    let wallet = crate::wallet::canister_evm_wallet().await;
+    let address = format!("{:?}", wallet.address());
 
     CACHED_BSC_ADDRESS.with(|addr| *addr.borrow_mut() = Some(address.clone()));
 
@@ -163,7 +247,7 @@ pub async fn get_cached_solana_address() -> String {
         return address;
     }
 
-    let wallet = crate::wallet::solana_wallet::SolanaWallet::new_canister_wallet().await;
+    let wallet = crate::wallet::canister_solana_wallet().await;
     let address = wallet.solana_address();
 
     CACHED_SOLANA_ADDRESS.with(|addr| *addr.borrow_mut() = Some(address.clone()));
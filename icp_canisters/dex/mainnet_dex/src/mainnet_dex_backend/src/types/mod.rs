@@ -1,4 +1,5 @@
 pub mod network;
 pub mod pool;
+pub mod registry;
 pub mod swap;
 pub mod token;
\ No newline at end of file
@@ -1,3 +1,4 @@
+use bit10_core::providers::RpcProvider;
 use candid::{CandidType, Deserialize};
 use ic_cdk::api::management_canister::ecdsa::{EcdsaCurve, EcdsaKeyId};
 
@@ -34,17 +35,18 @@ pub enum SolanaNetwork {
 }
 
 impl SolanaNetwork {
-    pub fn rpc_url(&self) -> String {
+    pub fn rpc_providers(&self) -> Vec<RpcProvider> {
         match self {
-            SolanaNetwork::Mainnet => {
-                "https://mainnet.helius-rpc.com/?api-key=hzggdhfbxcnvcvb".to_string()
-            }
-            SolanaNetwork::Devnet => {
-                format!(
-                    "https://rpc.ankr.com/solana_devnet/{}",
-                    crate::utils::constants::ANKR_API_KEY
-                )
-            }
+            SolanaNetwork::Mainnet => vec![
+                RpcProvider::new("solana", "https://api.mainnet-beta.solana.com"),
+                RpcProvider::new("publicnode", "https://solana-rpc.publicnode.com"),
+                RpcProvider::new("helius", "https://mainnet.helius-rpc.com/?api-key={api_key}"),
+            ],
+            SolanaNetwork::Devnet => vec![
+                RpcProvider::new("solana", "https://api.devnet.solana.com"),
+                RpcProvider::new("drpc", "https://solana-devnet.drpc.org"),
+                RpcProvider::new("ankr", "https://rpc.ankr.com/solana_devnet/{api_key}"),
+            ],
         }
     }
 }
@@ -65,6 +67,14 @@ impl BscNetwork {
     }
 }
 
+/// Chains the canister sends JSON-RPC requests to.
+#[derive(CandidType, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum RpcChain {
+    Base,
+    Bsc,
+    Solana,
+}
+
 #[derive(CandidType, Deserialize, Debug, Default, PartialEq, Eq, Clone, Copy)]
 pub enum Ed25519KeyName {
     #[default]
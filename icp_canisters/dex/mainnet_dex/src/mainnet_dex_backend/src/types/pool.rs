@@ -27,6 +27,22 @@ pub struct PoolInfo {
     pub token_b_balance: String,
 }
 
+impl From<PoolData> for PoolInfo {
+    fn from(p: PoolData) -> Self {
+        PoolInfo {
+            pool_id: p.pool_id,
+            token_a: p.token_a,
+            token_b: p.token_b,
+            token_a_address: p.token_a_address,
+            token_b_address: p.token_b_address,
+            token_a_chain: p.token_a_chain,
+            token_b_chain: p.token_b_chain,
+            token_a_balance: p.token_a_balance,
+            token_b_balance: p.token_b_balance,
+        }
+    }
+}
+
 #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
 pub struct PoolsResponse {
     pub pools: Vec<PoolInfo>,
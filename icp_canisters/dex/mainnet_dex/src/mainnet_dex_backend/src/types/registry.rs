@@ -0,0 +1,19 @@
+use crate::types::token::{Pair, Token};
+use candid::{CandidType, Deserialize, Principal};
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub enum RegistryChange {
+    TokenAdded(Token),
+    TokenUpdated(Token),
+    TokenActiveSet { token_id: String, is_active: bool },
+    PairAdded(Pair),
+    PairRemoved { pool_id: String },
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct RegistryEvent {
+    pub version: u64,
+    pub timestamp: u64,
+    pub caller: Principal,
+    pub change: RegistryChange,
+}
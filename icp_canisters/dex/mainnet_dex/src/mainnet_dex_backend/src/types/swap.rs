@@ -1,3 +1,5 @@
+use bit10_core::errors::ApiError;
+use bit10_core::events::{Event, SwapEvent, SwapStatus, TokenTransfer};
 use candid::{CandidType, Deserialize};
 
 #[derive(CandidType, Deserialize, Clone, Debug)]
@@ -35,4 +37,25 @@ pub struct SwapResponse {
     pub timestamp: u64,
 }
 
-pub type SwapResult = Result<SwapResponse, String>;
+impl SwapResponse {
+    pub fn to_event(&self) -> Event {
+        let status = SwapStatus::from_history(&self.status);
+        // A reverted swap pays the input back on the source chain.
+        let (out_chain, out_token) = match status {
+            SwapStatus::Completed => (&self.destination_chain, &self.token_out_address),
+            SwapStatus::Reverted => (&self.source_chain, &self.token_in_address),
+        };
+        Event::Swap(SwapEvent {
+            swap_id: Some(self.swap_id.clone()),
+            pool_id: self.pool_id.clone(),
+            sender: self.tick_in_wallet_address.clone(),
+            recipient: self.tick_out_wallet_address.clone(),
+            token_in: TokenTransfer::new(&self.source_chain, &self.token_in_address, &self.amount_in, &self.tx_hash_in),
+            token_out: TokenTransfer::new(out_chain, out_token, &self.amount_out, &self.tx_hash_out),
+            slippage: Some(self.slippage.clone()),
+            status,
+        })
+    }
+}
+
+pub type SwapResult = Result<SwapResponse, ApiError>;
@@ -1,29 +1,36 @@
 use crate::types::network::{BaseNetwork, BscNetwork};
+use bit10_core::providers::RpcProvider;
 
-pub const ANKR_API_KEY: &str =
-    "xgdghfbjxcfnkmszdhbfhjbdfv";
-
-pub fn get_base_rpc_url(network: BaseNetwork) -> String {
+// Keys are set per chain with `set_rpc_api_key` and substituted for
+// `{api_key}`, so none are compiled in. A provider waiting for its key is
+// skipped, so the keyless endpoints come first.
+pub fn get_base_rpc_providers(network: BaseNetwork) -> Vec<RpcProvider> {
     match network {
-        BaseNetwork::Mainnet => {
-            format!("https://rpc.ankr.com/base/{}", ANKR_API_KEY)
-        }
-        BaseNetwork::Sepolia => format!(
-            "https://rpc.ankr.com/base_sepolia/{}",
-            ANKR_API_KEY
-        ),
+        BaseNetwork::Mainnet => vec![
+            RpcProvider::new("base", "https://mainnet.base.org"),
+            RpcProvider::new("publicnode", "https://base-rpc.publicnode.com"),
+            RpcProvider::new("ankr", "https://rpc.ankr.com/base/{api_key}"),
+        ],
+        BaseNetwork::Sepolia => vec![
+            RpcProvider::new("base", "https://sepolia.base.org"),
+            RpcProvider::new("publicnode", "https://base-sepolia-rpc.publicnode.com"),
+            RpcProvider::new("ankr", "https://rpc.ankr.com/base_sepolia/{api_key}"),
+        ],
     }
 }
 
-pub fn get_bsc_rpc_url(network: BscNetwork) -> String {
+pub fn get_bsc_rpc_providers(network: BscNetwork) -> Vec<RpcProvider> {
     match network {
-        BscNetwork::Mainnet => {
-            format!("https://rpc.ankr.com/bsc/{}", ANKR_API_KEY)
-        }
-        BscNetwork::Testnet => format!(
-            "https://rpc.ankr.com/bsc_testnet_chapel/{}",
-            ANKR_API_KEY
-        ),
+        BscNetwork::Mainnet => vec![
+            RpcProvider::new("bnbchain", "https://bsc-dataseed.bnbchain.org"),
+            RpcProvider::new("publicnode", "https://bsc-rpc.publicnode.com"),
+            RpcProvider::new("ankr", "https://rpc.ankr.com/bsc/{api_key}"),
+        ],
+        BscNetwork::Testnet => vec![
+            RpcProvider::new("bnbchain", "https://data-seed-prebsc-1-s1.bnbchain.org:8545"),
+            RpcProvider::new("publicnode", "https://bsc-testnet-rpc.publicnode.com"),
+            RpcProvider::new("ankr", "https://rpc.ankr.com/bsc_testnet_chapel/{api_key}"),
+        ],
     }
 }
 
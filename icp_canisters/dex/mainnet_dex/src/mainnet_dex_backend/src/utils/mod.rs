@@ -1,3 +1,3 @@
 pub mod constants;
-pub mod converters;
-pub mod validators;
\ No newline at end of file
+
+pub use bit10_core::utils::{converters, validators};
@@ -0,0 +1,44 @@
+use crate::utils::types::{SwapResponse, SwapResult};
+use bit10_core::deposits::{deposit_key, DepositRegistry, Settlement};
+use std::cell::RefCell;
+
+thread_local! {
+    pub static DEPOSIT_REGISTRY: RefCell<DepositRegistry> = RefCell::new(DepositRegistry::default());
+}
+
+pub fn export_deposits() -> DepositRegistry {
+    DEPOSIT_REGISTRY.with(|d| d.borrow().clone())
+}
+
+pub fn import_deposits(registry: DepositRegistry) {
+    DEPOSIT_REGISTRY.with(|d| *d.borrow_mut() = registry);
+}
+
+/// Swaps recorded before the registry existed are marked as processed so
+/// their deposits cannot be submitted again.
+pub fn registry_for_history(history: &[SwapResponse]) -> DepositRegistry {
+    let now = ic_cdk::api::time();
+    let mut registry = DepositRegistry::default();
+    for swap in history {
+        if let Ok(claim) = registry.claim(&key(&swap.tx_hash_in), now) {
+            registry.finish(&claim, true, now);
+        }
+    }
+    registry
+}
+
+/// Every swap is decoded from its own transaction's input, so a transaction
+/// carries one deposit and is keyed as a whole.
+pub fn key(tx_hash: &str) -> String {
+    deposit_key("bsc", tx_hash, None)
+}
+
+pub fn release(key: &str) -> bool {
+    DEPOSIT_REGISTRY.with(|d| d.borrow_mut().release(key))
+}
+
+impl Settlement for SwapResult {
+    fn succeeded(&self) -> bool {
+        matches!(self, SwapResult::Success(_))
+    }
+}
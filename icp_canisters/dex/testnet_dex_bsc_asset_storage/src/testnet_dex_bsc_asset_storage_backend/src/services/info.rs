@@ -1,5 +1,5 @@
 use crate::utils::token::{get_supported_pairs, get_supported_tokens};
-use crate::wallet::BscWallet;
+use crate::wallet::canister_evm_wallet;
 use crate::utils::rpc::{get_block_number_rpc, fetch_nonce_safe};
 use candid::Nat;
 
@@ -14,9 +14,7 @@ pub fn supported_pairs() -> String {
 }
 
 pub async fn bsc_address() -> String {
-    let canister_id = ic_cdk::id();
-    let wallet = BscWallet::new(canister_id).await;
-    wallet.bsc_address().to_string()
+    canister_evm_wallet().await.address().to_string()
 }
 
 pub async fn get_block_number() -> Nat {
@@ -26,7 +24,7 @@ pub async fn get_block_number() -> Nat {
 }
 
 pub async fn get_transaction_count_public(address: String) -> Nat {
-    fetch_nonce_safe(&address)
+    fetch_nonce_safe(&address, "pending")
         .await
         .unwrap_or_else(|e| ic_cdk::trap(&format!("Failed to get transaction count: {}", e)))
         .into()
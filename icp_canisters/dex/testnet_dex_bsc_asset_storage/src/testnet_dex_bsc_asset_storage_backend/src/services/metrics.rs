@@ -0,0 +1,60 @@
+use crate::services::{cycles, events, info};
+use crate::state::read_state;
+use crate::utils::rpc::{estimate_transaction_fees_bsc, fetch_balance_safe};
+use bit10_core::metrics::{
+    self, GasMonitor, HealthReport, HttpRequest, HttpResponse, MetricsEncoder, PriceFeedClock,
+    DEFAULT_MAX_FEED_AGE_NS,
+};
+use std::cell::RefCell;
+
+thread_local! {
+    static PRICE_FEEDS: RefCell<PriceFeedClock> = RefCell::new(PriceFeedClock::default());
+    static GAS: RefCell<GasMonitor> = RefCell::new(GasMonitor::default());
+}
+
+pub fn record_price_feed(feed: &str, succeeded: bool) {
+    PRICE_FEEDS.with(|f| f.borrow_mut().record(feed, succeeded, ic_cdk::api::time()));
+}
+
+/// Swaps pay out from the canister's BSC wallet at the fixed fee estimate.
+pub async fn check_gas_balance() {
+    let address = info::bsc_address().await;
+    match fetch_balance_safe(&address).await {
+        Ok(balance) => {
+            let (gas_limit, max_fee_per_gas, _) = estimate_transaction_fees_bsc();
+            GAS.with(|g| g.borrow_mut().record("Bsc", balance, gas_limit * max_fee_per_gas, ic_cdk::api::time()));
+        }
+        Err(e) => ic_cdk::println!("Failed to read BSC gas balance: {}", e),
+    }
+}
+
+pub fn http_request(request: HttpRequest) -> HttpResponse {
+    match request.path() {
+        "/metrics" => encode_metrics().into_response(),
+        "/health" => health().into_response(),
+        _ => HttpResponse::not_found(),
+    }
+}
+
+fn encode_metrics() -> MetricsEncoder {
+    let mut encoder = MetricsEncoder::new();
+    metrics::encode_canister(
+        &mut encoder,
+        ic_cdk::api::canister_balance128(),
+        ic_cdk::api::stable::stable_size() * 65536,
+    );
+    events::encode_metrics(&mut encoder);
+    cycles::encode_metrics(&mut encoder);
+    let provider_health = read_state(|s| s.rpc_providers().health());
+    metrics::encode_provider_health(&mut encoder, &[("Bsc".to_string(), provider_health)]);
+    PRICE_FEEDS.with(|f| f.borrow().encode(&mut encoder));
+    GAS.with(|g| g.borrow().encode(&mut encoder));
+    encoder
+}
+
+fn health() -> HealthReport {
+    let mut report = HealthReport::new(ic_cdk::api::time());
+    PRICE_FEEDS.with(|f| f.borrow().check(&mut report, DEFAULT_MAX_FEED_AGE_NS));
+    GAS.with(|g| g.borrow().check(&mut report));
+    report
+}
@@ -1,2 +1,7 @@
+pub mod cycles;
+pub mod deposits;
+pub mod events;
 pub mod info;
+pub mod metrics;
+pub mod nonces;
 pub mod swap;
\ No newline at end of file
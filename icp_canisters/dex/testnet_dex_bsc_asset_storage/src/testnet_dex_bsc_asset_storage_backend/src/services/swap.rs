@@ -1,3 +1,4 @@
+use crate::services::{deposits, events, nonces};
 use crate::state::read_state;
 use crate::utils::constants::{EVM_RPC, TRANSFER_EVENT_SIGNATURE};
 use crate::utils::encoding::decode_create_transaction_args_from_input;
@@ -13,7 +14,9 @@ use crate::utils::types::{
     CreateTransactionArgs, RpcLog, RpcTransaction, RpcTransactionReceipt, SwapResponse, SwapResult,
     Token, TransactionData, TransactionResponse, InitArg, string_to_alloy_address,
 };
-use crate::wallet::BscWallet;
+use crate::wallet::canister_evm_wallet;
+use bit10_core::deposits::{with_deposit_claim, ClaimedDeposit};
+use bit10_core::errors::ApiError;
 use alloy_consensus::{SignableTransaction, TxEip1559, TxLegacy, TxEnvelope};
 use alloy_eips::eip2718::Encodable2718;
 use alloy_primitives::{hex, Address as AlloyAddress, Signature, U256};
@@ -28,6 +31,11 @@ thread_local! {
     pub static SWAP_HISTORY: RefCell<Vec<SwapResponse>> = RefCell::new(Vec::new());
 }
 
+fn record_swap(swap_response: &SwapResponse) {
+    events::record_event(swap_response.to_event());
+    SWAP_HISTORY.with(|h| h.borrow_mut().push(swap_response.clone()));
+}
+
 pub async fn create_transaction(
     args: CreateTransactionArgs,
 ) -> Result<TransactionResponse, String> {
@@ -71,10 +79,10 @@ pub async fn create_transaction(
             )
         })?;
 
-    let is_valid_pair = (token_in.token_id == pair.token_a_token_id
-        && token_out.token_id == pair.token_b_token_id)
-        || (token_in.token_id == pair.token_b_token_id
-            && token_out.token_id == pair.token_a_token_id);
+    let is_valid_pair = (token_in.has_id(&pair.token_a_token_id)
+        && token_out.has_id(&pair.token_b_token_id))
+        || (token_in.has_id(&pair.token_b_token_id)
+            && token_out.has_id(&pair.token_a_token_id));
 
     if !is_valid_pair {
         return Err("Token pair mismatch".to_string());
@@ -84,7 +92,7 @@ pub async fn create_transaction(
         return Err("Swap type mismatch".to_string());
     }
 
-    let canister_address_str = BscWallet::new_canister_wallet().await.bsc_address().to_string();
+    let canister_address_str = canister_evm_wallet().await.address().to_string();
     let canister_address_ic_address = ic_ethereum_types::Address::from_str(&canister_address_str)
         .map_err(|e| format!("Invalid canister address: {}", e))?;
 
@@ -149,16 +157,18 @@ pub async fn create_transaction(
 pub async fn verify_and_swap(transaction_hash: String) -> SwapResult {
     let transaction_hash = transaction_hash.to_lowercase();
 
-    let already_exists = SWAP_HISTORY.with(|h| {
-        h.borrow().iter().any(|swap| {
-            swap.tx_hash_in == transaction_hash || swap.tx_hash_out == transaction_hash
-        })
-    });
-    if already_exists {
-        return SwapResult::Error("Transaction already processed".to_string());
-    }
+    let key = deposits::key(&transaction_hash);
+    with_deposit_claim(&deposits::DEPOSIT_REGISTRY, &key, |claim| async move {
+        settle_swap(transaction_hash, &claim).await
+    })
+    .await
+    .unwrap_or_else(|e| SwapResult::Error(e.into()))
+}
 
-    let canister_address_str = BscWallet::new_canister_wallet().await.bsc_address().to_string();
+// Runs with the deposit claimed. Errors returned before the payout starts
+// release the claim so the user can retry.
+async fn settle_swap(transaction_hash: String, claim: &ClaimedDeposit) -> SwapResult {
+    let canister_address_str = canister_evm_wallet().await.address().to_string();
     let canister_address_bytes =
         hex::decode(canister_address_str.strip_prefix("0x").unwrap_or_default())
             .map_err(|_| "Invalid canister address hex".to_string())
@@ -166,7 +176,7 @@ pub async fn verify_and_swap(transaction_hash: String) -> SwapResult {
             
     let receipt: RpcTransactionReceipt = match get_transaction_receipt_rpc(&transaction_hash).await {
         Ok(r) => r,
-        Err(e) => return SwapResult::Error(format!("Failed to fetch transaction receipt: {}", e)),
+        Err(e) => return SwapResult::Error(ApiError::chain_unavailable("BSC", e)),
     };
 
     if receipt.status != "0x1" {
@@ -245,13 +255,13 @@ pub async fn verify_and_swap(transaction_hash: String) -> SwapResult {
                 ic_cdk::api::time(),
             )
         };
-        SWAP_HISTORY.with(|h| h.borrow_mut().push(swap_response_for_revert.clone()));
+        record_swap(&swap_response_for_revert);
         return SwapResult::Success(swap_response_for_revert);
     }
 
     let tx: RpcTransaction = match get_transaction_by_hash_rpc(&transaction_hash).await {
         Ok(t) => t,
-        Err(e) => return SwapResult::Error(format!("Failed to fetch transaction details: {}", e)),
+        Err(e) => return SwapResult::Error(ApiError::chain_unavailable("BSC", e)),
     };
 
     let mut is_valid_transfer_to_canister = false;
@@ -300,10 +310,10 @@ pub async fn verify_and_swap(transaction_hash: String) -> SwapResult {
     }
 
     if !is_valid_transfer_to_canister {
-        return SwapResult::Error(
-            "No valid BNB or BEP20 transfer to the canister was found in the transaction."
-                .to_string(),
-        );
+        return SwapResult::Error(ApiError::deposit_invalid(
+            &transaction_hash,
+            "No valid BNB or BEP20 transfer to the canister was found in the transaction.",
+        ));
     }
 
     match execute_swap_or_refund_with_response(
@@ -313,14 +323,15 @@ pub async fn verify_and_swap(transaction_hash: String) -> SwapResult {
         &transaction_hash,
         &receipt.from,
         is_erc20_transfer,
+        claim,
     )
     .await
     {
         Ok(swap_response) => {
-            SWAP_HISTORY.with(|h| h.borrow_mut().push(swap_response.clone()));
+            record_swap(&swap_response);
             SwapResult::Success(swap_response)
         }
-        Err(e) => SwapResult::Error(e),
+        Err(e) => SwapResult::Error(e.into()),
     }
 }
 
@@ -331,6 +342,7 @@ async fn execute_swap_or_refund_with_response(
     tx_hash_in: &str,
     from_address: &str,
     is_erc20_transfer: bool,
+    claim: &ClaimedDeposit,
 ) -> Result<SwapResponse, String> {
     let decoded_args = decode_create_transaction_args_from_input(tx_input, is_erc20_transfer)?;
 
@@ -363,8 +375,8 @@ async fn execute_swap_or_refund_with_response(
 
     let current_timestamp = ic_cdk::api::time();
 
-    let price_in = get_token_price(&token_in.price_feed_id).await?;
-    let price_out = get_token_price(&token_out.price_feed_id).await?;
+    let price_in = get_token_price(token_in.feed_id()?).await?;
+    let price_out = get_token_price(token_out.feed_id()?).await?;
 
     let usd_sent = base_amount.to_f64().unwrap_or(0.0) * price_in;
     let calculated_tick_out_decimal = Decimal::from_f64(usd_sent / price_out)
@@ -386,6 +398,8 @@ async fn execute_swap_or_refund_with_response(
     let mut final_status = "reverted".to_string();
     let mut final_amount_out = human_readable_amount.to_string();
 
+    claim.start_settlement()?;
+
     if !can_swap {
         ic_cdk::println!("❌ SWAP REVERTED: Slippage tolerance exceeded!");
         ic_cdk::println!("   - Slippage Tolerance: {}%", slippage_percent);
@@ -469,8 +483,90 @@ async fn execute_swap_or_refund_with_response(
     ))
 }
 
+/// Hands out the next nonce for the canister wallet. The first call seeds the
+/// counter from the `pending` transaction count; later payouts reserve from
+/// canister state and never race each other for the same nonce.
+async fn reserve_nonce(nonce_key: &str, address: &str) -> Result<u64, String> {
+    if !nonces::is_tracked(nonce_key) {
+        let chain_nonce = fetch_nonce_safe(address, "pending").await?;
+        nonces::track(nonce_key, chain_nonce);
+    }
+    nonces::reserve(nonce_key)
+}
+
+async fn submit_transaction_bsc(transaction: TxEip1559, nonce_key: &str) -> Result<String, String> {
+    let nonce = transaction.nonce;
+    let max_fee_per_gas = transaction.max_fee_per_gas;
+    let max_priority_fee_per_gas = transaction.max_priority_fee_per_gas;
+
+    match sign_and_send_raw_transaction_bsc(transaction).await {
+        Ok(tx_hash) => {
+            nonces::mark_submitted(nonce_key, nonce, tx_hash.clone(), max_fee_per_gas, max_priority_fee_per_gas);
+            Ok(tx_hash)
+        }
+        Err(e) => {
+            nonces::mark_failed(nonce_key, nonce);
+            if e.contains("nonce too low") {
+                let address = canister_evm_wallet().await.address().to_string();
+                if let Ok(chain_nonce) = fetch_nonce_safe(&address, "pending").await {
+                    nonces::track(nonce_key, chain_nonce);
+                }
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Drops mined nonces and fills abandoned ones with zero-value self
+/// transfers, so a failed payout does not block the ones queued behind it.
+pub async fn reconcile_nonces() -> Result<(), String> {
+    let canister_address = canister_evm_wallet().await.address().to_string();
+    let chain_id = read_state(|s| s.bsc_network().bsc_chain_id());
+    let nonce_key = nonces::nonce_key(chain_id, &canister_address);
+
+    if !nonces::is_tracked(&nonce_key) {
+        return Ok(());
+    }
+
+    let confirmed_nonce = fetch_nonce_safe(&canister_address, "latest").await?;
+    let report = nonces::reconcile(&nonce_key, confirmed_nonce);
+
+    for stuck in &report.stuck {
+        ic_cdk::println!(
+            "BSC transaction {:?} with nonce {} is still pending",
+            stuck.tx_hash,
+            stuck.nonce
+        );
+    }
+
+    let own_address = string_to_alloy_address(&canister_address)?;
+    let (_, max_fee_per_gas, max_priority_fee_per_gas) = estimate_transaction_fees_bsc();
+    for nonce in report.gaps {
+        nonces::mark_cancel(&nonce_key, nonce);
+
+        let transaction = TxEip1559 {
+            chain_id,
+            nonce,
+            gas_limit: 21_000,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            to: alloy_primitives::TxKind::Call(own_address),
+            value: U256::ZERO,
+            access_list: Default::default(),
+            input: Default::default(),
+        };
+
+        match submit_transaction_bsc(transaction, &nonce_key).await {
+            Ok(tx_hash) => ic_cdk::println!("Filled BSC nonce gap {} with {}", nonce, tx_hash),
+            Err(e) => ic_cdk::println!("Failed to fill BSC nonce gap {}: {}", nonce, e),
+        }
+    }
+
+    Ok(())
+}
+
 async fn sign_and_send_raw_transaction_bsc(transaction: TxEip1559) -> Result<String, String> {
-    let wallet = BscWallet::new_canister_wallet().await;
+    let wallet = canister_evm_wallet().await;
     let tx_hash_pre_sign = transaction.signature_hash().0;
 
     let (raw_signature, recovery_id) = wallet.sign_with_ecdsa(tx_hash_pre_sign).await;
@@ -488,63 +584,70 @@ async fn sign_and_send_raw_transaction_bsc(transaction: TxEip1559) -> Result<Str
         raw_transaction_hash
     );
 
-    let bnb_rpc_service = RpcService::Custom(RpcApi {
-        url: read_state(|s| s.bsc_rpc_url()).to_string(),
-        headers: Some(vec![
-            ic_cdk::api::management_canister::http_request::HttpHeader {
-                name: "x-api-key".to_string(),
-                value: crate::utils::constants::TATUM_API_KEY.to_string(),
-            },
-        ]),
-    });
-
     let json_request = format!(
         r#"{{"jsonrpc": "2.0", "method": "eth_sendRawTransaction", "params": ["{}"], "id": 1}}"#,
         raw_transaction_hex
     );
 
     let max_response_size_bytes = 2000_u64;
-    let num_cycles = 5_000_000_000u128;
-
-    let (response_result,) = EVM_RPC
-        .request(bnb_rpc_service, json_request, max_response_size_bytes, num_cycles)
-        .await
-        .map_err(|e| format!("EVM_RPC canister call failed: {:?}", e))?;
-
-    match response_result {
-        RequestResult::Ok(tx_result_json_str) => {
-            let rpc_response: serde_json::Value = serde_json::from_str(&tx_result_json_str)
-                .map_err(|e| format!("Failed to parse EVM_RPC response: {}", e))?;
-
-            if let Some(error) = rpc_response.get("error") {
-                if let Some(message) = error.get("message").and_then(|m| m.as_str()) {
-                    if message.contains("already known") ||
-                       message.contains("ALREADY_EXISTS") ||
-                       message.contains("replacement transaction underpriced") {
-                        ic_cdk::println!("Transaction already known/exists: {}. Returning its hash.", message);
-                        return Ok(format!("0x{:x}", raw_transaction_hash));
-                    }
-                }
-                return Err(format!("RPC error sending BSC transaction: {}", error));
+    let num_cycles = bit10_core::cycles::evm_rpc_request_cost(json_request.len() as u64, max_response_size_bytes, 1);
+
+    // Resending the same signed bytes through another node is harmless, so
+    // the broadcast moves on whenever a provider cannot be reached.
+    let mut last_error = "No RPC provider can be called".to_string();
+    let mut tx_result_json_str = None;
+    for provider in read_state(|s| s.rpc_providers()).callable() {
+        let bnb_rpc_service = RpcService::Custom(RpcApi {
+            url: provider.resolved_url(),
+            headers: Some(
+                provider
+                    .resolved_headers()
+                    .into_iter()
+                    .map(|(name, value)| ic_cdk::api::management_canister::http_request::HttpHeader { name, value })
+                    .collect(),
+            ),
+        });
+        match EVM_RPC
+            .request(bnb_rpc_service, json_request.clone(), max_response_size_bytes, num_cycles)
+            .await
+        {
+            Ok((RequestResult::Ok(body),)) => {
+                tx_result_json_str = Some(body);
+                break;
             }
-
-            rpc_response.get("result")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string())
-                .ok_or_else(|| format!("No transaction hash in BSC RPC response. Raw response: {}", tx_result_json_str))
+            Ok((RequestResult::Err(e),)) => last_error = format!("{}: {:?}", provider.name, e),
+            Err(e) => last_error = format!("{}: EVM_RPC canister call failed: {:?}", provider.name, e),
         }
-        RequestResult::Err(e) => {
-            Err(format!("EVM_RPC returned an error result: {:?}", e))
+    }
+    let tx_result_json_str = tx_result_json_str.ok_or(last_error)?;
+    let rpc_response: serde_json::Value = serde_json::from_str(&tx_result_json_str)
+        .map_err(|e| format!("Failed to parse EVM_RPC response: {}", e))?;
+
+    if let Some(error) = rpc_response.get("error") {
+        if let Some(message) = error.get("message").and_then(|m| m.as_str()) {
+            if message.contains("already known") ||
+               message.contains("ALREADY_EXISTS") ||
+               message.contains("replacement transaction underpriced") {
+                ic_cdk::println!("Transaction already known/exists: {}. Returning its hash.", message);
+                return Ok(format!("0x{:x}", raw_transaction_hash));
+            }
         }
+        return Err(format!("RPC error sending BSC transaction: {}", error));
     }
+
+    rpc_response.get("result")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+        .ok_or_else(|| format!("No transaction hash in BSC RPC response. Raw response: {}", tx_result_json_str))
 }
 
 async fn send_native_bnb_safe(to: AlloyAddress, amount: U256) -> Result<String, String> {
-    let canister_address = BscWallet::new_canister_wallet().await.bsc_address().to_string();
+    let canister_address = canister_evm_wallet().await.address().to_string();
 
-    let nonce = fetch_nonce_safe(&canister_address).await?;
     let (gas_limit, max_fee_per_gas, max_priority_fee_per_gas) = estimate_transaction_fees_bsc();
     let chain_id = read_state(|s| s.bsc_network().bsc_chain_id());
+    let nonce_key = nonces::nonce_key(chain_id, &canister_address);
+    let nonce = reserve_nonce(&nonce_key, &canister_address).await?;
 
     let transaction = TxEip1559 {
         chain_id,
@@ -558,7 +661,7 @@ async fn send_native_bnb_safe(to: AlloyAddress, amount: U256) -> Result<String,
         input: Default::default(),
     };
 
-    sign_and_send_raw_transaction_bsc(transaction).await
+    submit_transaction_bsc(transaction, &nonce_key).await
 }
 
 async fn send_bep20_token_safe(
@@ -566,11 +669,12 @@ async fn send_bep20_token_safe(
     to: AlloyAddress,
     amount: U256,
 ) -> Result<String, String> {
-    let canister_address = BscWallet::new_canister_wallet().await.bsc_address().to_string();
+    let canister_address = canister_evm_wallet().await.address().to_string();
 
-    let nonce = fetch_nonce_safe(&canister_address).await?;
     let (gas_limit, max_fee_per_gas, max_priority_fee_per_gas) = estimate_transaction_fees_bsc();
     let chain_id = read_state(|s| s.bsc_network().bsc_chain_id());
+    let nonce_key = nonces::nonce_key(chain_id, &canister_address);
+    let nonce = reserve_nonce(&nonce_key, &canister_address).await?;
 
     let method_id = hex::decode("a9059cbb").map_err(|_| "Failed to decode method ID for BEP20")?;
     let mut encoded = method_id;
@@ -595,5 +699,5 @@ async fn send_bep20_token_safe(
         input: encoded.into(),
     };
 
-    sign_and_send_raw_transaction_bsc(transaction).await
+    submit_transaction_bsc(transaction, &nonce_key).await
 }
\ No newline at end of file
@@ -1,5 +1,6 @@
 use crate::wallet::ecdsa::EcdsaPublicKey;
 use crate::utils::types::{BscNetwork, EcdsaKeyName, InitArg};
+use bit10_core::providers::{ProviderSet, RpcProvider};
 use ic_cdk::api::management_canister::ecdsa::{EcdsaCurve, EcdsaKeyId};
 use std::cell::RefCell;
 use std::ops::{Deref, DerefMut};
@@ -28,6 +29,7 @@ pub struct State {
     bsc_network: BscNetwork,
     ecdsa_key_name: EcdsaKeyName,
     ecdsa_public_key: Option<EcdsaPublicKey>,
+    rpc_providers: Option<ProviderSet>,
 }
 
 impl State {
@@ -39,8 +41,26 @@ impl State {
         self.bsc_network
     }
 
-    pub fn bsc_rpc_url(&self) -> &'static str {
-        self.bsc_network.rpc_url()
+    pub fn rpc_providers(&self) -> ProviderSet {
+        self.rpc_providers
+            .clone()
+            .unwrap_or_else(|| default_rpc_providers(self.bsc_network))
+    }
+
+    pub fn rpc_providers_mut(&mut self) -> &mut ProviderSet {
+        let bsc_network = self.bsc_network;
+        self.rpc_providers
+            .get_or_insert_with(|| default_rpc_providers(bsc_network))
+    }
+
+    /// `None` until a controller changes the defaults or a quorum read
+    /// starts tracking the default providers' health.
+    pub fn configured_rpc_providers(&self) -> Option<ProviderSet> {
+        self.rpc_providers.clone()
+    }
+
+    pub fn set_configured_rpc_providers(&mut self, rpc_providers: Option<ProviderSet>) {
+        self.rpc_providers = rpc_providers;
     }
 
     pub fn bsc_chain_id(&self) -> u64 {
@@ -48,6 +68,18 @@ impl State {
     }
 }
 
+fn default_rpc_providers(bsc_network: BscNetwork) -> ProviderSet {
+    let providers = match bsc_network {
+        BscNetwork::Testnet => vec![
+            RpcProvider::new("publicnode", "https://bsc-testnet-rpc.publicnode.com"),
+            RpcProvider::new("bnbchain", "https://data-seed-prebsc-1-s1.bnbchain.org:8545"),
+            RpcProvider::new("tatum", "https://bsc-testnet.gateway.tatum.io/")
+                .with_header("x-api-key", "{api_key}"),
+        ],
+    };
+    ProviderSet::new(providers, 2).expect("Invalid default BSC providers")
+}
+
 impl From<InitArg> for State {
     fn from(init_arg: InitArg) -> Self {
         State {
@@ -1,10 +1,12 @@
 use candid::Principal;
 
-pub const TATUM_API_KEY: &str = "<YOUR_API_KEY>";
-
 pub const EVM_RPC_CANISTER_ID: Principal =
     Principal::from_slice(b"\x00\x00\x00\x00\x02\x30\x00\xCC\x01\x01");
 
+/// The DEX router, `t2vfi-5aaaa-aaaap-qqbfa-cai`.
+pub const ROUTER_CANISTER_ID: Principal =
+    Principal::from_slice(b"\x00\x00\x00\x00\x01\xf0\x80\x4a\x01\x01");
+
 pub const PRICE_FEED_CANISTER_ID: Principal =
     Principal::from_slice("b\x00\x00\x00\x00\x01\xc0\xdd\x96\x01\x01")
         .expect("Invalid price feed canister principal");
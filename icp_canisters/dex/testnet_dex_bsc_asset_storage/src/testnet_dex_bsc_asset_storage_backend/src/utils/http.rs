@@ -4,12 +4,27 @@ use ic_cdk::api::management_canister::http_request::{
 };
 use candid::Nat;
 use num::ToPrimitive;
-use crate::utils::constants::TATUM_API_KEY;
-use crate::state::read_state;
+use bit10_core::providers::{json_rpc_body, normalize_json_rpc, RpcProvider};
+use bit10_core::transform::{canonical_body, ResponseShape};
+use crate::state::{mutate_state, read_state};
+
+/// Cycles the IC charges for `request`, from its size and response limit.
+fn outcall_cost(request: &CanisterHttpRequestArgument) -> u128 {
+    let bytes = bit10_core::cycles::request_bytes(
+        &request.url,
+        request.headers.iter().map(|h| (h.name.as_str(), h.value.as_str())),
+        request.body.as_deref(),
+        request
+            .transform
+            .as_ref()
+            .map(|t| (t.function.0.method.as_str(), t.context.as_slice())),
+    );
+    bit10_core::cycles::http_request_cost(bytes, request.max_response_bytes)
+}
 
 pub async fn make_robust_http_request(request: CanisterHttpRequestArgument) -> Result<Vec<u8>, String> {
     const MAX_RETRIES: u8 = 3;
-    let cycles: u128 = 50_000_000_000;
+    let cycles = outcall_cost(&request);
 
     for attempt in 0..MAX_RETRIES {
         match http_request(request.clone(), cycles).await {
@@ -37,18 +52,54 @@ pub async fn make_robust_http_request(request: CanisterHttpRequestArgument) -> R
     Err("Unexpected error in HTTP request loop".to_string())
 }
 
+/// Sends the request to one provider at a time, moving on only when the
+/// outcall fails. Nonces, fees and block heights go through here.
 pub async fn make_rpc_request(json_payload: String) -> Result<String, String> {
-    let transform_context = TransformContext::from_name("transform".to_string(), vec![]);
-    let rpc_url = read_state(|s| s.bsc_rpc_url());
+    let providers = read_state(|s| s.rpc_providers()).callable();
+    let mut errors = Vec::with_capacity(providers.len());
+    for provider in &providers {
+        match post_to_provider(provider, &json_payload).await {
+            Ok(body) => return Ok(body),
+            Err(e) => errors.push(format!("{}: {}", provider.name, e)),
+        }
+    }
+    Err(format!("Every RPC provider failed: {}", errors.join("; ")))
+}
+
+/// Sends the read to every provider and answers with the result a quorum of
+/// them agree on. Deposits are verified through here.
+pub async fn make_quorum_rpc_request(json_payload: String) -> Result<String, String> {
+    let providers = read_state(|s| s.rpc_providers()).callable();
+    let mut responses = Vec::with_capacity(providers.len());
+    for provider in &providers {
+        let response = post_to_provider(provider, &json_payload)
+            .await
+            .and_then(|body| normalize_json_rpc(&body));
+        responses.push((provider.name.clone(), response));
+    }
+    let result = mutate_state(|s| s.rpc_providers_mut().tally(responses, ic_cdk::api::time()))?;
+    Ok(json_rpc_body(&result))
+}
+
+async fn post_to_provider(provider: &RpcProvider, json_payload: &str) -> Result<String, String> {
+    let transform_context =
+        TransformContext::from_name("transform".to_string(), ResponseShape::for_payload(json_payload).to_context());
+
+    let mut headers = vec![
+        HttpHeader { name: "accept".to_string(), value: "application/json".to_string() },
+        HttpHeader { name: "content-type".to_string(), value: "application/json".to_string() },
+    ];
+    headers.extend(
+        provider
+            .resolved_headers()
+            .into_iter()
+            .map(|(name, value)| HttpHeader { name, value }),
+    );
 
     let request = CanisterHttpRequestArgument {
         method: HttpMethod::POST,
-        url: rpc_url.to_string(),
-        headers: vec![
-            HttpHeader { name: "accept".to_string(), value: "application/json".to_string() },
-            HttpHeader { name: "content-type".to_string(), value: "application/json".to_string() },
-            HttpHeader { name: "x-api-key".to_string(), value: TATUM_API_KEY.to_string() },
-        ],
+        url: provider.resolved_url(),
+        headers,
         body: Some(json_payload.as_bytes().to_vec()),
         max_response_bytes: Some(4_000),
         transform: Some(transform_context),
@@ -68,7 +119,7 @@ pub fn transform(raw: TransformArgs) -> HttpResponse {
     };
 
     if res.status == Nat::from(200u64) {
-        res.body = raw.response.body;
+        res.body = canonical_body(&raw.context, &raw.response.body);
     } else {
         ic_cdk::api::print(format!(
             "Received an error from the remote service: status {}, body: {}",
@@ -1,4 +1,4 @@
-use crate::utils::http::make_rpc_request;
+use crate::utils::http::{make_quorum_rpc_request, make_rpc_request};
 use crate::utils::types::{JsonRpcResponse, RpcTransaction, RpcTransactionReceipt};
 use candid::Nat;
 use num::BigUint;
@@ -25,10 +25,10 @@ pub async fn get_block_number_rpc() -> Result<Nat, String> {
     Ok(Nat(block_number))
 }
 
-pub async fn fetch_nonce_safe(address: &str) -> Result<u64, String> {
+pub async fn fetch_nonce_safe(address: &str, block_tag: &str) -> Result<u64, String> {
     let body = format!(
-        r#"{{"jsonrpc":"2.0","method":"eth_getTransactionCount","params":["{}", "pending"],"id":1}}"#,
-        address
+        r#"{{"jsonrpc":"2.0","method":"eth_getTransactionCount","params":["{}", "{}"],"id":1}}"#,
+        address, block_tag
     );
 
     let response_str = make_rpc_request(body).await?;
@@ -60,6 +60,25 @@ pub async fn fetch_gas_price_safe() -> Result<u128, String> {
         .map_err(|e| format!("Failed to parse hex gas price: {}", e))
 }
 
+pub async fn fetch_balance_safe(address: &str) -> Result<u128, String> {
+    let body = format!(
+        r#"{{"jsonrpc":"2.0","method":"eth_getBalance","params":["{}", "latest"],"id":1}}"#,
+        address
+    );
+
+    let response_str = make_rpc_request(body).await?;
+    let json: JsonRpcResponse<String> = serde_json::from_str(&response_str)
+        .map_err(|e| format!("Invalid JSON from BSC RPC for balance: {}", e))?;
+
+    if let Some(error) = json.error {
+        return Err(format!("RPC error fetching balance: {}", error));
+    }
+
+    let hex_balance = json.result.ok_or("eth_getBalance returned no result".to_string())?;
+    u128::from_str_radix(hex_balance.strip_prefix("0x").unwrap_or(&hex_balance), 16)
+        .map_err(|e| format!("Failed to parse hex balance: {}", e))
+}
+
 pub fn estimate_transaction_fees_bsc() -> (u128, u128, u128) {
     const GAS_LIMIT: u128 = 100_000;
     const MAX_FEE_PER_GAS: u128 = 10_000_000_000;
@@ -73,7 +92,7 @@ pub async fn get_transaction_receipt_rpc(transaction_hash: &str) -> Result<RpcTr
         transaction_hash
     );
 
-    let response_str = make_rpc_request(receipt_payload).await?;
+    let response_str = make_quorum_rpc_request(receipt_payload).await?;
     let json: JsonRpcResponse<RpcTransactionReceipt> = serde_json::from_str(&response_str)
         .map_err(|e| format!("Failed to parse RPC response for receipt: {}", e))?;
 
@@ -90,7 +109,7 @@ pub async fn get_transaction_by_hash_rpc(transaction_hash: &str) -> Result<RpcTr
         transaction_hash
     );
 
-    let response_str = make_rpc_request(tx_payload).await?;
+    let response_str = make_quorum_rpc_request(tx_payload).await?;
     let json: JsonRpcResponse<RpcTransaction> = serde_json::from_str(&response_str)
         .map_err(|e| format!("Failed to parse RPC response for transaction: {}", e))?;
 
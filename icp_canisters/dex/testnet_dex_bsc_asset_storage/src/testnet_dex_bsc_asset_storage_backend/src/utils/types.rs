@@ -2,6 +2,8 @@ use candid::{CandidType, Deserialize, Nat};
 use rust_decimal::Decimal;
 use serde::Deserialize as SerdeDeserialize;
 use alloy_primitives::U256;
+use bit10_core::errors::ApiError;
+use bit10_core::events::{Event, SwapEvent, SwapStatus, TokenTransfer};
 use std::str::FromStr;
 
 #[derive(CandidType, serde::Serialize, serde::Deserialize, Debug, Clone)]
@@ -32,30 +34,7 @@ pub struct TransactionResponse {
     pub transaction_data: TransactionData,
 }
 
-#[derive(CandidType, Deserialize, Debug, Clone, serde::Serialize)]
-pub struct Token {
-    pub token_id: String,
-    pub token_name: String,
-    pub token_symbol: String,
-    pub token_address: Option<String>,
-    pub token_chain: String,
-    pub token_decimals: u8,
-    pub is_native: bool,
-    pub is_active: bool,
-    pub price_feed_id: String,
-}
-
-#[derive(CandidType, Deserialize, Debug, Clone, serde::Serialize)]
-pub struct Pair {
-    pub pool_id: String,
-    pub token_a_symbol: String,
-    pub token_a_chain: String,
-    pub token_a_token_id: String,
-    pub token_b_symbol: String,
-    pub token_b_chain: String,
-    pub token_b_token_id: String,
-    pub pair_type: String,
-}
+pub use bit10_core::types::token::{Pair, Token};
 
 #[derive(CandidType, Deserialize, Debug)]
 pub struct PriceFeed {
@@ -124,12 +103,31 @@ impl SwapResponse {
             timestamp,
         }
     }
+
+    pub fn to_event(&self) -> Event {
+        let status = SwapStatus::from_history(&self.status);
+        // A reverted swap pays the input back on the source chain.
+        let (out_chain, out_token) = match status {
+            SwapStatus::Completed => (&self.destination_chain, &self.token_out_address),
+            SwapStatus::Reverted => (&self.source_chain, &self.token_in_address),
+        };
+        Event::Swap(SwapEvent {
+            swap_id: None,
+            pool_id: self.pool_id.clone(),
+            sender: self.tick_in_wallet_address.clone(),
+            recipient: self.tick_out_wallet_address.clone(),
+            token_in: TokenTransfer::new(&self.source_chain, &self.token_in_address, &self.amount_in, &self.tx_hash_in),
+            token_out: TokenTransfer::new(out_chain, out_token, &self.amount_out, &self.tx_hash_out),
+            slippage: Some(self.slippage.clone()),
+            status,
+        })
+    }
 }
 
 #[derive(CandidType, Deserialize, Debug, Clone)]
 pub enum SwapResult {
     Success(SwapResponse),
-    Error(String),
+    Error(ApiError),
 }
 
 #[derive(CandidType, Deserialize, Debug, Default, PartialEq, Eq)]
@@ -150,11 +148,6 @@ impl BscNetwork {
             BscNetwork::Testnet => 97,
         }
     }
-    pub fn rpc_url(&self) -> &'static str {
-        match self {
-            BscNetwork::Testnet => "https://bsc-testnet.gateway.tatum.io/",
-        }
-    }
 }
 
 
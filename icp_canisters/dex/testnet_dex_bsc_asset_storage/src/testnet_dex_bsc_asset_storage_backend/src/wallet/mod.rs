@@ -1,5 +1,17 @@
-pub mod bsc;
-pub mod ecdsa;
+use crate::state::{lazy_call_ecdsa_public_key, read_state};
+use candid::Principal;
 
-pub use bsc::BscWallet;
-pub use ecdsa::EcdsaPublicKey;
\ No newline at end of file
+pub use bit10_core::wallet::{ecdsa, evm_wallet};
+
+use evm_wallet::EvmWallet;
+
+/// The pool wallet has always been derived for the anonymous principal
+/// rather than being the root key, so it keeps that derivation to hold on to
+/// its address.
+pub async fn canister_evm_wallet() -> EvmWallet {
+    EvmWallet::new(
+        Principal::anonymous(),
+        &lazy_call_ecdsa_public_key().await,
+        read_state(|s| s.ecdsa_key_id()),
+    )
+}
@@ -3,13 +3,22 @@ mod wallet;
 mod utils;
 mod services;
 
-use state::{init_state, read_state, STATE};
-use utils::types::{InitArg, SwapResponse, CreateTransactionArgs, TransactionResponse, SwapResult, VerifyAndSwapArgs};
-use services::{info, swap};
+use state::{init_state, mutate_state, read_state, STATE};
+use utils::types::{ChainType, InitArg, SwapResponse, CreateTransactionArgs, TransactionResponse, SwapResult, VerifyAndSwapArgs};
+use services::{cycles, deposits, events, info, metrics, nonces, swap};
 use ic_cdk::{init, post_upgrade, query, update, pre_upgrade};
 use ic_cdk::api::management_canister::http_request::TransformArgs;
+use bit10_core::cycles::{ActionCycles, CyclesBudget, CyclesLedger};
+use bit10_core::deposits::DepositRegistry;
+use bit10_core::events::{ArchiveInfo, DataCertificate, EventLog, GetArchivesArgs, GetBlocksRequest, GetBlocksResult, SupportedBlockType};
+use bit10_core::metrics::{HttpRequest, HttpResponse};
+use bit10_core::nonce::NonceManager;
+use bit10_core::providers::{ProviderSetView, RpcConfig, RpcProvider};
 use std::cell::RefCell;
 
+const GAS_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(600);
+const NONCE_RECONCILE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(120);
+
 thread_local! {
     static SWAP_HISTORY_TEMP: RefCell<Vec<SwapResponse>> = RefCell::new(Vec::new());
 }
@@ -17,7 +26,12 @@ thread_local! {
 #[pre_upgrade]
 fn pre_upgrade_hook() {
     let history = swap::SWAP_HISTORY.with(|h| h.borrow().clone());
-    ic_cdk::storage::stable_save((history,))
+    let rpc_config = Some(read_state(|s| s.rpc_config()));
+    let event_log = Some(events::export_events());
+    let cycles_ledger = Some(cycles::export_cycles());
+    let nonce_manager = Some(nonces::export_nonces());
+    let deposit_registry = Some(deposits::export_deposits());
+    ic_cdk::storage::stable_save((history, rpc_config, event_log, cycles_ledger, nonce_manager, deposit_registry))
         .expect("Failed to save swap history to stable storage");
 }
 
@@ -26,9 +40,39 @@ async fn post_upgrade_hook(maybe_init: Option<InitArg>) {
     if let Some(init_arg) = maybe_init {
         init_state(init_arg);
     }
-    if let Ok((history,)) = ic_cdk::storage::stable_restore::<(Vec<SwapResponse>,)>() {
+    if let Ok((history, rpc_config, event_log, cycles_ledger, nonce_manager, deposit_registry)) = ic_cdk::storage::stable_restore::<(
+        Vec<SwapResponse>,
+        Option<RpcConfig<ChainType>>,
+        Option<EventLog>,
+        Option<CyclesLedger>,
+        Option<NonceManager>,
+        Option<DepositRegistry>,
+    )>() {
+        // Swaps recorded before the event log existed are replayed into it once.
+        let event_log = event_log.unwrap_or_else(|| {
+            let mut log = EventLog::default();
+            for swap in &history {
+                log.append(swap.to_event(), swap.timestamp);
+            }
+            log
+        });
+        events::import_events(event_log);
+        let deposit_registry = deposit_registry.unwrap_or_else(|| deposits::registry_for_history(&history));
+        deposits::import_deposits(deposit_registry);
         swap::SWAP_HISTORY.with(|h| *h.borrow_mut() = history);
+        if let Some(rpc_config) = rpc_config {
+            mutate_state(|s| s.set_rpc_config(rpc_config));
+        }
+        if let Some(cycles_ledger) = cycles_ledger {
+            cycles::import_cycles(cycles_ledger);
+        }
+        if let Some(nonce_manager) = nonce_manager {
+            nonces::import_nonces(nonce_manager);
+        }
     }
+    cycles::exempt_router();
+    start_gas_checks();
+    start_nonce_reconciliation();
 }
 
 #[init]
@@ -36,6 +80,17 @@ pub fn init(maybe_init: Option<InitArg>) {
     if let Some(init_arg) = maybe_init {
         init_state(init_arg)
     }
+    cycles::exempt_router();
+    start_gas_checks();
+    start_nonce_reconciliation();
+}
+
+fn start_nonce_reconciliation() {
+    ic_cdk_timers::set_timer_interval(NONCE_RECONCILE_INTERVAL, || ic_cdk::spawn(swap::reconcile_nonces()));
+}
+
+fn start_gas_checks() {
+    ic_cdk_timers::set_timer_interval(GAS_CHECK_INTERVAL, || ic_cdk::spawn(metrics::check_gas_balances()));
 }
 
 #[query]
@@ -76,6 +131,9 @@ async fn get_transaction_count_for_address(address: String) -> candid::Nat {
 
 #[update]
 async fn verify_and_swap(args: VerifyAndSwapArgs) -> SwapResult {
+    if let Err(err) = cycles::charge("swap", cycles::swap_cost()) {
+        return SwapResult::Error(err);
+    }
     swap::verify_and_swap(args).await
 }
 
@@ -89,6 +147,100 @@ fn get_swap_history_by_address(address: String) -> Vec<SwapResponse> {
     swap::get_swap_history_by_address(address)
 }
 
+#[query]
+fn icrc3_get_blocks(args: Vec<GetBlocksRequest>) -> GetBlocksResult {
+    events::get_blocks(args)
+}
+
+#[query]
+fn icrc3_get_tip_certificate() -> Option<DataCertificate> {
+    events::tip_certificate()
+}
+
+#[query]
+fn icrc3_get_archives(_args: GetArchivesArgs) -> Vec<ArchiveInfo> {
+    Vec::new()
+}
+
+#[query]
+fn icrc3_supported_block_types() -> Vec<SupportedBlockType> {
+    events::supported_block_types()
+}
+
+#[query]
+fn get_rpc_config() -> Result<Vec<(ChainType, ProviderSetView)>, String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can read the RPC config".to_string());
+    }
+
+    Ok(mutate_state(|s| s.rpc_config_view()))
+}
+
+#[update]
+fn set_rpc_providers(chain: ChainType, rpc_providers: Vec<RpcProvider>, quorum: u8) -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can set RPC providers".to_string());
+    }
+
+    mutate_state(|s| s.set_rpc_providers(chain, rpc_providers, quorum))
+}
+
+#[update]
+fn set_rpc_api_key(chain: ChainType, provider: String, api_key: Option<String>) -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can set RPC API keys".to_string());
+    }
+
+    mutate_state(|s| s.set_rpc_api_key(chain, &provider, api_key))
+}
+
+#[update]
+fn reset_rpc_providers(chain: ChainType) -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can reset RPC providers".to_string());
+    }
+
+    mutate_state(|s| s.reset_rpc_providers(chain));
+    Ok(())
+}
+
+#[update]
+fn release_deposit(key: String) -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can release deposits".to_string());
+    }
+
+    if deposits::release(&key) {
+        Ok(())
+    } else {
+        Err(format!("Deposit {} is not claimed", key))
+    }
+}
+
+#[query]
+fn get_cycles_budget() -> CyclesBudget {
+    cycles::get_budget()
+}
+
+#[update]
+fn set_cycles_budget(budget: CyclesBudget) -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can set the cycles budget".to_string());
+    }
+
+    cycles::set_budget(budget)
+}
+
+#[query]
+fn get_cycles_usage() -> Vec<ActionCycles> {
+    cycles::get_usage()
+}
+
+#[query]
+fn http_request(request: HttpRequest) -> HttpResponse {
+    metrics::http_request(request)
+}
+
 #[query]
 fn transform(raw: TransformArgs) -> ic_cdk::api::management_canister::http_request::HttpResponse {
     utils::http::transform(raw)
@@ -0,0 +1,54 @@
+use crate::utils::types::{ChainType, SwapResponse, SwapResult};
+use bit10_core::deposits::{deposit_key, DepositRegistry, Settlement};
+use std::cell::RefCell;
+
+thread_local! {
+    pub static DEPOSIT_REGISTRY: RefCell<DepositRegistry> = RefCell::new(DepositRegistry::default());
+}
+
+pub fn export_deposits() -> DepositRegistry {
+    DEPOSIT_REGISTRY.with(|d| d.borrow().clone())
+}
+
+pub fn import_deposits(registry: DepositRegistry) {
+    DEPOSIT_REGISTRY.with(|d| *d.borrow_mut() = registry);
+}
+
+/// Swaps recorded before the registry existed are marked as processed so
+/// their deposits cannot be submitted again. History stores the chain's
+/// display name, see `ChainType::name`.
+pub fn registry_for_history(history: &[SwapResponse]) -> DepositRegistry {
+    let now = ic_cdk::api::time();
+    let mut registry = DepositRegistry::default();
+    for swap in history {
+        let chain = match swap.source_chain.to_lowercase().as_str() {
+            "ethereum" => ChainType::Ethereum,
+            "binance smart chain" | "bsc" => ChainType::BSC,
+            _ => continue,
+        };
+        if let Ok(claim) = registry.claim(&key(&chain, &swap.tx_hash_in), now) {
+            registry.finish(&claim, true, now);
+        }
+    }
+    registry
+}
+
+/// Every swap is decoded from its own transaction's input, so a transaction
+/// carries one deposit and is keyed as a whole.
+pub fn key(chain: &ChainType, tx_hash: &str) -> String {
+    let chain = match chain {
+        ChainType::Ethereum => "ethereum",
+        ChainType::BSC => "bsc",
+    };
+    deposit_key(chain, tx_hash, None)
+}
+
+pub fn release(key: &str) -> bool {
+    DEPOSIT_REGISTRY.with(|d| d.borrow_mut().release(key))
+}
+
+impl Settlement for SwapResult {
+    fn succeeded(&self) -> bool {
+        matches!(self, SwapResult::Success(_))
+    }
+}
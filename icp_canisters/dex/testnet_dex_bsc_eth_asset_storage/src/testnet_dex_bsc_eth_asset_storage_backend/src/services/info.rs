@@ -1,5 +1,5 @@
 use crate::utils::token::{get_supported_pairs, get_supported_tokens};
-use crate::wallet::{BscWallet, EthereumWallet};
+use crate::wallet::canister_evm_wallet;
 use crate::utils::rpc::get_transaction_count_for_address_on_chain;
 use crate::utils::types::ChainType;
 use candid::Nat;
@@ -15,18 +15,15 @@ pub fn supported_pairs() -> String {
 }
 
 pub async fn bsc_address() -> String {
-    let canister_id = ic_cdk::id();
-    let wallet = BscWallet::new(canister_id).await;
-    wallet.bsc_address().to_string()
+    canister_evm_wallet().await.address().to_string()
 }
 
 pub async fn ethereum_address() -> String {
-    let wallet = EthereumWallet::new_canister_wallet().await;
-    wallet.ethereum_address().to_string()
+    canister_evm_wallet().await.address().to_string()
 }
 
 pub async fn get_transaction_count_public(address: String) -> Result<Nat, String> {
     let chain = ChainType::Ethereum;
-    let count = get_transaction_count_for_address_on_chain(address, chain).await?;
+    let count = get_transaction_count_for_address_on_chain(address, "pending", chain).await?;
     Ok(Nat::from(count))
 }
\ No newline at end of file
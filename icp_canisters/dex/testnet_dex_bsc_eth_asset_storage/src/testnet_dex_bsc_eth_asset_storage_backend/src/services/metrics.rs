@@ -0,0 +1,73 @@
+use crate::services::{cycles, events, info};
+use crate::utils::rpc::{estimate_transaction_fees_bsc, get_balance_on_chain, get_dynamic_fees};
+use crate::utils::types::ChainType;
+use bit10_core::metrics::{
+    self, GasMonitor, HealthReport, HttpRequest, HttpResponse, MetricsEncoder, PriceFeedClock,
+    DEFAULT_MAX_FEED_AGE_NS, EVM_TRANSFER_GAS,
+};
+use std::cell::RefCell;
+
+thread_local! {
+    static PRICE_FEEDS: RefCell<PriceFeedClock> = RefCell::new(PriceFeedClock::default());
+    static GAS: RefCell<GasMonitor> = RefCell::new(GasMonitor::default());
+}
+
+pub fn record_price_feed(feed: &str, succeeded: bool) {
+    PRICE_FEEDS.with(|f| f.borrow_mut().record(feed, succeeded, ic_cdk::api::time()));
+}
+
+fn record_gas(chain: ChainType, balance: u128, minimum: u128) {
+    GAS.with(|g| g.borrow_mut().record(chain.name(), balance, minimum, ic_cdk::api::time()));
+}
+
+/// Swaps pay out from the canister's wallet on either chain. Ethereum fees
+/// follow the gas price; BSC transfers use the fixed fee estimate.
+pub async fn check_gas_balances() {
+    let eth_address = info::ethereum_address().await;
+    match (get_balance_on_chain(&eth_address, ChainType::Ethereum).await, get_dynamic_fees().await) {
+        (Ok(balance), Ok((max_fee_per_gas, _))) => {
+            record_gas(ChainType::Ethereum, balance, max_fee_per_gas * EVM_TRANSFER_GAS)
+        }
+        (Err(e), _) | (_, Err(e)) => ic_cdk::println!("Failed to read Ethereum gas balance: {}", e),
+    }
+
+    let bsc_address = info::bsc_address().await;
+    match get_balance_on_chain(&bsc_address, ChainType::BSC).await {
+        Ok(balance) => {
+            let (gas_limit, max_fee_per_gas, _) = estimate_transaction_fees_bsc();
+            record_gas(ChainType::BSC, balance, gas_limit * max_fee_per_gas)
+        }
+        Err(e) => ic_cdk::println!("Failed to read BSC gas balance: {}", e),
+    }
+}
+
+pub fn http_request(request: HttpRequest) -> HttpResponse {
+    match request.path() {
+        "/metrics" => encode_metrics().into_response(),
+        "/health" => health().into_response(),
+        _ => HttpResponse::not_found(),
+    }
+}
+
+// Reads go to the primary provider without tallying, so there are no
+// per-provider outcall counts to report.
+fn encode_metrics() -> MetricsEncoder {
+    let mut encoder = MetricsEncoder::new();
+    metrics::encode_canister(
+        &mut encoder,
+        ic_cdk::api::canister_balance128(),
+        ic_cdk::api::stable::stable_size() * 65536,
+    );
+    events::encode_metrics(&mut encoder);
+    cycles::encode_metrics(&mut encoder);
+    PRICE_FEEDS.with(|f| f.borrow().encode(&mut encoder));
+    GAS.with(|g| g.borrow().encode(&mut encoder));
+    encoder
+}
+
+fn health() -> HealthReport {
+    let mut report = HealthReport::new(ic_cdk::api::time());
+    PRICE_FEEDS.with(|f| f.borrow().check(&mut report, DEFAULT_MAX_FEED_AGE_NS));
+    GAS.with(|g| g.borrow().check(&mut report));
+    report
+}
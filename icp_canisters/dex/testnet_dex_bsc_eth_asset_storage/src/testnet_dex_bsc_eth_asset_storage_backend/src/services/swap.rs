@@ -1,3 +1,4 @@
+use crate::services::{deposits, events, nonces};
 use crate::state::read_state;
 use crate::utils::token::{
     decimal_to_u256, extract_actual_amount_from_tx, get_supported_pairs, get_supported_tokens,
@@ -7,14 +8,17 @@ use crate::utils::types::{
     ChainType, CreateTransactionArgs, SwapResponse, SwapResult, Token, TransactionData,
     TransactionResponse, VerifyAndSwapArgs,
 };
-use crate::wallet::{BscWallet, EthereumWallet};
+use crate::wallet::canister_evm_wallet;
 use crate::utils::rpc::{
     estimate_transaction_fees_bsc, fetch_nonce_bsc_robust, fetch_nonce_eth, get_dynamic_fees,
-    get_transaction_by_hash_on_chain, get_transaction_receipt_on_chain,
+    get_transaction_by_hash_on_chain, get_transaction_count_for_address_on_chain,
+    get_transaction_receipt_on_chain,
 };
 use crate::utils::encoding::{decode_erc20_recipient, decode_transaction_data};
-use crate::utils::http::call_rpc_with_retry;
+use crate::utils::http::{call_rpc_with_retry, call_rpc_with_retry_on_chain};
 
+use bit10_core::deposits::{with_deposit_claim, ClaimedDeposit};
+use bit10_core::errors::ApiError;
 use alloy_consensus::{SignableTransaction, TxEip1559, TxEnvelope};
 use alloy_eips::eip2718::Encodable2718;
 use alloy_primitives::{hex, Address, Signature, U256};
@@ -34,6 +38,11 @@ thread_local! {
     pub static SWAP_HISTORY: RefCell<Vec<SwapResponse>> = RefCell::new(Vec::new());
 }
 
+fn record_swap(swap_response: &SwapResponse) {
+    events::record_event(swap_response.to_event());
+    SWAP_HISTORY.with(|h| h.borrow_mut().push(swap_response.clone()));
+}
+
 pub async fn create_transaction(args: CreateTransactionArgs) -> Result<TransactionResponse, String> {
     if args.token_in_address.eq_ignore_ascii_case(&args.token_out_address) {
         return Err("Token In and Token Out addresses cannot be the same.".to_string());
@@ -76,8 +85,8 @@ pub async fn create_transaction(args: CreateTransactionArgs) -> Result<Transacti
         })?;
 
     let is_valid_pair =
-        (token_in.token_id == pair.token_a_token_id && token_out.token_id == pair.token_b_token_id) ||
-        (token_in.token_id == pair.token_b_token_id && token_out.token_id == pair.token_a_token_id);
+        (token_in.has_id(&pair.token_a_token_id) && token_out.has_id(&pair.token_b_token_id)) ||
+        (token_in.has_id(&pair.token_b_token_id) && token_out.has_id(&pair.token_a_token_id));
 
     if !is_valid_pair {
         return Err("Token pair mismatch".to_string());
@@ -89,11 +98,11 @@ pub async fn create_transaction(args: CreateTransactionArgs) -> Result<Transacti
 
     let (canister_address_str, chain_id, tx_type, blockchain) = match args.source_chain.as_str() {
         "Binance Smart Chain" => {
-            let addr = BscWallet::new_canister_wallet().await.bsc_address().to_string();
+            let addr = canister_evm_wallet().await.address().to_string();
             (addr, 97u64, "legacy".to_string(), "BSC".to_string())
         }
         "Ethereum" => {
-            let addr = EthereumWallet::new_canister_wallet().await.ethereum_address().to_string();
+            let addr = canister_evm_wallet().await.address().to_string();
             let chain_id = read_state(|s| s.ethereum_network().chain_id());
             (addr, chain_id, "eip1559".to_string(), "Ethereum".to_string())
         }
@@ -185,45 +194,55 @@ pub async fn verify_and_swap(args: VerifyAndSwapArgs) -> SwapResult {
     let transaction_hash = args.transaction_hash.to_lowercase();
 
     if !transaction_hash.starts_with("0x") || transaction_hash.len() != 66 {
-        return SwapResult::Error("Invalid transaction hash format".to_string());
-    }
-
-    let already_exists = SWAP_HISTORY.with(|h| {
-        h.borrow().iter().any(|swap| {
-            swap.tx_hash_in == transaction_hash || swap.tx_hash_out == transaction_hash
-        })
-    });
-    if already_exists {
-        return SwapResult::Error("Transaction already processed".to_string());
+        return SwapResult::Error(ApiError::invalid_argument("transaction_hash", "Invalid transaction hash format"));
     }
 
     let chain = match args.source_chain.to_lowercase().as_str() {
         "ethereum" => ChainType::Ethereum,
         "binance smart chain" | "bsc" => ChainType::BSC,
         _ => {
-            return SwapResult::Error("Unsupported source chain".to_string());
+            return SwapResult::Error(ApiError::invalid_argument("source_chain", "Unsupported source chain"));
         }
     };
 
+    let key = deposits::key(&chain, &transaction_hash);
+    with_deposit_claim(&deposits::DEPOSIT_REGISTRY, &key, |claim| async move {
+        settle_swap(args, transaction_hash, chain, &claim).await
+    })
+    .await
+    .unwrap_or_else(|e| SwapResult::Error(e.into()))
+}
+
+// Runs with the deposit claimed. Errors returned before the payout starts
+// release the claim so the user can retry.
+async fn settle_swap(
+    args: VerifyAndSwapArgs,
+    transaction_hash: String,
+    chain: ChainType,
+    claim: &ClaimedDeposit,
+) -> SwapResult {
     let canister_address_str = match chain {
-        ChainType::Ethereum => EthereumWallet::new_canister_wallet().await.ethereum_address().to_string().to_lowercase(),
-        ChainType::BSC => BscWallet::new_canister_wallet().await.bsc_address().to_string().to_lowercase(),
+        ChainType::Ethereum => canister_evm_wallet().await.address().to_string().to_lowercase(),
+        ChainType::BSC => canister_evm_wallet().await.address().to_string().to_lowercase(),
     };
 
     let tx_data = match get_transaction_by_hash_on_chain(&args.transaction_hash, chain.clone()).await {
         Ok(tx) => tx,
         Err(e) => {
-            return SwapResult::Error(format!("Failed to get transaction data: {}", e));
+            return SwapResult::Error(ApiError::chain_unavailable(&args.source_chain, e));
         }
     };
 
     let tx_receipt = match get_transaction_receipt_on_chain(&args.transaction_hash, chain.clone()).await {
         Ok(receipt) => receipt,
-        Err(e) => return SwapResult::Error(format!("Failed to get transaction receipt: {}", e)),
+        Err(e) => return SwapResult::Error(ApiError::chain_unavailable(&args.source_chain, e)),
     };
 
     if tx_receipt.status != "0x1" {
-        return SwapResult::Error(format!("Source chain transaction failed with status: {}", tx_receipt.status));
+        return SwapResult::Error(ApiError::deposit_invalid(
+            &transaction_hash,
+            format!("Source chain transaction failed with status: {}", tx_receipt.status),
+        ));
     }
 
     let tx_to_address = tx_data.to
@@ -259,7 +278,10 @@ pub async fn verify_and_swap(args: VerifyAndSwapArgs) -> SwapResult {
 
 
     if !is_valid_transaction {
-        return SwapResult::Error("Transaction not directed to canister address or invalid format".to_string());
+        return SwapResult::Error(ApiError::deposit_invalid(
+            &transaction_hash,
+            "Transaction not directed to canister address or invalid format",
+        ));
     }
 
     if let Some(swap_args) = swap_args_option {
@@ -285,14 +307,17 @@ pub async fn verify_and_swap(args: VerifyAndSwapArgs) -> SwapResult {
 
         let (token_in_checked, token_out_checked) = match (token_in, token_out) {
             (Some(tin), Some(tout)) => (tin, tout),
-            (None, _) => return SwapResult::Error(format!("Token in not found: {}", swap_args.token_in_address)),
-            (_, None) => return SwapResult::Error(format!("Token out not found: {}", swap_args.token_out_address)),
+            (None, _) => return SwapResult::Error(ApiError::not_found(&swap_args.token_in_address)),
+            (_, None) => return SwapResult::Error(ApiError::not_found(&swap_args.token_out_address)),
         };
 
         let actual_amount_received = match extract_actual_amount_from_tx(&serde_json::to_value(&tx_data).unwrap_or_default(), &token_in_checked, &canister_address_str) {
             Ok(amount) => amount,
             Err(e) => {
-                return SwapResult::Error(format!("Failed to extract transaction amount: {}", e));
+                return SwapResult::Error(ApiError::deposit_invalid(
+                    &transaction_hash,
+                    format!("Failed to extract transaction amount: {}", e),
+                ));
             }
         };
 
@@ -304,9 +329,10 @@ pub async fn verify_and_swap(args: VerifyAndSwapArgs) -> SwapResult {
                     &swap_args,
                     &token_in_checked,
                     &token_out_checked,
+                    claim,
                 ).await {
                     Ok((hash, status, amount)) => (hash, status, amount),
-                    Err(e) => return SwapResult::Error(e),
+                    Err(e) => return SwapResult::Error(e.into()),
                 }
             }
             "binance smart chain" | "bsc" => {
@@ -316,13 +342,17 @@ pub async fn verify_and_swap(args: VerifyAndSwapArgs) -> SwapResult {
                     &swap_args,
                     &token_in_checked,
                     &token_out_checked,
+                    claim,
                 ).await {
                     Ok((hash, status, amount)) => (hash, status, amount),
-                    Err(e) => return SwapResult::Error(e),
+                    Err(e) => return SwapResult::Error(e.into()),
                 }
             }
             _ => {
-                return SwapResult::Error(format!("Unsupported destination chain: {}", swap_args.destination_chain));
+                return SwapResult::Error(ApiError::invalid_argument(
+                    "destination_chain",
+                    format!("Unsupported destination chain: {}", swap_args.destination_chain),
+                ));
             }
         };
 
@@ -346,11 +376,11 @@ pub async fn verify_and_swap(args: VerifyAndSwapArgs) -> SwapResult {
             timestamp: ic_cdk::api::time(),
         };
 
-        SWAP_HISTORY.with(|h| h.borrow_mut().push(swap_response.clone()));
+        record_swap(&swap_response);
 
         SwapResult::Success(swap_response)
     } else {
-        SwapResult::Error("Failed to parse swap arguments from metadata".to_string())
+        SwapResult::Error(ApiError::deposit_invalid(&transaction_hash, "Failed to parse swap arguments from metadata"))
     }
 }
 
@@ -360,12 +390,13 @@ async fn handle_eth_transaction(
     swap_args: &CreateTransactionArgs,
     token_in: &Token,
     token_out: &Token,
+    claim: &ClaimedDeposit,
 ) -> Result<(String, String, String), String> {
     let base_amount_decimal = u256_to_decimal(actual_amount_received, token_in.token_decimals)?;
     let platform_fee_excluded = base_amount_decimal / dec!(1.01);
 
-    let token_in_usd_value = get_token_price(&token_in.price_feed_id).await?;
-    let token_out_usd_value = get_token_price(&token_out.price_feed_id).await?;
+    let token_in_usd_value = get_token_price(token_in.feed_id()?).await?;
+    let token_out_usd_value = get_token_price(token_out.feed_id()?).await?;
 
     let expected_usd_value = platform_fee_excluded * Decimal::from_f64(token_in_usd_value)
         .ok_or("Failed to convert token_in USD value to Decimal")?;
@@ -382,6 +413,8 @@ async fn handle_eth_transaction(
     let min_acceptable_amount_out = expected_amount_out_decimal - slippage_tolerance;
     let max_acceptable_amount_out = expected_amount_out_decimal + slippage_tolerance;
 
+    claim.start_settlement()?;
+
     let (tx_hash_out, status, actual_amount_out_str) = if required_token_out_amount < min_acceptable_amount_out || required_token_out_amount > max_acceptable_amount_out {
         let revert_amount_u256 = decimal_to_u256(base_amount_decimal, token_in.token_decimals)?;
 
@@ -423,12 +456,13 @@ async fn handle_bsc_transaction(
     swap_args: &CreateTransactionArgs,
     token_in: &Token,
     token_out: &Token,
+    claim: &ClaimedDeposit,
 ) -> Result<(String, String, String), String> {
     let base_amount_decimal = u256_to_decimal(actual_amount_received, token_in.token_decimals)?;
     let platform_fee_excluded = base_amount_decimal / dec!(1.01);
 
-    let token_in_usd_value = get_token_price(&token_in.price_feed_id).await?;
-    let token_out_usd_value = get_token_price(&token_out.price_feed_id).await?;
+    let token_in_usd_value = get_token_price(token_in.feed_id()?).await?;
+    let token_out_usd_value = get_token_price(token_out.feed_id()?).await?;
 
     let expected_usd_value = platform_fee_excluded * Decimal::from_f64(token_in_usd_value)
         .ok_or("Failed to convert token_in USD value to Decimal")?;
@@ -445,6 +479,7 @@ async fn handle_bsc_transaction(
     let min_acceptable_amount_out = expected_amount_out_decimal - slippage_tolerance;
     let max_acceptable_amount_out = expected_amount_out_decimal + slippage_tolerance;
 
+    claim.start_settlement()?;
 
     let (tx_hash_out, status, actual_amount_out_str) = if required_token_out_amount < min_acceptable_amount_out || required_token_out_amount > max_acceptable_amount_out {
         let revert_amount_u256 = decimal_to_u256(base_amount_decimal, token_in.token_decimals)?;
@@ -482,7 +517,7 @@ async fn handle_bsc_transaction(
 }
 
 async fn sign_and_send_raw_transaction_eth(transaction: TxEip1559) -> Result<String, String> {
-    let wallet = EthereumWallet::new_canister_wallet().await;
+    let wallet = canister_evm_wallet().await;
     let tx_hash_pre_sign = transaction.signature_hash().0;
 
     let (raw_signature, recovery_id) = wallet.sign_with_ecdsa(tx_hash_pre_sign).await;
@@ -530,7 +565,7 @@ async fn sign_and_send_raw_transaction_eth(transaction: TxEip1559) -> Result<Str
 }
 
 async fn sign_and_send_raw_transaction_bsc(transaction: TxEip1559) -> Result<String, String> {
-    let wallet = BscWallet::new_canister_wallet().await;
+    let wallet = canister_evm_wallet().await;
     let tx_hash_pre_sign = transaction.signature_hash().0;
 
     let (raw_signature, recovery_id) = wallet.sign_with_ecdsa(tx_hash_pre_sign).await;
@@ -548,7 +583,7 @@ async fn sign_and_send_raw_transaction_bsc(transaction: TxEip1559) -> Result<Str
         raw_transaction_hex
     );
 
-    match call_rpc_with_retry(json_payload).await {
+    match call_rpc_with_retry_on_chain(json_payload, ChainType::BSC).await {
         Ok(response_body) => {
             let response: serde_json::Value = serde_json::from_str(&response_body)
                 .map_err(|e| format!("Failed to parse RPC response for BSC: {}", e))?;
@@ -577,11 +612,12 @@ async fn sign_and_send_raw_transaction_bsc(transaction: TxEip1559) -> Result<Str
 }
 
 async fn send_native_eth(to: Address, amount: U256) -> Result<String, String> {
-    let canister_address = EthereumWallet::new_canister_wallet().await.ethereum_address().to_string();
+    let canister_address = canister_evm_wallet().await.address().to_string();
 
-    let nonce = fetch_nonce_eth(&canister_address).await?;
     let (max_fee_per_gas, max_priority_fee_per_gas) = get_dynamic_fees().await?;
-    let chain_id = read_state(|s| s.ethereum_network().chain_id());
+    let chain_id = payout_chain_id(ChainType::Ethereum);
+    let nonce_key = nonces::nonce_key(chain_id, &canister_address);
+    let nonce = reserve_nonce(ChainType::Ethereum, &nonce_key, &canister_address).await?;
 
     let tx = TxEip1559 {
         chain_id,
@@ -595,17 +631,18 @@ async fn send_native_eth(to: Address, amount: U256) -> Result<String, String> {
         input: Default::default(),
     };
 
-    sign_and_send_raw_transaction_eth(tx).await
+    submit_transaction(ChainType::Ethereum, tx, &nonce_key).await
 }
 
 async fn send_erc20_token(token_contract: Address, to: Address, amount: U256) -> Result<String, String> {
-    let canister_address = EthereumWallet::new_canister_wallet().await.ethereum_address().to_string();
+    let canister_address = canister_evm_wallet().await.address().to_string();
 
-    let nonce = fetch_nonce_eth(&canister_address).await?;
     let (max_fee_per_gas, max_priority_fee_per_gas) = get_dynamic_fees().await?;
+    let chain_id = payout_chain_id(ChainType::Ethereum);
+    let nonce_key = nonces::nonce_key(chain_id, &canister_address);
+    let nonce = reserve_nonce(ChainType::Ethereum, &nonce_key, &canister_address).await?;
     let transfer_call = IERC20::transferCall { to, amount };
     let input_data = transfer_call.abi_encode();
-    let chain_id = read_state(|s| s.ethereum_network().chain_id());
 
     let tx = TxEip1559 {
         chain_id,
@@ -619,16 +656,16 @@ async fn send_erc20_token(token_contract: Address, to: Address, amount: U256) ->
         input: input_data.into(),
     };
 
-    sign_and_send_raw_transaction_eth(tx).await
+    submit_transaction(ChainType::Ethereum, tx, &nonce_key).await
 }
 
 async fn send_native_bnb(to: Address, amount: U256) -> Result<String, String> {
-    let canister_address = BscWallet::new_canister_wallet().await.bsc_address().to_string();
+    let canister_address = canister_evm_wallet().await.address().to_string();
 
-    let nonce = fetch_nonce_bsc_robust(&canister_address).await?;
     let (gas_limit, max_fee_per_gas, max_priority_fee_per_gas) = estimate_transaction_fees_bsc();
-    let chain_id = read_state(|s| s.bsc_network().bsc_chain_id());
-
+    let chain_id = payout_chain_id(ChainType::BSC);
+    let nonce_key = nonces::nonce_key(chain_id, &canister_address);
+    let nonce = reserve_nonce(ChainType::BSC, &nonce_key, &canister_address).await?;
     let transaction = TxEip1559 {
         chain_id,
         nonce,
@@ -641,14 +678,16 @@ async fn send_native_bnb(to: Address, amount: U256) -> Result<String, String> {
         input: Default::default(),
     };
 
-    sign_and_send_raw_transaction_bsc(transaction).await
+    submit_transaction(ChainType::BSC, transaction, &nonce_key).await
 }
 
 async fn send_bep20_token(token_contract: Address, to: Address, amount: U256) -> Result<String, String> {
-    let canister_address = BscWallet::new_canister_wallet().await.bsc_address().to_string();
+    let canister_address = canister_evm_wallet().await.address().to_string();
 
-    let nonce = fetch_nonce_bsc_robust(&canister_address).await?;
     let (gas_limit, max_fee_per_gas, max_priority_fee_per_gas) = estimate_transaction_fees_bsc();
+    let chain_id = payout_chain_id(ChainType::BSC);
+    let nonce_key = nonces::nonce_key(chain_id, &canister_address);
+    let nonce = reserve_nonce(ChainType::BSC, &nonce_key, &canister_address).await?;
 
     let method_id = hex::decode("a9059cbb")
         .map_err(|_| "Failed to decode method ID for BEP20")?;
@@ -662,8 +701,6 @@ async fn send_bep20_token(token_contract: Address, to: Address, amount: U256) ->
     amount_bytes.copy_from_slice(&amount.to_be_bytes::<32>());
     encoded.extend_from_slice(&amount_bytes);
 
-    let chain_id = read_state(|s| s.bsc_network().bsc_chain_id());
-
     let transaction = TxEip1559 {
         chain_id,
         nonce,
@@ -676,7 +713,125 @@ async fn send_bep20_token(token_contract: Address, to: Address, amount: U256) ->
         input: encoded.into(),
     };
 
-    sign_and_send_raw_transaction_bsc(transaction).await
+    submit_transaction(ChainType::BSC, transaction, &nonce_key).await
+}
+
+async fn canister_address(chain: ChainType) -> String {
+    match chain {
+        ChainType::Ethereum => canister_evm_wallet().await.address().to_string(),
+        ChainType::BSC => canister_evm_wallet().await.address().to_string(),
+    }
+}
+
+fn payout_chain_id(chain: ChainType) -> u64 {
+    match chain {
+        ChainType::Ethereum => read_state(|s| s.ethereum_network().chain_id()),
+        ChainType::BSC => read_state(|s| s.bsc_network().bsc_chain_id()),
+    }
+}
+
+/// Hands out the next nonce for a canister wallet. The first call per chain
+/// seeds the counter from the `pending` transaction count; later payouts
+/// reserve from canister state and never race each other for a nonce.
+async fn reserve_nonce(chain: ChainType, nonce_key: &str, address: &str) -> Result<u64, String> {
+    if !nonces::is_tracked(nonce_key) {
+        let chain_nonce = match chain {
+            ChainType::Ethereum => fetch_nonce_eth(address).await?,
+            ChainType::BSC => fetch_nonce_bsc_robust(address).await?,
+        };
+        nonces::track(nonce_key, chain_nonce);
+    }
+    nonces::reserve(nonce_key)
+}
+
+async fn submit_transaction(chain: ChainType, transaction: TxEip1559, nonce_key: &str) -> Result<String, String> {
+    let nonce = transaction.nonce;
+    let max_fee_per_gas = transaction.max_fee_per_gas;
+    let max_priority_fee_per_gas = transaction.max_priority_fee_per_gas;
+
+    let result = match chain {
+        ChainType::Ethereum => sign_and_send_raw_transaction_eth(transaction).await,
+        ChainType::BSC => sign_and_send_raw_transaction_bsc(transaction).await,
+    };
+
+    match result {
+        Ok(tx_hash) => {
+            nonces::mark_submitted(nonce_key, nonce, tx_hash.clone(), max_fee_per_gas, max_priority_fee_per_gas);
+            Ok(tx_hash)
+        }
+        Err(e) => {
+            nonces::mark_failed(nonce_key, nonce);
+            if e.contains("nonce too low") {
+                let address = canister_address(chain).await;
+                if let Ok(chain_nonce) = get_transaction_count_for_address_on_chain(address, "pending", chain).await {
+                    nonces::track(nonce_key, chain_nonce);
+                }
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Drops mined nonces on both payout chains and fills abandoned ones with
+/// zero-value self transfers, so a failed payout does not block later ones.
+pub async fn reconcile_nonces() {
+    for chain in [ChainType::Ethereum, ChainType::BSC] {
+        if let Err(e) = reconcile_chain_nonces(chain).await {
+            ic_cdk::println!("Nonce reconciliation on {} failed: {}", chain.name(), e);
+        }
+    }
+}
+
+async fn reconcile_chain_nonces(chain: ChainType) -> Result<(), String> {
+    let address = canister_address(chain).await;
+    let nonce_key = nonces::nonce_key(payout_chain_id(chain), &address);
+
+    if !nonces::is_tracked(&nonce_key) {
+        return Ok(());
+    }
+
+    let confirmed_nonce = get_transaction_count_for_address_on_chain(address.clone(), "latest", chain).await?;
+    let report = nonces::reconcile(&nonce_key, confirmed_nonce);
+
+    for stuck in &report.stuck {
+        ic_cdk::println!(
+            "{} transaction {:?} with nonce {} is still pending",
+            chain.name(),
+            stuck.tx_hash,
+            stuck.nonce
+        );
+    }
+
+    let own_address = Address::from_str(&address).map_err(|e| format!("Invalid canister address: {}", e))?;
+    for nonce in report.gaps {
+        let (max_fee_per_gas, max_priority_fee_per_gas) = match chain {
+            ChainType::Ethereum => get_dynamic_fees().await?,
+            ChainType::BSC => {
+                let (_, max_fee_per_gas, max_priority_fee_per_gas) = estimate_transaction_fees_bsc();
+                (max_fee_per_gas, max_priority_fee_per_gas)
+            }
+        };
+        nonces::mark_cancel(&nonce_key, nonce);
+
+        let transaction = TxEip1559 {
+            chain_id: payout_chain_id(chain),
+            nonce,
+            gas_limit: 21_000,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            to: alloy_primitives::TxKind::Call(own_address),
+            value: U256::ZERO,
+            access_list: Default::default(),
+            input: Default::default(),
+        };
+
+        match submit_transaction(chain, transaction, &nonce_key).await {
+            Ok(tx_hash) => ic_cdk::println!("Filled {} nonce gap {} with {}", chain.name(), nonce, tx_hash),
+            Err(e) => ic_cdk::println!("Failed to fill {} nonce gap {}: {}", chain.name(), nonce, e),
+        }
+    }
+
+    Ok(())
 }
 
 pub fn get_swap_history() -> Vec<SwapResponse> {
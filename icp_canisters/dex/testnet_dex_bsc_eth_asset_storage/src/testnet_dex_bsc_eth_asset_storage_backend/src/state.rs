@@ -1,5 +1,6 @@
 use crate::wallet::ecdsa::EcdsaPublicKey;
-use crate::utils::types::{BscNetwork, EcdsaKeyName, EthereumNetwork, InitArg};
+use crate::utils::types::{BscNetwork, ChainType, EcdsaKeyName, EthereumNetwork, InitArg};
+use bit10_core::providers::{ProviderSet, ProviderSetView, RpcConfig, RpcProvider};
 use ic_cdk::api::management_canister::ecdsa::{EcdsaCurve, EcdsaKeyId};
 use std::cell::RefCell;
 use std::ops::{Deref, DerefMut};
@@ -23,11 +24,7 @@ where
     STATE.with(|s| f(s.borrow_mut().deref_mut()))
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub struct CustomRpcConfig {
-    pub url: String,
-    pub api_key: Option<String>,
-}
+const ALL_CHAINS: [ChainType; 2] = [ChainType::Ethereum, ChainType::BSC];
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct State {
@@ -35,7 +32,7 @@ pub struct State {
     ethereum_network: EthereumNetwork,
     ecdsa_key_name: EcdsaKeyName,
     ecdsa_public_key: Option<EcdsaPublicKey>,
-    custom_rpc: Option<CustomRpcConfig>,
+    rpc_config: RpcConfig<ChainType>,
 }
 
 impl Default for State {
@@ -45,10 +42,7 @@ impl Default for State {
             ethereum_network: EthereumNetwork::default(),
             ecdsa_key_name: EcdsaKeyName::default(),
             ecdsa_public_key: None,
-            custom_rpc: Some(CustomRpcConfig {
-                url: "https://ethereum-sepolia.gateway.tatum.io/".to_string(),
-                api_key: Some("<YOUR-API-KEY>".to_string()),
-            }),
+            rpc_config: RpcConfig::default(),
         }
     }
 }
@@ -66,22 +60,78 @@ impl State {
         self.ethereum_network
     }
 
-    pub fn custom_rpc(&self) -> Option<&CustomRpcConfig> {
-        self.custom_rpc.as_ref()
+    fn default_rpc_providers(&self, chain: ChainType) -> ProviderSet {
+        let providers = match chain {
+            ChainType::BSC => match self.bsc_network {
+                BscNetwork::Testnet => vec![
+                    RpcProvider::new("publicnode", "https://bsc-testnet-rpc.publicnode.com"),
+                    RpcProvider::new("bnbchain", "https://data-seed-prebsc-1-s1.bnbchain.org:8545"),
+                    RpcProvider::new("tatum", "https://bsc-testnet.gateway.tatum.io/")
+                        .with_header("x-api-key", "{api_key}"),
+                ],
+            },
+            ChainType::Ethereum => match self.ethereum_network {
+                EthereumNetwork::Mainnet => vec![
+                    RpcProvider::new("llamarpc", "https://eth.llamarpc.com"),
+                    RpcProvider::new("publicnode", "https://ethereum-rpc.publicnode.com"),
+                    RpcProvider::new("drpc", "https://eth.drpc.org"),
+                ],
+                EthereumNetwork::Sepolia => vec![
+                    RpcProvider::new("publicnode", "https://ethereum-sepolia-rpc.publicnode.com"),
+                    RpcProvider::new("drpc", "https://sepolia.drpc.org"),
+                    RpcProvider::new("tatum", "https://ethereum-sepolia.gateway.tatum.io/")
+                        .with_header("x-api-key", "{api_key}"),
+                ],
+            },
+        };
+        ProviderSet::new(providers, 2).expect("Invalid default RPC providers")
     }
 
-    pub fn set_custom_rpc(&mut self, config: CustomRpcConfig) {
-        self.custom_rpc = Some(config);
+    /// The chain's callable providers, in the order requests fall back
+    /// through them.
+    pub fn callable_rpc_providers(&mut self, chain: ChainType) -> Vec<RpcProvider> {
+        let default = self.default_rpc_providers(chain);
+        self.rpc_config.get_or_insert_with(chain, || default).callable()
     }
 
-    pub fn clear_custom_rpc(&mut self) {
-        self.custom_rpc = None;
+    pub fn tally_rpc_responses(
+        &mut self,
+        chain: ChainType,
+        responses: Vec<(String, Result<String, String>)>,
+        now: u64,
+    ) -> Result<String, String> {
+        let default = self.default_rpc_providers(chain);
+        self.rpc_config.get_or_insert_with(chain, || default).tally(responses, now)
     }
 
-    pub fn bsc_rpc_url(&self) -> &'static str {
-        match self.bsc_network {
-            BscNetwork::Testnet => "https://bsc-testnet.gateway.tatum.io/",
+    pub fn rpc_config_view(&mut self) -> Vec<(ChainType, ProviderSetView)> {
+        for chain in ALL_CHAINS {
+            let default = self.default_rpc_providers(chain);
+            self.rpc_config.get_or_insert_with(chain, || default);
         }
+        self.rpc_config.view()
+    }
+
+    pub fn set_rpc_providers(&mut self, chain: ChainType, providers: Vec<RpcProvider>, quorum: u8) -> Result<(), String> {
+        let default = self.default_rpc_providers(chain);
+        self.rpc_config.set_providers(chain, providers, quorum, || default)
+    }
+
+    pub fn set_rpc_api_key(&mut self, chain: ChainType, provider: &str, api_key: Option<String>) -> Result<(), String> {
+        let default = self.default_rpc_providers(chain);
+        self.rpc_config.set_api_key(chain, provider, api_key, || default)
+    }
+
+    pub fn reset_rpc_providers(&mut self, chain: ChainType) {
+        self.rpc_config.reset(&chain);
+    }
+
+    pub fn rpc_config(&self) -> RpcConfig<ChainType> {
+        self.rpc_config.clone()
+    }
+
+    pub fn set_rpc_config(&mut self, rpc_config: RpcConfig<ChainType>) {
+        self.rpc_config = rpc_config;
     }
 
     pub fn bsc_chain_id(&self) -> u64 {
@@ -89,13 +139,6 @@ impl State {
             BscNetwork::Testnet => 97,
         }
     }
-
-    pub fn ethereum_rpc_url(&self) -> &'static str {
-        match self.ethereum_network {
-            EthereumNetwork::Mainnet => "https://eth.llamarpc.com",
-            EthereumNetwork::Sepolia => "https://ethereum-sepolia.gateway.tatum.io/",
-        }
-    }
 }
 
 impl From<InitArg> for State {
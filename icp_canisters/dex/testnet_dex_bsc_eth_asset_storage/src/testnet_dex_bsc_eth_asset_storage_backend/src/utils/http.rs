@@ -2,39 +2,30 @@ use ic_cdk::api::management_canister::http_request::{
     CanisterHttpRequestArgument, HttpHeader, HttpMethod, HttpResponse, TransformArgs,
     TransformContext, http_request,
 };
+use bit10_core::providers::{json_rpc_body, normalize_json_rpc, RpcProvider};
+use bit10_core::transform::{canonical_body, ResponseShape};
+use crate::state::mutate_state;
+use crate::utils::types::ChainType;
 use candid::Nat;
 use num::ToPrimitive;
 
-pub async fn make_http_request(request: CanisterHttpRequestArgument) -> Result<Vec<u8>, String> {
-    const MAX_RETRIES: u8 = 5;
-
-    let mut retries = 0;
-
-    while retries < MAX_RETRIES {
-        let cycles: u128 = 25_000_000_000;
-        match http_request(request.clone(), cycles).await {
-            Ok((response,)) => {
-                if response.status.0.to_u64().unwrap_or(0) == 200 {
-                    return Ok(response.body);
-                } else {
-                    return Err(format!("HTTP error: status {}", response.status));
-                }
-            },
-            Err((_, msg)) if msg.contains("No consensus") || msg.contains("SysTransient") => {
-                retries += 1;
-                ic_cdk::println!("Retry {} for request: {}", retries, request.url);
-                continue;
-            },
-            Err((_, msg)) => return Err(msg),
-        }
-    }
-
-    Err(format!("Failed after {} retries. Last error: No consensus could be reached", MAX_RETRIES))
+/// Cycles the IC charges for `request`, from its size and response limit.
+fn outcall_cost(request: &CanisterHttpRequestArgument) -> u128 {
+    let bytes = bit10_core::cycles::request_bytes(
+        &request.url,
+        request.headers.iter().map(|h| (h.name.as_str(), h.value.as_str())),
+        request.body.as_deref(),
+        request
+            .transform
+            .as_ref()
+            .map(|t| (t.function.0.method.as_str(), t.context.as_slice())),
+    );
+    bit10_core::cycles::http_request_cost(bytes, request.max_response_bytes)
 }
 
 pub async fn make_robust_http_request(request: CanisterHttpRequestArgument) -> Result<Vec<u8>, String> {
     const MAX_RETRIES: u8 = 3;
-    let cycles: u128 = 50_000_000_000;
+    let cycles = outcall_cost(&request);
 
     for attempt in 0..MAX_RETRIES {
         match http_request(request.clone(), cycles).await {
@@ -63,59 +54,59 @@ pub async fn make_robust_http_request(request: CanisterHttpRequestArgument) -> R
 }
 
 pub async fn call_rpc_with_retry(json_payload: String) -> Result<String, String> {
-    use crate::state::read_state;
-    use super::constants::TATUM_API_KEY;
-
-    let request_headers = vec![
-        HttpHeader {
-            name: "Content-Type".to_string(),
-            value: "application/json".to_string(),
-        },
-        HttpHeader {
-            name: "X-API-Key".to_string(),
-            value: TATUM_API_KEY.to_string(),
-        },
-    ];
-
-    let transform_context = TransformContext::from_name("transform".to_string(), vec![]);
-    let network = read_state(|s| s.ethereum_network());
-    let url = network.rpc_url().to_string();
-
-    let request = CanisterHttpRequestArgument {
-        url,
-        method: HttpMethod::POST,
-        body: Some(json_payload.into_bytes()),
-        max_response_bytes: Some(8192),
-        transform: Some(transform_context),
-        headers: request_headers,
-    };
+    call_rpc_with_retry_on_chain(json_payload, ChainType::Ethereum).await
+}
 
-    make_http_request(request).await.and_then(|body| {
-        String::from_utf8(body).map_err(|e| format!("Failed to decode response: {}", e))
-    })
+/// Sends the request to one of the chain's providers at a time, moving on
+/// only when the outcall fails. Nonces, fees and broadcasts go through here.
+pub async fn call_rpc_with_retry_on_chain(json_payload: String, chain: ChainType) -> Result<String, String> {
+    let providers = mutate_state(|s| s.callable_rpc_providers(chain));
+    let mut errors = Vec::with_capacity(providers.len());
+    for provider in &providers {
+        match post_to_provider(provider, &json_payload).await {
+            Ok(body) => return Ok(body),
+            Err(e) => errors.push(format!("{}: {}", provider.name, e)),
+        }
+    }
+    Err(format!("Every RPC provider failed: {}", errors.join("; ")))
 }
 
-pub async fn call_rpc_with_retry_on_chain(json_payload: String, chain: crate::utils::types::ChainType) -> Result<String, String> {
-    use crate::state::read_state;
-    use super::constants::TATUM_API_KEY;
+/// Sends the read to every provider for the chain and answers with the
+/// result a quorum of them agree on. Deposits are verified through here.
+pub async fn call_quorum_rpc_on_chain(json_payload: String, chain: ChainType) -> Result<String, String> {
+    let providers = mutate_state(|s| s.callable_rpc_providers(chain));
+    let mut responses = Vec::with_capacity(providers.len());
+    for provider in &providers {
+        let response = post_to_provider(provider, &json_payload)
+            .await
+            .and_then(|body| normalize_json_rpc(&body));
+        responses.push((provider.name.clone(), response));
+    }
+    let result = mutate_state(|s| s.tally_rpc_responses(chain, responses, ic_cdk::api::time()))?;
+    Ok(json_rpc_body(&result))
+}
 
-    let transform_context = TransformContext::from_name("transform".to_string(), vec![]);
+async fn post_to_provider(provider: &RpcProvider, json_payload: &str) -> Result<String, String> {
+    let transform_context =
+        TransformContext::from_name("transform".to_string(), ResponseShape::for_payload(json_payload).to_context());
 
-    let (url, api_key) = match chain {
-        crate::utils::types::ChainType::BSC => (read_state(|s| s.bsc_rpc_url()), TATUM_API_KEY),
-        crate::utils::types::ChainType::Ethereum => (read_state(|s| s.ethereum_rpc_url()), TATUM_API_KEY),
-    };
+    let mut headers = vec![
+        HttpHeader { name: "accept".to_string(), value: "application/json".to_string() },
+        HttpHeader { name: "content-type".to_string(), value: "application/json".to_string() },
+    ];
+    headers.extend(
+        provider
+            .resolved_headers()
+            .into_iter()
+            .map(|(name, value)| HttpHeader { name, value }),
+    );
 
     let request = CanisterHttpRequestArgument {
         method: HttpMethod::POST,
-        url: url.to_string(),
-        headers: vec![
-            HttpHeader { name: "accept".to_string(), value: "application/json".to_string() },
-            HttpHeader { name: "content-type".to_string(), value: "application/json".to_string() },
-            HttpHeader { name: "x-api-key".to_string(), value: api_key.to_string() },
-        ],
+        url: provider.resolved_url(),
+        headers,
         body: Some(json_payload.as_bytes().to_vec()),
-        max_response_bytes: Some(4_000),
+        max_response_bytes: Some(8192),
         transform: Some(transform_context),
     };
 
@@ -131,7 +122,7 @@ pub fn transform(raw: TransformArgs) -> HttpResponse {
         ..Default::default()
     };
     if res.status == Nat::from(200u64) {
-        res.body = raw.response.body;
+        res.body = canonical_body(&raw.context, &raw.response.body);
     } else {
         ic_cdk::api::print(format!(
             "Received an error from the remote service:\n{:#?}",
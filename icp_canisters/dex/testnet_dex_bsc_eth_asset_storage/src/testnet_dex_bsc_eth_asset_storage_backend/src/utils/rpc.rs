@@ -1,7 +1,6 @@
-use crate::state::read_state;
-use crate::utils::http::{call_rpc_with_retry, call_rpc_with_retry_on_chain};
+use crate::utils::http::{call_quorum_rpc_on_chain, call_rpc_with_retry, call_rpc_with_retry_on_chain};
 use crate::utils::types::{
-    ChainType, EthereumNetwork, JsonRpcResponse, RpcTransaction, RpcTransactionReceipt,
+    ChainType, JsonRpcResponse, RpcTransaction, RpcTransactionReceipt,
 };
 use num::ToPrimitive;
 use alloy_primitives::U256;
@@ -35,17 +34,14 @@ pub async fn get_dynamic_fees() -> Result<(u128, u128), String> {
     }
 }
 
-pub fn get_rpc_url(network: EthereumNetwork) -> &'static str {
-    match network {
-        EthereumNetwork::Mainnet => "https://eth.llamarpc.com",
-        EthereumNetwork::Sepolia => "https://ethereum-sepolia.gateway.tatum.io/",
-    }
-}
-
-pub async fn get_transaction_count_for_address_on_chain(address: String, chain: ChainType) -> Result<u64, String> {
+pub async fn get_transaction_count_for_address_on_chain(
+    address: String,
+    block_tag: &str,
+    chain: ChainType,
+) -> Result<u64, String> {
     let json_payload = format!(
-        r#"{{"jsonrpc": "2.0", "method": "eth_getTransactionCount", "params": ["{}", "pending"], "id": 1}}"#,
-        address
+        r#"{{"jsonrpc": "2.0", "method": "eth_getTransactionCount", "params": ["{}", "{}"], "id": 1}}"#,
+        address, block_tag
     );
 
     let body_str = call_rpc_with_retry_on_chain(json_payload, chain).await?;
@@ -65,12 +61,34 @@ pub async fn get_transaction_count_for_address_on_chain(address: String, chain:
     }
 }
 
+pub async fn get_balance_on_chain(address: &str, chain: ChainType) -> Result<u128, String> {
+    let json_payload = format!(
+        r#"{{"jsonrpc": "2.0", "method": "eth_getBalance", "params": ["{}", "latest"], "id": 1}}"#,
+        address
+    );
+
+    let body_str = call_rpc_with_retry_on_chain(json_payload, chain).await?;
+    let response: JsonRpcResponse<String> = serde_json::from_str(&body_str)
+        .map_err(|e| format!("Failed to parse balance response: {}", e))?;
+
+    if let Some(error) = response.error {
+        return Err(format!("RPC error fetching balance: {}", error));
+    }
+
+    if let Some(result) = response.result {
+        u128::from_str_radix(result.strip_prefix("0x").unwrap_or(&result), 16)
+            .map_err(|e| format!("Failed to parse balance hex: {}", e))
+    } else {
+        Err("Failed to get balance from response".to_string())
+    }
+}
+
 pub async fn fetch_nonce_eth(address: &str) -> Result<u64, String> {
-    get_transaction_count_for_address_on_chain(address.to_string(), ChainType::Ethereum).await
+    get_transaction_count_for_address_on_chain(address.to_string(), "pending", ChainType::Ethereum).await
 }
 
 pub async fn fetch_nonce_bsc_robust(address: &str) -> Result<u64, String> {
-    get_transaction_count_for_address_on_chain(address.to_string(), ChainType::BSC).await
+    get_transaction_count_for_address_on_chain(address.to_string(), "pending", ChainType::BSC).await
 }
 
 pub async fn fetch_nonce_bsc(address: &str) -> u64 {
@@ -89,7 +107,7 @@ pub async fn get_transaction_by_hash_on_chain(tx_hash: &str, chain: ChainType) -
         tx_hash
     );
 
-    let result_str = call_rpc_with_retry_on_chain(json_payload, chain).await?;
+    let result_str = call_quorum_rpc_on_chain(json_payload, chain).await?;
     let response: JsonRpcResponse<RpcTransaction> = serde_json::from_str(&result_str)
         .map_err(|e| format!("Failed to parse transaction response: {}", e))?;
 
@@ -106,7 +124,7 @@ pub async fn get_transaction_receipt_on_chain(tx_hash: &str, chain: ChainType) -
         tx_hash
     );
 
-    let result_str = call_rpc_with_retry_on_chain(json_payload, chain).await?;
+    let result_str = call_quorum_rpc_on_chain(json_payload, chain).await?;
     let response: JsonRpcResponse<RpcTransactionReceipt> = serde_json::from_str(&result_str)
         .map_err(|e| format!("Failed to parse receipt response: {}", e))?;
 
@@ -1,4 +1,5 @@
 use crate::utils::types::{Token, Pair, PriceFeed, PriceFeedId};
+use crate::services::metrics;
 use crate::utils::constants::PRICE_FEED_CANISTER_ID;
 use ic_cdk::Principal;
 use rust_decimal::Decimal;
@@ -9,7 +10,7 @@ use ciborium::from_reader;
 pub fn get_supported_tokens() -> Vec<Token> {
     vec![
         Token {
-            token_id: "1839".to_string(),
+            token_id: Some("1839".to_string()),
             token_name: "tBNB".to_string(),
             token_symbol: "tBNB".to_string(),
             token_address: Some("0x0000000000000000000000000000000000000000b".to_string()),
@@ -17,10 +18,11 @@ pub fn get_supported_tokens() -> Vec<Token> {
             token_decimals: 18,
             is_native: true,
             is_active: true,
-            price_feed_id: "2374178234y3749".to_string(),
+            price_feed_id: Some("2374178234y3749".to_string()),
+            price_feed_link: None,
         },
         Token {
-            token_id: "3408_1839".to_string(),
+            token_id: Some("3408_1839".to_string()),
             token_name: "USD Coin".to_string(),
             token_symbol: "USDC".to_string(),
             token_address: Some("0x64544969ed7EBf5f083679233325356EbE738930".to_string()),
@@ -28,10 +30,11 @@ pub fn get_supported_tokens() -> Vec<Token> {
             token_decimals: 18,
             is_native: false,
             is_active: true,
-            price_feed_id: "2374178234y3749".to_string(),
+            price_feed_id: Some("2374178234y3749".to_string()),
+            price_feed_link: None,
         },
         Token {
-            token_id: "4023_1839".to_string(),
+            token_id: Some("4023_1839".to_string()),
             token_name: "BTCB".to_string(),
             token_symbol: "BTCB".to_string(),
             token_address: Some("0x6ce8dA28E2f864420840cF74474eFf5fD80E65B8".to_string()),
@@ -39,10 +42,11 @@ pub fn get_supported_tokens() -> Vec<Token> {
             token_decimals: 18,
             is_native: false,
             is_active: true,
-            price_feed_id: "2374178234y3749".to_string(),
+            price_feed_id: Some("2374178234y3749".to_string()),
+            price_feed_link: None,
         },
         Token {
-            token_id: "1027".to_string(),
+            token_id: Some("1027".to_string()),
             token_name: "Ethereum".to_string(),
             token_symbol: "ETH".to_string(),
             token_address: Some("0x0000000000000000000000000000000000000000e".to_string()),
@@ -50,10 +54,11 @@ pub fn get_supported_tokens() -> Vec<Token> {
             token_decimals: 18,
             is_native: true,
             is_active: true,
-            price_feed_id: "2374178234y3749".to_string(),
+            price_feed_id: Some("2374178234y3749".to_string()),
+            price_feed_link: None,
         },
         Token {
-            token_id: "3408_1027".to_string(),
+            token_id: Some("3408_1027".to_string()),
             token_name: "USD Coin".to_string(),
             token_symbol: "USDC".to_string(),
             token_address: Some("0x1c7D4B196Cb0C7B01d743Fbc6116a902379C7238".to_string()),
@@ -61,7 +66,8 @@ pub fn get_supported_tokens() -> Vec<Token> {
             token_decimals: 6,
             is_native: false,
             is_active: true,
-            price_feed_id: "2374178234y3749".to_string(),
+            price_feed_id: Some("2374178234y3749".to_string()),
+            price_feed_link: None,
         }
     ]
 }
@@ -136,6 +142,7 @@ pub async fn get_token_price(data_id: &str) -> Result<f64, String> {
 
     let price_result: Result<(Option<PriceFeed>,), _> =
         ic_cdk::call(price_feed_canister, "get_value", (data_id,)).await;
+    metrics::record_price_feed(data_id, matches!(price_result, Ok((Some(_),))));
 
     let price_feed = match price_result {
         Ok((Some(feed),)) => feed,
@@ -1,7 +1,13 @@
-pub mod bsc;
-pub mod ecdsa;
-pub mod ethereum;
+use crate::state::{lazy_call_ecdsa_public_key, read_state};
 
-pub use bsc::BscWallet;
-pub use ethereum::EthereumWallet;
-pub use ecdsa::EcdsaPublicKey;
\ No newline at end of file
+pub use bit10_core::wallet::{ecdsa, evm_wallet};
+
+use evm_wallet::EvmWallet;
+
+/// One root-key wallet holds the pool on every EVM chain.
+pub async fn canister_evm_wallet() -> EvmWallet {
+    EvmWallet::new_canister_wallet(
+        &lazy_call_ecdsa_public_key().await,
+        read_state(|s| s.ecdsa_key_id()),
+    )
+}
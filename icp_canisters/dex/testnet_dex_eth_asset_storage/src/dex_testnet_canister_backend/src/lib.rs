@@ -4,7 +4,9 @@ mod utils;
 mod wallet;
 
 pub use utils::controller::{
-    create_transaction, ethereum_address, get_transaction_count_for_address, init, post_upgrade,
-    supported_pairs, supported_tokens, transform, verify_and_swap, CreateTransactionArgs,
+    create_transaction, ethereum_address, get_cycles_budget, get_cycles_usage, get_rpc_config, get_transaction_count_for_address, http_request,
+    icrc3_get_archives, icrc3_get_blocks, icrc3_get_tip_certificate, icrc3_supported_block_types, init,
+    post_upgrade, reset_rpc_providers, set_cycles_budget, set_rpc_api_key, set_rpc_providers, supported_pairs,
+    supported_tokens, transform, verify_and_swap, CreateTransactionArgs,
     EcdsaKeyName, EthereumNetwork, InitArg, Pair, SwapResult, Token, TransactionResponse,
 };
@@ -1,10 +1,17 @@
-use crate::state::{read_state, InitArg, EthereumNetwork, EcdsaKeyName};
+use crate::state::{deposits, metrics, nonces};
+use crate::state::{mutate_state, read_state, InitArg, EthereumNetwork, EcdsaKeyName};
 use crate::utils::parsing::nat_to_u64;
-use crate::wallet::eth_wallet::EthereumWallet;
+use crate::wallet::canister_evm_wallet;
 use alloy_consensus::{SignableTransaction, TxEip1559, TxEnvelope};
 use alloy_eips::eip2718::Encodable2718;
 use alloy_primitives::{hex, Address, Signature, U256};
 use alloy_sol_types::{sol, SolCall};
+use bit10_core::deposits::ClaimedDeposit;
+use bit10_core::errors::ApiError;
+use bit10_core::events::{Event, SwapEvent, SwapStatus, TokenTransfer};
+use bit10_core::fees;
+use bit10_core::providers::{json_rpc_body, normalize_json_rpc, RpcProvider};
+use bit10_core::transform::{canonical_body, ResponseShape};
 use candid::{CandidType, Deserialize, Nat, Principal};
 use ciborium::from_reader;
 use evm_rpc_canister_types::{EvmRpcCanister, RequestResult, RpcError};
@@ -61,30 +68,7 @@ pub struct TransactionResponse {
     pub transaction_data: TransactionData,
 }
 
-#[derive(CandidType, Deserialize, Debug, Clone, serde::Serialize)]
-pub struct Token {
-    pub token_id: String,
-    pub token_name: String,
-    pub token_symbol: String,
-    pub token_address: Option<String>,
-    pub token_chain: String,
-    pub token_decimals: u8,
-    pub is_native: bool,
-    pub is_active: bool,
-    pub price_feed_id: String,
-}
-
-#[derive(CandidType, Deserialize, Debug, Clone, serde::Serialize)]
-pub struct Pair {
-    pub pool_id: String,
-    pub token_a_symbol: String,
-    pub token_a_chain: String,
-    pub token_a_token_id: String,
-    pub token_b_symbol: String,
-    pub token_b_chain: String,
-    pub token_b_token_id: String,
-    pub pair_type: String,
-}
+pub use bit10_core::types::token::{Pair, Token};
 
 #[derive(CandidType, Deserialize, Debug)]
 pub struct PriceFeed {
@@ -117,17 +101,38 @@ pub struct SwapResponse {
     pub timestamp: u64,
 }
 
+impl SwapResponse {
+    pub fn to_event(&self) -> Event {
+        let status = SwapStatus::from_history(&self.status);
+        // A reverted swap pays the input back on the source chain.
+        let (out_chain, out_token) = match status {
+            SwapStatus::Completed => (&self.destination_chain, &self.token_out_address),
+            SwapStatus::Reverted => (&self.source_chain, &self.token_in_address),
+        };
+        Event::Swap(SwapEvent {
+            swap_id: None,
+            pool_id: self.pool_id.clone(),
+            sender: self.tick_in_wallet_address.clone(),
+            recipient: self.tick_out_wallet_address.clone(),
+            token_in: TokenTransfer::new(&self.source_chain, &self.token_in_address, &self.amount_in, &self.tx_hash_in),
+            token_out: TokenTransfer::new(out_chain, out_token, &self.amount_out, &self.tx_hash_out),
+            slippage: Some(self.slippage.clone()),
+            status,
+        })
+    }
+}
+
 #[derive(CandidType, Deserialize, Debug, Clone)]
 pub enum SwapResult {
     Success(SwapResponse),
-    Error(String),
+    Error(ApiError),
 }
 
 fn get_supported_tokens(network: EthereumNetwork) -> Vec<Token> {
     match network {
         EthereumNetwork::Mainnet => vec![
             Token {
-                token_id: "1027".to_string(),
+                token_id: Some("1027".to_string()),
                 token_name: "Ethereum".to_string(),
                 token_symbol: "ETH".to_string(),
                 token_address: Some(
@@ -137,10 +142,11 @@ fn get_supported_tokens(network: EthereumNetwork) -> Vec<Token> {
                 token_decimals: 18,
                 is_native: true,
                 is_active: true,
-                price_feed_id: "26346734".to_string(),
+                price_feed_id: Some("26346734".to_string()),
+                price_feed_link: None,
             },
             Token {
-                token_id: "3408_1027".to_string(),
+                token_id: Some("3408_1027".to_string()),
                 token_name: "USD Coin".to_string(),
                 token_symbol: "USDC".to_string(),
                 token_address: Some(
@@ -150,12 +156,13 @@ fn get_supported_tokens(network: EthereumNetwork) -> Vec<Token> {
                 token_decimals: 6,
                 is_native: false,
                 is_active: true,
-                price_feed_id: "2365732848".to_string(),
+                price_feed_id: Some("2365732848".to_string()),
+                price_feed_link: None,
             },
         ],
         EthereumNetwork::Sepolia => vec![
             Token {
-                token_id: "1027".to_string(),
+                token_id: Some("1027".to_string()),
                 token_name: "Ethereum".to_string(),
                 token_symbol: "ETH".to_string(),
                 token_address: Some(
@@ -165,10 +172,11 @@ fn get_supported_tokens(network: EthereumNetwork) -> Vec<Token> {
                 token_decimals: 18,
                 is_native: true,
                 is_active: true,
-                price_feed_id: "3541625346".to_string(),
+                price_feed_id: Some("3541625346".to_string()),
+                price_feed_link: None,
             },
             Token {
-                token_id: "3408_1027".to_string(),
+                token_id: Some("3408_1027".to_string()),
                 token_name: "USD Coin".to_string(),
                 token_symbol: "USDC".to_string(),
                 token_address: Some(
@@ -178,7 +186,8 @@ fn get_supported_tokens(network: EthereumNetwork) -> Vec<Token> {
                 token_decimals: 6,
                 is_native: false,
                 is_active: true,
-                price_feed_id: "2354273467".to_string(),
+                price_feed_id: Some("2354273467".to_string()),
+                price_feed_link: None,
             },
         ],
     }
@@ -201,41 +210,58 @@ pub const EVM_RPC_CANISTER_ID: Principal =
     Principal::from_slice(b"\x00\x00\x00\x00\x02\x30\x00\xCC\x01\x01");
 pub const EVM_RPC: EvmRpcCanister = EvmRpcCanister(EVM_RPC_CANISTER_ID);
 
-fn get_rpc_url(network: EthereumNetwork) -> &'static str {
-    match network {
-        EthereumNetwork::Mainnet => "https://eth.llamarpc.com",
-        EthereumNetwork::Sepolia => "https://ethereum-sepolia.gateway.tatum.io/",
-    }
-}
-
-const TATUM_API_KEY: &str =
-    "shjgdhjslkdsfkljdfjkdlkfgjlek";
-
 pub async fn ethereum_address() -> String {
-    let wallet = EthereumWallet::new_canister_wallet().await;
-    wallet.ethereum_address().to_string()
+    canister_evm_wallet().await.address().to_string()
 }
 
 pub async fn get_transaction_count_for_address(address: String) -> Nat {
+    transaction_count(&address, "latest")
+        .await
+        .map(Nat::from)
+        .unwrap_or_else(|e| ic_cdk::trap(&e))
+}
+
+async fn transaction_count(address: &str, block_tag: &str) -> Result<u64, String> {
     let json_payload = format!(
-        r#"{{"jsonrpc": "2.0", "method": "eth_getTransactionCount", "params": ["{}", "latest"], "id": 1}}"#,
-        address
+        r#"{{"jsonrpc": "2.0", "method": "eth_getTransactionCount", "params": ["{}", "{}"], "id": 1}}"#,
+        address, block_tag
     );
-    let response = call_tatum_rpc(json_payload).await;
-    match response {
+    match call_rpc(json_payload).await {
         RequestResult::Ok(result_str) => {
-            let response: serde_json::Value = serde_json::from_str(&result_str).unwrap();
-            if let Some(result) = response.get("result").and_then(|v| v.as_str()) {
-                let count_hex = result.strip_prefix("0x").unwrap_or(result);
-                let count = u64::from_str_radix(count_hex, 16).unwrap();
-                Nat::from(count)
-            } else {
-                ic_cdk::trap("Failed to parse transaction count response")
-            }
+            let response: serde_json::Value = serde_json::from_str(&result_str)
+                .map_err(|e| format!("Failed to parse transaction count response: {}", e))?;
+            let result = response
+                .get("result")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| "Failed to parse transaction count response".to_string())?;
+            fees::parse_hex_u128(result).map(|count| count as u64)
         }
-        RequestResult::Err(e) => {
-            ic_cdk::trap(&format!("Failed to get transaction count: {:?}", e))
+        RequestResult::Err(e) => Err(format!("Failed to get transaction count: {:?}", e)),
+    }
+}
+
+/// Swaps pay out from the canister's Ethereum wallet, so `/health` degrades
+/// once it cannot cover a token transfer at the suggested fee.
+pub async fn check_gas_balance() {
+    let address = ethereum_address().await;
+    let json_payload = format!(
+        r#"{{"jsonrpc": "2.0", "method": "eth_getBalance", "params": ["{}", "latest"], "id": 1}}"#,
+        address
+    );
+    let balance = match call_rpc(json_payload).await {
+        RequestResult::Ok(result_str) => serde_json::from_str::<serde_json::Value>(&result_str)
+            .ok()
+            .and_then(|response| response.get("result")?.as_str().map(str::to_string))
+            .and_then(|hex| u128::from_str_radix(hex.strip_prefix("0x").unwrap_or(&hex), 16).ok())
+            .ok_or_else(|| "Failed to parse balance response".to_string()),
+        RequestResult::Err(e) => Err(format!("Failed to get balance: {:?}", e)),
+    };
+
+    match (balance, get_dynamic_fees().await) {
+        (Ok(balance), Ok((max_fee_per_gas, _))) => {
+            metrics::record_gas("Ethereum", balance, max_fee_per_gas * bit10_core::metrics::EVM_TRANSFER_GAS)
         }
+        (Err(e), _) | (_, Err(e)) => ic_cdk::println!("Failed to read Ethereum gas balance: {}", e),
     }
 }
 
@@ -300,15 +326,31 @@ pub async fn create_transaction_internal(
         data_bytes = transfer_data;
     }
 
-    let data_gas: u128 = data_bytes
-        .iter()
-        .map(|&byte| if byte == 0 { 4 } else { 16 })
-        .sum();
-    let base_gas = if token_in.is_native { 21_000 } else { 65_000 };
-    gas_limit = base_gas + data_gas;
+    let value_hex = format!("0x{:x}", value);
+    let data_hex = format!("0x{}", hex::encode(&data_bytes));
+    gas_limit = match estimate_gas_limit(
+        &args.tick_in_wallet_address,
+        &to_address,
+        &value_hex,
+        &data_hex,
+    )
+    .await
+    {
+        Ok(limit) => limit,
+        Err(e) => {
+            ic_cdk::println!("eth_estimateGas failed, using intrinsic gas: {}", e);
+            let data_gas: u128 = data_bytes
+                .iter()
+                .map(|&byte| if byte == 0 { 4 } else { 16 })
+                .sum();
+            let base_gas = if token_in.is_native { 21_000 } else { 65_000 };
+            base_gas + data_gas
+        }
+    };
 
-    let (_dummy_gas, max_fee_per_gas, max_priority_fee_per_gas) =
-        estimate_transaction_fees();
+    let (max_fee_per_gas, max_priority_fee_per_gas) = get_dynamic_fees()
+        .await
+        .unwrap_or_else(|e| ic_cdk::trap(&format!("Failed to estimate fees: {}", e)));
     let nonce = nat_to_u64(
         get_transaction_count_for_address(args.tick_in_wallet_address.clone()).await,
     );
@@ -319,8 +361,8 @@ pub async fn create_transaction_internal(
         blockchain: "Ethereum".to_string(),
         from: args.tick_in_wallet_address.clone(),
         to: to_address,
-        value: format!("0x{:x}", value),
-        data: format!("0x{}", hex::encode(data_bytes)),
+        value: value_hex,
+        data: data_hex,
         gas_limit: format!("0x{:x}", gas_limit),
         max_priority_fee_per_gas: format!("0x{:x}", max_priority_fee_per_gas),
         max_fee_per_gas: format!("0x{:x}", max_fee_per_gas),
@@ -360,7 +402,7 @@ struct RpcTransaction {
     input: String,
 }
 
-pub async fn verify_and_swap_internal(transaction_hash: String) -> SwapResult {
+pub async fn verify_and_swap_internal(transaction_hash: String, claim: &ClaimedDeposit) -> SwapResult {
     let canister_address_str = ethereum_address().await;
     let canister_address_bytes =
         hex::decode(canister_address_str.strip_prefix("0x").unwrap()).unwrap();
@@ -374,7 +416,7 @@ pub async fn verify_and_swap_internal(transaction_hash: String) -> SwapResult {
             transaction_hash
         );
         let receipt_response: JsonRpcResponse<RpcTransactionReceipt> =
-            match call_tatum_rpc(receipt_payload).await {
+            match call_quorum_rpc(receipt_payload).await {
                 RequestResult::Ok(result_str) => {
                     match serde_json::from_str(&result_str) {
                         Ok(res) => res,
@@ -383,7 +425,10 @@ pub async fn verify_and_swap_internal(transaction_hash: String) -> SwapResult {
                                 "Verification failed: Failed to parse RPC receipt: {}",
                                 e
                             );
-                            return SwapResult::Error(format!("Failed to parse RPC receipt: {}", e));
+                            return SwapResult::Error(ApiError::chain_unavailable(
+                                "Ethereum",
+                                format!("Failed to parse RPC receipt: {}", e),
+                            ));
                         }
                     }
                 }
@@ -392,7 +437,7 @@ pub async fn verify_and_swap_internal(transaction_hash: String) -> SwapResult {
                         "Verification failed: RPC call for receipt failed: {:?}",
                         e
                     );
-                    return SwapResult::Error(format!("RPC call for receipt failed: {:?}", e));
+                    return SwapResult::Error(ApiError::chain_unavailable("Ethereum", format!("{:?}", e)));
                 }
             };
 
@@ -417,7 +462,10 @@ pub async fn verify_and_swap_internal(transaction_hash: String) -> SwapResult {
                 "Verification failed: Transaction receipt not found after {} retries. Transaction may still be pending.",
                 MAX_RETRIES
             );
-            return SwapResult::Error("Transaction receipt not found after retries. Transaction may still be pending.".to_string());
+            return SwapResult::Error(ApiError::deposit_invalid(
+                &transaction_hash,
+                "Transaction receipt not found after retries. Transaction may still be pending.",
+            ));
         }
     };
 
@@ -425,7 +473,10 @@ pub async fn verify_and_swap_internal(transaction_hash: String) -> SwapResult {
         ic_cdk::println!(
             "Verification failed: Transaction was reverted (status is not 0x1)."
         );
-        return SwapResult::Error("Transaction was reverted (status is not 0x1).".to_string());
+        return SwapResult::Error(ApiError::deposit_invalid(
+            &transaction_hash,
+            "Transaction was reverted (status is not 0x1).",
+        ));
     }
 
     let tx_payload = format!(
@@ -433,7 +484,7 @@ pub async fn verify_and_swap_internal(transaction_hash: String) -> SwapResult {
         transaction_hash
     );
     let tx_response: JsonRpcResponse<RpcTransaction> =
-        match call_tatum_rpc(tx_payload).await {
+        match call_quorum_rpc(tx_payload).await {
             RequestResult::Ok(result_str) => match serde_json::from_str(&result_str) {
                 Ok(res) => res,
                 Err(e) => {
@@ -441,7 +492,10 @@ pub async fn verify_and_swap_internal(transaction_hash: String) -> SwapResult {
                     "Verification failed: Could not fetch original transaction details: {}",
                     e
                 );
-                    return SwapResult::Error(format!("Could not fetch original transaction details: {}", e));
+                    return SwapResult::Error(ApiError::chain_unavailable(
+                        "Ethereum",
+                        format!("Could not fetch original transaction details: {}", e),
+                    ));
                 }
             },
             RequestResult::Err(e) => {
@@ -449,7 +503,7 @@ pub async fn verify_and_swap_internal(transaction_hash: String) -> SwapResult {
                     "Verification failed: RPC call for transaction failed: {:?}",
                     e
                 );
-                return SwapResult::Error(format!("RPC call for transaction failed: {:?}", e));
+                return SwapResult::Error(ApiError::chain_unavailable("Ethereum", format!("{:?}", e)));
             }
         };
 
@@ -459,7 +513,7 @@ pub async fn verify_and_swap_internal(transaction_hash: String) -> SwapResult {
             ic_cdk::println!(
                 "Verification failed: Could not find transaction details by hash."
             );
-            return SwapResult::Error("Could not find transaction details by hash.".to_string());
+            return SwapResult::Error(ApiError::not_found(&transaction_hash));
         }
     };
 
@@ -483,13 +537,14 @@ pub async fn verify_and_swap_internal(transaction_hash: String) -> SwapResult {
                     amount_smallest_unit,
                     &transaction_hash,
                     &receipt.from,
+                    claim,
                 )
                 .await
                 {
                     Ok(swap_response) => return SwapResult::Success(swap_response),
                     Err(e) => {
                         ic_cdk::println!("Error during swap/refund execution: {}", e);
-                        return SwapResult::Error(format!("Error during swap/refund execution: {}", e));
+                        return SwapResult::Error(format!("Error during swap/refund execution: {}", e).into());
                     }
                 }
             }
@@ -510,13 +565,14 @@ pub async fn verify_and_swap_internal(transaction_hash: String) -> SwapResult {
             value,
             &transaction_hash,
             &receipt.from,
+            claim,
         )
         .await
         {
             Ok(swap_response) => return SwapResult::Success(swap_response),
             Err(e) => {
                 ic_cdk::println!("Error during swap/refund execution: {}", e);
-                return SwapResult::Error(format!("Error during swap/refund execution: {}", e));
+                return SwapResult::Error(format!("Error during swap/refund execution: {}", e).into());
             }
         }
     }
@@ -524,7 +580,10 @@ pub async fn verify_and_swap_internal(transaction_hash: String) -> SwapResult {
     ic_cdk::println!(
         "Verification failed: No valid ETH or ERC20 transfer to the canister was found."
     );
-    SwapResult::Error("No valid ETH or ERC20 transfer to the canister was found.".to_string())
+    SwapResult::Error(ApiError::deposit_invalid(
+        &transaction_hash,
+        "No valid ETH or ERC20 transfer to the canister was found.",
+    ))
 }
 
 async fn execute_swap_or_refund_with_response(
@@ -533,6 +592,7 @@ async fn execute_swap_or_refund_with_response(
     amount_smallest_unit: U256,
     tx_hash_in: &str,
     from_address: &str,
+    claim: &ClaimedDeposit,
 ) -> Result<SwapResponse, String> {
     let input_bytes = hex::decode(tx_input.strip_prefix("0x").unwrap_or(""))
         .map_err(|_| "Failed to decode tx input".to_string())?;
@@ -571,8 +631,8 @@ async fn execute_swap_or_refund_with_response(
     let human_readable_amount = amount_decimal / divisor;
     let base_amount = calculate_base_amount_from_sent(&human_readable_amount);
 
-    let price_in = get_token_price(&token_in.price_feed_id).await?;
-    let price_out = get_token_price(&token_out.price_feed_id).await?;
+    let price_in = get_token_price(token_in.feed_id()?).await?;
+    let price_out = get_token_price(token_out.feed_id()?).await?;
 
     let usd_sent = base_amount.to_f64().unwrap_or(0.0) * price_in;
     let calculated_tick_out =
@@ -595,6 +655,8 @@ async fn execute_swap_or_refund_with_response(
 
     let current_timestamp = ic_cdk::api::time();
 
+    claim.start_settlement()?;
+
     // Check if transaction should be reverted due to slippage
     if percent_difference > slippage_percent {
         ic_cdk::println!("❌ TRANSACTION REVERTED: Slippage tolerance exceeded!");
@@ -757,6 +819,7 @@ async fn get_token_price(data_id: &str) -> Result<f64, String> {
 
     let price_result: Result<(Option<PriceFeed>,), _> =
         ic_cdk::call(price_feed_canister, "get_value", (data_id,)).await;
+    metrics::record_price_feed(data_id, matches!(price_result, Ok((Some(_),))));
 
     let price_feed = match price_result {
         Ok((Some(feed),)) => feed,
@@ -783,7 +846,7 @@ pub fn transform(raw: TransformArgs) -> HttpResponse {
         ..Default::default()
     };
     if res.status == Nat::from(200u64) {
-        res.body = raw.response.body;
+        res.body = canonical_body(&raw.context, &raw.response.body);
     } else {
         ic_cdk::api::print(format!(
             "Received an error from the remote service:\n{:#?}",
@@ -793,35 +856,84 @@ pub fn transform(raw: TransformArgs) -> HttpResponse {
     res
 }
 
-async fn call_tatum_rpc(json_payload: String) -> RequestResult {
+fn outcall_cost(request: &ic_cdk::api::management_canister::http_request::CanisterHttpRequestArgument) -> u128 {
+    let bytes = bit10_core::cycles::request_bytes(
+        &request.url,
+        request.headers.iter().map(|h| (h.name.as_str(), h.value.as_str())),
+        request.body.as_deref(),
+        request
+            .transform
+            .as_ref()
+            .map(|t| (t.function.0.method.as_str(), t.context.as_slice())),
+    );
+    bit10_core::cycles::http_request_cost(bytes, request.max_response_bytes)
+}
+
+/// Sends the request to one provider at a time, moving on only when the
+/// outcall fails. Nonces, fees and broadcasts go through here.
+async fn call_rpc(json_payload: String) -> RequestResult {
+    let mut last_error = rpc_error("No RPC provider can be called".to_string());
+    for provider in read_state(|s| s.rpc_providers()).callable() {
+        match post_to_provider(&provider, &json_payload).await {
+            RequestResult::Ok(body) => return RequestResult::Ok(body),
+            RequestResult::Err(e) => last_error = e,
+        }
+    }
+    RequestResult::Err(last_error)
+}
+
+/// Sends the read to every provider and answers with the result a quorum of
+/// them agree on. Deposits are verified through here.
+async fn call_quorum_rpc(json_payload: String) -> RequestResult {
+    let providers = read_state(|s| s.rpc_providers()).callable();
+    let mut responses = Vec::with_capacity(providers.len());
+    for provider in &providers {
+        let response = match post_to_provider(provider, &json_payload).await {
+            RequestResult::Ok(body) => normalize_json_rpc(&body),
+            RequestResult::Err(e) => Err(format!("{:?}", e)),
+        };
+        responses.push((provider.name.clone(), response));
+    }
+    match mutate_state(|s| s.rpc_providers_mut().tally(responses, ic_cdk::api::time())) {
+        Ok(result) => RequestResult::Ok(json_rpc_body(&result)),
+        Err(e) => RequestResult::Err(rpc_error(e)),
+    }
+}
+
+fn rpc_error(message: String) -> RpcError {
+    RpcError::JsonRpcError(evm_rpc_canister_types::JsonRpcError { code: -1, message })
+}
+
+async fn post_to_provider(provider: &RpcProvider, json_payload: &str) -> RequestResult {
     use ic_cdk::api::management_canister::http_request::{
         http_request, CanisterHttpRequestArgument, HttpMethod, TransformContext,
     };
-    let request_headers = vec![
-        HttpHeader {
-            name: "Content-Type".to_string(),
-            value: "application/json".to_string(),
-        },
-        HttpHeader {
-            name: "X-API-Key".to_string(),
-            value: TATUM_API_KEY.to_string(),
-        },
-    ];
 
-    let transform_context = TransformContext::from_name("transform".to_string(), vec![]);
-    let network = read_state(|s| s.ethereum_network());
-    let url = get_rpc_url(network).to_string();
+    let mut request_headers = vec![HttpHeader {
+        name: "Content-Type".to_string(),
+        value: "application/json".to_string(),
+    }];
+    request_headers.extend(
+        provider
+            .resolved_headers()
+            .into_iter()
+            .map(|(name, value)| HttpHeader { name, value }),
+    );
+
+    let transform_context =
+        TransformContext::from_name("transform".to_string(), ResponseShape::for_payload(json_payload).to_context());
 
     let request = CanisterHttpRequestArgument {
-        url,
+        url: provider.resolved_url(),
         method: HttpMethod::POST,
-        body: Some(json_payload.into_bytes()),
+        body: Some(json_payload.as_bytes().to_vec()),
         max_response_bytes: Some(8192),
         transform: Some(transform_context),
         headers: request_headers,
     };
 
-    match http_request(request, 25_000_000_000).await {
+    let cycles = outcall_cost(&request);
+    match http_request(request, cycles).await {
         Ok((response,)) => {
             if response.status == Nat::from(200u64) {
                 let body = String::from_utf8(response.body).unwrap();
@@ -835,52 +947,42 @@ async fn call_tatum_rpc(json_payload: String) -> RequestResult {
                 RequestResult::Err(RpcError::JsonRpcError(json_error))
             }
         }
-        Err((r, m)) => {
-            let json_error = evm_rpc_canister_types::JsonRpcError {
-                code: -1,
-                message: format!("Request failed: {:?} - {}", r, m),
-            };
-            RequestResult::Err(RpcError::JsonRpcError(json_error))
-        }
+        Err((r, m)) => RequestResult::Err(rpc_error(format!("Request failed: {:?} - {}", r, m))),
     }
 }
 
 async fn get_dynamic_fees() -> Result<(u128, u128), String> {
-    let json_payload =
-        r#"{"jsonrpc": "2.0", "method": "eth_gasPrice", "params": [], "id": 1}"#
-            .to_string();
+    let fee_config = read_state(|s| s.fee_config().clone());
 
-    match call_tatum_rpc(json_payload).await {
+    match call_rpc(fees::fee_history_payload(&fee_config)).await {
         RequestResult::Ok(result_str) => {
-            let response: JsonRpcResponse<String> = serde_json::from_str(&result_str)
-                .map_err(|e| format!("Failed to parse gas price response: {}", e))?;
-
-            if let Some(gas_price_hex) = response.result {
-                let gas_price = u128::from_str_radix(
-                    gas_price_hex.strip_prefix("0x").unwrap_or(""),
-                    16,
-                )
-                .map_err(|e| format!("Failed to parse gas price hex: {}", e))?;
-
-                let max_priority_fee_per_gas = 2_000_000_000u128;
-                let max_fee_per_gas = gas_price + max_priority_fee_per_gas;
-
-                Ok((max_fee_per_gas, max_priority_fee_per_gas))
-            } else {
-                Err("eth_gasPrice returned no result".to_string())
-            }
+            let history = fees::parse_fee_history(&result_str)?;
+            let suggested = fees::suggest_fees(&history, &fee_config)?;
+            Ok((suggested.max_fee_per_gas, suggested.max_priority_fee_per_gas))
         }
         RequestResult::Err(e) => {
-            Err(format!("RPC call for gas price failed: {:?}", e))
+            Err(format!("RPC call for fee history failed: {:?}", e))
         }
     }
 }
 
-fn estimate_transaction_fees() -> (u128, u128, u128) {
-    const GAS_LIMIT: u128 = 21_000;
-    const MAX_FEE_PER_GAS: u128 = 50_000_000_000;
-    const MAX_PRIORITY_FEE_PER_GAS: u128 = 1_500_000_000;
-    (GAS_LIMIT, MAX_FEE_PER_GAS, MAX_PRIORITY_FEE_PER_GAS)
+async fn estimate_gas_limit(
+    from: &str,
+    to: &str,
+    value_hex: &str,
+    data_hex: &str,
+) -> Result<u128, String> {
+    let json_payload = fees::estimate_gas_payload(from, to, value_hex, data_hex);
+
+    match call_rpc(json_payload).await {
+        RequestResult::Ok(result_str) => {
+            let estimate = fees::parse_estimate_gas(&result_str)?;
+            read_state(|s| fees::gas_limit_from_estimate(estimate, s.fee_config()))
+        }
+        RequestResult::Err(e) => {
+            Err(format!("RPC call for gas estimate failed: {:?}", e))
+        }
+    }
 }
 
 pub fn supported_tokens_internal() -> String {
@@ -900,11 +1002,13 @@ fn calculate_base_amount_from_sent(amount_sent: &Decimal) -> Decimal {
 
 async fn send_native_eth(to: Address, amount: U256) -> Result<String, String> {
     let canister_address = ethereum_address().await;
-    let nonce = nat_to_u64(get_transaction_count_for_address(canister_address).await);
     let (max_fee_per_gas, max_priority_fee_per_gas) = get_dynamic_fees().await?;
+    let chain_id = read_state(|s| s.ethereum_network().chain_id());
+    let nonce_key = nonces::nonce_key(chain_id, &canister_address);
+    let nonce = reserve_nonce(&nonce_key, &canister_address).await?;
 
     let tx = TxEip1559 {
-        chain_id: read_state(|s| s.ethereum_network().chain_id()),
+        chain_id,
         nonce,
         gas_limit: 21000,
         max_fee_per_gas,
@@ -915,7 +1019,7 @@ async fn send_native_eth(to: Address, amount: U256) -> Result<String, String> {
         input: Default::default(),
     };
 
-    _sign_and_send_raw_transaction(tx).await
+    submit_transaction(tx, &nonce_key).await
 }
 
 async fn send_erc20_token(
@@ -924,16 +1028,25 @@ async fn send_erc20_token(
     amount: U256,
 ) -> Result<String, String> {
     let canister_address = ethereum_address().await;
-    let nonce = nat_to_u64(get_transaction_count_for_address(canister_address).await);
     let (max_fee_per_gas, max_priority_fee_per_gas) = get_dynamic_fees().await?;
 
     let transfer_call = IERC20::transferCall { to, amount };
     let input_data = transfer_call.abi_encode();
+    let gas_limit = estimate_gas_limit(
+        &canister_address,
+        &token_contract.to_string(),
+        "0x0",
+        &format!("0x{}", hex::encode(&input_data)),
+    )
+    .await?;
+    let chain_id = read_state(|s| s.ethereum_network().chain_id());
+    let nonce_key = nonces::nonce_key(chain_id, &canister_address);
+    let nonce = reserve_nonce(&nonce_key, &canister_address).await?;
 
     let tx = TxEip1559 {
-        chain_id: read_state(|s| s.ethereum_network().chain_id()),
+        chain_id,
         nonce,
-        gas_limit: 100_000,
+        gas_limit,
         max_fee_per_gas,
         max_priority_fee_per_gas,
         to: alloy_primitives::TxKind::Call(token_contract),
@@ -942,13 +1055,95 @@ async fn send_erc20_token(
         input: input_data.into(),
     };
 
-    _sign_and_send_raw_transaction(tx).await
+    submit_transaction(tx, &nonce_key).await
+}
+
+/// Hands out the next nonce for the canister wallet. The first call seeds the
+/// counter from the `pending` transaction count; later payouts reserve from
+/// canister state, so concurrent swaps never share a nonce.
+async fn reserve_nonce(nonce_key: &str, address: &str) -> Result<u64, String> {
+    if !nonces::is_tracked(nonce_key) {
+        let chain_nonce = transaction_count(address, "pending").await?;
+        nonces::track(nonce_key, chain_nonce);
+    }
+    nonces::reserve(nonce_key)
+}
+
+async fn submit_transaction(transaction: TxEip1559, nonce_key: &str) -> Result<String, String> {
+    let nonce = transaction.nonce;
+    let max_fee_per_gas = transaction.max_fee_per_gas;
+    let max_priority_fee_per_gas = transaction.max_priority_fee_per_gas;
+
+    match _sign_and_send_raw_transaction(transaction).await {
+        Ok(tx_hash) => {
+            nonces::mark_submitted(nonce_key, nonce, tx_hash.clone(), max_fee_per_gas, max_priority_fee_per_gas);
+            Ok(tx_hash)
+        }
+        Err(e) => {
+            nonces::mark_failed(nonce_key, nonce);
+            if e.contains("nonce too low") {
+                if let Ok(chain_nonce) = transaction_count(&ethereum_address().await, "pending").await {
+                    nonces::track(nonce_key, chain_nonce);
+                }
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Drops mined nonces and fills abandoned ones with zero-value self
+/// transfers, so a failed payout does not hold back the ones after it.
+pub async fn reconcile_nonces() -> Result<(), String> {
+    let canister_address = ethereum_address().await;
+    let chain_id = read_state(|s| s.ethereum_network().chain_id());
+    let nonce_key = nonces::nonce_key(chain_id, &canister_address);
+
+    if !nonces::is_tracked(&nonce_key) {
+        return Ok(());
+    }
+
+    let confirmed_nonce = transaction_count(&canister_address, "latest").await?;
+    let report = nonces::reconcile(&nonce_key, confirmed_nonce);
+
+    for stuck in &report.stuck {
+        ic_cdk::println!(
+            "Transaction {:?} with nonce {} is still pending",
+            stuck.tx_hash,
+            stuck.nonce
+        );
+    }
+
+    let own_address = Address::from_str(&canister_address)
+        .map_err(|e| format!("Invalid canister address: {}", e))?;
+    for nonce in report.gaps {
+        let (max_fee_per_gas, max_priority_fee_per_gas) = get_dynamic_fees().await?;
+        nonces::mark_cancel(&nonce_key, nonce);
+
+        let tx = TxEip1559 {
+            chain_id,
+            nonce,
+            gas_limit: 21_000,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            to: alloy_primitives::TxKind::Call(own_address),
+            value: U256::ZERO,
+            access_list: Default::default(),
+            input: Default::default(),
+        };
+
+        match submit_transaction(tx, &nonce_key).await {
+            Ok(tx_hash) => ic_cdk::println!("Filled nonce gap {} with {}", nonce, tx_hash),
+            Err(e) => ic_cdk::println!("Failed to fill nonce gap {}: {}", nonce, e),
+        }
+    }
+
+    Ok(())
 }
 
 async fn _sign_and_send_raw_transaction(
     transaction: TxEip1559,
 ) -> Result<String, String> {
-    let wallet = EthereumWallet::new_canister_wallet().await;
+    let wallet = canister_evm_wallet().await;
     let tx_hash = transaction.signature_hash().0;
     let (raw_signature, recovery_id) = wallet.sign_with_ecdsa(tx_hash).await;
     let signature =
@@ -966,7 +1161,7 @@ async fn _sign_and_send_raw_transaction(
         raw_transaction_hex
     );
 
-    match call_tatum_rpc(json_payload).await {
+    match call_rpc(json_payload).await {
         RequestResult::Ok(_) => Ok(raw_transaction_hash.to_string()),
         RequestResult::Err(e) => {
             Err(format!("Failed to send raw transaction: {:?}", e))
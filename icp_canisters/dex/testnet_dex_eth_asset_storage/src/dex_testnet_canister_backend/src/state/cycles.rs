@@ -0,0 +1,69 @@
+use crate::state::state::read_state;
+use bit10_core::cycles::{self, ActionCycles, CyclesBudget, CyclesLedger};
+use bit10_core::errors::ApiError;
+use bit10_core::metrics::{self, MetricsEncoder};
+use candid::Principal;
+use std::cell::RefCell;
+
+// Reading the deposit transaction and receipt, then the nonce and fees
+// before broadcasting the payout.
+const SWAP_OUTCALLS: u32 = 4;
+
+/// The DEX router, `t2vfi-5aaaa-aaaap-qqbfa-cai`.
+const ROUTER_CANISTER_ID: Principal =
+    Principal::from_slice(b"\x00\x00\x00\x00\x01\xf0\x80\x4a\x01\x01");
+
+thread_local! {
+    static CYCLES: RefCell<CyclesLedger> = RefCell::new(CyclesLedger::default());
+}
+
+pub fn export_cycles() -> CyclesLedger {
+    CYCLES.with(|c| c.borrow().clone())
+}
+
+pub fn import_cycles(ledger: CyclesLedger) {
+    CYCLES.with(|c| *c.borrow_mut() = ledger);
+}
+
+/// Every swap the router forwards arrives with the router as caller, so it
+/// gets no per-caller limit here and limits end users itself. Runs on init
+/// and after every upgrade, so restored budgets pick it up as well.
+pub fn exempt_router() {
+    CYCLES.with(|c| c.borrow_mut().exempt(ROUTER_CANISTER_ID));
+}
+
+pub fn swap_cost() -> u128 {
+    let key_name = read_state(|s| s.ecdsa_key_id().name);
+    cycles::json_rpc_cost(SWAP_OUTCALLS) + cycles::signature_cost(&key_name)
+}
+
+/// Charges the caller before the deposit is verified, so calls that fail
+/// verification still count against their budget. Controllers are not
+/// limited, and the router only by the overall cap, see `exempt_router`.
+pub fn charge(action: &str, cost: u128) -> Result<(), ApiError> {
+    let caller = ic_cdk::caller();
+    CYCLES.with(|c| {
+        let mut ledger = c.borrow_mut();
+        if ic_cdk::api::is_controller(&caller) {
+            ledger.record(action, cost);
+            return Ok(());
+        }
+        ledger.charge(caller, action, cost, ic_cdk::api::time())
+    })
+}
+
+pub fn get_budget() -> CyclesBudget {
+    CYCLES.with(|c| c.borrow().budget().clone())
+}
+
+pub fn set_budget(budget: CyclesBudget) -> Result<(), String> {
+    CYCLES.with(|c| c.borrow_mut().set_budget(budget))
+}
+
+pub fn get_usage() -> Vec<ActionCycles> {
+    CYCLES.with(|c| c.borrow().actions().cloned().collect())
+}
+
+pub fn encode_metrics(encoder: &mut MetricsEncoder) {
+    CYCLES.with(|c| metrics::encode_cycles(encoder, &c.borrow()));
+}
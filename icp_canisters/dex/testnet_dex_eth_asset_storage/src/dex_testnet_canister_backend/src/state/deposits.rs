@@ -0,0 +1,51 @@
+use crate::services::swap_service::SwapResult;
+use bit10_core::deposits::{deposit_key, DepositRegistry, Settlement};
+use bit10_core::events::{Event, EventLog};
+use std::cell::RefCell;
+
+thread_local! {
+    pub static DEPOSIT_REGISTRY: RefCell<DepositRegistry> = RefCell::new(DepositRegistry::default());
+}
+
+pub fn export_deposits() -> DepositRegistry {
+    DEPOSIT_REGISTRY.with(|d| d.borrow().clone())
+}
+
+pub fn import_deposits(registry: DepositRegistry) {
+    DEPOSIT_REGISTRY.with(|d| *d.borrow_mut() = registry);
+}
+
+/// This canister keeps no swap history, so deposits paid out before the
+/// registry existed are recovered from the event log.
+pub fn registry_for_events(event_log: &EventLog) -> DepositRegistry {
+    let now = ic_cdk::api::time();
+    let mut registry = DepositRegistry::default();
+    for block in event_log.blocks() {
+        let Event::Swap(swap) = &block.event else {
+            continue;
+        };
+        let Some(tx_hash) = &swap.token_in.tx_hash else {
+            continue;
+        };
+        if let Ok(claim) = registry.claim(&key(tx_hash), now) {
+            registry.finish(&claim, true, now);
+        }
+    }
+    registry
+}
+
+/// Every swap is decoded from its own transaction's input, so a transaction
+/// carries one deposit and is keyed as a whole.
+pub fn key(tx_hash: &str) -> String {
+    deposit_key("ethereum", tx_hash, None)
+}
+
+pub fn release(key: &str) -> bool {
+    DEPOSIT_REGISTRY.with(|d| d.borrow_mut().release(key))
+}
+
+impl Settlement for SwapResult {
+    fn succeeded(&self) -> bool {
+        matches!(self, SwapResult::Success(_))
+    }
+}
@@ -0,0 +1,56 @@
+use bit10_core::certification::CertifiedTree;
+use bit10_core::events::{self, DataCertificate, Event, EventLog, GetBlocksRequest, GetBlocksResult, SupportedBlockType};
+use bit10_core::metrics::{self, MetricsEncoder};
+use std::cell::RefCell;
+
+thread_local! {
+    static EVENT_LOG: RefCell<EventLog> = RefCell::new(EventLog::default());
+}
+
+pub fn export_events() -> EventLog {
+    EVENT_LOG.with(|log| log.borrow().clone())
+}
+
+/// Certified data does not survive an upgrade, so this also re-certifies.
+pub fn import_events(log: EventLog) {
+    EVENT_LOG.with(|l| *l.borrow_mut() = log);
+    certify();
+}
+
+pub fn record_event(event: Event) -> u64 {
+    let id = EVENT_LOG.with(|log| log.borrow_mut().append(event, ic_cdk::api::time()));
+    certify();
+    id
+}
+
+pub fn get_blocks(requests: Vec<GetBlocksRequest>) -> GetBlocksResult {
+    EVENT_LOG.with(|log| log.borrow().get_blocks(&requests))
+}
+
+pub fn tip_certificate() -> Option<DataCertificate> {
+    if EVENT_LOG.with(|log| log.borrow().is_empty()) {
+        return None;
+    }
+    Some(DataCertificate {
+        certificate: ic_cdk::api::data_certificate()?,
+        hash_tree: events::tip_hash_tree(&certified_tree()),
+    })
+}
+
+pub fn supported_block_types() -> Vec<SupportedBlockType> {
+    events::supported_block_types(&[events::SWAP_BLOCK_TYPE])
+}
+
+pub fn encode_metrics(encoder: &mut MetricsEncoder) {
+    EVENT_LOG.with(|log| metrics::encode_events(encoder, &log.borrow()));
+}
+
+fn certified_tree() -> CertifiedTree {
+    let mut tree = CertifiedTree::default();
+    EVENT_LOG.with(|log| log.borrow().certify(&mut tree));
+    tree
+}
+
+fn certify() {
+    ic_cdk::api::set_certified_data(&certified_tree().root_hash());
+}
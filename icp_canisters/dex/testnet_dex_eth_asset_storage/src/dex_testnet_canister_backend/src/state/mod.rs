@@ -1 +1,6 @@
+pub mod cycles;
+pub mod deposits;
+pub mod events;
+pub mod metrics;
+pub mod nonces;
 pub mod state;
\ No newline at end of file
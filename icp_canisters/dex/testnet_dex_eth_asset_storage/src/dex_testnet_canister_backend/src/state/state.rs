@@ -1,5 +1,7 @@
 use crate::wallet::ecdsa::EcdsaPublicKey;
 use crate::{EcdsaKeyName, EthereumNetwork, InitArg};
+use bit10_core::fees::FeeConfig;
+use bit10_core::providers::{ProviderSet, RpcProvider};
 use evm_rpc_canister_types::{EthMainnetService, EthSepoliaService, RpcServices};
 use ic_cdk::api::management_canister::ecdsa::EcdsaKeyId;
 use std::cell::RefCell;
@@ -24,18 +26,13 @@ where
     STATE.with(|s| f(s.borrow_mut().deref_mut()))
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub struct CustomRpcConfig {
-    pub url: String,
-    pub api_key: Option<String>,
-}
-
 #[derive(Debug, PartialEq, Eq)]
 pub struct State {
     ethereum_network: EthereumNetwork,
     ecdsa_key_name: EcdsaKeyName,
     ecdsa_public_key: Option<EcdsaPublicKey>,
-    custom_rpc: Option<CustomRpcConfig>,
+    rpc_providers: Option<ProviderSet>,
+    fee_config: FeeConfig,
 }
 
 impl Default for State {
@@ -44,10 +41,8 @@ impl Default for State {
             ethereum_network: EthereumNetwork::default(),
             ecdsa_key_name: EcdsaKeyName::default(),
             ecdsa_public_key: None,
-            custom_rpc: Some(CustomRpcConfig {
-                url: "https://ethereum-sepolia.gateway.tatum.io/".to_string(),
-                api_key: Some("dfhghjdbfhdkjfghkjfdghkdfgj".to_string()),
-            }),
+            rpc_providers: None,
+            fee_config: FeeConfig::ethereum(),
         }
     }
 }
@@ -61,8 +56,33 @@ impl State {
         self.ethereum_network
     }
 
-    pub fn custom_rpc(&self) -> Option<&CustomRpcConfig> {
-        self.custom_rpc.as_ref()
+    pub fn fee_config(&self) -> &FeeConfig {
+        &self.fee_config
+    }
+
+    /// Endpoints for the canister's own JSON-RPC requests. Deposit lookups
+    /// need `quorum` of them to agree; everything else goes to the first
+    /// callable one that answers.
+    pub fn rpc_providers(&self) -> ProviderSet {
+        self.rpc_providers
+            .clone()
+            .unwrap_or_else(|| default_rpc_providers(self.ethereum_network))
+    }
+
+    pub fn rpc_providers_mut(&mut self) -> &mut ProviderSet {
+        let ethereum_network = self.ethereum_network;
+        self.rpc_providers
+            .get_or_insert_with(|| default_rpc_providers(ethereum_network))
+    }
+
+    /// `None` until a controller changes the defaults or a quorum read
+    /// starts tracking the default providers' health.
+    pub fn configured_rpc_providers(&self) -> Option<ProviderSet> {
+        self.rpc_providers.clone()
+    }
+
+    pub fn set_configured_rpc_providers(&mut self, rpc_providers: Option<ProviderSet>) {
+        self.rpc_providers = rpc_providers;
     }
 
     pub fn evm_rpc_services(&self) -> RpcServices {
@@ -83,13 +103,23 @@ impl State {
         }
     }
 
-    pub fn set_custom_rpc(&mut self, config: CustomRpcConfig) {
-        self.custom_rpc = Some(config);
-    }
+}
 
-    pub fn clear_custom_rpc(&mut self) {
-        self.custom_rpc = None;
-    }
+fn default_rpc_providers(ethereum_network: EthereumNetwork) -> ProviderSet {
+    let providers = match ethereum_network {
+        EthereumNetwork::Mainnet => vec![
+            RpcProvider::new("llamarpc", "https://eth.llamarpc.com"),
+            RpcProvider::new("publicnode", "https://ethereum-rpc.publicnode.com"),
+            RpcProvider::new("drpc", "https://eth.drpc.org"),
+        ],
+        EthereumNetwork::Sepolia => vec![
+            RpcProvider::new("publicnode", "https://ethereum-sepolia-rpc.publicnode.com"),
+            RpcProvider::new("drpc", "https://sepolia.drpc.org"),
+            RpcProvider::new("tatum", "https://ethereum-sepolia.gateway.tatum.io/")
+                .with_header("X-API-Key", "{api_key}"),
+        ],
+    };
+    ProviderSet::new(providers, 2).expect("Invalid default Ethereum providers")
 }
 
 impl From<InitArg> for State {
@@ -97,6 +127,12 @@ impl From<InitArg> for State {
         let mut state = State::default();
         state.ethereum_network = init_arg.ethereum_network.unwrap_or_default();
         state.ecdsa_key_name = init_arg.ecdsa_key_name.unwrap_or_default();
+        if let Some(fee_config) = init_arg.fee_config {
+            if let Err(e) = fee_config.validate() {
+                ic_cdk::trap(&format!("invalid fee config: {}", e));
+            }
+            state.fee_config = fee_config;
+        }
         state
     }
 }
@@ -1,9 +1,15 @@
 use crate::services::swap_service::{
     CreateTransactionArgs, SwapResult, TransactionResponse,
 };
-use crate::state::{init_state, read_state};
+use crate::state::{cycles, deposits, events, metrics, nonces};
+use crate::state::{init_state, mutate_state, read_state};
 use ic_cdk::api::management_canister::http_request::{HttpResponse, TransformArgs};
-use ic_cdk::{init, post_upgrade, query, update};
+use bit10_core::cycles::{ActionCycles, CyclesBudget, CyclesLedger};
+use bit10_core::deposits::{with_deposit_claim, DepositRegistry};
+use bit10_core::events::{ArchiveInfo, DataCertificate, EventLog, GetArchivesArgs, GetBlocksRequest, GetBlocksResult, SupportedBlockType};
+use bit10_core::nonce::NonceManager;
+use bit10_core::providers::{ProviderSet, ProviderSetView, RpcProvider};
+use ic_cdk::{init, post_upgrade, pre_upgrade, query, update};
 
 pub use crate::services::swap_service::{
     CreateTransactionArgs, Pair, SwapResponse, SwapResult, Token, TransactionData,
@@ -14,6 +20,7 @@ pub use crate::services::swap_service::{
 pub struct InitArg {
     pub ethereum_network: Option<EthereumNetwork>,
     pub ecdsa_key_name: Option<EcdsaKeyName>,
+    pub fee_config: Option<bit10_core::fees::FeeConfig>,
 }
 
 #[derive(candid::CandidType, candid::Deserialize, Debug, Default, PartialEq, Eq, Clone, Copy)]
@@ -54,11 +61,45 @@ impl From<&EcdsaKeyName> for ic_cdk::api::management_canister::ecdsa::EcdsaKeyId
     }
 }
 
+const GAS_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(600);
+const NONCE_RECONCILE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(120);
+
 #[init]
 pub fn init(maybe_init: Option<InitArg>) {
     if let Some(init_arg) = maybe_init {
         init_state(init_arg)
     }
+    cycles::exempt_router();
+    start_gas_checks();
+    start_nonce_reconciliation();
+}
+
+fn start_nonce_reconciliation() {
+    ic_cdk_timers::set_timer_interval(NONCE_RECONCILE_INTERVAL, || {
+        ic_cdk::spawn(async {
+            if let Err(e) = crate::services::swap_service::reconcile_nonces().await {
+                ic_cdk::println!("Nonce reconciliation failed: {}", e);
+            }
+        })
+    });
+}
+
+fn start_gas_checks() {
+    ic_cdk_timers::set_timer_interval(GAS_CHECK_INTERVAL, || {
+        ic_cdk::spawn(crate::services::swap_service::check_gas_balance())
+    });
+}
+
+#[pre_upgrade]
+fn pre_upgrade() {
+    ic_cdk::storage::stable_save((
+        read_state(|s| s.configured_rpc_providers()),
+        Some(events::export_events()),
+        Some(cycles::export_cycles()),
+        Some(nonces::export_nonces()),
+        Some(deposits::export_deposits()),
+    ))
+        .expect("Failed to save state to stable storage");
 }
 
 #[post_upgrade]
@@ -66,6 +107,28 @@ fn post_upgrade(maybe_init: Option<InitArg>) {
     if let Some(init_arg) = maybe_init {
         init_state(init_arg);
     }
+    if let Ok((rpc_providers, event_log, cycles_ledger, nonce_manager, deposit_registry)) = ic_cdk::storage::stable_restore::<(
+        Option<ProviderSet>,
+        Option<EventLog>,
+        Option<CyclesLedger>,
+        Option<NonceManager>,
+        Option<DepositRegistry>,
+    )>() {
+        mutate_state(|s| s.set_configured_rpc_providers(rpc_providers));
+        let event_log = event_log.unwrap_or_default();
+        let deposit_registry = deposit_registry.unwrap_or_else(|| deposits::registry_for_events(&event_log));
+        deposits::import_deposits(deposit_registry);
+        events::import_events(event_log);
+        if let Some(cycles_ledger) = cycles_ledger {
+            cycles::import_cycles(cycles_ledger);
+        }
+        if let Some(nonce_manager) = nonce_manager {
+            nonces::import_nonces(nonce_manager);
+        }
+    }
+    cycles::exempt_router();
+    start_gas_checks();
+    start_nonce_reconciliation();
 }
 
 #[update]
@@ -85,7 +148,39 @@ pub async fn create_transaction(args: CreateTransactionArgs) -> TransactionRespo
 
 #[update]
 pub async fn verify_and_swap(transaction_hash: String) -> SwapResult {
-    crate::services::swap_service::verify_and_swap_internal(transaction_hash).await
+    if let Err(err) = cycles::charge("swap", cycles::swap_cost()) {
+        return SwapResult::Error(err);
+    }
+    let key = deposits::key(&transaction_hash);
+    let result = with_deposit_claim(&deposits::DEPOSIT_REGISTRY, &key, |claim| async move {
+        crate::services::swap_service::verify_and_swap_internal(transaction_hash, &claim).await
+    })
+    .await
+    .unwrap_or_else(|e| SwapResult::Error(e.into()));
+    if let SwapResult::Success(swap_response) = &result {
+        events::record_event(swap_response.to_event());
+    }
+    result
+}
+
+#[query]
+pub fn icrc3_get_blocks(args: Vec<GetBlocksRequest>) -> GetBlocksResult {
+    events::get_blocks(args)
+}
+
+#[query]
+pub fn icrc3_get_tip_certificate() -> Option<DataCertificate> {
+    events::tip_certificate()
+}
+
+#[query]
+pub fn icrc3_get_archives(_args: GetArchivesArgs) -> Vec<ArchiveInfo> {
+    Vec::new()
+}
+
+#[query]
+pub fn icrc3_supported_block_types() -> Vec<SupportedBlockType> {
+    events::supported_block_types()
 }
 
 #[query]
@@ -93,6 +188,11 @@ fn transform(raw: TransformArgs) -> HttpResponse {
     crate::services::swap_service::transform(raw)
 }
 
+#[query]
+pub fn http_request(request: bit10_core::metrics::HttpRequest) -> bit10_core::metrics::HttpResponse {
+    metrics::http_request(request)
+}
+
 #[query]
 pub fn supported_tokens() -> String {
     crate::services::swap_service::supported_tokens_internal()
@@ -101,4 +201,73 @@ pub fn supported_tokens() -> String {
 #[query]
 pub fn supported_pairs() -> String {
     crate::services::swap_service::supported_pairs_internal()
-}
\ No newline at end of file
+}
+
+#[query]
+pub fn get_rpc_config() -> Result<ProviderSetView, String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can read the RPC config".to_string());
+    }
+
+    Ok(read_state(|s| s.rpc_providers().view()))
+}
+
+#[update]
+pub fn set_rpc_providers(rpc_providers: Vec<RpcProvider>, quorum: u8) -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can set RPC providers".to_string());
+    }
+
+    mutate_state(|s| s.rpc_providers_mut().replace_providers(rpc_providers, quorum))
+}
+
+#[update]
+pub fn set_rpc_api_key(provider: String, api_key: Option<String>) -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can set RPC API keys".to_string());
+    }
+
+    mutate_state(|s| s.rpc_providers_mut().set_api_key(&provider, api_key))
+}
+
+#[update]
+pub fn reset_rpc_providers() -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can reset RPC providers".to_string());
+    }
+
+    mutate_state(|s| s.set_configured_rpc_providers(None));
+    Ok(())
+}
+
+#[update]
+pub fn release_deposit(key: String) -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can release deposits".to_string());
+    }
+
+    if deposits::release(&key) {
+        Ok(())
+    } else {
+        Err(format!("Deposit {} is not claimed", key))
+    }
+}
+
+#[query]
+pub fn get_cycles_budget() -> CyclesBudget {
+    cycles::get_budget()
+}
+
+#[update]
+pub fn set_cycles_budget(budget: CyclesBudget) -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can set the cycles budget".to_string());
+    }
+
+    cycles::set_budget(budget)
+}
+
+#[query]
+pub fn get_cycles_usage() -> Vec<ActionCycles> {
+    cycles::get_usage()
+}
@@ -1,3 +1,8 @@
+mod cycles;
+mod metrics;
+
+use bit10_core::cycles::{ActionCycles, CyclesBudget, CyclesLedger};
+use bit10_core::errors::ApiError;
 use candid::{CandidType, Deserialize, Principal};
 use ic_cdk::api::call::call;
 
@@ -58,7 +63,7 @@ struct SwapResponse {
 #[derive(Debug, Clone, CandidType, Deserialize)]
 enum SwapResult {
     Success(SwapResponse),
-    Error(String),
+    Error(ApiError),
 }
 
 #[derive(Debug, Clone, CandidType, Deserialize)]
@@ -86,63 +91,77 @@ struct GetSwapHistoryByAddressArgs {
     tick_in_wallet_address: String,
 }
 
-fn get_principal_from_pool_id(pool_id: &str) -> Result<Principal, String> {
+fn get_principal_from_pool_id(pool_id: &str) -> Result<Principal, ApiError> {
     match pool_id {
         "y0a4pk" => Ok(Principal::from_slice(b"\x00\x00\x00\x00\x01\xf0\x7f\xc5\x01\x01")),
         "39ef62" | "cg15vp" | "dat49f" => Ok(Principal::from_slice(b"\x00\x00\x00\x00\x01\xf0\x80\x56\x01\x01")),
         "a9f2k3" | "m3n8x1" | "z6y4b8" | "q7p5d2" | "h4c9v7" | "t8k1w5" => Ok(Principal::from_slice(b"\x00\x00\x00\x00\x01\xf0\x80\x78\x01\x01")),
-        _ => Err(format!("Unknown pool_id: {}", pool_id)),
+        _ => Err(ApiError::not_found(format!("pool_id {}", pool_id))),
     }
 }
 
-fn get_principal_from_chain(chain: &str) -> Result<Principal, String> {
+fn get_principal_from_chain(chain: &str) -> Result<Principal, ApiError> {
     match chain.to_lowercase().as_str() {
         "ethereum" => Ok(Principal::from_slice(b"\x00\x00\x00\x00\x01\xf0\x7f\xc5\x01\x01")),
         "binance" => Ok(Principal::from_slice(b"\x00\x00\x00\x00\x01\xf0\x80\x56\x01\x01")),
-        _ => Err(format!("Unknown chain: {}", chain)),
+        _ => Err(ApiError::invalid_argument("chain", format!("Unknown chain: {}", chain))),
+    }
+}
+
+#[ic_cdk::pre_upgrade]
+fn pre_upgrade() {
+    ic_cdk::storage::stable_save((Some(cycles::export_cycles()),))
+        .expect("Failed to save cycles ledger to stable storage");
+}
+
+#[ic_cdk::post_upgrade]
+fn post_upgrade() {
+    // Routers installed before the cycles ledger existed have nothing saved.
+    if let Ok((Some(cycles_ledger),)) = ic_cdk::storage::stable_restore::<(Option<CyclesLedger>,)>() {
+        cycles::import_cycles(cycles_ledger);
     }
 }
 
 #[ic_cdk::update]
 async fn create_transaction(
     args: CreateTransactionArgs,
-) -> Result<TransactionResponse, String> {
+) -> Result<TransactionResponse, ApiError> {
     let target_canister = get_principal_from_pool_id(&args.pool_id)?;
 
     let result: Result<(TransactionResponse,), _> =
         call(target_canister, "create_transaction", (args,)).await;
 
+    metrics::record_call("create_transaction", result.is_ok());
+
     match result {
         Ok((response,)) => Ok(response),
-        Err((code, msg)) => Err(format!(
-            "Inter-canister call failed: {:?} - {}",
-            code, msg
-        )),
+        Err((code, msg)) => Err(ApiError::call_rejected(target_canister, code as u32, msg)),
     }
 }
 
 #[ic_cdk::update]
 async fn verify_and_swap(
     args: VerifyAndSwapArgs,
-) -> Result<SwapResult, String> {
+) -> Result<SwapResult, ApiError> {
+    cycles::charge("verify_and_swap", cycles::swap_cost())?;
     let target_canister = get_principal_from_pool_id(&args.pool_id)?;
 
     let result: Result<(SwapResult,), _> =
         call(target_canister, "verify_and_swap", (args.transaction_hash,)).await;
 
+    metrics::record_call("verify_and_swap", result.is_ok());
+
     match result {
         Ok((response,)) => Ok(response),
-        Err((code, msg)) => Err(format!(
-            "Inter-canister call failed: {:?} - {}",
-            code, msg
-        )),
+        Err((code, msg)) => Err(ApiError::call_rejected(target_canister, code as u32, msg)),
     }
 }
 
 #[ic_cdk::update]
 async fn cross_chain_verify_and_swap(
     args: CrossChainVerifyAndSwapArgs,
-) -> Result<SwapResult, String> {
+) -> Result<SwapResult, ApiError> {
+    cycles::charge("cross_chain_verify_and_swap", cycles::swap_cost())?;
     let target_canister = get_principal_from_pool_id(&args.pool_id)?;
 
     let call_args = CrossChainCallArgs {
@@ -153,29 +172,51 @@ async fn cross_chain_verify_and_swap(
     let result: Result<(SwapResult,), _> =
         call(target_canister, "verify_and_swap", (call_args,)).await;
 
+    metrics::record_call("cross_chain_verify_and_swap", result.is_ok());
+
     match result {
         Ok((response,)) => Ok(response),
-        Err((code, msg)) => Err(format!(
-            "Inter-canister call failed: {:?} - {}",
-            code, msg
-        )),
+        Err((code, msg)) => Err(ApiError::call_rejected(target_canister, code as u32, msg)),
     }
 }
 
 #[ic_cdk::update]
 async fn get_swap_history_by_address(
     args: GetSwapHistoryByAddressArgs,
-) -> Result<Vec<SwapResponse>, String> {
+) -> Result<Vec<SwapResponse>, ApiError> {
     let target_canister = get_principal_from_chain(&args.chain)?;
 
     let result: Result<(Vec<SwapResponse>,), _> =
         call(target_canister, "get_swap_history_by_address", (args.tick_in_wallet_address,)).await;
 
+    metrics::record_call("get_swap_history_by_address", result.is_ok());
+
     match result {
         Ok((history,)) => Ok(history),
-        Err((code, msg)) => Err(format!(
-            "Inter-canister call failed: {:?} - {}",
-            code, msg
-        )),
+        Err((code, msg)) => Err(ApiError::call_rejected(target_canister, code as u32, msg)),
+    }
+}
+
+#[ic_cdk::query]
+fn get_cycles_budget() -> CyclesBudget {
+    cycles::get_budget()
+}
+
+#[ic_cdk::update]
+fn set_cycles_budget(budget: CyclesBudget) -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can set the cycles budget".to_string());
     }
-}
\ No newline at end of file
+
+    cycles::set_budget(budget)
+}
+
+#[ic_cdk::query]
+fn get_cycles_usage() -> Vec<ActionCycles> {
+    cycles::get_usage()
+}
+
+#[ic_cdk::query]
+fn http_request(request: bit10_core::metrics::HttpRequest) -> bit10_core::metrics::HttpResponse {
+    metrics::http_request(request)
+}
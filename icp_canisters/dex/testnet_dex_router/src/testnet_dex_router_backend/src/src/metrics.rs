@@ -0,0 +1,49 @@
+use crate::cycles;
+use bit10_core::metrics::{self, HealthReport, HttpRequest, HttpResponse, MetricsEncoder};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+thread_local! {
+    // (method, succeeded) -> forwarded calls
+    static CALLS: RefCell<BTreeMap<(&'static str, bool), u64>> = RefCell::new(BTreeMap::new());
+}
+
+pub fn record_call(method: &'static str, succeeded: bool) {
+    CALLS.with(|c| *c.borrow_mut().entry((method, succeeded)).or_default() += 1);
+}
+
+pub fn http_request(request: HttpRequest) -> HttpResponse {
+    match request.path() {
+        "/metrics" => encode_metrics().into_response(),
+        "/health" => HealthReport::new(ic_cdk::api::time()).into_response(),
+        _ => HttpResponse::not_found(),
+    }
+}
+
+fn encode_metrics() -> MetricsEncoder {
+    let mut encoder = MetricsEncoder::new();
+    metrics::encode_canister(
+        &mut encoder,
+        ic_cdk::api::canister_balance128(),
+        ic_cdk::api::stable::stable_size() * 65536,
+    );
+    CALLS.with(|c| {
+        let calls = c.borrow();
+        let labels: Vec<([(&str, &str); 2], f64)> = calls
+            .iter()
+            .map(|((method, succeeded), count)| {
+                let outcome = if *succeeded { "success" } else { "failure" };
+                ([("method", *method), ("outcome", outcome)], *count as f64)
+            })
+            .collect();
+        let samples: Vec<(&[(&str, &str)], f64)> =
+            labels.iter().map(|(labels, count)| (&labels[..], *count)).collect();
+        encoder.counter_vec(
+            "bit10_router_calls_total",
+            "Calls forwarded to pool canisters by method and outcome.",
+            &samples,
+        );
+    });
+    cycles::encode_metrics(&mut encoder);
+    encoder
+}
@@ -0,0 +1,84 @@
+use crate::events;
+use crate::{BorrowResponseData, LendResponseData, BORROW_HISTORY, LEND_HISTORY};
+use bit10_core::certification::{candid_hash, list_hash, Certified, CertifiedTree};
+use std::cell::RefCell;
+
+pub const TOTAL_LIQUIDITY_LABEL: &[u8] = b"total_liquidity";
+pub const LEND_HISTORY_LABEL: &[u8] = b"lend_history";
+pub const BORROW_HISTORY_LABEL: &[u8] = b"borrow_history";
+
+thread_local! {
+    /// Everything the canister certifies: the event log's tip, the
+    /// `total_liquidity` result and the lend and borrow histories as
+    /// `list_hash` chains.
+    static TREE: RefCell<CertifiedTree> = RefCell::new(CertifiedTree::default());
+}
+
+fn update(f: impl FnOnce(&mut CertifiedTree)) {
+    TREE.with(|tree| {
+        let mut tree = tree.borrow_mut();
+        f(&mut tree);
+        ic_cdk::api::set_certified_data(&tree.root_hash());
+    });
+}
+
+/// Derives the whole tree from state. Certified data does not survive an
+/// upgrade, so this runs on restore; everything else updates single labels.
+pub fn certify_all() {
+    update(|tree| {
+        *tree = CertifiedTree::default();
+        events::certify_tip(tree);
+        tree.insert(TOTAL_LIQUIDITY_LABEL, candid_hash(&crate::liquidity_data()));
+        tree.insert(LEND_HISTORY_LABEL, LEND_HISTORY.with(|h| list_hash(h.borrow().as_slice())));
+        tree.insert(BORROW_HISTORY_LABEL, BORROW_HISTORY.with(|h| list_hash(h.borrow().as_slice())));
+    });
+}
+
+pub fn certify_tip() {
+    update(events::certify_tip);
+}
+
+/// Liquidity depends on the supported tokens as well as on the histories.
+pub fn certify_total_liquidity() {
+    let liquidity = candid_hash(&crate::liquidity_data());
+    update(|tree| tree.insert(TOTAL_LIQUIDITY_LABEL, liquidity));
+}
+
+pub fn append_lend(lend: &LendResponseData) {
+    let liquidity = candid_hash(&crate::liquidity_data());
+    update(|tree| {
+        tree.append(LEND_HISTORY_LABEL, lend);
+        tree.insert(TOTAL_LIQUIDITY_LABEL, liquidity);
+    });
+}
+
+pub fn append_borrow(borrow: &BorrowResponseData) {
+    let liquidity = candid_hash(&crate::liquidity_data());
+    update(|tree| {
+        tree.append(BORROW_HISTORY_LABEL, borrow);
+        tree.insert(TOTAL_LIQUIDITY_LABEL, liquidity);
+    });
+}
+
+/// Withdrawals close a lend in place, so the chain has to be folded again
+/// from the start.
+pub fn certify_lend_history() {
+    let lend_history = LEND_HISTORY.with(|h| list_hash(h.borrow().as_slice()));
+    let liquidity = candid_hash(&crate::liquidity_data());
+    update(|tree| {
+        tree.insert(LEND_HISTORY_LABEL, lend_history);
+        tree.insert(TOTAL_LIQUIDITY_LABEL, liquidity);
+    });
+}
+
+pub fn tip_hash_tree() -> Vec<u8> {
+    TREE.with(|tree| bit10_core::events::tip_hash_tree(&tree.borrow()))
+}
+
+pub fn certified<T>(data: T, label: &[u8]) -> Certified<T> {
+    Certified {
+        data,
+        certificate: ic_cdk::api::data_certificate(),
+        hash_tree: TREE.with(|tree| tree.borrow().witness(&[&[label]]).to_cbor()),
+    }
+}
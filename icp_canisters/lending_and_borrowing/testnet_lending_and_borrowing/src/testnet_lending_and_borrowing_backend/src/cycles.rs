@@ -0,0 +1,67 @@
+use crate::state::read_state;
+use bit10_core::cycles::{self, ActionCycles, CyclesBudget, CyclesLedger};
+use bit10_core::errors::ApiError;
+use bit10_core::metrics::{self, MetricsEncoder};
+use std::cell::RefCell;
+
+// A borrow prices its collateral from an HTTP link.
+const BORROW_OUTCALLS: u32 = 1;
+// An Ethereum lend reads the transaction and its receipt.
+const ETH_LEND_OUTCALLS: u32 = 2;
+// A withdrawal reads the nonce and fees, then broadcasts the signed transfer.
+const ETH_WITHDRAW_OUTCALLS: u32 = 3;
+
+thread_local! {
+    static CYCLES: RefCell<CyclesLedger> = RefCell::new(CyclesLedger::default());
+}
+
+pub fn export_cycles() -> CyclesLedger {
+    CYCLES.with(|c| c.borrow().clone())
+}
+
+pub fn import_cycles(ledger: CyclesLedger) {
+    CYCLES.with(|c| *c.borrow_mut() = ledger);
+}
+
+pub fn borrow_cost() -> u128 {
+    cycles::json_rpc_cost(BORROW_OUTCALLS)
+}
+
+pub fn eth_lend_cost() -> u128 {
+    cycles::json_rpc_cost(ETH_LEND_OUTCALLS)
+}
+
+pub fn eth_withdraw_cost() -> u128 {
+    let key_name = read_state(|s| s.ecdsa_key_id().name);
+    cycles::json_rpc_cost(ETH_WITHDRAW_OUTCALLS) + cycles::signature_cost(&key_name)
+}
+
+/// Charges the caller for `action` before its outcalls are made. Controllers
+/// are not limited.
+pub fn charge(action: &str, cost: u128) -> Result<(), ApiError> {
+    let caller = ic_cdk::caller();
+    CYCLES.with(|c| {
+        let mut ledger = c.borrow_mut();
+        if ic_cdk::api::is_controller(&caller) {
+            ledger.record(action, cost);
+            return Ok(());
+        }
+        ledger.charge(caller, action, cost, ic_cdk::api::time())
+    })
+}
+
+pub fn get_budget() -> CyclesBudget {
+    CYCLES.with(|c| c.borrow().budget().clone())
+}
+
+pub fn set_budget(budget: CyclesBudget) -> Result<(), String> {
+    CYCLES.with(|c| c.borrow_mut().set_budget(budget))
+}
+
+pub fn get_usage() -> Vec<ActionCycles> {
+    CYCLES.with(|c| c.borrow().actions().cloned().collect())
+}
+
+pub fn encode_metrics(encoder: &mut MetricsEncoder) {
+    CYCLES.with(|c| metrics::encode_cycles(encoder, &c.borrow()));
+}
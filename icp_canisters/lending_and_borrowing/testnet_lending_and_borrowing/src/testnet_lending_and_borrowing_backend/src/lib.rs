@@ -1,6 +1,12 @@
 mod ecdsa;
 mod bsc_wallet;
+mod certified;
+mod cycles;
 mod ethereum_wallet;
+mod events;
+mod metrics;
+mod nonces;
+mod registry;
 mod state;
 mod utils;
 
@@ -29,9 +35,10 @@ use num_traits::ToPrimitive;
 use ciborium::from_reader;
 use crate::bsc_wallet::BscWallet;
 use crate::ethereum_wallet::EthereumWallet;
-use crate::state::{init_state, read_state, InitArg, BscNetwork, EthereumNetwork, EcdsaKeyName};
+use crate::registry::{RegistryEvent, TokenRegistry};
+use crate::state::{init_state, mutate_state, read_state, InitArg, BscNetwork, EthereumNetwork, EcdsaKeyName};
 use crate::utils::{
-    http::{make_http_request, call_rpc_with_retry_eth, get_rpc_url_eth},
+    http::{make_http_request, call_rpc_with_retry_eth},
     tokens::{
         get_supported_lending_tokens, get_supported_pairs, get_token_price_from_price_feed_canister,
         get_token_price_from_http_link
@@ -39,10 +46,10 @@ use crate::utils::{
     transactions::{
         get_transaction_by_hash_ethereum, get_transaction_receipt_ethereum,
         send_erc20_token_from_canister, send_native_eth_from_canister,
-        get_transaction_count_ethereum,
+        get_transaction_count_ethereum, get_balance_ethereum, get_dynamic_fees,
     },
     constants::{
-        PLATFORM_ICP_WALLET_PRINCIPAL, PRICE_FEED_CANISTER_ID, TATUM_API_KEY_ETHEREUM,
+        PLATFORM_ICP_WALLET_PRINCIPAL, PRICE_FEED_CANISTER_ID,
         PLATFORM_ETHEREUM_WALLET_ADDRESS,
     },
     validation::{
@@ -60,24 +67,27 @@ thread_local! {
     );
     static CACHED_BSC_ADDRESS: RefCell<Option<String>> = RefCell::new(None);
     static CACHED_ETH_ADDRESS: RefCell<Option<String>> = RefCell::new(None);
+    static DEPOSIT_REGISTRY: RefCell<DepositRegistry> = RefCell::new(DepositRegistry::default());
 }
 
-#[derive(CandidType, Deserialize, Debug, Clone, serde::Serialize)]
-pub struct Token {
-    pub token_id: Option<String>,
-    pub token_name: String,
-    pub token_symbol: String,
-    pub token_address: Option<String>,
-    pub token_chain: String,
-    pub token_decimals: u8,
-    pub is_native: bool,
-    pub is_active: bool,
-    pub price_feed_id: Option<String>,
-    pub price_feed_link: Option<String>,
-}
+pub use bit10_core::types::token::Token;
+use bit10_core::deposits::{deposit_key, DepositClaim, DepositRegistry};
+use bit10_core::errors::ApiError;
+use bit10_core::certification::Certified;
+use bit10_core::cycles::{ActionCycles, CyclesBudget, CyclesLedger};
+use bit10_core::metrics::EVM_TRANSFER_GAS;
+use bit10_core::events::{
+    ArchiveInfo, BorrowEvent, DataCertificate, Event, EventLog, GetArchivesArgs, GetBlocksRequest, GetBlocksResult,
+    LendEvent, LendWithdrawEvent, SupportedBlockType, TokenTransfer,
+};
+use bit10_core::nonce::NonceManager;
+use bit10_core::providers::{ProviderSet, ProviderSetView, RpcProvider};
+use bit10_core::transform::canonical_body;
 
+/// A borrowable token and the collateral it is lent against, unlike the swap
+/// pool `Pair` in `bit10_core`.
 #[derive(CandidType, Deserialize, Debug, Clone, serde::Serialize)]
-pub struct Pair {
+pub struct LendingPair {
     pub borrow_token_chain: String,
     pub borrow_token_address: String,
     pub borrow_token_id: String,
@@ -128,10 +138,36 @@ struct LendResponseData {
     pub closed_at: Option<String>,
 }
 
+impl LendResponseData {
+    fn to_event(&self) -> Event {
+        Event::Lend(LendEvent {
+            lend_id: self.lend_id.clone(),
+            lender: self.lender_address.clone(),
+            deposit: TokenTransfer::new(&self.token_chain, &self.token_address, &self.token_amount, &self.token_sent_trx_hash),
+            interest_rate: self.interest_rate.clone(),
+        })
+    }
+
+    /// `None` until the lend has been withdrawn.
+    fn to_withdraw_event(&self) -> Option<Event> {
+        let return_amount = self.return_amount.as_ref()?;
+        Some(Event::LendWithdraw(LendWithdrawEvent {
+            lend_id: self.lend_id.clone(),
+            lender: self.lender_address.clone(),
+            withdrawal: TokenTransfer::new(
+                &self.token_chain,
+                &self.token_address,
+                return_amount,
+                self.return_trx_hash.as_deref().unwrap_or_default(),
+            ),
+        }))
+    }
+}
+
 #[derive(CandidType, serde::Serialize)]
 enum LendResponse {
     Ok(LendResponseData),
-    Err(String),
+    Err(ApiError),
 }
 
 #[derive(CandidType, Deserialize, Clone)]
@@ -168,10 +204,32 @@ struct BorrowResponseData {
     pub closed_at: Option<String>,
 }
 
+impl BorrowResponseData {
+    fn to_event(&self) -> Event {
+        Event::Borrow(BorrowEvent {
+            borrow_id: self.borrow_id.clone(),
+            borrower: self.borrower_address.clone(),
+            borrowed: TokenTransfer::new(
+                &self.borrow_token_chain,
+                &self.borrow_token_address,
+                &self.borrow_token_amount,
+                &self.borrow_trx_hash,
+            ),
+            collateral: TokenTransfer::new(
+                &self.collateral_token_chain,
+                &self.collateral_token_address,
+                &self.collateral_token_amount,
+                &self.collateral_trx_hash,
+            ),
+            interest_rate: self.interest_rate.clone(),
+        })
+    }
+}
+
 #[derive(CandidType, serde::Serialize)]
 enum BorrowResponse {
     Ok(BorrowResponseData),
-    Err(String),
+    Err(ApiError),
 }
 
 #[derive(CandidType, Deserialize, Debug, Clone, serde::Serialize)]
@@ -199,8 +257,14 @@ fn pre_upgrade_hook() {
     let borrow_history = BORROW_HISTORY.with(|h| h.borrow().clone());
     let bsc_addr = CACHED_BSC_ADDRESS.with(|addr| addr.borrow().clone());
     let eth_addr = CACHED_ETH_ADDRESS.with(|addr| addr.borrow().clone());
-
-    ic_cdk::storage::stable_save((lend_history, borrow_history, bsc_addr, eth_addr))
+    let token_registry = Some(registry::export_registry());
+    let deposits = Some(DEPOSIT_REGISTRY.with(|d| d.borrow().clone()));
+    let rpc_providers = read_state(|s| s.configured_rpc_providers());
+    let event_log = Some(events::export_events());
+    let cycles_ledger = Some(cycles::export_cycles());
+    let nonce_manager = Some(nonces::export_nonces());
+
+    ic_cdk::storage::stable_save((lend_history, borrow_history, bsc_addr, eth_addr, token_registry, deposits, rpc_providers, event_log, cycles_ledger, nonce_manager))
         .expect("Failed to save data to stable storage");
 }
 
@@ -210,19 +274,42 @@ fn post_upgrade_hook(maybe_init: Option<InitArg>) {
         init_state(init_arg);
     }
 
-    if let Ok((lend_history, borrow_history, bsc_addr, eth_addr)) =
+    if let Ok((lend_history, borrow_history, bsc_addr, eth_addr, token_registry, deposits, rpc_providers, event_log, cycles_ledger, nonce_manager)) =
         ic_cdk::storage::stable_restore::<(
             Vec<LendResponseData>,
             Vec<BorrowResponseData>,
             Option<String>,
             Option<String>,
+            Option<TokenRegistry>,
+            Option<DepositRegistry>,
+            Option<ProviderSet>,
+            Option<EventLog>,
+            Option<CyclesLedger>,
+            Option<NonceManager>,
         )>()
     {
+        // Lends and borrows recorded before the event log existed are replayed into it once.
+        let event_log = event_log.unwrap_or_else(|| backfill_events(&lend_history, &borrow_history));
+        events::import_events(event_log);
         LEND_HISTORY.with(|h| *h.borrow_mut() = lend_history);
         BORROW_HISTORY.with(|h| *h.borrow_mut() = borrow_history);
         CACHED_BSC_ADDRESS.with(|addr| *addr.borrow_mut() = bsc_addr);
         CACHED_ETH_ADDRESS.with(|addr| *addr.borrow_mut() = eth_addr);
+        if let Some(token_registry) = token_registry {
+            registry::import_registry(token_registry);
+        }
+        DEPOSIT_REGISTRY.with(|d| *d.borrow_mut() = deposits.unwrap_or_default());
+        mutate_state(|s| s.set_configured_rpc_providers(rpc_providers));
+        if let Some(cycles_ledger) = cycles_ledger {
+            cycles::import_cycles(cycles_ledger);
+        }
+        if let Some(nonce_manager) = nonce_manager {
+            nonces::import_nonces(nonce_manager);
+        }
+        certified::certify_all();
     }
+    start_gas_checks();
+    start_nonce_reconciliation();
 }
 
 #[init]
@@ -230,6 +317,41 @@ pub fn init(maybe_init: Option<InitArg>) {
     if let Some(init_arg) = maybe_init {
         init_state(init_arg)
     }
+    start_gas_checks();
+    start_nonce_reconciliation();
+}
+
+const GAS_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(600);
+const NONCE_RECONCILE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(120);
+
+fn start_nonce_reconciliation() {
+    ic_cdk_timers::set_timer_interval(NONCE_RECONCILE_INTERVAL, || {
+        ic_cdk::futures::spawn(async {
+            if let Err(e) = utils::transactions::reconcile_nonces().await {
+                ic_cdk::println!("Nonce reconciliation failed: {}", e);
+            }
+        })
+    });
+}
+
+fn start_gas_checks() {
+    ic_cdk_timers::set_timer_interval(GAS_CHECK_INTERVAL, || ic_cdk::futures::spawn(check_gas_balance()));
+}
+
+/// Withdrawals and borrows are paid from the canister's Ethereum wallet, so
+/// `/health` degrades once it cannot cover a token transfer.
+async fn check_gas_balance() {
+    let address = ethereum_address().await;
+    match (get_balance_ethereum(&address).await, get_dynamic_fees().await) {
+        (Ok(balance), Ok((max_fee_per_gas, _))) => {
+            metrics::record_gas("Ethereum", balance, max_fee_per_gas * EVM_TRANSFER_GAS)
+        }
+        (Err(e), _) | (_, Err(e)) => ic_cdk::println!("Failed to read Ethereum gas balance: {}", e),
+    }
+}
+
+pub(crate) fn deposits_in_flight() -> usize {
+    DEPOSIT_REGISTRY.with(|d| bit10_core::metrics::deposits_in_flight(&d.borrow()))
 }
 
 #[query]
@@ -238,6 +360,146 @@ fn supported_lending_tokens() -> String {
     serde_json::json!({ "token": tokens }).to_string()
 }
 
+#[query]
+fn supported_pairs() -> Vec<LendingPair> {
+    get_supported_pairs()
+}
+
+#[query]
+fn get_registry_version() -> u64 {
+    registry::get_registry_version()
+}
+
+#[query]
+fn get_registry_events(offset: u64, limit: u64) -> Vec<RegistryEvent> {
+    registry::get_registry_events(offset, limit)
+}
+
+#[query]
+fn get_rpc_config() -> Result<ProviderSetView, String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can read the RPC config".to_string());
+    }
+
+    Ok(read_state(|s| s.rpc_providers().view()))
+}
+
+#[update]
+fn set_rpc_providers(rpc_providers: Vec<RpcProvider>, quorum: u8) -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can set RPC providers".to_string());
+    }
+
+    mutate_state(|s| s.rpc_providers_mut().replace_providers(rpc_providers, quorum))
+}
+
+#[update]
+fn set_rpc_api_key(provider: String, api_key: Option<String>) -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can set RPC API keys".to_string());
+    }
+
+    mutate_state(|s| s.rpc_providers_mut().set_api_key(&provider, api_key))
+}
+
+#[update]
+fn reset_rpc_providers() -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can reset RPC providers".to_string());
+    }
+
+    mutate_state(|s| s.set_configured_rpc_providers(None));
+    Ok(())
+}
+
+#[update]
+fn release_deposit(key: String) -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can release deposits".to_string());
+    }
+
+    if DEPOSIT_REGISTRY.with(|d| d.borrow_mut().release(&key)) {
+        Ok(())
+    } else {
+        Err(format!("Deposit {} is not claimed", key))
+    }
+}
+
+#[query]
+fn get_cycles_budget() -> CyclesBudget {
+    cycles::get_budget()
+}
+
+#[update]
+fn set_cycles_budget(budget: CyclesBudget) -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can set the cycles budget".to_string());
+    }
+
+    cycles::set_budget(budget)
+}
+
+#[query]
+fn get_cycles_usage() -> Vec<ActionCycles> {
+    cycles::get_usage()
+}
+
+#[update]
+fn add_token(token: Token) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    if !ic_cdk::api::is_controller(&caller) {
+        return Err("Only controllers can add tokens".to_string());
+    }
+
+    let version = registry::add_token(caller, token)?;
+    certified::certify_total_liquidity();
+    Ok(version)
+}
+
+#[update]
+fn update_token(token: Token) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    if !ic_cdk::api::is_controller(&caller) {
+        return Err("Only controllers can update tokens".to_string());
+    }
+
+    let version = registry::update_token(caller, token)?;
+    certified::certify_total_liquidity();
+    Ok(version)
+}
+
+#[update]
+fn set_token_active(token_chain: String, token_address: String, is_active: bool) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    if !ic_cdk::api::is_controller(&caller) {
+        return Err("Only controllers can enable or disable tokens".to_string());
+    }
+
+    let version = registry::set_token_active(caller, &token_chain, &token_address, is_active)?;
+    certified::certify_total_liquidity();
+    Ok(version)
+}
+
+#[update]
+fn add_pair(pair: LendingPair) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    if !ic_cdk::api::is_controller(&caller) {
+        return Err("Only controllers can add pairs".to_string());
+    }
+
+    registry::add_pair(caller, pair)
+}
+
+#[update]
+fn remove_pair(borrow_token_id: String, collateral_token_id: String) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    if !ic_cdk::api::is_controller(&caller) {
+        return Err("Only controllers can remove pairs".to_string());
+    }
+
+    registry::remove_pair(caller, &borrow_token_id, &collateral_token_id)
+}
+
 #[update]
 pub async fn bsc_address() -> String {
     let cached = CACHED_BSC_ADDRESS.with(|addr| addr.borrow().clone());
@@ -268,6 +530,47 @@ pub async fn ethereum_address() -> String {
     address
 }
 
+fn backfill_events(lend_history: &[LendResponseData], borrow_history: &[BorrowResponseData]) -> EventLog {
+    let parse = |timestamp: &str| timestamp.parse::<u64>().unwrap_or_default();
+    let mut records: Vec<(u64, Event)> = Vec::new();
+    for lend in lend_history {
+        records.push((parse(&lend.opened_at), lend.to_event()));
+        if let Some(event) = lend.to_withdraw_event() {
+            records.push((parse(lend.return_timestamp.as_deref().unwrap_or_default()), event));
+        }
+    }
+    for borrow in borrow_history {
+        records.push((parse(&borrow.opened_at), borrow.to_event()));
+    }
+    records.sort_by_key(|(timestamp, _)| *timestamp);
+
+    let mut log = EventLog::default();
+    for (timestamp, event) in records {
+        log.append(event, timestamp);
+    }
+    log
+}
+
+#[query]
+fn icrc3_get_blocks(args: Vec<GetBlocksRequest>) -> GetBlocksResult {
+    events::get_blocks(args)
+}
+
+#[query]
+fn icrc3_get_tip_certificate() -> Option<DataCertificate> {
+    events::tip_certificate()
+}
+
+#[query]
+fn icrc3_get_archives(_args: GetArchivesArgs) -> Vec<ArchiveInfo> {
+    Vec::new()
+}
+
+#[query]
+fn icrc3_supported_block_types() -> Vec<SupportedBlockType> {
+    events::supported_block_types()
+}
+
 #[query]
 fn get_lend_history() -> Vec<LendResponseData> {
     LEND_HISTORY.with(|h| h.borrow().clone())
@@ -290,6 +593,16 @@ fn get_borrow_history() -> Vec<BorrowResponseData> {
     BORROW_HISTORY.with(|h| h.borrow().clone())
 }
 
+#[query]
+fn get_certified_lend_history() -> Certified<Vec<LendResponseData>> {
+    certified::certified(LEND_HISTORY.with(|h| h.borrow().clone()), certified::LEND_HISTORY_LABEL)
+}
+
+#[query]
+fn get_certified_borrow_history() -> Certified<Vec<BorrowResponseData>> {
+    certified::certified(BORROW_HISTORY.with(|h| h.borrow().clone()), certified::BORROW_HISTORY_LABEL)
+}
+
 #[query]
 fn get_borrow_history_by_address(address: String) -> Vec<BorrowResponseData> {
     let search_address = address.to_lowercase();
@@ -304,6 +617,15 @@ fn get_borrow_history_by_address(address: String) -> Vec<BorrowResponseData> {
 
 #[query]
 fn total_liquidity() -> Vec<LiquidityData> {
+    liquidity_data()
+}
+
+#[query]
+fn get_certified_total_liquidity() -> Certified<Vec<LiquidityData>> {
+    certified::certified(liquidity_data(), certified::TOTAL_LIQUIDITY_LABEL)
+}
+
+fn liquidity_data() -> Vec<LiquidityData> {
     let supported_tokens = get_supported_lending_tokens();
     let mut liquidity_data = Vec::new();
 
@@ -356,30 +678,30 @@ async fn icp_lend(args: LendArgs) -> LendResponse {
     let caller = ic_cdk::caller();
 
     if !args.token_chain.eq_ignore_ascii_case("icp") {
-        return LendResponse::Err(format!(
+        return LendResponse::Err(ApiError::invalid_argument("token_chain", format!(
             "Invalid token chain. Expected 'ICP', got '{}'",
             args.token_chain
-        ));
+        )));
     }
 
     let lender_principal = match validate_principal_address(&args.lender_address) {
         Ok(p) => p,
-        Err(e) => return LendResponse::Err(format!("Invalid lender address: {}", e)),
+        Err(e) => return LendResponse::Err(ApiError::invalid_argument("lender_address", e)),
     };
 
     if caller != lender_principal {
-        return LendResponse::Err(
-            "Unauthorized: Caller must be the same as lender_address for ICP lending".to_string(),
-        );
+        return LendResponse::Err(ApiError::unauthorized(
+            "Caller must be the same as lender_address for ICP lending",
+        ));
     }
 
     let token_amount_decimal = match parse_decimal_amount(&args.token_amount) {
         Ok(d) => d,
-        Err(e) => return LendResponse::Err(format!("Invalid token amount: {}", e)),
+        Err(e) => return LendResponse::Err(ApiError::invalid_argument("token_amount", e)),
     };
 
     if token_amount_decimal <= Decimal::ZERO {
-        return LendResponse::Err("Token amount must be greater than 0".to_string());
+        return LendResponse::Err(ApiError::invalid_argument("token_amount", "must be greater than 0"));
     }
 
     let token = match validate_token_on_chain(
@@ -388,20 +710,20 @@ async fn icp_lend(args: LendArgs) -> LendResponse {
         &get_supported_lending_tokens(),
     ) {
         Ok(t) => t,
-        Err(e) => return LendResponse::Err(e),
+        Err(e) => return LendResponse::Err(e.into()),
     };
 
     if !token.is_active {
-        return LendResponse::Err(format!(
+        return LendResponse::Err(ApiError::invalid_argument("token_address", format!(
             "Token '{}' is currently inactive for lending",
             token.token_symbol
-        ));
+        )));
     }
 
     let lend_id = generate_uuid_without_hyphens().await;
 
     let amount_nat = decimal_to_scaled_nat(token_amount_decimal, token.token_decimals)
-        .map_err(|e| LendResponse::Err(format!("Failed to scale token amount: {}", e)))
+        .map_err(|e| LendResponse::Err(format!("Failed to scale token amount: {}", e).into()))
         .unwrap();
 
     let to_account = Account {
@@ -424,7 +746,7 @@ async fn icp_lend(args: LendArgs) -> LendResponse {
 
     let ledger_principal = match validate_principal_address(&args.token_address) {
         Ok(p) => p,
-        Err(e) => return LendResponse::Err(format!("Invalid token ledger principal: {}", e)),
+        Err(e) => return LendResponse::Err(format!("Invalid token ledger principal: {}", e).into()),
     };
 
     let transfer_result: Result<(Result<BlockIndex, TransferFromError>,), _> =
@@ -432,8 +754,8 @@ async fn icp_lend(args: LendArgs) -> LendResponse {
 
     let block_index = match transfer_result {
         Ok((Ok(index),)) => index,
-        Ok((Err(e),)) => return LendResponse::Err(format!("ICP transfer failed: {:?}", e)),
-        Err(e) => return LendResponse::Err(format!("ICP inter-canister call failed: {:?}", e)),
+        Ok((Err(e),)) => return LendResponse::Err(ApiError::transfer_failed(format!("{:?}", e))),
+        Err((code, msg)) => return LendResponse::Err(ApiError::call_rejected(ledger_principal, code as u32, msg)),
     };
 
     let lend_data = LendResponseData {
@@ -453,6 +775,8 @@ async fn icp_lend(args: LendArgs) -> LendResponse {
     };
 
     LEND_HISTORY.with(|h| h.borrow_mut().push(lend_data.clone()));
+    certified::append_lend(&lend_data);
+    events::record_event(lend_data.to_event());
 
     LendResponse::Ok(lend_data)
 }
@@ -464,36 +788,36 @@ async fn icp_lend_withdraw(lend_id: String) -> LendResponse {
 
     let lend_index = match lend_history.iter().position(|lend| lend.lend_id == lend_id) {
         Some(index) => index,
-        None => return LendResponse::Err(format!("Lend ID '{}' not found in history", lend_id)),
+        None => return LendResponse::Err(ApiError::not_found(&lend_id)),
     };
 
     let mut lend_data = lend_history[lend_index].clone();
 
     if lend_data.status != "Active" {
-        return LendResponse::Err(format!(
+        return LendResponse::Err(ApiError::invalid_argument("lend_id", format!(
             "Lend ID '{}' is not active. Current status: {}",
             lend_id, lend_data.status
-        ));
+        )));
     }
 
     let caller_principal_str = caller.to_text();
     if !lend_data.lender_address.eq_ignore_ascii_case(&caller_principal_str) {
-        return LendResponse::Err(format!(
+        return LendResponse::Err(ApiError::unauthorized(format!(
             "Unauthorized: Caller '{}' does not match lender address '{}'",
             caller_principal_str, lend_data.lender_address
-        ));
+        )));
     }
 
     if !lend_data.token_chain.eq_ignore_ascii_case("icp") {
-        return LendResponse::Err(format!(
+        return LendResponse::Err(ApiError::invalid_argument("lend_id", format!(
             "Invalid token chain for withdrawal. Expected 'ICP', got '{}'",
             lend_data.token_chain
-        ));
+        )));
     }
 
     let current_time = ic_cdk::api::time();
     if let Err(e) = validate_timestamp_elapsed(&lend_data.opened_at, current_time, 8 * 24 * 60 * 60 + 60) {
-        return LendResponse::Err(e);
+        return LendResponse::Err(e.into());
     }
 
     let token = match validate_token_on_chain(
@@ -502,19 +826,19 @@ async fn icp_lend_withdraw(lend_id: String) -> LendResponse {
         &get_supported_lending_tokens(),
     ) {
         Ok(t) => t,
-        Err(e) => return LendResponse::Err(e),
+        Err(e) => return LendResponse::Err(e.into()),
     };
 
     let original_amount_decimal = match parse_decimal_amount(&lend_data.token_amount) {
         Ok(d) => d,
-        Err(e) => return LendResponse::Err(format!("Invalid original token amount: {}", e)),
+        Err(e) => return LendResponse::Err(format!("Invalid original token amount: {}", e).into()),
     };
 
     let return_amount_decimal = original_amount_decimal * Decimal::from_str("0.9995").unwrap();
     let return_amount_str = return_amount_decimal.to_string();
 
     let return_amount_nat = decimal_to_scaled_nat(return_amount_decimal, token.token_decimals)
-        .map_err(|e| LendResponse::Err(format!("Failed to scale return amount: {}", e)))
+        .map_err(|e| LendResponse::Err(format!("Failed to scale return amount: {}", e).into()))
         .unwrap();
 
     let to_account = Account {
@@ -533,7 +857,7 @@ async fn icp_lend_withdraw(lend_id: String) -> LendResponse {
 
     let ledger_principal = match validate_principal_address(&lend_data.token_address) {
         Ok(p) => p,
-        Err(e) => return LendResponse::Err(format!("Invalid token ledger principal: {}", e)),
+        Err(e) => return LendResponse::Err(format!("Invalid token ledger principal: {}", e).into()),
     };
 
     let transfer_result: Result<(Result<BlockIndex, TransferError>,), _> =
@@ -541,8 +865,8 @@ async fn icp_lend_withdraw(lend_id: String) -> LendResponse {
 
     let block_index = match transfer_result {
         Ok((Ok(index),)) => index,
-        Ok((Err(e),)) => return LendResponse::Err(format!("ICP transfer failed: {:?}", e)),
-        Err(e) => return LendResponse::Err(format!("ICP inter-canister call failed: {:?}", e)),
+        Ok((Err(e),)) => return LendResponse::Err(ApiError::transfer_failed(format!("{:?}", e))),
+        Err((code, msg)) => return LendResponse::Err(ApiError::call_rejected(ledger_principal, code as u32, msg)),
     };
 
     let current_time_str = current_time.to_string();
@@ -554,6 +878,10 @@ async fn icp_lend_withdraw(lend_id: String) -> LendResponse {
 
     lend_history[lend_index] = lend_data.clone();
     LEND_HISTORY.with(|h| *h.borrow_mut() = lend_history);
+    certified::certify_lend_history();
+    if let Some(event) = lend_data.to_withdraw_event() {
+        events::record_event(event);
+    }
 
     LendResponse::Ok(lend_data)
 }
@@ -562,15 +890,19 @@ async fn icp_lend_withdraw(lend_id: String) -> LendResponse {
 async fn icp_borrow(args: BorrowArgs) -> BorrowResponse {
     let caller = ic_cdk::caller();
 
+    if let Err(err) = cycles::charge("borrow", cycles::borrow_cost()) {
+        return BorrowResponse::Err(err);
+    }
+
     let borrower_principal = match validate_principal_address(&args.borrower_address) {
         Ok(p) => p,
-        Err(e) => return BorrowResponse::Err(format!("Invalid borrower address: {}", e)),
+        Err(e) => return BorrowResponse::Err(ApiError::invalid_argument("borrower_address", e)),
     };
 
     if caller != borrower_principal {
-        return BorrowResponse::Err(
-            "Unauthorized: Caller must be the same as borrower_address for ICP collateral".to_string(),
-        );
+        return BorrowResponse::Err(ApiError::unauthorized(
+            "Caller must be the same as borrower_address for ICP collateral",
+        ));
     }
 
     let borrow_token_chain = args.borrow_token_chain.to_lowercase();
@@ -586,27 +918,27 @@ async fn icp_borrow(args: BorrowArgs) -> BorrowResponse {
     }) {
         Some(p) => p,
         None => {
-            return BorrowResponse::Err(format!(
+            return BorrowResponse::Err(ApiError::invalid_argument("borrow_token_address", format!(
                 "Invalid borrowing pair. Borrow token '{}' on chain '{}' and collateral token '{}' on chain '{}' is not supported",
                 borrow_token_address, borrow_token_chain, collateral_token_address, collateral_token_chain
-            ));
+            )));
         }
     };
 
     let borrow_token_amount_decimal = match parse_decimal_amount(&args.borrow_token_amount) {
         Ok(d) => d,
-        Err(e) => return BorrowResponse::Err(format!("Invalid borrow token amount: {}", e)),
+        Err(e) => return BorrowResponse::Err(ApiError::invalid_argument("borrow_token_amount", e)),
     };
     if borrow_token_amount_decimal <= Decimal::ZERO {
-        return BorrowResponse::Err("Borrow token amount must be greater than 0".to_string());
+        return BorrowResponse::Err(ApiError::invalid_argument("borrow_token_amount", "must be greater than 0"));
     }
 
     let collateral_token_amount_decimal = match parse_decimal_amount(&args.collateral_token_amount) {
         Ok(d) => d,
-        Err(e) => return BorrowResponse::Err(format!("Invalid collateral token amount: {}", e)),
+        Err(e) => return BorrowResponse::Err(ApiError::invalid_argument("collateral_token_amount", e)),
     };
     if collateral_token_amount_decimal <= Decimal::ZERO {
-        return BorrowResponse::Err("Collateral token amount must be greater than 0".to_string());
+        return BorrowResponse::Err(ApiError::invalid_argument("collateral_token_amount", "must be greater than 0"));
     }
 
     let liquidity_data = total_liquidity();
@@ -620,11 +952,11 @@ async fn icp_borrow(args: BorrowArgs) -> BorrowResponse {
         .unwrap_or_else(Decimal::ZERO);
 
     if borrow_token_amount_decimal > available_liquidity {
-        return BorrowResponse::Err(format!(
-            "Insufficient liquidity. Requested: {}, Available: {}",
-            borrow_token_amount_decimal,
-            available_liquidity
-        ));
+        return BorrowResponse::Err(ApiError::InsufficientLiquidity {
+            token: borrow_token_address.clone(),
+            available: available_liquidity.to_string(),
+            required: borrow_token_amount_decimal.to_string(),
+        });
     }
 
     let borrow_token = match validate_token_on_chain(
@@ -633,7 +965,7 @@ async fn icp_borrow(args: BorrowArgs) -> BorrowResponse {
         &get_supported_lending_tokens(),
     ) {
         Ok(t) => t,
-        Err(e) => return BorrowResponse::Err(e),
+        Err(e) => return BorrowResponse::Err(e.into()),
     };
 
     let collateral_token = match validate_token_on_chain(
@@ -657,7 +989,7 @@ async fn icp_borrow(args: BorrowArgs) -> BorrowResponse {
                     price_feed_link: Some(pair.collateral_price_feed_link.clone()),
                 }
             } else {
-                return BorrowResponse::Err(e);
+                return BorrowResponse::Err(e.into());
             }
         },
     };
@@ -665,21 +997,27 @@ async fn icp_borrow(args: BorrowArgs) -> BorrowResponse {
     let borrow_token_price = match get_token_price_from_price_feed_canister(borrow_token).await {
         Ok(price) => price,
         Err(e) => {
-            return BorrowResponse::Err(format!("Failed to get borrow token price: {}", e));
+            return BorrowResponse::Err(ApiError::price_feed_unavailable(&borrow_token.token_symbol, e));
         }
     };
     if borrow_token_price <= 0.0 {
-        return BorrowResponse::Err(format!("Invalid borrow token price: {}", borrow_token_price));
+        return BorrowResponse::Err(ApiError::price_feed_unavailable(
+            &borrow_token.token_symbol,
+            format!("Invalid borrow token price: {}", borrow_token_price),
+        ));
     }
 
     let collateral_token_price = match get_token_price_from_http_link(&collateral_token).await {
         Ok(price) => price,
         Err(e) => {
-            return BorrowResponse::Err(format!("Failed to get collateral token price: {}", e));
+            return BorrowResponse::Err(ApiError::price_feed_unavailable(&collateral_token.token_symbol, e));
         }
     };
     if collateral_token_price <= 0.0 {
-        return BorrowResponse::Err(format!("Invalid collateral token price: {}", collateral_token_price));
+        return BorrowResponse::Err(ApiError::price_feed_unavailable(
+            &collateral_token.token_symbol,
+            format!("Invalid collateral token price: {}", collateral_token_price),
+        ));
     }
 
     let borrow_value = borrow_token_price * borrow_token_amount_decimal;
@@ -687,10 +1025,10 @@ async fn icp_borrow(args: BorrowArgs) -> BorrowResponse {
 
     let max_borrow_value_allowed = collateral_value * Decimal::from_str("0.8").unwrap(); // 80% LTV
     if borrow_value > max_borrow_value_allowed {
-        return BorrowResponse::Err(format!(
+        return BorrowResponse::Err(ApiError::invalid_argument("collateral_token_amount", format!(
             "Insufficient collateral. Maximum borrow value allowed: ${:.8}, but requested: ${:.8}",
             max_borrow_value_allowed, borrow_value
-        ));
+        )));
     }
 
     let collateral_token_for_transfer = match validate_token_on_chain(
@@ -714,7 +1052,7 @@ async fn icp_borrow(args: BorrowArgs) -> BorrowResponse {
                     price_feed_link: Some(pair.collateral_price_feed_link.clone()),
                 }
             } else {
-                 return BorrowResponse::Err(e);
+                 return BorrowResponse::Err(e.into());
             }
         },
     };
@@ -723,12 +1061,12 @@ async fn icp_borrow(args: BorrowArgs) -> BorrowResponse {
         collateral_token_amount_decimal,
         collateral_token_for_transfer.token_decimals,
     )
-    .map_err(|e| BorrowResponse::Err(format!("Failed to scale collateral amount: {}", e)))
+    .map_err(|e| BorrowResponse::Err(format!("Failed to scale collateral amount: {}", e).into()))
     .unwrap();
 
     let collateral_ledger_principal = match validate_principal_address(&collateral_token_address) {
         Ok(p) => p,
-        Err(e) => return BorrowResponse::Err(format!("Invalid collateral ledger principal: {}", e)),
+        Err(e) => return BorrowResponse::Err(format!("Invalid collateral ledger principal: {}", e).into()),
     };
 
     let collateral_transfer_args = TransferFromArgs {
@@ -752,13 +1090,15 @@ async fn icp_borrow(args: BorrowArgs) -> BorrowResponse {
 
     let collateral_block_index = match collateral_transfer_result {
         Ok((Ok(index),)) => index,
-        Ok((Err(e),)) => return BorrowResponse::Err(format!("Collateral transfer failed: {:?}", e)),
-        Err(e) => return BorrowResponse::Err(format!("Collateral inter-canister call failed: {:?}", e)),
+        Ok((Err(e),)) => return BorrowResponse::Err(ApiError::transfer_failed(format!("Collateral transfer failed: {:?}", e))),
+        Err((code, msg)) => {
+            return BorrowResponse::Err(ApiError::call_rejected(collateral_ledger_principal, code as u32, msg))
+        }
     };
 
     let borrow_wallet_principal = match validate_principal_address(&args.borrow_wallet_address) {
         Ok(p) => p,
-        Err(e) => return BorrowResponse::Err(format!("Invalid borrow wallet address: {}", e)),
+        Err(e) => return BorrowResponse::Err(ApiError::invalid_argument("borrow_wallet_address", e)),
     };
 
     let borrow_token_for_transfer = match validate_token_on_chain(
@@ -767,19 +1107,19 @@ async fn icp_borrow(args: BorrowArgs) -> BorrowResponse {
         &get_supported_lending_tokens(),
     ) {
         Ok(t) => t,
-        Err(e) => return BorrowResponse::Err(e),
+        Err(e) => return BorrowResponse::Err(e.into()),
     };
 
     let borrow_amount_nat = decimal_to_scaled_nat(
         borrow_token_amount_decimal,
         borrow_token_for_transfer.token_decimals,
     )
-    .map_err(|e| BorrowResponse::Err(format!("Failed to scale borrow amount: {}", e)))
+    .map_err(|e| BorrowResponse::Err(format!("Failed to scale borrow amount: {}", e).into()))
     .unwrap();
 
     let borrow_ledger_principal = match validate_principal_address(&borrow_token_address) {
         Ok(p) => p,
-        Err(e) => return BorrowResponse::Err(format!("Invalid borrow token ledger principal: {}", e)),
+        Err(e) => return BorrowResponse::Err(format!("Invalid borrow token ledger principal: {}", e).into()),
     };
 
     let borrow_transfer_args = TransferArg {
@@ -799,8 +1139,8 @@ async fn icp_borrow(args: BorrowArgs) -> BorrowResponse {
 
     let borrow_block_index = match borrow_transfer_result {
         Ok((Ok(index),)) => index,
-        Ok((Err(e),)) => return BorrowResponse::Err(format!("Borrow token transfer failed: {:?}", e)),
-        Err(e) => return BorrowResponse::Err(format!("Borrow inter-canister call failed: {:?}", e)),
+        Ok((Err(e),)) => return BorrowResponse::Err(ApiError::transfer_failed(format!("Borrow token transfer failed: {:?}", e))),
+        Err((code, msg)) => return BorrowResponse::Err(ApiError::call_rejected(borrow_ledger_principal, code as u32, msg)),
     };
 
     let borrow_id = generate_uuid_without_hyphens().await;
@@ -827,6 +1167,8 @@ async fn icp_borrow(args: BorrowArgs) -> BorrowResponse {
     };
 
     BORROW_HISTORY.with(|h| h.borrow_mut().push(borrow_data.clone()));
+    certified::append_borrow(&borrow_data);
+    events::record_event(borrow_data.to_event());
 
     BorrowResponse::Ok(borrow_data)
 }
@@ -908,6 +1250,10 @@ pub async fn eth_lend_create_transaction(args: LendArgs) -> TransactionResponse
 
 #[update]
 async fn eth_lend(trx_hash: String) -> LendResponse {
+    if let Err(err) = cycles::charge("eth_lend", cycles::eth_lend_cost()) {
+        return LendResponse::Err(err);
+    }
+
     let trx_hash_lower = trx_hash.to_lowercase();
 
     let already_exists = LEND_HISTORY.with(|h| {
@@ -917,22 +1263,37 @@ async fn eth_lend(trx_hash: String) -> LendResponse {
     });
 
     if already_exists {
-        return LendResponse::Err("Transaction already processed".to_string());
+        return LendResponse::Err(ApiError::already_processed(&trx_hash_lower));
     }
 
+    let deposit_key = deposit_key("ethereum", &trx_hash_lower, None);
+    let claim = match DEPOSIT_REGISTRY.with(|d| d.borrow_mut().claim(&deposit_key, ic_cdk::api::time())) {
+        Ok(claim) => claim,
+        Err(e) => return LendResponse::Err(e.into()),
+    };
+
+    let response = record_eth_lend(trx_hash_lower, &claim).await;
+    DEPOSIT_REGISTRY.with(|d| {
+        d.borrow_mut()
+            .finish(&claim, matches!(response, LendResponse::Ok(_)), ic_cdk::api::time())
+    });
+    response
+}
+
+async fn record_eth_lend(trx_hash_lower: String, claim: &DepositClaim) -> LendResponse {
     let tx = match get_transaction_by_hash_ethereum(&trx_hash_lower).await {
         Ok(transaction) => transaction,
-        Err(e) => return LendResponse::Err(format!("Failed to get transaction details: {}", e)),
+        Err(e) => return LendResponse::Err(ApiError::chain_unavailable("Ethereum", e)),
     };
 
     let tx_receipt = match get_transaction_receipt_ethereum(&trx_hash_lower).await {
         Ok(receipt) => receipt,
-        Err(e) => return LendResponse::Err(format!("Failed to get transaction receipt: {}", e)),
+        Err(e) => return LendResponse::Err(ApiError::chain_unavailable("Ethereum", e)),
     };
 
     let tx_status = tx_receipt.get("status").and_then(|v| v.as_str()).unwrap_or("0x0");
     if tx_status != "0x1" {
-        return LendResponse::Err("Transaction was not successful".to_string());
+        return LendResponse::Err(ApiError::deposit_invalid(&trx_hash_lower, "Transaction was not successful"));
     }
 
     let supported_tokens = get_supported_lending_tokens();
@@ -948,16 +1309,16 @@ async fn eth_lend(trx_hash: String) -> LendResponse {
             let token = supported_tokens
                 .iter()
                 .find(|t| t.is_native && t.token_chain.eq_ignore_ascii_case("ethereum"))
-                .ok_or_else(|| LendResponse::Err("Native ETH token not configured".to_string()))?;
+                .ok_or_else(|| LendResponse::Err("Native ETH token not configured".into()))?;
 
             let value_u256 = U256::from_str_radix(tx_value_hex.strip_prefix("0x").unwrap_or("0"), 16)
-                .map_err(|e| LendResponse::Err(format!("Invalid transaction value hex: {}", e)))?;
+                .map_err(|e| LendResponse::Err(format!("Invalid transaction value hex: {}", e).into()))?;
 
             let amount_decimal = u256_to_decimal_string(value_u256, token.token_decimals)
-                .map_err(LendResponse::Err)
+                .map_err(|e| LendResponse::Err(e.into()))
                 .unwrap()
                 .parse::<Decimal>()
-                .map_err(|e| LendResponse::Err(format!("Failed to parse decimal: {}", e)))?;
+                .map_err(|e| LendResponse::Err(format!("Failed to parse decimal: {}", e).into()))?;
 
             let decoded_lend_id = if tx_input_hex.len() > 2 {
                 String::from_utf8(hex::decode(&tx_input_hex[2..]).unwrap_or_default())
@@ -969,34 +1330,34 @@ async fn eth_lend(trx_hash: String) -> LendResponse {
             (token.token_address.clone().unwrap_or_default(), amount_decimal, decoded_lend_id)
         } else if tx_input_hex.starts_with("0xa9059cbb") {
             if tx_input_hex.len() < 138 {
-                return LendResponse::Err("Invalid ERC20 transfer input data length".to_string());
+                return LendResponse::Err(ApiError::deposit_invalid(&trx_hash_lower, "Invalid ERC20 transfer input data length"));
             }
 
             let recipient_padded_hex = &tx_input_hex[10..74];
             let transfer_recipient_address = format!("0x{}", &recipient_padded_hex[24..64]).to_lowercase();
 
             if !transfer_recipient_address.eq_ignore_ascii_case(&canister_eth_address_str) {
-                return LendResponse::Err(format!(
+                return LendResponse::Err(ApiError::deposit_invalid(&trx_hash_lower, format!(
                     "ERC20 transfer was not sent to canister address. Expected: {}, Got: {}",
                     canister_eth_address_str, transfer_recipient_address
-                ));
+                )));
             }
 
             let token = supported_tokens
                 .iter()
                 .find(|t| t.token_address.as_deref().map_or(false, |addr| addr.eq_ignore_ascii_case(&tx_to))
                         && t.token_chain.eq_ignore_ascii_case("ethereum"))
-                .ok_or_else(|| LendResponse::Err(format!("ERC20 token contract {} not configured", tx_to)))?;
+                .ok_or_else(|| LendResponse::Err(format!("ERC20 token contract {} not configured", tx_to).into()))?;
 
             let amount_hex = &tx_input_hex[74..138];
             let amount_u256 = U256::from_str_radix(amount_hex, 16)
-                .map_err(|e| LendResponse::Err(format!("Invalid amount in ERC20 input data: {}", e)))?;
+                .map_err(|e| LendResponse::Err(format!("Invalid amount in ERC20 input data: {}", e).into()))?;
 
             let amount_decimal = u256_to_decimal_string(amount_u256, token.token_decimals)
-                .map_err(LendResponse::Err)
+                .map_err(|e| LendResponse::Err(e.into()))
                 .unwrap()
                 .parse::<Decimal>()
-                .map_err(|e| LendResponse::Err(format!("Failed to parse decimal: {}", e)))?;
+                .map_err(|e| LendResponse::Err(format!("Failed to parse decimal: {}", e).into()))?;
 
             let decoded_lend_id = if tx_input_hex.len() > 138 {
                 let lend_id_hex = &tx_input_hex[138..];
@@ -1008,9 +1369,16 @@ async fn eth_lend(trx_hash: String) -> LendResponse {
 
             (token.token_address.clone().unwrap_or_default(), amount_decimal, decoded_lend_id)
         } else {
-            return LendResponse::Err("Transaction is neither native ETH to canister nor an ERC20 transfer".to_string());
+            return LendResponse::Err(ApiError::deposit_invalid(
+                &trx_hash_lower,
+                "Transaction is neither native ETH to canister nor an ERC20 transfer",
+            ));
         };
 
+    if let Err(e) = DEPOSIT_REGISTRY.with(|d| d.borrow_mut().start_settlement(claim, ic_cdk::api::time())) {
+        return LendResponse::Err(e.into());
+    }
+
     let new_lend_id = generate_uuid_without_hyphens().await;
 
     let lend_data = LendResponseData {
@@ -1030,33 +1398,39 @@ async fn eth_lend(trx_hash: String) -> LendResponse {
     };
 
     LEND_HISTORY.with(|h| h.borrow_mut().push(lend_data.clone()));
+    certified::append_lend(&lend_data);
+    events::record_event(lend_data.to_event());
 
     LendResponse::Ok(lend_data)
 }
 
 #[update]
 async fn eth_lend_withdraw(req: SignatureVerificationRequest) -> LendResponse {
+    if let Err(err) = cycles::charge("eth_lend_withdraw", cycles::eth_withdraw_cost()) {
+        return LendResponse::Err(err);
+    }
+
     let mut lend_history = LEND_HISTORY.with(|h| h.borrow().clone());
 
     let lend_index = match lend_history.iter().position(|lend| lend.lend_id == req.lend_id) {
         Some(index) => index,
-        None => return LendResponse::Err(format!("Lend ID '{}' not found in history", req.lend_id)),
+        None => return LendResponse::Err(ApiError::not_found(&req.lend_id)),
     };
 
     let mut lend_data = lend_history[lend_index].clone();
 
     if lend_data.status != "Active" {
-        return LendResponse::Err(format!(
+        return LendResponse::Err(ApiError::invalid_argument("lend_id", format!(
             "Lend ID '{}' is not active. Current status: {}",
             req.lend_id, lend_data.status
-        ));
+        )));
     }
 
     if !lend_data.token_chain.eq_ignore_ascii_case("ethereum") {
-        return LendResponse::Err(format!(
+        return LendResponse::Err(ApiError::invalid_argument("lend_id", format!(
             "Invalid token chain for withdrawal. Expected 'Ethereum', got '{}'",
             lend_data.token_chain
-        ));
+        )));
     }
 
     let message = format!(
@@ -1068,20 +1442,20 @@ async fn eth_lend_withdraw(req: SignatureVerificationRequest) -> LendResponse {
 
     let recovered_address = match recover_ethereum_address_from_signature(&message, &req.signature) {
         Ok(address) => address,
-        Err(e) => return LendResponse::Err(format!("Signature verification failed: {}", e)),
+        Err(e) => return LendResponse::Err(ApiError::unauthorized(format!("Signature verification failed: {}", e))),
     };
 
     let expected_address = lend_data.lender_address.to_lowercase();
     if !recovered_address.eq_ignore_ascii_case(&expected_address) {
-        return LendResponse::Err(format!(
+        return LendResponse::Err(ApiError::unauthorized(format!(
             "ACCESS DENIED! Signature verification failed. Expected address: {}, but signature was signed by: {}",
             expected_address, recovered_address
-        ));
+        )));
     }
 
     let current_time = ic_cdk::api::time();
     if let Err(e) = validate_timestamp_elapsed(&lend_data.opened_at, current_time, 8 * 24 * 60 * 60 + 60) {
-        return LendResponse::Err(e);
+        return LendResponse::Err(e.into());
     }
 
     let token = match validate_token_on_chain(
@@ -1090,40 +1464,40 @@ async fn eth_lend_withdraw(req: SignatureVerificationRequest) -> LendResponse {
         &get_supported_lending_tokens(),
     ) {
         Ok(t) => t,
-        Err(e) => return LendResponse::Err(e),
+        Err(e) => return LendResponse::Err(e.into()),
     };
 
     let original_amount_decimal = match parse_decimal_amount(&lend_data.token_amount) {
         Ok(d) => d,
-        Err(e) => return LendResponse::Err(format!("Invalid original token amount: {}", e)),
+        Err(e) => return LendResponse::Err(format!("Invalid original token amount: {}", e).into()),
     };
 
     let return_amount_decimal = original_amount_decimal * Decimal::from_str("0.9995").unwrap(); // 0.05% platform fee
     let return_amount_str = return_amount_decimal.to_string();
 
     let final_amount_u256 = decimal_to_scaled_u256(return_amount_decimal, token.token_decimals)
-        .map_err(|e| LendResponse::Err(format!("Failed to scale return amount: {}", e)))
+        .map_err(|e| LendResponse::Err(format!("Failed to scale return amount: {}", e).into()))
         .unwrap();
 
     let lender_ethereum_address = match validate_ethereum_address(&lend_data.lender_address) {
         Ok(addr) => addr,
-        Err(e) => return LendResponse::Err(format!("Invalid lender Ethereum address: {}", e)),
+        Err(e) => return LendResponse::Err(format!("Invalid lender Ethereum address: {}", e).into()),
     };
 
     let transaction_hash = if token.is_native {
         match send_native_eth_from_canister(lender_ethereum_address, final_amount_u256).await {
             Ok(tx_hash) => tx_hash,
-            Err(e) => return LendResponse::Err(format!("Failed to send native ETH: {}", e)),
+            Err(e) => return LendResponse::Err(ApiError::transfer_failed(format!("Failed to send native ETH: {}", e))),
         }
     } else {
         let token_contract = match validate_ethereum_address(&lend_data.token_address) {
             Ok(addr) => addr,
-            Err(e) => return LendResponse::Err(format!("Invalid token contract address: {}", e)),
+            Err(e) => return LendResponse::Err(format!("Invalid token contract address: {}", e).into()),
         };
 
         match send_erc20_token_from_canister(token_contract, lender_ethereum_address, final_amount_u256).await {
             Ok(tx_hash) => tx_hash,
-            Err(e) => return LendResponse::Err(format!("Failed to send ERC20 token: {}", e)),
+            Err(e) => return LendResponse::Err(ApiError::transfer_failed(format!("Failed to send ERC20 token: {}", e))),
         }
     };
 
@@ -1136,6 +1510,10 @@ async fn eth_lend_withdraw(req: SignatureVerificationRequest) -> LendResponse {
 
     lend_history[lend_index] = lend_data.clone();
     LEND_HISTORY.with(|h| *h.borrow_mut() = lend_history);
+    certified::certify_lend_history();
+    if let Some(event) = lend_data.to_withdraw_event() {
+        events::record_event(event);
+    }
 
     LendResponse::Ok(lend_data)
 }
@@ -1193,7 +1571,7 @@ fn transform(raw: TransformArgs) -> HttpResponse {
         ..Default::default()
     };
     if res.status == Nat::from(200u64) {
-        res.body = raw.response.body;
+        res.body = canonical_body(&raw.context, &raw.response.body);
     } else {
         ic_cdk::api::print(format!(
             "Received an error from the remote service:\n{:#?}",
@@ -1203,4 +1581,9 @@ fn transform(raw: TransformArgs) -> HttpResponse {
     res
 }
 
+#[query]
+fn http_request(request: bit10_core::metrics::HttpRequest) -> bit10_core::metrics::HttpResponse {
+    metrics::http_request(request)
+}
+
 ic_cdk::export_candid!();
\ No newline at end of file
@@ -0,0 +1,254 @@
+use crate::utils::tokens::{default_supported_lending_tokens, default_supported_pairs};
+use crate::{LendingPair, Token};
+use candid::{CandidType, Deserialize, Principal};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+thread_local! {
+    static TOKEN_REGISTRY: RefCell<TokenRegistry> = RefCell::new(TokenRegistry::with_defaults());
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone, serde::Serialize)]
+pub enum RegistryChange {
+    TokenAdded(Token),
+    TokenUpdated(Token),
+    TokenActiveSet {
+        token_chain: String,
+        token_address: String,
+        is_active: bool,
+    },
+    PairAdded(LendingPair),
+    PairRemoved {
+        borrow_token_id: String,
+        collateral_token_id: String,
+    },
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone, serde::Serialize)]
+pub struct RegistryEvent {
+    pub version: u64,
+    pub timestamp: u64,
+    pub caller: Principal,
+    pub change: RegistryChange,
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone, Default)]
+pub struct TokenRegistry {
+    pub version: u64,
+    pub tokens: BTreeMap<String, Token>,
+    pub pairs: BTreeMap<String, LendingPair>,
+    pub events: Vec<RegistryEvent>,
+}
+
+impl TokenRegistry {
+    pub fn with_defaults() -> Self {
+        TokenRegistry {
+            version: 0,
+            tokens: default_supported_lending_tokens()
+                .into_iter()
+                .filter_map(|token| token_key(&token).ok().map(|key| (key, token)))
+                .collect(),
+            pairs: default_supported_pairs()
+                .into_iter()
+                .map(|pair| (pair_key(&pair.borrow_token_id, &pair.collateral_token_id), pair))
+                .collect(),
+            events: Vec::new(),
+        }
+    }
+
+    fn record(&mut self, caller: Principal, change: RegistryChange) -> u64 {
+        self.version += 1;
+        self.events.push(RegistryEvent {
+            version: self.version,
+            timestamp: ic_cdk::api::time(),
+            caller,
+            change,
+        });
+        self.version
+    }
+}
+
+pub fn export_registry() -> TokenRegistry {
+    TOKEN_REGISTRY.with(|r| r.borrow().clone())
+}
+
+pub fn import_registry(registry: TokenRegistry) {
+    TOKEN_REGISTRY.with(|r| *r.borrow_mut() = registry);
+}
+
+pub fn get_tokens() -> Vec<Token> {
+    TOKEN_REGISTRY.with(|r| r.borrow().tokens.values().cloned().collect())
+}
+
+pub fn get_pairs() -> Vec<LendingPair> {
+    TOKEN_REGISTRY.with(|r| r.borrow().pairs.values().cloned().collect())
+}
+
+pub fn get_registry_version() -> u64 {
+    TOKEN_REGISTRY.with(|r| r.borrow().version)
+}
+
+pub fn get_registry_events(offset: u64, limit: u64) -> Vec<RegistryEvent> {
+    TOKEN_REGISTRY.with(|r| {
+        r.borrow()
+            .events
+            .iter()
+            .skip(usize::try_from(offset).unwrap_or(usize::MAX))
+            .take(usize::try_from(limit).unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    })
+}
+
+pub fn add_token(caller: Principal, token: Token) -> Result<u64, String> {
+    let key = token_key(&token)?;
+
+    TOKEN_REGISTRY.with(|r| {
+        let mut registry = r.borrow_mut();
+
+        if registry.tokens.contains_key(&key) {
+            return Err(format!(
+                "Token {} on {} is already registered",
+                token.token_address.clone().unwrap_or_default(),
+                token.token_chain
+            ));
+        }
+
+        registry.tokens.insert(key, token.clone());
+        Ok(registry.record(caller, RegistryChange::TokenAdded(token)))
+    })
+}
+
+pub fn update_token(caller: Principal, token: Token) -> Result<u64, String> {
+    let key = token_key(&token)?;
+
+    TOKEN_REGISTRY.with(|r| {
+        let mut registry = r.borrow_mut();
+
+        if !registry.tokens.contains_key(&key) {
+            return Err(format!(
+                "Token {} on {} not found",
+                token.token_address.clone().unwrap_or_default(),
+                token.token_chain
+            ));
+        }
+
+        registry.tokens.insert(key, token.clone());
+        Ok(registry.record(caller, RegistryChange::TokenUpdated(token)))
+    })
+}
+
+pub fn set_token_active(
+    caller: Principal,
+    token_chain: &str,
+    token_address: &str,
+    is_active: bool,
+) -> Result<u64, String> {
+    let key = format!("{}:{}", token_chain, token_address).to_lowercase();
+
+    TOKEN_REGISTRY.with(|r| {
+        let mut registry = r.borrow_mut();
+
+        let token = registry
+            .tokens
+            .get_mut(&key)
+            .ok_or_else(|| format!("Token {} on {} not found", token_address, token_chain))?;
+
+        if token.is_active == is_active {
+            return Err(format!(
+                "Token {} on {} is already {}",
+                token_address,
+                token_chain,
+                if is_active { "active" } else { "inactive" }
+            ));
+        }
+
+        token.is_active = is_active;
+        Ok(registry.record(
+            caller,
+            RegistryChange::TokenActiveSet {
+                token_chain: token_chain.to_string(),
+                token_address: token_address.to_string(),
+                is_active,
+            },
+        ))
+    })
+}
+
+pub fn add_pair(caller: Principal, pair: LendingPair) -> Result<u64, String> {
+    if pair.borrow_token_id.trim().is_empty() || pair.collateral_token_id.trim().is_empty() {
+        return Err("borrow_token_id and collateral_token_id are required".to_string());
+    }
+
+    let key = pair_key(&pair.borrow_token_id, &pair.collateral_token_id);
+
+    TOKEN_REGISTRY.with(|r| {
+        let mut registry = r.borrow_mut();
+
+        if registry.pairs.contains_key(&key) {
+            return Err(format!(
+                "Pair {} / {} already exists",
+                pair.borrow_token_id, pair.collateral_token_id
+            ));
+        }
+
+        let borrow_key = format!("{}:{}", pair.borrow_token_chain, pair.borrow_token_address)
+            .to_lowercase();
+        if !registry.tokens.contains_key(&borrow_key) {
+            return Err(format!(
+                "Borrow token {} on {} is not registered",
+                pair.borrow_token_address, pair.borrow_token_chain
+            ));
+        }
+
+        registry.pairs.insert(key, pair.clone());
+        Ok(registry.record(caller, RegistryChange::PairAdded(pair)))
+    })
+}
+
+pub fn remove_pair(
+    caller: Principal,
+    borrow_token_id: &str,
+    collateral_token_id: &str,
+) -> Result<u64, String> {
+    TOKEN_REGISTRY.with(|r| {
+        let mut registry = r.borrow_mut();
+
+        if registry
+            .pairs
+            .remove(&pair_key(borrow_token_id, collateral_token_id))
+            .is_none()
+        {
+            return Err(format!(
+                "Pair {} / {} not found",
+                borrow_token_id, collateral_token_id
+            ));
+        }
+
+        Ok(registry.record(
+            caller,
+            RegistryChange::PairRemoved {
+                borrow_token_id: borrow_token_id.to_string(),
+                collateral_token_id: collateral_token_id.to_string(),
+            },
+        ))
+    })
+}
+
+fn token_key(token: &Token) -> Result<String, String> {
+    let address = token
+        .token_address
+        .as_ref()
+        .filter(|address| !address.trim().is_empty())
+        .ok_or("token_address is required")?;
+
+    if token.token_chain.trim().is_empty() {
+        return Err("token_chain cannot be empty".to_string());
+    }
+
+    Ok(format!("{}:{}", token.token_chain, address).to_lowercase())
+}
+
+fn pair_key(borrow_token_id: &str, collateral_token_id: &str) -> String {
+    format!("{}/{}", borrow_token_id, collateral_token_id).to_lowercase()
+}
@@ -1,5 +1,6 @@
 use crate::ecdsa::EcdsaPublicKey;
 use super::{BscNetwork, EthereumNetwork, EcdsaKeyName, InitArg};
+use bit10_core::providers::{ProviderSet, RpcProvider};
 use ic_cdk::api::management_canister::ecdsa::EcdsaKeyId;
 use std::cell::RefCell;
 use std::ops::{Deref, DerefMut};
@@ -23,19 +24,13 @@ where
     STATE.with(|s| f(s.borrow_mut().deref_mut()))
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, candid::CandidType, serde::Serialize, serde::Deserialize)]
-pub struct CustomRpcConfig {
-    pub url: String,
-    pub api_key: Option<String>,
-}
-
 #[derive(Debug, PartialEq, Eq, candid::CandidType, serde::Serialize, serde::Deserialize)]
 pub struct State {
     bsc_network: BscNetwork,
     ethereum_network: EthereumNetwork,
     ecdsa_key_name: EcdsaKeyName,
     ecdsa_public_key: Option<EcdsaPublicKey>,
-    custom_rpc: Option<CustomRpcConfig>,
+    rpc_providers: Option<ProviderSet>,
 }
 
 impl Default for State {
@@ -45,10 +40,7 @@ impl Default for State {
             ethereum_network: EthereumNetwork::default(),
             ecdsa_key_name: EcdsaKeyName::default(),
             ecdsa_public_key: None,
-            custom_rpc: Some(CustomRpcConfig {
-                url: "https://ethereum-sepolia.gateway.tatum.io/".to_string(),
-                api_key: Some("zdfhabdsjsndkfgnsmldfgv".to_string()),
-            }),
+            rpc_providers: None,
         }
     }
 }
@@ -66,16 +58,28 @@ impl State {
         self.ethereum_network
     }
 
-    pub fn custom_rpc(&self) -> Option<&CustomRpcConfig> {
-        self.custom_rpc.as_ref()
+    /// Ethereum endpoints. Deposit lookups need `quorum` of them to agree;
+    /// everything else goes to the first callable one that answers.
+    pub fn rpc_providers(&self) -> ProviderSet {
+        self.rpc_providers
+            .clone()
+            .unwrap_or_else(|| default_rpc_providers(self.ethereum_network))
+    }
+
+    pub fn rpc_providers_mut(&mut self) -> &mut ProviderSet {
+        let ethereum_network = self.ethereum_network;
+        self.rpc_providers
+            .get_or_insert_with(|| default_rpc_providers(ethereum_network))
     }
 
-    pub fn set_custom_rpc(&mut self, config: CustomRpcConfig) {
-        self.custom_rpc = Some(config);
+    /// `None` until a controller changes the defaults or a quorum read
+    /// starts tracking the default providers' health.
+    pub fn configured_rpc_providers(&self) -> Option<ProviderSet> {
+        self.rpc_providers.clone()
     }
 
-    pub fn clear_custom_rpc(&mut self) {
-        self.custom_rpc = None;
+    pub fn set_configured_rpc_providers(&mut self, rpc_providers: Option<ProviderSet>) {
+        self.rpc_providers = rpc_providers;
     }
 
     pub fn bsc_rpc_url(&self) -> &'static str {
@@ -90,12 +94,23 @@ impl State {
         }
     }
 
-    pub fn ethereum_rpc_url(&self) -> &'static str {
-        match self.ethereum_network {
-            EthereumNetwork::Mainnet => "https://eth.llamarpc.com",
-            EthereumNetwork::Sepolia => "https://ethereum-sepolia.gateway.tatum.io/",
-        }
-    }
+}
+
+fn default_rpc_providers(ethereum_network: EthereumNetwork) -> ProviderSet {
+    let providers = match ethereum_network {
+        EthereumNetwork::Mainnet => vec![
+            RpcProvider::new("llamarpc", "https://eth.llamarpc.com"),
+            RpcProvider::new("publicnode", "https://ethereum-rpc.publicnode.com"),
+            RpcProvider::new("drpc", "https://eth.drpc.org"),
+        ],
+        EthereumNetwork::Sepolia => vec![
+            RpcProvider::new("publicnode", "https://ethereum-sepolia-rpc.publicnode.com"),
+            RpcProvider::new("drpc", "https://sepolia.drpc.org"),
+            RpcProvider::new("tatum", "https://ethereum-sepolia.gateway.tatum.io/")
+                .with_header("X-API-Key", "{api_key}"),
+        ],
+    };
+    ProviderSet::new(providers, 2).expect("Invalid default Ethereum providers")
 }
 
 impl From<InitArg> for State {
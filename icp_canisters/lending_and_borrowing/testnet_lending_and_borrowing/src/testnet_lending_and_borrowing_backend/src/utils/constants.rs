@@ -1,7 +1,5 @@
 use candid::Principal;
 
-pub const TATUM_API_KEY_ETHEREUM: &str = "sdghfbhjdbgjkndfkgmdflgfdgfg";
-
 pub const PRICE_FEED_CANISTER_ID: &[u8] = b"\x00\x00\x00\x00\x01\xc0\xdd\x96\x01\x01";
 
 pub const PLATFORM_ICP_WALLET_PRINCIPAL: &[u8] = b"\x00\x00\x00\x00\x02\x30\x00\xcc\x01\x01";
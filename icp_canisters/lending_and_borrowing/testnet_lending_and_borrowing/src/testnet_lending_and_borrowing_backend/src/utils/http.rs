@@ -5,19 +5,14 @@ use ic_cdk::api::time;
 use num_traits::ToPrimitive;
 use std::time::Duration;
 
-use crate::state::{BscNetwork, EthereumNetwork, read_state};
-use crate::utils::constants::TATUM_API_KEY_ETHEREUM;
+use bit10_core::providers::{json_rpc_body, normalize_json_rpc, RpcProvider};
+use bit10_core::transform::ResponseShape;
+
+use crate::state::{BscNetwork, mutate_state, read_state};
 
 const MAX_RETRIES: u8 = 5;
 const BASE_RETRY_DELAY_MS: u64 = 500;
 
-pub fn get_rpc_url_eth(network: EthereumNetwork) -> &'static str {
-    match network {
-        EthereumNetwork::Mainnet => "https://eth.llamarpc.com",
-        EthereumNetwork::Sepolia => "https://ethereum-sepolia.gateway.tatum.io/",
-    }
-}
-
 pub fn get_rpc_url_bsc(network: BscNetwork) -> &'static str {
     match network {
         BscNetwork::Testnet => "https://bsc-testnet.gateway.tatum.io/",
@@ -25,11 +20,25 @@ pub fn get_rpc_url_bsc(network: BscNetwork) -> &'static str {
 }
 
 
+/// Cycles the IC charges for `request`, from its size and response limit.
+fn outcall_cost(request: &CanisterHttpRequestArgument) -> u128 {
+    let bytes = bit10_core::cycles::request_bytes(
+        &request.url,
+        request.headers.iter().map(|h| (h.name.as_str(), h.value.as_str())),
+        request.body.as_deref(),
+        request
+            .transform
+            .as_ref()
+            .map(|t| (t.function.0.method.as_str(), t.context.as_slice())),
+    );
+    bit10_core::cycles::http_request_cost(bytes, request.max_response_bytes)
+}
+
 pub async fn make_http_request(request: CanisterHttpRequestArgument) -> Result<Vec<u8>, String> {
     let mut retries = 0;
 
     while retries < MAX_RETRIES {
-        let cycles: u128 = 25_000_000_000;
+        let cycles = outcall_cost(&request);
         match http_request(request.clone(), cycles).await {
             Ok((response,)) => {
                 let status_code = response.status.0.to_u64().unwrap_or(0);
@@ -62,37 +71,59 @@ pub async fn make_http_request(request: CanisterHttpRequestArgument) -> Result<V
     ))
 }
 
+/// Sends the request to one provider at a time, moving on only when the
+/// outcall fails. Nonces, fees and broadcasts go through here.
 pub async fn call_rpc_with_retry_eth(json_payload: String) -> Result<String, String> {
-    let request_headers = vec![
-        HttpHeader {
-            name: "Content-Type".to_string(),
-            value: "application/json".to_string(),
-        },
-        HttpHeader {
-            name: "X-API-Key".to_string(),
-            value: TATUM_API_KEY_ETHEREUM.to_string(),
-        },
-    ];
+    let providers = read_state(|s| s.rpc_providers()).callable();
+    let mut errors = Vec::with_capacity(providers.len());
+    for provider in &providers {
+        match post_to_provider(provider, &json_payload).await {
+            Ok(body) => return Ok(body),
+            Err(e) => errors.push(format!("{}: {}", provider.name, e)),
+        }
+    }
+    Err(format!("Every RPC provider failed: {}", errors.join("; ")))
+}
+
+/// Sends the read to every provider and answers with the result a quorum of
+/// them agree on. Deposits are verified through here.
+pub async fn call_rpc_with_quorum_eth(json_payload: String) -> Result<String, String> {
+    let providers = read_state(|s| s.rpc_providers()).callable();
+    let mut responses = Vec::with_capacity(providers.len());
+    for provider in &providers {
+        let response = post_to_provider(provider, &json_payload)
+            .await
+            .and_then(|body| normalize_json_rpc(&body));
+        responses.push((provider.name.clone(), response));
+    }
+    let result = mutate_state(|s| s.rpc_providers_mut().tally(responses, time()))?;
+    Ok(json_rpc_body(&result))
+}
 
-    let transform_context = TransformContext::from_name("transform".to_string(), vec![]);
-    let network = read_state(|s| s.ethereum_network());
-    let url = get_rpc_url_eth(network).to_string();
+async fn post_to_provider(provider: &RpcProvider, json_payload: &str) -> Result<String, String> {
+    let mut request_headers = vec![HttpHeader {
+        name: "Content-Type".to_string(),
+        value: "application/json".to_string(),
+    }];
+    request_headers.extend(
+        provider
+            .resolved_headers()
+            .into_iter()
+            .map(|(name, value)| HttpHeader { name, value }),
+    );
+
+    let transform_context =
+        TransformContext::from_name("transform".to_string(), ResponseShape::for_payload(json_payload).to_context());
 
     let request = CanisterHttpRequestArgument {
-        url,
+        url: provider.resolved_url(),
         method: HttpMethod::POST,
-        body: Some(json_payload.into_bytes()),
+        body: Some(json_payload.as_bytes().to_vec()),
         max_response_bytes: Some(8192),
         transform: Some(transform_context),
         headers: request_headers,
     };
 
-    match make_http_request(request).await {
-        Ok(body) => {
-            let body_str =
-                String::from_utf8(body).map_err(|e| format!("Failed to decode response: {}", e))?;
-            Ok(body_str)
-        }
-        Err(e) => Err(e),
-    }
-}
\ No newline at end of file
+    let body = make_http_request(request).await?;
+    String::from_utf8(body).map_err(|e| format!("Failed to decode response: {}", e))
+}
@@ -1,8 +1,11 @@
+use bit10_core::transform::ResponseShape;
 use candid::Principal;
 use ciborium::from_reader;
 use serde_json::Value;
 
 use crate::lib::Token;
+use crate::metrics;
+use crate::registry;
 use crate::lib::PriceFeedResult;
 use crate::utils::http::{make_http_request};
 use crate::utils::constants::PRICE_FEED_CANISTER_ID;
@@ -10,7 +13,7 @@ use ic_cdk::api::management_canister::http_request::{
     CanisterHttpRequestArgument, HttpHeader, HttpMethod, TransformContext,
 };
 
-pub fn get_supported_lending_tokens() -> Vec<Token> {
+pub fn default_supported_lending_tokens() -> Vec<Token> {
     vec![
         Token {
             token_id: Some("3408_8916".to_string()),
@@ -51,9 +54,9 @@ pub fn get_supported_lending_tokens() -> Vec<Token> {
     ]
 }
 
-pub fn get_supported_pairs() -> Vec<crate::lib::Pair> {
+pub fn default_supported_pairs() -> Vec<crate::lib::LendingPair> {
     vec![
-        crate::lib::Pair {
+        crate::lib::LendingPair {
             borrow_token_chain: "ICP".to_string(),
             borrow_token_address: "eegan-kqaaa-aaaap-qhmgq-cai".to_string(),
             borrow_token_id: "3408_8916".to_string(),
@@ -62,7 +65,7 @@ pub fn get_supported_pairs() -> Vec<crate::lib::Pair> {
             collateral_token_id: "top".to_string(),
             collateral_price_feed_link: "https://bsdhbdjfkgfdgfdg.bit10.app/bit10-top-current-price".to_string(),
         },
-        crate::lib::Pair {
+        crate::lib::LendingPair {
             borrow_token_chain: "Ethereum".to_string(),
             borrow_token_address: "0x1c7D4B196Cb0C7B01d743Fbc6116a902379C7238".to_string(),
             borrow_token_id: "3408_1027".to_string(),
@@ -71,7 +74,7 @@ pub fn get_supported_pairs() -> Vec<crate::lib::Pair> {
             collateral_token_id: "top".to_string(),
             collateral_price_feed_link: "https://sdbhfjdffkmgfg.bit10.app/bit10-top-current-price".to_string(),
         },
-        crate::lib::Pair {
+        crate::lib::LendingPair {
             borrow_token_chain: "Ethereum".to_string(),
             borrow_token_address: "0x0000000000000000000000000000000000000000".to_string(),
             borrow_token_id: "ETH".to_string(),
@@ -83,6 +86,14 @@ pub fn get_supported_pairs() -> Vec<crate::lib::Pair> {
     ]
 }
 
+pub fn get_supported_lending_tokens() -> Vec<Token> {
+    registry::get_tokens()
+}
+
+pub fn get_supported_pairs() -> Vec<crate::lib::LendingPair> {
+    registry::get_pairs()
+}
+
 pub async fn get_token_price_from_price_feed_canister(token: &Token) -> Result<f64, String> {
     let price_feed_id = token.price_feed_id.as_ref()
         .ok_or_else(|| format!("Token {} does not have a price_feed_id", token.token_symbol))?;
@@ -96,7 +107,7 @@ pub async fn get_token_price_from_price_feed_canister(token: &Token) -> Result<f
         (price_feed_id.clone(),),
     ).await;
 
-    match price_result {
+    let price = match price_result {
         Ok((Some(feed),)) => {
             match from_reader::<f64, _>(&feed.value[..]) {
                 Ok(value) => Ok(value),
@@ -113,7 +124,9 @@ pub async fn get_token_price_from_price_feed_canister(token: &Token) -> Result<f
         },
         Ok((None,)) => Err(format!("Price feed not found for {}", token.token_symbol)),
         Err(e) => Err(format!("Failed to get price feed for {}: {:?}", token.token_symbol, e)),
-    }
+    };
+    metrics::record_price_feed(price_feed_id, price.is_ok());
+    price
 }
 
 pub async fn get_token_price_from_http_link(token: &Token) -> Result<f64, String> {
@@ -127,7 +140,8 @@ pub async fn get_token_price_from_http_link(token: &Token) -> Result<f64, String
         }
     ];
 
-    let transform_context = TransformContext::from_name("transform".to_string(), vec![]);
+    let transform_context =
+        TransformContext::from_name("transform".to_string(), ResponseShape::json(&["tokenPrice"]).to_context());
 
     let request = CanisterHttpRequestArgument {
         url: price_feed_link.clone(),
@@ -138,7 +152,7 @@ pub async fn get_token_price_from_http_link(token: &Token) -> Result<f64, String
         headers: request_headers,
     };
 
-    match make_http_request(request).await {
+    let price = match make_http_request(request).await {
         Ok(bytes) => {
             match String::from_utf8(bytes) {
                 Ok(response_str) => {
@@ -169,5 +183,7 @@ pub async fn get_token_price_from_http_link(token: &Token) -> Result<f64, String
             "Failed to make HTTP request for {} price feed: {}",
             token.token_symbol, e
         )),
-    }
+    };
+    metrics::record_price_feed(&token.token_symbol, price.is_ok());
+    price
 }
\ No newline at end of file
@@ -4,8 +4,9 @@ use alloy_primitives::{Address, Signature, U256, hex, TxKind};
 use serde_json::Value;
 use std::str::FromStr;
 use crate::ethereum_wallet::EthereumWallet;
+use crate::nonces;
 use crate::state::{read_state};
-use crate::utils::http::{call_rpc_with_retry_eth};
+use crate::utils::http::{call_rpc_with_quorum_eth, call_rpc_with_retry_eth};
 
 pub async fn get_transaction_by_hash_ethereum(tx_hash: &str) -> Result<serde_json::Value, String> {
     let json_payload = format!(
@@ -13,7 +14,7 @@ pub async fn get_transaction_by_hash_ethereum(tx_hash: &str) -> Result<serde_jso
         tx_hash
     );
 
-    match call_rpc_with_retry_eth(json_payload).await {
+    match call_rpc_with_quorum_eth(json_payload).await {
         Ok(result_str) => {
             let response: serde_json::Value = serde_json::from_str(&result_str)
                 .map_err(|e| format!("Failed to parse transaction response: {}", e))?;
@@ -41,7 +42,7 @@ pub async fn get_transaction_receipt_ethereum(tx_hash: &str) -> Result<serde_jso
         tx_hash
     );
 
-    match call_rpc_with_retry_eth(json_payload).await {
+    match call_rpc_with_quorum_eth(json_payload).await {
         Ok(result_str) => {
             let response: serde_json::Value = serde_json::from_str(&result_str)
                 .map_err(|e| format!("Failed to parse receipt response: {}", e))?;
@@ -63,10 +64,10 @@ pub async fn get_transaction_receipt_ethereum(tx_hash: &str) -> Result<serde_jso
     }
 }
 
-pub async fn get_transaction_count_ethereum(address: &str) -> Result<u64, String> {
+pub async fn get_transaction_count_ethereum(address: &str, block_tag: &str) -> Result<u64, String> {
     let json_payload = format!(
-        r#"{{"jsonrpc": "2.0", "method": "eth_getTransactionCount", "params": ["{}", "pending"], "id": 1}}"#,
-        address
+        r#"{{"jsonrpc": "2.0", "method": "eth_getTransactionCount", "params": ["{}", "{}"], "id": 1}}"#,
+        address, block_tag
     );
 
     match call_rpc_with_retry_eth(json_payload).await {
@@ -117,6 +118,30 @@ pub async fn get_dynamic_fees() -> Result<(u128, u128), String> {
     }
 }
 
+pub async fn get_balance_ethereum(address: &str) -> Result<u128, String> {
+    let json_payload = format!(
+        r#"{{"jsonrpc": "2.0", "method": "eth_getBalance", "params": ["{}", "latest"], "id": 1}}"#,
+        address
+    );
+
+    match call_rpc_with_retry_eth(json_payload).await {
+        Ok(body_str) => {
+            let response: serde_json::Value = serde_json::from_str(&body_str)
+                .map_err(|e| format!("Failed to parse balance response: {}", e))?;
+
+            if let Some(result) = response.get("result").and_then(|v| v.as_str()) {
+                u128::from_str_radix(result.strip_prefix("0x").unwrap_or(result), 16)
+                    .map_err(|e| format!("Failed to parse balance hex: {}", e))
+            } else if let Some(error) = response.get("error") {
+                Err(format!("RPC error getting balance: {}", error))
+            } else {
+                Err("eth_getBalance returned no 'result' field or it's not a string".to_string())
+            }
+        }
+        Err(e) => Err(format!("Failed to get balance: {}", e)),
+    }
+}
+
 pub async fn sign_and_send_ethereum_transaction(
     transaction: TxEip1559,
     wallet: &EthereumWallet,
@@ -177,6 +202,94 @@ pub async fn sign_and_send_ethereum_transaction(
     }
 }
 
+/// Hands out the next nonce for the canister wallet. The first call seeds the
+/// counter from the `pending` transaction count; after that withdrawals and
+/// borrows reserve from canister state and never share a nonce.
+async fn reserve_nonce(nonce_key: &str, address: &str) -> Result<u64, String> {
+    if !nonces::is_tracked(nonce_key) {
+        let chain_nonce = get_transaction_count_ethereum(address, "pending").await?;
+        nonces::track(nonce_key, chain_nonce);
+    }
+    nonces::reserve(nonce_key)
+}
+
+async fn submit_ethereum_transaction(
+    transaction: TxEip1559,
+    wallet: &EthereumWallet,
+    nonce_key: &str,
+) -> Result<String, String> {
+    let nonce = transaction.nonce;
+    let max_fee_per_gas = transaction.max_fee_per_gas;
+    let max_priority_fee_per_gas = transaction.max_priority_fee_per_gas;
+
+    match sign_and_send_ethereum_transaction(transaction, wallet).await {
+        Ok(tx_hash) => {
+            nonces::mark_submitted(nonce_key, nonce, tx_hash.clone(), max_fee_per_gas, max_priority_fee_per_gas);
+            Ok(tx_hash)
+        }
+        Err(e) => {
+            nonces::mark_failed(nonce_key, nonce);
+            if e.contains("nonce too low") {
+                let address = wallet.ethereum_address().to_string();
+                if let Ok(chain_nonce) = get_transaction_count_ethereum(&address, "pending").await {
+                    nonces::track(nonce_key, chain_nonce);
+                }
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Drops mined nonces and fills abandoned ones with zero-value self
+/// transfers, so one failed payout cannot block every later one.
+pub async fn reconcile_nonces() -> Result<(), String> {
+    let wallet = EthereumWallet::new_canister_wallet().await;
+    let canister_address = wallet.ethereum_address().to_string();
+    let chain_id = read_state(|s| s.ethereum_network().chain_id());
+    let nonce_key = nonces::nonce_key(chain_id, &canister_address);
+
+    if !nonces::is_tracked(&nonce_key) {
+        return Ok(());
+    }
+
+    let confirmed_nonce = get_transaction_count_ethereum(&canister_address, "latest").await?;
+    let report = nonces::reconcile(&nonce_key, confirmed_nonce);
+
+    for stuck in &report.stuck {
+        ic_cdk::println!(
+            "Ethereum transaction {:?} with nonce {} is still pending",
+            stuck.tx_hash,
+            stuck.nonce
+        );
+    }
+
+    let own_address = Address::from_str(&canister_address)
+        .map_err(|e| format!("Invalid canister address: {}", e))?;
+    for nonce in report.gaps {
+        let (max_fee_per_gas, max_priority_fee_per_gas) = get_dynamic_fees().await?;
+        nonces::mark_cancel(&nonce_key, nonce);
+
+        let transaction = TxEip1559 {
+            chain_id,
+            nonce,
+            gas_limit: 21_000,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            to: TxKind::Call(own_address),
+            value: U256::ZERO,
+            access_list: Default::default(),
+            input: Default::default(),
+        };
+
+        match submit_ethereum_transaction(transaction, &wallet, &nonce_key).await {
+            Ok(tx_hash) => ic_cdk::println!("Filled Ethereum nonce gap {} with {}", nonce, tx_hash),
+            Err(e) => ic_cdk::println!("Failed to fill Ethereum nonce gap {}: {}", nonce, e),
+        }
+    }
+
+    Ok(())
+}
+
 pub async fn send_erc20_token_from_canister(
     token_contract: Address,
     to: Address,
@@ -185,10 +298,12 @@ pub async fn send_erc20_token_from_canister(
     let wallet = EthereumWallet::new_canister_wallet().await;
     let canister_address = wallet.ethereum_address().to_string();
 
-    let nonce = get_transaction_count_ethereum(&canister_address).await?;
-
     let (max_fee_per_gas, max_priority_fee_per_gas) = get_dynamic_fees().await?;
 
+    let chain_id = read_state(|s| s.ethereum_network().chain_id());
+    let nonce_key = nonces::nonce_key(chain_id, &canister_address);
+    let nonce = reserve_nonce(&nonce_key, &canister_address).await?;
+
     let method_id = hex::decode("a9059cbb").unwrap();
     let mut encoded = method_id;
 
@@ -200,8 +315,6 @@ pub async fn send_erc20_token_from_canister(
     amount_bytes.copy_from_slice(&amount.to_be_bytes::<32>());
     encoded.extend_from_slice(&amount_bytes);
 
-    let chain_id = read_state(|s| s.ethereum_network().chain_id());
-
     let transaction = TxEip1559 {
         chain_id,
         nonce,
@@ -214,18 +327,18 @@ pub async fn send_erc20_token_from_canister(
         input: encoded.into(),
     };
 
-    sign_and_send_ethereum_transaction(transaction, &wallet).await
+    submit_ethereum_transaction(transaction, &wallet, &nonce_key).await
 }
 
 pub async fn send_native_eth_from_canister(to: Address, amount: U256) -> Result<String, String> {
     let wallet = EthereumWallet::new_canister_wallet().await;
     let canister_address = wallet.ethereum_address().to_string();
 
-    let nonce = get_transaction_count_ethereum(&canister_address).await?;
-
     let (max_fee_per_gas, max_priority_fee_per_gas) = get_dynamic_fees().await?;
 
     let chain_id = read_state(|s| s.ethereum_network().chain_id());
+    let nonce_key = nonces::nonce_key(chain_id, &canister_address);
+    let nonce = reserve_nonce(&nonce_key, &canister_address).await?;
 
     let transaction = TxEip1559 {
         chain_id,
@@ -239,5 +352,5 @@ pub async fn send_native_eth_from_canister(to: Address, amount: U256) -> Result<
         input: Default::default(),
     };
 
-    sign_and_send_ethereum_transaction(transaction, &wallet).await
+    submit_ethereum_transaction(transaction, &wallet, &nonce_key).await
 }
\ No newline at end of file
@@ -1,5 +1,6 @@
 use ic_cdk::api::management_canister::main::CanisterIdRecord;
 use candid::Principal;
+use ic_cdk::api::management_canister::bitcoin::BitcoinNetwork;
 
 pub const BIT10_BTC_LEDGER_CANISTER_ID: Principal = Principal::from_slice(b"\x00\x00\x00\x00\x02\x30\x00\xCC\x01\x01"); // 7hfb6-caaaa-aaaar-qadga-cai
 
@@ -7,7 +8,25 @@ pub const PRICE_FEED_CANISTER_ID: Principal = Principal::from_slice(b"\x82\x7f\x
 
 pub const MAX_RETRY_COUNT: u8 = 5;
 pub const RETRY_INTERVAL_SECONDS: u64 = 1800; // 30 minutes
-pub const HTTP_REQUEST_CYCLES: u128 = 25_000_000_000;
 
-// Hardcoed for testing limits on BIT10 Testnet
+// Initial capacity of the default BTC pool on BIT10 Testnet
 pub const BTC_POOL_SIZE: u64 = 278_521_692; // 2.78521692 BTC
+
+pub const BTC_NETWORK: BitcoinNetwork = BitcoinNetwork::Testnet;
+pub const BTC_DEPOSIT_ECDSA_KEY_NAME: &str = "test_key_1";
+pub const DEFAULT_BTC_MIN_CONFIRMATIONS: u32 = 6;
+
+pub const SOLANA_RPC_URL: &str = "https://api.devnet.solana.com";
+// Deposits are credited only when this many Solana providers return the
+// same transaction.
+pub const SOLANA_RPC_QUORUM: u8 = 2;
+pub const SOL_DEPOSIT_SCHNORR_KEY_NAME: &str = "test_key_1";
+
+pub const DEFAULT_BTC_PRICE_FEED_ID: &str = "2374178234y3749";
+
+
+// SLP reward weight is amount * (SLP_DURATION_BASE_DAYS + lock days)
+pub const SLP_DURATION_BASE_DAYS: u64 = 365;
+// Penalty for withdrawing an SLP deposit right after locking it, falling
+// linearly to zero at unlock time
+pub const DEFAULT_SLP_EARLY_WITHDRAW_PENALTY_BPS: u16 = 1_000; // 10%
@@ -1,12 +1,16 @@
 use candid::{CandidType, Deserialize, Serialize};
 use candid::Nat;
 use ic_cdk::Principal;
+use bit10_core::errors::ApiError;
 
 #[derive(CandidType, Deserialize, Clone)]
 pub struct ILPArgs {
     pub tick_in_name: String,
     pub tick_in_network: String,
     pub tick_in_tx_block: String,
+    // The output paying the caller's deposit address. Required for Bitcoin,
+    // where one transaction can pay several deposit addresses.
+    pub tick_in_output_index: Option<u32>,
     pub tick_out_name: String,
 }
 
@@ -31,5 +35,34 @@ pub struct IlpResponseData {
 #[derive(CandidType, Serialize)]
 pub enum IlpResponse {
     Ok(IlpResponseData),
-    Err(String),
+    Err(ApiError),
+}
+
+/// One instant-liquidity pool: deposits of `tick_in_name` on
+/// `tick_in_network` are paid out in `tick_out_name` from `tick_out_ledger`,
+/// up to `capacity` tick-in base units in total.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct IlpPool {
+    pub pool_id: String,
+    pub tick_in_name: String,
+    pub tick_in_network: String,
+    pub tick_in_decimals: u32,
+    // SPL mint for Solana token deposits; None for native SOL and BTC.
+    pub tick_in_mint: Option<String>,
+    pub tick_out_name: String,
+    pub tick_out_ledger: Principal,
+    pub capacity: Nat,
+    pub used: Nat,
+    pub price_feed_id: String,
+    pub spread_bps: u16,
+    pub enabled: bool,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct PoolStatus {
+    pub pool_id: String,
+    pub capacity: Nat,
+    pub used: Nat,
+    pub remaining: Nat,
+    pub enabled: bool,
 }
@@ -1,54 +1,97 @@
 use candid::{Principal, Nat};
-use ic_cdk::api::management_canister::http_request::{HttpMethod, CanisterHttpRequestArgument};
 use icrc_ledger_types::icrc1::transfer::{BlockIndex, NumTokens, TransferArg, TransferError};
 use crate::constants::*;
-use crate::modules::{ILPArgs, IlpResponse, IlpResponseData, PriceFeed};
-use crate::services::verification::verify_transaction_with_retry;
-use crate::state::storage::RESPONSES;
+use crate::modules::{ILPArgs, IlpPool, IlpResponse, IlpResponseData, PoolStatus, PriceFeed};
+use crate::modules::slp_rewards::accrue_revenue;
+use crate::services::bitcoin::{btc_deposit_address, confirmed_deposit_amount};
+use crate::services::metrics;
+use crate::services::solana::{get_finalized_transaction, sol_credited, sol_deposit_address, spl_credited};
+use crate::state::storage::{insert_response, with_deposits_mut, ILP_POOLS};
+use bit10_core::deposits::{deposit_key, DepositClaim};
+use bit10_core::errors::ApiError;
 
-pub async fn handle_ilp(args: ILPArgs) -> IlpResponse {
+const BTC_DECIMALS: u32 = 8;
+const SUPPORTED_NETWORKS: &[&str] = &["bitcoin_testnet", "solana_devnet"];
+
+pub async fn handle_ilp(mut args: ILPArgs) -> IlpResponse {
     let caller: Principal = ic_cdk::caller();
 
-    let exists = unsafe {
-        RESPONSES.as_ref().map_or(false, |responses| {
-            responses.values().any(|response| response.tick_in_tx_block == args.tick_in_tx_block)
-        })
+    let pool = match find_pool(&args) {
+        Ok(pool) => pool,
+        Err(e) => return IlpResponse::Err(e),
     };
 
-    if exists {
-        return IlpResponse::Err(format!("Transaction {} already processed", args.tick_in_tx_block));
+    // Bitcoin txids are hex, so the same deposit must not be claimable twice
+    // by changing its case.
+    if pool.tick_in_network == "bitcoin_testnet" {
+        args.tick_in_tx_block = args.tick_in_tx_block.to_ascii_lowercase();
     }
 
-    let tick_out_ledger_canister_id = match args.tick_out_name.as_str() {
-        "ckUSDC" => BIT10_BTC_LEDGER_CANISTER_ID,
-        _ => return IlpResponse::Err(format!("Unsupported token: {:?}", args.tick_out_name)),
+    let output_index = match (pool.tick_in_network.as_str(), args.tick_in_output_index) {
+        ("bitcoin_testnet", None) => {
+            return IlpResponse::Err(ApiError::invalid_argument(
+                "tick_in_output_index",
+                "Bitcoin deposits must name the output paying the deposit address",
+            ))
+        }
+        ("bitcoin_testnet", index) => index,
+        _ => None,
     };
 
-    let (tick_in_amount_nat, tick_in_user_address) = match process_tick_in(&args).await {
-        Ok(result) => result,
-        Err(e) => return IlpResponse::Err(e),
+    let deposit_key = deposit_key(&pool.tick_in_network, &args.tick_in_tx_block, output_index);
+    let claim = match with_deposits_mut(&deposit_key, |d| d.claim(&deposit_key, ic_cdk::api::time())) {
+        Ok(claim) => claim,
+        Err(e) => return IlpResponse::Err(e.into()),
     };
 
-    let total_tick_in_amount = unsafe {
-        RESPONSES.as_ref().map_or(Nat::from(0u64), |responses| {
-            responses.values().fold(Nat::from(0u64), |acc, response| {
-                acc + response.tick_in_amount.clone()
-            })
-        })
-    };
+    let response = settle_ilp(caller, args, pool, &claim).await;
+    with_deposits_mut(&claim.key, |d| {
+        d.finish(&claim, matches!(response, IlpResponse::Ok(_)), ic_cdk::api::time())
+    });
+    response
+}
 
-    let btc_pool = btc_pool_size().await;
+async fn settle_ilp(caller: Principal, args: ILPArgs, pool: IlpPool, claim: &DepositClaim) -> IlpResponse {
+    let (tick_in_amount_nat, tick_in_user_address) = match process_tick_in(caller, &args, &pool).await {
+        Ok(result) => result,
+        Err(e) => return IlpResponse::Err(e),
+    };
 
-    if btc_pool.clone() - total_tick_in_amount.clone() <= tick_in_amount_nat {
-        return IlpResponse::Err("BTC in pool Full".to_string());
+    // Capacity is taken before any await so concurrent deposits cannot
+    // overfill the pool; it is handed back if the payout fails.
+    if let Err(e) = reserve_capacity(&pool.pool_id, &tick_in_amount_nat) {
+        return IlpResponse::Err(e);
     }
 
-    let price_feed = match get_price_feed().await {
+    let price_feed = match get_price_feed(&pool.price_feed_id).await {
         Ok(feed) => feed,
-        Err(e) => return IlpResponse::Err(e),
+        Err(e) => {
+            release_capacity(&pool.pool_id, &tick_in_amount_nat);
+            return IlpResponse::Err(e);
+        }
+    };
+
+    // Fails before settlement starts, so a deposit that cannot be valued is
+    // released rather than kept with nothing paid out.
+    let (result_nat, result_f64, spread_nat) = match calculate_result(
+        tick_in_amount_nat.clone(),
+        pool.tick_in_decimals,
+        pool.spread_bps,
+        &pool.price_feed_id,
+        price_feed,
+    ) {
+        Ok(result) => result,
+        Err(e) => {
+            release_capacity(&pool.pool_id, &tick_in_amount_nat);
+            return IlpResponse::Err(e);
+        }
     };
+    let tick_out_ledger_canister_id = pool.tick_out_ledger;
 
-    let (result_nat, result_f64) = calculate_result(tick_in_amount_nat, price_feed);
+    if let Err(e) = with_deposits_mut(&claim.key, |d| d.start_settlement(claim, ic_cdk::api::time())) {
+        release_capacity(&pool.pool_id, &tick_in_amount_nat);
+        return IlpResponse::Err(e.into());
+    }
 
     match transfer_tokens(caller, result_nat.clone(), tick_out_ledger_canister_id).await {
         Ok(block_index) => {
@@ -62,102 +105,279 @@ pub async fn handle_ilp(args: ILPArgs) -> IlpResponse {
                 block_index,
             );
 
-            unsafe {
-                let index = RESPONSES.as_ref().map_or(0, |r| r.len() as u64);
-                RESPONSES.as_mut().unwrap().insert(index, response_data.clone());
-            }
+            insert_response(response_data.clone());
+
+            // The spread stays in the hub and is shared with SLP stakers.
+            accrue_revenue(tick_out_ledger_canister_id, spread_nat);
 
             IlpResponse::Ok(response_data)
         }
-        Err(e) => IlpResponse::Err(e),
+        Err(e) => {
+            release_capacity(&pool.pool_id, &tick_in_amount_nat);
+            IlpResponse::Err(e)
+        }
     }
 }
 
-async fn process_tick_in(args: &ILPArgs) -> Result<(Nat, String), String> {
-    let mut tick_in_amount_nat = Nat::from(0u64);
-    let mut tick_in_user_address = "some-address".to_string();
+fn find_pool(args: &ILPArgs) -> Result<IlpPool, ApiError> {
+    let pool = ILP_POOLS
+        .with(|pools| {
+            pools
+                .borrow()
+                .values()
+                .find(|pool| {
+                    pool.tick_in_name == args.tick_in_name
+                        && pool.tick_in_network == args.tick_in_network
+                        && pool.tick_out_name == args.tick_out_name
+                })
+                .cloned()
+        })
+        .ok_or_else(|| {
+            ApiError::invalid_argument(
+                "tick_out_name",
+                format!(
+                    "No pool for {} on {} paying out {}",
+                    args.tick_in_name, args.tick_in_network, args.tick_out_name
+                ),
+            )
+        })?;
 
-    match args.tick_in_name.as_str() {
-        "BTC" => {
-            if args.tick_in_network != "bitcoin_testnet" {
-                return Err("Invalid network for BTC. Expected 'bitcoin_testnet'".to_string());
-            }
-            
-            let url = format!(
-                "https://backend-91c09684-367.bit10.app/verify-transaction?txid={}&chain={}&verification_secret=cd64873b47571ffd6517c5c5184bedf",
-                args.tick_in_tx_block,
-                args.tick_in_network
-            );
-            
-            let request = CanisterHttpRequestArgument {
-                url,
-                method: HttpMethod::GET,
-                body: None,
-                headers: vec![],
-                transform: None,
-                max_response_bytes: Some(2_000_000),
+    if !pool.enabled {
+        return Err(format!("Pool {} is disabled", pool.pool_id).into());
+    }
+    Ok(pool)
+}
+
+fn reserve_capacity(pool_id: &str, amount: &Nat) -> Result<(), ApiError> {
+    ILP_POOLS.with(|pools| {
+        let mut pools = pools.borrow_mut();
+        let pool = pools
+            .get_mut(pool_id)
+            .ok_or_else(|| ApiError::not_found(pool_id))?;
+        if pool.used.clone() + amount.clone() > pool.capacity {
+            let available = if pool.capacity > pool.used {
+                pool.capacity.clone() - pool.used.clone()
+            } else {
+                Nat::from(0u64)
+            };
+            return Err(ApiError::InsufficientLiquidity {
+                token: pool.tick_in_name.clone(),
+                available: available.0.to_string(),
+                required: amount.0.to_string(),
+            });
+        }
+        pool.used += amount.clone();
+        Ok(())
+    })
+}
+
+fn release_capacity(pool_id: &str, amount: &Nat) {
+    ILP_POOLS.with(|pools| {
+        if let Some(pool) = pools.borrow_mut().get_mut(pool_id) {
+            pool.used = if pool.used > *amount {
+                pool.used.clone() - amount.clone()
+            } else {
+                Nat::from(0u64)
             };
-            
-            match verify_transaction_with_retry(request.clone()).await {
-                Ok(verification) => {
-                    if let Some(output) = verification.outputs.iter().find(|o| o.vout == 0) {
-                        tick_in_amount_nat = Nat::from(output.satoshi);
-                    }
-                    if let Some(output) = verification.outputs.iter().find(|o| o.vout == 1) {
-                        tick_in_user_address = output.address.clone();
-                    }
-                },
-                Err(e) => return Err(format!("Failed to verify transaction: {}", e)),
+        }
+    });
+}
+
+pub fn pool_status(pool_id: &str) -> Option<PoolStatus> {
+    ILP_POOLS.with(|pools| {
+        pools.borrow().get(pool_id).map(|pool| PoolStatus {
+            pool_id: pool.pool_id.clone(),
+            capacity: pool.capacity.clone(),
+            used: pool.used.clone(),
+            remaining: if pool.capacity > pool.used {
+                pool.capacity.clone() - pool.used.clone()
+            } else {
+                Nat::from(0u64)
+            },
+            enabled: pool.enabled,
+        })
+    })
+}
+
+pub fn get_pools() -> Vec<IlpPool> {
+    ILP_POOLS.with(|pools| pools.borrow().values().cloned().collect())
+}
+
+/// Adds or replaces a pool. `used` is owned by the canister, so an existing
+/// pool keeps its usage and a new pool starts empty.
+pub fn upsert_pool(mut pool: IlpPool) -> Result<(), String> {
+    if pool.pool_id.trim().is_empty() {
+        return Err("pool_id must not be empty".to_string());
+    }
+    if !SUPPORTED_NETWORKS.contains(&pool.tick_in_network.as_str()) {
+        return Err(format!("Unsupported network: {}", pool.tick_in_network));
+    }
+    if pool.tick_in_network == "bitcoin_testnet" && pool.tick_in_mint.is_some() {
+        return Err("tick_in_mint is only valid for Solana pools".to_string());
+    }
+    if pool.spread_bps >= 10_000 {
+        return Err("spread_bps must be below 10000".to_string());
+    }
+    if pool.price_feed_id.trim().is_empty() {
+        return Err("price_feed_id must not be empty".to_string());
+    }
+
+    ILP_POOLS.with(|pools| {
+        let mut pools = pools.borrow_mut();
+        let duplicate = pools.values().any(|existing| {
+            existing.pool_id != pool.pool_id
+                && existing.tick_in_name == pool.tick_in_name
+                && existing.tick_in_network == pool.tick_in_network
+                && existing.tick_out_name == pool.tick_out_name
+        });
+        if duplicate {
+            return Err(format!(
+                "Another pool already serves {} on {} paying out {}",
+                pool.tick_in_name, pool.tick_in_network, pool.tick_out_name
+            ));
+        }
+
+        pool.used = pools
+            .get(&pool.pool_id)
+            .map(|existing| existing.used.clone())
+            .unwrap_or_else(|| Nat::from(0u64));
+        pools.insert(pool.pool_id.clone(), pool);
+        Ok(())
+    })
+}
+
+pub fn set_pool_enabled(pool_id: &str, enabled: bool) -> Result<(), String> {
+    ILP_POOLS.with(|pools| {
+        let mut pools = pools.borrow_mut();
+        let pool = pools
+            .get_mut(pool_id)
+            .ok_or_else(|| format!("Pool {} not found", pool_id))?;
+        pool.enabled = enabled;
+        Ok(())
+    })
+}
+
+/// The BTC pool that used to be hardcoded. Seeded with the usage already
+/// recorded so upgrading does not reset its capacity.
+pub fn default_btc_pool(existing: &[IlpResponseData]) -> IlpPool {
+    let used = existing
+        .iter()
+        .filter(|response| response.tick_in_name == "BTC")
+        .fold(Nat::from(0u64), |acc, response| acc + response.tick_in_amount.clone());
+
+    IlpPool {
+        pool_id: "BTC-ckUSDC".to_string(),
+        tick_in_name: "BTC".to_string(),
+        tick_in_network: "bitcoin_testnet".to_string(),
+        tick_in_decimals: BTC_DECIMALS,
+        tick_in_mint: None,
+        tick_out_name: "ckUSDC".to_string(),
+        tick_out_ledger: BIT10_BTC_LEDGER_CANISTER_ID,
+        capacity: Nat::from(BTC_POOL_SIZE),
+        used,
+        price_feed_id: DEFAULT_BTC_PRICE_FEED_ID.to_string(),
+        spread_bps: 0,
+        enabled: true,
+    }
+}
+
+/// Verifies the deposit and returns the credited amount in the asset's base
+/// unit and the address it was credited to.
+async fn process_tick_in(caller: Principal, args: &ILPArgs, pool: &IlpPool) -> Result<(Nat, String), ApiError> {
+    match pool.tick_in_network.as_str() {
+        "bitcoin_testnet" => {
+            // Only an output paying the caller's own deposit address counts,
+            // so a transaction cannot be claimed by anyone other than its sender.
+            let deposit_address = btc_deposit_address(caller).await?;
+            let vout = args.tick_in_output_index.unwrap_or_default();
+            let amount = confirmed_deposit_amount(&deposit_address, &args.tick_in_tx_block, vout).await?;
+            if amount == Nat::from(0u64) {
+                return Err(ApiError::deposit_invalid(
+                    &args.tick_in_tx_block,
+                    format!("Output {} is not a confirmed payment to deposit address {}", vout, deposit_address),
+                ));
             }
+            Ok((amount, deposit_address))
         }
-        "SOL" => {
-            if args.tick_in_network != "solana_devnet" {
-                return Err("Invalid network for SOL. Expected 'solana_devnet'".to_string());
+        "solana_devnet" => {
+            let deposit_address = sol_deposit_address(caller).await?;
+            let transaction = get_finalized_transaction(&args.tick_in_tx_block).await?;
+            let amount = match &pool.tick_in_mint {
+                Some(mint) => spl_credited(&transaction, &deposit_address, mint)?,
+                None => sol_credited(&transaction, &deposit_address)?,
+            };
+
+            if amount == Nat::from(0u64) {
+                return Err(ApiError::deposit_invalid(
+                    &args.tick_in_tx_block,
+                    format!("Does not credit {} to deposit address {}", pool.tick_in_name, deposit_address),
+                ));
             }
+            Ok((amount, deposit_address))
         }
-        _ => return Err("Invalid tick_in_name. Expected 'BTC' or 'SOL'".to_string()),
+        network => Err(ApiError::invalid_argument("tick_in_network", format!("Unsupported network: {}", network))),
     }
-
-    Ok((tick_in_amount_nat, tick_in_user_address))
 }
 
-async fn get_price_feed() -> Result<PriceFeed, String> {
+async fn get_price_feed(feed_id: &str) -> Result<PriceFeed, ApiError> {
     let price_result: Result<(Option<PriceFeed>,), _> = ic_cdk::call(
         PRICE_FEED_CANISTER_ID,
         "get_value",
-        ("2374178234y3749",)
+        (feed_id.to_string(),)
     ).await;
+    metrics::record_price_feed(feed_id, matches!(price_result, Ok((Some(_),))));
 
     match price_result {
         Ok((Some(feed),)) => Ok(feed),
-        Ok((None,)) => Err("Price feed not found".to_string()),
-        Err(e) => Err(format!("Failed to get price feed: {:?}", e)),
+        Ok((None,)) => Err(ApiError::price_feed_unavailable(feed_id, "Price feed not found")),
+        Err((code, msg)) => Err(ApiError::price_feed_unavailable(feed_id, format!("{:?} - {}", code, msg))),
     }
 }
 
-fn calculate_result(tick_in_amount_nat: Nat, price_feed: PriceFeed) -> (Nat, f64) {
-    let tick_in_value = match ciborium::from_reader::<f64, _>(&price_feed.value[..]) {
-        Ok(value) => value as f64,
-        Err(_) => {
-            match ciborium::from_reader::<u64, _>(&price_feed.value[..]) {
-                Ok(int_value) => int_value as f64,
-                Err(_) => 0.0,
-            }
-        }
-    };
+/// Values the deposit in USD less the pool spread. The payout keeps the
+/// 8-decimal scale used for BTC deposits, so other assets are rescaled from
+/// their own decimals first. Also returns the spread kept back, in payout units.
+fn calculate_result(
+    tick_in_amount_nat: Nat,
+    tick_in_decimals: u32,
+    spread_bps: u16,
+    feed_id: &str,
+    price_feed: PriceFeed,
+) -> Result<(Nat, f64, Nat), ApiError> {
+    let tick_in_value = ciborium::from_reader::<f64, _>(&price_feed.value[..])
+        .or_else(|_| ciborium::from_reader::<u64, _>(&price_feed.value[..]).map(|value| value as f64))
+        .map_err(|e| ApiError::price_feed_unavailable(feed_id, format!("Undecodable price: {}", e)))?;
+    if !tick_in_value.is_finite() || tick_in_value <= 0.0 {
+        return Err(ApiError::price_feed_unavailable(feed_id, format!("Invalid price: {}", tick_in_value)));
+    }
+
+    let tick_in_amount = tick_in_amount_nat.0.to_u64().ok_or_else(|| {
+        ApiError::invalid_argument("tick_in_amount", format!("{} is too large to convert", tick_in_amount_nat))
+    })?;
+
+    let scale = 10f64.powi(BTC_DECIMALS as i32 - tick_in_decimals as i32);
+    let spread = 1.0 - spread_bps as f64 / 10_000.0;
+    let gross = tick_in_amount as f64 * scale * tick_in_value;
+    let result = gross * spread;
+    if result < 1.0 {
+        return Err(ApiError::invalid_argument(
+            "tick_in_amount",
+            format!("{} is too small to pay out anything", tick_in_amount_nat),
+        ));
+    }
 
-    let result = tick_in_amount_nat.0.to_u64().unwrap_or(0) as f64 * tick_in_value;
     let result_nat = Nat::from(result as u64);
     let result_f64 = result / 100_000_000.0;
+    let spread_nat = Nat::from((gross as u64).saturating_sub(result as u64));
 
-    (result_nat, result_f64)
+    Ok((result_nat, result_f64, spread_nat))
 }
 
 async fn transfer_tokens(
     caller: Principal,
     amount: Nat,
     canister_id: Principal,
-) -> Result<BlockIndex, String> {
+) -> Result<BlockIndex, ApiError> {
     let transfer_args: TransferArg = TransferArg {
         memo: None,
         amount: NumTokens::from(amount),
@@ -176,8 +396,8 @@ async fn transfer_tokens(
 
     match transfer_result {
         Ok((Ok(block_index),)) => Ok(block_index),
-        Ok((Err(e),)) => Err(format!("Transfer failed: {:?}", e)),
-        Err((code, msg)) => Err(format!("Call failed: {:?} - {}", code, msg)),
+        Ok((Err(e),)) => Err(ApiError::transfer_failed(format!("{:?}", e))),
+        Err((code, msg)) => Err(ApiError::call_rejected(canister_id, code as u32, msg)),
     }
 }
 
@@ -3,9 +3,15 @@ pub mod ilp;
 pub mod slp;
 pub mod ilp_handler;
 pub mod slp_handler;
+pub mod slp_rewards;
+pub mod treasury;
+pub mod treasury_handler;
 
 pub use common::*;
 pub use ilp::*;
 pub use slp::*;
 pub use ilp_handler::*;
 pub use slp_handler::*;
+pub use slp_rewards::*;
+pub use treasury::*;
+pub use treasury_handler::*;
@@ -17,6 +17,7 @@ pub struct SLPResponseData {
     pub tick_in_block: Nat,
     pub tick_in_address: Principal,
     pub tick_in_timestamp: String,
+    pub deposit_id: Option<u64>,
 }
 
 #[derive(CandidType, Serialize)]
@@ -46,12 +47,31 @@ pub enum SLPWithdrawResponse {
     Err(String),
 }
 
+#[derive(CandidType, Serialize, Deserialize, Clone)]
+pub struct SLPEarlyWithdrawResponseData {
+    pub deposit_id: u64,
+    pub tick_out_name: String,
+    pub deposit_amount: Nat,
+    pub penalty_amount: Nat,
+    pub tick_out_amount: Nat,
+    pub tick_out_block: Nat,
+    pub tick_out_address: Principal,
+    pub tick_out_time: String,
+}
+
+#[derive(CandidType, Serialize)]
+pub enum SLPEarlyWithdrawResponse {
+    Ok(SLPEarlyWithdrawResponseData),
+    Err(String),
+}
+
 #[derive(CandidType, Deserialize, Clone)]
 pub struct TransferFromCanisterArgs {
     pub tick_out_name: String,
     pub tick_out_amount: Nat,
     pub tick_out_duration: Nat,
     pub tick_out_address: Principal,
+    pub reason: String,
 }
 
 #[derive(CandidType, Serialize, Deserialize, Clone)]
@@ -62,6 +82,8 @@ pub struct TransferFromCanisterResponseData {
     pub tick_out_caller: Principal,
     pub tick_out_block: Nat,
     pub tick_out_time: String,
+    pub deployment_id: u64,
+    pub expected_return_time: String,
 }
 
 #[derive(CandidType, Serialize)]
@@ -1,10 +1,19 @@
 use candid::{Principal, Nat};
+use num_traits::ToPrimitive;
 use icrc_ledger_types::icrc1::account::Account;
 use icrc_ledger_types::icrc1::transfer::{BlockIndex, NumTokens, TransferArg, TransferError};
 use icrc_ledger_types::icrc2::transfer_from::{TransferFromArgs, TransferFromError};
 use crate::constants::*;
-use crate::modules::{SLPArgs, SLPResponse, SLPResponseData, SLPWithdrawArgs, SLPWithdrawResponse};
-use crate::state::storage::{SLP_RESPONSES, SLP_WITHDRAW_RESPONSES};
+use crate::modules::{
+    SLPArgs, SLPEarlyWithdrawResponse, SLPEarlyWithdrawResponseData, SLPResponse, SLPResponseData,
+    SLPWithdrawArgs, SLPWithdrawResponse, SLPWithdrawResponseData,
+};
+use crate::modules::slp_rewards::accrue_revenue;
+use crate::state::storage::{
+    insert_slp_response, insert_slp_withdraw_response, SLP_EARLY_WITHDRAWS_IN_FLIGHT,
+    SLP_EARLY_WITHDRAW_PENALTY_BPS, SLP_EARLY_WITHDRAW_RESPONSES, SLP_RESPONSES, SLP_REWARDS,
+    SLP_WITHDRAW_RESPONSES,
+};
 
 pub async fn handle_slp(args: SLPArgs) -> SLPResponse {
     let caller: Principal = ic_cdk::caller();
@@ -23,12 +32,19 @@ pub async fn handle_slp(args: SLPArgs) -> SLPResponse {
                 tick_in_block: block_index,
                 tick_in_address: caller,
                 tick_in_timestamp: ic_cdk::api::time().to_string(),
+                deposit_id: None,
             };
 
-            unsafe {
-                let index = SLP_RESPONSES.as_ref().map_or(0, |r| r.len() as u64);
-                SLP_RESPONSES.as_mut().unwrap().insert(index, response_data.clone());
-            }
+            let response_data = insert_slp_response(response_data);
+            SLP_REWARDS.with(|r| {
+                r.borrow_mut().add_position(
+                    response_data.deposit_id.unwrap_or_default(),
+                    caller,
+                    &response_data.tick_in_amount,
+                    response_data.duration.0.to_u64().unwrap_or(0),
+                    ic_cdk::api::time(),
+                )
+            });
 
             SLPResponse::Ok(response_data)
         }
@@ -68,10 +84,7 @@ pub async fn handle_slp_withdraw(args: SLPWithdrawArgs) -> SLPWithdrawResponse {
                 tick_out_time: ic_cdk::api::time().to_string(),
             };
 
-            unsafe {
-                let index = SLP_WITHDRAW_RESPONSES.as_ref().map_or(0, |r| r.len() as u64);
-                SLP_WITHDRAW_RESPONSES.as_mut().unwrap().insert(index, response_data.clone());
-            }
+            insert_slp_withdraw_response(response_data.clone());
 
             SLPWithdrawResponse::Ok(response_data)
         }
@@ -79,8 +92,97 @@ pub async fn handle_slp_withdraw(args: SLPWithdrawArgs) -> SLPWithdrawResponse {
     }
 }
 
+/// Withdraws a still-locked deposit in full, less a penalty proportional to
+/// the lock time left. The penalty stays in the hub and funds SLP rewards.
+pub async fn handle_slp_early_withdraw(deposit_id: u64) -> SLPEarlyWithdrawResponse {
+    let caller: Principal = ic_cdk::caller();
+
+    let deposit = match SLP_RESPONSES.with(|r| r.borrow().get(&deposit_id)) {
+        Some(deposit) if deposit.tick_in_address == caller => deposit,
+        _ => return SLPEarlyWithdrawResponse::Err(format!("Deposit {} not found", deposit_id)),
+    };
+
+    let already_withdrawn = SLP_EARLY_WITHDRAW_RESPONSES.with(|r| r.borrow().contains_key(&deposit_id));
+    if already_withdrawn || SLP_EARLY_WITHDRAWS_IN_FLIGHT.with(|f| f.borrow().contains(&deposit_id)) {
+        return SLPEarlyWithdrawResponse::Err(format!("Deposit {} already withdrawn", deposit_id));
+    }
+
+    let start_time = match deposit.tick_in_timestamp.parse::<u64>() {
+        Ok(time) => time,
+        Err(_) => return SLPEarlyWithdrawResponse::Err(format!("Deposit {} has an invalid timestamp", deposit_id)),
+    };
+    let lock_ns = deposit.duration.0.to_u64().unwrap_or(0).saturating_mul(24 * 60 * 60 * 1_000_000_000);
+    let unlock_time = start_time.saturating_add(lock_ns);
+    let now = ic_cdk::api::time();
+    if now >= unlock_time {
+        return SLPEarlyWithdrawResponse::Err(format!(
+            "Deposit {} is already unlocked, use te_slp_withdraw instead",
+            deposit_id
+        ));
+    }
+
+    let tick_out_ledger_canister_id = match deposit.tick_in_name.as_str() {
+        "BIT10.BTC" => BIT10_BTC_LEDGER_CANISTER_ID,
+        _ => return SLPEarlyWithdrawResponse::Err(format!("Unsupported token: {:?}", deposit.tick_in_name)),
+    };
+
+    let penalty_bps = SLP_EARLY_WITHDRAW_PENALTY_BPS.with(|p| *p.borrow());
+    let penalty_amount = early_withdraw_penalty(&deposit.tick_in_amount, penalty_bps, unlock_time - now, lock_ns);
+    let tick_out_amount = deposit.tick_in_amount.clone() - penalty_amount.clone();
+
+    // The deposit stops earning and is marked as in flight before the
+    // transfer, so it cannot be withdrawn twice concurrently.
+    SLP_EARLY_WITHDRAWS_IN_FLIGHT.with(|f| f.borrow_mut().insert(deposit_id));
+    let position = SLP_REWARDS.with(|r| r.borrow_mut().remove_position(deposit_id));
+
+    let result = transfer_to_user(caller, tick_out_amount.clone(), tick_out_ledger_canister_id).await;
+    SLP_EARLY_WITHDRAWS_IN_FLIGHT.with(|f| f.borrow_mut().remove(&deposit_id));
+
+    match result {
+        Ok(block_index) => {
+            let response_data = SLPEarlyWithdrawResponseData {
+                deposit_id,
+                tick_out_name: deposit.tick_in_name,
+                deposit_amount: deposit.tick_in_amount,
+                penalty_amount: penalty_amount.clone(),
+                tick_out_amount,
+                tick_out_block: block_index,
+                tick_out_address: caller,
+                tick_out_time: ic_cdk::api::time().to_string(),
+            };
+
+            SLP_EARLY_WITHDRAW_RESPONSES.with(|r| r.borrow_mut().insert(deposit_id, response_data.clone()));
+            accrue_revenue(tick_out_ledger_canister_id, penalty_amount);
+
+            SLPEarlyWithdrawResponse::Ok(response_data)
+        }
+        Err(e) => {
+            if let Some(position) = position {
+                SLP_REWARDS.with(|r| r.borrow_mut().restore_position(deposit_id, position));
+            }
+            SLPEarlyWithdrawResponse::Err(e)
+        }
+    }
+}
+
+pub fn set_slp_early_withdraw_penalty_bps(penalty_bps: u16) -> Result<(), String> {
+    if penalty_bps > 10_000 {
+        return Err("Penalty must not exceed 10000 bps".to_string());
+    }
+    SLP_EARLY_WITHDRAW_PENALTY_BPS.with(|p| *p.borrow_mut() = penalty_bps);
+    Ok(())
+}
+
+// Linear in the share of the lock period still remaining.
+fn early_withdraw_penalty(amount: &Nat, penalty_bps: u16, remaining_ns: u64, lock_ns: u64) -> Nat {
+    if lock_ns == 0 {
+        return Nat::from(0u64);
+    }
+    amount.clone() * Nat::from(penalty_bps) * Nat::from(remaining_ns) / (Nat::from(10_000u64) * Nat::from(lock_ns))
+}
+
 // Helper functions
-async fn transfer_from_user(
+pub async fn transfer_from_user(
     caller: Principal,
     amount: Nat,
     canister_id: Principal,
@@ -117,7 +219,7 @@ async fn transfer_from_user(
     }
 }
 
-async fn transfer_to_user(
+pub async fn transfer_to_user(
     caller: Principal,
     amount: Nat,
     canister_id: Principal,
@@ -146,15 +248,20 @@ async fn transfer_to_user(
 }
 
 fn calculate_available_amounts(caller: Principal, token_name: &str) -> (Nat, Nat) {
-    let slp_responses = get_slp_responses();
-    let previous_withdrawals = get_slp_withdraw_responses();
-    
-    let filtered_responses: Vec<&SLPResponseData> = slp_responses.iter()
-        .filter(|response| 
-            response.tick_in_address == caller &&
-            response.tick_in_name == token_name
-        )
-        .collect();
+    let previous_withdrawals: Vec<SLPWithdrawResponseData> =
+        SLP_WITHDRAW_RESPONSES.with(|r| r.borrow().iter().map(|(_, w)| w).collect());
+
+    let filtered_responses: Vec<SLPResponseData> = SLP_RESPONSES.with(|r| {
+        r.borrow()
+            .iter()
+            .filter(|(id, response)|
+                response.tick_in_address == caller &&
+                response.tick_in_name == token_name &&
+                !SLP_EARLY_WITHDRAW_RESPONSES.with(|e| e.borrow().contains_key(id))
+            )
+            .map(|(_, response)| response)
+            .collect()
+    });
 
     let current_time = ic_cdk::api::time();
     let mut total_available = Nat::from(0u64);
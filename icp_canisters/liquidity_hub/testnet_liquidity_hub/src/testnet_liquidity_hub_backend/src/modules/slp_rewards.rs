@@ -0,0 +1,413 @@
+use candid::{CandidType, Deserialize, Nat, Principal, Serialize};
+use std::collections::BTreeMap;
+use crate::constants::{BIT10_BTC_LEDGER_CANISTER_ID, SLP_DURATION_BASE_DAYS};
+use crate::modules::slp_handler::{transfer_from_user, transfer_to_user};
+use crate::state::storage::{ILP_POOLS, SLP_REWARDS};
+
+const NANOS_PER_DAY: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+fn reward_scale() -> Nat {
+    Nat::from(1_000_000_000_000_000_000u128)
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct SlpPosition {
+    pub owner: Principal,
+    pub weight: Nat,
+    pub unlock_time: u64,
+    // Keyed by reward ledger. A ledger that is missing has not been funded
+    // since the position was opened, so nothing is owed against it.
+    pub reward_debt: BTreeMap<Principal, Nat>,
+}
+
+/// One reward token. Stakers earn every token the hub is funded with, each
+/// tracked by its own accumulator over the shared position weights.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct RewardToken {
+    pub acc_reward_per_weight: Nat,
+    // Funding received while nothing was staked, paid out with the next event.
+    pub undistributed: Nat,
+    pub total_funded: Nat,
+    pub claimable: BTreeMap<Principal, Nat>,
+    pub claimed: BTreeMap<Principal, Nat>,
+}
+
+/// Reward accounting for SLP deposits. Every funding event spreads its amount
+/// over the weight of the positions that are still locked, where a position
+/// weighs `amount * (SLP_DURATION_BASE_DAYS + duration_days)`, so longer
+/// locks earn proportionally more.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SlpRewards {
+    pub total_weight: Nat,
+    pub positions: BTreeMap<u64, SlpPosition>,
+    pub tokens: BTreeMap<Principal, RewardToken>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct SlpTokenRewards {
+    pub reward_token_name: String,
+    pub reward_ledger: Principal,
+    pub claimable: Nat,
+    pub claimed: Nat,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct SlpRewardsInfo {
+    pub rewards: Vec<SlpTokenRewards>,
+    pub active_weight: Nat,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct SlpRewardClaim {
+    pub reward_token_name: String,
+    pub reward_ledger: Principal,
+    pub amount: Nat,
+    pub block_index: Nat,
+}
+
+impl RewardToken {
+    fn new() -> Self {
+        RewardToken {
+            acc_reward_per_weight: Nat::from(0u64),
+            undistributed: Nat::from(0u64),
+            total_funded: Nat::from(0u64),
+            claimable: BTreeMap::new(),
+            claimed: BTreeMap::new(),
+        }
+    }
+
+    fn accumulated(&self, weight: &Nat) -> Nat {
+        weight.clone() * self.acc_reward_per_weight.clone() / reward_scale()
+    }
+}
+
+impl SlpRewards {
+    pub fn add_position(&mut self, id: u64, owner: Principal, amount: &Nat, duration_days: u64, start_time: u64) {
+        let weight = amount.clone() * Nat::from(SLP_DURATION_BASE_DAYS + duration_days);
+        let reward_debt = self.debts(&weight);
+        self.total_weight += weight.clone();
+        self.positions.insert(
+            id,
+            SlpPosition {
+                owner,
+                weight,
+                unlock_time: start_time.saturating_add(duration_days.saturating_mul(NANOS_PER_DAY)),
+                reward_debt,
+            },
+        );
+    }
+
+    /// Stops unlocked positions from earning. Their pending rewards move to
+    /// the owner's claimable balance.
+    pub fn expire(&mut self, now: u64) {
+        let expired: Vec<u64> = self
+            .positions
+            .iter()
+            .filter(|(_, p)| p.unlock_time <= now)
+            .map(|(id, _)| *id)
+            .collect();
+
+        for id in expired {
+            self.remove_position(id);
+        }
+    }
+
+    /// Stops a position from earning, e.g. when it is withdrawn early. What it
+    /// earned so far stays claimable by the owner.
+    pub fn remove_position(&mut self, id: u64) -> Option<SlpPosition> {
+        let position = self.positions.remove(&id)?;
+        for (ledger, token) in self.tokens.iter_mut() {
+            let pending = pending(token, &position, ledger);
+            *token.claimable.entry(position.owner).or_insert_with(|| Nat::from(0u64)) += pending;
+        }
+        self.total_weight = saturating_sub(&self.total_weight, &position.weight);
+        Some(position)
+    }
+
+    /// Puts back a position taken out by `remove_position`. Its earnings were
+    /// already moved to the claimable balance, so it resumes from now.
+    pub fn restore_position(&mut self, id: u64, mut position: SlpPosition) {
+        position.reward_debt = self.debts(&position.weight);
+        self.total_weight += position.weight.clone();
+        self.positions.insert(id, position);
+    }
+
+    pub fn fund(&mut self, ledger: Principal, amount: Nat, now: u64) {
+        self.expire(now);
+        let total_weight = self.total_weight.clone();
+        let token = self.tokens.entry(ledger).or_insert_with(RewardToken::new);
+        token.total_funded += amount.clone();
+
+        let amount = amount + std::mem::replace(&mut token.undistributed, Nat::from(0u64));
+        if total_weight == 0u64 {
+            token.undistributed = amount;
+            return;
+        }
+        token.acc_reward_per_weight += amount * reward_scale() / total_weight;
+    }
+
+    /// Moves everything the owner has earned so far in one reward token into
+    /// the claimable balance and takes it out for payment.
+    pub fn take_claimable(&mut self, owner: Principal, ledger: Principal, now: u64) -> Nat {
+        self.expire(now);
+
+        let Some(token) = self.tokens.get_mut(&ledger) else {
+            return Nat::from(0u64);
+        };
+        let mut earned = Nat::from(0u64);
+        for position in self.positions.values_mut().filter(|p| p.owner == owner) {
+            let accumulated = token.accumulated(&position.weight);
+            let debt = position.reward_debt.entry(ledger).or_insert_with(|| Nat::from(0u64));
+            earned += saturating_sub(&accumulated, debt);
+            *debt = accumulated;
+        }
+
+        earned + token.claimable.remove(&owner).unwrap_or_else(|| Nat::from(0u64))
+    }
+
+    pub fn restore_claimable(&mut self, owner: Principal, ledger: Principal, amount: Nat) {
+        if let Some(token) = self.tokens.get_mut(&ledger) {
+            *token.claimable.entry(owner).or_insert_with(|| Nat::from(0u64)) += amount;
+        }
+    }
+
+    pub fn record_claimed(&mut self, owner: Principal, ledger: Principal, amount: Nat) {
+        if let Some(token) = self.tokens.get_mut(&ledger) {
+            *token.claimed.entry(owner).or_insert_with(|| Nat::from(0u64)) += amount;
+        }
+    }
+
+    /// `token_name` names each reward ledger, so a name always follows the
+    /// ledger rather than whoever funded it first.
+    pub fn info(&self, owner: Principal, token_name: impl Fn(&Principal) -> String) -> SlpRewardsInfo {
+        let positions: Vec<&SlpPosition> = self.positions.values().filter(|p| p.owner == owner).collect();
+
+        let rewards = self
+            .tokens
+            .iter()
+            .map(|(ledger, token)| {
+                let mut claimable = token.claimable.get(&owner).cloned().unwrap_or_else(|| Nat::from(0u64));
+                for position in &positions {
+                    claimable += pending(token, position, ledger);
+                }
+                SlpTokenRewards {
+                    reward_token_name: token_name(ledger),
+                    reward_ledger: *ledger,
+                    claimable,
+                    claimed: token.claimed.get(&owner).cloned().unwrap_or_else(|| Nat::from(0u64)),
+                }
+            })
+            .collect();
+
+        let mut active_weight = Nat::from(0u64);
+        for position in positions {
+            active_weight += position.weight.clone();
+        }
+
+        SlpRewardsInfo { rewards, active_weight }
+    }
+
+    fn debts(&self, weight: &Nat) -> BTreeMap<Principal, Nat> {
+        self.tokens
+            .iter()
+            .map(|(ledger, token)| (*ledger, token.accumulated(weight)))
+            .collect()
+    }
+}
+
+fn pending(token: &RewardToken, position: &SlpPosition, ledger: &Principal) -> Nat {
+    let debt = position.reward_debt.get(ledger).cloned().unwrap_or_else(|| Nat::from(0u64));
+    saturating_sub(&token.accumulated(&position.weight), &debt)
+}
+
+fn saturating_sub(a: &Nat, b: &Nat) -> Nat {
+    if a > b {
+        a.clone() - b.clone()
+    } else {
+        Nat::from(0u64)
+    }
+}
+
+/// Credits revenue the hub already holds, such as the ILP spread kept back
+/// from a payout or an early withdrawal penalty.
+pub fn accrue_revenue(ledger: Principal, amount: Nat) {
+    if amount == Nat::from(0u64) {
+        return;
+    }
+    SLP_REWARDS.with(|r| r.borrow_mut().fund(ledger, amount, ic_cdk::api::time()));
+}
+
+/// Names the tokens the hub can pay rewards in: BIT10.BTC and whatever the
+/// ILP pools pay out.
+fn reward_token_name(ledger: Principal) -> Option<String> {
+    if ledger == BIT10_BTC_LEDGER_CANISTER_ID {
+        return Some("BIT10.BTC".to_string());
+    }
+    ILP_POOLS.with(|pools| {
+        pools
+            .borrow()
+            .values()
+            .find(|pool| pool.tick_out_ledger == ledger)
+            .map(|pool| pool.tick_out_name.clone())
+    })
+}
+
+/// A ledger that is no longer listed keeps earning out under its id.
+fn display_name(ledger: &Principal) -> String {
+    reward_token_name(*ledger).unwrap_or_else(|| ledger.to_text())
+}
+
+/// Pulls reward tokens from the caller through an ICRC-2 approval and
+/// distributes them to stakers.
+pub async fn fund_slp_rewards(ledger: Principal, amount: Nat) -> Result<Nat, String> {
+    if amount == Nat::from(0u64) {
+        return Err("Amount must be greater than 0".to_string());
+    }
+    if reward_token_name(ledger).is_none() {
+        return Err(format!("Unsupported reward ledger: {}", ledger));
+    }
+    let block_index = transfer_from_user(ic_cdk::caller(), amount.clone(), ledger).await?;
+    SLP_REWARDS.with(|r| r.borrow_mut().fund(ledger, amount, ic_cdk::api::time()));
+    Ok(block_index)
+}
+
+pub async fn claim_slp_rewards(ledger: Principal) -> Result<SlpRewardClaim, String> {
+    let caller = ic_cdk::caller();
+    let amount = SLP_REWARDS.with(|r| r.borrow_mut().take_claimable(caller, ledger, ic_cdk::api::time()));
+    if amount == Nat::from(0u64) {
+        return Err("No rewards to claim".to_string());
+    }
+
+    match transfer_to_user(caller, amount.clone(), ledger).await {
+        Ok(block_index) => {
+            SLP_REWARDS.with(|r| r.borrow_mut().record_claimed(caller, ledger, amount.clone()));
+            Ok(SlpRewardClaim {
+                reward_token_name: display_name(&ledger),
+                reward_ledger: ledger,
+                amount,
+                block_index,
+            })
+        }
+        Err(e) => {
+            SLP_REWARDS.with(|r| r.borrow_mut().restore_claimable(caller, ledger, amount));
+            Err(e)
+        }
+    }
+}
+
+pub fn get_slp_rewards(owner: Principal) -> SlpRewardsInfo {
+    SLP_REWARDS.with(|r| r.borrow().info(owner, display_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = NANOS_PER_DAY;
+
+    // A 35 day lock of 1 weighs 400, which divides the reward scale, so the
+    // expected amounts below carry no rounding.
+
+    fn principal(id: u8) -> Principal {
+        Principal::from_slice(&[id])
+    }
+
+    fn nat(value: u64) -> Nat {
+        Nat::from(value)
+    }
+
+    fn btc() -> Principal {
+        principal(100)
+    }
+
+    fn usdc() -> Principal {
+        principal(101)
+    }
+
+    fn token_name(ledger: &Principal) -> String {
+        let name = if *ledger == btc() { "BIT10.BTC" } else { "USDC" };
+        name.to_string()
+    }
+
+    #[test]
+    fn funding_is_split_by_weight() {
+        let mut rewards = SlpRewards::default();
+        rewards.add_position(1, principal(1), &nat(100), 30, 0);
+        rewards.add_position(2, principal(2), &nat(100), 425, 0);
+
+        // Weights are 100 * 395 and 100 * 790.
+        rewards.fund(btc(), nat(1_185), 0);
+        assert_eq!(rewards.take_claimable(principal(1), btc(), 0), nat(395));
+        assert_eq!(rewards.take_claimable(principal(2), btc(), 0), nat(790));
+        assert_eq!(rewards.take_claimable(principal(1), btc(), 0), nat(0));
+    }
+
+    #[test]
+    fn each_reward_ledger_is_tracked_separately() {
+        let mut rewards = SlpRewards::default();
+        rewards.add_position(1, principal(1), &nat(1), 35, 0);
+        rewards.fund(btc(), nat(50), 0);
+        rewards.fund(usdc(), nat(70), 0);
+
+        assert_eq!(rewards.take_claimable(principal(1), btc(), 0), nat(50));
+        let info = rewards.info(principal(1), token_name);
+        let usdc_rewards = info.rewards.iter().find(|r| r.reward_ledger == usdc()).unwrap();
+        assert_eq!(usdc_rewards.reward_token_name, "USDC");
+        assert_eq!(usdc_rewards.claimable, nat(70));
+        assert_eq!(rewards.take_claimable(principal(1), principal(102), 0), nat(0));
+    }
+
+    #[test]
+    fn new_positions_do_not_earn_past_funding() {
+        let mut rewards = SlpRewards::default();
+        rewards.add_position(1, principal(1), &nat(1), 35, 0);
+        rewards.fund(btc(), nat(100), 0);
+        rewards.add_position(2, principal(2), &nat(1), 35, 0);
+        rewards.fund(btc(), nat(100), 0);
+
+        assert_eq!(rewards.take_claimable(principal(1), btc(), 0), nat(150));
+        assert_eq!(rewards.take_claimable(principal(2), btc(), 0), nat(50));
+    }
+
+    #[test]
+    fn funding_without_stakers_waits_for_the_next_event() {
+        let mut rewards = SlpRewards::default();
+        rewards.fund(btc(), nat(100), 0);
+        rewards.add_position(1, principal(1), &nat(1), 35, 0);
+        assert_eq!(rewards.take_claimable(principal(1), btc(), 0), nat(0));
+
+        rewards.fund(btc(), nat(20), 0);
+        assert_eq!(rewards.take_claimable(principal(1), btc(), 0), nat(120));
+        assert_eq!(rewards.tokens[&btc()].total_funded, nat(120));
+    }
+
+    #[test]
+    fn unlocked_positions_keep_their_rewards_but_stop_earning() {
+        let mut rewards = SlpRewards::default();
+        rewards.add_position(1, principal(1), &nat(1), 35, 0);
+        rewards.add_position(2, principal(2), &nat(1), 35, 30 * DAY);
+        rewards.fund(btc(), nat(100), 0);
+        rewards.fund(btc(), nat(100), 35 * DAY);
+
+        assert_eq!(rewards.take_claimable(principal(1), btc(), 35 * DAY), nat(50));
+        assert_eq!(rewards.take_claimable(principal(2), btc(), 35 * DAY), nat(150));
+        assert_eq!(rewards.info(principal(1), token_name).active_weight, nat(0));
+    }
+
+    #[test]
+    fn failed_claims_are_restored() {
+        let mut rewards = SlpRewards::default();
+        rewards.add_position(1, principal(1), &nat(1), 35, 0);
+        rewards.fund(btc(), nat(100), 0);
+
+        let amount = rewards.take_claimable(principal(1), btc(), 0);
+        rewards.restore_claimable(principal(1), btc(), amount);
+        let amount = rewards.take_claimable(principal(1), btc(), 0);
+        assert_eq!(amount, nat(100));
+
+        rewards.record_claimed(principal(1), btc(), amount);
+        let info = rewards.info(principal(1), token_name);
+        assert_eq!(info.rewards[0].claimed, nat(100));
+        assert_eq!(info.rewards[0].claimable, nat(0));
+    }
+}
@@ -0,0 +1,48 @@
+use candid::{CandidType, Deserialize, Serialize};
+use candid::Nat;
+use ic_cdk::Principal;
+
+#[derive(CandidType, Serialize, Deserialize, Clone)]
+pub struct TreasuryRepayment {
+    pub amount: Nat,
+    pub from: Principal,
+    pub block: Nat,
+    pub time: String,
+}
+
+/// Locked SLP liquidity a controller moved out of the hub with
+/// `te_transfer_from_canister`, together with what has been paid back.
+#[derive(CandidType, Serialize, Deserialize, Clone)]
+pub struct TreasuryDeployment {
+    pub deployment_id: u64,
+    pub tick_out_name: String,
+    pub amount: Nat,
+    pub repaid: Nat,
+    pub destination: Principal,
+    pub caller: Principal,
+    pub reason: String,
+    pub block: Option<Nat>,
+    pub deployed_time: String,
+    pub expected_return_time: String,
+    pub repayments: Vec<TreasuryRepayment>,
+}
+
+#[derive(CandidType, Deserialize, Clone)]
+pub struct RepayDeploymentArgs {
+    pub deployment_id: u64,
+    pub amount: Nat,
+}
+
+#[derive(CandidType, Serialize)]
+pub enum RepayDeploymentResponse {
+    Ok(TreasuryDeployment),
+    Err(String),
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone)]
+pub struct TreasuryExposure {
+    pub tick_name: String,
+    pub locked_slp_balance: Nat,
+    pub outstanding: Nat,
+    pub deployments: Vec<TreasuryDeployment>,
+}
@@ -0,0 +1,228 @@
+use candid::{Principal, Nat};
+use num_traits::ToPrimitive;
+use crate::constants::*;
+use crate::modules::{
+    RepayDeploymentArgs, RepayDeploymentResponse, TransferFromCanisterArgs, TransferFromCanisterResponse,
+    TransferFromCanisterResponseData, TreasuryDeployment, TreasuryExposure, TreasuryRepayment,
+};
+use crate::modules::slp_handler::{transfer_from_user, transfer_to_user};
+use crate::state::storage::{
+    next_treasury_deployment_id, SLP_EARLY_WITHDRAW_RESPONSES, SLP_RESPONSES, TREASURY_DEPLOYMENTS,
+    TREASURY_REPAYMENTS_IN_FLIGHT,
+};
+
+const NANOS_PER_DAY: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+/// Moves still-locked SLP liquidity out of the hub. Every movement is kept
+/// as a deployment that stays outstanding until it is repaid in full.
+pub async fn handle_transfer_from_canister(caller: Principal, args: TransferFromCanisterArgs) -> TransferFromCanisterResponse {
+    if args.reason.trim().is_empty() {
+        return TransferFromCanisterResponse::Err("A reason is required".to_string());
+    }
+
+    let tick_out_duration_ns = args.tick_out_duration.0.to_u64().unwrap_or(0).saturating_mul(NANOS_PER_DAY);
+    if tick_out_duration_ns == 0 {
+        return TransferFromCanisterResponse::Err("Invalid duration".to_string());
+    }
+
+    let tick_out_ledger_canister_id = match args.tick_out_name.as_str() {
+        "BIT10.BTC" => BIT10_BTC_LEDGER_CANISTER_ID,
+        _ => return TransferFromCanisterResponse::Err(format!("Unsupported token: {:?}", args.tick_out_name)),
+    };
+
+    let total_available = saturating_sub(
+        &locked_slp_balance(&args.tick_out_name),
+        &outstanding_amount(&args.tick_out_name),
+    );
+    if total_available < args.tick_out_amount {
+        return TransferFromCanisterResponse::Err(format!(
+            "Insufficient available balance. Available: {}, Requested: {}",
+            total_available, args.tick_out_amount
+        ));
+    }
+
+    let now = ic_cdk::api::time();
+    let expected_return_time = now.saturating_add(tick_out_duration_ns).to_string();
+
+    // Recorded before the transfer so concurrent calls see the amount as
+    // deployed; dropped again if the transfer fails.
+    let deployment_id = next_treasury_deployment_id();
+    TREASURY_DEPLOYMENTS.with(|d| {
+        d.borrow_mut().insert(
+            deployment_id,
+            TreasuryDeployment {
+                deployment_id,
+                tick_out_name: args.tick_out_name.clone(),
+                amount: args.tick_out_amount.clone(),
+                repaid: Nat::from(0u64),
+                destination: args.tick_out_address,
+                caller,
+                reason: args.reason.clone(),
+                block: None,
+                deployed_time: now.to_string(),
+                expected_return_time: expected_return_time.clone(),
+                repayments: Vec::new(),
+            },
+        )
+    });
+
+    match transfer_to_user(args.tick_out_address, args.tick_out_amount.clone(), tick_out_ledger_canister_id).await {
+        Ok(block_index) => {
+            TREASURY_DEPLOYMENTS.with(|d| {
+                let mut deployments = d.borrow_mut();
+                if let Some(mut deployment) = deployments.get(&deployment_id) {
+                    deployment.block = Some(block_index.clone());
+                    deployments.insert(deployment_id, deployment);
+                }
+            });
+
+            TransferFromCanisterResponse::Ok(TransferFromCanisterResponseData {
+                tick_out_name: args.tick_out_name,
+                tick_out_amount: args.tick_out_amount,
+                tick_out_address: args.tick_out_address,
+                tick_out_caller: caller,
+                tick_out_block: block_index,
+                tick_out_time: ic_cdk::api::time().to_string(),
+                deployment_id,
+                expected_return_time,
+            })
+        }
+        Err(e) => {
+            TREASURY_DEPLOYMENTS.with(|d| d.borrow_mut().remove(&deployment_id));
+            TransferFromCanisterResponse::Err(e)
+        }
+    }
+}
+
+/// Pulls `amount` back from the caller through an ICRC-2 approval and books
+/// it against the deployment. Anyone may repay, usually the destination.
+pub async fn handle_repay_deployment(args: RepayDeploymentArgs) -> RepayDeploymentResponse {
+    let caller: Principal = ic_cdk::caller();
+
+    let deployment = match TREASURY_DEPLOYMENTS.with(|d| d.borrow().get(&args.deployment_id)) {
+        Some(deployment) if deployment.block.is_some() => deployment,
+        _ => return RepayDeploymentResponse::Err(format!("Deployment {} not found", args.deployment_id)),
+    };
+
+    let in_flight = TREASURY_REPAYMENTS_IN_FLIGHT
+        .with(|r| r.borrow().get(&args.deployment_id).cloned())
+        .unwrap_or_else(|| Nat::from(0u64));
+    let remaining = saturating_sub(&saturating_sub(&deployment.amount, &deployment.repaid), &in_flight);
+    if args.amount == Nat::from(0u64) {
+        return RepayDeploymentResponse::Err("Amount must be greater than 0".to_string());
+    }
+    if args.amount > remaining {
+        return RepayDeploymentResponse::Err(format!(
+            "Repayment exceeds the outstanding amount. Outstanding: {}, Requested: {}",
+            remaining, args.amount
+        ));
+    }
+
+    let tick_in_ledger_canister_id = match deployment.tick_out_name.as_str() {
+        "BIT10.BTC" => BIT10_BTC_LEDGER_CANISTER_ID,
+        _ => return RepayDeploymentResponse::Err(format!("Unsupported token: {:?}", deployment.tick_out_name)),
+    };
+
+    // Reserved before the transfer so concurrent repayments cannot together
+    // pull in more than is outstanding.
+    TREASURY_REPAYMENTS_IN_FLIGHT.with(|r| {
+        *r.borrow_mut().entry(args.deployment_id).or_insert_with(|| Nat::from(0u64)) += args.amount.clone()
+    });
+    let result = transfer_from_user(caller, args.amount.clone(), tick_in_ledger_canister_id).await;
+    release_repayment(args.deployment_id, &args.amount);
+
+    match result {
+        Ok(block_index) => TREASURY_DEPLOYMENTS.with(|d| {
+            let mut deployments = d.borrow_mut();
+            match deployments.get(&args.deployment_id) {
+                Some(mut deployment) => {
+                    deployment.repaid += args.amount.clone();
+                    deployment.repayments.push(TreasuryRepayment {
+                        amount: args.amount,
+                        from: caller,
+                        block: block_index,
+                        time: ic_cdk::api::time().to_string(),
+                    });
+                    deployments.insert(args.deployment_id, deployment.clone());
+                    RepayDeploymentResponse::Ok(deployment)
+                }
+                None => RepayDeploymentResponse::Err(format!("Deployment {} not found", args.deployment_id)),
+            }
+        }),
+        Err(e) => RepayDeploymentResponse::Err(e),
+    }
+}
+
+fn release_repayment(deployment_id: u64, amount: &Nat) {
+    TREASURY_REPAYMENTS_IN_FLIGHT.with(|r| {
+        let mut in_flight = r.borrow_mut();
+        if let Some(reserved) = in_flight.get(&deployment_id) {
+            let reserved = saturating_sub(reserved, amount);
+            if reserved == Nat::from(0u64) {
+                in_flight.remove(&deployment_id);
+            } else {
+                in_flight.insert(deployment_id, reserved);
+            }
+        }
+    });
+}
+
+pub fn get_treasury_deployments() -> Vec<TreasuryDeployment> {
+    TREASURY_DEPLOYMENTS.with(|d| d.borrow().iter().map(|(_, deployment)| deployment).collect())
+}
+
+/// Outstanding deployments of `tick_name` next to the SLP balance that is
+/// still locked, i.e. how much of the stakers' liquidity is in use.
+pub fn get_treasury_exposure(tick_name: String) -> TreasuryExposure {
+    let deployments: Vec<TreasuryDeployment> = get_treasury_deployments()
+        .into_iter()
+        .filter(|d| d.tick_out_name == tick_name && d.repaid < d.amount)
+        .collect();
+
+    TreasuryExposure {
+        locked_slp_balance: locked_slp_balance(&tick_name),
+        outstanding: outstanding_amount(&tick_name),
+        tick_name,
+        deployments,
+    }
+}
+
+fn locked_slp_balance(tick_name: &str) -> Nat {
+    let now = ic_cdk::api::time();
+    SLP_RESPONSES.with(|responses| {
+        responses
+            .borrow()
+            .iter()
+            .filter(|(id, response)| {
+                response.tick_in_name == tick_name
+                    && !SLP_EARLY_WITHDRAW_RESPONSES.with(|e| e.borrow().contains_key(id))
+            })
+            .filter(|(_, response)| {
+                let start_time = match response.tick_in_timestamp.parse::<u64>() {
+                    Ok(time) => time,
+                    Err(_) => return false,
+                };
+                let duration_ns = response.duration.0.to_u64().unwrap_or(0).saturating_mul(NANOS_PER_DAY);
+                now < start_time.saturating_add(duration_ns)
+            })
+            .fold(Nat::from(0u64), |acc, (_, response)| acc + response.tick_in_amount.clone())
+    })
+}
+
+fn outstanding_amount(tick_name: &str) -> Nat {
+    TREASURY_DEPLOYMENTS.with(|deployments| {
+        deployments
+            .borrow()
+            .iter()
+            .filter(|(_, d)| d.tick_out_name == tick_name)
+            .fold(Nat::from(0u64), |acc, (_, d)| acc + saturating_sub(&d.amount, &d.repaid))
+    })
+}
+
+fn saturating_sub(a: &Nat, b: &Nat) -> Nat {
+    if a > b {
+        a.clone() - b.clone()
+    } else {
+        Nat::from(0u64)
+    }
+}
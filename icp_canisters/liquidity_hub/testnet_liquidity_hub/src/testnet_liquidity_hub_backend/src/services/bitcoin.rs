@@ -0,0 +1,109 @@
+use bech32::{u5, ToBase32, Variant};
+use candid::{Nat, Principal};
+use ic_cdk::api::management_canister::bitcoin::{
+    bitcoin_get_utxos, BitcoinNetwork, GetUtxosRequest, UtxoFilter,
+};
+use ic_cdk::api::management_canister::ecdsa::{
+    ecdsa_public_key, EcdsaCurve, EcdsaKeyId, EcdsaPublicKeyArgument,
+};
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+use crate::constants::{BTC_DEPOSIT_ECDSA_KEY_NAME, BTC_NETWORK};
+use crate::state::storage::{BTC_DEPOSIT_ADDRESSES, BTC_MIN_CONFIRMATIONS};
+
+/// P2WPKH address controlled by the canister through threshold ECDSA and
+/// derived from the user's principal. Users send BTC here before calling ILP.
+pub async fn btc_deposit_address(user: Principal) -> Result<String, String> {
+    if let Some(address) = BTC_DEPOSIT_ADDRESSES.with(|a| a.borrow().get(&user).cloned()) {
+        return Ok(address);
+    }
+
+    let (response,) = ecdsa_public_key(EcdsaPublicKeyArgument {
+        canister_id: None,
+        derivation_path: deposit_derivation_path(&user),
+        key_id: EcdsaKeyId {
+            curve: EcdsaCurve::Secp256k1,
+            name: BTC_DEPOSIT_ECDSA_KEY_NAME.to_string(),
+        },
+    })
+    .await
+    .map_err(|(code, msg)| format!("Failed to get ECDSA public key: {:?} - {}", code, msg))?;
+
+    let address = p2wpkh_address(&response.public_key, BTC_NETWORK)?;
+    BTC_DEPOSIT_ADDRESSES.with(|a| a.borrow_mut().insert(user, address.clone()));
+    Ok(address)
+}
+
+/// The value of output `vout` of `txid` if it pays `address` and has at least
+/// the configured number of confirmations. Returns zero if the output is
+/// unknown, pays a different address or is not deep enough yet.
+pub async fn confirmed_deposit_amount(address: &str, txid: &str, vout: u32) -> Result<Nat, String> {
+    let mut txid_bytes = hex_decode(txid)?;
+    if txid_bytes.len() != 32 {
+        return Err(format!("Invalid Bitcoin transaction id: {}", txid));
+    }
+    // Transaction ids are displayed in reverse byte order.
+    txid_bytes.reverse();
+
+    let min_confirmations = BTC_MIN_CONFIRMATIONS.with(|c| *c.borrow());
+    let mut filter = Some(UtxoFilter::MinConfirmations(min_confirmations));
+    let mut amount = 0u64;
+
+    loop {
+        let (response,) = bitcoin_get_utxos(GetUtxosRequest {
+            address: address.to_string(),
+            network: BTC_NETWORK,
+            filter,
+        })
+        .await
+        .map_err(|(code, msg)| format!("bitcoin_get_utxos failed: {:?} - {}", code, msg))?;
+
+        amount += response
+            .utxos
+            .iter()
+            .filter(|utxo| utxo.outpoint.txid == txid_bytes && utxo.outpoint.vout == vout)
+            .map(|utxo| utxo.value)
+            .sum::<u64>();
+
+        match response.next_page {
+            Some(page) => filter = Some(UtxoFilter::Page(page)),
+            None => break,
+        }
+    }
+
+    Ok(Nat::from(amount))
+}
+
+fn deposit_derivation_path(user: &Principal) -> Vec<Vec<u8>> {
+    const SCHEMA_V1: u8 = 1;
+    vec![vec![SCHEMA_V1], user.as_slice().to_vec()]
+}
+
+fn p2wpkh_address(public_key: &[u8], network: BitcoinNetwork) -> Result<String, String> {
+    let hrp = match network {
+        BitcoinNetwork::Mainnet => "bc",
+        BitcoinNetwork::Testnet => "tb",
+        BitcoinNetwork::Regtest => "bcrt",
+    };
+
+    let sha = Sha256::digest(public_key);
+    let pubkey_hash = Ripemd160::digest(sha);
+
+    let mut data = vec![u5::try_from_u8(0).expect("0 is a valid witness version")];
+    data.extend(pubkey_hash.to_base32());
+    bech32::encode(hrp, data, Variant::Bech32)
+        .map_err(|e| format!("Failed to encode deposit address: {}", e))
+}
+
+fn hex_decode(hex: &str) -> Result<Vec<u8>, String> {
+    if hex.len() % 2 != 0 {
+        return Err(format!("Invalid hex string: {}", hex));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .map_err(|e| format!("Invalid hex string {}: {}", hex, e))
+        })
+        .collect()
+}
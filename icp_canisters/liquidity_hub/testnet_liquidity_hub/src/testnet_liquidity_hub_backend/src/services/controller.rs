@@ -0,0 +1,17 @@
+use candid::Principal;
+use ic_cdk::api::management_canister::main::{canister_status, CanisterIdRecord};
+
+pub async fn ensure_controller(caller: Principal) -> Result<(), String> {
+    let canister_id = ic_cdk::id();
+    let controllers = match canister_status(CanisterIdRecord { canister_id }).await {
+        Ok((status,)) => status.settings.controllers,
+        Err((code, msg)) => {
+            return Err(format!("Failed to get canister status: {:?} - {}", code, msg));
+        }
+    };
+
+    if !controllers.contains(&caller) {
+        return Err("Only the canister controller can call this method".to_string());
+    }
+    Ok(())
+}
@@ -1,11 +1,27 @@
 use ic_cdk::api::management_canister::http_request::{HttpMethod, http_request, CanisterHttpRequestArgument};
-use crate::constants::{HTTP_REQUEST_CYCLES, MAX_RETRY_COUNT, RETRY_INTERVAL_SECONDS};
+use crate::constants::{MAX_RETRY_COUNT, RETRY_INTERVAL_SECONDS};
+use bit10_core::cycles;
 use crate::state::storage::{RETRY_COUNT, RETRY_RESULT, TIMER_ID};
 use ic_cdk_timers;
 use std::time::Duration;
 
+/// Cycles the IC charges for `request`, from its size and response limit.
+fn outcall_cost(request: &CanisterHttpRequestArgument) -> u128 {
+    let bytes = cycles::request_bytes(
+        &request.url,
+        request.headers.iter().map(|h| (h.name.as_str(), h.value.as_str())),
+        request.body.as_deref(),
+        request
+            .transform
+            .as_ref()
+            .map(|t| (t.function.0.method.as_str(), t.context.as_slice())),
+    );
+    cycles::http_request_cost(bytes, request.max_response_bytes)
+}
+
 pub async fn retry_http_request(request: CanisterHttpRequestArgument) -> Result<Vec<u8>, String> {
-    match http_request(request.clone(), HTTP_REQUEST_CYCLES).await {
+    let cycles = outcall_cost(&request);
+    match http_request(request.clone(), cycles).await {
         Ok((response,)) => {
             if response.status.0.to_u64().unwrap_or(0) == 200 {
                 Ok(response.body)
@@ -34,7 +50,7 @@ pub fn start_retry_interval(request: CanisterHttpRequestArgument) {
                 let request = request.clone();
                 async move {
                     if let Some(Ok(response_body)) = RETRY_RESULT.with(|result| result.borrow().clone()) {
-                        if let Ok(verification) = serde_json::from_slice::<crate::models::common::VerificationResponse>(&response_body) {
+                        if let Ok(verification) = serde_json::from_slice::<crate::modules::common::VerificationResponse>(&response_body) {
                             if verification.message.contains("Transaction verified successfully") ||
                                verification.message.contains("First output address does not match expected address") {
                                 ic_cdk::println!("Stopping retries as transaction is verified or address mismatch");
@@ -95,7 +111,7 @@ pub async fn make_http_request(request: CanisterHttpRequestArgument) -> Result<V
                     },
                     "raw_rand",
                     (),
-                    HTTP_REQUEST_CYCLES
+                    0
                 ).await.unwrap_or_default();
                 
                 if let Some(result) = RETRY_RESULT.with(|result| result.borrow().clone()) {
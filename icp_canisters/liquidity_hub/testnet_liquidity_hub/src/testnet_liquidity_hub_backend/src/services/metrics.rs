@@ -0,0 +1,46 @@
+use crate::state::storage::{DEPOSIT_REGISTRY, SLP_EARLY_WITHDRAWS_IN_FLIGHT, TREASURY_REPAYMENTS_IN_FLIGHT};
+use bit10_core::metrics::{self, HealthReport, HttpRequest, HttpResponse, MetricsEncoder, PriceFeedClock, DEFAULT_MAX_FEED_AGE_NS};
+use std::cell::RefCell;
+
+thread_local! {
+    static PRICE_FEEDS: RefCell<PriceFeedClock> = RefCell::new(PriceFeedClock::default());
+}
+
+pub fn record_price_feed(feed: &str, succeeded: bool) {
+    PRICE_FEEDS.with(|f| f.borrow_mut().record(feed, succeeded, ic_cdk::api::time()));
+}
+
+pub fn http_request(request: HttpRequest) -> HttpResponse {
+    match request.path() {
+        "/metrics" => encode_metrics().into_response(),
+        "/health" => health().into_response(),
+        _ => HttpResponse::not_found(),
+    }
+}
+
+// Solana reads go to the primary provider only, so there is no provider
+// health to report.
+fn encode_metrics() -> MetricsEncoder {
+    let mut encoder = MetricsEncoder::new();
+    metrics::encode_canister(
+        &mut encoder,
+        ic_cdk::api::canister_balance128(),
+        ic_cdk::api::stable::stable64_size() * 65536,
+    );
+    metrics::encode_pending_sagas(
+        &mut encoder,
+        &[
+            ("deposit", DEPOSIT_REGISTRY.with(|r| metrics::deposits_in_flight(&r.borrow()))),
+            ("slp_early_withdraw", SLP_EARLY_WITHDRAWS_IN_FLIGHT.with(|w| w.borrow().len())),
+            ("treasury_repayment", TREASURY_REPAYMENTS_IN_FLIGHT.with(|r| r.borrow().len())),
+        ],
+    );
+    PRICE_FEEDS.with(|f| f.borrow().encode(&mut encoder));
+    encoder
+}
+
+fn health() -> HealthReport {
+    let mut report = HealthReport::new(ic_cdk::api::time());
+    PRICE_FEEDS.with(|f| f.borrow().check(&mut report, DEFAULT_MAX_FEED_AGE_NS));
+    report
+}
@@ -1,5 +1,10 @@
+pub mod bitcoin;
+pub mod controller;
 pub mod http;
-pub mod verification;
+pub mod metrics;
+pub mod solana;
 
 pub use http::*;
-pub use verification::*;
+pub use solana::*;
+pub use bitcoin::*;
+pub use controller::*;
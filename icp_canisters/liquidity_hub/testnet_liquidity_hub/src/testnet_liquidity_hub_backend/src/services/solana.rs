@@ -0,0 +1,169 @@
+use candid::{CandidType, Deserialize, Nat, Principal};
+use bit10_core::providers::{normalize_json_rpc, RpcProvider};
+use bit10_core::transform::ResponseShape;
+use ic_cdk::api::management_canister::http_request::{
+    CanisterHttpRequestArgument, HttpHeader, HttpMethod, TransformContext,
+};
+use crate::constants::SOL_DEPOSIT_SCHNORR_KEY_NAME;
+use crate::services::http::retry_http_request;
+use crate::state::storage::{solana_rpc_providers, with_solana_rpc_providers_mut, SOL_DEPOSIT_ADDRESSES};
+
+#[derive(CandidType, Deserialize)]
+enum SchnorrAlgorithm {
+    #[serde(rename = "ed25519")]
+    Ed25519,
+}
+
+#[derive(CandidType, Deserialize)]
+struct SchnorrKeyId {
+    algorithm: SchnorrAlgorithm,
+    name: String,
+}
+
+#[derive(CandidType, Deserialize)]
+struct SchnorrPublicKeyArgument {
+    canister_id: Option<Principal>,
+    derivation_path: Vec<Vec<u8>>,
+    key_id: SchnorrKeyId,
+}
+
+#[derive(CandidType, Deserialize)]
+struct SchnorrPublicKeyResponse {
+    public_key: Vec<u8>,
+    chain_code: Vec<u8>,
+}
+
+/// Solana address derived for the user from the hub's threshold Ed25519 key.
+/// Users send SOL or SPL tokens here before calling ILP.
+pub async fn sol_deposit_address(user: Principal) -> Result<String, String> {
+    if let Some(address) = SOL_DEPOSIT_ADDRESSES.with(|a| a.borrow().get(&user).cloned()) {
+        return Ok(address);
+    }
+
+    let (response,): (SchnorrPublicKeyResponse,) = ic_cdk::call(
+        Principal::management_canister(),
+        "schnorr_public_key",
+        (SchnorrPublicKeyArgument {
+            canister_id: None,
+            derivation_path: vec![vec![1u8], user.as_slice().to_vec()],
+            key_id: SchnorrKeyId {
+                algorithm: SchnorrAlgorithm::Ed25519,
+                name: SOL_DEPOSIT_SCHNORR_KEY_NAME.to_string(),
+            },
+        },),
+    )
+    .await
+    .map_err(|(code, msg)| format!("Failed to get Ed25519 public key: {:?} - {}", code, msg))?;
+
+    let address = bs58::encode(&response.public_key).into_string();
+    SOL_DEPOSIT_ADDRESSES.with(|a| a.borrow_mut().insert(user, address.clone()));
+    Ok(address)
+}
+
+/// Fetches a finalized transaction from every Solana provider and keeps the
+/// one a quorum agrees on. Returns an error if it is unknown, not yet
+/// finalized, or failed on chain.
+pub async fn get_finalized_transaction(signature: &str) -> Result<serde_json::Value, String> {
+    let json_payload = format!(
+        r#"{{"jsonrpc":"2.0","id":1,"method":"getTransaction","params":["{}",{{"encoding":"json","maxSupportedTransactionVersion":0,"commitment":"finalized"}}]}}"#,
+        signature
+    );
+
+    let providers = solana_rpc_providers().callable();
+    let mut responses = Vec::with_capacity(providers.len());
+    for provider in &providers {
+        let response = post_to_provider(provider, &json_payload)
+            .await
+            .and_then(|body| normalize_json_rpc(&body));
+        responses.push((provider.name.clone(), response));
+    }
+    let result = with_solana_rpc_providers_mut(|set| set.tally(responses, ic_cdk::api::time()))?;
+
+    let transaction: serde_json::Value = serde_json::from_str(&result)
+        .map_err(|e| format!("Failed to parse transaction response: {}", e))?;
+    if transaction.is_null() {
+        return Err(format!("Transaction {} not found or not finalized yet", signature));
+    }
+    if !transaction["meta"]["err"].is_null() {
+        return Err(format!("Transaction {} failed on chain", signature));
+    }
+    Ok(transaction)
+}
+
+async fn post_to_provider(provider: &RpcProvider, json_payload: &str) -> Result<String, String> {
+    let transform_context =
+        TransformContext::from_name("transform".to_string(), ResponseShape::for_payload(json_payload).to_context());
+    let mut headers = vec![HttpHeader {
+        name: "Content-Type".to_string(),
+        value: "application/json".to_string(),
+    }];
+    headers.extend(
+        provider
+            .resolved_headers()
+            .into_iter()
+            .map(|(name, value)| HttpHeader { name, value }),
+    );
+
+    let request = CanisterHttpRequestArgument {
+        url: provider.resolved_url(),
+        method: HttpMethod::POST,
+        body: Some(json_payload.as_bytes().to_vec()),
+        max_response_bytes: Some(100_000),
+        transform: Some(transform_context),
+        headers,
+    };
+
+    let body = retry_http_request(request).await?;
+    String::from_utf8(body).map_err(|e| format!("Failed to decode response: {}", e))
+}
+
+/// Lamports credited to `address` by the transaction.
+pub fn sol_credited(transaction: &serde_json::Value, address: &str) -> Result<Nat, String> {
+    let index = account_keys(transaction)
+        .iter()
+        .position(|key| key == address)
+        .ok_or_else(|| format!("Transaction does not touch deposit address {}", address))?;
+
+    let meta = &transaction["meta"];
+    let pre = meta["preBalances"][index].as_u64().unwrap_or(0);
+    let post = meta["postBalances"][index].as_u64().unwrap_or(0);
+    Ok(Nat::from(post.saturating_sub(pre)))
+}
+
+/// Base units of `mint` credited to token accounts owned by `owner`.
+pub fn spl_credited(transaction: &serde_json::Value, owner: &str, mint: &str) -> Result<Nat, String> {
+    let meta = &transaction["meta"];
+    let total = |balances: &serde_json::Value| -> u128 {
+        balances
+            .as_array()
+            .map(|entries| {
+                entries
+                    .iter()
+                    .filter(|b| b["owner"].as_str() == Some(owner) && b["mint"].as_str() == Some(mint))
+                    .filter_map(|b| b["uiTokenAmount"]["amount"].as_str())
+                    .filter_map(|amount| amount.parse::<u128>().ok())
+                    .sum()
+            })
+            .unwrap_or(0)
+    };
+
+    let pre = total(&meta["preTokenBalances"]);
+    let post = total(&meta["postTokenBalances"]);
+    Ok(Nat::from(post.saturating_sub(pre)))
+}
+
+// Static keys followed by the addresses loaded from lookup tables, which is
+// the order balances are reported in.
+fn account_keys(transaction: &serde_json::Value) -> Vec<String> {
+    let as_strings = |value: &serde_json::Value| -> Vec<String> {
+        value
+            .as_array()
+            .map(|keys| keys.iter().filter_map(|k| k.as_str().map(str::to_string)).collect())
+            .unwrap_or_default()
+    };
+
+    let mut keys = as_strings(&transaction["transaction"]["message"]["accountKeys"]);
+    keys.extend(as_strings(&transaction["meta"]["loadedAddresses"]["writable"]));
+    keys.extend(as_strings(&transaction["meta"]["loadedAddresses"]["readonly"]));
+    keys
+}
@@ -1,55 +1,439 @@
-use std::collections::BTreeMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
 use std::cell::RefCell;
+use std::thread::LocalKey;
+use bit10_core::deposits::{deposit_key, transaction_key, DepositRecord, DepositRegistry};
+use bit10_core::providers::{ProviderSet, RpcProvider};
+use candid::{CandidType, Decode, Deserialize, Encode, Nat, Principal};
 use ic_cdk::storage;
-use crate::models::ilp::IlpResponseData;
-use crate::models::slp::{SLPResponseData, SLPWithdrawResponseData};
+use ic_stable_structures::memory_manager::{MemoryId, MemoryManager, VirtualMemory};
+use ic_stable_structures::storable::Bound;
+use ic_stable_structures::{DefaultMemoryImpl, StableBTreeMap, StableCell, Storable};
+use num_traits::ToPrimitive;
+use crate::constants::{
+    DEFAULT_BTC_MIN_CONFIRMATIONS, DEFAULT_SLP_EARLY_WITHDRAW_PENALTY_BPS, SOLANA_RPC_QUORUM, SOLANA_RPC_URL,
+};
+use crate::modules::ilp::{IlpPool, IlpResponseData};
+use crate::modules::ilp_handler::default_btc_pool;
+use crate::modules::slp::{SLPEarlyWithdrawResponseData, SLPResponseData, SLPWithdrawResponseData};
+use crate::modules::slp_rewards::SlpRewards;
+use crate::modules::treasury::TreasuryDeployment;
+
+type Memory = VirtualMemory<DefaultMemoryImpl>;
+
+// Version 0 is the original layout, where everything was written to stable
+// memory with `stable_save` in `pre_upgrade`. Version 2 moved the deposit
+// registry out of the heap state into its own map.
+pub const SCHEMA_VERSION: u32 = 2;
+
+const SCHEMA_VERSION_MEMORY_ID: MemoryId = MemoryId::new(0);
+const RESPONSES_MEMORY_ID: MemoryId = MemoryId::new(1);
+const SLP_RESPONSES_MEMORY_ID: MemoryId = MemoryId::new(2);
+const SLP_WITHDRAW_RESPONSES_MEMORY_ID: MemoryId = MemoryId::new(3);
+const SLP_EARLY_WITHDRAW_RESPONSES_MEMORY_ID: MemoryId = MemoryId::new(4);
+const TREASURY_DEPLOYMENTS_MEMORY_ID: MemoryId = MemoryId::new(5);
+const NEXT_RESPONSE_ID_MEMORY_ID: MemoryId = MemoryId::new(6);
+const NEXT_SLP_RESPONSE_ID_MEMORY_ID: MemoryId = MemoryId::new(7);
+const NEXT_SLP_WITHDRAW_RESPONSE_ID_MEMORY_ID: MemoryId = MemoryId::new(8);
+const NEXT_TREASURY_DEPLOYMENT_ID_MEMORY_ID: MemoryId = MemoryId::new(9);
+const HEAP_STATE_MEMORY_ID: MemoryId = MemoryId::new(10);
+const DEPOSIT_RECORDS_MEMORY_ID: MemoryId = MemoryId::new(11);
 
 thread_local! {
     pub static RETRY_COUNT: RefCell<u8> = RefCell::new(0);
     pub static RETRY_RESULT: RefCell<Option<Result<Vec<u8>, String>>> = RefCell::new(None);
     pub static VERIFICATION_RETRY_COUNT: RefCell<u8> = RefCell::new(0);
     pub static TIMER_ID: RefCell<Option<ic_cdk_timers::TimerId>> = RefCell::new(None);
+    pub static BTC_MIN_CONFIRMATIONS: RefCell<u32> = RefCell::new(DEFAULT_BTC_MIN_CONFIRMATIONS);
+    // Derived addresses never change, so this cache is not persisted.
+    pub static BTC_DEPOSIT_ADDRESSES: RefCell<BTreeMap<Principal, String>> = RefCell::new(BTreeMap::new());
+    pub static SOL_DEPOSIT_ADDRESSES: RefCell<BTreeMap<Principal, String>> = RefCell::new(BTreeMap::new());
+    pub static ILP_POOLS: RefCell<BTreeMap<String, IlpPool>> = RefCell::new(BTreeMap::new());
+    pub static SLP_REWARDS: RefCell<SlpRewards> = RefCell::new(SlpRewards::default());
+    pub static SLP_EARLY_WITHDRAW_PENALTY_BPS: RefCell<u16> = RefCell::new(DEFAULT_SLP_EARLY_WITHDRAW_PENALTY_BPS);
+    // Deposits with an early withdrawal transfer in flight.
+    pub static SLP_EARLY_WITHDRAWS_IN_FLIGHT: RefCell<BTreeSet<u64>> = RefCell::new(BTreeSet::new());
+    // Amounts being pulled in by repayments still in flight, by deployment id.
+    pub static TREASURY_REPAYMENTS_IN_FLIGHT: RefCell<BTreeMap<u64, Nat>> = RefCell::new(BTreeMap::new());
+    // Heap copy of `DEPOSIT_RECORDS`, changed only through `with_deposits_mut`.
+    pub static DEPOSIT_REGISTRY: RefCell<DepositRegistry> = RefCell::new(DepositRegistry::default());
+    // `None` until a controller changes the default Solana providers.
+    pub static SOLANA_RPC_PROVIDERS: RefCell<Option<ProviderSet>> = RefCell::new(None);
+
+    // Initialized lazily, so `post_upgrade` can read a version 0 image before
+    // the memory manager takes over stable memory.
+    static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> = RefCell::new(
+        MemoryManager::init(DefaultMemoryImpl::default())
+    );
+
+    static STORED_SCHEMA_VERSION: RefCell<StableCell<u32, Memory>> = RefCell::new(
+        StableCell::init(memory(SCHEMA_VERSION_MEMORY_ID), 0).expect("Failed to init schema version")
+    );
+    pub static RESPONSES: RefCell<StableBTreeMap<u64, IlpResponseData, Memory>> = RefCell::new(
+        StableBTreeMap::init(memory(RESPONSES_MEMORY_ID))
+    );
+    pub static SLP_RESPONSES: RefCell<StableBTreeMap<u64, SLPResponseData, Memory>> = RefCell::new(
+        StableBTreeMap::init(memory(SLP_RESPONSES_MEMORY_ID))
+    );
+    pub static SLP_WITHDRAW_RESPONSES: RefCell<StableBTreeMap<u64, SLPWithdrawResponseData, Memory>> = RefCell::new(
+        StableBTreeMap::init(memory(SLP_WITHDRAW_RESPONSES_MEMORY_ID))
+    );
+    // Keyed by deposit id.
+    pub static SLP_EARLY_WITHDRAW_RESPONSES: RefCell<StableBTreeMap<u64, SLPEarlyWithdrawResponseData, Memory>> = RefCell::new(
+        StableBTreeMap::init(memory(SLP_EARLY_WITHDRAW_RESPONSES_MEMORY_ID))
+    );
+    pub static TREASURY_DEPLOYMENTS: RefCell<StableBTreeMap<u64, TreasuryDeployment, Memory>> = RefCell::new(
+        StableBTreeMap::init(memory(TREASURY_DEPLOYMENTS_MEMORY_ID))
+    );
+    // Keyed by deposit key. Grows with every deposit, so it is written as
+    // claims change rather than in `pre_upgrade`.
+    static DEPOSIT_RECORDS: RefCell<StableBTreeMap<String, StoredDepositRecord, Memory>> = RefCell::new(
+        StableBTreeMap::init(memory(DEPOSIT_RECORDS_MEMORY_ID))
+    );
+
+    // Ids are handed out from counters rather than `len()`, so they are never
+    // reused even if entries are removed.
+    static NEXT_RESPONSE_ID: RefCell<StableCell<u64, Memory>> = RefCell::new(
+        StableCell::init(memory(NEXT_RESPONSE_ID_MEMORY_ID), 0).expect("Failed to init id counter")
+    );
+    static NEXT_SLP_RESPONSE_ID: RefCell<StableCell<u64, Memory>> = RefCell::new(
+        StableCell::init(memory(NEXT_SLP_RESPONSE_ID_MEMORY_ID), 0).expect("Failed to init id counter")
+    );
+    static NEXT_SLP_WITHDRAW_RESPONSE_ID: RefCell<StableCell<u64, Memory>> = RefCell::new(
+        StableCell::init(memory(NEXT_SLP_WITHDRAW_RESPONSE_ID_MEMORY_ID), 0).expect("Failed to init id counter")
+    );
+    static NEXT_TREASURY_DEPLOYMENT_ID: RefCell<StableCell<u64, Memory>> = RefCell::new(
+        StableCell::init(memory(NEXT_TREASURY_DEPLOYMENT_ID_MEMORY_ID), 0).expect("Failed to init id counter")
+    );
+
+    static HEAP_STATE: RefCell<StableCell<HeapState, Memory>> = RefCell::new(
+        StableCell::init(memory(HEAP_STATE_MEMORY_ID), HeapState::default()).expect("Failed to init heap state")
+    );
+}
+
+/// Settings and small aggregates that live on the heap between upgrades and
+/// are written to their own stable cell in `pre_upgrade`.
+#[derive(CandidType, Deserialize, Clone, Default)]
+struct HeapState {
+    btc_min_confirmations: Option<u32>,
+    ilp_pools: Option<BTreeMap<String, IlpPool>>,
+    slp_rewards: Option<SlpRewards>,
+    slp_early_withdraw_penalty_bps: Option<u16>,
+    // Only read from version 1 images, see `DEPOSIT_RECORDS`.
+    deposit_registry: Option<DepositRegistry>,
+    solana_rpc_providers: Option<ProviderSet>,
+}
+
+#[derive(CandidType, Deserialize, Clone)]
+struct StoredDepositRecord(DepositRecord);
+
+macro_rules! candid_storable {
+    ($($t:ty),*) => {
+        $(
+            impl Storable for $t {
+                fn to_bytes(&self) -> Cow<[u8]> {
+                    Cow::Owned(Encode!(self).expect("Failed to encode value"))
+                }
+
+                fn from_bytes(bytes: Cow<[u8]>) -> Self {
+                    Decode!(bytes.as_ref(), Self).expect("Failed to decode value")
+                }
+
+                const BOUND: Bound = Bound::Unbounded;
+            }
+        )*
+    };
+}
+
+candid_storable!(
+    IlpResponseData,
+    SLPResponseData,
+    SLPWithdrawResponseData,
+    SLPEarlyWithdrawResponseData,
+    TreasuryDeployment,
+    HeapState,
+    StoredDepositRecord
+);
+
+fn memory(id: MemoryId) -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(id))
 }
 
-pub type StableResponses = BTreeMap<u64, IlpResponseData>;
-pub type StableSLPResponses = BTreeMap<u64, SLPResponseData>;
-pub type StableSLPWithdrawResponses = BTreeMap<u64, SLPWithdrawResponseData>;
+fn next_id(counter: &'static LocalKey<RefCell<StableCell<u64, Memory>>>) -> u64 {
+    counter.with(|c| {
+        let mut c = c.borrow_mut();
+        let id = *c.get();
+        c.set(id + 1).expect("Failed to update id counter");
+        id
+    })
+}
+
+fn set_next_id(counter: &'static LocalKey<RefCell<StableCell<u64, Memory>>>, id: u64) {
+    counter.with(|c| c.borrow_mut().set(id).expect("Failed to update id counter"));
+}
+
+pub fn insert_response(response: IlpResponseData) -> u64 {
+    let id = next_id(&NEXT_RESPONSE_ID);
+    RESPONSES.with(|r| r.borrow_mut().insert(id, response));
+    id
+}
+
+/// Stores the deposit under a fresh id, which is also its `deposit_id`.
+pub fn insert_slp_response(mut response: SLPResponseData) -> SLPResponseData {
+    let id = next_id(&NEXT_SLP_RESPONSE_ID);
+    response.deposit_id = Some(id);
+    SLP_RESPONSES.with(|r| r.borrow_mut().insert(id, response.clone()));
+    response
+}
+
+pub fn insert_slp_withdraw_response(response: SLPWithdrawResponseData) -> u64 {
+    let id = next_id(&NEXT_SLP_WITHDRAW_RESPONSE_ID);
+    SLP_WITHDRAW_RESPONSES.with(|r| r.borrow_mut().insert(id, response));
+    id
+}
 
-pub static mut RESPONSES: Option<StableResponses> = None;
-pub static mut SLP_RESPONSES: Option<StableSLPResponses> = None;
-pub static mut SLP_WITHDRAW_RESPONSES: Option<StableSLPWithdrawResponses> = None;
+pub fn next_treasury_deployment_id() -> u64 {
+    next_id(&NEXT_TREASURY_DEPLOYMENT_ID)
+}
+
+/// Runs `f` on the deposit registry and writes the records of `key`'s
+/// transaction, the only ones a claim on `key` can change, to stable memory.
+pub fn with_deposits_mut<R>(key: &str, f: impl FnOnce(&mut DepositRegistry) -> R) -> R {
+    DEPOSIT_REGISTRY.with(|d| {
+        let mut registry = d.borrow_mut();
+        let result = f(&mut registry);
+        DEPOSIT_RECORDS.with(|s| {
+            let mut stored = s.borrow_mut();
+            let transaction = transaction_key(key).to_string();
+            let prefix = format!("{}:", transaction);
+            let mut held: Vec<String> = stored
+                .range(prefix.clone()..)
+                .map(|(held, _)| held)
+                .take_while(|held| held.starts_with(&prefix))
+                .collect();
+            held.push(transaction);
+            for held in held {
+                stored.remove(&held);
+            }
+            for (held, record) in registry.transaction_deposits(key) {
+                stored.insert(held.clone(), StoredDepositRecord(record.clone()));
+            }
+        });
+        result
+    })
+}
+
+#[ic_cdk::init]
+pub fn init() {
+    STORED_SCHEMA_VERSION.with(|v| v.borrow_mut().set(SCHEMA_VERSION).expect("Failed to set schema version"));
+    let pool = default_btc_pool(&[]);
+    ILP_POOLS.with(|p| p.borrow_mut().insert(pool.pool_id.clone(), pool));
+}
 
 #[ic_cdk::pre_upgrade]
 pub fn pre_upgrade() {
-    unsafe {
-        let responses_vec = RESPONSES.as_ref().map_or(Vec::new(), |r| r.values().cloned().collect());
-        let slp_responses_vec = SLP_RESPONSES.as_ref().map_or(Vec::new(), |r| r.values().cloned().collect());
-        let slp_withdraw_responses_vec = SLP_WITHDRAW_RESPONSES.as_ref().map_or(Vec::new(), |r| r.values().cloned().collect());
-
-        storage::stable_save((responses_vec, slp_responses_vec, slp_withdraw_responses_vec))
-            .expect("Failed to save responses to stable storage");
-    }
+    let heap_state = HeapState {
+        btc_min_confirmations: Some(BTC_MIN_CONFIRMATIONS.with(|c| *c.borrow())),
+        ilp_pools: Some(ILP_POOLS.with(|p| p.borrow().clone())),
+        slp_rewards: Some(SLP_REWARDS.with(|r| r.borrow().clone())),
+        slp_early_withdraw_penalty_bps: Some(SLP_EARLY_WITHDRAW_PENALTY_BPS.with(|p| *p.borrow())),
+        deposit_registry: None,
+        solana_rpc_providers: SOLANA_RPC_PROVIDERS.with(|p| p.borrow().clone()),
+    };
+    HEAP_STATE.with(|s| s.borrow_mut().set(heap_state).expect("Failed to save heap state"));
 }
 
 #[ic_cdk::post_upgrade]
 pub fn post_upgrade() {
-    unsafe {
-        RESPONSES = Some(StableResponses::new());
-        SLP_RESPONSES = Some(StableSLPResponses::new());
-        SLP_WITHDRAW_RESPONSES = Some(StableSLPWithdrawResponses::new());
-
-        if let Ok((responses_vec, slp_responses_vec, slp_withdraw_responses_vec)) = 
-            storage::stable_restore::<(Vec<IlpResponseData>, Vec<SLPResponseData>, Vec<SLPWithdrawResponseData>)>() 
-        {
-            for (index, response) in responses_vec.into_iter().enumerate() {
-                RESPONSES.as_mut().unwrap().insert(index as u64, response);
+    // Must run before anything touches the stable structures above.
+    let legacy = if has_legacy_layout() {
+        Some(
+            storage::stable_restore::<LegacyState>()
+                .unwrap_or_else(|e| ic_cdk::trap(&format!("Failed to restore legacy state: {}", e))),
+        )
+    } else {
+        None
+    };
+
+    let (mut heap_state, stored_version) = match legacy {
+        Some(legacy) => (migrate_legacy_state(legacy), 0),
+        None => {
+            let stored_version = STORED_SCHEMA_VERSION.with(|v| *v.borrow().get());
+            if stored_version > SCHEMA_VERSION {
+                ic_cdk::trap(&format!(
+                    "Stable memory has schema version {}, this build only supports up to {}",
+                    stored_version, SCHEMA_VERSION
+                ));
             }
-            for (index, response) in slp_responses_vec.into_iter().enumerate() {
-                SLP_RESPONSES.as_mut().unwrap().insert(index as u64, response);
+            (HEAP_STATE.with(|s| s.borrow().get().clone()), stored_version)
+        }
+    };
+
+    restore_deposit_registry(heap_state.deposit_registry.take(), stored_version);
+    restore_heap_state(heap_state);
+    STORED_SCHEMA_VERSION.with(|v| v.borrow_mut().set(SCHEMA_VERSION).expect("Failed to set schema version"));
+}
+
+type LegacyState = (
+    Vec<IlpResponseData>,
+    Vec<SLPResponseData>,
+    Vec<SLPWithdrawResponseData>,
+    Option<u32>,
+    Option<BTreeMap<String, IlpPool>>,
+    Option<SlpRewards>,
+    Option<Vec<SLPEarlyWithdrawResponseData>>,
+    Option<u16>,
+    Option<Vec<TreasuryDeployment>>,
+);
+
+// The memory manager marks stable memory with "MGR"; anything else that is
+// not empty was written by `stable_save`.
+fn has_legacy_layout() -> bool {
+    if ic_cdk::api::stable::stable64_size() == 0 {
+        return false;
+    }
+    let mut magic = [0u8; 3];
+    ic_cdk::api::stable::stable64_read(0, &mut magic);
+    &magic != b"MGR"
+}
+
+fn migrate_legacy_state(legacy: LegacyState) -> HeapState {
+    let (
+        responses_vec,
+        slp_responses_vec,
+        slp_withdraw_responses_vec,
+        btc_min_confirmations,
+        ilp_pools,
+        slp_rewards,
+        slp_early_withdraw_responses_vec,
+        slp_early_withdraw_penalty_bps,
+        treasury_deployments_vec,
+    ) = legacy;
+
+    let ilp_pools = ilp_pools.unwrap_or_else(|| {
+        let pool = default_btc_pool(&responses_vec);
+        BTreeMap::from([(pool.pool_id.clone(), pool)])
+    });
+    let slp_rewards = slp_rewards.unwrap_or_else(|| rewards_for_existing_positions(&slp_responses_vec));
+
+    // Legacy ids were positions in these vectors, so inserting in order keeps
+    // every existing deposit id.
+    for response in responses_vec {
+        insert_response(response);
+    }
+    for response in slp_responses_vec {
+        insert_slp_response(response);
+    }
+    for response in slp_withdraw_responses_vec {
+        insert_slp_withdraw_response(response);
+    }
+    for response in slp_early_withdraw_responses_vec.unwrap_or_default() {
+        SLP_EARLY_WITHDRAW_RESPONSES.with(|r| r.borrow_mut().insert(response.deposit_id, response));
+    }
+    let mut next_deployment_id = 0;
+    for deployment in treasury_deployments_vec.unwrap_or_default() {
+        next_deployment_id = next_deployment_id.max(deployment.deployment_id + 1);
+        TREASURY_DEPLOYMENTS.with(|d| d.borrow_mut().insert(deployment.deployment_id, deployment));
+    }
+    set_next_id(&NEXT_TREASURY_DEPLOYMENT_ID, next_deployment_id);
+
+    HeapState {
+        btc_min_confirmations,
+        ilp_pools: Some(ilp_pools),
+        slp_rewards: Some(slp_rewards),
+        slp_early_withdraw_penalty_bps,
+        deposit_registry: None,
+        solana_rpc_providers: None,
+    }
+}
+
+fn restore_heap_state(heap_state: HeapState) {
+    if let Some(confirmations) = heap_state.btc_min_confirmations {
+        BTC_MIN_CONFIRMATIONS.with(|c| *c.borrow_mut() = confirmations);
+    }
+    let ilp_pools = heap_state.ilp_pools.unwrap_or_else(|| {
+        let responses: Vec<IlpResponseData> = RESPONSES.with(|r| r.borrow().iter().map(|(_, v)| v).collect());
+        let pool = default_btc_pool(&responses);
+        BTreeMap::from([(pool.pool_id.clone(), pool)])
+    });
+    ILP_POOLS.with(|p| *p.borrow_mut() = ilp_pools);
+    if let Some(slp_rewards) = heap_state.slp_rewards {
+        SLP_REWARDS.with(|r| *r.borrow_mut() = slp_rewards);
+    }
+    if let Some(penalty_bps) = heap_state.slp_early_withdraw_penalty_bps {
+        SLP_EARLY_WITHDRAW_PENALTY_BPS.with(|p| *p.borrow_mut() = penalty_bps);
+    }
+    SOLANA_RPC_PROVIDERS.with(|p| *p.borrow_mut() = heap_state.solana_rpc_providers);
+}
+
+// Before version 2 the registry was kept in the heap state, or not at all
+// before version 1, and is copied into `DEPOSIT_RECORDS` once.
+fn restore_deposit_registry(from_heap_state: Option<DepositRegistry>, stored_version: u32) {
+    let registry = if stored_version >= 2 {
+        DEPOSIT_RECORDS.with(|s| s.borrow().iter().map(|(key, record)| (key, record.0)).collect())
+    } else {
+        let registry = from_heap_state.unwrap_or_else(registry_for_existing_responses);
+        DEPOSIT_RECORDS.with(|s| {
+            let mut stored = s.borrow_mut();
+            for (key, record) in registry.deposits() {
+                stored.insert(key.clone(), StoredDepositRecord(record.clone()));
             }
-            for (index, response) in slp_withdraw_responses_vec.into_iter().enumerate() {
-                SLP_WITHDRAW_RESPONSES.as_mut().unwrap().insert(index as u64, response);
+        });
+        registry
+    };
+    DEPOSIT_REGISTRY.with(|d| *d.borrow_mut() = registry);
+}
+
+fn default_solana_rpc_providers() -> ProviderSet {
+    ProviderSet::new(
+        vec![
+            RpcProvider::new("solana", SOLANA_RPC_URL),
+            RpcProvider::new("drpc", "https://solana-devnet.drpc.org"),
+            RpcProvider::new("ankr", "https://rpc.ankr.com/solana_devnet/{api_key}"),
+        ],
+        SOLANA_RPC_QUORUM,
+    )
+    .expect("Invalid default Solana providers")
+}
+
+pub fn solana_rpc_providers() -> ProviderSet {
+    SOLANA_RPC_PROVIDERS.with(|p| p.borrow().clone().unwrap_or_else(default_solana_rpc_providers))
+}
+
+pub fn with_solana_rpc_providers_mut<R>(f: impl FnOnce(&mut ProviderSet) -> R) -> R {
+    SOLANA_RPC_PROVIDERS.with(|p| f(p.borrow_mut().get_or_insert_with(default_solana_rpc_providers)))
+}
+
+// Deposits paid out before the registry existed are marked as processed so
+// they cannot be claimed again.
+fn registry_for_existing_responses() -> DepositRegistry {
+    let now = ic_cdk::api::time();
+    let mut registry = DepositRegistry::default();
+    RESPONSES.with(|r| {
+        for (_, response) in r.borrow().iter() {
+            // Old responses credited whole transactions, which also blocks
+            // every output of them from being claimed again.
+            let key = deposit_key(&response.tick_in_network, &response.tick_in_tx_block, None);
+            if let Ok(claim) = registry.claim(&key, now) {
+                registry.finish(&claim, true, now);
             }
         }
+    });
+    registry
+}
+
+// Deposits made before rewards existed start earning from the upgrade on.
+fn rewards_for_existing_positions(slp_responses: &[SLPResponseData]) -> SlpRewards {
+    let now = ic_cdk::api::time();
+    let mut rewards = SlpRewards::default();
+    for (index, response) in slp_responses.iter().enumerate() {
+        let start_time = match response.tick_in_timestamp.parse::<u64>() {
+            Ok(time) => time,
+            Err(_) => continue,
+        };
+        let duration_days = response.duration.0.to_u64().unwrap_or(0);
+        rewards.add_position(index as u64, response.tick_in_address, &response.tick_in_amount, duration_days, start_time);
     }
+    rewards.expire(now);
+    rewards
 }